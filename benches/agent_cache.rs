@@ -0,0 +1,50 @@
+// Per-call cost of `query llm` dispatches with the cached LLM agent instance
+// against constructing a fresh one each call (what the orchestrator did before
+// caching), for an agent whose constructor builds a 20k-entry dict. Run with
+// `cargo bench --bench agent_cache`.
+
+use pyo3::types::PyModule;
+use pyo3::Python;
+use sovereign_cli::CognitiveOrchestrator;
+use std::time::{Duration, Instant};
+
+const CALLS: u32 = 2000;
+const MODULE: &str = "python.agents.llm_agent";
+
+const LLM: &str = r#"
+class LLMAgent:
+    """Stands in for an agent that loads a model or opens a session on construction"""
+
+    def __init__(self):
+        self.vocab = {f"token{i}": i for i in range(20000)}
+
+    def generate(self, prompt):
+        return prompt
+"#;
+
+fn per_call(orch: &CognitiveOrchestrator, reload: bool) -> Duration {
+    let started = Instant::now();
+    for call in 0..CALLS {
+        if reload {
+            orch.reload_agents();
+        }
+        orch.dispatch(format!("query llm {}", call), "bench");
+    }
+    started.elapsed() / CALLS
+}
+
+fn main() {
+    Python::with_gil(|py| {
+        let module = PyModule::from_code(py, LLM, "llm_agent.py", MODULE).unwrap();
+        py.import("sys").unwrap().getattr("modules").unwrap().set_item(MODULE, module).unwrap();
+    });
+    let orch = CognitiveOrchestrator::builder().learning(false).build().unwrap();
+    assert!(orch.dispatch("query llm warmup".to_string(), "bench").status);
+
+    let fresh = per_call(&orch, true);
+    let cached = per_call(&orch, false);
+    println!("{} dispatches each", CALLS);
+    println!("{:<24} {:>10.2?}", "fresh instance per call", fresh);
+    println!("{:<24} {:>10.2?}", "cached instance", cached);
+    println!("{:<24} {:>9.1}x", "speedup", fresh.as_secs_f64() / cached.as_secs_f64());
+}
@@ -0,0 +1,57 @@
+// The two hot paths: `QuantumAmplifier::amplify` on square states of 64 to
+// 4096, and `nearest` over 1k to 500k memory vectors at dim 384, the search
+// `Context::nearest` runs. Each input is built once, outside the measurement.
+// Run with `cargo bench --bench amplification`; criterion compares each run
+// with the last one saved under `target/criterion`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use faer::Mat;
+use sovereign_cli::{MemoryVectors, QuantumAmplifier};
+use std::hint::black_box;
+
+const STATE_SIZES: [usize; 7] = [64, 128, 256, 512, 1024, 2048, 4096];
+const MEMORY_SIZES: [usize; 5] = [1_000, 10_000, 50_000, 100_000, 500_000];
+const DIM: usize = 384;
+const ROUNDS: usize = 4;
+const SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
+fn next(seed: &mut u64) -> f64 {
+    *seed ^= *seed << 13;
+    *seed ^= *seed >> 7;
+    *seed ^= *seed << 17;
+    (*seed >> 11) as f64 / (1u64 << 52) as f64 - 1.0
+}
+
+fn amplify(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("amplify/{}_rounds", ROUNDS));
+    group.sample_size(10);
+    let amplifier = QuantumAmplifier::new();
+    let mut seed = SEED;
+    for n in STATE_SIZES {
+        let state = Mat::from_fn(n, n, |_, _| next(&mut seed));
+        group.throughput(Throughput::Elements((n * n) as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(n), &state, |b, state| {
+            b.iter(|| amplifier.amplify(black_box(state), ROUNDS))
+        });
+    }
+    group.finish();
+}
+
+fn nearest(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("nearest/top_10_dim_{}", DIM));
+    group.sample_size(10);
+    let mut seed = SEED;
+    let query: Vec<f64> = (0..DIM).map(|_| next(&mut seed)).collect();
+    for vectors in MEMORY_SIZES {
+        let data: Vec<f32> = (0..vectors * DIM).map(|_| next(&mut seed) as f32).collect();
+        let memory = MemoryVectors::from_flat(DIM, data).unwrap();
+        group.throughput(Throughput::Elements(vectors as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(vectors), &memory, |b, memory| {
+            b.iter(|| memory.nearest(black_box(&query), 10))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, amplify, nearest);
+criterion_main!(benches);
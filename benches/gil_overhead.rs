@@ -0,0 +1,53 @@
+// Per-subtask overhead of a 20-step plan of no-op Python agent calls, with the
+// GIL released for the run and taken per call (how `process` used to run from
+// Python) against the run holding it throughout with `hold_gil`. Run with
+// `cargo bench --bench gil_overhead`.
+
+use pyo3::types::PyModule;
+use pyo3::Python;
+use sovereign_cli::{hold_gil, CognitiveOrchestrator, MockBackend, PyAgent};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const STEPS: usize = 20;
+const RUNS: u32 = 200;
+
+const NOOP: &str = r#"
+class NoopAgent:
+    def can_handle(self, sub_task):
+        return sub_task.startswith("noop:")
+
+    def execute(self, sub_task, context):
+        return {"output": "", "status": True}
+"#;
+
+fn per_subtask(orch: &CognitiveOrchestrator, run: impl Fn(&CognitiveOrchestrator) -> String) -> Duration {
+    run(orch);
+    let started = Instant::now();
+    for _ in 0..RUNS {
+        run(orch);
+    }
+    started.elapsed() / (RUNS * STEPS as u32)
+}
+
+fn main() {
+    let steps: Vec<String> = (0..STEPS).map(|step| format!("noop:step {}", step)).collect();
+    let mock = MockBackend::new().plan("bench", steps);
+    let orch = CognitiveOrchestrator::builder().backend(Arc::new(mock)).learning(false).build().unwrap();
+    let agent = Python::with_gil(|py| {
+        let module = PyModule::from_code(py, NOOP, "noop_agent.py", "noop_agent").unwrap();
+        module.getattr("NoopAgent").unwrap().call0().unwrap().into()
+    });
+    orch.register_agent(Box::new(PyAgent::new("noop".to_string(), agent, 10)));
+
+    let process = |orch: &CognitiveOrchestrator| orch.process("bench it".to_string(), "bench");
+    let (per_call, held) = Python::with_gil(|py| {
+        let per_call = per_subtask(&orch, |orch| py.allow_threads(|| process(orch)));
+        let held = per_subtask(&orch, |orch| hold_gil(py, || process(orch)));
+        (per_call, held)
+    });
+
+    println!("{} runs of {} no-op Python subtasks, per subtask", RUNS, STEPS);
+    println!("{:<16} {:>10.2?}", "GIL per call", per_call);
+    println!("{:<16} {:>10.2?}", "GIL held", held);
+}
@@ -0,0 +1,65 @@
+// Memory footprint and brute-force recall time of 100k memory vectors at dim 384:
+// the nested `Vec<Vec<f64>>` layout contexts used before, the contiguous `f32`
+// buffer, and int8 quantization. Run with `cargo bench --bench memory_vectors`.
+
+use sovereign_cli::{MemoryVectors, Quantization};
+use std::hint::black_box;
+use std::mem::size_of;
+use std::time::{Duration, Instant};
+
+const VECTORS: usize = 100_000;
+const DIM: usize = 384;
+const QUERIES: usize = 20;
+
+fn synthetic(seed: &mut u64) -> Vec<f64> {
+    (0..DIM)
+        .map(|_| {
+            *seed ^= *seed << 13;
+            *seed ^= *seed >> 7;
+            *seed ^= *seed << 17;
+            (*seed >> 11) as f64 / (1u64 << 52) as f64 - 1.0
+        })
+        .collect()
+}
+
+/// Top-`k` cosine search over the nested layout, as `Context::nearest` did.
+fn nested_nearest(rows: &[Vec<f64>], query: &[f64], k: usize) -> Vec<(usize, f64)> {
+    let query_norm = query.iter().map(|q| q * q).sum::<f64>().sqrt();
+    let mut scored: Vec<(usize, f64)> = rows
+        .iter()
+        .enumerate()
+        .map(|(idx, row)| {
+            let (dot, norm) = row.iter().zip(query).fold((0.0, 0.0), |(dot, norm), (r, q)| (dot + r * q, norm + r * r));
+            (idx, dot / (norm.sqrt() * query_norm))
+        })
+        .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    scored.truncate(k);
+    scored
+}
+
+fn per_query(mut recall: impl FnMut(&[f64]) -> Vec<(usize, f64)>, queries: &[Vec<f64>]) -> Duration {
+    let started = Instant::now();
+    for query in queries {
+        black_box(recall(query));
+    }
+    started.elapsed() / queries.len() as u32
+}
+
+fn report(layout: &str, bytes: usize, time: Duration) {
+    println!("{:<16} {:>8.1} MiB {:>10.2?} per recall", layout, bytes as f64 / (1 << 20) as f64, time);
+}
+
+fn main() {
+    let mut seed = 0x2545_f491_4f6c_dd1d;
+    let rows: Vec<Vec<f64>> = (0..VECTORS).map(|_| synthetic(&mut seed)).collect();
+    let queries: Vec<Vec<f64>> = (0..QUERIES).map(|_| synthetic(&mut seed)).collect();
+    let f32s = MemoryVectors::try_from(rows.clone()).unwrap();
+    let int8 = f32s.clone().with_quantization(Quantization::Int8);
+
+    println!("{} vectors of dim {}, top 10 of {} queries", VECTORS, DIM, QUERIES);
+    let nested_bytes = rows.len() * (size_of::<Vec<f64>>() + DIM * size_of::<f64>());
+    report("Vec<Vec<f64>>", nested_bytes, per_query(|query| nested_nearest(&rows, query, 10), &queries));
+    report("f32", f32s.bytes(), per_query(|query| f32s.nearest(query, 10), &queries));
+    report("int8", int8.bytes(), per_query(|query| int8.nearest(query, 10), &queries));
+}
@@ -0,0 +1,45 @@
+// `process` against `process_parallel` over 8 independent subtasks of a mock
+// agent sleeping 25ms each, at concurrencies of 1 to 8. The speedup over the
+// sequential run should stay near the concurrency. Run with
+// `cargo bench --bench parallel_dispatch`.
+
+use sovereign_cli::{AgentResult, CognitiveOrchestrator, MockBackend};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const STEPS: usize = 8;
+const NAP: Duration = Duration::from_millis(25);
+const CONCURRENCY: [usize; 4] = [1, 2, 4, 8];
+
+fn orchestrator() -> CognitiveOrchestrator {
+    let steps: Vec<String> = (0..STEPS).map(|step| format!("nap:{}", step)).collect();
+    let mock = MockBackend::new().plan("bench", steps).on("nap", |rest| {
+        thread::sleep(NAP);
+        AgentResult::ok(rest)
+    });
+    CognitiveOrchestrator::builder().backend(Arc::new(mock)).learning(false).build().unwrap()
+}
+
+fn time(run: impl FnOnce()) -> Duration {
+    let started = Instant::now();
+    run();
+    started.elapsed()
+}
+
+fn main() {
+    let orch = orchestrator();
+    println!("{} subtasks sleeping {:?} each", STEPS, NAP);
+    let sequential = time(|| {
+        orch.process("bench it".to_string(), "bench");
+    });
+    println!("{:<16} {:>10.2?}", "process", sequential);
+    for concurrency in CONCURRENCY {
+        let elapsed = time(|| {
+            let results = orch.process_parallel("bench it".to_string(), "bench", concurrency, false);
+            assert!(results.iter().all(|result| result.status));
+        });
+        let label = format!("parallel x{}", concurrency);
+        println!("{:<16} {:>10.2?} {:>6.2}x", label, elapsed, sequential.as_secs_f64() / elapsed.as_secs_f64());
+    }
+}
@@ -0,0 +1,43 @@
+// File size and `load_contexts` time of a context holding 50k memory vectors
+// at dim 384, saved in each `PersistenceFormat`. Run with
+// `cargo bench --bench persistence`.
+
+use sovereign_cli::{CognitiveOrchestrator, PersistenceFormat};
+use std::time::{Duration, Instant};
+
+const VECTORS: usize = 50_000;
+const LOADS: u32 = 3;
+
+fn load_time(path: &std::path::Path) -> Duration {
+    let started = Instant::now();
+    for _ in 0..LOADS {
+        let orch = CognitiveOrchestrator::builder().learning(false).build().unwrap();
+        assert_eq!(orch.load_contexts(path).unwrap().loaded.len(), 1);
+    }
+    started.elapsed() / LOADS
+}
+
+fn path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("sovereign-bench-{}-{}", name, std::process::id()))
+}
+
+fn main() {
+    let orch = CognitiveOrchestrator::builder().learning(false).build().unwrap();
+    for idx in 0..VECTORS {
+        orch.remember("bench", &format!("memory {} about topic {} in batch {}", idx, idx % 97, idx / 1000)).unwrap();
+    }
+    let seed = path("seed");
+    orch.save_contexts(&seed).unwrap();
+
+    println!("1 context with {} vectors of dim 384, mean of {} loads", VECTORS, LOADS);
+    for format in [PersistenceFormat::Json, PersistenceFormat::MessagePack, PersistenceFormat::Bincode] {
+        let path = path(format.name());
+        let orch = CognitiveOrchestrator::builder().learning(false).build().unwrap().with_persistence_format(format);
+        orch.load_contexts(&seed).unwrap();
+        orch.save_contexts(&path).unwrap();
+        let bytes = std::fs::metadata(&path).unwrap().len();
+        println!("{:<12} {:>8.1} MiB {:>10.2?} per load", format.name(), bytes as f64 / (1 << 20) as f64, load_time(&path));
+        std::fs::remove_file(&path).unwrap();
+    }
+    std::fs::remove_file(&seed).unwrap();
+}
@@ -0,0 +1,61 @@
+// `process_parallel` over 16 subtasks of a Python agent blocking 50ms in a
+// `requests`-style call that releases the GIL, through worker pools of 1 to 16
+// threads. The speedup over one worker should stay near the pool size. Run
+// with `cargo bench --bench worker_pool`.
+
+use pyo3::types::PyModule;
+use pyo3::Python;
+use sovereign_cli::{CognitiveOrchestrator, MockBackend, PyAgent, WorkerPoolConfig};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const STEPS: usize = 16;
+const POOL_SIZES: [usize; 5] = [1, 2, 4, 8, 16];
+
+const FETCH: &str = r#"
+import time
+
+def get(url):
+    # Stands in for an HTTP call to a model API: blocks without the GIL.
+    time.sleep(0.05)
+    return "200 " + url
+
+class FetchAgent:
+    def can_handle(self, sub_task):
+        return sub_task.startswith("fetch:")
+
+    def execute(self, sub_task, context):
+        return {"output": get(sub_task[len("fetch:"):]), "status": True}
+"#;
+
+fn run(size: usize) -> Duration {
+    let steps: Vec<String> = (0..STEPS).map(|step| format!("fetch:https://api.example/{}", step)).collect();
+    let mock = MockBackend::new().plan("bench", steps);
+    let orch = CognitiveOrchestrator::builder()
+        .backend(Arc::new(mock))
+        .learning(false)
+        .worker_pool(WorkerPoolConfig::new(size).imports(["time"]))
+        .build()
+        .unwrap();
+    let agent = Python::with_gil(|py| {
+        let module = PyModule::from_code(py, FETCH, "fetch_agent.py", "fetch_agent").unwrap();
+        module.getattr("FetchAgent").unwrap().call0().unwrap().into()
+    });
+    orch.register_agent(Box::new(PyAgent::new("fetch".to_string(), agent, 10)));
+
+    let started = Instant::now();
+    let results = orch.process_parallel("bench it".to_string(), "bench", STEPS, false);
+    let elapsed = started.elapsed();
+    assert!(results.iter().all(|result| result.status));
+    orch.shutdown(Duration::ZERO);
+    elapsed
+}
+
+fn main() {
+    println!("{} subtasks blocking 50ms each", STEPS);
+    let baseline = run(1);
+    for size in POOL_SIZES {
+        let elapsed = if size == 1 { baseline } else { run(size) };
+        println!("{:>3} workers {:>10.2?} {:>6.2}x", size, elapsed, baseline.as_secs_f64() / elapsed.as_secs_f64());
+    }
+}
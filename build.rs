@@ -0,0 +1,11 @@
+fn main() {
+    #[cfg(feature = "grpc")]
+    {
+        if std::env::var_os("PROTOC").is_none() {
+            let protoc = protoc_bin_vendored::protoc_bin_path().expect("vendored protoc");
+            std::env::set_var("PROTOC", protoc);
+        }
+        println!("cargo:rerun-if-changed=proto/ace.proto");
+        tonic_prost_build::compile_protos("proto/ace.proto").expect("compile proto/ace.proto");
+    }
+}
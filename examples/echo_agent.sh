@@ -0,0 +1,33 @@
+#!/bin/sh
+# A `SubprocessAgent` in plain sh. It takes `echo:<action> <text>` subtasks and
+# answers with the text, the context it ran in and its pid.
+#
+#     SubprocessAgent::spawn("echo", SubprocessConfig::new("examples/echo_agent.sh"))
+#
+# Requests and answers are one JSON object per line; see `SubprocessAgent`.
+
+# The string `$2` of the JSON object `$1`, still escaped, so it can go straight
+# back into JSON. Good enough for flat string fields.
+field() {
+    printf '%s\n' "$1" | sed -nE 's/.*"'"$2"'":"(([^"\\]|\\.)*)".*/\1/p'
+}
+
+while IFS= read -r line; do
+    case $(field "$line" method) in
+        handshake)
+            echo '{"protocol": 1, "handles": ["echo"]}'
+            ;;
+        execute)
+            subtask=$(field "$line" subtask)
+            case $subtask in
+                *' '*) text=${subtask#* } ;;
+                *) text= ;;
+            esac
+            printf '{"output": "%s", "status": true, "metadata": {"context_id": "%s", "pid": %s}}\n' \
+                "$text" "$(field "$line" context_id)" "$$"
+            ;;
+        *)
+            echo '{"output": "unknown method", "status": false}'
+            ;;
+    esac
+done
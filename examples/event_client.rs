@@ -0,0 +1,27 @@
+use futures_util::StreamExt;
+use tokio_tungstenite::tungstenite::Message;
+
+/// Prints the events an `ace-server` publishes, one JSON message per line.
+///
+///     cargo run --example event_client -- ws://127.0.0.1:8080/events?context_id=ctx1
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let url = std::env::args().nth(1).unwrap_or_else(|| "ws://127.0.0.1:8080/events".to_string());
+    let (mut socket, _) = tokio_tungstenite::connect_async(url.as_str()).await?;
+    eprintln!("connected to {}", url);
+
+    let mut last_seq = None;
+    while let Some(message) = socket.next().await {
+        let Message::Text(text) = message? else { continue };
+        let event: serde_json::Value = serde_json::from_str(&text)?;
+        // Without a filter, a gap in `seq` means this client fell behind and lost messages.
+        if let (Some(last), Some(seq)) = (last_seq, event["seq"].as_u64()) {
+            if seq > last + 1 {
+                eprintln!("dropped {} events", seq - last - 1);
+            }
+        }
+        last_seq = event["seq"].as_u64();
+        println!("{}", text);
+    }
+    Ok(())
+}
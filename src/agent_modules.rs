@@ -0,0 +1,342 @@
+use crate::{agent_instance, OrchestratorError};
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, RwLock};
+
+/// The Python-backed agents the orchestrator imports on its own, as opposed to
+/// ones registered with `register_python_agent`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AgentKind {
+    Planner,
+    Llm,
+    Viral,
+    Debug,
+    Memory,
+}
+
+impl AgentKind {
+    pub const ALL: [AgentKind; 5] = [AgentKind::Planner, AgentKind::Llm, AgentKind::Viral, AgentKind::Debug, AgentKind::Memory];
+
+    /// Stable name, also the `Config` key under `python_modules`.
+    pub fn name(self) -> &'static str {
+        match self {
+            AgentKind::Planner => "planner",
+            AgentKind::Llm => "llm",
+            AgentKind::Viral => "viral",
+            AgentKind::Debug => "debug",
+            AgentKind::Memory => "memory",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        AgentKind::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    /// The method the backend calls on the agent's instance, with the names of
+    /// the positional arguments it passes.
+    pub fn method(self) -> (&'static str, &'static [&'static str]) {
+        match self {
+            AgentKind::Planner => ("decompose", &["command"]),
+            AgentKind::Llm => ("generate", &["prompt"]),
+            AgentKind::Viral => ("simulate_viral_engagement", &["nodes", "hook_rate"]),
+            AgentKind::Debug => ("re_plan", &["alt", "context_id"]),
+            AgentKind::Memory => ("store_context", &["text", "context_id", "payload"]),
+        }
+    }
+
+    fn default_module(self) -> AgentModule {
+        let (module, class) = match self {
+            AgentKind::Planner => ("python.agents.planner_agent", "PlannerAgent"),
+            AgentKind::Llm => ("python.agents.llm_agent", "LLMAgent"),
+            AgentKind::Viral => ("python.agents.viral_agent", "ViralAgent"),
+            AgentKind::Debug => ("python.agents.debug_agent", "DebugAgent"),
+            AgentKind::Memory => ("python.memory", "QdrantMemory"),
+        };
+        AgentModule::new(module, class)
+    }
+}
+
+/// A class to instantiate with no arguments, written `module:Class` in config.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AgentModule {
+    pub module: String,
+    pub class: String,
+}
+
+impl AgentModule {
+    pub fn new(module: impl Into<String>, class: impl Into<String>) -> Self {
+        Self { module: module.into(), class: class.into() }
+    }
+
+    /// `module:Class`, or a bare `module` that keeps `default_class`.
+    pub fn parse(spec: &str, default_class: &str) -> Self {
+        match spec.split_once(':') {
+            Some((module, class)) => Self::new(module.trim(), class.trim()),
+            None => Self::new(spec.trim(), default_class),
+        }
+    }
+
+    /// `module.Class`, as used in error targets and metric labels.
+    pub fn target(&self) -> String {
+        format!("{}.{}", self.module, self.class)
+    }
+}
+
+impl fmt::Display for AgentModule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.module, self.class)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct AgentModuleSpecs {
+    planner: Option<String>,
+    llm: Option<String>,
+    viral: Option<String>,
+    debug: Option<String>,
+    memory: Option<String>,
+}
+
+impl Default for AgentModuleSpecs {
+    fn default() -> Self {
+        AgentModuleConfig::default().into()
+    }
+}
+
+/// Where each built-in agent's Python class lives. Serialized as `module:Class`
+/// strings; a bare module name keeps the default class.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "AgentModuleSpecs", into = "AgentModuleSpecs")]
+pub struct AgentModuleConfig {
+    pub planner: AgentModule,
+    pub llm: AgentModule,
+    pub viral: AgentModule,
+    pub debug: AgentModule,
+    /// Anomaly logging, when no native memory store is set.
+    pub memory: AgentModule,
+}
+
+impl Default for AgentModuleConfig {
+    fn default() -> Self {
+        Self {
+            planner: AgentKind::Planner.default_module(),
+            llm: AgentKind::Llm.default_module(),
+            viral: AgentKind::Viral.default_module(),
+            debug: AgentKind::Debug.default_module(),
+            memory: AgentKind::Memory.default_module(),
+        }
+    }
+}
+
+impl From<AgentModuleSpecs> for AgentModuleConfig {
+    fn from(specs: AgentModuleSpecs) -> Self {
+        let mut config = Self::default();
+        for (kind, spec) in [
+            (AgentKind::Planner, specs.planner),
+            (AgentKind::Llm, specs.llm),
+            (AgentKind::Viral, specs.viral),
+            (AgentKind::Debug, specs.debug),
+            (AgentKind::Memory, specs.memory),
+        ] {
+            if let Some(spec) = spec {
+                let module = AgentModule::parse(&spec, &kind.default_module().class);
+                config.set(kind, module);
+            }
+        }
+        config
+    }
+}
+
+impl From<AgentModuleConfig> for AgentModuleSpecs {
+    fn from(config: AgentModuleConfig) -> Self {
+        Self {
+            planner: Some(config.planner.to_string()),
+            llm: Some(config.llm.to_string()),
+            viral: Some(config.viral.to_string()),
+            debug: Some(config.debug.to_string()),
+            memory: Some(config.memory.to_string()),
+        }
+    }
+}
+
+impl AgentModuleConfig {
+    pub fn get(&self, kind: AgentKind) -> &AgentModule {
+        match kind {
+            AgentKind::Planner => &self.planner,
+            AgentKind::Llm => &self.llm,
+            AgentKind::Viral => &self.viral,
+            AgentKind::Debug => &self.debug,
+            AgentKind::Memory => &self.memory,
+        }
+    }
+
+    pub fn set(&mut self, kind: AgentKind, module: AgentModule) {
+        match kind {
+            AgentKind::Planner => self.planner = module,
+            AgentKind::Llm => self.llm = module,
+            AgentKind::Viral => self.viral = module,
+            AgentKind::Debug => self.debug = module,
+            AgentKind::Memory => self.memory = module,
+        }
+    }
+}
+
+enum Slot {
+    /// A thread is constructing the instance; others wait on `Shared::built`.
+    Building,
+    Ready(Py<PyAny>),
+}
+
+#[derive(Default)]
+struct Shared {
+    config: RwLock<AgentModuleConfig>,
+    /// Never held across a Python call or while waiting for the GIL, so threads
+    /// that hold the GIL can take it without deadlocking.
+    instances: Mutex<HashMap<AgentKind, Slot>>,
+    built: Condvar,
+}
+
+impl Shared {
+    fn slots(&self) -> MutexGuard<'_, HashMap<AgentKind, Slot>> {
+        self.instances.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Removes the matching slots and wakes any waiters. The instances are
+    /// returned so they are released after the lock.
+    fn evict(&self, keep: impl Fn(&AgentKind) -> bool) -> Vec<Slot> {
+        let mut slots = self.slots();
+        let kinds: Vec<AgentKind> = slots.keys().copied().filter(|kind| !keep(kind)).collect();
+        let evicted = kinds.iter().filter_map(|kind| slots.remove(kind)).collect();
+        drop(slots);
+        self.built.notify_all();
+        evicted
+    }
+}
+
+/// The orchestrator's module configuration and agent instances, shared with the
+/// agents that call into Python so changes apply to the next call.
+#[derive(Clone, Default)]
+pub struct AgentModules(Arc<Shared>);
+
+impl AgentModules {
+    pub fn new(config: AgentModuleConfig) -> Self {
+        Self(Arc::new(Shared { config: RwLock::new(config), ..Shared::default() }))
+    }
+
+    pub fn get(&self, kind: AgentKind) -> AgentModule {
+        self.0.config.read().unwrap_or_else(|e| e.into_inner()).get(kind).clone()
+    }
+
+    /// Also drops the agent's cached instance.
+    pub fn set(&self, kind: AgentKind, module: AgentModule) {
+        self.0.config.write().unwrap_or_else(|e| e.into_inner()).set(kind, module);
+        self.0.evict(|cached| *cached != kind);
+    }
+
+    pub fn config(&self) -> AgentModuleConfig {
+        self.0.config.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// The agent's instance, imported and constructed on first use and reused
+    /// after that. Threads asking while another constructs it wait with the GIL
+    /// released, so each class is instantiated once per cache fill. A failed
+    /// construction is not cached.
+    pub fn instance<'py>(&self, py: Python<'py>, kind: AgentKind) -> Result<&'py PyAny, OrchestratorError> {
+        loop {
+            {
+                let mut slots = self.0.slots();
+                match slots.get(&kind) {
+                    Some(Slot::Ready(instance)) => return Ok(instance.clone_ref(py).into_ref(py)),
+                    Some(Slot::Building) => {}
+                    None => {
+                        slots.insert(kind, Slot::Building);
+                        break;
+                    }
+                }
+            }
+            py.allow_threads(|| {
+                let slots = self.0.slots();
+                let _slots = self
+                    .0
+                    .built
+                    .wait_while(slots, |slots| matches!(slots.get(&kind), Some(Slot::Building)))
+                    .unwrap_or_else(|e| e.into_inner());
+            });
+        }
+
+        let built = agent_instance(py, &self.get(kind));
+        let mut slots = self.0.slots();
+        match &built {
+            // Unless `set` or `reload` dropped the slot meanwhile.
+            Ok(instance) if matches!(slots.get(&kind), Some(Slot::Building)) => {
+                slots.insert(kind, Slot::Ready((*instance).into()));
+            }
+            _ => {
+                if matches!(slots.get(&kind), Some(Slot::Building)) {
+                    slots.remove(&kind);
+                }
+            }
+        }
+        drop(slots);
+        self.0.built.notify_all();
+        built
+    }
+
+    /// Drops every cached instance; the next call constructs fresh ones. Modules
+    /// already in `sys.modules` are not re-imported.
+    pub fn reload(&self) {
+        self.0.evict(|_| false);
+    }
+
+    /// Imports every configured module and looks up its class, without
+    /// instantiating anything.
+    pub fn validate(&self) -> Vec<AgentAvailability> {
+        let config = self.config();
+        Python::with_gil(|py| {
+            AgentKind::ALL
+                .into_iter()
+                .map(|kind| {
+                    let module = config.get(kind);
+                    let error = match py.import(module.module.as_str()) {
+                        Ok(imported) => imported.getattr(module.class.as_str()).err().map(|err| err.to_string()),
+                        Err(err) => Some(err.to_string()),
+                    };
+                    AgentAvailability {
+                        agent: kind.name().to_string(),
+                        module: module.module.clone(),
+                        class: module.class.clone(),
+                        available: error.is_none(),
+                        error,
+                    }
+                })
+                .collect()
+        })
+    }
+}
+
+/// One row of `validate_agents`.
+#[pyclass(module = "sovereign_cli", get_all)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AgentAvailability {
+    pub agent: String,
+    pub module: String,
+    pub class: String,
+    pub available: bool,
+    /// The import or attribute error, when unavailable.
+    pub error: Option<String>,
+}
+
+#[pymethods]
+impl AgentAvailability {
+    fn __repr__(&self) -> String {
+        format!(
+            "AgentAvailability(agent={:?}, target={:?}, available={})",
+            self.agent,
+            format!("{}:{}", self.module, self.class),
+            self.available
+        )
+    }
+}
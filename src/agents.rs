@@ -0,0 +1,723 @@
+use crate::attributes;
+use crate::budget::estimate_tokens;
+use crate::result_cache::normalize_subtask;
+use crate::timing;
+use crate::{
+    call_agent, call_agent_or, AgentBackend, AgentKind, AgentResult, Blackboard, Budgets, Context, Embedder, MetricsRecorder, MwpmDecoder,
+    OrchestratorError, PropagationState, PythonBackend, QuantumAmplifier, Subtask, ViralConfig, ViralParams, ViralPropagator,
+};
+use pythonize::{depythonize, pythonize};
+use pyo3::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tracing::warn;
+
+/// A dispatch route. Agents must be shareable across threads because parallel and
+/// async runs execute them off the orchestrator's thread.
+pub trait Agent: Send + Sync {
+    fn name(&self) -> &str;
+    fn can_handle(&self, sub_task: &str) -> bool;
+    fn execute(&self, sub_task: &str, ctx: &mut Context) -> AgentResult;
+
+    /// `execute` within a run, which passes what its earlier steps left on the
+    /// blackboard. Agents that don't read it keep this default.
+    fn execute_with_blackboard(&self, sub_task: &str, ctx: &mut Context, _blackboard: &Blackboard) -> AgentResult {
+        self.execute(sub_task, ctx)
+    }
+
+    /// The Python agent this agent's next call goes out to, if any, for rate limiting.
+    fn kind(&self) -> Option<AgentKind> {
+        None
+    }
+
+    /// What, next to `kind`, identifies this agent's result in the result cache:
+    /// the normalized subtask, for agents whose output depends on nothing else.
+    /// `None` keeps the result out of the cache.
+    fn cache_key(&self, sub_task: &str, _ctx: &Context) -> Option<String> {
+        Some(normalize_subtask(sub_task))
+    }
+
+    /// Higher priorities are consulted first; equal priorities keep registration order.
+    fn priority(&self) -> i32 {
+        0
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct AgentRegistry {
+    agents: Vec<Arc<dyn Agent>>,
+}
+
+impl AgentRegistry {
+    /// The built-in LLM and viral routes, MWPM, the remaining viral plan steps and
+    /// memory, each taking the subtasks tagged with its name. LLM calls are charged
+    /// to `budgets`; memory recall embeds with `embedder`.
+    pub fn with_defaults(
+        simulation: ViralSimulation,
+        decoder: MwpmDecoder,
+        backend: &Arc<dyn AgentBackend>,
+        budgets: &Budgets,
+        embedder: &Arc<dyn Embedder>,
+    ) -> Self {
+        let mut registry = Self::default();
+        registry.register(Box::new(LlmAgent::new(backend.clone()).with_budgets(budgets.clone())));
+        registry.register(Box::new(ViralAgent::new(simulation.clone())));
+        registry.register(Box::new(MwpmAgent::new(decoder)));
+        registry.register(Box::new(ContentAgent::new(backend.clone())));
+        registry.register(Box::new(HookAgent::new(DEFAULT_HOOK_BOOST)));
+        registry.register(Box::new(SpreadAgent::new(simulation)));
+        registry.register(Box::new(EvalAgent));
+        registry.register(Box::new(MemoryAgent::new(embedder.clone())));
+        registry
+    }
+
+    pub fn register(&mut self, agent: Box<dyn Agent>) {
+        let priority = agent.priority();
+        let pos = self
+            .agents
+            .iter()
+            .position(|existing| existing.priority() < priority)
+            .unwrap_or(self.agents.len());
+        self.agents.insert(pos, Arc::from(agent));
+    }
+
+    pub fn route(&self, sub_task: &str) -> Option<Arc<dyn Agent>> {
+        self.agents.iter().find(|agent| agent.can_handle(sub_task)).cloned()
+    }
+
+    /// The agent registered as `name`; the highest-priority one if several are.
+    pub fn get(&self, name: &str) -> Option<Arc<dyn Agent>> {
+        self.agents.iter().find(|agent| agent.name() == name).cloned()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.agents.iter().map(|agent| agent.name().to_string()).collect()
+    }
+}
+
+/// Routes `llm:generate <prompt>` (or `query llm <prompt>`) to the backend's `generate`, which for
+/// `PythonBackend` is `python.agents.llm_agent.LLMAgent` by default. Each call is
+/// charged to the context's budgets: the `tokens` and `cost_usd` the agent reports
+/// in its metadata, or else tokens estimated from the prompt and output.
+pub struct LlmAgent {
+    backend: Arc<dyn AgentBackend>,
+    budgets: Budgets,
+}
+
+impl LlmAgent {
+    pub fn new(backend: Arc<dyn AgentBackend>) -> Self {
+        Self { backend, budgets: Budgets::default() }
+    }
+
+    pub fn with_budgets(mut self, budgets: Budgets) -> Self {
+        self.budgets = budgets;
+        self
+    }
+}
+
+impl Default for LlmAgent {
+    fn default() -> Self {
+        Self::new(Arc::new(PythonBackend::default()))
+    }
+}
+
+impl Agent for LlmAgent {
+    fn name(&self) -> &str {
+        "llm"
+    }
+
+    fn can_handle(&self, sub_task: &str) -> bool {
+        Subtask::is_for(sub_task, "llm")
+    }
+
+    fn kind(&self) -> Option<AgentKind> {
+        Some(AgentKind::Llm)
+    }
+
+    fn execute(&self, sub_task: &str, ctx: &mut Context) -> AgentResult {
+        let prompt = Subtask::payload_of(sub_task);
+        if let Err(err) = self.budgets.reserve_call(&ctx.key()) {
+            return AgentResult::from_error("Budget Exceeded", err);
+        }
+
+        match self.backend.generate_with_metadata(&prompt) {
+            Ok((output, metadata)) => {
+                let mut result = AgentResult { metadata, ..AgentResult::ok(output) };
+                let tokens = result
+                    .get_deserialized::<u64>(AgentResult::TOKENS_USED)
+                    .unwrap_or_else(|| estimate_tokens(&prompt) + estimate_tokens(&result.output));
+                let cost_usd = result.get_f64("cost_usd").unwrap_or(0.0);
+                self.budgets.record(&ctx.key(), tokens, cost_usd);
+                result.set(AgentResult::TOKENS_USED, tokens);
+                result
+            }
+            Err(err) => AgentResult::from_error("LLM Error", err),
+        }
+    }
+}
+
+/// Viral simulation shared by the viral and spread agents: the native propagator
+/// or the backend's (the Python `ViralAgent` unless `with_backend` says
+/// otherwise), chosen by `prefer_native`. Each run but a trial records the
+/// virality score and re-amplifies the context's metrics.
+#[derive(Clone)]
+pub struct ViralSimulation {
+    propagator: Arc<ViralPropagator>,
+    amplifier: Arc<QuantumAmplifier>,
+    prefer_native: Arc<AtomicBool>,
+    backend: Arc<dyn AgentBackend>,
+    recorder: MetricsRecorder,
+    config: ViralConfig,
+}
+
+impl ViralSimulation {
+    /// `prefer_native` is shared with the orchestrator so it can be toggled after registration.
+    pub fn new(propagator: Arc<ViralPropagator>, amplifier: Arc<QuantumAmplifier>, prefer_native: Arc<AtomicBool>) -> Self {
+        let config = ViralConfig { topology: propagator.topology, ..ViralConfig::default() };
+        Self {
+            propagator,
+            amplifier,
+            prefer_native,
+            backend: Arc::new(PythonBackend::default()),
+            recorder: MetricsRecorder::default(),
+            config,
+        }
+    }
+
+    pub fn with_backend(mut self, backend: Arc<dyn AgentBackend>) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Samples each run's metrics into the context's `metrics_history`.
+    pub fn with_recorder(mut self, recorder: MetricsRecorder) -> Self {
+        self.recorder = recorder;
+        self
+    }
+
+    /// What contexts without their own `viral_config` use; its topology should be
+    /// the propagator's.
+    pub fn with_config(mut self, config: ViralConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// The context's viral settings, else these, with its `viral.*` attributes laid over them.
+    fn config(&self, ctx: &Context) -> Result<ViralConfig, OrchestratorError> {
+        attributes::viral_config(ctx.viral_config.unwrap_or(self.config), &ctx.attributes)
+    }
+
+    /// A `continued` run extends the context's propagation state, when it is for
+    /// this graph, by `rounds` rather than starting over; only it touches the context.
+    fn simulate_native(
+        &self,
+        ctx: &mut Context,
+        continued: bool,
+        (nodes, hook_rate, rounds): (usize, f64, usize),
+    ) -> Result<HashMap<String, serde_json::Value>, OrchestratorError> {
+        let config = self.config(ctx)?;
+        let rewired;
+        let propagator = if config.topology == self.propagator.topology {
+            self.propagator.as_ref()
+        } else {
+            rewired = (*self.propagator).clone().with_topology(config.topology);
+            &rewired
+        };
+        let stale = |state: &PropagationState| state.graph().node_count() != nodes || state.graph().topology() != config.topology;
+        if continued && ctx.propagation.as_ref().is_some_and(stale) {
+            ctx.propagation = None;
+        }
+        let mut result_dict = HashMap::new();
+        let report = match &mut ctx.propagation {
+            Some(state) if continued => {
+                state.hook_rate = hook_rate;
+                result_dict.insert("continued".to_string(), serde_json::Value::from(true));
+                propagator.continue_simulation(state, rounds)
+            }
+            _ if continued => {
+                let (report, state) = propagator.start_simulation(nodes, hook_rate, rounds, propagator.seed);
+                ctx.propagation = Some(state);
+                result_dict.insert("continued".to_string(), serde_json::Value::from(false));
+                report
+            }
+            _ => propagator.simulate(nodes, hook_rate, rounds, propagator.seed),
+        };
+        result_dict.insert(AgentResult::VIRALITY.to_string(), serde_json::Value::from(report.virality_score));
+        result_dict.insert(
+            "metrics".to_string(),
+            serde_json::to_value(&report).map_err(OrchestratorError::serialization)?,
+        );
+        result_dict.insert("engine".to_string(), serde_json::Value::from("native"));
+        result_dict.insert(
+            "topology".to_string(),
+            serde_json::to_value(propagator.topology).map_err(OrchestratorError::serialization)?,
+        );
+        Ok(result_dict)
+    }
+
+    /// `Viral` unless the native propagator runs instead.
+    fn kind(&self) -> Option<AgentKind> {
+        (!self.prefer_native.load(Ordering::Relaxed)).then_some(AgentKind::Viral)
+    }
+
+    /// Returns the virality score and a successful result, without output, whose
+    /// metadata is the raw simulation result. A `continued` native run picks up
+    /// where the context's last one stopped. A trial of `params` runs with its
+    /// overrides and leaves the context as it was.
+    fn run(&self, ctx: &mut Context, continued: bool, params: &ViralParams) -> Result<(f64, AgentResult), OrchestratorError> {
+        if params.commit {
+            let metrics = &mut ctx.viral_metrics;
+            metrics.engagement_nodes = params.nodes.unwrap_or(metrics.engagement_nodes);
+            metrics.hook_rate = params.hook_rate.unwrap_or(metrics.hook_rate);
+        }
+        let nodes = params.nodes.unwrap_or(ctx.viral_metrics.engagement_nodes);
+        let hook_rate = params.hook_rate.unwrap_or(ctx.viral_metrics.hook_rate);
+        let metadata = if self.prefer_native.load(Ordering::Relaxed) {
+            let rounds = params.rounds.unwrap_or(self.propagator.rounds);
+            let continued = continued && !params.trial();
+            timing::release_gil(|| self.simulate_native(ctx, continued, (nodes, hook_rate, rounds)))?
+        } else {
+            self.backend.simulate_viral(nodes, hook_rate)?
+        };
+        let mut result = AgentResult { metadata, ..AgentResult::ok(String::new()) };
+        if params.overrides() {
+            result.set("overrides", params);
+        }
+
+        let virality = result.get_f64(AgentResult::VIRALITY).unwrap_or(0.0);
+        if params.trial() {
+            return Ok((virality, result));
+        }
+        ctx.viral_metrics.virality_score = virality;
+        timing::release_gil(|| self.amplifier.amplify_metrics(&mut ctx.viral_metrics));
+        self.recorder.record(ctx);
+
+        Ok((virality, result))
+    }
+}
+
+/// Handles `viral:simulate`, and free text mentioning "viral"; succeeds only when
+/// virality exceeds the context's `virality_threshold`, which the result's
+/// metadata reports with the margin by which the virality cleared or missed it.
+/// The subtask's `ViralParams` override the simulation's, or fail it.
+pub struct ViralAgent {
+    simulation: ViralSimulation,
+}
+
+impl ViralAgent {
+    pub fn new(simulation: ViralSimulation) -> Self {
+        Self { simulation }
+    }
+}
+
+impl Agent for ViralAgent {
+    fn name(&self) -> &str {
+        "viral"
+    }
+
+    fn can_handle(&self, sub_task: &str) -> bool {
+        Subtask::is_for(sub_task, "viral")
+    }
+
+    fn kind(&self) -> Option<AgentKind> {
+        self.simulation.kind()
+    }
+
+    fn execute(&self, sub_task: &str, ctx: &mut Context) -> AgentResult {
+        let threshold = match self.simulation.config(ctx) {
+            Ok(config) => config.virality_threshold,
+            Err(err) => return AgentResult::from_error("Viral Error", err),
+        };
+        let simulated = ViralParams::parse(sub_task).and_then(|params| self.simulation.run(ctx, false, &params));
+        let (virality, mut result) = match simulated {
+            Ok(simulated) => simulated,
+            Err(err) => return AgentResult::from_error("Viral Error", err),
+        };
+
+        result.status = virality > threshold;
+        result.set("virality_threshold", threshold);
+        result.set("virality_margin", virality - threshold);
+        result.output = format!(
+            "Viral: Virality={:.4}, Metrics: {}",
+            virality,
+            result.metadata.get("metrics").unwrap_or(&serde_json::Value::String("N/A".to_string()))
+        );
+        result
+    }
+}
+
+/// Handles `mwpm:amplify` ("amplify MWPM"): decodes a repetition-code circuit sized from
+/// `engagement_nodes` at the context's current infidelity, and reports the decoded
+/// fidelity plus the amplification factor scaled by it. The context is left unchanged.
+pub struct MwpmAgent {
+    decoder: MwpmDecoder,
+}
+
+impl MwpmAgent {
+    pub fn new(decoder: MwpmDecoder) -> Self {
+        Self { decoder }
+    }
+}
+
+impl Agent for MwpmAgent {
+    fn name(&self) -> &str {
+        "mwpm"
+    }
+
+    fn can_handle(&self, sub_task: &str) -> bool {
+        Subtask::is_for(sub_task, "mwpm")
+    }
+
+    fn execute(&self, _sub_task: &str, ctx: &mut Context) -> AgentResult {
+        let metrics = &ctx.viral_metrics;
+        let report =
+            timing::release_gil(|| self.decoder.decode(metrics.engagement_nodes, 1.0 - metrics.quantum_fidelity));
+        let corrected = metrics.amplification_factor * report.fidelity;
+
+        let metadata = match serde_json::to_value(&report) {
+            Ok(serde_json::Value::Object(map)) => map.into_iter().collect(),
+            _ => HashMap::new(),
+        };
+        let output = format!(
+            "MWPM: distance={}, rounds={}, fidelity={:.4}, corrected amplification={:.4}",
+            report.distance, report.rounds, report.fidelity, corrected
+        );
+        // Worse than a coin flip means the decoder made things worse.
+        let mut result = if report.fidelity >= 0.5 { AgentResult::ok(output) } else { AgentResult::fail(output) };
+        result.metadata = metadata;
+        result.set("corrected_amplification", corrected);
+        result
+    }
+}
+
+/// Prompt for `content:generate`; `{goals}` and `{brief}` are filled from the context and subtask.
+const CONTENT_PROMPT: &str =
+    "Write a short, high-engagement social post with a strong opening hook. Goals: {goals}. Brief: {brief}";
+
+/// Handles `content:generate <brief>` ("gen content <brief>") by asking the backend's LLM for a post built from
+/// `CONTENT_PROMPT`.
+pub struct ContentAgent {
+    backend: Arc<dyn AgentBackend>,
+}
+
+impl ContentAgent {
+    pub fn new(backend: Arc<dyn AgentBackend>) -> Self {
+        Self { backend }
+    }
+
+    fn prompt(&self, sub_task: &str, ctx: &Context) -> String {
+        let goals: Vec<&str> = ctx.open_goals().collect();
+        let goals = if goals.is_empty() { "grow engagement".to_string() } else { goals.join("; ") };
+        let brief = Subtask::payload_of(sub_task);
+        let brief = brief.trim();
+        CONTENT_PROMPT
+            .replace("{goals}", &goals)
+            .replace("{brief}", if brief.is_empty() { "none" } else { brief })
+    }
+}
+
+impl Default for ContentAgent {
+    fn default() -> Self {
+        Self::new(Arc::new(PythonBackend::default()))
+    }
+}
+
+impl Agent for ContentAgent {
+    fn name(&self) -> &str {
+        "content"
+    }
+
+    fn can_handle(&self, sub_task: &str) -> bool {
+        Subtask::is_for(sub_task, "content")
+    }
+
+    fn kind(&self) -> Option<AgentKind> {
+        Some(AgentKind::Llm)
+    }
+
+    /// The prompt, since it carries the context's goals.
+    fn cache_key(&self, sub_task: &str, ctx: &Context) -> Option<String> {
+        Some(normalize_subtask(&self.prompt(sub_task, ctx)))
+    }
+
+    fn execute(&self, sub_task: &str, ctx: &mut Context) -> AgentResult {
+        let prompt = self.prompt(sub_task, ctx);
+        match self.backend.generate(&prompt) {
+            Ok(output) => {
+                let mut result = AgentResult::ok(output);
+                result.set("prompt", prompt);
+                result
+            }
+            Err(err) => AgentResult::from_error("Content Error", err),
+        }
+    }
+}
+
+/// Hook-rate increase applied by a bare `hook:inject`.
+pub const DEFAULT_HOOK_BOOST: f64 = 0.1;
+
+/// Handles `hook:inject` ("inject hook"): `hook:inject <rate>` sets the context's
+/// hook rate, a bare `hook:inject` raises it by the boost. Either way it stays in `[0, 1]`.
+pub struct HookAgent {
+    boost: f64,
+}
+
+impl HookAgent {
+    pub fn new(boost: f64) -> Self {
+        Self { boost }
+    }
+}
+
+impl Agent for HookAgent {
+    fn name(&self) -> &str {
+        "hook"
+    }
+
+    fn can_handle(&self, sub_task: &str) -> bool {
+        Subtask::is_for(sub_task, "hook")
+    }
+
+    fn execute(&self, sub_task: &str, ctx: &mut Context) -> AgentResult {
+        let arg = Subtask::payload_of(sub_task);
+        let arg = arg.trim();
+        let previous = ctx.viral_metrics.hook_rate;
+        let requested = if arg.is_empty() {
+            previous + self.boost
+        } else {
+            match arg.parse::<f64>() {
+                Ok(rate) if rate.is_finite() => rate,
+                _ => {
+                    let err = OrchestratorError::Extraction {
+                        target: sub_task.to_string(),
+                        expected: "hook rate".to_string(),
+                        message: format!("invalid hook rate: {}", arg),
+                    };
+                    return AgentResult::from_error("Hook Error", err);
+                }
+            }
+        };
+        let hook_rate = requested.clamp(0.0, 1.0);
+        ctx.viral_metrics.hook_rate = hook_rate;
+
+        let mut result = AgentResult::ok(format!("Hook: hook_rate {:.4} -> {:.4}", previous, hook_rate));
+        result.set("previous_hook_rate", previous);
+        result.set("hook_rate", hook_rate);
+        result
+    }
+}
+
+/// Handles `spread:measure` ("measure spread") by running the viral simulation. Unlike `ViralAgent`
+/// it reports the spread without judging it, so low virality still succeeds. Natively, each
+/// measurement continues the context's last one over the same graph, so reach accumulates
+/// until `reset_propagation`.
+pub struct SpreadAgent {
+    simulation: ViralSimulation,
+}
+
+impl SpreadAgent {
+    pub fn new(simulation: ViralSimulation) -> Self {
+        Self { simulation }
+    }
+}
+
+impl Agent for SpreadAgent {
+    fn name(&self) -> &str {
+        "spread"
+    }
+
+    fn can_handle(&self, sub_task: &str) -> bool {
+        Subtask::is_for(sub_task, "spread")
+    }
+
+    fn kind(&self) -> Option<AgentKind> {
+        self.simulation.kind()
+    }
+
+    fn execute(&self, _sub_task: &str, ctx: &mut Context) -> AgentResult {
+        match self.simulation.run(ctx, true, &ViralParams::default()) {
+            Ok((virality, result)) => AgentResult {
+                output: format!(
+                    "Spread: Virality={:.4} over {} nodes",
+                    virality, ctx.viral_metrics.engagement_nodes
+                ),
+                ..result
+            },
+            Err(err) => AgentResult::from_error("Spread Error", err),
+        }
+    }
+}
+
+/// Handles `eval:metrics` ("eval metrics") by summarizing the context's `ViralMetrics`.
+pub struct EvalAgent;
+
+impl Agent for EvalAgent {
+    fn name(&self) -> &str {
+        "eval"
+    }
+
+    fn can_handle(&self, sub_task: &str) -> bool {
+        Subtask::is_for(sub_task, "eval")
+    }
+
+    fn execute(&self, _sub_task: &str, ctx: &mut Context) -> AgentResult {
+        let metrics = &ctx.viral_metrics;
+        let metadata = match serde_json::to_value(metrics) {
+            Ok(serde_json::Value::Object(map)) => map.into_iter().collect(),
+            _ => HashMap::new(),
+        };
+        let output = format!(
+            "Metrics: virality={:.4}, nodes={}, hook_rate={:.4}, amplification={:.4}, fidelity={:.4}",
+            metrics.virality_score,
+            metrics.engagement_nodes,
+            metrics.hook_rate,
+            metrics.amplification_factor,
+            metrics.quantum_fidelity
+        );
+        AgentResult { metadata, ..AgentResult::ok(output) }
+    }
+}
+
+/// Remembered texts `memory:recall` returns.
+pub const DEFAULT_MEMORY_RECALL: usize = 3;
+
+/// Handles `memory:remember <text>`, which embeds the text into the context's
+/// memory, and `memory:recall <query>`, which returns the closest remembered
+/// texts, one per line, scored under the context's memory decay.
+pub struct MemoryAgent {
+    embedder: Arc<dyn Embedder>,
+}
+
+impl MemoryAgent {
+    pub fn new(embedder: Arc<dyn Embedder>) -> Self {
+        Self { embedder }
+    }
+
+    fn remember(&self, text: &str, ctx: &mut Context) -> Result<AgentResult, OrchestratorError> {
+        let idx = ctx.add_memory_text(self.embedder.embed(text)?, text.to_string())?;
+        let mut result = AgentResult::ok(format!("Memory: remembered #{}", idx));
+        result.set("index", idx);
+        Ok(result)
+    }
+
+    fn recall(&self, query: &str, ctx: &mut Context) -> Result<AgentResult, OrchestratorError> {
+        let query = self.embedder.embed(query)?;
+        // The orchestrator stamps `last_accessed` with the current time as it hands out the context.
+        let hits: Vec<(usize, String, f64)> = ctx
+            .decayed_nearest(&query, ctx.memory_vectors.len(), ctx.last_accessed)
+            .into_iter()
+            .filter_map(|(idx, score)| ctx.memory_text(idx).map(|text| (idx, text.to_string(), score)))
+            .take(DEFAULT_MEMORY_RECALL)
+            .collect();
+        for (idx, ..) in &hits {
+            ctx.memory_recalled(*idx);
+        }
+        let texts: Vec<&str> = hits.iter().map(|(_, text, _)| text.as_str()).collect();
+        let scored: Vec<serde_json::Value> =
+            hits.iter().map(|(_, text, score)| serde_json::json!({ "text": text, "score": score })).collect();
+        let mut result = AgentResult::ok(texts.join("\n"));
+        result.set("hits", scored);
+        Ok(result)
+    }
+}
+
+impl Agent for MemoryAgent {
+    fn name(&self) -> &str {
+        "memory"
+    }
+
+    fn can_handle(&self, sub_task: &str) -> bool {
+        Subtask::is_for(sub_task, "memory")
+    }
+
+    /// Recall depends on what the context remembers.
+    fn cache_key(&self, _sub_task: &str, _ctx: &Context) -> Option<String> {
+        None
+    }
+
+    fn execute(&self, sub_task: &str, ctx: &mut Context) -> AgentResult {
+        let subtask = match Subtask::parse(sub_task) {
+            Ok(subtask) => subtask,
+            Err(err) => return AgentResult::from_error("Memory Error", err),
+        };
+        let result = match subtask.action.as_str() {
+            "remember" => self.remember(&subtask.payload, ctx),
+            "recall" => self.recall(&subtask.payload, ctx),
+            action => Err(OrchestratorError::Extraction {
+                target: sub_task.to_string(),
+                expected: "memory action (remember or recall)".to_string(),
+                message: format!("unknown action {:?}", action),
+            }),
+        };
+        result.unwrap_or_else(|err| AgentResult::from_error("Memory Error", err))
+    }
+}
+
+/// The dict a Python agent's `execute` must return.
+#[derive(Deserialize)]
+struct PyAgentReply {
+    output: String,
+    status: bool,
+    #[serde(default)]
+    metadata: HashMap<String, serde_json::Value>,
+}
+
+/// A Python object exposing `can_handle(sub_task) -> bool` and
+/// `execute(sub_task, context_dict) -> {"output", "status", "metadata"?}`.
+pub struct PyAgent {
+    name: String,
+    agent: Py<PyAny>,
+    priority: i32,
+}
+
+impl PyAgent {
+    pub fn new(name: String, agent: Py<PyAny>, priority: i32) -> Self {
+        Self { name, agent, priority }
+    }
+}
+
+impl Agent for PyAgent {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    fn can_handle(&self, sub_task: &str) -> bool {
+        let handles = timing::with_gil(|py| {
+            call_agent(py, self.agent.as_ref(py), "can_handle", (sub_task,))?
+                .is_true()
+                .map_err(|e| OrchestratorError::extraction("can_handle", "bool", e))
+        });
+        handles.unwrap_or_else(|err| {
+            warn!("Agent {} can_handle failed: {}", self.name, err);
+            false
+        })
+    }
+
+    fn execute(&self, sub_task: &str, ctx: &mut Context) -> AgentResult {
+        self.execute_with_blackboard(sub_task, ctx, &Blackboard::default())
+    }
+
+    /// Agents whose `execute` takes only the subtask and context are called without
+    /// the blackboard.
+    fn execute_with_blackboard(&self, sub_task: &str, ctx: &mut Context, blackboard: &Blackboard) -> AgentResult {
+        let reply = timing::with_gil(|py| {
+            let ctx_dict = pythonize(py, &*ctx)
+                .map_err(|e| OrchestratorError::extraction("Context", "dict", e))?;
+            let board = blackboard.to_dict(py).map_err(|e| OrchestratorError::extraction("Blackboard", "dict", e))?;
+            let args = (sub_task, ctx_dict.clone_ref(py), board);
+            let reply = call_agent_or(py, self.agent.as_ref(py), "execute", args, (sub_task, ctx_dict))?;
+            depythonize::<PyAgentReply>(reply)
+                .map_err(|e| OrchestratorError::extraction(&format!("{}.execute", self.name), "result dict", e))
+        });
+
+        match reply {
+            Ok(reply) => AgentResult { status: reply.status, metadata: reply.metadata, ..AgentResult::ok(reply.output) },
+            Err(err) => AgentResult::from_error(&format!("{} Error", self.name), err),
+        }
+    }
+}
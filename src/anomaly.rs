@@ -0,0 +1,306 @@
+use crate::{AgentBackend, AgentResult, Context, MemoryFilter, MemorySearchHit, MemoryStore, OrchestratorError, Redactor};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+/// Anomalies kept for a later store before the oldest are dropped.
+pub const MAX_PENDING_ANOMALIES: usize = 10_000;
+/// Past anomalies like a new one that `self_debug` shows the debug agent.
+pub const SIMILAR_ANOMALIES: usize = 3;
+
+/// The payload field a recovered anomaly's replacement steps are kept under.
+const RESOLUTION: &str = "resolution";
+
+/// A failed subtask as `self_debug` logs it to memory.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Anomaly {
+    pub context_id: String,
+    /// The command whose run dispatched `subtask`; the subtask itself when it was
+    /// debugged on its own.
+    pub command: String,
+    pub subtask: String,
+    /// The failure's error kind, or `failed` for a result that carries no error.
+    pub error_type: String,
+    pub output: String,
+    pub at: DateTime<Utc>,
+}
+
+impl Anomaly {
+    pub(crate) fn new(context_id: &str, command: &str, subtask: &str, result: &AgentResult, at: DateTime<Utc>) -> Self {
+        Self {
+            context_id: context_id.to_string(),
+            command: command.to_string(),
+            subtask: subtask.to_string(),
+            error_type: result.error.as_ref().map_or("failed", OrchestratorError::kind).to_string(),
+            output: result.output.clone(),
+            at,
+        }
+    }
+
+    /// The anomaly as it is stored, its command, subtask and output redacted.
+    pub(crate) fn redacted(&self, redactor: &Redactor) -> Self {
+        Self {
+            command: redactor.redacted(&self.command),
+            subtask: redactor.redacted(&self.subtask),
+            output: redactor.redacted(&self.output),
+            ..self.clone()
+        }
+    }
+
+    pub(crate) fn text(&self) -> String {
+        format!("Anomaly: {}", self.output)
+    }
+
+    pub(crate) fn payload(&self) -> Map<String, Value> {
+        let mut payload = Map::new();
+        payload.insert("type".to_string(), Value::from("error"));
+        payload.insert("error_type".to_string(), Value::from(self.error_type.as_str()));
+        payload.insert("command".to_string(), Value::from(self.command.as_str()));
+        payload.insert("subtask".to_string(), Value::from(self.subtask.as_str()));
+        payload.insert("output".to_string(), Value::from(self.output.as_str()));
+        payload.insert("timestamp".to_string(), Value::from(self.at.to_rfc3339_opts(SecondsFormat::Millis, true)));
+        payload.insert("context_id".to_string(), Value::from(self.context_id.as_str()));
+        payload
+    }
+
+    /// Natively when a store is configured, else through the backend's memory agent.
+    fn store(&self, backend: &dyn AgentBackend, memory: Option<&dyn MemoryStore>) -> Result<(), OrchestratorError> {
+        let (text, payload) = (self.text(), self.payload());
+        match memory {
+            Some(store) => store.store_context(&text, &self.context_id, payload).map(|_| ()),
+            None => backend.log_anomaly(&text, &self.context_id, &payload),
+        }
+    }
+}
+
+/// Matches the anomalies `self_debug` keeps in context memory.
+pub(crate) fn filter() -> MemoryFilter {
+    MemoryFilter::equals("type", "error")
+}
+
+/// `alt` followed by the similar past anomalies, each with the steps that
+/// recovered from it, if a re-plan did.
+pub(crate) fn replan_prompt(alt: &str, similar: &[MemorySearchHit]) -> String {
+    if similar.is_empty() {
+        return alt.to_string();
+    }
+    let field = |hit: &MemorySearchHit, name: &str| hit.payload.get(name).and_then(Value::as_str).unwrap_or_default().to_string();
+    let lines: Vec<String> = similar
+        .iter()
+        .map(|hit| {
+            let resolution = match hit.payload.get(RESOLUTION).and_then(Value::as_array) {
+                Some(steps) => {
+                    let steps: Vec<&str> = steps.iter().filter_map(Value::as_str).collect();
+                    format!("resolved by {}", steps.join(" -> "))
+                }
+                None => "unresolved".to_string(),
+            };
+            format!("- {}: {} ({})", field(hit, "subtask"), field(hit, "output"), resolution)
+        })
+        .collect();
+    format!("{}\n\nSimilar past anomalies:\n{}", alt, lines.join("\n"))
+}
+
+/// Records `steps` as the resolution of the latest unresolved anomaly kept in
+/// `context` for `subtask` of `command`; returns whether there was one.
+pub(crate) fn resolve(context: &mut Context, command: &str, subtask: &str, steps: &[String]) -> bool {
+    let is_open = |payload: &Map<String, Value>| {
+        let field = |name: &str| payload.get(name).and_then(Value::as_str);
+        filter().matches(payload) && field("command") == Some(command) && field("subtask") == Some(subtask) && !payload.contains_key(RESOLUTION)
+    };
+    match context.memory_payloads.iter_mut().rev().flatten().find(|payload| is_open(payload)) {
+        Some(payload) => {
+            payload.insert(RESOLUTION.to_string(), Value::from(steps.to_vec()));
+            true
+        }
+        None => false,
+    }
+}
+
+/// Anomalies whose store failed, oldest first, retried ahead of the next one
+/// logged and on shutdown. Anomalies are redacted as they are queued. Clones
+/// share the queue.
+#[derive(Clone, Default)]
+pub struct AnomalyLog {
+    queue: Arc<Mutex<VecDeque<Anomaly>>>,
+    redactor: Redactor,
+}
+
+impl AnomalyLog {
+    pub fn new(redactor: Redactor) -> Self {
+        Self { queue: Arc::default(), redactor }
+    }
+
+    fn queue(&self) -> std::sync::MutexGuard<'_, VecDeque<Anomaly>> {
+        self.queue.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub(crate) fn redactor(&self) -> &Redactor {
+        &self.redactor
+    }
+
+    pub fn pending(&self) -> usize {
+        self.queue().len()
+    }
+
+    /// Queues `anomaly` behind the pending ones and stores them in order,
+    /// stopping at the first failure; those not stored stay pending.
+    pub(crate) fn record(&self, anomaly: Anomaly, backend: &dyn AgentBackend, memory: Option<&dyn MemoryStore>) {
+        let mut queue = self.queue();
+        if queue.len() >= MAX_PENDING_ANOMALIES {
+            let dropped = queue.pop_front();
+            warn!(subtask = ?dropped.map(|anomaly| anomaly.subtask), "Pending anomaly dropped");
+        }
+        queue.push_back(anomaly.redacted(&self.redactor));
+        Self::drain(&mut queue, backend, memory);
+    }
+
+    /// Stores the pending anomalies in order; returns how many are still pending.
+    pub(crate) fn flush(&self, backend: &dyn AgentBackend, memory: Option<&dyn MemoryStore>) -> usize {
+        let mut queue = self.queue();
+        Self::drain(&mut queue, backend, memory);
+        queue.len()
+    }
+
+    fn drain(queue: &mut VecDeque<Anomaly>, backend: &dyn AgentBackend, memory: Option<&dyn MemoryStore>) {
+        while let Some(anomaly) = queue.front() {
+            if let Err(err) = anomaly.store(backend, memory) {
+                warn!(pending = queue.len(), "Anomaly log failed for {:?}: {}", anomaly.subtask, err);
+                return;
+            }
+            queue.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AgentResult, CognitiveOrchestrator, FixedClock, MemoryHit, MemoryStore, MockBackend, OrchestratorError};
+    use serde_json::{Map, Value};
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    /// Fails its first `failures` writes, then keeps the payloads it is given.
+    struct FlakyStore {
+        failures: Mutex<usize>,
+        stored: Mutex<Vec<Map<String, Value>>>,
+    }
+
+    impl FlakyStore {
+        fn new(failures: usize) -> Arc<Self> {
+            Arc::new(Self { failures: Mutex::new(failures), stored: Mutex::new(vec![]) })
+        }
+
+        fn subtasks(&self) -> Vec<Value> {
+            self.stored.lock().unwrap().iter().map(|payload| payload["subtask"].clone()).collect()
+        }
+    }
+
+    impl MemoryStore for FlakyStore {
+        fn store_context(&self, _text: &str, _context_id: &str, payload: Map<String, Value>) -> Result<String, OrchestratorError> {
+            let mut failures = self.failures.lock().unwrap();
+            if *failures > 0 {
+                *failures -= 1;
+                return Err(OrchestratorError::Memory { message: "qdrant unavailable".to_string() });
+            }
+            let mut stored = self.stored.lock().unwrap();
+            stored.push(payload);
+            Ok(stored.len().to_string())
+        }
+
+        fn search(&self, _query_vec: &[f32], _limit: usize) -> Result<Vec<MemoryHit>, OrchestratorError> {
+            Ok(vec![])
+        }
+    }
+
+    fn orchestrator(store: &Arc<FlakyStore>) -> CognitiveOrchestrator {
+        let mock = MockBackend::new().plan("ask", ["post one", "post two", "post three"]).on("post", |rest| AgentResult {
+            output: format!("{} failed", rest),
+            status: false,
+            metadata: HashMap::new(),
+            error: None,
+        });
+        CognitiveOrchestrator::builder()
+            .backend(Arc::new(mock))
+            .memory_store(store.clone())
+            .clock(Arc::new(FixedClock::new(chrono::DateTime::UNIX_EPOCH)))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn failed_writes_are_retried_in_order_on_the_next_one() {
+        let store = FlakyStore::new(2);
+        let orch = orchestrator(&store);
+        let mut pending = vec![];
+        for subtask in ["post one", "post two", "post three"] {
+            let result = orch.dispatch(subtask.to_string(), "ctx1");
+            orch.self_debug(&result, subtask, "ctx1");
+            pending.push(orch.pending_anomaly_count());
+        }
+        assert_eq!(pending, [1, 2, 0]);
+        assert_eq!(store.subtasks(), ["post one", "post two", "post three"]);
+
+        let payload = &store.stored.lock().unwrap()[0];
+        assert_eq!(payload["type"], "error");
+        assert_eq!(payload["error_type"], "failed");
+        assert_eq!(payload["context_id"], "ctx1");
+        assert_eq!(payload["timestamp"], "1970-01-01T00:00:00.000Z");
+    }
+
+    #[test]
+    fn shutdown_flushes_pending_anomalies() {
+        let store = FlakyStore::new(2);
+        let orch = orchestrator(&store);
+        orch.process("ask".to_string(), "ctx1");
+        assert_eq!(orch.pending_anomaly_count(), 0);
+        assert_eq!(store.subtasks(), ["post one", "post two", "post three"]);
+        assert_eq!(store.stored.lock().unwrap()[0]["command"], "ask");
+
+        let store = FlakyStore::new(2);
+        let orch = orchestrator(&store);
+        let result = orch.dispatch("post one".to_string(), "ctx1");
+        orch.self_debug(&result, "post one", "ctx1");
+        assert_eq!(orch.pending_anomaly_count(), 1);
+        let report = orch.shutdown(Duration::ZERO);
+        assert_eq!(report.pending_anomalies, 1);
+        let report = orch.shutdown(Duration::ZERO);
+        assert_eq!((report.pending_anomalies, orch.pending_anomaly_count()), (0, 0));
+        assert_eq!(store.subtasks(), ["post one"]);
+    }
+
+    #[test]
+    fn replans_are_shown_similar_past_anomalies_and_their_resolutions() {
+        let result = |output: &str, status| AgentResult { output: output.to_string(), status, metadata: HashMap::new(), error: None };
+        let mock = Arc::new(
+            MockBackend::new()
+                .plan("campaign", ["post teaser", "post launch"])
+                .on("post teaser", move |_| result("low virality on teaser", false))
+                .on("post launch", move |_| result("launched", true))
+                .on("post alt", move |_| result("alt posted", true))
+                .on("audit", move |_| result("audit failed", false))
+                .replan(["post alt"]),
+        );
+        let orch = CognitiveOrchestrator::builder().backend(mock.clone()).build().unwrap();
+        orch.process("campaign".to_string(), "ctx1");
+        orch.dispatch("audit now".to_string(), "ctx1");
+        orch.self_debug(&result("audit failed", false), "audit now", "ctx1");
+        orch.process("campaign".to_string(), "ctx1");
+
+        let replans = mock.replans();
+        assert_eq!(replans[0].0, "replan viral alt strategy");
+        assert_eq!(
+            replans[1].0,
+            "replan viral alt strategy\n\nSimilar past anomalies:\n\
+             - post teaser: low virality on teaser (resolved by post alt)\n\
+             - audit now: audit failed (unresolved)"
+        );
+        let context = orch.get_context("ctx1").unwrap();
+        let resolutions: Vec<Option<&Value>> = context.memory_payloads.iter().flatten().map(|payload| payload.get("resolution")).collect();
+        assert_eq!(resolutions, [Some(&serde_json::json!(["post alt"])), None, Some(&serde_json::json!(["post alt"]))]);
+    }
+}
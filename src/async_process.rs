@@ -0,0 +1,257 @@
+use crate::coroutine;
+use crate::dispatch_policy::blocked_result;
+use crate::timing;
+use crate::{
+    debug_failure, dispatch_span, AgentKind, Anomaly, AgentResult, Blackboard, CancelToken, CognitiveOrchestrator, DebugStrategy,
+    FailureClass, OrchestratorError, Plan,
+};
+use pyo3::prelude::*;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use tracing::{info_span, warn, Instrument, Span};
+
+/// Lends the orchestrator for one synchronous step at a time, so a run never
+/// holds a borrow (or the GIL) across an `.await`.
+pub(crate) trait OrchestratorAccess: Send {
+    fn with<R>(&mut self, f: impl FnOnce(&CognitiveOrchestrator) -> R) -> R;
+}
+
+impl OrchestratorAccess for &CognitiveOrchestrator {
+    fn with<R>(&mut self, f: impl FnOnce(&CognitiveOrchestrator) -> R) -> R {
+        f(self)
+    }
+}
+
+/// Python-owned orchestrator, borrowed under the GIL for each step.
+pub(crate) struct PyOrchestrator(pub Py<CognitiveOrchestrator>);
+
+impl OrchestratorAccess for PyOrchestrator {
+    fn with<R>(&mut self, f: impl FnOnce(&CognitiveOrchestrator) -> R) -> R {
+        Python::with_gil(|py| f(&self.0.borrow(py)))
+    }
+}
+
+/// Runs `task` on the blocking pool inside the caller's current span.
+async fn blocking<T: Send + 'static>(task: impl FnOnce() -> T + Send + 'static) -> Option<T> {
+    let span = Span::current();
+    match tokio::task::spawn_blocking(coroutine::carry_loop(move || span.in_scope(task))).await {
+        Ok(value) => Some(value),
+        Err(err) => {
+            warn!("Blocking task failed: {}", err);
+            None
+        }
+    }
+}
+
+/// Keeps the run's context pinned against eviction and the run counted as in
+/// flight for shutdown and cancellation, including when the future is dropped.
+struct Pinned<A: OrchestratorAccess> {
+    access: A,
+    context_id: String,
+    admitted: u64,
+    cancel: CancelToken,
+}
+
+impl<A: OrchestratorAccess> Pinned<A> {
+    fn new(mut access: A, context_id: String, command: &str) -> Result<Self, OrchestratorError> {
+        let (admitted, cancel) = access.with(|orch| {
+            let admitted = orch.drain.admit(&context_id, command)?;
+            orch.pin(&context_id);
+            Ok((admitted, orch.cancellations.register(&context_id)))
+        })?;
+        Ok(Self { access, context_id, admitted, cancel })
+    }
+}
+
+impl<A: OrchestratorAccess> Drop for Pinned<A> {
+    fn drop(&mut self) {
+        let (context_id, admitted, cancel) = (&self.context_id, self.admitted, &self.cancel);
+        self.access.with(|orch| {
+            orch.unpin(context_id);
+            orch.drain.finish(admitted);
+            orch.cancellations.release(context_id, cancel);
+        });
+    }
+}
+
+/// Runs `command`, or with `coalesce_window` set, returns the output of an
+/// identical async run in flight or just finished on the context.
+pub(crate) async fn drive<A: OrchestratorAccess>(mut access: A, command: String, context_id: String) -> String {
+    let run_id = uuid::Uuid::new_v4().to_string();
+    let (span, coalescer, clock) = access.with(|orch| {
+        let span = info_span!("process", run_id = %run_id, context_id = %context_id, command = %orch.redactor.redacted(&command));
+        (span, orch.async_coalescer.clone(), orch.clock.clone())
+    });
+    let key = (context_id.clone(), command.clone());
+    let run = run(access, command, context_id, run_id).instrument(span);
+    coalescer.run_async(&key.0, &key.1, || clock.now(), run).await.0
+}
+
+async fn run<A: OrchestratorAccess>(mut access: A, command: String, context_id: String, run_id: String) -> String {
+    let command = match access.with(|orch| orch.input_policy.clean(&command)) {
+        Ok(command) => command,
+        Err(err) => {
+            warn!("Run rejected: {}", err);
+            return serde_json::to_string(&[format!("Input Error: {}", err)]).unwrap_or_default();
+        }
+    };
+    let mut pinned = match Pinned::new(access, context_id.clone(), &command) {
+        Ok(pinned) => pinned,
+        Err(err) => {
+            warn!("Run rejected: {}", err);
+            return serde_json::to_string(&[format!("Shutdown Error: {}", err)]).unwrap_or_default();
+        }
+    };
+    let (admitted, cancel) = (pinned.admitted, pinned.cancel.clone());
+    let access = &mut pinned.access;
+    let (locks, clock, redactor) = access.with(|orch| (orch.run_locks.clone(), orch.clock.clone(), orch.redactor.clone()));
+    let _lock = locks.acquire_async(&context_id, &command, clock).await;
+    let plan_span = info_span!("proactive_plan", context_id = %context_id);
+    let templated = plan_span.in_scope(|| {
+        access.with(|orch| {
+            orch.ensure_context(&context_id);
+            // Expired goals are marked here too, though only `process` re-plans for them.
+            let _ = orch.sweep_goals(&context_id);
+            orch.template_plan(&command)
+        })
+    });
+    let plan = match templated {
+        Some(plan) => plan,
+        None => {
+            let planned = {
+                let command = command.clone();
+                let backend = access.with(|orch| orch.backend.clone());
+                blocking(move || backend.plan(&command)).instrument(plan_span).await
+            };
+            match planned {
+                Some(Ok(plan)) => plan,
+                Some(Err(err)) if err.is_invalid_plan() => {
+                    warn!("Plan rejected: {}", err);
+                    return serde_json::to_string(&[format!("Plan Error: {}", err)]).unwrap_or_default();
+                }
+                Some(Err(err)) => {
+                    warn!("Planner fallback: {}", err);
+                    Plan::from(vec![command.clone()])
+                }
+                None => Plan::from(vec![command.clone()]),
+            }
+        }
+    };
+    // Plan waves run one subtask at a time here, which topological order allows.
+    let subtasks = access.with(|orch| orch.validate_plan(&command, plan)).0.subtasks();
+
+    let blocked = match access.with(|orch| orch.review_plan(&subtasks)) {
+        Ok(blocked) => blocked,
+        Err(err) => {
+            warn!("Plan rejected: {}", err);
+            return serde_json::to_string(&[format!("Plan Error: {}", err)]).unwrap_or_default();
+        }
+    };
+
+    let (timeout, retry, max_replans) =
+        access.with(|orch| (orch.subtask_timeout, orch.retry_policy(), orch.max_replans));
+    let mut pending: VecDeque<(String, Option<OrchestratorError>)> = subtasks.into_iter().zip(blocked).collect();
+    let mut replans = 0;
+    let mut outputs = vec![];
+    let mut blackboard = Blackboard::default();
+    while let Some((sub, blocked)) = pending.pop_front() {
+        if cancel.is_cancelled() || !access.with(|orch| orch.drain.proceed(admitted)) {
+            break;
+        }
+        let expanded = match blocked {
+            Some(err) => Err(err),
+            None => {
+                let attributes = access.with(|orch| orch.contexts.read(&context_id, |context| context.attributes.clone()));
+                blackboard.expand_with(&sub, &attributes.unwrap_or_default())
+            }
+        };
+        let (sub, blocked) = match expanded {
+            Ok(expanded) if expanded != sub => {
+                let blocked = access.with(|orch| orch.review_subtask(&expanded));
+                (expanded, blocked)
+            }
+            Ok(_) => (sub, None),
+            Err(err) => (sub, Some(err)),
+        };
+        if let Some(err) = blocked {
+            let mut res = blocked_result(err);
+            res.set(AgentResult::RUN_ID, &run_id);
+            let strategies = access.with(|orch| {
+                orch.record_execution(&context_id, &command, &sub, &res, Duration::ZERO);
+                orch.debug_strategies()
+            });
+            let class = FailureClass::of(&res).unwrap_or(FailureClass::PolicyBlocked);
+            outputs.push(res.output);
+            access.with(|orch| orch.drain.progress(admitted, outputs.len(), pending.len()));
+            if strategies.strategy(class) == DebugStrategy::Abort {
+                break;
+            }
+            continue;
+        }
+        let job = access.with(|orch| orch.prepare_dispatch(sub.clone(), &context_id)).with_blackboard(&blackboard);
+        let agent = job.agent_name();
+        let retry = retry.clone();
+        let started = Instant::now();
+        let timeout = access.with(|orch| orch.drain.cap(timeout));
+        let dispatched = blocking(move || job.run_with_policy(&retry, timeout)).instrument(dispatch_span(&redactor.redacted(&sub), &context_id));
+        let res: AgentResult = match dispatched.await {
+            Some((mut res, context)) => {
+                access.with(|orch| {
+                    let duration = started.elapsed();
+                    timing::record_total(&mut res, duration);
+                    res.set(AgentResult::RUN_ID, &run_id);
+                    orch.metrics.dispatched(agent.as_deref(), &res, duration);
+                    if let Some(context) = context {
+                        orch.complete_dispatch(context);
+                    }
+                    orch.record_execution(&context_id, &command, &sub, &res, duration);
+                });
+                res
+            }
+            None => break,
+        };
+        outputs.push(res.output.clone());
+        if res.status && access.with(|orch| orch.agent_kind(&sub)) == Some(AgentKind::Llm) {
+            blackboard.set(Blackboard::step_output(outputs.len()), res.output.clone());
+        }
+
+        if !res.status && !cancel.is_cancelled() {
+            let (backend, memory, anomalies, strategies, anomaly, similar) = access.with(|orch| {
+                let anomaly = Anomaly::new(&context_id, &command, &sub, &res, orch.clock.now());
+                let similar = orch.similar_anomalies(&anomaly);
+                orch.remember_anomaly(&anomaly);
+                (orch.backend.clone(), orch.memory_store(), orch.anomalies.clone(), orch.debug_strategies(), anomaly, similar)
+            });
+            let debugged = blocking(move || {
+                debug_failure(backend.as_ref(), memory.as_deref(), &anomalies, &strategies, &res, anomaly, &similar)
+            });
+            let (decision, plan) = debugged.await.flatten().unzip();
+            if decision.is_some_and(|decision| decision.strategy == DebugStrategy::Abort) {
+                warn!("Dropping the rest of the plan after {:?} failed", redactor.redacted(&sub));
+                break;
+            }
+            if let Some(plan) = plan.flatten() {
+                access.with(|orch| orch.metrics.replanned());
+                let subtasks = plan.subtasks();
+                if replans < max_replans {
+                    match access.with(|orch| orch.review_plan(&subtasks)) {
+                        Ok(blocked) => {
+                            replans += 1;
+                            for step in subtasks.into_iter().zip(blocked).rev() {
+                                pending.push_front(step);
+                            }
+                        }
+                        Err(err) => warn!("Re-plan for {:?} rejected: {}", sub, err),
+                    }
+                } else {
+                    warn!("Re-plan limit of {} reached; keeping the plan for {:?}", max_replans, sub);
+                }
+            }
+        }
+        access.with(|orch| orch.drain.progress(admitted, outputs.len(), pending.len()));
+
+        tokio::task::yield_now().await;
+    }
+
+    serde_json::to_string(&outputs).unwrap_or_else(|_| outputs.join("\n"))
+}
@@ -0,0 +1,186 @@
+use crate::{OrchestratorError, ViralConfig};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Bytes a context's attributes may take as JSON unless configured otherwise.
+pub const DEFAULT_MAX_ATTRIBUTE_BYTES: usize = 64 * 1024;
+
+/// The prefix of a subtask's `{{attr.<key>}}` references to its context's attributes.
+pub const ATTRIBUTE_REFERENCE_PREFIX: &str = "attr.";
+
+/// The prefix of attributes that override the context's `ViralConfig` by field
+/// path, such as `viral.virality_threshold` or `viral.topology.mean_degree`.
+pub const VIRAL_ATTRIBUTE_PREFIX: &str = "viral.";
+
+/// Application data attached to a context, such as a campaign id or A/B arm, by key.
+pub type Attributes = HashMap<String, Value>;
+
+/// What `attributes` take as a JSON object, the measure `max_attribute_bytes` bounds.
+pub fn attributes_bytes(attributes: &Attributes) -> usize {
+    serde_json::to_vec(attributes).map_or(0, |json| json.len())
+}
+
+/// `value` as a `T`, failing with `Extraction` naming the attribute.
+pub(crate) fn typed<T: DeserializeOwned>(key: &str, value: Value) -> Result<T, OrchestratorError> {
+    serde_json::from_value(value).map_err(|err| {
+        OrchestratorError::extraction(&format!("attribute {:?}", key), std::any::type_name::<T>(), err)
+    })
+}
+
+/// `config` with the `viral.*` attributes laid over it, shorter paths first, as
+/// viral subtasks on the context resolve it. Fails with `Extraction` naming the
+/// attribute that leaves the settings invalid.
+pub(crate) fn viral_config(config: ViralConfig, attributes: &Attributes) -> Result<ViralConfig, OrchestratorError> {
+    let mut overrides: Vec<(&str, &Value)> = attributes
+        .iter()
+        .filter_map(|(key, value)| Some((key.strip_prefix(VIRAL_ATTRIBUTE_PREFIX)?, value)))
+        .collect();
+    if overrides.is_empty() {
+        return Ok(config);
+    }
+    overrides.sort_by_key(|(path, _)| *path);
+    let mut resolved = serde_json::to_value(config).map_err(OrchestratorError::serialization)?;
+    let mut config = config;
+    for (path, value) in overrides {
+        let key = format!("{}{}", VIRAL_ATTRIBUTE_PREFIX, path);
+        let invalid = |message: String| {
+            OrchestratorError::extraction(&format!("attribute {:?}", key), std::any::type_name::<ViralConfig>(), message)
+        };
+        let mut target = &mut resolved;
+        for field in path.split('.') {
+            target = match target {
+                Value::Object(fields) => fields.entry(field.to_string()).or_insert(Value::Null),
+                _ => return Err(invalid(format!("{:?} is not a setting with fields", field))),
+            };
+        }
+        *target = value.clone();
+        config = typed(&key, resolved.clone())?;
+        if let Some((field, message)) = config.invalid() {
+            return Err(invalid(format!("{} {}", field, message)));
+        }
+    }
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        AgentResult, CognitiveOrchestrator, MockBackend, OrchestratorError, PersistenceFormat, PlannerInputConfig, Topology,
+        ViralConfig,
+    };
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn orchestrator(max_bytes: usize) -> CognitiveOrchestrator {
+        let mock = MockBackend::new()
+            .plan("launch", ["post {{attr.arm}} teaser", "viral:simulate nodes={{attr.audience}}"])
+            .on("post", |rest| AgentResult::ok(format!("posted {}", rest)));
+        CognitiveOrchestrator::builder()
+            .backend(Arc::new(mock))
+            .prefer_native(true)
+            .max_attribute_bytes(max_bytes)
+            .learning(false)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn attributes_keep_their_types_through_every_persistence_format() {
+        let dir = std::env::temp_dir().join(format!("ace-attributes-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let values = [json!("c-42"), json!(7), json!(7.0), json!(-3), json!(u64::MAX), json!(null), json!({"arm": ["b", 2]})];
+        for format in [PersistenceFormat::Json, PersistenceFormat::MessagePack, PersistenceFormat::Bincode] {
+            let orch = orchestrator(1024).with_persistence_format(format);
+            for (idx, value) in values.iter().enumerate() {
+                orch.set_attribute("ctx1", &format!("k{}", idx), value.clone()).unwrap();
+            }
+            let path = dir.join(format!("contexts.{}", format.name()));
+            orch.save_contexts(&path).unwrap();
+            let loaded = orchestrator(1024);
+            loaded.load_contexts(&path).unwrap();
+            for (idx, value) in values.iter().enumerate() {
+                let got = loaded.get_attribute("ctx1", &format!("k{}", idx)).unwrap().unwrap();
+                assert_eq!(serde_json::to_string(&got).unwrap(), serde_json::to_string(value).unwrap(), "{:?}", format);
+            }
+            let export: serde_json::Value = serde_json::from_str(&orch.export_context("ctx1").unwrap()).unwrap();
+            assert_eq!(export["context"]["attributes"]["k2"].to_string(), "7.0");
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn typed_accessors_and_the_size_bound() {
+        let orch = orchestrator(64);
+        assert!(matches!(orch.get_attribute("ctx1", "arm"), Err(OrchestratorError::MissingContext { .. })));
+        orch.set_attribute("ctx1", "audience", json!(48)).unwrap();
+        assert_eq!(orch.get_attribute_as::<usize>("ctx1", "audience").unwrap(), Some(48));
+        assert_eq!(orch.get_attribute_as::<usize>("ctx1", "arm").unwrap(), None);
+        let err = orch.get_attribute_as::<String>("ctx1", "audience").unwrap_err();
+        assert_eq!(err.kind(), "extraction");
+        assert!(err.to_string().contains("attribute \"audience\""), "{}", err);
+
+        let err = orch.set_attribute("ctx1", "notes", json!("x".repeat(64))).unwrap_err();
+        assert_eq!(
+            err,
+            OrchestratorError::AttributesTooLarge { context_id: "ctx1".to_string(), bytes: 90, limit: 64 }
+        );
+        // A refused value leaves the attributes as they were; replacing one counts only the new value.
+        assert_eq!(orch.get_attribute("ctx1", "notes").unwrap(), None);
+        orch.set_attribute("ctx1", "audience", json!(64)).unwrap();
+        assert_eq!(orch.remove_attribute("ctx1", "audience").unwrap(), Some(json!(64)));
+        assert_eq!(orch.remove_attribute("ctx1", "audience").unwrap(), None);
+        assert!(orch.get_context("ctx1").unwrap().attributes.is_empty());
+    }
+
+    #[test]
+    fn planner_input_and_subtask_references_see_the_attributes() {
+        let orch = orchestrator(1024).with_planner_input(PlannerInputConfig::default());
+        orch.set_attribute("ctx1", "arm", json!("b")).unwrap();
+        orch.set_attribute("ctx1", "audience", json!(48)).unwrap();
+        let input = orch.planner_input("launch", "ctx1", &[]);
+        assert_eq!(input.attributes["arm"], json!("b"));
+        assert_eq!(serde_json::to_value(&input).unwrap()["attributes"], json!({"arm": "b", "audience": 48}));
+
+        let report = orch.process_report("launch".to_string(), "ctx1");
+        assert_eq!(report.results[0].output, "posted b teaser");
+        // `nodes=` overrides the run's simulation without committing to the context's metrics.
+        assert_eq!(report.results[1].metadata["overrides"]["nodes"], 48);
+        assert_eq!(orch.get_context("ctx1").unwrap().viral_metrics.engagement_nodes, 32);
+
+        orch.remove_attribute("ctx1", "arm").unwrap();
+        let report = orch.process_report("launch".to_string(), "ctx1");
+        let error = report.results[0].error.as_ref().unwrap();
+        assert_eq!(error.kind(), "unresolved_reference");
+    }
+
+    #[test]
+    fn viral_attributes_override_the_resolved_viral_config() {
+        let orch = orchestrator(1024);
+        let default = orch.viral_config("ctx1").unwrap();
+        orch.set_attribute("ctx1", "viral.virality_threshold", json!(0.0)).unwrap();
+        orch.set_attribute("ctx1", "viral.topology", json!({"kind": "small_world", "mean_degree": 4, "rewire": 0.1})).unwrap();
+        orch.set_attribute("ctx1", "viral.topology.mean_degree", json!(6)).unwrap();
+        let small_world = Topology::SmallWorld { mean_degree: 6, rewire: 0.1 };
+        assert_eq!(orch.viral_config("ctx1").unwrap(), ViralConfig { topology: small_world, virality_threshold: 0.0 });
+        let result = orch.dispatch("viral:simulate".to_string(), "ctx1");
+        assert!(result.status);
+        assert_eq!(result.metadata["virality_threshold"], 0.0);
+        assert_eq!(result.metadata["topology"], json!({"kind": "small_world", "mean_degree": 6, "rewire": 0.1}));
+
+        // They win over the context's own settings; other contexts keep the default.
+        orch.set_viral_config("ctx1", ViralConfig { virality_threshold: 0.9, ..default });
+        assert_eq!(orch.viral_config("ctx1").unwrap().virality_threshold, 0.0);
+        assert_eq!(orch.viral_config("ctx2").unwrap(), default);
+
+        // A value that leaves the settings invalid is refused as it is set, changing nothing.
+        let err = orch.set_attribute("ctx1", "viral.virality_threshold", json!(1.5)).unwrap_err();
+        assert_eq!(err.kind(), "extraction");
+        assert!(err.to_string().contains("attribute \"viral.virality_threshold\""), "{}", err);
+        assert_eq!(orch.get_attribute("ctx1", "viral.virality_threshold").unwrap(), Some(json!(0.0)));
+        assert_eq!(orch.set_attribute("ctx1", "viral.bogus", json!(1)).unwrap_err().kind(), "extraction");
+        assert_eq!(orch.get_attribute("ctx1", "viral.bogus").unwrap(), None);
+        assert_eq!(orch.viral_config("ctx1").unwrap().virality_threshold, 0.0);
+        assert!(orch.dispatch("viral:simulate".to_string(), "ctx1").status);
+    }
+}
@@ -0,0 +1,415 @@
+use crate::planning::{PlannerStep, Replan};
+use crate::timing;
+use crate::{call_agent, call_agent_or, Agent, AgentKind, AgentModules, AgentResult, Context, OrchestratorError, Plan, PlannerInput};
+use pythonize::{depythonize, pythonize};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Everything the orchestrator asks of its planner, LLM, viral, debug and memory
+/// agents. `PythonBackend` calls the configured Python classes; `MockBackend`
+/// answers from canned responses so runs need no Python agent modules.
+pub trait AgentBackend: Send + Sync {
+    /// Decomposes a command no plan template matched.
+    fn plan(&self, command: &str) -> Result<Plan, OrchestratorError>;
+
+    /// `plan` with what the command's context holds. Plans the input's single-string
+    /// prompt unless the backend can pass the whole input on.
+    fn plan_input(&self, input: &PlannerInput) -> Result<Plan, OrchestratorError> {
+        self.plan(input.prompt())
+    }
+
+    /// Completes a prompt for the LLM and content agents.
+    fn generate(&self, prompt: &str) -> Result<String, OrchestratorError>;
+
+    /// `generate` with the metadata the LLM reported, such as `tokens` and
+    /// `cost_usd`, for the LLM agent to charge to budgets.
+    fn generate_with_metadata(&self, prompt: &str) -> Result<(String, HashMap<String, serde_json::Value>), OrchestratorError> {
+        self.generate(prompt).map(|output| (output, HashMap::new()))
+    }
+
+    /// Runs the viral simulation when native propagation is off. The result must
+    /// carry a numeric `virality`.
+    fn simulate_viral(&self, nodes: usize, hook_rate: f64) -> Result<HashMap<String, serde_json::Value>, OrchestratorError>;
+
+    /// The debug agent's replacement for a low-virality failure.
+    fn re_plan(&self, alt: &str, context_id: &str) -> Result<Plan, OrchestratorError>;
+
+    /// Records a failed result when no native memory store is set.
+    fn log_anomaly(&self, text: &str, context_id: &str, payload: &Map<String, Value>) -> Result<(), OrchestratorError>;
+
+    /// A route consulted before the agent registry.
+    fn route(&self, _sub_task: &str) -> Option<Arc<dyn Agent>> {
+        None
+    }
+
+    /// The Python agent modules it calls, for `preflight` to check; `None` for a
+    /// backend that needs none.
+    fn agent_modules(&self) -> Option<&AgentModules> {
+        None
+    }
+}
+
+/// `LLMAgent.generate` returns a string, or `{"output": str, "metadata": dict}`
+/// to report usage.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Generation {
+    Text(String),
+    Reply {
+        output: String,
+        #[serde(default)]
+        metadata: HashMap<String, serde_json::Value>,
+    },
+}
+
+/// Calls the Python classes `AgentModules` points at, reusing their cached instances.
+#[derive(Clone, Default)]
+pub struct PythonBackend {
+    modules: AgentModules,
+}
+
+impl PythonBackend {
+    pub fn new(modules: AgentModules) -> Self {
+        Self { modules }
+    }
+}
+
+impl AgentBackend for PythonBackend {
+    fn plan(&self, command: &str) -> Result<Plan, OrchestratorError> {
+        // Use Python planner agent for general decomposition
+        let steps = timing::with_gil(|py| {
+            let planner = self.modules.instance(py, AgentKind::Planner)?;
+            let steps = call_agent(py, planner, "decompose", (command,))?;
+            depythonize::<Vec<PlannerStep>>(steps)
+                .map_err(|e| OrchestratorError::extraction("PlannerAgent.decompose", "list[str] or list[dict]", e))
+        })?;
+        Plan::from_planner(steps)
+    }
+
+    /// Passes `input` to `decompose` as a dict, and its prompt to a planner that
+    /// raises `TypeError` on the dict.
+    fn plan_input(&self, input: &PlannerInput) -> Result<Plan, OrchestratorError> {
+        let steps = timing::with_gil(|py| {
+            let planner = self.modules.instance(py, AgentKind::Planner)?;
+            let dict = pythonize(py, input).map_err(|e| OrchestratorError::call(py, "planner input", e.into()))?;
+            let steps = call_agent_or(py, planner, "decompose", (dict,), (input.prompt(),))?;
+            depythonize::<Vec<PlannerStep>>(steps)
+                .map_err(|e| OrchestratorError::extraction("PlannerAgent.decompose", "list[str] or list[dict]", e))
+        })?;
+        Plan::from_planner(steps)
+    }
+
+    fn generate(&self, prompt: &str) -> Result<String, OrchestratorError> {
+        self.generate_with_metadata(prompt).map(|(output, _)| output)
+    }
+
+    fn generate_with_metadata(&self, prompt: &str) -> Result<(String, HashMap<String, serde_json::Value>), OrchestratorError> {
+        let generation = timing::with_gil(|py| {
+            let llm = self.modules.instance(py, AgentKind::Llm)?;
+            let reply = call_agent(py, llm, "generate", (prompt,))?;
+            depythonize::<Generation>(reply)
+                .map_err(|e| OrchestratorError::extraction("LLMAgent.generate", "str or dict", e))
+        })?;
+        Ok(match generation {
+            Generation::Text(output) => (output, HashMap::new()),
+            Generation::Reply { output, metadata } => (output, metadata),
+        })
+    }
+
+    fn simulate_viral(&self, nodes: usize, hook_rate: f64) -> Result<HashMap<String, serde_json::Value>, OrchestratorError> {
+        timing::with_gil(|py| {
+            let viral = self.modules.instance(py, AgentKind::Viral)?;
+            let result_py = call_agent(py, viral, "simulate_viral_engagement", (nodes, hook_rate))?;
+            depythonize::<HashMap<String, serde_json::Value>>(result_py)
+                .map_err(|e| OrchestratorError::extraction("ViralAgent.simulate_viral_engagement", "dict", e))
+        })
+    }
+
+    fn re_plan(&self, alt: &str, context_id: &str) -> Result<Plan, OrchestratorError> {
+        timing::with_gil(|py| {
+            let debug = self.modules.instance(py, AgentKind::Debug)?;
+            let reply = call_agent(py, debug, "re_plan", (alt, context_id))?;
+            depythonize::<Replan>(reply)
+                .map_err(|e| OrchestratorError::extraction("DebugAgent.re_plan", "str or list", e))
+        })
+        .and_then(Replan::into_plan)
+    }
+
+    fn log_anomaly(&self, text: &str, context_id: &str, payload: &Map<String, Value>) -> Result<(), OrchestratorError> {
+        timing::with_gil(|py| {
+            let memory = self.modules.instance(py, AgentKind::Memory)?;
+            let payload = pythonize(py, payload).map_err(|e| OrchestratorError::call(py, "payload", e.into()))?;
+            call_agent(py, memory, "store_context", (text, context_id, payload))?;
+            Ok(())
+        })
+    }
+
+    fn agent_modules(&self) -> Option<&AgentModules> {
+        Some(&self.modules)
+    }
+}
+
+type Handler = Arc<dyn Fn(&str) -> AgentResult + Send + Sync>;
+type Planner = Arc<dyn Fn(&str) -> Result<Plan, OrchestratorError> + Send + Sync>;
+type Generator = Arc<dyn Fn(&str) -> Result<String, OrchestratorError> + Send + Sync>;
+
+/// Canned agent responses for tests. Subtasks starting with a prefix given to
+/// `on` dispatch to its handler ahead of every registered agent; the rest reach the
+/// built-in agents, whose Python calls are answered here instead:
+///
+/// - planning uses the longest matching `plan` prefix, else `planner`, else
+///   runs the command as its only step;
+/// - `generate` echoes the prompt unless `generator` is set;
+/// - the viral simulation reports the `virality` score, 0.0 by default;
+/// - `re_plan` returns the `replan` steps, and fails when there are none.
+///
+/// Anomalies and re-plan requests are recorded for inspection.
+#[derive(Default)]
+pub struct MockBackend {
+    routes: Vec<(String, Arc<dyn Agent>)>,
+    plans: Vec<(String, Vec<String>)>,
+    planner: Option<Planner>,
+    generator: Option<Generator>,
+    virality: f64,
+    replan: Option<Vec<String>>,
+    anomalies: Mutex<Vec<(String, String)>>,
+    replans: Mutex<Vec<(String, String)>>,
+}
+
+impl MockBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answers subtasks starting with `prefix`; the handler gets the rest of the
+    /// subtask, trimmed. Longer prefixes win.
+    pub fn on(mut self, prefix: &str, handler: impl Fn(&str) -> AgentResult + Send + Sync + 'static) -> Self {
+        let agent = CannedAgent { name: format!("mock:{}", prefix), prefix: prefix.to_string(), handler: Arc::new(handler) };
+        self.routes.push((prefix.to_string(), Arc::new(agent)));
+        self.routes.sort_by_key(|(prefix, _)| std::cmp::Reverse(prefix.len()));
+        self
+    }
+
+    /// Plans commands starting with `prefix` as a linear chain of `steps`.
+    pub fn plan<S: Into<String>>(mut self, prefix: &str, steps: impl IntoIterator<Item = S>) -> Self {
+        self.plans.push((prefix.to_string(), steps.into_iter().map(Into::into).collect()));
+        self.plans.sort_by_key(|(prefix, _)| std::cmp::Reverse(prefix.len()));
+        self
+    }
+
+    /// Plans commands no `plan` prefix matched.
+    pub fn planner(mut self, planner: impl Fn(&str) -> Result<Plan, OrchestratorError> + Send + Sync + 'static) -> Self {
+        self.planner = Some(Arc::new(planner));
+        self
+    }
+
+    pub fn generator(mut self, generator: impl Fn(&str) -> Result<String, OrchestratorError> + Send + Sync + 'static) -> Self {
+        self.generator = Some(Arc::new(generator));
+        self
+    }
+
+    pub fn virality(mut self, virality: f64) -> Self {
+        self.virality = virality;
+        self
+    }
+
+    pub fn replan<S: Into<String>>(mut self, steps: impl IntoIterator<Item = S>) -> Self {
+        self.replan = Some(steps.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// `(text, context_id)` of every anomaly logged so far.
+    pub fn anomalies(&self) -> Vec<(String, String)> {
+        self.anomalies.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// `(alt, context_id)` of every re-plan requested so far.
+    pub fn replans(&self) -> Vec<(String, String)> {
+        self.replans.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+impl AgentBackend for MockBackend {
+    fn plan(&self, command: &str) -> Result<Plan, OrchestratorError> {
+        if let Some((_, steps)) = self.plans.iter().find(|(prefix, _)| command.starts_with(prefix.as_str())) {
+            return Ok(Plan::linear(steps.iter().cloned()));
+        }
+        match &self.planner {
+            Some(planner) => planner(command),
+            None => Ok(Plan::from(vec![command.to_string()])),
+        }
+    }
+
+    fn generate(&self, prompt: &str) -> Result<String, OrchestratorError> {
+        match &self.generator {
+            Some(generator) => generator(prompt),
+            None => Ok(prompt.to_string()),
+        }
+    }
+
+    fn simulate_viral(&self, _nodes: usize, _hook_rate: f64) -> Result<HashMap<String, serde_json::Value>, OrchestratorError> {
+        let mut result = HashMap::new();
+        result.insert("virality".to_string(), serde_json::Value::from(self.virality));
+        result.insert("engine".to_string(), serde_json::Value::from("mock"));
+        Ok(result)
+    }
+
+    fn re_plan(&self, alt: &str, context_id: &str) -> Result<Plan, OrchestratorError> {
+        self.replans.lock().unwrap_or_else(|e| e.into_inner()).push((alt.to_string(), context_id.to_string()));
+        match &self.replan {
+            Some(steps) => Ok(Plan::linear(steps.iter().cloned())),
+            None => Err(OrchestratorError::CallFailed {
+                target: "MockBackend.re_plan".to_string(),
+                message: "no re-plan configured".to_string(),
+                traceback: None,
+            }),
+        }
+    }
+
+    fn log_anomaly(&self, text: &str, context_id: &str, _payload: &Map<String, Value>) -> Result<(), OrchestratorError> {
+        self.anomalies.lock().unwrap_or_else(|e| e.into_inner()).push((text.to_string(), context_id.to_string()));
+        Ok(())
+    }
+
+    fn route(&self, sub_task: &str) -> Option<Arc<dyn Agent>> {
+        self.routes
+            .iter()
+            .find(|(prefix, _)| sub_task.starts_with(prefix.as_str()))
+            .map(|(_, agent)| agent.clone())
+    }
+}
+
+/// A `MockBackend::on` route.
+struct CannedAgent {
+    name: String,
+    prefix: String,
+    handler: Handler,
+}
+
+impl Agent for CannedAgent {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn can_handle(&self, sub_task: &str) -> bool {
+        sub_task.starts_with(self.prefix.as_str())
+    }
+
+    fn execute(&self, sub_task: &str, _ctx: &mut Context) -> AgentResult {
+        (self.handler)(sub_task[self.prefix.len()..].trim())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CognitiveOrchestrator;
+
+    fn ok(output: &str) -> AgentResult {
+        AgentResult { output: output.to_string(), status: true, metadata: HashMap::new(), error: None }
+    }
+
+    fn failed(output: &str) -> AgentResult {
+        AgentResult { output: output.to_string(), status: false, metadata: HashMap::new(), error: None }
+    }
+
+    fn orchestrator(mock: &Arc<MockBackend>) -> CognitiveOrchestrator {
+        CognitiveOrchestrator::builder().backend(mock.clone()).build().unwrap()
+    }
+
+    fn outputs(output: &str) -> Vec<String> {
+        serde_json::from_str(output).unwrap()
+    }
+
+    #[test]
+    fn plans_from_canned_steps_and_falls_back_to_the_command() {
+        let mock = Arc::new(MockBackend::new().plan("launch", ["query llm draft", "query llm polish"]));
+        let orch = orchestrator(&mock);
+
+        let plan = orch.proactive_plan("launch campaign".to_string(), "ctx").unwrap();
+        assert_eq!(plan.subtasks(), ["query llm draft", "query llm polish"]);
+        let plan = orch.proactive_plan("eval metrics".to_string(), "ctx").unwrap();
+        assert_eq!(plan.subtasks(), ["eval metrics"]);
+    }
+
+    #[test]
+    fn planner_failure_degrades_to_a_single_step() {
+        let mock = Arc::new(MockBackend::new().planner(|command| {
+            Err(OrchestratorError::CallFailed { target: "planner".to_string(), message: command.to_string(), traceback: None })
+        }));
+        let orch = orchestrator(&mock);
+
+        assert_eq!(outputs(&orch.process("query llm hello".to_string(), "ctx")), ["hello"]);
+    }
+
+    #[test]
+    fn dispatch_prefers_canned_routes_over_registered_agents() {
+        let mock = Arc::new(
+            MockBackend::new()
+                .on("query llm", |prompt| ok(&prompt.to_uppercase()))
+                .on("query llm secret", |_| ok("redacted")),
+        );
+        let orch = orchestrator(&mock);
+
+        assert_eq!(orch.dispatch("query llm hi".to_string(), "ctx").output, "HI");
+        assert_eq!(orch.dispatch("query llm secret plan".to_string(), "ctx").output, "redacted");
+        assert_eq!(orch.get_history("ctx", None).len(), 2);
+    }
+
+    #[test]
+    fn builtin_agents_call_the_backend() {
+        let mock = Arc::new(MockBackend::new().generator(|prompt| Ok(format!("post about {}", prompt))).virality(0.9));
+        let orch = orchestrator(&mock);
+
+        assert_eq!(orch.dispatch("query llm cats".to_string(), "ctx").output, "post about cats");
+        let viral = orch.dispatch("go viral".to_string(), "ctx");
+        assert!(viral.status, "{}", viral.output);
+        assert_eq!(viral.metadata["engine"], "mock");
+    }
+
+    #[test]
+    fn failures_are_logged_as_anomalies() {
+        let mock = Arc::new(MockBackend::new().on("deploy", |_| failed("deploy failed")));
+        let orch = orchestrator(&mock);
+
+        let result = orch.dispatch("deploy now".to_string(), "ctx");
+        assert!(!result.status);
+        assert!(orch.self_debug(&result, "deploy now", "ctx").is_none());
+        assert_eq!(mock.anomalies(), [("Anomaly: deploy failed".to_string(), "ctx".to_string())]);
+        assert!(mock.replans().is_empty());
+    }
+
+    #[test]
+    fn low_virality_replans_and_runs_the_replacement() {
+        let mock = Arc::new(
+            MockBackend::new()
+                .plan("campaign", ["post teaser", "post launch"])
+                .on("post teaser", |_| failed("low virality on teaser"))
+                .on("post launch", |_| ok("launched"))
+                .on("post alt", |_| ok("alt posted"))
+                .replan(["post alt"]),
+        );
+        let orch = orchestrator(&mock);
+
+        let output = outputs(&orch.process("campaign".to_string(), "ctx"));
+        assert_eq!(output, ["low virality on teaser", "alt posted", "launched"]);
+        assert_eq!(mock.replans(), [("replan viral alt strategy".to_string(), "ctx".to_string())]);
+        assert_eq!(mock.anomalies().len(), 1);
+    }
+
+    #[test]
+    fn replans_stop_at_the_limit() {
+        let mock = Arc::new(
+            MockBackend::new()
+                .on("post", |_| failed("low virality"))
+                .replan(["post again"]),
+        );
+        let orch = CognitiveOrchestrator::builder().backend(mock.clone()).max_replans(2).build().unwrap();
+
+        let output = outputs(&orch.process("post once".to_string(), "ctx"));
+        assert_eq!(output.len(), 3);
+        assert_eq!(mock.replans().len(), 3);
+    }
+}
@@ -0,0 +1,157 @@
+use crate::history::secs;
+use crate::streaming::ProcessRun;
+use crate::{CognitiveOrchestrator, OrchestratorError, ProcessEvent};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{debug, info_span};
+
+/// One request of `CognitiveOrchestrator::process_batch`, once it has run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchOutcome {
+    /// Position of the request in the batch.
+    pub index: usize,
+    pub context_id: String,
+    pub command: String,
+    /// The JSON array `process` returns.
+    pub output: String,
+    /// A rejected plan, or else the errors of failed subtasks in the order they ran.
+    pub errors: Vec<OrchestratorError>,
+    pub started_at: DateTime<Utc>,
+    #[serde(with = "secs")]
+    pub duration: Duration,
+}
+
+/// A batch request as Python passes it.
+#[derive(Deserialize)]
+pub(crate) struct BatchRequest {
+    pub command: String,
+    pub context_id: String,
+}
+
+/// Runs the requests on up to `concurrency` worker threads, each taking the next
+/// request as it finishes one, and calls `finished` from the worker as soon as a
+/// request completes. Outcomes come back in request order. The workers share the
+/// orchestrator, so they wait on each other only for a context two of them are
+/// running on and for the GIL, which each Python call takes for itself. Must not
+/// be called while holding the GIL.
+pub(crate) fn run(
+    orch: &CognitiveOrchestrator,
+    requests: Vec<(String, String)>,
+    concurrency: usize,
+    finished: impl Fn(&BatchOutcome) + Sync,
+) -> Vec<BatchOutcome> {
+    let span = info_span!("process_batch", requests = requests.len(), concurrency);
+    let next = AtomicUsize::new(0);
+    let outcomes: Mutex<Vec<Option<BatchOutcome>>> = Mutex::new((0..requests.len()).map(|_| None).collect());
+    let workers = concurrency.clamp(1, requests.len().max(1));
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                let _entered = span.enter();
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some((command, context_id)) = requests.get(index) else { return };
+                    let outcome = run_request(orch, index, command.clone(), context_id.clone());
+                    finished(&outcome);
+                    outcomes.lock().unwrap_or_else(|e| e.into_inner())[index] = Some(outcome);
+                }
+            });
+        }
+    });
+    outcomes.into_inner().unwrap_or_else(|e| e.into_inner()).into_iter().flatten().collect()
+}
+
+fn run_request(orch: &CognitiveOrchestrator, index: usize, command: String, context_id: String) -> BatchOutcome {
+    let started_at = orch.clock.now();
+    let started = Instant::now();
+    let mut run = ProcessRun::new(command.clone(), context_id.clone(), None);
+    let mut output = String::new();
+    let mut errors = vec![];
+    while let Some(event) = run.next_event(orch) {
+        match event {
+            ProcessEvent::SubtaskFinished { result, .. } => errors.extend(result.error),
+            ProcessEvent::Completed { output: completed, .. } => output = completed,
+            _ => {}
+        }
+    }
+    if let Some(err) = run.plan_error() {
+        errors.push(err.clone());
+    }
+    debug!(index, context_id = %context_id, errors = errors.len(), "batch request done");
+    BatchOutcome { index, context_id, command, output, errors, started_at, duration: started.elapsed() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AgentResult, MockBackend};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    fn requests(n: usize) -> Vec<(String, String)> {
+        (0..n).map(|i| (format!("post clip {}", i), format!("ctx{}", i))).collect()
+    }
+
+    fn orchestrator(delay: Duration) -> CognitiveOrchestrator {
+        let mock = MockBackend::new()
+            .on("post", move |rest| {
+                thread::sleep(delay);
+                AgentResult { output: rest.to_string(), status: true, metadata: HashMap::new(), error: None }
+            })
+            .on("bogus", |_| {
+                AgentResult::from_error("Dispatch Error", OrchestratorError::Cancelled { subtask: "bogus".to_string() })
+            });
+        CognitiveOrchestrator::builder().backend(Arc::new(mock)).build().unwrap()
+    }
+
+    #[test]
+    fn outcomes_come_back_in_request_order() {
+        let orch = orchestrator(Duration::ZERO);
+        let mut batch = requests(5);
+        batch.push(("bogus".to_string(), "ctx5".to_string()));
+        let outcomes = orch.process_batch(batch, 3);
+
+        let indices: Vec<usize> = outcomes.iter().map(|outcome| outcome.index).collect();
+        assert_eq!(indices, [0, 1, 2, 3, 4, 5]);
+        assert_eq!(outcomes[2].context_id, "ctx2");
+        assert_eq!(outcomes[2].output, r#"["clip 2"]"#);
+        assert!(outcomes[..5].iter().all(|outcome| outcome.errors.is_empty()));
+        assert!(matches!(outcomes[5].errors.as_slice(), [OrchestratorError::Cancelled { .. }]));
+        assert!(orch.get_context("ctx4").is_some());
+        assert_eq!(orch.get_history("ctx3", None).len(), 1);
+    }
+
+    #[test]
+    fn requests_run_concurrently() {
+        let orch = orchestrator(Duration::from_millis(100));
+        let started = Instant::now();
+        let outcomes = orch.process_batch(requests(4), 4);
+        assert_eq!(outcomes.len(), 4);
+        assert!(started.elapsed() < Duration::from_millis(300), "{:?}", started.elapsed());
+    }
+
+    #[test]
+    fn finished_requests_are_appended_to_the_jsonl_file() {
+        let path = std::env::temp_dir().join(format!("ace-batch-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let orch = orchestrator(Duration::ZERO);
+        let outcomes = orch.process_batch_jsonl(requests(3), 2, &path).unwrap();
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let mut lines: Vec<BatchOutcome> = written.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        lines.sort_by_key(|outcome| outcome.index);
+        let summary = |outcomes: &[BatchOutcome]| -> Vec<(usize, String)> {
+            outcomes.iter().map(|outcome| (outcome.index, outcome.output.clone())).collect()
+        };
+        assert_eq!(summary(&lines), summary(&outcomes));
+
+        let missing = std::env::temp_dir().join("ace-batch-missing-dir").join("out.jsonl");
+        assert!(matches!(orch.process_batch_jsonl(requests(1), 1, &missing), Err(OrchestratorError::Io { .. })));
+    }
+}
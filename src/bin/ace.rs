@@ -0,0 +1,596 @@
+use clap::{Parser, Subcommand};
+use sovereign_cli::{
+    export_schemas, run_workload, workload_backend, Arrival, CognitiveOrchestrator, CognitiveOrchestratorBuilder, CommandMix,
+    CheckStatus, Config, Context, ContextDistribution, Plan, PlanEstimate, PreflightCheck, PreflightReport, ProcessEvent,
+    ProcessReport, RunDiff, WorkloadReport, WorkloadSpec, DEFAULT_TENANT,
+};
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Runs and inspects ACE-AGI orchestrator commands. Contexts persist in the
+/// state file between invocations.
+#[derive(Parser)]
+#[command(name = "ace", version)]
+struct Cli {
+    /// TOML or YAML config file; `ACE_*` environment variables override it.
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Context snapshot loaded at start and saved after changes.
+    #[arg(long, global = true, default_value = ".ace-contexts.json")]
+    state: PathBuf,
+
+    /// One JSON document per line instead of human-readable output.
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Plans and runs a command, printing each subtask as it finishes.
+    Run {
+        command: String,
+        #[arg(long, default_value = "default")]
+        context: String,
+        /// Per-subtask timeout in seconds.
+        #[arg(long)]
+        timeout: Option<f64>,
+        /// Also prints how the run differs from the context's last run of the
+        /// same command, kept beside the state file.
+        #[arg(long)]
+        diff: bool,
+    },
+    /// Shows the plan for a command without running it.
+    Plan {
+        command: String,
+        #[arg(long, default_value = "default")]
+        context: String,
+        /// Also estimates each subtask's agent, LLM tokens and wall time, and
+        /// whether the context's budget would cover it.
+        #[arg(long)]
+        estimate: bool,
+    },
+    /// Lists, shows or deletes saved contexts.
+    Contexts {
+        #[command(subcommand)]
+        action: ContextsAction,
+    },
+    /// Reads commands from stdin and runs each against one context.
+    Repl {
+        #[arg(long, default_value = "default")]
+        context: String,
+    },
+    /// Writes the JSON Schemas of results, contexts, viral metrics and process
+    /// reports into a directory, one `<Type>.schema.json` each.
+    Schema {
+        #[arg(default_value = "schemas")]
+        dir: PathBuf,
+    },
+    /// Sends a seeded synthetic workload through a mock backend and reports
+    /// latency percentiles, failure rates and evictions. Leaves the state alone.
+    Bench {
+        /// The same seed sends the same requests.
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+        #[arg(long, default_value_t = 1000)]
+        requests: usize,
+        #[arg(long, default_value_t = 100)]
+        contexts: usize,
+        /// Picks contexts with this Zipf exponent rather than uniformly.
+        #[arg(long)]
+        zipf: Option<f64>,
+        /// Weights of LLM, viral and unknown commands, e.g. `0.5,0.4,0.1`.
+        #[arg(long, value_delimiter = ',', default_values_t = [0.5, 0.4, 0.1])]
+        mix: Vec<f64>,
+        /// Requests in flight at once.
+        #[arg(long, default_value_t = 8)]
+        concurrency: usize,
+        /// Sends this many requests a second rather than each as one completes.
+        #[arg(long)]
+        rate: Option<f64>,
+        /// How long each mock LLM call takes, in milliseconds.
+        #[arg(long, default_value_t = 0)]
+        llm_latency_ms: u64,
+    },
+    /// Checks the config, agent modules, memory store and persistence paths,
+    /// printing what to fix for each failure. Exits 2 when any check fails.
+    Doctor {
+        /// Also plans and generates a reply to a no-op prompt through the agents.
+        #[arg(long)]
+        with_smoke: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ContextsAction {
+    List,
+    Show { id: String },
+    Delete { id: String },
+}
+
+/// ANSI styling, off for `--json`, non-terminals and when `NO_COLOR` is set.
+struct Style {
+    color: bool,
+}
+
+impl Style {
+    fn new(json: bool) -> Self {
+        Self { color: !json && std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none() }
+    }
+
+    fn paint(&self, code: &str, text: &str) -> String {
+        if self.color {
+            format!("\x1b[{}m{}\x1b[0m", code, text)
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn ok(&self, text: &str) -> String {
+        self.paint("32", text)
+    }
+
+    fn failed(&self, text: &str) -> String {
+        self.paint("31", text)
+    }
+
+    fn note(&self, text: &str) -> String {
+        self.paint("33", text)
+    }
+
+    fn dim(&self, text: &str) -> String {
+        self.paint("2", text)
+    }
+}
+
+struct App {
+    orchestrator: CognitiveOrchestrator,
+    state: PathBuf,
+    json: bool,
+    style: Style,
+}
+
+impl App {
+    fn new(cli: &Cli) -> Result<Self, String> {
+        let config = config(cli)?;
+        let auto_diff = matches!(cli.command, Command::Run { diff: true, .. });
+        let orchestrator =
+            CognitiveOrchestratorBuilder::from(config).build().map_err(|e| e.to_string())?.with_auto_diff(auto_diff);
+        if cli.state.exists() {
+            let report = orchestrator.load_contexts(&cli.state).map_err(|e| e.to_string())?;
+            for (id, reason) in &report.failed {
+                eprintln!("ace: skipped context {} in {}: {}", id, cli.state.display(), reason);
+            }
+        }
+        Ok(Self { orchestrator, state: cli.state.clone(), json: cli.json, style: Style::new(cli.json) })
+    }
+
+    fn save(&self) -> Result<(), String> {
+        self.orchestrator.save_contexts(&self.state).map_err(|e| e.to_string())
+    }
+
+    fn print_json(&self, value: &impl serde::Serialize) {
+        println!("{}", serde_json::to_string(value).unwrap_or_default());
+    }
+
+    /// Each context's last run with `--diff`, beside the state file.
+    fn runs_path(&self) -> PathBuf {
+        self.state.with_extension("runs.json")
+    }
+
+    fn load_runs(&self) -> Result<HashMap<String, ProcessReport>, String> {
+        let path = self.runs_path();
+        if !path.exists() {
+            return Ok(HashMap::new());
+        }
+        let text = std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        serde_json::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Runs `command` on `context_id` against the last run kept for it, then keeps
+    /// this one in its place and prints the diff.
+    fn run_with_diff(&mut self, command: String, context_id: &str, timeout: Option<Duration>) -> Result<bool, String> {
+        let mut runs = self.load_runs()?;
+        if let Some(previous) = runs.remove(context_id) {
+            self.orchestrator.set_last_report(context_id, previous);
+        }
+        let succeeded = self.run(command, context_id, timeout);
+        let Some(report) = self.orchestrator.last_report(context_id) else { return Ok(succeeded) };
+        self.print_diff(report.diff.as_ref());
+        runs.insert(context_id.to_string(), report);
+        let path = self.runs_path();
+        let text = serde_json::to_string(&runs).map_err(|e| e.to_string())?;
+        std::fs::write(&path, text).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(succeeded)
+    }
+
+    fn print_diff(&self, diff: Option<&RunDiff>) {
+        if self.json {
+            return self.print_json(&serde_json::json!({ "diff": diff }));
+        }
+        let Some(diff) = diff else {
+            return println!("{} no earlier run of this command", self.style.dim("diff:"));
+        };
+        if diff.is_empty() {
+            return println!("{} no changes", self.style.dim("diff:"));
+        }
+        println!("{}", self.style.dim("diff:"));
+        for subtask in &diff.added {
+            println!("  {} {}", self.style.ok("+"), subtask);
+        }
+        for subtask in &diff.removed {
+            println!("  {} {}", self.style.failed("-"), subtask);
+        }
+        if diff.reordered {
+            println!("  {} shared subtasks reordered", self.style.note("~"));
+        }
+        for flip in &diff.status_flips {
+            let status = |ok: bool| if ok { self.style.ok("ok") } else { self.style.failed("FAIL") };
+            println!("  {} {} -> {}  {}", self.style.note("status"), status(flip.before), status(flip.after), flip.subtask);
+        }
+        for delta in &diff.metric_deltas {
+            println!("  {} {} {:.4} -> {:.4} ({:+.4})", self.style.note("metric"), delta.metric, delta.before, delta.after, delta.delta);
+        }
+        for slow in &diff.latency_regressions {
+            println!(
+                "  {} {} {:.1}ms -> {:.1}ms (+{:.0}%)",
+                self.style.failed("slower"),
+                slow.subtask,
+                slow.before_ms,
+                slow.after_ms,
+                slow.change_pct
+            );
+        }
+    }
+
+    fn run(&mut self, command: String, context_id: &str, timeout: Option<Duration>) -> bool {
+        let mut started = VecDeque::new();
+        let mut succeeded = true;
+        let json = self.json;
+        let style = &self.style;
+        self.orchestrator.process_streaming_with_timeout(command, context_id, timeout, |event| {
+            if json {
+                println!("{}", serde_json::to_string(&event).unwrap_or_default());
+            }
+            match event {
+                ProcessEvent::PlanReady { subtasks, .. } if !json => {
+                    println!("{} {}", style.dim("plan:"), subtasks.join(" -> "));
+                }
+                ProcessEvent::SubtaskStarted { subtask, .. } => started.push_back(subtask),
+                ProcessEvent::SubtaskFinished { result, at, .. } => {
+                    let subtask = started.pop_front().unwrap_or_default();
+                    succeeded &= result.status;
+                    if !json {
+                        let mark = if result.status { style.ok("ok  ") } else { style.failed("FAIL") };
+                        let elapsed = style.dim(&format!("{:.2}s", at.as_secs_f64()));
+                        println!("{} {} {}  {}", mark, subtask, elapsed, result.output);
+                    }
+                }
+                ProcessEvent::ReplanTriggered { subtask, subtasks, .. } if !json => {
+                    println!("{} {} -> {}", style.note("replan"), subtask, subtasks.join(" -> "));
+                }
+                _ => {}
+            }
+        });
+        succeeded
+    }
+
+    fn print_plan(&self, plan: &Plan) {
+        if self.json {
+            return self.print_json(plan);
+        }
+        for node in plan.nodes() {
+            let deps: Vec<String> = node.depends_on.iter().map(|dep| dep.to_string()).collect();
+            let after = if deps.is_empty() { String::new() } else { self.style.dim(&format!("  (after {})", deps.join(", "))) };
+            println!("{:>3}. {}{}", node.id, node.subtask, after);
+        }
+    }
+
+    fn print_estimate(&self, estimate: &PlanEstimate) {
+        if self.json {
+            return self.print_json(estimate);
+        }
+        let deps: std::collections::HashMap<_, _> =
+            estimate.plan.nodes().iter().map(|node| (node.id, &node.depends_on)).collect();
+        for subtask in &estimate.subtasks {
+            let after = match deps.get(&subtask.id) {
+                Some(deps) if !deps.is_empty() => {
+                    let deps: Vec<String> = deps.iter().map(|dep| dep.to_string()).collect();
+                    self.style.dim(&format!("  (after {})", deps.join(", ")))
+                }
+                _ => String::new(),
+            };
+            let agent = subtask.agent.clone().unwrap_or_else(|| self.style.failed("unrouted"));
+            let tokens = if subtask.llm { format!("  ~{} tokens", subtask.llm_tokens) } else { String::new() };
+            let latency = subtask.latency.map_or_else(
+                || "-".to_string(),
+                |latency| format!("p50 {:.2}s p95 {:.2}s", latency.p50.as_secs_f64(), latency.p95.as_secs_f64()),
+            );
+            println!("{:>3}. {}{}  {}{}  {}", subtask.id, subtask.subtask, after, agent, tokens, self.style.dim(&latency));
+        }
+        println!("  llm calls:      {}", estimate.llm_calls);
+        println!("  llm tokens:     ~{}", estimate.llm_tokens);
+        let unknown = match estimate.without_latency {
+            0 => String::new(),
+            n => self.style.dim(&format!("  ({} without latency history)", n)),
+        };
+        println!(
+            "  wall time:      p50 {:.2}s p95 {:.2}s{}",
+            estimate.wall_time_p50.as_secs_f64(),
+            estimate.wall_time_p95.as_secs_f64(),
+            unknown
+        );
+        match &estimate.budget_exceeded {
+            Some(limit) => println!("  budget:         {}", self.style.failed(&format!("would exceed {}", limit))),
+            None => println!("  budget:         {}", self.style.ok("within")),
+        }
+    }
+
+    fn print_context(&self, context: &Context) {
+        if self.json {
+            return self.print_json(context);
+        }
+        let metrics = &context.viral_metrics;
+        println!("{}", context.context_id);
+        let goals: Vec<&str> = context.open_goals().collect();
+        println!("  goals:          {}", if goals.is_empty() { "-".to_string() } else { goals.join("; ") });
+        println!("  virality:       {:.4}", metrics.virality_score);
+        println!("  nodes:          {}", metrics.engagement_nodes);
+        println!("  hook rate:      {:.4}", metrics.hook_rate);
+        println!("  amplification:  {:.4}", metrics.amplification_factor);
+        println!("  fidelity:       {:.4}", metrics.quantum_fidelity);
+        println!("  memories:       {}", context.memory_vectors.len());
+        println!("  created:        {}", context.created_at.to_rfc3339());
+        println!("  last accessed:  {}", context.last_accessed.to_rfc3339());
+    }
+
+    fn contexts(&mut self, action: ContextsAction) -> Result<(), String> {
+        match action {
+            ContextsAction::List => {
+                let contexts = self.orchestrator.list_contexts(DEFAULT_TENANT);
+                if self.json {
+                    self.print_json(&contexts);
+                } else if contexts.is_empty() {
+                    println!("{}", self.style.dim(&format!("no contexts in {}", self.state.display())));
+                } else {
+                    for context in contexts {
+                        let accessed = context.last_accessed.format("%Y-%m-%d %H:%M:%S");
+                        println!(
+                            "{}  goals={}  memories={}  virality={:.4}  {}",
+                            context.context_id,
+                            context.goal_count,
+                            context.memory_count,
+                            context.virality_score,
+                            self.style.dim(&accessed.to_string())
+                        );
+                    }
+                }
+                Ok(())
+            }
+            ContextsAction::Show { id } => match self.orchestrator.get_context(&id) {
+                Some(context) => {
+                    self.print_context(&context);
+                    Ok(())
+                }
+                None => Err(format!("unknown context: {}", id)),
+            },
+            ContextsAction::Delete { id } => {
+                self.orchestrator.remove_context(&id).ok_or_else(|| format!("unknown context: {}", id))?;
+                self.save()?;
+                if self.json {
+                    self.print_json(&serde_json::json!({ "deleted": id }));
+                } else {
+                    println!("deleted {}", id);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// `:plan <command>`, `:context` and `:quit` besides plain commands; the
+    /// state is saved after every run.
+    fn repl(&mut self, context_id: &str) -> Result<(), String> {
+        let stdin = std::io::stdin();
+        let interactive = stdin.is_terminal();
+        let mut lines = stdin.lock().lines();
+        loop {
+            if interactive {
+                print!("{} ", self.style.note(&format!("ace[{}]>", context_id)));
+                let _ = std::io::stdout().flush();
+            }
+            let Some(line) = lines.next() else { return Ok(()) };
+            let line = line.map_err(|e| e.to_string())?;
+            let line = line.trim();
+            match line {
+                "" => continue,
+                ":quit" | ":q" => return Ok(()),
+                ":context" => match self.orchestrator.get_context(context_id) {
+                    Some(context) => self.print_context(&context),
+                    None => println!("{}", self.style.dim("no runs yet")),
+                },
+                _ => {
+                    if let Some(command) = line.strip_prefix(":plan ") {
+                        match self.orchestrator.plan_or_fallback(command.to_string(), context_id) {
+                            Ok(plan) => self.print_plan(&plan),
+                            Err(err) => eprintln!("ace: {}", err),
+                        }
+                    } else {
+                        self.run(line.to_string(), context_id, None);
+                        self.save()?;
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn config(cli: &Cli) -> Result<Config, String> {
+    match &cli.config {
+        Some(path) => Config::load(path),
+        None => Config::from_env(),
+    }
+    .map_err(|e| e.to_string())
+}
+
+fn timeout(secs: Option<f64>) -> Result<Option<Duration>, String> {
+    secs.map(|secs| Duration::try_from_secs_f64(secs).map_err(|e| format!("--timeout: {}", e))).transpose()
+}
+
+/// Needs no orchestrator, so runs without loading config or state.
+fn schema(dir: &Path, json: bool) -> Result<bool, String> {
+    let written = export_schemas(dir).map_err(|e| e.to_string())?;
+    for path in written {
+        if json {
+            println!("{}", serde_json::json!({ "written": path }));
+        } else {
+            println!("wrote {}", path.display());
+        }
+    }
+    Ok(true)
+}
+
+/// Runs `spec` on an orchestrator as configured but with the workload's mock
+/// backend in place of Python, and without the saved contexts.
+fn bench(cli: &Cli, spec: &WorkloadSpec, llm_latency: Duration) -> Result<bool, String> {
+    if let Some((field, message)) = spec.invalid() {
+        return Err(format!("{}: {}", field, message));
+    }
+    let orchestrator = CognitiveOrchestratorBuilder::from(config(cli)?)
+        .backend(Arc::new(workload_backend(llm_latency)))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let report = run_workload(&orchestrator, spec);
+    if cli.json {
+        println!("{}", serde_json::to_string(&report).unwrap_or_default());
+    } else {
+        print_workload(&report, &Style::new(false));
+    }
+    Ok(true)
+}
+
+/// Builds the orchestrator as configured, without loading the saved contexts,
+/// so a broken state file is reported rather than stopping the checks.
+fn doctor(cli: &Cli, with_smoke: bool) -> Result<bool, String> {
+    let built = config(cli).and_then(|config| CognitiveOrchestratorBuilder::from(config).build().map_err(|e| e.to_string()));
+    let mut report = match built {
+        Ok(orchestrator) if with_smoke => orchestrator.preflight_with_smoke(),
+        Ok(orchestrator) => orchestrator.preflight(),
+        Err(err) => PreflightReport {
+            checks: vec![PreflightCheck::fail("config", err, "fix the config file or ACE_* variable named above")],
+        },
+    };
+    report.checks.push(PreflightCheck::writable("paths.state", &cli.state));
+    if cli.json {
+        println!("{}", serde_json::to_string(&report).unwrap_or_default());
+    } else {
+        print_preflight(&report, &Style::new(false));
+    }
+    Ok(report.is_ready())
+}
+
+fn print_preflight(report: &PreflightReport, style: &Style) {
+    for check in &report.checks {
+        let status = match check.status {
+            CheckStatus::Pass => style.ok("PASS"),
+            CheckStatus::Warn => style.note("WARN"),
+            CheckStatus::Fail => style.failed("FAIL"),
+        };
+        println!("{} {:<16} {}", status, check.name, check.detail);
+        if let Some(remediation) = &check.remediation {
+            println!("     {}", style.dim(&format!("fix: {}", remediation)));
+        }
+    }
+}
+
+fn print_workload(report: &WorkloadReport, style: &Style) {
+    let rows = std::iter::once(("all".to_string(), &report.overall))
+        .chain(report.by_kind.iter().map(|(kind, row)| (kind.name().to_string(), row)));
+    for (name, row) in rows {
+        let failed = format!("{:>5.1}% failed", row.failure_rate * 100.0);
+        let failed = if row.failures > 0 { style.failed(&failed) } else { style.ok(&failed) };
+        let latency = &row.latency;
+        println!(
+            "{:<8} {:>6} requests  {}  p50 {:.1}ms p90 {:.1}ms p99 {:.1}ms max {:.1}ms",
+            name,
+            row.requests,
+            failed,
+            latency.p50.as_secs_f64() * 1e3,
+            latency.p90.as_secs_f64() * 1e3,
+            latency.p99.as_secs_f64() * 1e3,
+            latency.max.as_secs_f64() * 1e3
+        );
+    }
+    println!("  throughput:     {:.1} requests/s over {:.2}s", report.throughput, report.elapsed.as_secs_f64());
+    println!("  contexts:       {} touched, {} evicted", report.contexts_touched, report.evictions);
+}
+
+fn execute(cli: Cli) -> Result<bool, String> {
+    if let Command::Schema { dir } = &cli.command {
+        return schema(dir, cli.json);
+    }
+    if let Command::Bench { seed, requests, contexts, zipf, mix, concurrency, rate, llm_latency_ms } = &cli.command {
+        let [llm, viral, unknown] = mix[..] else {
+            return Err(format!("--mix: expected 3 weights, got {}", mix.len()));
+        };
+        let spec = WorkloadSpec {
+            seed: *seed,
+            requests: *requests,
+            contexts: *contexts,
+            context_distribution: zipf.map_or(ContextDistribution::Uniform, |exponent| ContextDistribution::Zipfian { exponent }),
+            mix: CommandMix { llm, viral, unknown },
+            arrival: match *rate {
+                Some(per_sec) => Arrival::ConstantRate { per_sec, concurrency: *concurrency },
+                None => Arrival::ClosedLoop { concurrency: *concurrency },
+            },
+        };
+        return bench(&cli, &spec, Duration::from_millis(*llm_latency_ms));
+    }
+    if let Command::Doctor { with_smoke } = cli.command {
+        return doctor(&cli, with_smoke);
+    }
+    let mut app = App::new(&cli)?;
+    match cli.command {
+        Command::Run { command, context, timeout: secs, diff: false } => {
+            let succeeded = app.run(command, &context, timeout(secs)?);
+            app.save()?;
+            Ok(succeeded)
+        }
+        Command::Run { command, context, timeout: secs, diff: true } => {
+            let succeeded = app.run_with_diff(command, &context, timeout(secs)?)?;
+            app.save()?;
+            Ok(succeeded)
+        }
+        Command::Plan { command, context, estimate: true } => {
+            let estimate = app.orchestrator.estimate(command, &context).map_err(|e| e.to_string())?;
+            app.print_estimate(&estimate);
+            Ok(true)
+        }
+        Command::Plan { command, context, estimate: false } => {
+            let plan = app.orchestrator.plan_or_fallback(command, &context).map_err(|e| e.to_string())?;
+            app.print_plan(&plan);
+            Ok(true)
+        }
+        Command::Contexts { action } => app.contexts(action).map(|_| true),
+        Command::Repl { context } => app.repl(&context).map(|_| true),
+        Command::Schema { .. } | Command::Bench { .. } | Command::Doctor { .. } => unreachable!("handled before loading state"),
+    }
+}
+
+fn main() -> ExitCode {
+    match execute(Cli::parse()) {
+        Ok(true) => ExitCode::SUCCESS,
+        // Some subtask failed; its result was already printed.
+        Ok(false) => ExitCode::from(2),
+        Err(message) => {
+            eprintln!("ace: {}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use sovereign_cli::server;
+use sovereign_cli::{CognitiveOrchestrator, CognitiveOrchestratorBuilder, Config};
+use std::process::ExitCode;
+use std::time::Duration;
+
+const USAGE: &str = "usage: ace-server [--addr HOST:PORT] [--config FILE] [--log LEVEL] [--grace SECONDS]";
+
+struct Args {
+    addr: String,
+    config: Option<String>,
+    log: String,
+    /// How long runs in flight may take to finish after SIGTERM or Ctrl-C.
+    grace: String,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args =
+        Args { addr: "127.0.0.1:8080".to_string(), config: None, log: "info".to_string(), grace: "30".to_string() };
+    let mut argv = std::env::args().skip(1);
+    while let Some(flag) = argv.next() {
+        let slot = match flag.as_str() {
+            "--addr" => &mut args.addr,
+            "--log" => &mut args.log,
+            "--grace" => &mut args.grace,
+            "--config" => args.config.insert(String::new()),
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            other => return Err(format!("unknown argument {:?}\n{}", other, USAGE)),
+        };
+        *slot = argv.next().ok_or_else(|| format!("{} needs a value\n{}", flag, USAGE))?;
+    }
+    Ok(args)
+}
+
+/// The config file when given, then `ACE_*` overrides.
+fn orchestrator(config: Option<&str>) -> Result<CognitiveOrchestrator, String> {
+    let config = match config {
+        Some(path) => Config::load(path),
+        None => Config::from_env(),
+    }
+    .map_err(|e| e.to_string())?;
+    CognitiveOrchestratorBuilder::from(config).build().map_err(|e| e.to_string())
+}
+
+/// Resolves on Ctrl-C or, on Unix, SIGTERM.
+async fn terminated() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut term = match signal(SignalKind::terminate()) {
+            Ok(term) => term,
+            Err(err) => {
+                tracing::warn!("Cannot listen for SIGTERM: {}", err);
+                let _ = tokio::signal::ctrl_c().await;
+                return;
+            }
+        };
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = term.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = tokio::signal::ctrl_c().await;
+    tracing::info!("Shutting down");
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{}", message);
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Err(err) = CognitiveOrchestrator::init_tracing(&args.log) {
+        eprintln!("{}", err);
+        return ExitCode::FAILURE;
+    }
+    let grace = match args.grace.parse().ok().and_then(|secs| Duration::try_from_secs_f64(secs).ok()) {
+        Some(grace) => grace,
+        None => {
+            eprintln!("ace-server: --grace must be a number of seconds, got {:?}", args.grace);
+            return ExitCode::FAILURE;
+        }
+    };
+    let orchestrator = match orchestrator(args.config.as_deref()) {
+        Ok(orchestrator) => orchestrator,
+        Err(message) => {
+            eprintln!("ace-server: {}", message);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let listener = match tokio::net::TcpListener::bind(&args.addr).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            eprintln!("ace-server: cannot bind {}: {}", args.addr, err);
+            return ExitCode::FAILURE;
+        }
+    };
+    tracing::info!("Listening on {}", args.addr);
+    match server::serve_until(listener, orchestrator, terminated(), grace).await {
+        Ok(report) => {
+            for run in &report.interrupted {
+                tracing::warn!("Interrupted {:?} on {} after {} subtasks", run.command, run.context_id, run.finished);
+            }
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("ace-server: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
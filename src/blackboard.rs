@@ -0,0 +1,159 @@
+use crate::{Attributes, OrchestratorError, ATTRIBUTE_REFERENCE_PREFIX};
+use pyo3::prelude::*;
+use pythonize::pythonize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// What one run's steps leave for later ones, by key. Each successful LLM step
+/// writes its output under `step_<n>.output`, `n` counting the run's results
+/// from 1, and a subtask's `{{key}}` references are expanded from it before
+/// dispatch. Python agents whose `execute` takes a third argument get it as a dict.
+#[pyclass(module = "sovereign_cli")]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(transparent)]
+pub struct Blackboard {
+    entries: BTreeMap<String, Value>,
+}
+
+impl Blackboard {
+    /// The key step `n`'s output is written under.
+    pub fn step_output(n: usize) -> String {
+        format!("step_{}.output", n)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.entries.get(key)
+    }
+
+    pub fn set(&mut self, key: impl Into<String>, value: impl Into<Value>) {
+        self.entries.insert(key.into(), value.into());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn entries(&self) -> &BTreeMap<String, Value> {
+        &self.entries
+    }
+
+    /// `subtask` with each `{{key}}` replaced by the value under `key`, strings as
+    /// they are and anything else as JSON; whitespace around the key is ignored.
+    /// `\{{` stands for a literal `{{`, and a `{{` never closed is left as it is.
+    /// Fails with `UnresolvedReference` for a key nothing was written under.
+    pub fn expand(&self, subtask: &str) -> Result<String, OrchestratorError> {
+        self.expand_with(subtask, &Attributes::new())
+    }
+
+    /// `expand`, also resolving `{{attr.<key>}}` from the context's `attributes`.
+    pub fn expand_with(&self, subtask: &str, attributes: &Attributes) -> Result<String, OrchestratorError> {
+        let mut expanded = String::with_capacity(subtask.len());
+        let mut rest = subtask;
+        while let Some(open) = rest.find("{{") {
+            if rest[..open].ends_with('\\') {
+                expanded.push_str(&rest[..open - 1]);
+                expanded.push_str("{{");
+                rest = &rest[open + 2..];
+                continue;
+            }
+            let Some(close) = rest[open + 2..].find("}}") else { break };
+            let reference = rest[open + 2..open + 2 + close].trim();
+            let attribute = reference.strip_prefix(ATTRIBUTE_REFERENCE_PREFIX).and_then(|key| attributes.get(key));
+            let value = attribute.or_else(|| self.entries.get(reference)).ok_or_else(|| {
+                OrchestratorError::UnresolvedReference { subtask: subtask.to_string(), reference: reference.to_string() }
+            })?;
+            expanded.push_str(&rest[..open]);
+            match value {
+                Value::String(text) => expanded.push_str(text),
+                value => expanded.push_str(&value.to_string()),
+            }
+            rest = &rest[open + 2 + close + 2..];
+        }
+        expanded.push_str(rest);
+        Ok(expanded)
+    }
+
+    /// A dict of the entries, as Python agents receive it.
+    pub(crate) fn to_dict(&self, py: Python) -> PyResult<PyObject> {
+        Ok(pythonize(py, &self.entries)?)
+    }
+}
+
+#[pymethods]
+impl Blackboard {
+    #[pyo3(name = "get")]
+    fn py_get(&self, py: Python, key: &str) -> PyResult<Option<PyObject>> {
+        self.entries.get(key).map(|value| Ok(pythonize(py, value)?)).transpose()
+    }
+
+    #[pyo3(name = "to_dict")]
+    fn py_to_dict(&self, py: Python) -> PyResult<PyObject> {
+        self.to_dict(py)
+    }
+
+    fn __len__(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn __contains__(&self, key: &str) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Blackboard(keys={:?})", self.entries.keys().collect::<Vec<_>>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AgentResult, CognitiveOrchestrator, MockBackend};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn board() -> Blackboard {
+        let mut board = Blackboard::default();
+        board.set(Blackboard::step_output(1), "wait for it");
+        board.set("reach", json!({"views": 10}));
+        board
+    }
+
+    #[test]
+    fn references_expand_strings_raw_and_other_values_as_json() {
+        let board = board();
+        assert_eq!(board.expand("viral:simulate {{step_1.output}}").unwrap(), "viral:simulate wait for it");
+        assert_eq!(board.expand("post {{ reach }} and {{step_1.output}}!").unwrap(), "post {\"views\":10} and wait for it!");
+        assert_eq!(board.expand("no references").unwrap(), "no references");
+    }
+
+    #[test]
+    fn escaped_and_unclosed_braces_stay_literal() {
+        let board = board();
+        assert_eq!(board.expand(r"template \{{name}} for {{step_1.output}}").unwrap(), "template {{name}} for wait for it");
+        assert_eq!(board.expand("json {\"a\": {\"b\": 1}}").unwrap(), "json {\"a\": {\"b\": 1}}");
+        assert_eq!(board.expand("open {{ but never closed").unwrap(), "open {{ but never closed");
+    }
+
+    #[test]
+    fn missing_references_fail_the_step_without_dispatching_it() {
+        let err = board().expand("post {{step_2.output}}").unwrap_err();
+        assert_eq!(err.kind(), "unresolved_reference");
+        assert!(err.to_string().contains("step_2.output"), "{}", err);
+
+        let mock = MockBackend::new()
+            .plan("launch", ["llm:generate a hook", "post {{step_1.output}}", "post {{step_9.output}}"])
+            .generator(|prompt| Ok(format!("hook about {}", prompt)))
+            .on("post", |rest| AgentResult::ok(format!("posted {}", rest)));
+        let orch = CognitiveOrchestrator::builder().backend(Arc::new(mock)).learning(false).build().unwrap();
+        let report = orch.process_report("launch it".to_string(), "ctx1");
+        assert_eq!(report.subtasks, ["llm:generate a hook", "post hook about a hook", "post {{step_9.output}}"]);
+        assert_eq!(report.results[1].output, "posted hook about a hook");
+        assert_eq!(report.results[2].error.as_ref().map(OrchestratorError::kind), Some("unresolved_reference"));
+        assert!(!report.success);
+        // Only the LLM step wrote to the blackboard.
+        assert_eq!(report.blackboard.entries().keys().collect::<Vec<_>>(), ["step_1.output"]);
+        assert_eq!(report.blackboard.get("step_1.output"), Some(&json!("hook about a hook")));
+    }
+}
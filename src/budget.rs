@@ -0,0 +1,287 @@
+use crate::tenant::split_key;
+use crate::OrchestratorError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Ceilings on LLM spend; `None` leaves that dimension unlimited. Once any one is
+/// reached, further LLM subtasks fail with `budget_exceeded` without being called.
+/// The call that crosses a token or cost ceiling still completes, since its usage
+/// is only known afterwards.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Budget {
+    pub max_llm_calls: Option<u64>,
+    pub max_tokens: Option<u64>,
+    pub max_cost_usd: Option<f64>,
+}
+
+/// LLM spend counted against a budget.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct BudgetUsage {
+    pub llm_calls: u64,
+    pub tokens: u64,
+    pub cost_usd: f64,
+}
+
+/// A budget with what has been spent against it and what is left.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BudgetStatus {
+    pub limit: Budget,
+    pub used: BudgetUsage,
+    /// The limit minus what was used, never below zero; `None` where unlimited.
+    pub remaining: Budget,
+}
+
+impl Budget {
+    /// The first ceiling `used` has reached, by its `BudgetUsage` field name.
+    pub fn exceeded(&self, used: &BudgetUsage) -> Option<&'static str> {
+        if self.max_llm_calls.is_some_and(|max| used.llm_calls >= max) {
+            Some("llm_calls")
+        } else if self.max_tokens.is_some_and(|max| used.tokens >= max) {
+            Some("tokens")
+        } else if self.max_cost_usd.is_some_and(|max| used.cost_usd >= max) {
+            Some("cost_usd")
+        } else {
+            None
+        }
+    }
+
+    pub fn status(&self, used: BudgetUsage) -> BudgetStatus {
+        let remaining = Budget {
+            max_llm_calls: self.max_llm_calls.map(|max| max.saturating_sub(used.llm_calls)),
+            max_tokens: self.max_tokens.map(|max| max.saturating_sub(used.tokens)),
+            max_cost_usd: self.max_cost_usd.map(|max| (max - used.cost_usd).max(0.0)),
+        };
+        BudgetStatus { limit: *self, used, remaining }
+    }
+}
+
+impl BudgetUsage {
+    fn add(&mut self, tokens: u64, cost_usd: f64) {
+        self.tokens = self.tokens.saturating_add(tokens);
+        self.cost_usd += cost_usd;
+    }
+}
+
+/// Tokens an LLM call is charged when the agent does not report them: about four
+/// characters per token.
+pub fn estimate_tokens(text: &str) -> u64 {
+    u64::try_from(text.chars().count().div_ceil(4)).unwrap_or(u64::MAX)
+}
+
+#[derive(Default)]
+struct Ledger {
+    /// Set with `CognitiveOrchestrator::set_budget`.
+    context: Option<(Budget, BudgetUsage)>,
+    /// The budget of the `process` run in flight on the context, if it has one.
+    run: Option<(Budget, BudgetUsage)>,
+}
+
+impl Ledger {
+    fn budgets(&mut self) -> impl Iterator<Item = &mut (Budget, BudgetUsage)> {
+        self.context.iter_mut().chain(self.run.iter_mut())
+    }
+}
+
+#[derive(Default)]
+struct Ledgers {
+    /// Keyed by `Context::key`.
+    contexts: HashMap<String, Ledger>,
+    /// Set with `CognitiveOrchestrator::set_tenant_budget`.
+    tenants: HashMap<String, (Budget, BudgetUsage)>,
+}
+
+impl Ledgers {
+    /// Every budget a call on the context counts against.
+    fn budgets(&mut self, key: &str) -> impl Iterator<Item = &mut (Budget, BudgetUsage)> {
+        let ledger = self.contexts.get_mut(key).into_iter().flat_map(Ledger::budgets);
+        ledger.chain(self.tenants.get_mut(split_key(key).0))
+    }
+}
+
+/// Per-context and per-tenant LLM spend, shared by the orchestrator and the
+/// `LlmAgent` so every dispatch path is charged. A context is only tracked while
+/// it has a budget. Clones share the ledger.
+#[derive(Clone, Default)]
+pub struct Budgets(Arc<Mutex<Ledgers>>);
+
+impl Budgets {
+    fn ledgers(&self) -> std::sync::MutexGuard<'_, Ledgers> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Replaces the context's budget, keeping what it has spent so far.
+    pub fn set(&self, context_id: &str, budget: Budget) {
+        let mut ledgers = self.ledgers();
+        let ledger = ledgers.contexts.entry(context_id.to_string()).or_default();
+        let used = ledger.context.map(|(_, used)| used).unwrap_or_default();
+        ledger.context = Some((budget, used));
+    }
+
+    pub fn clear(&self, context_id: &str) {
+        let mut ledgers = self.ledgers();
+        if let Some(ledger) = ledgers.contexts.get_mut(context_id) {
+            ledger.context = None;
+            if ledger.run.is_none() {
+                ledgers.contexts.remove(context_id);
+            }
+        }
+    }
+
+    /// The context's budget and spend, when it has one.
+    pub fn status(&self, context_id: &str) -> Option<BudgetStatus> {
+        let ledgers = self.ledgers();
+        let (budget, used) = ledgers.contexts.get(context_id)?.context?;
+        Some(budget.status(used))
+    }
+
+    /// Replaces the tenant's budget, keeping what its contexts have spent so far.
+    pub fn set_tenant(&self, tenant: &str, budget: Budget) {
+        let mut ledgers = self.ledgers();
+        let used = ledgers.tenants.get(tenant).map(|(_, used)| *used).unwrap_or_default();
+        ledgers.tenants.insert(tenant.to_string(), (budget, used));
+    }
+
+    pub fn clear_tenant(&self, tenant: &str) {
+        self.ledgers().tenants.remove(tenant);
+    }
+
+    pub fn tenant_status(&self, tenant: &str) -> Option<BudgetStatus> {
+        let (budget, used) = *self.ledgers().tenants.get(tenant)?;
+        Some(budget.status(used))
+    }
+
+    /// Starts counting a run's spend against `budget`, on top of the context's own.
+    pub(crate) fn begin_run(&self, context_id: &str, budget: Budget) {
+        self.ledgers().contexts.entry(context_id.to_string()).or_default().run = Some((budget, BudgetUsage::default()));
+    }
+
+    /// Ends the run's budget and returns what it spent.
+    pub(crate) fn end_run(&self, context_id: &str) -> Option<BudgetStatus> {
+        let mut ledgers = self.ledgers();
+        let ledger = ledgers.contexts.get_mut(context_id)?;
+        let (budget, used) = ledger.run.take()?;
+        if ledger.context.is_none() {
+            ledgers.contexts.remove(context_id);
+        }
+        Some(budget.status(used))
+    }
+
+    /// Counts one LLM call, unless a budget of the context or its tenant has been reached.
+    pub(crate) fn reserve_call(&self, context_id: &str) -> Result<(), OrchestratorError> {
+        let mut ledgers = self.ledgers();
+        if let Some(limit) = ledgers.budgets(context_id).find_map(|(budget, used)| budget.exceeded(used)) {
+            let context_id = split_key(context_id).1.to_string();
+            return Err(OrchestratorError::BudgetExceeded { context_id, limit: limit.to_string() });
+        }
+        for (_, used) in ledgers.budgets(context_id) {
+            used.llm_calls += 1;
+        }
+        Ok(())
+    }
+
+    /// The first ceiling, of the context's budgets or its tenant's, that `calls`
+    /// more LLM calls spending `tokens` would go over; a reached cost ceiling
+    /// counts once any call is made. Nothing is charged.
+    pub(crate) fn would_exceed(&self, context_id: &str, calls: u64, tokens: u64) -> Option<&'static str> {
+        self.ledgers().budgets(context_id).find_map(|(budget, used)| {
+            let remaining = budget.status(*used).remaining;
+            if remaining.max_llm_calls.is_some_and(|max| calls > max) {
+                Some("llm_calls")
+            } else if remaining.max_tokens.is_some_and(|max| tokens > max) {
+                Some("tokens")
+            } else if remaining.max_cost_usd.is_some_and(|max| calls > 0 && max <= 0.0) {
+                Some("cost_usd")
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Charges a finished call's tokens and cost.
+    pub(crate) fn record(&self, context_id: &str, tokens: u64, cost_usd: f64) {
+        for (_, used) in self.ledgers().budgets(context_id) {
+            used.add(tokens, cost_usd);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BusPayload, CognitiveOrchestrator, MockBackend, ProcessEvent};
+
+    fn orchestrator() -> CognitiveOrchestrator {
+        let mock = MockBackend::new().plan("ask", ["query llm one", "query llm two", "query llm three"]);
+        CognitiveOrchestrator::builder().backend(Arc::new(mock)).build().unwrap()
+    }
+
+    fn budget_errors(output: &str, orch: &CognitiveOrchestrator) -> usize {
+        let outputs: Vec<String> = serde_json::from_str(output).unwrap();
+        assert_eq!(outputs.len(), 3);
+        orch.get_history("ctx1", None)
+            .iter()
+            .filter(|record| matches!(record.result.error, Some(OrchestratorError::BudgetExceeded { .. })))
+            .count()
+    }
+
+    #[test]
+    fn call_ceiling_refuses_further_llm_subtasks() {
+        let orch = orchestrator();
+        orch.set_budget("ctx1", Budget { max_llm_calls: Some(2), ..Budget::default() });
+        let output = orch.process("ask".to_string(), "ctx1");
+        assert_eq!(budget_errors(&output, &orch), 1);
+
+        let history = orch.get_history("ctx1", None);
+        let refused = history.last().unwrap().result.error.clone().unwrap();
+        assert_eq!(refused.kind(), "budget_exceeded");
+        assert_eq!(refused, OrchestratorError::BudgetExceeded { context_id: "ctx1".to_string(), limit: "llm_calls".to_string() });
+
+        let status = orch.get_budget("ctx1").unwrap();
+        assert_eq!(status.used.llm_calls, 2);
+        assert_eq!(status.remaining.max_llm_calls, Some(0));
+        assert_eq!(status.remaining.max_tokens, None);
+    }
+
+    #[test]
+    fn token_ceiling_is_checked_independently() {
+        let orch = orchestrator();
+        // Each call echoes its prompt: "one" is 1 token in and 1 out.
+        orch.set_budget("ctx1", Budget { max_tokens: Some(3), ..Budget::default() });
+        let output = orch.process("ask".to_string(), "ctx1");
+        assert_eq!(budget_errors(&output, &orch), 1);
+
+        let status = orch.get_budget("ctx1").unwrap();
+        assert_eq!((status.used.llm_calls, status.used.tokens), (2, 4));
+        assert_eq!(status.remaining.max_tokens, Some(0));
+        assert_eq!(status.limit.max_llm_calls, None);
+    }
+
+    #[test]
+    fn run_budgets_are_reported_and_released() {
+        let orch = orchestrator();
+        let mut subscription = orch.event_bus().subscribe();
+        orch.process_with_budget("ask".to_string(), "ctx1", Budget { max_llm_calls: Some(1), ..Budget::default() });
+        assert_eq!(budget_errors(&orch.process("ask".to_string(), "ctx1"), &orch), 2);
+
+        let completed: Vec<Option<BudgetStatus>> = std::iter::from_fn(|| subscription.try_recv())
+            .filter_map(|event| match event.payload {
+                BusPayload::Process(ProcessEvent::Completed { budget, .. }) => Some(budget),
+                _ => None,
+            })
+            .collect();
+        let [Some(run), None] = completed.as_slice() else { panic!("{:?}", completed) };
+        assert_eq!(run.used.llm_calls, 1);
+        assert_eq!(run.remaining.max_llm_calls, Some(0));
+        // The run's budget ended with it; the second run was not limited.
+        assert!(orch.get_budget("ctx1").is_none());
+    }
+
+    #[test]
+    fn estimates_round_up() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("one"), 1);
+        assert_eq!(estimate_tokens("hello world"), 3);
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// Set once a run is cancelled; dispatch checks it to cut its waits short.
+#[derive(Clone, Default)]
+pub struct CancelToken(Arc<(Mutex<bool>, Condvar)>);
+
+impl CancelToken {
+    fn flag(&self) -> std::sync::MutexGuard<'_, bool> {
+        self.0 .0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn cancel(&self) {
+        *self.flag() = true;
+        self.0 .1.notify_all();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.flag()
+    }
+
+    /// Sleeps for `duration`, returning early if cancelled. Returns whether it was.
+    pub fn sleep(&self, duration: Duration) -> bool {
+        let deadline = Instant::now() + duration;
+        let mut cancelled = self.flag();
+        while !*cancelled {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            cancelled = self.0 .1.wait_timeout(cancelled, deadline - now).unwrap_or_else(|e| e.into_inner()).0;
+        }
+        *cancelled
+    }
+
+    fn same(&self, other: &CancelToken) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// Cancellation tokens of the runs in flight, by context key; runs on the same
+/// context share one. Clones share the tokens, so another thread can cancel a run
+/// while it holds the orchestrator.
+#[derive(Clone, Default)]
+pub struct Cancellations(Arc<Mutex<HashMap<String, (CancelToken, usize)>>>);
+
+impl Cancellations {
+    fn runs(&self) -> std::sync::MutexGuard<'_, HashMap<String, (CancelToken, usize)>> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Cancels the runs in flight on `key`, which stop before their next subtask;
+    /// later runs start afresh. Returns whether there were any.
+    pub fn cancel(&self, key: &str) -> bool {
+        let Some((token, _)) = self.runs().remove(key) else { return false };
+        token.cancel();
+        true
+    }
+
+    pub(crate) fn register(&self, key: &str) -> CancelToken {
+        let mut runs = self.runs();
+        let (token, count) = runs.entry(key.to_string()).or_default();
+        *count += 1;
+        token.clone()
+    }
+
+    /// The token of the runs in flight on `key`, if any.
+    pub(crate) fn token(&self, key: &str) -> Option<CancelToken> {
+        self.runs().get(key).map(|(token, _)| token.clone())
+    }
+
+    /// The run holding `token` is over; a no-op once it was cancelled.
+    pub(crate) fn release(&self, key: &str, token: &CancelToken) {
+        let mut runs = self.runs();
+        if let Some((current, count)) = runs.get_mut(key) {
+            if current.same(token) {
+                *count -= 1;
+                if *count == 0 {
+                    runs.remove(key);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AgentResult, CognitiveOrchestrator, MockBackend, OrchestratorError};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn cancelling_from_another_thread_stops_after_the_current_subtask() {
+        let mock = MockBackend::new().plan("ask", ["post one", "post two", "post three"]).on("post", |rest| {
+            thread::sleep(Duration::from_millis(100));
+            AgentResult { output: rest.to_string(), status: true, metadata: HashMap::new(), error: None }
+        });
+        let orch = CognitiveOrchestrator::builder().backend(Arc::new(mock)).build().unwrap();
+        let cancellations = orch.cancellations();
+        assert!(!orch.cancel("ctx1"));
+
+        let canceller = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            cancellations.cancel("ctx1")
+        });
+        let report = orch.process_report("ask".to_string(), "ctx1");
+        assert!(canceller.join().unwrap());
+        assert_eq!(report.outputs(), ["one"]);
+        assert!(report.cancelled && !report.success);
+
+        // The next run on the context is not cancelled.
+        assert_eq!(orch.process("ask".to_string(), "ctx1"), r#"["one","two","three"]"#);
+    }
+
+    #[test]
+    fn cancelling_cuts_a_timeout_wait_short() {
+        let mock = MockBackend::new().plan("ask", ["post one", "post two"]).on("post", |rest| {
+            thread::sleep(Duration::from_millis(300));
+            AgentResult { output: rest.to_string(), status: true, metadata: HashMap::new(), error: None }
+        });
+        let orch = CognitiveOrchestrator::builder()
+            .backend(Arc::new(mock))
+            .subtask_timeout(Duration::from_secs(10))
+            .build()
+            .unwrap();
+        let cancellations = orch.cancellations();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            cancellations.cancel("ctx1");
+        });
+        let started = Instant::now();
+        let report = orch.process_report("ask".to_string(), "ctx1");
+        assert!(started.elapsed() < Duration::from_millis(250));
+        assert_eq!(report.results.len(), 1);
+        assert_eq!(report.results[0].error, Some(OrchestratorError::Cancelled { subtask: "post one".to_string() }));
+        assert!(report.cancelled);
+    }
+}
@@ -0,0 +1,292 @@
+use crate::{Agent, AgentKind, AgentResult, Clock, OrchestratorError};
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Serializer};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::warn;
+
+/// When a breaker opens and how long it stays open before a probe is let through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitPolicy {
+    /// Consecutive failures that open the breaker; at least 1.
+    pub failure_threshold: u32,
+    pub cooldown: Duration,
+}
+
+impl CircuitPolicy {
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        Self { failure_threshold: failure_threshold.max(1), cooldown }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    Closed,
+    /// Dispatches fail with `circuit_open` until the cooldown is over.
+    Open,
+    /// One probe dispatch is in flight; its result closes or reopens the breaker.
+    HalfOpen,
+}
+
+/// An agent kind's breaker as `agent_health` reports it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AgentHealth {
+    #[serde(serialize_with = "kind_name")]
+    pub agent: AgentKind,
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    pub total_failures: u64,
+    pub last_error: Option<String>,
+    /// When the breaker last opened, while it is open or half-open.
+    pub opened_at: Option<DateTime<Utc>>,
+}
+
+fn kind_name<S: Serializer>(kind: &AgentKind, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(kind.name())
+}
+
+/// Failures that say the agent is unavailable, as opposed to an answer it gave.
+fn unavailable(result: &AgentResult) -> bool {
+    matches!(
+        result.error,
+        Some(
+            OrchestratorError::ModuleImport { .. }
+                | OrchestratorError::AttributeMissing { .. }
+                | OrchestratorError::CallFailed { .. }
+                | OrchestratorError::Timeout { .. }
+        )
+    )
+}
+
+struct Breaker {
+    state: CircuitState,
+    consecutive_failures: u32,
+    total_failures: u64,
+    last_error: Option<String>,
+    opened_at: Option<DateTime<Utc>>,
+}
+
+impl Breaker {
+    fn new() -> Self {
+        Self { state: CircuitState::Closed, consecutive_failures: 0, total_failures: 0, last_error: None, opened_at: None }
+    }
+
+    fn health(&self, agent: AgentKind) -> AgentHealth {
+        AgentHealth {
+            agent,
+            state: self.state,
+            consecutive_failures: self.consecutive_failures,
+            total_failures: self.total_failures,
+            last_error: self.last_error.clone(),
+            opened_at: self.opened_at,
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    policies: HashMap<AgentKind, CircuitPolicy>,
+    breakers: HashMap<AgentKind, Breaker>,
+}
+
+/// Per-`AgentKind` circuit breakers on calls out to Python agents, checked by
+/// dispatch before the rate limit; kinds without a policy are tracked but never
+/// opened. Clones share the breakers.
+#[derive(Clone)]
+pub struct CircuitBreakers {
+    state: Arc<Mutex<State>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl CircuitBreakers {
+    pub(crate) fn new(clock: Arc<dyn Clock>) -> Self {
+        Self { state: Arc::default(), clock }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Replaces the policy for `kind`, closing its breaker; `None` removes it.
+    pub fn set(&self, kind: AgentKind, policy: Option<CircuitPolicy>) {
+        let mut state = self.state();
+        match policy {
+            Some(policy) => state.policies.insert(kind, policy),
+            None => state.policies.remove(&kind),
+        };
+        if let Some(breaker) = state.breakers.get_mut(&kind) {
+            breaker.state = CircuitState::Closed;
+            breaker.consecutive_failures = 0;
+            breaker.opened_at = None;
+        }
+    }
+
+    pub fn get(&self, kind: AgentKind) -> Option<CircuitPolicy> {
+        self.state().policies.get(&kind).copied()
+    }
+
+    /// Every kind dispatched so far or with a policy, in `AgentKind::ALL` order.
+    pub fn health(&self) -> Vec<AgentHealth> {
+        let state = self.state();
+        AgentKind::ALL
+            .into_iter()
+            .filter_map(|kind| match state.breakers.get(&kind) {
+                Some(breaker) => Some(breaker.health(kind)),
+                None => state.policies.contains_key(&kind).then(|| Breaker::new().health(kind)),
+            })
+            .collect()
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.state().breakers.values().any(|breaker| breaker.state != CircuitState::Closed)
+    }
+
+    /// `CircuitOpen` while `agent`'s breaker is open, or half-open with its probe
+    /// in flight. Once the cooldown is over, lets one probe through.
+    pub(crate) fn admit(&self, agent: &dyn Agent) -> Result<(), OrchestratorError> {
+        let Some(kind) = agent.kind() else { return Ok(()) };
+        let now = self.clock.now();
+        let mut state = self.state();
+        let Some(policy) = state.policies.get(&kind).copied() else { return Ok(()) };
+        let Some(breaker) = state.breakers.get_mut(&kind) else { return Ok(()) };
+        let reopens = breaker.opened_at.map(|opened_at| opened_at + policy.cooldown);
+        match breaker.state {
+            CircuitState::Closed => Ok(()),
+            CircuitState::Open if reopens.is_some_and(|reopens| now >= reopens) => {
+                breaker.state = CircuitState::HalfOpen;
+                Ok(())
+            }
+            CircuitState::Open | CircuitState::HalfOpen => {
+                let retry_in = reopens.and_then(|reopens| (reopens - now).to_std().ok()).unwrap_or_default();
+                Err(OrchestratorError::CircuitOpen {
+                    agent: kind.name().to_string(),
+                    retry_in_ms: u64::try_from(retry_in.as_millis()).unwrap_or(u64::MAX),
+                })
+            }
+        }
+    }
+
+    /// Counts `result` against `agent`'s breaker, opening it at the policy's
+    /// threshold or when a probe fails, and closing it when a probe succeeds.
+    pub(crate) fn record(&self, agent: &dyn Agent, result: &AgentResult) {
+        let Some(kind) = agent.kind() else { return };
+        let now = self.clock.now();
+        let mut state = self.state();
+        let policy = state.policies.get(&kind).copied();
+        let breaker = state.breakers.entry(kind).or_insert_with(Breaker::new);
+        if !unavailable(result) {
+            breaker.consecutive_failures = 0;
+            breaker.state = CircuitState::Closed;
+            breaker.opened_at = None;
+            return;
+        }
+        breaker.consecutive_failures += 1;
+        breaker.total_failures += 1;
+        breaker.last_error = result.error.as_ref().map(ToString::to_string);
+        let Some(policy) = policy else { return };
+        if breaker.state == CircuitState::HalfOpen || breaker.consecutive_failures >= policy.failure_threshold {
+            if breaker.state != CircuitState::Open {
+                warn!(agent = kind.name(), failures = breaker.consecutive_failures, "circuit opened");
+            }
+            breaker.state = CircuitState::Open;
+            breaker.opened_at = Some(now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CognitiveOrchestrator, FixedClock, MockBackend};
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    #[test]
+    fn breakers_open_probe_and_close_again() {
+        let clock = Arc::new(FixedClock::new(DateTime::UNIX_EPOCH));
+        let (down, calls) = (Arc::new(AtomicBool::new(true)), Arc::new(AtomicUsize::new(0)));
+        let (is_down, seen) = (down.clone(), calls.clone());
+        let mock = MockBackend::new().generator(move |prompt| {
+            seen.fetch_add(1, Ordering::Relaxed);
+            match is_down.load(Ordering::Relaxed) {
+                true => Err(OrchestratorError::CallFailed { target: "llm".to_string(), message: "down".to_string(), traceback: None }),
+                false => Ok(prompt.to_string()),
+            }
+        });
+        let orch = CognitiveOrchestrator::builder()
+            .backend(Arc::new(mock))
+            .clock(clock.clone())
+            .build()
+            .unwrap()
+            .with_circuit_breaker(AgentKind::Llm, CircuitPolicy::new(2, Duration::from_secs(30)));
+        let state = |orch: &CognitiveOrchestrator| orch.agent_health()[0].state;
+        assert_eq!(orch.agent_health()[0], Breaker::new().health(AgentKind::Llm));
+
+        orch.dispatch("query llm a".to_string(), "ctx1");
+        assert_eq!(state(&orch), CircuitState::Closed);
+        orch.dispatch("query llm b".to_string(), "ctx1");
+        assert_eq!(state(&orch), CircuitState::Open);
+
+        // Open: short-circuited without calling the agent.
+        let result = orch.dispatch("query llm c".to_string(), "ctx1");
+        assert_eq!(result.error, Some(OrchestratorError::CircuitOpen { agent: "llm".to_string(), retry_in_ms: 30_000 }));
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+
+        // A failed probe after the cooldown reopens it for another cooldown.
+        clock.advance(chrono::Duration::seconds(30));
+        orch.dispatch("query llm d".to_string(), "ctx1");
+        assert_eq!((state(&orch), calls.load(Ordering::Relaxed)), (CircuitState::Open, 3));
+        assert!(orch.dispatch("query llm e".to_string(), "ctx1").error.is_some_and(|err| err.kind() == "circuit_open"));
+
+        // A successful probe closes it.
+        clock.advance(chrono::Duration::seconds(30));
+        down.store(false, Ordering::Relaxed);
+        assert!(orch.dispatch("query llm f".to_string(), "ctx1").status);
+        let health = &orch.agent_health()[0];
+        assert_eq!((health.state, health.consecutive_failures, health.total_failures), (CircuitState::Closed, 0, 3));
+        assert_eq!(health.last_error.as_deref(), Some("Python call llm raised down"));
+        assert!(health.opened_at.is_none());
+    }
+
+    struct Llm;
+
+    impl Agent for Llm {
+        fn name(&self) -> &str {
+            "llm"
+        }
+
+        fn can_handle(&self, _sub_task: &str) -> bool {
+            true
+        }
+
+        fn execute(&self, _sub_task: &str, _ctx: &mut crate::Context) -> AgentResult {
+            unreachable!()
+        }
+
+        fn kind(&self) -> Option<AgentKind> {
+            Some(AgentKind::Llm)
+        }
+    }
+
+    #[test]
+    fn the_probe_is_the_only_call_while_half_open() {
+        let clock = Arc::new(FixedClock::new(DateTime::UNIX_EPOCH));
+        let breakers = CircuitBreakers::new(clock.clone());
+        breakers.set(AgentKind::Llm, Some(CircuitPolicy::new(0, Duration::from_secs(5))));
+        let failed = AgentResult::failed("down".to_string(), OrchestratorError::Timeout { subtask: "query".to_string(), timeout_ms: 1 });
+        breakers.record(&Llm, &failed);
+        assert!(breakers.is_open());
+
+        clock.advance(chrono::Duration::seconds(5));
+        assert!(breakers.admit(&Llm).is_ok());
+        assert_eq!(breakers.health()[0].state, CircuitState::HalfOpen);
+        assert!(breakers.admit(&Llm).is_err());
+
+        // Answers that fail on their own merits do not count against the agent.
+        let answered = AgentResult { output: "low virality".to_string(), status: false, metadata: HashMap::new(), error: None };
+        breakers.record(&Llm, &answered);
+        assert_eq!(breakers.health()[0].state, CircuitState::Closed);
+        assert!(!breakers.is_open());
+    }
+}
@@ -0,0 +1,306 @@
+use crate::timing;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+type Key = (String, String);
+
+enum Slot<T> {
+    InFlight,
+    Finished { at: DateTime<Utc>, value: T },
+}
+
+/// Blocking joiners park on `finished`, async ones on `finished_async`; a
+/// leader finishing or unwinding wakes both.
+struct Slots<T> {
+    slots: Mutex<HashMap<Key, Slot<T>>>,
+    finished: Condvar,
+    finished_async: Notify,
+}
+
+impl<T> Slots<T> {
+    fn lock(&self) -> MutexGuard<'_, HashMap<Key, Slot<T>>> {
+        self.slots.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn notify(&self) {
+        self.finished.notify_all();
+        self.finished_async.notify_waiters();
+    }
+}
+
+/// Runs by (context, command), in flight or finished within the window, that an
+/// identical run joins instead of dispatching; `None` for a window turns it off.
+/// Unlike idempotency keys, this matches on what was asked rather than a key
+/// the caller supplies. `T` is what a run hands those joining it. Clones share
+/// the runs.
+#[derive(Clone)]
+pub(crate) struct Coalescer<T> {
+    window: Option<Duration>,
+    slots: Arc<Slots<T>>,
+}
+
+enum Claim<T> {
+    Joined(T),
+    Lead,
+    Wait,
+}
+
+/// Frees a leader's slot if its run unwinds, or its future is dropped, so those
+/// waiting on it run themselves.
+struct Leader<T> {
+    slots: Arc<Slots<T>>,
+    key: Option<Key>,
+}
+
+impl<T> Leader<T> {
+    fn finish(mut self, at: DateTime<Utc>, value: T) {
+        if let Some(key) = self.key.take() {
+            self.slots.lock().insert(key, Slot::Finished { at, value });
+            self.slots.notify();
+        }
+    }
+}
+
+impl<T> Drop for Leader<T> {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            self.slots.lock().remove(&key);
+            self.slots.notify();
+        }
+    }
+}
+
+impl<T: Clone + Send> Coalescer<T> {
+    pub(crate) fn new(window: Option<Duration>) -> Self {
+        let slots = Slots { slots: Mutex::default(), finished: Condvar::new(), finished_async: Notify::new() };
+        Self { window, slots: Arc::new(slots) }
+    }
+
+    /// What `key`'s slot gives a run at `now`: a finished run's value, unless it
+    /// is past the window for one that has not `joined` it in flight; a wait for
+    /// one in flight; or the lead, taking the slot.
+    fn claim(&self, slots: &mut HashMap<Key, Slot<T>>, key: &Key, joined: bool, now: DateTime<Utc>, window: Duration) -> Claim<T> {
+        let expired = |at: DateTime<Utc>| (now - at).to_std().is_ok_and(|age| age >= window);
+        match slots.get(key) {
+            Some(Slot::InFlight) => return Claim::Wait,
+            Some(Slot::Finished { at, value }) if joined || !expired(*at) => return Claim::Joined(value.clone()),
+            _ => {}
+        }
+        slots.retain(|_, slot| !matches!(slot, Slot::Finished { at, .. } if expired(*at)));
+        slots.insert(key.clone(), Slot::InFlight);
+        Claim::Lead
+    }
+
+    fn leader(&self, key: Key) -> Leader<T> {
+        Leader { slots: self.slots.clone(), key: Some(key) }
+    }
+
+    /// The value of `run`, or, with a window, of the identical run in flight or
+    /// finished within it, with whether it was that one's. A run joined while in
+    /// flight is returned however long it took. Waits with the GIL released,
+    /// since the run it waits on may need it.
+    pub(crate) fn run(
+        &self,
+        context_id: &str,
+        command: &str,
+        now: impl Fn() -> DateTime<Utc> + Sync,
+        run: impl FnOnce() -> T,
+    ) -> (T, bool) {
+        let Some(window) = self.window else { return (run(), false) };
+        let key = (context_id.to_string(), command.to_string());
+        let mut claim = self.claim(&mut self.slots.lock(), &key, false, now(), window);
+        if let Claim::Wait = claim {
+            // The guard cannot cross into `release_gil`, so the lock is taken again there.
+            claim = timing::release_gil(|| {
+                let mut slots = self.slots.lock();
+                loop {
+                    match self.claim(&mut slots, &key, true, now(), window) {
+                        Claim::Wait => slots = self.slots.finished.wait(slots).unwrap_or_else(|e| e.into_inner()),
+                        claim => return claim,
+                    }
+                }
+            });
+        }
+        if let Claim::Joined(value) = claim {
+            return (value, true);
+        }
+        let leader = self.leader(key);
+        let value = run();
+        leader.finish(now(), value.clone());
+        (value, false)
+    }
+
+    /// `run` for async runs, which wait without blocking their thread.
+    pub(crate) async fn run_async(
+        &self,
+        context_id: &str,
+        command: &str,
+        now: impl Fn() -> DateTime<Utc>,
+        run: impl Future<Output = T>,
+    ) -> (T, bool) {
+        let Some(window) = self.window else { return (run.await, false) };
+        let key = (context_id.to_string(), command.to_string());
+        let mut joined = false;
+        loop {
+            let finished = self.slots.finished_async.notified();
+            tokio::pin!(finished);
+            finished.as_mut().enable();
+            let claim = self.claim(&mut self.slots.lock(), &key, joined, now(), window);
+            match claim {
+                Claim::Joined(value) => return (value, true),
+                Claim::Lead => break,
+                Claim::Wait => joined = true,
+            }
+            finished.await;
+        }
+        let leader = self.leader(key);
+        let value = run.await;
+        leader.finish(now(), value.clone());
+        (value, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AgentResult, CognitiveOrchestrator, FixedClock, MockBackend, Priority, ProcessEvent, Submission};
+    use chrono::DateTime;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    const NAP: Duration = Duration::from_millis(80);
+
+    fn orchestrator(window: Option<Duration>, nap: bool) -> (CognitiveOrchestrator, Arc<FixedClock>, Arc<AtomicUsize>) {
+        let clock = Arc::new(FixedClock::new(DateTime::UNIX_EPOCH));
+        let calls = Arc::new(AtomicUsize::new(0));
+        let (seen, ticking) = (calls.clone(), clock.clone());
+        let mock = MockBackend::new().plan("launch", ["post teaser"]).on("post", move |rest| {
+            seen.fetch_add(1, Ordering::SeqCst);
+            if nap {
+                thread::sleep(NAP);
+                // Past the window, so only a run that joined in flight gets this one.
+                ticking.advance(chrono::Duration::minutes(5));
+            }
+            AgentResult::ok(format!("posted {}", rest))
+        });
+        let mut builder = CognitiveOrchestrator::builder().backend(Arc::new(mock)).clock(clock.clone()).learning(false);
+        if let Some(window) = window {
+            builder = builder.coalesce_window(window);
+        }
+        (builder.build().unwrap(), clock, calls)
+    }
+
+    #[test]
+    fn a_duplicate_in_flight_joins_the_run() {
+        let (orch, _, calls) = orchestrator(Some(Duration::from_secs(60)), true);
+        let orch = Arc::new(orch);
+        let runs: Vec<_> = (0..2)
+            .map(|_| {
+                let orch = orch.clone();
+                thread::spawn(move || orch.process_report("launch".to_string(), "ctx1"))
+            })
+            .collect();
+        let mut reports: Vec<_> = runs.into_iter().map(|run| run.join().unwrap()).collect();
+        reports.sort_by_key(|report| report.coalesced);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(!reports[0].coalesced && reports[1].coalesced);
+        assert_eq!(reports[1].run_id, reports[0].run_id);
+        assert_eq!(reports[1].outputs(), ["posted teaser"]);
+        assert_eq!(serde_json::to_value(&reports[1]).unwrap()["coalesced"], true);
+        assert!(serde_json::to_value(&reports[0]).unwrap().get("coalesced").is_none());
+    }
+
+    #[test]
+    fn a_finished_run_is_reused_within_the_window_only() {
+        let (orch, clock, calls) = orchestrator(Some(Duration::from_secs(60)), false);
+        let first = orch.process_report("launch".to_string(), "ctx1");
+        clock.advance(chrono::Duration::seconds(59));
+        let again = orch.process_report("launch".to_string(), "ctx1");
+        assert!(again.coalesced);
+        assert_eq!((again.run_id.as_ref(), calls.load(Ordering::SeqCst)), (first.run_id.as_ref(), 1));
+        // Another command or context is its own run.
+        assert!(!orch.process_report("launch now".to_string(), "ctx1").coalesced);
+        assert!(!orch.process_report("launch".to_string(), "ctx2").coalesced);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        clock.advance(chrono::Duration::seconds(1));
+        let later = orch.process_report("launch".to_string(), "ctx1");
+        assert!(!later.coalesced && later.run_id != first.run_id);
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+
+        // Off by default.
+        let (orch, _, calls) = orchestrator(None, false);
+        orch.process_report("launch".to_string(), "ctx1");
+        assert!(!orch.process_report("launch".to_string(), "ctx1").coalesced);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn runs_on_their_own_terms_or_under_a_lock_run_themselves() {
+        let (orch, _, calls) = orchestrator(Some(Duration::from_secs(60)), false);
+        let first = orch.process_report("launch".to_string(), "ctx1");
+        let own_id = orch.process_report_with("launch".to_string(), "ctx1", Submission::default().run_id("upstream-1"));
+        assert!(!own_id.coalesced);
+        assert_eq!(own_id.run_id.as_deref(), Some("upstream-1"));
+        let timed = Submission::default().timeout(Some(Duration::from_secs(5)));
+        assert!(!orch.process_report_with("launch".to_string(), "ctx1", timed).coalesced);
+        let urgent = Submission::default().priority(Priority::High);
+        assert!(!orch.process_report_with("launch".to_string(), "ctx1", urgent).coalesced);
+        orch.try_process("launch".to_string(), "ctx1").unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 5);
+
+        // None of them took the slot of the plain run.
+        let again = orch.process_report("launch".to_string(), "ctx1");
+        assert!(again.coalesced && again.run_id == first.run_id);
+        assert_eq!(calls.load(Ordering::SeqCst), 5);
+    }
+
+    #[test]
+    fn a_streamed_duplicate_gets_the_runs_events() {
+        let (orch, _, calls) = orchestrator(Some(Duration::from_secs(60)), true);
+        let orch = Arc::new(orch);
+        let runs: Vec<_> = (0..2)
+            .map(|_| {
+                let orch = orch.clone();
+                thread::spawn(move || {
+                    let mut events = vec![];
+                    orch.process_streaming("launch".to_string(), "ctx1", |event| events.push(event));
+                    events
+                })
+            })
+            .collect();
+        let mut streams: Vec<Vec<ProcessEvent>> = runs.into_iter().map(|run| run.join().unwrap()).collect();
+        let coalesced = |events: &[ProcessEvent]| matches!(events.last(), Some(ProcessEvent::Completed { coalesced: true, .. }));
+        streams.sort_by_key(|events| coalesced(events));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(!coalesced(&streams[0]) && coalesced(&streams[1]));
+        assert_eq!(streams[1][0].run_id(), streams[0][0].run_id());
+        let finished = |events: &[ProcessEvent]| -> Vec<String> {
+            events
+                .iter()
+                .filter_map(|event| match event {
+                    ProcessEvent::SubtaskFinished { result, .. } => Some(result.output.clone()),
+                    _ => None,
+                })
+                .collect()
+        };
+        assert_eq!(finished(&streams[1]), ["posted teaser"]);
+        assert_eq!(finished(&streams[1]), finished(&streams[0]));
+    }
+
+    #[tokio::test]
+    async fn async_duplicates_share_one_run() {
+        let (orch, _, calls) = orchestrator(Some(Duration::from_secs(60)), true);
+        let (first, second) = tokio::join!(
+            orch.process_async("launch".to_string(), "ctx1"),
+            orch.process_async("launch".to_string(), "ctx1")
+        );
+        assert_eq!((first.as_str(), second.as_str()), (r#"["posted teaser"]"#, r#"["posted teaser"]"#));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
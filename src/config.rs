@@ -0,0 +1,842 @@
+use crate::coalesce::Coalescer;
+use crate::context_map::ContextMap;
+use crate::dispatch_policy::CompiledPolicy;
+use crate::history::{secs, DEFAULT_HISTORY_LIMIT};
+use crate::metrics::OrchestratorMetrics;
+use crate::observe::LiveRuns;
+use crate::run_lock::RunLocks;
+use crate::run_store::RunStore;
+use crate::submission::{QueueConfig, SubmissionQueue};
+use crate::{
+    AgentBackend, AgentKind, AnomalyLog, DebugStrategies, AgentModule, AgentModuleConfig, AgentModules, AgentRegistry, Budgets, Cancellations, CircuitBreakers, Clock, CognitiveOrchestrator, DispatchPolicy, Embedder, EventBus, ExecutionHistory, GoalExpiry, HashEmbedder, IdempotencyKeys, InputPolicy, Journal, JournalConfig, LearningConfig, MemoryStore,
+    MetricsRecorder, MwpmDecoder, OrchestratorError, PersistenceFormat, PlanTemplates, PlanValidator, PlannerInputConfig, PostProcessors, PythonBackend, Quantization, QuantumAmplifier, RateLimits, Redactor, ResultCache, RetryPolicy, RetryPredicate, ShutdownHandle, SystemClock, Topology, ViralConfig,
+    ViralMetrics, ViralPropagator, ViralSimulation, WebhookConfig, WorkerPool, WorkerPoolConfig, DEFAULT_MAX_ATTRIBUTE_BYTES, DEFAULT_MAX_REPLANS,
+    DEFAULT_METRICS_HISTORY_LIMIT,
+};
+use pyo3::exceptions::PyValueError;
+use pyo3::PyErr;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use thiserror::Error;
+
+/// A rejected setting. `key` is its path in `Config`, e.g. `retry.base_delay_secs`,
+/// or the environment variable it came from.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ConfigError {
+    #[error("invalid {key}: {message}")]
+    Invalid { key: String, message: String },
+
+    #[error("could not read config {}: {message}", path.display())]
+    Read { path: PathBuf, message: String },
+
+    #[error("could not parse config {}: {message}", path.display())]
+    Parse { path: PathBuf, message: String },
+
+    #[error("{key} requires the `{feature}` feature")]
+    FeatureDisabled { key: String, feature: &'static str },
+
+    #[error("could not connect {key}: {source}")]
+    Connect {
+        key: String,
+        #[source]
+        source: OrchestratorError,
+    },
+}
+
+impl ConfigError {
+    pub fn invalid(key: impl Into<String>, message: impl Into<String>) -> Self {
+        ConfigError::Invalid { key: key.into(), message: message.into() }
+    }
+
+    /// The offending setting; `None` when the file as a whole could not be read.
+    pub fn key(&self) -> Option<&str> {
+        match self {
+            ConfigError::Invalid { key, .. } | ConfigError::FeatureDisabled { key, .. } | ConfigError::Connect { key, .. } => {
+                Some(key)
+            }
+            ConfigError::Read { .. } | ConfigError::Parse { .. } => None,
+        }
+    }
+}
+
+impl From<ConfigError> for PyErr {
+    fn from(err: ConfigError) -> Self {
+        PyValueError::new_err(err.to_string())
+    }
+}
+
+/// `RetryPolicy` without the predicate, which stays `transient_failure` unless set
+/// through `CognitiveOrchestratorBuilder::retry_policy`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryConfig {
+    pub max_attempts: u32,
+    #[serde(rename = "base_delay_secs", with = "secs")]
+    pub base_delay: Duration,
+    #[serde(rename = "max_delay_secs", with = "secs")]
+    pub max_delay: Duration,
+    pub jitter_seed: Option<u64>,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        let policy = RetryPolicy::none();
+        Self {
+            max_attempts: policy.max_attempts,
+            base_delay: policy.base_delay,
+            max_delay: policy.max_delay,
+            jitter_seed: policy.jitter_seed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QdrantConfig {
+    pub url: String,
+    pub collection: String,
+    #[serde(default = "default_vector_size")]
+    pub vector_size: usize,
+}
+
+fn default_vector_size() -> usize {
+    crate::memory::DEFAULT_VECTOR_SIZE
+}
+
+/// Serializable orchestrator settings; every field defaults to the behavior of
+/// `CognitiveOrchestrator::new()`. Durations are in seconds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub prefer_native: bool,
+    pub seed: Option<u64>,
+    #[serde(rename = "subtask_timeout_secs", with = "secs::option")]
+    pub subtask_timeout: Option<Duration>,
+    #[serde(rename = "context_ttl_secs", with = "secs::option")]
+    pub context_ttl: Option<Duration>,
+    pub max_contexts: Option<usize>,
+    pub eviction_path: Option<PathBuf>,
+    /// Where runs are journaled for crash recovery, as `journal` configures.
+    pub journal_path: Option<PathBuf>,
+    pub max_replans: usize,
+    /// What happens to goals found past their deadline.
+    pub goal_expiry: GoalExpiry,
+    pub history_limit: usize,
+    /// Metrics samples kept per context; see `Context::metrics_history`.
+    pub metrics_history_limit: usize,
+    /// Reports of the most recent runs `get_run` finds by run id; 0 keeps none.
+    pub run_retention: usize,
+    /// Bytes each context's attributes may take as JSON.
+    pub max_attribute_bytes: usize,
+    /// How long a finished run is returned to identical (command, context) runs;
+    /// `None` turns coalescing off. See `OrchestratorBuilder::coalesce_window` for
+    /// which runs coalesce.
+    #[serde(rename = "coalesce_window_secs", with = "secs::option")]
+    pub coalesce_window: Option<Duration>,
+    pub retry: RetryConfig,
+    /// Viral metrics every new context starts with.
+    #[serde(rename = "default")]
+    pub default_metrics: ViralMetrics,
+    pub viral: ViralConfig,
+    pub python_modules: AgentModuleConfig,
+    pub qdrant: Option<QdrantConfig>,
+    pub learning: LearningConfig,
+    pub planner: PlannerInputConfig,
+    pub dispatch_policy: DispatchPolicy,
+    pub debug_strategies: DebugStrategies,
+    pub plan_validation: PlanValidator,
+    pub journal: JournalConfig,
+    /// Endpoints sent bus events as they happen; needs the `webhooks` feature.
+    pub webhooks: Vec<WebhookConfig>,
+    pub worker_pool: WorkerPoolConfig,
+    pub input: InputPolicy,
+    /// How many runs may be in flight, and what happens to those past it.
+    pub queue: QueueConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            prefer_native: false,
+            seed: None,
+            subtask_timeout: None,
+            context_ttl: None,
+            max_contexts: None,
+            eviction_path: None,
+            journal_path: None,
+            max_replans: DEFAULT_MAX_REPLANS,
+            goal_expiry: GoalExpiry::default(),
+            history_limit: DEFAULT_HISTORY_LIMIT,
+            metrics_history_limit: DEFAULT_METRICS_HISTORY_LIMIT,
+            run_retention: 0,
+            max_attribute_bytes: DEFAULT_MAX_ATTRIBUTE_BYTES,
+            coalesce_window: None,
+            retry: RetryConfig::default(),
+            default_metrics: ViralMetrics::default(),
+            viral: ViralConfig::default(),
+            python_modules: AgentModuleConfig::default(),
+            qdrant: None,
+            learning: LearningConfig::default(),
+            planner: PlannerInputConfig::default(),
+            dispatch_policy: DispatchPolicy::default(),
+            debug_strategies: DebugStrategies::default(),
+            plan_validation: PlanValidator::default(),
+            journal: JournalConfig::default(),
+            webhooks: vec![],
+            worker_pool: WorkerPoolConfig::default(),
+            input: InputPolicy::default(),
+            queue: QueueConfig::default(),
+        }
+    }
+}
+
+fn compile_policy(policy: &DispatchPolicy) -> Result<CompiledPolicy, ConfigError> {
+    CompiledPolicy::compile(policy.clone()).map_err(|e| ConfigError::invalid("dispatch_policy", e.to_string()))
+}
+
+fn positive(key: &str, duration: Option<Duration>) -> Result<(), ConfigError> {
+    match duration {
+        Some(duration) if duration.is_zero() => Err(ConfigError::invalid(key, "must be greater than zero")),
+        _ => Ok(()),
+    }
+}
+
+fn unit_interval(key: &str, value: f64) -> Result<(), ConfigError> {
+    if (0.0..=1.0).contains(&value) {
+        Ok(())
+    } else {
+        Err(ConfigError::invalid(key, format!("must be between 0 and 1, got {}", value)))
+    }
+}
+
+/// Prefix of the environment variables `Config::apply_env` reads.
+pub const ENV_PREFIX: &str = "ACE_";
+
+/// Tables of `Config`, which environment variables address as `ACE_<TABLE>_<KEY>`.
+const ENV_TABLES: [&str; 13] = [
+    "python_modules",
+    "retry",
+    "default",
+    "qdrant",
+    "learning",
+    "planner",
+    "dispatch_policy",
+    "debug_strategies",
+    "plan_validation",
+    "journal",
+    "worker_pool",
+    "input",
+    "queue",
+];
+
+/// `ACE_RETRY_MAX_ATTEMPTS` -> `["retry", "max_attempts"]`; `None` for other variables.
+fn env_key(name: &str) -> Option<Vec<String>> {
+    let key = name.strip_prefix(ENV_PREFIX)?.to_lowercase();
+    for table in ENV_TABLES {
+        if let Some(field) = key.strip_prefix(table).and_then(|rest| rest.strip_prefix('_')) {
+            return Some(vec![table.to_string(), field.to_string()]);
+        }
+    }
+    Some(vec![key])
+}
+
+fn set_path(value: &mut serde_json::Value, path: &[String], leaf: serde_json::Value) {
+    let Some((last, parents)) = path.split_last() else {
+        return;
+    };
+    let mut node = value;
+    for key in parents {
+        if !node.is_object() {
+            *node = serde_json::Value::Object(Default::default());
+        }
+        node = &mut node[key.as_str()];
+    }
+    if !node.is_object() {
+        *node = serde_json::Value::Object(Default::default());
+    }
+    node[last.as_str()] = leaf;
+}
+
+/// Overlays `value` onto `base`, table by table. A table tagged with a different
+/// `kind`, such as another `viral.topology`, replaces the base one whole.
+fn merge(base: &mut serde_json::Value, value: serde_json::Value) {
+    match (base, value) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(value))
+            if value.get("kind").is_none_or(|kind| base.get("kind") == Some(kind)) =>
+        {
+            for (key, value) in value {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, value) => *base = value,
+    }
+}
+
+impl Config {
+    /// Reads a `.toml`, `.yaml` or `.yml` file; missing keys keep their defaults.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .map_err(|e| ConfigError::Read { path: path.to_path_buf(), message: e.to_string() })?;
+        let parse = |message: String| ConfigError::Parse { path: path.to_path_buf(), message };
+        let value: serde_json::Value = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::from_str(&text).map_err(|e| parse(e.to_string()))?,
+            Some("yaml" | "yml") => serde_yaml::from_str(&text).map_err(|e| parse(e.to_string()))?,
+            _ => return Err(parse("expected a .toml, .yaml or .yml file".to_string())),
+        };
+        Self::from_value(value, &HashMap::new())
+    }
+
+    /// Defaults overridden by `ACE_*` environment variables.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Config::default().apply_env()
+    }
+
+    /// `from_file`, then `apply_env`: environment variables win over the file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        Self::from_file(path)?.apply_env()
+    }
+
+    pub fn apply_env(self) -> Result<Self, ConfigError> {
+        self.apply_vars(std::env::vars())
+    }
+
+    /// Overrides settings from `ACE_<KEY>` or `ACE_<TABLE>_<KEY>` variables, e.g.
+    /// `ACE_SUBTASK_TIMEOUT_SECS=30` or `ACE_QDRANT_URL=http://localhost:6334`.
+    /// Values are read as JSON scalars where they parse as one, else as strings.
+    /// Any other `ACE_*` variable is an error naming it.
+    pub fn apply_vars(self, vars: impl IntoIterator<Item = (String, String)>) -> Result<Self, ConfigError> {
+        let mut value = serde_json::to_value(&self).map_err(|e| ConfigError::invalid("config", e.to_string()))?;
+        let mut sources = HashMap::new();
+        for (name, raw) in vars {
+            let Some(path) = env_key(&name) else {
+                continue;
+            };
+            let leaf = serde_json::from_str(&raw).unwrap_or(serde_json::Value::String(raw));
+            set_path(&mut value, &path, leaf);
+            sources.insert(path.join("."), name);
+        }
+        Self::from_value(value, &sources)
+    }
+
+    /// Deserializes `value` over the defaults, so partial tables keep their other
+    /// keys, naming the offending key (or the variable it came from) on error.
+    pub(crate) fn from_value(value: serde_json::Value, sources: &HashMap<String, String>) -> Result<Self, ConfigError> {
+        let mut merged = serde_json::to_value(Config::default()).map_err(|e| ConfigError::invalid("config", e.to_string()))?;
+        merge(&mut merged, value);
+        serde_path_to_error::deserialize(merged).map_err(|err| {
+            let path = err.path().to_string();
+            let key = sources.get(&path).cloned().unwrap_or(path);
+            ConfigError::invalid(key, err.into_inner().to_string())
+        })
+    }
+
+    /// Checks each setting and the combinations between them.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        positive("subtask_timeout_secs", self.subtask_timeout)?;
+        positive("context_ttl_secs", self.context_ttl)?;
+        positive("coalesce_window_secs", self.coalesce_window)?;
+        if self.max_contexts == Some(0) {
+            return Err(ConfigError::invalid("max_contexts", "must be greater than zero"));
+        }
+
+        let retry = &self.retry;
+        if retry.max_attempts == 0 {
+            return Err(ConfigError::invalid("retry.max_attempts", "must be at least 1"));
+        }
+        if retry.max_attempts > 1 {
+            positive("retry.base_delay_secs", Some(retry.base_delay))?;
+            if retry.max_delay < retry.base_delay {
+                return Err(ConfigError::invalid("retry.max_delay_secs", "must not be below retry.base_delay_secs"));
+            }
+        }
+
+        let metrics = &self.default_metrics;
+        if metrics.engagement_nodes == 0 {
+            return Err(ConfigError::invalid("default.engagement_nodes", "must be greater than zero"));
+        }
+        unit_interval("default.hook_rate", metrics.hook_rate)?;
+        unit_interval("default.quantum_fidelity", metrics.quantum_fidelity)?;
+        unit_interval("default.virality_score", metrics.virality_score)?;
+        if !metrics.amplification_factor.is_finite() || metrics.amplification_factor <= 0.0 {
+            return Err(ConfigError::invalid("default.amplification_factor", "must be a positive number"));
+        }
+
+        if let Some((field, message)) = self.viral.invalid() {
+            return Err(ConfigError::invalid(format!("viral.{}", field), message));
+        }
+
+        for kind in AgentKind::ALL {
+            let module = self.python_modules.get(kind);
+            if module.module.is_empty() || module.class.is_empty() {
+                return Err(ConfigError::invalid(
+                    format!("python_modules.{}", kind.name()),
+                    "must be `module` or `module:Class`",
+                ));
+            }
+        }
+
+        unit_interval("learning.reuse_threshold", self.learning.reuse_threshold)?;
+        compile_policy(&self.dispatch_policy)?;
+        if let Some((class, message)) = self.debug_strategies.invalid() {
+            return Err(ConfigError::invalid(format!("debug_strategies.{}", class), message));
+        }
+        if let Some((field, message)) = self.plan_validation.invalid() {
+            return Err(ConfigError::invalid(format!("plan_validation.{}", field), message));
+        }
+        if let Some((field, message)) = self.journal.invalid() {
+            return Err(ConfigError::invalid(format!("journal.{}", field), message));
+        }
+        if let Some((field, message)) = self.worker_pool.invalid() {
+            return Err(ConfigError::invalid(format!("worker_pool.{}", field), message));
+        }
+        if let Some((field, message)) = self.input.invalid() {
+            return Err(ConfigError::invalid(format!("input.{}", field), message));
+        }
+        if let Some((field, message)) = self.queue.invalid() {
+            return Err(ConfigError::invalid(format!("queue.{}", field), message));
+        }
+
+        for (i, webhook) in self.webhooks.iter().enumerate() {
+            if let Some((field, message)) = webhook.invalid() {
+                return Err(ConfigError::invalid(format!("webhooks[{}].{}", i, field), message));
+            }
+        }
+
+        if let Some(qdrant) = &self.qdrant {
+            if qdrant.url.trim().is_empty() {
+                return Err(ConfigError::invalid("qdrant.url", "must not be empty"));
+            }
+            if qdrant.collection.trim().is_empty() {
+                return Err(ConfigError::invalid("qdrant.collection", "must not be empty"));
+            }
+            if qdrant.vector_size == 0 {
+                return Err(ConfigError::invalid("qdrant.vector_size", "must be greater than zero"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Configures a `CognitiveOrchestrator`. Settings that serialize live in a
+/// `Config`; the clock, retry predicate and memory store are set here only. With
+/// a fixed clock and a seed, the same calls produce byte-identical contexts,
+/// snapshots and simulation results.
+pub struct CognitiveOrchestratorBuilder {
+    config: Config,
+    clock: Arc<dyn Clock>,
+    retry_on: Option<RetryPredicate>,
+    memory_store: Option<Arc<dyn MemoryStore>>,
+    backend: Option<Arc<dyn AgentBackend>>,
+    events: Option<EventBus>,
+    embedder: Option<Arc<dyn Embedder>>,
+}
+
+impl Default for CognitiveOrchestratorBuilder {
+    fn default() -> Self {
+        Config::default().into()
+    }
+}
+
+impl From<Config> for CognitiveOrchestratorBuilder {
+    fn from(config: Config) -> Self {
+        Self {
+            config,
+            clock: Arc::new(SystemClock),
+            retry_on: None,
+            memory_store: None,
+            backend: None,
+            events: None,
+            embedder: None,
+        }
+    }
+}
+
+impl CognitiveOrchestratorBuilder {
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Seeds the native viral and MWPM simulations and retry jitter. Without a
+    /// seed the simulations use seed 0 and jitter is seeded from the system clock.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.config.seed = Some(seed);
+        self
+    }
+
+    pub fn prefer_native(mut self, prefer_native: bool) -> Self {
+        self.config.prefer_native = prefer_native;
+        self
+    }
+
+    pub fn subtask_timeout(mut self, timeout: Duration) -> Self {
+        self.config.subtask_timeout = Some(timeout);
+        self
+    }
+
+    pub fn context_ttl(mut self, ttl: Duration) -> Self {
+        self.config.context_ttl = Some(ttl);
+        self
+    }
+
+    pub fn max_contexts(mut self, max_contexts: usize) -> Self {
+        self.config.max_contexts = Some(max_contexts);
+        self
+    }
+
+    pub fn eviction_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.eviction_path = Some(path.into());
+        self
+    }
+
+    pub fn max_replans(mut self, max_replans: usize) -> Self {
+        self.config.max_replans = max_replans;
+        self
+    }
+
+    /// What sweeping does with goals past their deadline.
+    pub fn goal_expiry(mut self, expiry: GoalExpiry) -> Self {
+        self.config.goal_expiry = expiry;
+        self
+    }
+
+    pub fn history_limit(mut self, limit: usize) -> Self {
+        self.config.history_limit = limit;
+        self
+    }
+
+    /// Keeps the reports of the last `count` runs for `get_run`.
+    pub fn run_retention(mut self, count: usize) -> Self {
+        self.config.run_retention = count;
+        self
+    }
+
+    /// Bounds each context's attributes to `bytes` as JSON.
+    pub fn max_attribute_bytes(mut self, bytes: usize) -> Self {
+        self.config.max_attribute_bytes = bytes;
+        self
+    }
+
+    /// Returns the report of an identical (command, context) run in flight, or
+    /// finished within `window`, instead of running it again. This covers
+    /// `process`, `process_report`, `process_streaming` (and so the HTTP and gRPC
+    /// process calls) and `process_async`, which coalesces with other async runs
+    /// only. Runs given their own run id, timeout, budget or non-normal priority
+    /// never coalesce, since an identical run would not honour them; nor do
+    /// `try_process` runs, which hold the context's lock before they start,
+    /// idempotent or resumed runs, and step-by-step `process_stream` and
+    /// `process_channel` runs.
+    pub fn coalesce_window(mut self, window: Duration) -> Self {
+        self.config.coalesce_window = Some(window);
+        self
+    }
+
+    /// How viral simulations wire the engagement graph they spread over.
+    pub fn viral_topology(mut self, topology: Topology) -> Self {
+        self.config.viral.topology = topology;
+        self
+    }
+
+    /// Virality a viral subtask must exceed to succeed.
+    pub fn virality_threshold(mut self, threshold: f64) -> Self {
+        self.config.viral.virality_threshold = threshold;
+        self
+    }
+
+    /// What `self_debug` does about each class of failure.
+    pub fn debug_strategies(mut self, strategies: DebugStrategies) -> Self {
+        self.config.debug_strategies = strategies;
+        self
+    }
+
+    /// What the planner's answers are held to.
+    pub fn plan_validator(mut self, validator: PlanValidator) -> Self {
+        self.config.plan_validation = validator;
+        self
+    }
+
+    /// Journals every run at `path`, as `journal` says, so a crashed process's
+    /// runs can be recovered.
+    pub fn journal(mut self, path: impl Into<PathBuf>, journal: JournalConfig) -> Self {
+        self.config.journal_path = Some(path.into());
+        self.config.journal = journal;
+        self
+    }
+
+    /// Dispatches `process_parallel`'s subtasks through a pool of warm worker threads.
+    pub fn worker_pool(mut self, pool: WorkerPoolConfig) -> Self {
+        self.config.worker_pool = pool;
+        self
+    }
+
+    /// What commands and subtasks must be, and how they are cleaned, before
+    /// they are planned or dispatched.
+    pub fn input_policy(mut self, policy: InputPolicy) -> Self {
+        self.config.input = policy;
+        self
+    }
+
+    /// Bounds the runs in flight, queueing the rest by priority; see `QueueConfig`.
+    pub fn submission_queue(mut self, queue: QueueConfig) -> Self {
+        self.config.queue = queue;
+        self
+    }
+
+    pub fn metrics_history_limit(mut self, limit: usize) -> Self {
+        self.config.metrics_history_limit = limit;
+        self
+    }
+
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.config.retry = RetryConfig {
+            max_attempts: policy.max_attempts,
+            base_delay: policy.base_delay,
+            max_delay: policy.max_delay,
+            jitter_seed: policy.jitter_seed,
+        };
+        self.retry_on = Some(policy.retry_on);
+        self
+    }
+
+    pub fn default_metrics(mut self, metrics: ViralMetrics) -> Self {
+        self.config.default_metrics = metrics;
+        self
+    }
+
+    pub fn engagement_nodes(mut self, nodes: usize) -> Self {
+        self.config.default_metrics.engagement_nodes = nodes;
+        self
+    }
+
+    pub fn agent_modules(mut self, modules: AgentModuleConfig) -> Self {
+        self.config.python_modules = modules;
+        self
+    }
+
+    pub fn agent_module(mut self, kind: AgentKind, module: AgentModule) -> Self {
+        self.config.python_modules.set(kind, module);
+        self
+    }
+
+    /// Connects the native Qdrant client at `build`; needs the `qdrant` feature.
+    pub fn qdrant(mut self, url: impl Into<String>, collection: impl Into<String>, vector_size: usize) -> Self {
+        self.config.qdrant = Some(QdrantConfig { url: url.into(), collection: collection.into(), vector_size });
+        self
+    }
+
+    /// Whether successful runs are learned and reused when planning; on by default.
+    pub fn learning(mut self, enabled: bool) -> Self {
+        self.config.learning.enabled = enabled;
+        self
+    }
+
+    /// Takes precedence over any Qdrant settings.
+    pub fn memory_store(mut self, store: Arc<dyn MemoryStore>) -> Self {
+        self.memory_store = Some(store);
+        self
+    }
+
+    /// Replaces the Python agents; `python_modules` then only affects
+    /// `validate_agents`. Defaults to a `PythonBackend` over them.
+    pub fn backend(mut self, backend: Arc<dyn AgentBackend>) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// Embeds text for `remember` and `recall_text`. Defaults to a `HashEmbedder`
+    /// of `DEFAULT_VECTOR_SIZE`; with the `embeddings` feature, a `CandleEmbedder`
+    /// runs a local sentence-embedding model instead.
+    pub fn embedder(mut self, embedder: Arc<dyn Embedder>) -> Self {
+        self.embedder = Some(embedder);
+        self
+    }
+
+    /// Publishes to `bus`, e.g. one shared by several orchestrators. Defaults to
+    /// a bus of `DEFAULT_EVENT_CAPACITY`.
+    pub fn event_bus(mut self, bus: EventBus) -> Self {
+        self.events = Some(bus);
+        self
+    }
+
+    /// POSTs the bus events `webhook` wants to it, from `build` on; needs the
+    /// `webhooks` feature.
+    pub fn webhook(mut self, webhook: WebhookConfig) -> Self {
+        self.config.webhooks.push(webhook);
+        self
+    }
+
+    fn connect_memory(&self) -> Result<Option<Arc<dyn MemoryStore>>, ConfigError> {
+        if let Some(store) = &self.memory_store {
+            return Ok(Some(store.clone()));
+        }
+        let Some(qdrant) = &self.config.qdrant else {
+            return Ok(None);
+        };
+        #[cfg(feature = "qdrant")]
+        {
+            let store = crate::memory::QdrantStore::new(&qdrant.url, &qdrant.collection, qdrant.vector_size)
+                .map_err(|source| ConfigError::Connect { key: "qdrant".to_string(), source })?;
+            Ok(Some(Arc::new(store)))
+        }
+        #[cfg(not(feature = "qdrant"))]
+        {
+            let _ = qdrant;
+            Err(ConfigError::FeatureDisabled { key: "qdrant".to_string(), feature: "qdrant" })
+        }
+    }
+
+    #[cfg(feature = "webhooks")]
+    fn start_webhooks(
+        webhooks: &[WebhookConfig],
+        events: &EventBus,
+        metrics: &OrchestratorMetrics,
+    ) -> Result<Vec<crate::WebhookNotifier>, ConfigError> {
+        webhooks
+            .iter()
+            .enumerate()
+            .map(|(i, webhook)| {
+                crate::WebhookNotifier::start(webhook.clone(), events, metrics.webhook_failures())
+                    .map_err(|err| ConfigError::invalid(format!("webhooks[{}]", i), err.to_string()))
+            })
+            .collect()
+    }
+
+    pub fn build(self) -> Result<CognitiveOrchestrator, ConfigError> {
+        self.config.validate()?;
+        let memory_store = self.connect_memory()?;
+        let journal = match &self.config.journal_path {
+            Some(path) => Some(
+                Journal::open(path, self.config.journal)
+                    .map_err(|source| ConfigError::Connect { key: "journal_path".to_string(), source })?,
+            ),
+            None => None,
+        };
+        let config = self.config;
+        let modules = AgentModules::new(config.python_modules);
+        let backend = self.backend.unwrap_or_else(|| Arc::new(PythonBackend::new(modules.clone())));
+
+        let mut propagator = ViralPropagator::new().with_topology(config.viral.topology);
+        let mut decoder = MwpmDecoder::new();
+        if let Some(seed) = config.seed {
+            propagator.seed = seed;
+            decoder.seed = seed;
+        }
+        let viral_propagator = Arc::new(propagator);
+        let quantum_amplifier = Arc::new(QuantumAmplifier::new());
+        let prefer_native = Arc::new(AtomicBool::new(config.prefer_native));
+        let metrics_recorder = MetricsRecorder::new(self.clock.clone(), config.metrics_history_limit);
+        let simulation = ViralSimulation::new(viral_propagator.clone(), quantum_amplifier.clone(), prefer_native.clone())
+            .with_config(config.viral)
+            .with_backend(backend.clone())
+            .with_recorder(metrics_recorder.clone());
+
+        let budgets = Budgets::default();
+        let metrics = OrchestratorMetrics::new();
+        let rate_limits = RateLimits::new(self.clock.clone(), metrics.rate_limit_waits());
+        let (queue_depth, queue_waits) = metrics.submission_queue();
+        let result_cache = ResultCache::new(self.clock.clone());
+        let breakers = CircuitBreakers::new(self.clock.clone());
+        let redactor = Redactor::default();
+
+        let mut retry_policy = RetryPolicy::new(config.retry.max_attempts);
+        retry_policy.base_delay = config.retry.base_delay;
+        retry_policy.max_delay = config.retry.max_delay;
+        retry_policy.jitter_seed = config.retry.jitter_seed.or(config.seed);
+        if let Some(retry_on) = self.retry_on {
+            retry_policy.retry_on = retry_on;
+        }
+
+        let embedder = self.embedder.unwrap_or_else(|| Arc::new(HashEmbedder::default()));
+        let dispatch_policy = compile_policy(&config.dispatch_policy)?;
+        let events = self.events.unwrap_or_default();
+        #[cfg(feature = "webhooks")]
+        let webhooks = Self::start_webhooks(&config.webhooks, &events, &metrics)?;
+        #[cfg(not(feature = "webhooks"))]
+        if !config.webhooks.is_empty() {
+            return Err(ConfigError::FeatureDisabled { key: "webhooks".to_string(), feature: "webhooks" });
+        }
+
+        Ok(CognitiveOrchestrator {
+            contexts: ContextMap::default(),
+            plan_templates: RwLock::new(PlanTemplates::with_defaults()),
+            dispatch_policy: RwLock::new(Arc::new(dispatch_policy)),
+            debug_strategies: RwLock::new(config.debug_strategies),
+            agents: RwLock::new(AgentRegistry::with_defaults(simulation, decoder, &backend, &budgets, &embedder)),
+            memory_store: RwLock::new(memory_store),
+            viral_propagator,
+            viral_config: config.viral,
+            prefer_native,
+            quantum_amplifier,
+            context_ttl: config.context_ttl,
+            max_contexts: config.max_contexts,
+            tenant_max_contexts: RwLock::default(),
+            eviction_path: config.eviction_path,
+            context_store: None,
+            journal,
+            subtask_timeout: config.subtask_timeout,
+            retry_policy: RwLock::new(retry_policy),
+            max_replans: config.max_replans,
+            goal_expiry: config.goal_expiry,
+            history: Mutex::new(ExecutionHistory::new(config.history_limit)),
+            pinned: Mutex::default(),
+            trash: Mutex::default(),
+            metrics,
+            clock: self.clock,
+            seed: config.seed,
+            default_metrics: config.default_metrics,
+            agent_modules: modules,
+            backend,
+            events,
+            #[cfg(feature = "webhooks")]
+            webhooks,
+            embedder,
+            auto_snapshots: false,
+            legacy_output: true,
+            auto_diff: false,
+            latency_regression_pct: crate::DEFAULT_LATENCY_REGRESSION_PCT,
+            last_reports: Mutex::default(),
+            runs: RunStore::new(config.run_retention),
+            max_attribute_bytes: config.max_attribute_bytes,
+            coalescer: Coalescer::new(config.coalesce_window),
+            async_coalescer: Coalescer::new(config.coalesce_window),
+            learning: config.learning,
+            planner_input: config.planner,
+            plan_validator: config.plan_validation,
+            memory_quantization: Quantization::None,
+            persistence_format: PersistenceFormat::Json,
+            snapshots: Mutex::default(),
+            budgets,
+            rate_limits,
+            result_cache,
+            breakers,
+            post_processors: PostProcessors::default(),
+            metrics_recorder,
+            auto_tune: Mutex::new(None),
+            drain: ShutdownHandle::default(),
+            idempotency: IdempotencyKeys::default(),
+            cancellations: Cancellations::default(),
+            run_locks: RunLocks::default(),
+            queue: SubmissionQueue::new(config.queue, queue_depth, queue_waits),
+            live: LiveRuns::default(),
+            anomalies: AnomalyLog::new(redactor.clone()),
+            worker_pool: (config.worker_pool.size > 0).then(|| WorkerPool::start(&config.worker_pool)),
+            redactor,
+            input_policy: config.input,
+        })
+    }
+}
@@ -0,0 +1,141 @@
+use crate::{Context, MemoryMeta};
+
+impl Context {
+    /// Applies what a subtask changed in its copy of the context, `changed`
+    /// against the `base` it was handed, onto this context, which other subtasks
+    /// of the same wave may already have changed. Viral metrics and attributes
+    /// merge per field, goals per id; memory entries and metrics samples the
+    /// subtask added are appended after those already here, and recall counts
+    /// add up. Anything else it changed replaces what is here.
+    pub(crate) fn absorb(&mut self, base: &Context, changed: Context, metrics_limit: usize) {
+        let (metrics, before, after) = (&mut self.viral_metrics, &base.viral_metrics, &changed.viral_metrics);
+        if after.virality_score != before.virality_score {
+            metrics.virality_score = after.virality_score;
+        }
+        if after.engagement_nodes != before.engagement_nodes {
+            metrics.engagement_nodes = after.engagement_nodes;
+        }
+        if after.hook_rate != before.hook_rate {
+            metrics.hook_rate = after.hook_rate;
+        }
+        if after.amplification_factor != before.amplification_factor {
+            metrics.amplification_factor = after.amplification_factor;
+        }
+        if after.quantum_fidelity != before.quantum_fidelity {
+            metrics.quantum_fidelity = after.quantum_fidelity;
+        }
+        for sample in changed.metrics_history.recorded_since(&base.metrics_history) {
+            self.metrics_history.record(sample.clone(), metrics_limit);
+        }
+
+        for (key, value) in &changed.attributes {
+            if base.attributes.get(key) != Some(value) {
+                self.attributes.insert(key.clone(), value.clone());
+            }
+        }
+        for key in base.attributes.keys().filter(|key| !changed.attributes.contains_key(*key)) {
+            self.attributes.remove(key);
+        }
+        for goal in &changed.active_goals {
+            if base.goal(&goal.id) == Some(goal) {
+                continue;
+            }
+            match self.active_goals.iter_mut().find(|existing| existing.id == goal.id) {
+                Some(existing) => *existing = goal.clone(),
+                None => self.active_goals.push(goal.clone()),
+            }
+        }
+
+        self.absorb_memory(base, &changed);
+        if changed.memory_decay != base.memory_decay {
+            self.memory_decay = changed.memory_decay;
+        }
+        if changed.viral_config != base.viral_config {
+            self.viral_config = changed.viral_config;
+        }
+        if changed.propagation != base.propagation {
+            self.propagation = changed.propagation;
+        }
+        if changed.lineage != base.lineage {
+            self.lineage = changed.lineage;
+        }
+        if changed.extra != base.extra {
+            self.extra = changed.extra;
+        }
+    }
+
+    fn absorb_memory(&mut self, base: &Context, changed: &Context) {
+        let known = base.memory_vectors.len();
+        // A subtask that dropped entries leaves nothing to line the others' up against.
+        if changed.memory_vectors.len() < known || self.memory_vectors.len() < known {
+            if changed.memory_vectors != base.memory_vectors {
+                self.memory_vectors = changed.memory_vectors.clone();
+                self.memory_texts = changed.memory_texts.clone();
+                self.memory_payloads = changed.memory_payloads.clone();
+                self.memory_meta = changed.memory_meta.clone();
+            }
+            return;
+        }
+        for idx in 0..known {
+            let (before, after) = (base.memory_meta(idx), changed.memory_meta(idx));
+            if before == after {
+                continue;
+            }
+            let mut meta = self.memory_meta(idx);
+            meta.access_count += after.access_count.saturating_sub(before.access_count);
+            self.memory_meta.resize(self.memory_vectors.len(), MemoryMeta::new(self.created_at));
+            self.memory_meta[idx] = meta;
+        }
+        for idx in known..changed.memory_vectors.len() {
+            let Some(vec) = changed.memory_vectors.get(idx) else { continue };
+            // Skipped if another subtask of the wave added vectors of another dimension.
+            let Ok(at) = self.memory_vectors.push(&vec) else { continue };
+            self.memory_meta.resize(at, MemoryMeta::new(self.created_at));
+            self.memory_meta.push(changed.memory_meta(idx));
+            if let Some(text) = changed.memory_text(idx) {
+                self.memory_texts.resize(at, None);
+                self.memory_texts.push(Some(text.to_string()));
+            }
+            if let Some(payload) = changed.memory_payload(idx) {
+                self.memory_payloads.resize(at, None);
+                self.memory_payloads.push(Some(payload.clone()));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AgentResult, CognitiveOrchestrator, MockBackend, Plan, PlanNode};
+    use std::sync::Arc;
+
+    fn node(id: usize, subtask: &str) -> PlanNode {
+        PlanNode { id, subtask: subtask.to_string(), depends_on: vec![] }
+    }
+
+    #[test]
+    fn a_wave_keeps_every_subtasks_changes_and_debugs_every_failure() {
+        let mock = Arc::new(
+            MockBackend::new()
+                .planner(|_| Plan::new(vec![node(0, "post a"), node(1, "inject hook 0.5"), node(2, "post b"), node(3, "measure spread")]))
+                .on("post alt", |_| AgentResult::ok("alt posted"))
+                .on("post", |rest| AgentResult::fail(format!("low virality on {}", rest)))
+                .virality(0.9)
+                .replan(["post alt"]),
+        );
+        let orch = CognitiveOrchestrator::builder().backend(mock.clone()).learning(false).build().unwrap();
+
+        let report = orch.process_report("launch".to_string(), "ctx1");
+        // The hook and the spread measurement ran side by side; neither undid the other.
+        let context = orch.get_context("ctx1").unwrap();
+        assert_eq!((context.viral_metrics.hook_rate, context.viral_metrics.virality_score), (0.5, 0.9));
+        assert_eq!(context.metrics_history.len(), 1);
+
+        assert_eq!(report.debug.len(), 2);
+        assert_eq!((report.replans, mock.replans().len()), (2, 2));
+        let replanned: Vec<_> = report.results.iter().filter_map(|result| result.get_str("replanned_from")).collect();
+        assert_eq!(replanned, ["post a", "post b"]);
+        assert_eq!(report.outputs()[4..], ["alt posted", "alt posted"]);
+        assert!(report.success);
+    }
+}
@@ -0,0 +1,73 @@
+use crate::{CognitiveOrchestrator, OrchestratorError, ProcessReport, ViralMetrics};
+use pyo3::prelude::*;
+
+/// One context of an orchestrator, as `orchestrator.context(context_id)` returns
+/// it for a `with` block: entering creates the context; leaving persists it into
+/// the eviction flush snapshot when `persist` is set, then removes it when
+/// `ephemeral` is, whether or not the block raised.
+#[pyclass(module = "sovereign_cli")]
+pub struct ContextHandle {
+    orchestrator: Py<CognitiveOrchestrator>,
+    #[pyo3(get)]
+    context_id: String,
+    #[pyo3(get)]
+    persist: bool,
+    #[pyo3(get)]
+    ephemeral: bool,
+}
+
+impl ContextHandle {
+    pub(crate) fn new(orchestrator: Py<CognitiveOrchestrator>, context_id: String, persist: bool, ephemeral: bool) -> Self {
+        Self { orchestrator, context_id, persist, ephemeral }
+    }
+}
+
+#[pymethods]
+impl ContextHandle {
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf.orchestrator.borrow(slf.py()).ensure_context(&slf.context_id);
+        slf
+    }
+
+    /// Never suppresses the block's exception; one from persisting is raised
+    /// after an ephemeral context has been removed.
+    fn __exit__(&self, py: Python, _exc_type: &PyAny, _exc_value: &PyAny, _traceback: &PyAny) -> PyResult<bool> {
+        let orch = self.orchestrator.borrow(py);
+        let (orch, context_id) = (&*orch, self.context_id.as_str());
+        let persisted = if self.persist { py.allow_threads(|| orch.persist_context(context_id)).map(drop) } else { Ok(()) };
+        if self.ephemeral {
+            orch.remove_context(&self.context_id);
+        }
+        persisted?;
+        Ok(false)
+    }
+
+    /// `CognitiveOrchestrator.process` on this context.
+    #[pyo3(signature = (command, timeout=None, budget=None))]
+    fn process(&self, py: Python, command: String, timeout: Option<f64>, budget: Option<&PyAny>) -> PyResult<String> {
+        self.orchestrator.borrow(py).py_process(py, command, &self.context_id, timeout, budget, None, true)
+    }
+
+    #[pyo3(signature = (command, timeout=None, budget=None))]
+    fn process_report(&self, py: Python, command: String, timeout: Option<f64>, budget: Option<&PyAny>) -> PyResult<ProcessReport> {
+        self.orchestrator.borrow(py).py_process_report(py, command, &self.context_id, timeout, budget)
+    }
+
+    #[pyo3(signature = (text, payload=None))]
+    fn remember(&self, py: Python, text: &str, payload: Option<&PyAny>) -> PyResult<usize> {
+        self.orchestrator.borrow(py).py_remember(&self.context_id, text, payload)
+    }
+
+    /// The context's current viral metrics.
+    fn metrics(&self, py: Python) -> PyResult<ViralMetrics> {
+        let context = self.orchestrator.borrow(py).get_context(&self.context_id);
+        match context {
+            Some(context) => Ok(context.viral_metrics),
+            None => Err(OrchestratorError::MissingContext { context_id: self.context_id.clone() }.into()),
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!("ContextHandle(context_id={:?}, persist={}, ephemeral={})", self.context_id, self.persist, self.ephemeral)
+    }
+}
@@ -0,0 +1,163 @@
+use crate::Context;
+use pyo3::Python;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard, TryLockError};
+
+/// A live context behind its own lock.
+pub(crate) type ContextSlot = Arc<Mutex<Context>>;
+
+/// The orchestrator's live contexts by key, each behind its own lock so work on
+/// different contexts runs in parallel. The map's lock is only held to find, add
+/// or remove a slot, never while a context is locked, so the two cannot deadlock.
+#[derive(Default)]
+pub(crate) struct ContextMap(RwLock<HashMap<String, ContextSlot>>);
+
+impl ContextMap {
+    fn map(&self) -> RwLockReadGuard<'_, HashMap<String, ContextSlot>> {
+        self.0.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn map_mut(&self) -> RwLockWriteGuard<'_, HashMap<String, ContextSlot>> {
+        self.0.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.map().len()
+    }
+
+    pub(crate) fn contains(&self, key: &str) -> bool {
+        self.map().contains_key(key)
+    }
+
+    pub(crate) fn slot(&self, key: &str) -> Option<ContextSlot> {
+        self.map().get(key).cloned()
+    }
+
+    /// Every slot with its key, in no particular order.
+    pub(crate) fn slots(&self) -> Vec<(String, ContextSlot)> {
+        self.map().iter().map(|(key, slot)| (key.clone(), slot.clone())).collect()
+    }
+
+    /// The slot for `key`, inserting `make()` when there is none, and whether it did.
+    pub(crate) fn get_or_insert_with(&self, key: &str, make: impl FnOnce() -> Context) -> (ContextSlot, bool) {
+        if let Some(slot) = self.slot(key) {
+            return (slot, false);
+        }
+        let mut map = self.map_mut();
+        match map.get(key) {
+            Some(slot) => (slot.clone(), false),
+            None => {
+                let slot = Arc::new(Mutex::new(make()));
+                map.insert(key.to_string(), slot.clone());
+                (slot, true)
+            }
+        }
+    }
+
+    /// Stores `context` under its key, in place when it is live, so holders of
+    /// its slot see the new value.
+    pub(crate) fn insert(&self, context: Context) {
+        let key = context.key();
+        match self.slot(&key) {
+            Some(slot) => *lock(&slot) = context,
+            None => {
+                self.map_mut().insert(key, Arc::new(Mutex::new(context)));
+            }
+        }
+    }
+
+    pub(crate) fn remove(&self, key: &str) -> Option<Context> {
+        let slot = self.map_mut().remove(key)?;
+        let context = lock(&slot).clone();
+        Some(context)
+    }
+
+    /// The context under its lock, if it is live.
+    pub(crate) fn read<R>(&self, key: &str, f: impl FnOnce(&Context) -> R) -> Option<R> {
+        let slot = self.slot(key)?;
+        let context = lock(&slot);
+        Some(f(&context))
+    }
+
+    pub(crate) fn update<R>(&self, key: &str, f: impl FnOnce(&mut Context) -> R) -> Option<R> {
+        let slot = self.slot(key)?;
+        let mut context = lock(&slot);
+        Some(f(&mut context))
+    }
+
+    /// Copies of the live contexts, each taken under its lock, for saving.
+    pub(crate) fn copies(&self) -> Vec<Context> {
+        self.slots().into_iter().map(|(_, slot)| lock(&slot).clone()).collect()
+    }
+}
+
+/// Locks a context. Its holder may be an agent waiting for the GIL, so a caller
+/// that has to wait does so with the GIL released.
+pub(crate) fn lock(slot: &Mutex<Context>) -> MutexGuard<'_, Context> {
+    loop {
+        match slot.try_lock() {
+            Ok(context) => return context,
+            Err(TryLockError::Poisoned(e)) => return e.into_inner(),
+            Err(TryLockError::WouldBlock) => Python::with_gil(|py| py.allow_threads(|| drop(slot.lock()))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AgentResult, CognitiveOrchestrator, MockBackend};
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    type Windows = Arc<Mutex<Vec<(Instant, Instant)>>>;
+
+    fn shared<T: Send + Sync>(orch: T) -> Arc<T> {
+        Arc::new(orch)
+    }
+
+    /// Runs `post` on each context from its own thread, returning when each
+    /// agent call started and ended.
+    fn run_concurrently(context_ids: [&'static str; 2]) -> Vec<(Instant, Instant)> {
+        let windows = Windows::default();
+        let recorded = windows.clone();
+        let mock = MockBackend::new().on("post", move |rest| {
+            let started = Instant::now();
+            thread::sleep(Duration::from_millis(100));
+            recorded.lock().unwrap().push((started, Instant::now()));
+            AgentResult { output: rest.to_string(), status: true, metadata: HashMap::new(), error: None }
+        });
+        let orch = shared(CognitiveOrchestrator::builder().backend(Arc::new(mock)).learning(false).build().unwrap());
+        let runs: Vec<_> = context_ids
+            .into_iter()
+            .map(|context_id| {
+                let orch = orch.clone();
+                thread::spawn(move || orch.process("post clip".to_string(), context_id))
+            })
+            .collect();
+        for run in runs {
+            assert_eq!(run.join().unwrap(), r#"["clip"]"#);
+        }
+        let windows = windows.lock().unwrap().clone();
+        windows
+    }
+
+    fn overlap((a, b): (&(Instant, Instant), &(Instant, Instant))) -> bool {
+        a.0 < b.1 && b.0 < a.1
+    }
+
+    #[test]
+    fn runs_on_different_contexts_execute_in_parallel() {
+        let windows = run_concurrently(["ctx1", "ctx2"]);
+        assert_eq!(windows.len(), 2);
+        assert!(overlap((&windows[0], &windows[1])), "{:?}", windows);
+    }
+
+    #[test]
+    fn runs_on_one_context_take_turns() {
+        let windows = run_concurrently(["ctx1", "ctx1"]);
+        assert_eq!(windows.len(), 2);
+        assert!(!overlap((&windows[0], &windows[1])), "{:?}", windows);
+    }
+}
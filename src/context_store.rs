@@ -0,0 +1,630 @@
+use crate::{Context, OrchestratorError, TrashedContext};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where the orchestrator keeps contexts it does not hold in memory: it puts the
+/// ones it evicts, puts them all on shutdown, and gets the ones `process` is
+/// called with before creating them afresh. Keys are `Context::key`. Deleted
+/// contexts go to a trash kept apart, which `get` and `list` do not see.
+pub trait ContextStore: Send + Sync {
+    /// Stores `context` under its key, replacing what was there.
+    fn put(&self, context: &Context) -> Result<(), OrchestratorError>;
+
+    fn get(&self, key: &str) -> Result<Option<Context>, OrchestratorError>;
+
+    /// Whether there was a context under `key`.
+    fn delete(&self, key: &str) -> Result<bool, OrchestratorError>;
+
+    /// Every stored key, sorted.
+    fn list(&self) -> Result<Vec<String>, OrchestratorError>;
+
+    /// `put` of each of `contexts`, all or none where the store can manage it.
+    fn put_snapshot(&self, contexts: &[Context]) -> Result<(), OrchestratorError> {
+        contexts.iter().try_for_each(|context| self.put(context))
+    }
+
+    /// Stores `trashed` in the trash under its key, replacing what was there.
+    fn put_trashed(&self, trashed: &TrashedContext) -> Result<(), OrchestratorError>;
+
+    fn get_trashed(&self, key: &str) -> Result<Option<TrashedContext>, OrchestratorError>;
+
+    /// Whether there was a trashed context under `key`.
+    fn delete_trashed(&self, key: &str) -> Result<bool, OrchestratorError>;
+
+    /// Every trashed key, sorted.
+    fn list_trashed(&self) -> Result<Vec<String>, OrchestratorError>;
+}
+
+#[cfg(any(feature = "sqlite", feature = "redb"))]
+fn store_error(err: impl std::fmt::Display) -> OrchestratorError {
+    OrchestratorError::Store { message: err.to_string() }
+}
+
+/// The binary stores' blob: MessagePack, which keeps the field names JSON values need.
+#[cfg(any(feature = "sqlite", feature = "redb"))]
+fn encode(value: &impl serde::Serialize) -> Result<Vec<u8>, OrchestratorError> {
+    rmp_serde::to_vec_named(value).map_err(store_error)
+}
+
+#[cfg(any(feature = "sqlite", feature = "redb"))]
+fn decode<T: serde::de::DeserializeOwned>(key: &str, bytes: &[u8]) -> Result<T, OrchestratorError> {
+    rmp_serde::from_slice(bytes).map_err(|e| store_error(format!("context {:?}: {}", key, e)))
+}
+
+/// One pretty-printed JSON file per context in a directory, created on first
+/// `put`, and the trash likewise in its `trash` subdirectory. File names escape
+/// whatever is not alphanumeric, `-` or `_`.
+#[derive(Debug, Clone)]
+pub struct FileContextStore {
+    dir: PathBuf,
+}
+
+impl FileContextStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", file_stem(key)))
+    }
+
+    fn trash_dir(&self) -> PathBuf {
+        self.dir.join("trash")
+    }
+
+    fn trash_path(&self, key: &str) -> PathBuf {
+        self.trash_dir().join(format!("{}.json", file_stem(key)))
+    }
+}
+
+fn file_stem(key: &str) -> String {
+    key.bytes()
+        .map(|byte| match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+fn key_of(stem: &str) -> Option<String> {
+    let mut bytes = vec![];
+    let mut rest = stem.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+fn write_json(path: &Path, value: &impl serde::Serialize) -> Result<(), OrchestratorError> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    fs::create_dir_all(dir).map_err(|e| OrchestratorError::io(dir, e))?;
+    let json = serde_json::to_vec_pretty(value).map_err(OrchestratorError::serialization)?;
+    // Each writer renames its own temp file into place, so readers never see half a file.
+    let tmp = path.with_extension(format!("{}.tmp", uuid::Uuid::new_v4()));
+    fs::write(&tmp, json).map_err(|e| OrchestratorError::io(&tmp, e))?;
+    fs::rename(&tmp, path).map_err(|e| OrchestratorError::io(path, e))
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<Option<T>, OrchestratorError> {
+    match fs::read(path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).map(Some).map_err(OrchestratorError::serialization),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(OrchestratorError::io(path, err)),
+    }
+}
+
+fn remove_json(path: &Path) -> Result<bool, OrchestratorError> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(err) => Err(OrchestratorError::io(path, err)),
+    }
+}
+
+/// The keys of the JSON files in `dir`, sorted.
+fn json_keys(dir: &Path) -> Result<Vec<String>, OrchestratorError> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(OrchestratorError::io(dir, err)),
+    };
+    let mut keys = vec![];
+    for entry in entries {
+        let name = entry.map_err(|e| OrchestratorError::io(dir, e))?.file_name();
+        if let Some(key) = name.to_str().and_then(|name| name.strip_suffix(".json")).and_then(key_of) {
+            keys.push(key);
+        }
+    }
+    keys.sort();
+    Ok(keys)
+}
+
+impl ContextStore for FileContextStore {
+    fn put(&self, context: &Context) -> Result<(), OrchestratorError> {
+        write_json(&self.path(&context.key()), context)
+    }
+
+    fn get(&self, key: &str) -> Result<Option<Context>, OrchestratorError> {
+        read_json(&self.path(key))
+    }
+
+    fn delete(&self, key: &str) -> Result<bool, OrchestratorError> {
+        remove_json(&self.path(key))
+    }
+
+    fn list(&self) -> Result<Vec<String>, OrchestratorError> {
+        json_keys(&self.dir)
+    }
+
+    fn put_trashed(&self, trashed: &TrashedContext) -> Result<(), OrchestratorError> {
+        write_json(&self.trash_path(&trashed.key()), trashed)
+    }
+
+    fn get_trashed(&self, key: &str) -> Result<Option<TrashedContext>, OrchestratorError> {
+        read_json(&self.trash_path(key))
+    }
+
+    fn delete_trashed(&self, key: &str) -> Result<bool, OrchestratorError> {
+        remove_json(&self.trash_path(key))
+    }
+
+    fn list_trashed(&self) -> Result<Vec<String>, OrchestratorError> {
+        json_keys(&self.trash_dir())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteContextStore;
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::{decode, encode, store_error, ContextStore};
+    use crate::{Context, OrchestratorError, TrashedContext};
+    use chrono::{DateTime, SecondsFormat, Utc};
+    use rusqlite::{params, Connection, ErrorCode, OptionalExtension, TransactionBehavior};
+    use std::path::{Path, PathBuf};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    /// How long SQLite itself waits on another connection's lock.
+    const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+    /// Then how many more times a statement is tried, backing off between them.
+    const BUSY_RETRIES: u32 = 8;
+
+    /// One row per context in a SQLite database, with the context as a MessagePack
+    /// blob and its `last_accessed` indexed, and the trash in a table of its own. The database is in WAL mode, writes
+    /// take the write lock up front and busy ones are retried, so stores on the
+    /// same file, in this process or others, can write at once.
+    pub struct SqliteContextStore {
+        path: PathBuf,
+        conn: Mutex<Connection>,
+    }
+
+    impl SqliteContextStore {
+        pub fn open(path: impl Into<PathBuf>) -> Result<Self, OrchestratorError> {
+            let path = path.into();
+            let conn = Connection::open(&path).map_err(store_error)?;
+            conn.busy_timeout(BUSY_TIMEOUT).map_err(store_error)?;
+            retry(|| {
+                conn.query_row("PRAGMA journal_mode = WAL", [], |row| row.get::<_, String>(0))?;
+                conn.execute_batch(
+                    "CREATE TABLE IF NOT EXISTS contexts (
+                         key TEXT PRIMARY KEY,
+                         last_accessed TEXT NOT NULL,
+                         data BLOB NOT NULL
+                     );
+                     CREATE INDEX IF NOT EXISTS contexts_last_accessed ON contexts (last_accessed);
+                     CREATE TABLE IF NOT EXISTS trash (
+                         key TEXT PRIMARY KEY,
+                         deleted_at TEXT NOT NULL,
+                         data BLOB NOT NULL
+                     );",
+                )
+            })?;
+            Ok(Self { path, conn: Mutex::new(conn) })
+        }
+
+        pub fn path(&self) -> &Path {
+            &self.path
+        }
+
+        /// Keys of the contexts last accessed before `cutoff`, oldest first, for
+        /// pruning the store.
+        pub fn accessed_before(&self, cutoff: DateTime<Utc>) -> Result<Vec<String>, OrchestratorError> {
+            let conn = self.conn();
+            retry(|| {
+                let mut statement =
+                    conn.prepare_cached("SELECT key FROM contexts WHERE last_accessed < ?1 ORDER BY last_accessed")?;
+                let keys = statement.query_map([timestamp(cutoff)], |row| row.get(0))?;
+                keys.collect()
+            })
+        }
+
+        fn conn(&self) -> std::sync::MutexGuard<'_, Connection> {
+            self.conn.lock().unwrap_or_else(|e| e.into_inner())
+        }
+
+        fn write(&self, contexts: &[Context]) -> Result<(), OrchestratorError> {
+            let rows = contexts
+                .iter()
+                .map(|context| Ok((context.key(), timestamp(context.last_accessed), encode(context)?)))
+                .collect::<Result<Vec<_>, OrchestratorError>>()?;
+            let mut conn = self.conn();
+            retry(|| {
+                let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+                {
+                    let mut statement = tx.prepare_cached(
+                        "INSERT INTO contexts (key, last_accessed, data) VALUES (?1, ?2, ?3)
+                         ON CONFLICT (key) DO UPDATE SET last_accessed = excluded.last_accessed, data = excluded.data",
+                    )?;
+                    for (key, accessed, data) in &rows {
+                        statement.execute(params![key, accessed, data])?;
+                    }
+                }
+                tx.commit()
+            })
+        }
+    }
+
+    /// Fixed-width UTC, so the index orders rows by time.
+    fn timestamp(at: DateTime<Utc>) -> String {
+        at.to_rfc3339_opts(SecondsFormat::Micros, true)
+    }
+
+    /// `f`, tried again while another connection holds the database past the busy timeout.
+    fn retry<T>(mut f: impl FnMut() -> rusqlite::Result<T>) -> Result<T, OrchestratorError> {
+        let mut attempt = 0;
+        loop {
+            match f() {
+                Err(rusqlite::Error::SqliteFailure(err, _))
+                    if matches!(err.code, ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked) && attempt < BUSY_RETRIES =>
+                {
+                    attempt += 1;
+                    std::thread::sleep(Duration::from_millis(10 << attempt.min(6)));
+                }
+                result => return result.map_err(store_error),
+            }
+        }
+    }
+
+    impl ContextStore for SqliteContextStore {
+        fn put(&self, context: &Context) -> Result<(), OrchestratorError> {
+            self.write(std::slice::from_ref(context))
+        }
+
+        fn get(&self, key: &str) -> Result<Option<Context>, OrchestratorError> {
+            let conn = self.conn();
+            let data: Option<Vec<u8>> = retry(|| {
+                conn.prepare_cached("SELECT data FROM contexts WHERE key = ?1")?.query_row([key], |row| row.get(0)).optional()
+            })?;
+            data.map(|bytes| decode(key, &bytes)).transpose()
+        }
+
+        fn delete(&self, key: &str) -> Result<bool, OrchestratorError> {
+            let conn = self.conn();
+            retry(|| conn.execute("DELETE FROM contexts WHERE key = ?1", [key])).map(|deleted| deleted > 0)
+        }
+
+        fn list(&self) -> Result<Vec<String>, OrchestratorError> {
+            let conn = self.conn();
+            retry(|| {
+                let mut statement = conn.prepare_cached("SELECT key FROM contexts ORDER BY key")?;
+                let keys = statement.query_map([], |row| row.get(0))?;
+                keys.collect()
+            })
+        }
+
+        /// In one transaction.
+        fn put_snapshot(&self, contexts: &[Context]) -> Result<(), OrchestratorError> {
+            self.write(contexts)
+        }
+
+        fn put_trashed(&self, trashed: &TrashedContext) -> Result<(), OrchestratorError> {
+            let (key, deleted_at, data) = (trashed.key(), timestamp(trashed.deleted_at), encode(trashed)?);
+            let conn = self.conn();
+            retry(|| {
+                conn.execute(
+                    "INSERT INTO trash (key, deleted_at, data) VALUES (?1, ?2, ?3)
+                     ON CONFLICT (key) DO UPDATE SET deleted_at = excluded.deleted_at, data = excluded.data",
+                    params![key, deleted_at, data],
+                )
+            })
+            .map(|_| ())
+        }
+
+        fn get_trashed(&self, key: &str) -> Result<Option<TrashedContext>, OrchestratorError> {
+            let conn = self.conn();
+            let data: Option<Vec<u8>> = retry(|| {
+                conn.prepare_cached("SELECT data FROM trash WHERE key = ?1")?.query_row([key], |row| row.get(0)).optional()
+            })?;
+            data.map(|bytes| decode(key, &bytes)).transpose()
+        }
+
+        fn delete_trashed(&self, key: &str) -> Result<bool, OrchestratorError> {
+            let conn = self.conn();
+            retry(|| conn.execute("DELETE FROM trash WHERE key = ?1", [key])).map(|deleted| deleted > 0)
+        }
+
+        fn list_trashed(&self) -> Result<Vec<String>, OrchestratorError> {
+            let conn = self.conn();
+            retry(|| {
+                let mut statement = conn.prepare_cached("SELECT key FROM trash ORDER BY key")?;
+                let keys = statement.query_map([], |row| row.get(0))?;
+                keys.collect()
+            })
+        }
+    }
+}
+
+#[cfg(feature = "redb")]
+pub use self::redb::RedbContextStore;
+
+#[cfg(feature = "redb")]
+mod redb {
+    use super::{decode, encode, store_error, ContextStore};
+    use crate::{Context, OrchestratorError, TrashedContext};
+    use ::redb::{Database, ReadableDatabase, ReadableTable, TableDefinition};
+    use std::path::{Path, PathBuf};
+
+    const CONTEXTS: TableDefinition<&str, &[u8]> = TableDefinition::new("contexts");
+    const TRASH: TableDefinition<&str, &[u8]> = TableDefinition::new("trash");
+
+    /// Contexts as MessagePack blobs in a redb database, which one process opens
+    /// at a time; within it, writes are serialized and each is atomic.
+    pub struct RedbContextStore {
+        path: PathBuf,
+        db: Database,
+    }
+
+    impl RedbContextStore {
+        pub fn open(path: impl Into<PathBuf>) -> Result<Self, OrchestratorError> {
+            let path = path.into();
+            let db = Database::create(&path).map_err(store_error)?;
+            // Opening the tables in a write creates them, so reads never find them missing.
+            let tx = db.begin_write().map_err(store_error)?;
+            tx.open_table(CONTEXTS).map_err(store_error)?;
+            tx.open_table(TRASH).map_err(store_error)?;
+            tx.commit().map_err(store_error)?;
+            Ok(Self { path, db })
+        }
+
+        pub fn path(&self) -> &Path {
+            &self.path
+        }
+
+        fn write(&self, contexts: &[Context]) -> Result<(), OrchestratorError> {
+            let rows = contexts.iter().map(|context| Ok((context.key(), encode(context)?))).collect::<Result<Vec<_>, OrchestratorError>>()?;
+            let tx = self.db.begin_write().map_err(store_error)?;
+            {
+                let mut table = tx.open_table(CONTEXTS).map_err(store_error)?;
+                for (key, data) in &rows {
+                    table.insert(key.as_str(), data.as_slice()).map_err(store_error)?;
+                }
+            }
+            tx.commit().map_err(store_error)
+        }
+
+        fn keys(&self, table: TableDefinition<&str, &[u8]>) -> Result<Vec<String>, OrchestratorError> {
+            let tx = self.db.begin_read().map_err(store_error)?;
+            let table = tx.open_table(table).map_err(store_error)?;
+            let mut keys = vec![];
+            for row in table.iter().map_err(store_error)? {
+                keys.push(row.map_err(store_error)?.0.value().to_string());
+            }
+            Ok(keys)
+        }
+
+        fn remove(&self, table: TableDefinition<&str, &[u8]>, key: &str) -> Result<bool, OrchestratorError> {
+            let tx = self.db.begin_write().map_err(store_error)?;
+            let deleted = tx.open_table(table).map_err(store_error)?.remove(key).map_err(store_error)?.is_some();
+            tx.commit().map_err(store_error)?;
+            Ok(deleted)
+        }
+    }
+
+    impl ContextStore for RedbContextStore {
+        fn put(&self, context: &Context) -> Result<(), OrchestratorError> {
+            self.write(std::slice::from_ref(context))
+        }
+
+        fn get(&self, key: &str) -> Result<Option<Context>, OrchestratorError> {
+            let tx = self.db.begin_read().map_err(store_error)?;
+            let table = tx.open_table(CONTEXTS).map_err(store_error)?;
+            let data = table.get(key).map_err(store_error)?;
+            data.map(|bytes| decode(key, bytes.value())).transpose()
+        }
+
+        fn delete(&self, key: &str) -> Result<bool, OrchestratorError> {
+            self.remove(CONTEXTS, key)
+        }
+
+        fn list(&self) -> Result<Vec<String>, OrchestratorError> {
+            self.keys(CONTEXTS)
+        }
+
+        /// In one transaction.
+        fn put_snapshot(&self, contexts: &[Context]) -> Result<(), OrchestratorError> {
+            self.write(contexts)
+        }
+
+        fn put_trashed(&self, trashed: &TrashedContext) -> Result<(), OrchestratorError> {
+            let data = encode(trashed)?;
+            let tx = self.db.begin_write().map_err(store_error)?;
+            tx.open_table(TRASH).map_err(store_error)?.insert(trashed.key().as_str(), data.as_slice()).map_err(store_error)?;
+            tx.commit().map_err(store_error)
+        }
+
+        fn get_trashed(&self, key: &str) -> Result<Option<TrashedContext>, OrchestratorError> {
+            let tx = self.db.begin_read().map_err(store_error)?;
+            let table = tx.open_table(TRASH).map_err(store_error)?;
+            let data = table.get(key).map_err(store_error)?;
+            data.map(|bytes| decode(key, bytes.value())).transpose()
+        }
+
+        fn delete_trashed(&self, key: &str) -> Result<bool, OrchestratorError> {
+            self.remove(TRASH, key)
+        }
+
+        fn list_trashed(&self) -> Result<Vec<String>, OrchestratorError> {
+            self.keys(TRASH)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{file_stem, key_of, ContextStore, FileContextStore};
+    use crate::{CognitiveOrchestrator, Context, MockBackend, TrashedContext};
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    fn context(context_id: &str) -> Context {
+        let orch = CognitiveOrchestrator::builder().learning(false).build().unwrap();
+        orch.remember(context_id, "teaser a échoué").unwrap();
+        orch.get_context(context_id).unwrap()
+    }
+
+    /// A fresh path, with whatever an earlier run left there removed.
+    fn path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("sovereign-context-store-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    /// What every store must do, on an empty one.
+    fn exercise(store: &dyn ContextStore) {
+        assert_eq!(store.list().unwrap(), Vec::<String>::new());
+        assert_eq!(store.get("ctx1").unwrap(), None);
+        let (first, second) = (context("ctx1"), context("ctx 2/ü"));
+        store.put_snapshot(&[first.clone(), second.clone()]).unwrap();
+        assert_eq!(store.get("ctx1").unwrap(), Some(first.clone()));
+        assert_eq!(store.get("ctx 2/ü").unwrap(), Some(second));
+        assert_eq!(store.list().unwrap(), ["ctx 2/ü", "ctx1"]);
+
+        let mut changed = first;
+        changed.memory_decay = 0.5;
+        store.put(&changed).unwrap();
+        assert_eq!(store.get("ctx1").unwrap(), Some(changed));
+        assert!(store.delete("ctx1").unwrap());
+        assert!(!store.delete("ctx1").unwrap());
+        assert_eq!(store.list().unwrap(), ["ctx 2/ü"]);
+
+        let trashed = TrashedContext { context: context("ctx 3"), deleted_at: chrono::DateTime::UNIX_EPOCH };
+        assert_eq!(store.list_trashed().unwrap(), Vec::<String>::new());
+        store.put_trashed(&trashed).unwrap();
+        assert_eq!(store.get_trashed("ctx 3").unwrap(), Some(trashed));
+        assert_eq!((store.list().unwrap(), store.list_trashed().unwrap()), (vec!["ctx 2/ü".to_string()], vec!["ctx 3".to_string()]));
+        assert_eq!(store.get("ctx 3").unwrap(), None);
+        assert!(store.delete_trashed("ctx 3").unwrap());
+        assert!(!store.delete_trashed("ctx 3").unwrap());
+    }
+
+    #[test]
+    fn file_store_keeps_one_file_per_context() {
+        let dir = path("files");
+        exercise(&FileContextStore::new(&dir));
+        let key = "acme\u{1f}ctx 2/ü";
+        assert_eq!(key_of(&file_stem(key)).as_deref(), Some(key));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn evicted_contexts_are_stored_and_loaded_back_by_process() {
+        let dir = path("orchestrator");
+        let orch = CognitiveOrchestrator::builder()
+            .backend(Arc::new(MockBackend::new()))
+            .learning(false)
+            .max_contexts(1)
+            .build()
+            .unwrap()
+            .with_context_store(Box::new(FileContextStore::new(&dir)));
+        orch.remember("ctx1", "teaser a échoué").unwrap();
+        let stored = orch.get_context("ctx1").unwrap();
+        orch.remember("ctx2", "budget dépassé").unwrap();
+        assert_eq!(orch.context_ids(), ["ctx2"]);
+        assert_eq!(orch.context_store().unwrap().list().unwrap(), ["ctx1"]);
+
+        orch.process("nothing to do".to_string(), "ctx1");
+        let loaded = orch.get_context("ctx1").unwrap();
+        assert_eq!(loaded.memory_texts, stored.memory_texts);
+        assert_eq!(loaded.created_at, stored.created_at);
+
+        let report = orch.shutdown(std::time::Duration::ZERO);
+        assert!(report.stored);
+        assert_eq!(FileContextStore::new(&dir).list().unwrap(), ["ctx1", "ctx2"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_store_survives_concurrent_writers() {
+        use super::SqliteContextStore;
+
+        exercise(&SqliteContextStore::open(path("contexts.sqlite")).unwrap());
+        let file = path("concurrent.sqlite");
+
+        // Writers on their own connections and writers sharing one, all at once,
+        // every one rewriting a context the others write too.
+        let shared = Arc::new(SqliteContextStore::open(&file).unwrap());
+        let writers: Vec<_> = (0..8)
+            .map(|writer| {
+                let (file, shared) = (file.clone(), shared.clone());
+                std::thread::spawn(move || {
+                    let own = SqliteContextStore::open(&file).unwrap();
+                    let store: &dyn ContextStore = if writer % 2 == 0 { &own } else { shared.as_ref() };
+                    for round in 0..25 {
+                        let mut context = context(&format!("writer{}", writer));
+                        context.extra.insert("round".to_string(), round.into());
+                        store.put(&context).unwrap();
+                        store.put_snapshot(&[context.clone(), Context { context_id: "shared".to_string(), ..context }]).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let store = SqliteContextStore::open(&file).unwrap();
+        let keys = store.list().unwrap();
+        assert_eq!(keys.len(), 9, "{:?}", keys);
+        for key in &keys {
+            let context = store.get(key).unwrap().unwrap();
+            assert_eq!(context.key(), *key);
+            assert!(context.extra["round"].as_u64().is_some());
+        }
+        assert_eq!(store.get("writer0").unwrap().unwrap().extra["round"], 24);
+        assert_eq!(store.accessed_before(chrono::Utc::now()).unwrap().len(), 9);
+        drop((store, shared));
+        for file in [path("contexts.sqlite"), file] {
+            for suffix in ["", "-wal", "-shm"] {
+                let _ = std::fs::remove_file(format!("{}{}", file.display(), suffix));
+            }
+        }
+    }
+
+    #[cfg(feature = "redb")]
+    #[test]
+    fn redb_store_keeps_contexts_across_opens() {
+        use super::RedbContextStore;
+
+        let file = path("contexts.redb");
+        exercise(&RedbContextStore::open(&file).unwrap());
+        let reopened = RedbContextStore::open(&file).unwrap();
+        assert_eq!(reopened.list().unwrap(), ["ctx 2/ü"]);
+        drop(reopened);
+        std::fs::remove_file(&file).unwrap();
+    }
+}
@@ -0,0 +1,78 @@
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+use std::cell::RefCell;
+use std::future::Future;
+use std::sync::Arc;
+use std::thread;
+
+tokio::task_local! {
+    /// The event loop of the Python caller awaiting a `process_async` run.
+    static TASK_LOOP: Arc<PyObject>;
+}
+
+thread_local! {
+    /// `TASK_LOOP` of the run this blocking-pool thread is working for.
+    static THREAD_LOOP: RefCell<Option<Arc<PyObject>>> = const { RefCell::new(None) };
+}
+
+/// Awaits `run` with `event_loop` as the loop its agents' coroutines run on.
+pub(crate) async fn on_loop<F: Future>(event_loop: PyObject, run: F) -> F::Output {
+    TASK_LOOP.scope(Arc::new(event_loop), run).await
+}
+
+/// Restores the thread's previous loop, including when the task panics.
+struct Restore(Option<Arc<PyObject>>);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        THREAD_LOOP.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+/// `task`, carrying the current run's loop to whichever thread runs it.
+pub(crate) fn carry_loop<T, F>(task: F) -> impl FnOnce() -> T + Send + 'static
+where
+    T: 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let event_loop = TASK_LOOP.try_with(Arc::clone).ok();
+    move || {
+        let _restore = Restore(THREAD_LOOP.with(|current| current.replace(event_loop)));
+        task()
+    }
+}
+
+/// Agent methods may be `async def`: a coroutine an agent returned is run to
+/// completion and its result returned in its place. During `process_async` it
+/// runs on the awaiting caller's loop, so it can share that loop's clients;
+/// otherwise on a loop of its own, started on another thread when this one is
+/// already running a loop (a synchronous `process` called from async code).
+pub(crate) fn resolve(value: &PyAny) -> PyResult<&PyAny> {
+    let py = value.py();
+    let asyncio = py.import("asyncio")?;
+    if !asyncio.call_method1("iscoroutine", (value,))?.is_true()? {
+        return Ok(value);
+    }
+    if let Some(event_loop) = THREAD_LOOP.with(|current| current.borrow().clone()) {
+        let event_loop = event_loop.as_ref().as_ref(py);
+        if event_loop.call_method0("is_running")?.is_true()? {
+            // `result` waits with the GIL released while the caller's loop runs it.
+            return asyncio.call_method1("run_coroutine_threadsafe", (value, event_loop))?.call_method0("result");
+        }
+    }
+    if asyncio.call_method0("get_running_loop").is_err() {
+        return asyncio.call_method1("run", (value,));
+    }
+    let coroutine: PyObject = value.into();
+    let result = py.allow_threads(|| {
+        thread::scope(|scope| {
+            let run = || Python::with_gil(|py| Ok(py.import("asyncio")?.call_method1("run", (coroutine,))?.into_py(py)));
+            scope.spawn(run).join()
+        })
+    });
+    match result {
+        Ok(result) => result.map(|value: PyObject| value.into_ref(py)),
+        Err(_) => Err(PyRuntimeError::new_err("the thread running the agent's coroutine panicked")),
+    }
+}
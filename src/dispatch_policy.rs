@@ -0,0 +1,223 @@
+use crate::timing;
+use crate::{AgentResult, OrchestratorError, Subtask};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+/// A pattern over subtasks. It matches a subtask when it matches the text as
+/// planned or its structured `agent:action payload` form, so `llm:` also
+/// catches `query llm ...`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "pattern", rename_all = "snake_case")]
+pub enum SubtaskPattern {
+    Exact(String),
+    Prefix(String),
+    Regex(String),
+}
+
+impl fmt::Display for SubtaskPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SubtaskPattern::Exact(pattern) => write!(f, "exact {:?}", pattern),
+            SubtaskPattern::Prefix(pattern) => write!(f, "prefix {:?}", pattern),
+            SubtaskPattern::Regex(pattern) => write!(f, "regex {:?}", pattern),
+        }
+    }
+}
+
+/// Which planned subtasks `process` may dispatch. Every plan, re-plans
+/// included, is checked before any of its subtasks runs. A blocked subtask
+/// completes as a failed result with a `policy_blocked` error; in `strict` mode
+/// the whole plan is rejected instead, and a re-plan simply not used.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DispatchPolicy {
+    /// When not empty, only subtasks matching one of these run.
+    pub allow: Vec<SubtaskPattern>,
+    /// Subtasks matching any of these never run, allowlisted or not.
+    pub deny: Vec<SubtaskPattern>,
+    /// Subtasks past this many in a plan are blocked.
+    pub max_plan_len: Option<usize>,
+    pub strict: bool,
+}
+
+struct Matcher {
+    pattern: SubtaskPattern,
+    regex: Option<Regex>,
+}
+
+impl Matcher {
+    fn compile(pattern: SubtaskPattern) -> Result<Self, OrchestratorError> {
+        let regex = match &pattern {
+            SubtaskPattern::Regex(source) => Some(Regex::new(source).map_err(|e| {
+                OrchestratorError::InvalidPattern { pattern: source.clone(), message: e.to_string() }
+            })?),
+            _ => None,
+        };
+        Ok(Self { pattern, regex })
+    }
+
+    fn matches(&self, text: &str) -> bool {
+        match (&self.pattern, &self.regex) {
+            (SubtaskPattern::Exact(pattern), _) => text == pattern,
+            (SubtaskPattern::Prefix(pattern), _) => text.starts_with(pattern.as_str()),
+            (SubtaskPattern::Regex(_), Some(regex)) => regex.is_match(text),
+            (SubtaskPattern::Regex(_), None) => false,
+        }
+    }
+}
+
+/// A `DispatchPolicy` with its patterns compiled.
+#[derive(Default)]
+pub(crate) struct CompiledPolicy {
+    policy: DispatchPolicy,
+    allow: Vec<Matcher>,
+    deny: Vec<Matcher>,
+}
+
+impl CompiledPolicy {
+    pub(crate) fn compile(policy: DispatchPolicy) -> Result<Self, OrchestratorError> {
+        let compile = |patterns: &[SubtaskPattern]| patterns.iter().cloned().map(Matcher::compile).collect::<Result<Vec<_>, _>>();
+        Ok(Self { allow: compile(&policy.allow)?, deny: compile(&policy.deny)?, policy })
+    }
+
+    pub(crate) fn policy(&self) -> &DispatchPolicy {
+        &self.policy
+    }
+
+    /// Why `subtask` may not run, when it may not.
+    fn check(&self, subtask: &str) -> Option<String> {
+        let text = subtask.trim();
+        let structured = Subtask::resolve(text).ok().flatten().map(|subtask| subtask.to_string());
+        let forms: Vec<&str> = std::iter::once(text).chain(structured.as_deref()).collect();
+        let matches = |matcher: &&Matcher| forms.iter().any(|form| matcher.matches(form));
+        if let Some(matcher) = self.deny.iter().find(matches) {
+            return Some(format!("denied by {}", matcher.pattern));
+        }
+        if !self.allow.is_empty() && !self.allow.iter().any(|matcher| matches(&matcher)) {
+            return Some("not allowlisted".to_string());
+        }
+        None
+    }
+
+    /// A `PolicyBlocked` error if `subtask` may not run, wherever it is in its plan.
+    pub(crate) fn review_subtask(&self, subtask: &str) -> Option<OrchestratorError> {
+        self.check(subtask).map(|reason| OrchestratorError::PolicyBlocked { subtask: subtask.to_string(), reason })
+    }
+
+    /// A `PolicyBlocked` error for each of `subtasks`, in plan order, that may
+    /// not run; in strict mode, the first such error for the whole plan.
+    pub(crate) fn review(&self, subtasks: &[String]) -> Result<Vec<Option<OrchestratorError>>, OrchestratorError> {
+        let blocked: Vec<Option<OrchestratorError>> = subtasks
+            .iter()
+            .enumerate()
+            .map(|(position, subtask)| match self.policy.max_plan_len {
+                Some(max) if position >= max => Some(OrchestratorError::PolicyBlocked {
+                    subtask: subtask.clone(),
+                    reason: format!("plan of {} subtasks exceeds max_plan_len {}", subtasks.len(), max),
+                }),
+                _ => self.review_subtask(subtask),
+            })
+            .collect();
+        match blocked.iter().flatten().next() {
+            Some(err) if self.policy.strict => Err(err.clone()),
+            _ => Ok(blocked),
+        }
+    }
+}
+
+/// What a blocked subtask completes with: one the policy denied, or one whose
+/// references did not resolve.
+pub(crate) fn blocked_result(err: OrchestratorError) -> AgentResult {
+    let label = match err {
+        OrchestratorError::UnresolvedReference { .. } => "Reference Error",
+        _ => "Policy Blocked",
+    };
+    let mut result = AgentResult::from_error(label, err);
+    timing::record_total(&mut result, Duration::ZERO);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DispatchPolicy, SubtaskPattern};
+    use crate::{AgentResult, CognitiveOrchestrator, MockBackend, OrchestratorError};
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    /// An orchestrator planning "launch" as four "post" subtasks, recording the
+    /// ones dispatched.
+    fn orchestrator(policy: DispatchPolicy) -> (CognitiveOrchestrator, Arc<Mutex<Vec<String>>>) {
+        let dispatched = Arc::new(Mutex::new(vec![]));
+        let seen = dispatched.clone();
+        let mock = MockBackend::new()
+            .plan("launch", ["post teaser", "post rm -rf /", "post launch", "post recap"])
+            .on("post", move |rest| {
+                seen.lock().unwrap().push(rest.to_string());
+                AgentResult { output: rest.to_string(), status: true, metadata: HashMap::new(), error: None }
+            });
+        let orch = CognitiveOrchestrator::builder().backend(Arc::new(mock)).learning(false).build().unwrap();
+        orch.set_dispatch_policy(policy).unwrap();
+        (orch, dispatched)
+    }
+
+    #[test]
+    fn regex_denies_block_only_matching_subtasks() {
+        let deny = vec![SubtaskPattern::Regex(r"rm\s+-rf".to_string())];
+        let (orch, dispatched) = orchestrator(DispatchPolicy { deny, ..DispatchPolicy::default() });
+        let report = orch.process_report("launch".to_string(), "ctx1");
+        assert_eq!(*dispatched.lock().unwrap(), ["teaser", "launch", "recap"]);
+        let blocked = &report.results[1];
+        assert!(!blocked.status && !report.success);
+        assert_eq!(blocked.error.as_ref().map(OrchestratorError::kind), Some("policy_blocked"));
+        assert_eq!(blocked.metadata["error"]["reason"], r#"denied by regex "rm\\s+-rf""#);
+        assert_eq!(orch.get_history("ctx1", None).len(), 4);
+    }
+
+    #[test]
+    fn allowlists_match_the_structured_form() {
+        let allow = vec![SubtaskPattern::Prefix("llm:".to_string()), SubtaskPattern::Exact("post teaser".to_string())];
+        let (orch, dispatched) = orchestrator(DispatchPolicy { allow, ..DispatchPolicy::default() });
+        orch.process("launch".to_string(), "ctx1");
+        assert_eq!(*dispatched.lock().unwrap(), ["teaser"]);
+        let policy = orch.dispatch_policy();
+        let review = super::CompiledPolicy::compile(policy).unwrap().review(&["query llm hi".to_string()]).unwrap();
+        assert_eq!(review, [None]);
+    }
+
+    #[test]
+    fn plans_past_the_length_cap_are_cut_or_rejected() {
+        let (orch, dispatched) = orchestrator(DispatchPolicy { max_plan_len: Some(2), ..DispatchPolicy::default() });
+        let report = orch.process_report("launch".to_string(), "ctx1");
+        assert_eq!(*dispatched.lock().unwrap(), ["teaser", "rm -rf /"]);
+        assert_eq!(report.results.iter().map(|result| result.status).collect::<Vec<_>>(), [true, true, false, false]);
+        assert!(report.results[3].output.contains("exceeds max_plan_len 2"), "{}", report.results[3].output);
+
+        let strict = DispatchPolicy { max_plan_len: Some(2), strict: true, ..DispatchPolicy::default() };
+        let (orch, dispatched) = orchestrator(strict);
+        let report = orch.process_report("launch".to_string(), "ctx1");
+        assert!(dispatched.lock().unwrap().is_empty());
+        assert!(report.results.is_empty() && !report.success);
+        assert!(report.error.unwrap().starts_with("Plan Error: subtask \"post launch\" blocked"));
+    }
+
+    #[test]
+    fn strict_mode_rejects_the_plan_and_policies_reload() {
+        let deny = vec![SubtaskPattern::Prefix("post rm".to_string())];
+        let (orch, dispatched) = orchestrator(DispatchPolicy { deny: deny.clone(), strict: true, ..DispatchPolicy::default() });
+        let output: Vec<String> = serde_json::from_str(&orch.process("launch".to_string(), "ctx1")).unwrap();
+        assert_eq!(output.len(), 1);
+        assert!(output[0].starts_with("Plan Error:"), "{:?}", output);
+        assert!(dispatched.lock().unwrap().is_empty());
+
+        let (orch, dispatched) = orchestrator(DispatchPolicy { deny, strict: true, ..DispatchPolicy::default() });
+        orch.set_dispatch_policy(DispatchPolicy::default()).unwrap();
+        orch.process("launch".to_string(), "ctx1");
+        assert_eq!(dispatched.lock().unwrap().len(), 4);
+
+        let invalid = DispatchPolicy { deny: vec![SubtaskPattern::Regex("(".to_string())], ..DispatchPolicy::default() };
+        assert!(matches!(orch.set_dispatch_policy(invalid), Err(OrchestratorError::InvalidPattern { .. })));
+        assert_eq!(orch.dispatch_policy(), DispatchPolicy::default());
+    }
+}
@@ -0,0 +1,160 @@
+use crate::memory::{hash_embed, DEFAULT_VECTOR_SIZE};
+use crate::OrchestratorError;
+
+/// Turns text into the vectors `CognitiveOrchestrator::remember` stores in
+/// `memory_vectors`. Every vector one embedder returns has the same length.
+pub trait Embedder: Send + Sync {
+    fn embed(&self, text: &str) -> Result<Vec<f64>, OrchestratorError>;
+    fn dim(&self) -> usize;
+}
+
+/// `memory::hash_embed`: deterministic and model-free, so the default, and the
+/// embedder tests swap in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HashEmbedder {
+    dim: usize,
+}
+
+impl Default for HashEmbedder {
+    fn default() -> Self {
+        Self::new(DEFAULT_VECTOR_SIZE)
+    }
+}
+
+impl HashEmbedder {
+    pub fn new(dim: usize) -> Self {
+        Self { dim: dim.max(1) }
+    }
+}
+
+impl Embedder for HashEmbedder {
+    fn embed(&self, text: &str) -> Result<Vec<f64>, OrchestratorError> {
+        Ok(hash_embed(text, self.dim).into_iter().map(f64::from).collect())
+    }
+
+    fn dim(&self) -> usize {
+        self.dim
+    }
+}
+
+#[cfg(feature = "embeddings")]
+pub use candle::CandleEmbedder;
+
+#[cfg(feature = "embeddings")]
+mod candle {
+    use super::Embedder;
+    use crate::OrchestratorError;
+    use candle_core::{DType, Device, Tensor};
+    use candle_nn::VarBuilder;
+    use candle_transformers::models::bert::{BertModel, Config, DTYPE};
+    use std::path::Path;
+    use tokenizers::{Tokenizer, TruncationParams};
+
+    fn failed(err: impl std::fmt::Display) -> OrchestratorError {
+        OrchestratorError::Embedding { message: err.to_string() }
+    }
+
+    /// A local sentence-transformers BERT model (such as all-MiniLM-L6-v2) run on
+    /// the CPU: mean-pooled over the tokens, then L2-normalized.
+    pub struct CandleEmbedder {
+        model: BertModel,
+        tokenizer: Tokenizer,
+        device: Device,
+        dim: usize,
+    }
+
+    impl CandleEmbedder {
+        /// Loads `config.json`, `tokenizer.json` and `model.safetensors` from `dir`.
+        pub fn load(dir: impl AsRef<Path>) -> Result<Self, OrchestratorError> {
+            let dir = dir.as_ref();
+            let read = |name: &str| {
+                let path = dir.join(name);
+                std::fs::read_to_string(&path).map_err(|e| OrchestratorError::io(&path, e))
+            };
+            let config: Config = serde_json::from_str(&read("config.json")?).map_err(OrchestratorError::serialization)?;
+            let mut tokenizer = Tokenizer::from_file(dir.join("tokenizer.json")).map_err(failed)?;
+            tokenizer
+                .with_truncation(Some(TruncationParams { max_length: config.max_position_embeddings, ..Default::default() }))
+                .map_err(failed)?;
+            tokenizer.with_padding(None);
+
+            let device = Device::Cpu;
+            // SAFETY: the weights file is mapped read-only and not modified while loaded.
+            let weights =
+                unsafe { VarBuilder::from_mmaped_safetensors(&[dir.join("model.safetensors")], DTYPE, &device) }.map_err(failed)?;
+            let model = BertModel::load(weights, &config).map_err(failed)?;
+            Ok(Self { model, tokenizer, device, dim: config.hidden_size })
+        }
+
+        fn forward(&self, text: &str) -> candle_core::Result<Vec<f32>> {
+            let encoding = self.tokenizer.encode(text, true).map_err(candle_core::Error::wrap)?;
+            let ids = Tensor::new(encoding.get_ids(), &self.device)?.unsqueeze(0)?;
+            let type_ids = ids.zeros_like()?;
+            let mask = Tensor::new(encoding.get_attention_mask(), &self.device)?.unsqueeze(0)?;
+            let hidden = self.model.forward(&ids, &type_ids, Some(&mask))?;
+
+            let mask = mask.to_dtype(DType::F32)?.unsqueeze(2)?;
+            let pooled = hidden.broadcast_mul(&mask)?.sum(1)?.broadcast_div(&mask.sum(1)?)?;
+            let norm = pooled.sqr()?.sum_keepdim(1)?.sqrt()?;
+            pooled.broadcast_div(&norm)?.squeeze(0)?.to_vec1::<f32>()
+        }
+    }
+
+    impl Embedder for CandleEmbedder {
+        fn embed(&self, text: &str) -> Result<Vec<f64>, OrchestratorError> {
+            Ok(self.forward(text).map_err(failed)?.into_iter().map(f64::from).collect())
+        }
+
+        fn dim(&self) -> usize {
+            self.dim
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CognitiveOrchestrator;
+    use std::sync::Arc;
+
+    /// One axis per keyword, so similarities are exact.
+    struct KeywordEmbedder;
+
+    impl Embedder for KeywordEmbedder {
+        fn embed(&self, text: &str) -> Result<Vec<f64>, OrchestratorError> {
+            Ok(["launch", "teaser", "merch"].iter().map(|word| if text.contains(word) { 1.0 } else { 0.0 }).collect())
+        }
+
+        fn dim(&self) -> usize {
+            3
+        }
+    }
+
+    #[test]
+    fn hash_embedder_is_deterministic_and_normalized() {
+        let embedder = HashEmbedder::new(16);
+        let vec = embedder.embed("grow engagement fast").unwrap();
+        assert_eq!(vec.len(), embedder.dim());
+        assert_eq!(vec, embedder.embed("Grow engagement, fast!").unwrap());
+        assert!((vec.iter().map(|x| x * x).sum::<f64>() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn remember_and_recall_by_text() {
+        let orch = CognitiveOrchestrator::builder().embedder(Arc::new(KeywordEmbedder)).build().unwrap();
+        orch.add_memory("ctx1", vec![1.0, 1.0, 0.0]).unwrap();
+        assert_eq!(orch.remember("ctx1", "launch day").unwrap(), 1);
+        orch.remember("ctx1", "teaser clip").unwrap();
+        orch.remember("ctx1", "merch drop").unwrap();
+
+        let context = orch.get_context("ctx1").unwrap();
+        assert_eq!(context.memory_texts, [None, Some("launch day".to_string()), Some("teaser clip".to_string()), Some("merch drop".to_string())]);
+
+        // The untexted vector would rank first; only remembered texts are returned.
+        let hits = orch.recall_text("ctx1", "launch teaser", 2).unwrap();
+        let texts: Vec<&str> = hits.iter().map(|(text, _)| text.as_str()).collect();
+        assert_eq!(texts, ["launch day", "teaser clip"]);
+        assert!((hits[0].1 - std::f64::consts::FRAC_1_SQRT_2).abs() < 1e-6);
+        assert!(matches!(orch.recall_text("ctx2", "launch", 1), Err(OrchestratorError::MissingContext { .. })));
+    }
+}
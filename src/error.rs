@@ -0,0 +1,250 @@
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Failures carry plain text rather than live `PyErr`s so they can travel inside
+/// `AgentResult`, be cloned, compared, and serialized into metadata.
+#[derive(Debug, Clone, PartialEq, Error, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum OrchestratorError {
+    #[error("failed to import Python module {module}: {message}")]
+    ModuleImport { module: String, message: String, traceback: Option<String> },
+
+    #[error("Python attribute {target} is missing: {message}")]
+    AttributeMissing { target: String, message: String },
+
+    #[error("Python call {target} raised {message}")]
+    CallFailed { target: String, message: String, traceback: Option<String> },
+
+    #[error("could not extract {expected} from {target}: {message}")]
+    Extraction { target: String, expected: String, message: String },
+
+    #[error("unknown subtask: {subtask} (registered agents: {})", agents.join(", "))]
+    UnknownSubtask { subtask: String, agents: Vec<String> },
+
+    #[error("malformed subtask {subtask:?}: {message} (expected agent:action payload)")]
+    MalformedSubtask { subtask: String, message: String },
+
+    #[error("unknown context: {context_id}")]
+    MissingContext { context_id: String },
+
+    #[error("context {context_id} already exists")]
+    ContextExists { context_id: String },
+
+    #[error("subtask {subtask:?} was cancelled")]
+    Cancelled { subtask: String },
+
+    #[error("subtask {subtask:?} timed out after {timeout_ms} ms")]
+    Timeout { subtask: String, timeout_ms: u64 },
+
+    #[error("I/O error on {}: {message}", path.display())]
+    Io { path: PathBuf, message: String },
+
+    #[error("serialization error: {message}")]
+    Serialization { message: String },
+
+    #[error("invalid pattern {pattern:?}: {message}")]
+    InvalidPattern { pattern: String, message: String },
+
+    /// `directive` is the `tracing` filter `init_tracing` was given.
+    #[error("invalid log filter {directive:?}: {message}")]
+    InvalidLogFilter { directive: String, message: String },
+
+    #[error("memory store error: {message}")]
+    Memory { message: String },
+
+    #[error("context store error: {message}")]
+    Store { message: String },
+
+    #[error("embedding failed: {message}")]
+    Embedding { message: String },
+
+    #[error("vector dimension mismatch: expected {expected}, got {actual}")]
+    DimensionMismatch { expected: usize, actual: usize },
+
+    #[error("invalid plan: {message}")]
+    InvalidPlan { message: String },
+
+    #[error("plan has a dependency cycle through: {}", subtasks.join(", "))]
+    PlanCycle { subtasks: Vec<String> },
+
+    #[error("unknown goal {goal_id:?} in context {context_id}")]
+    UnknownGoal { context_id: String, goal_id: String },
+
+    #[error("context {context_id} already has a goal {goal_id:?}")]
+    DuplicateGoal { context_id: String, goal_id: String },
+
+    /// `limit` is the `BudgetUsage` field that reached its ceiling.
+    #[error("context {context_id} has used up its {limit} budget")]
+    BudgetExceeded { context_id: String, limit: String },
+
+    #[error("invalid tenant {tenant:?}: must be non-empty without control characters")]
+    InvalidTenant { tenant: String },
+
+    #[error("the orchestrator is shutting down")]
+    ShuttingDown,
+
+    /// `depth` submissions were already waiting; `shed` when this one was waiting
+    /// too, until a higher priority submission took its place.
+    #[error("submission queue full at depth {depth}{}", shed_note(.shed))]
+    QueueFull { depth: usize, shed: bool },
+
+    #[error("a run with idempotency key {key:?} is still in flight")]
+    IdempotencyPending { key: String },
+
+    #[error("idempotency key {key:?} was already used for another command or context")]
+    IdempotencyMismatch { key: String },
+
+    #[error("circuit open for the {agent} agent; retry in {retry_in_ms} ms")]
+    CircuitOpen { agent: String, retry_in_ms: u64 },
+
+    #[error("subtask {subtask:?} blocked by the dispatch policy: {reason}")]
+    PolicyBlocked { subtask: String, reason: String },
+
+    #[error("no incomplete journaled run {run_id:?}")]
+    UnknownRun { run_id: String },
+
+    /// `command` is the run holding the context.
+    #[error("context {context_id} is busy running {command:?}")]
+    ContextBusy { context_id: String, command: String },
+
+    /// A command or subtask the input policy refuses; `reason` is `nul` or `too_long`.
+    #[error("invalid input ({reason}): {message}")]
+    InvalidInput { reason: String, message: String },
+
+    /// A `{{reference}}` in the subtask that no earlier step of the run wrote.
+    #[error("subtask {subtask:?} references {reference:?}, which no earlier step wrote")]
+    UnresolvedReference { subtask: String, reference: String },
+
+    /// An inline `key=value` argument of the subtask that its agent refuses.
+    #[error("invalid argument {key} in {subtask:?}: {message}")]
+    InvalidArgument { subtask: String, key: String, message: String },
+
+    /// `bytes` is what the context's attributes would have taken as JSON.
+    #[error("attributes of context {context_id} would take {bytes} bytes, over the limit of {limit}")]
+    AttributesTooLarge { context_id: String, bytes: usize, limit: usize },
+}
+
+fn shed_note(shed: &bool) -> &'static str {
+    if *shed {
+        "; shed for a higher priority"
+    } else {
+        ""
+    }
+}
+
+fn traceback_text(py: Python, err: &PyErr) -> Option<String> {
+    err.traceback(py).and_then(|tb| tb.format().ok())
+}
+
+impl OrchestratorError {
+    pub fn import(py: Python, module: &str, err: PyErr) -> Self {
+        OrchestratorError::ModuleImport {
+            module: module.to_string(),
+            message: err.to_string(),
+            traceback: traceback_text(py, &err),
+        }
+    }
+
+    pub fn attribute(target: &str, err: PyErr) -> Self {
+        OrchestratorError::AttributeMissing { target: target.to_string(), message: err.to_string() }
+    }
+
+    pub fn call(py: Python, target: &str, err: PyErr) -> Self {
+        OrchestratorError::CallFailed {
+            target: target.to_string(),
+            message: err.to_string(),
+            traceback: traceback_text(py, &err),
+        }
+    }
+
+    pub fn extraction(target: &str, expected: &str, err: impl std::fmt::Display) -> Self {
+        OrchestratorError::Extraction {
+            target: target.to_string(),
+            expected: expected.to_string(),
+            message: err.to_string(),
+        }
+    }
+
+    pub fn io(path: &Path, err: std::io::Error) -> Self {
+        OrchestratorError::Io { path: path.to_path_buf(), message: err.to_string() }
+    }
+
+    pub fn invalid_input(reason: &str, message: impl Into<String>) -> Self {
+        OrchestratorError::InvalidInput { reason: reason.to_string(), message: message.into() }
+    }
+
+    pub fn invalid_argument(subtask: &str, key: &str, message: impl Into<String>) -> Self {
+        OrchestratorError::InvalidArgument { subtask: subtask.to_string(), key: key.to_string(), message: message.into() }
+    }
+
+    pub fn serialization(err: serde_json::Error) -> Self {
+        OrchestratorError::Serialization { message: err.to_string() }
+    }
+
+    /// Stable snake_case name, matching the serialized `kind` tag.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            OrchestratorError::ModuleImport { .. } => "module_import",
+            OrchestratorError::AttributeMissing { .. } => "attribute_missing",
+            OrchestratorError::CallFailed { .. } => "call_failed",
+            OrchestratorError::Extraction { .. } => "extraction",
+            OrchestratorError::UnknownSubtask { .. } => "unknown_subtask",
+            OrchestratorError::MalformedSubtask { .. } => "malformed_subtask",
+            OrchestratorError::MissingContext { .. } => "missing_context",
+            OrchestratorError::ContextExists { .. } => "context_exists",
+            OrchestratorError::Cancelled { .. } => "cancelled",
+            OrchestratorError::Timeout { .. } => "timeout",
+            OrchestratorError::Io { .. } => "io",
+            OrchestratorError::Serialization { .. } => "serialization",
+            OrchestratorError::InvalidPattern { .. } => "invalid_pattern",
+            OrchestratorError::InvalidLogFilter { .. } => "invalid_log_filter",
+            OrchestratorError::Memory { .. } => "memory",
+            OrchestratorError::Store { .. } => "store",
+            OrchestratorError::Embedding { .. } => "embedding",
+            OrchestratorError::DimensionMismatch { .. } => "dimension_mismatch",
+            OrchestratorError::InvalidPlan { .. } => "invalid_plan",
+            OrchestratorError::PlanCycle { .. } => "plan_cycle",
+            OrchestratorError::UnknownGoal { .. } => "unknown_goal",
+            OrchestratorError::DuplicateGoal { .. } => "duplicate_goal",
+            OrchestratorError::BudgetExceeded { .. } => "budget_exceeded",
+            OrchestratorError::InvalidTenant { .. } => "invalid_tenant",
+            OrchestratorError::ShuttingDown => "shutting_down",
+            OrchestratorError::QueueFull { .. } => "queue_full",
+            OrchestratorError::IdempotencyPending { .. } => "idempotency_pending",
+            OrchestratorError::IdempotencyMismatch { .. } => "idempotency_mismatch",
+            OrchestratorError::CircuitOpen { .. } => "circuit_open",
+            OrchestratorError::PolicyBlocked { .. } => "policy_blocked",
+            OrchestratorError::UnknownRun { .. } => "unknown_run",
+            OrchestratorError::ContextBusy { .. } => "context_busy",
+            OrchestratorError::UnresolvedReference { .. } => "unresolved_reference",
+            OrchestratorError::InvalidInput { .. } => "invalid_input",
+            OrchestratorError::InvalidArgument { .. } => "invalid_argument",
+            OrchestratorError::AttributesTooLarge { .. } => "attributes_too_large",
+        }
+    }
+
+    /// Python traceback text, when the failure came from a raised exception.
+    pub fn traceback(&self) -> Option<&str> {
+        match self {
+            OrchestratorError::ModuleImport { traceback, .. } | OrchestratorError::CallFailed { traceback, .. } => {
+                traceback.as_deref()
+            }
+            _ => None,
+        }
+    }
+
+    /// The planner answered, but with a plan that cannot run (bad ids or a cycle).
+    pub fn is_invalid_plan(&self) -> bool {
+        matches!(self, OrchestratorError::InvalidPlan { .. } | OrchestratorError::PlanCycle { .. })
+    }
+}
+
+impl From<OrchestratorError> for PyErr {
+    fn from(err: OrchestratorError) -> Self {
+        PyRuntimeError::new_err(err.to_string())
+    }
+}
@@ -0,0 +1,131 @@
+use crate::budget::estimate_tokens;
+use crate::history::secs;
+use crate::{Agent, AgentKind, AgentLatency, NodeId, Plan, Subtask};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// One plan node as `CognitiveOrchestrator::estimate` expects it to run.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SubtaskEstimate {
+    pub id: NodeId,
+    pub subtask: String,
+    /// The agent that would handle it; `None` when none accepts it.
+    pub agent: Option<String>,
+    /// Whether its agent is an LLM one, whose calls count against budgets.
+    pub llm: bool,
+    /// Tokens an LLM subtask is expected to spend: those of its payload, and as
+    /// many again for the reply. Zero for other agents.
+    pub llm_tokens: u64,
+    /// The agent's recent wall times; `None` before it has handled a dispatch.
+    pub latency: Option<AgentLatency>,
+}
+
+impl SubtaskEstimate {
+    pub(crate) fn new(id: NodeId, subtask: String, agent: Option<&dyn Agent>, latency: Option<AgentLatency>) -> Self {
+        let llm = agent.and_then(|agent| agent.kind()) == Some(AgentKind::Llm);
+        let llm_tokens = if llm { 2 * estimate_tokens(&Subtask::payload_of(&subtask)) } else { 0 };
+        Self { id, subtask, agent: agent.map(|agent| agent.name().to_string()), llm, llm_tokens, latency }
+    }
+}
+
+/// What running a command would take, worked out without running it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlanEstimate {
+    pub command: String,
+    pub plan: Plan,
+    /// In plan order.
+    pub subtasks: Vec<SubtaskEstimate>,
+    pub llm_calls: u64,
+    pub llm_tokens: u64,
+    /// Wave by wave, the slowest subtask's median wall time, summed. Subtasks
+    /// without a latency count as zero.
+    #[serde(with = "secs")]
+    pub wall_time_p50: Duration,
+    /// The same with each subtask's 95th percentile.
+    #[serde(with = "secs")]
+    pub wall_time_p95: Duration,
+    /// Subtasks whose agent has no latency yet, so the wall times undercount.
+    pub without_latency: usize,
+    /// The budget ceiling the run would go over, by `BudgetUsage` field name.
+    pub budget_exceeded: Option<String>,
+}
+
+impl PlanEstimate {
+    pub(crate) fn new(command: String, plan: Plan, subtasks: Vec<SubtaskEstimate>) -> Self {
+        let by_id: HashMap<NodeId, &SubtaskEstimate> = subtasks.iter().map(|estimate| (estimate.id, estimate)).collect();
+        let wall_time = |percentile: fn(&AgentLatency) -> Duration| -> Duration {
+            plan.waves()
+                .iter()
+                .map(|wave| {
+                    wave.iter()
+                        .filter_map(|node| by_id.get(&node.id)?.latency.as_ref().map(percentile))
+                        .max()
+                        .unwrap_or_default()
+                })
+                .sum()
+        };
+        let (wall_time_p50, wall_time_p95) = (wall_time(|latency| latency.p50), wall_time(|latency| latency.p95));
+        let llm_calls = subtasks.iter().filter(|estimate| estimate.llm).count() as u64;
+        let llm_tokens = subtasks.iter().map(|estimate| estimate.llm_tokens).sum();
+        let without_latency = subtasks.iter().filter(|estimate| estimate.latency.is_none()).count();
+        Self {
+            command,
+            plan,
+            subtasks,
+            llm_calls,
+            llm_tokens,
+            wall_time_p50,
+            wall_time_p95,
+            without_latency,
+            budget_exceeded: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AgentResult, Budget, CognitiveOrchestrator, MockBackend};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    fn orchestrator() -> CognitiveOrchestrator {
+        let mock = MockBackend::new().plan("ask", ["query llm hello world", "post teaser"]).on("post", |rest| {
+            thread::sleep(Duration::from_millis(20));
+            AgentResult { output: rest.to_string(), status: true, metadata: HashMap::new(), error: None }
+        });
+        CognitiveOrchestrator::builder().backend(Arc::new(mock)).learning(false).build().unwrap()
+    }
+
+    #[test]
+    fn estimates_route_and_count_without_running() {
+        let orch = orchestrator();
+        let estimate = orch.estimate("ask".to_string(), "ctx1").unwrap();
+        assert!(orch.get_context("ctx1").is_none());
+        assert!(orch.get_history("ctx1", None).is_empty());
+
+        let agents: Vec<Option<&str>> = estimate.subtasks.iter().map(|subtask| subtask.agent.as_deref()).collect();
+        assert_eq!(agents, [Some("llm"), Some("mock:post")]);
+        // "hello world" is three prompt tokens, doubled for the reply.
+        assert_eq!((estimate.llm_calls, estimate.llm_tokens), (1, 6));
+        assert_eq!((estimate.without_latency, estimate.wall_time_p50), (2, Duration::ZERO));
+        assert_eq!(estimate.budget_exceeded, None);
+    }
+
+    #[test]
+    fn past_dispatches_and_budgets_inform_the_estimate() {
+        let orch = orchestrator();
+        orch.process("ask".to_string(), "ctx1");
+        orch.set_budget("ctx1", Budget { max_tokens: Some(5), ..Budget::default() });
+
+        let estimate = orch.estimate("ask".to_string(), "ctx1").unwrap();
+        let latency = estimate.subtasks[1].latency.unwrap();
+        assert_eq!(latency.samples, 1);
+        assert!(latency.p50 >= Duration::from_millis(20) && latency.p95 == latency.p50);
+        assert!(estimate.wall_time_p50 >= latency.p50);
+        assert_eq!(estimate.without_latency, 0);
+        assert_eq!(estimate.budget_exceeded.as_deref(), Some("tokens"));
+    }
+}
@@ -0,0 +1,214 @@
+use crate::{Attributes, ProcessEvent, ViralMetrics};
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+/// Messages each subscriber can fall behind by before the oldest are dropped.
+pub const DEFAULT_EVENT_CAPACITY: usize = 1024;
+
+/// `metrics_updated`: a context's viral metrics after a dispatch wrote it back.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename = "metrics_updated")]
+pub struct MetricsUpdate {
+    pub viral_metrics: ViralMetrics,
+}
+
+/// `goals_completed`: the last open goal of a context was completed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename = "goals_completed")]
+pub struct GoalsCompleted {
+    pub goal_ids: Vec<String>,
+}
+
+/// `goals_expired`: a sweep found these goals past their deadline.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename = "goals_expired")]
+pub struct GoalsExpired {
+    pub goal_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum BusPayload {
+    Process(ProcessEvent),
+    Metrics(MetricsUpdate),
+    Goals(GoalsCompleted),
+    ExpiredGoals(GoalsExpired),
+}
+
+/// One broadcast message. Serializes flat, e.g.
+/// `{"seq": 7, "context_id": "ctx1", "event": "subtask_finished", ...}`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BusEvent {
+    /// Increases by one per message across the bus, so gaps show drops.
+    pub seq: u64,
+    pub context_id: String,
+    #[serde(flatten)]
+    pub payload: BusPayload,
+    /// The context's attributes as it was published, for webhooks that send some.
+    #[serde(skip)]
+    pub attributes: Attributes,
+}
+
+struct Shared {
+    sender: broadcast::Sender<BusEvent>,
+    /// Held while sending so messages go out in `seq` order.
+    next_seq: Mutex<u64>,
+    dropped: AtomicU64,
+}
+
+/// Fans orchestrator events out to live subscribers. Publishing never waits: a
+/// subscriber more than `capacity` messages behind loses the oldest ones, which
+/// are counted in `dropped`. Clones share the bus.
+#[derive(Clone)]
+pub struct EventBus(Arc<Shared>);
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(DEFAULT_EVENT_CAPACITY)
+    }
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self(Arc::new(Shared { sender, next_seq: Mutex::new(0), dropped: AtomicU64::new(0) }))
+    }
+
+    /// Receives every message published from now on.
+    pub fn subscribe(&self) -> Subscription {
+        Subscription { receiver: self.0.sender.subscribe(), bus: self.0.clone(), dropped: 0 }
+    }
+
+    pub fn subscriber_count(&self) -> usize {
+        self.0.sender.receiver_count()
+    }
+
+    /// Messages lost by lagging subscribers so far, counted as they notice.
+    pub fn dropped(&self) -> u64 {
+        self.0.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Lazily builds the payload, and takes the context's attributes, so there is
+    /// no cost without subscribers.
+    pub(crate) fn publish(
+        &self,
+        context_id: &str,
+        attributes: impl FnOnce() -> Attributes,
+        payload: impl FnOnce() -> BusPayload,
+    ) {
+        if self.0.sender.receiver_count() == 0 {
+            return;
+        }
+        let mut next_seq = self.0.next_seq.lock().unwrap_or_else(|e| e.into_inner());
+        *next_seq += 1;
+        let event =
+            BusEvent { seq: *next_seq, context_id: context_id.to_string(), payload: payload(), attributes: attributes() };
+        // Only fails when the last subscriber left meanwhile.
+        let _ = self.0.sender.send(event);
+    }
+}
+
+/// A subscriber's end of the bus.
+pub struct Subscription {
+    receiver: broadcast::Receiver<BusEvent>,
+    bus: Arc<Shared>,
+    dropped: u64,
+}
+
+impl Subscription {
+    /// The next message, skipping past any that were dropped; `None` once the bus
+    /// is gone.
+    pub async fn recv(&mut self) -> Option<BusEvent> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(event) => return Some(event),
+                Err(broadcast::error::RecvError::Lagged(missed)) => self.lagged(missed),
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    /// `recv` without waiting; `None` when nothing is queued.
+    pub fn try_recv(&mut self) -> Option<BusEvent> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(event) => return Some(event),
+                Err(broadcast::error::TryRecvError::Lagged(missed)) => self.lagged(missed),
+                Err(_) => return None,
+            }
+        }
+    }
+
+    /// Messages this subscriber has lost.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    fn lagged(&mut self, missed: u64) {
+        self.dropped += missed;
+        self.bus.dropped.fetch_add(missed, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AgentResult, CognitiveOrchestrator, MockBackend};
+    use std::collections::HashMap;
+
+    fn metrics() -> BusPayload {
+        BusPayload::Metrics(MetricsUpdate { viral_metrics: ViralMetrics::default() })
+    }
+
+    #[test]
+    fn nothing_is_built_without_subscribers() {
+        let bus = EventBus::new(4);
+        bus.publish("ctx1", || unreachable!(), || unreachable!());
+        let mut subscription = bus.subscribe();
+        bus.publish("ctx1", Attributes::new, metrics);
+        assert_eq!(subscription.try_recv().unwrap().seq, 1);
+    }
+
+    #[test]
+    fn lagging_subscribers_lose_the_oldest_messages() {
+        let bus = EventBus::new(2);
+        let mut slow = bus.subscribe();
+        for _ in 0..5 {
+            bus.publish("ctx1", Attributes::new, metrics);
+        }
+        let seqs: Vec<u64> = std::iter::from_fn(|| slow.try_recv()).map(|event| event.seq).collect();
+        assert_eq!(seqs, [4, 5]);
+        assert_eq!(slow.dropped(), 3);
+        assert_eq!(bus.dropped(), 3);
+    }
+
+    #[test]
+    fn orchestrator_runs_are_published() {
+        let mock = MockBackend::new()
+            .plan("launch", ["post teaser", "post launch"])
+            .on("post teaser", |_| AgentResult {
+                output: "low virality on teaser".to_string(),
+                status: false,
+                metadata: HashMap::new(),
+                error: None,
+            })
+            .on("post", |rest| AgentResult { output: rest.to_string(), status: true, metadata: HashMap::new(), error: None })
+            .replan(["post alt"]);
+        let orch = CognitiveOrchestrator::builder().backend(Arc::new(mock)).build().unwrap();
+        let mut subscription = orch.event_bus().subscribe();
+        orch.process_streaming("launch campaign".to_string(), "ctx1", |_| {});
+
+        let events: Vec<BusEvent> = std::iter::from_fn(|| subscription.try_recv()).collect();
+        assert!(events.iter().all(|event| event.context_id == "ctx1"));
+        assert!(events.windows(2).all(|pair| pair[1].seq == pair[0].seq + 1));
+        let kinds: Vec<String> =
+            events.iter().map(|event| serde_json::to_value(event).unwrap()["event"].as_str().unwrap().to_string()).collect();
+        assert_eq!(kinds.first().map(String::as_str), Some("plan_ready"));
+        assert_eq!(kinds.last().map(String::as_str), Some("completed"));
+        for kind in ["subtask_finished", "replan_triggered", "metrics_updated"] {
+            assert!(kinds.iter().any(|k| k == kind), "{} missing from {:?}", kind, kinds);
+        }
+    }
+}
@@ -0,0 +1,358 @@
+use crate::{AgentResult, Plan};
+use pyo3::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// What kind of failure a failed result is, from its error (or the `error`
+/// metadata of one built in Python) and its output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureClass {
+    /// The simulated virality missed its threshold, or the output says "low virality".
+    LowVirality,
+    /// The LLM agent's call failed.
+    LlmError,
+    Timeout,
+    PolicyBlocked,
+    /// No agent handles the subtask, or it is a malformed structured one.
+    UnknownSubtask,
+    /// A Python agent raised, could not be loaded, or replied with the wrong shape.
+    AgentException,
+    Other,
+}
+
+impl FailureClass {
+    pub const ALL: [FailureClass; 7] = [
+        FailureClass::LowVirality,
+        FailureClass::LlmError,
+        FailureClass::Timeout,
+        FailureClass::PolicyBlocked,
+        FailureClass::UnknownSubtask,
+        FailureClass::AgentException,
+        FailureClass::Other,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FailureClass::LowVirality => "low_virality",
+            FailureClass::LlmError => "llm_error",
+            FailureClass::Timeout => "timeout",
+            FailureClass::PolicyBlocked => "policy_blocked",
+            FailureClass::UnknownSubtask => "unknown_subtask",
+            FailureClass::AgentException => "agent_exception",
+            FailureClass::Other => "other",
+        }
+    }
+
+    /// `None` for a result that succeeded.
+    pub fn of(result: &AgentResult) -> Option<Self> {
+        if result.status {
+            return None;
+        }
+        if result.output.starts_with("LLM Error:") {
+            return Some(FailureClass::LlmError);
+        }
+        let kind = match &result.error {
+            Some(err) => Some(err.kind()),
+            None => result.metadata.get("error").and_then(|error| error.get("kind")).and_then(serde_json::Value::as_str),
+        };
+        let class = match kind {
+            Some("timeout") => FailureClass::Timeout,
+            Some("policy_blocked") => FailureClass::PolicyBlocked,
+            Some("unknown_subtask" | "malformed_subtask") => FailureClass::UnknownSubtask,
+            Some("module_import" | "attribute_missing" | "call_failed" | "extraction") => FailureClass::AgentException,
+            Some(_) => FailureClass::Other,
+            None if low_virality(result) => FailureClass::LowVirality,
+            None => FailureClass::Other,
+        };
+        Some(class)
+    }
+}
+
+impl fmt::Display for FailureClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+fn low_virality(result: &AgentResult) -> bool {
+    let margin = result.metadata.get("virality_margin").and_then(serde_json::Value::as_f64);
+    result.output.contains("low virality") || margin.is_some_and(|margin| margin < 0.0)
+}
+
+/// What `self_debug` does about a failed subtask.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DebugStrategy {
+    /// Runs the debug agent's re-plan in place of the subtask.
+    Replan,
+    /// Runs the subtask again, its text noting the failure, in place of itself.
+    Retry,
+    /// Logs the failure and carries on with the plan.
+    Skip,
+    /// Logs the failure and drops the rest of the plan.
+    Abort,
+}
+
+impl DebugStrategy {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DebugStrategy::Replan => "replan",
+            DebugStrategy::Retry => "retry",
+            DebugStrategy::Skip => "skip",
+            DebugStrategy::Abort => "abort",
+        }
+    }
+}
+
+impl fmt::Display for DebugStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The strategy for each `FailureClass`, under `debug_strategies` in `Config`.
+/// By default only low virality is re-planned; every other failure is skipped.
+/// Re-plans and retries count toward `max_replans` alike.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct DebugStrategies {
+    pub low_virality: DebugStrategy,
+    pub llm_error: DebugStrategy,
+    pub timeout: DebugStrategy,
+    /// Only `skip` or `abort`: a re-plan or retry would route around the policy.
+    pub policy_blocked: DebugStrategy,
+    pub unknown_subtask: DebugStrategy,
+    pub agent_exception: DebugStrategy,
+    pub other: DebugStrategy,
+}
+
+impl Default for DebugStrategies {
+    fn default() -> Self {
+        Self {
+            low_virality: DebugStrategy::Replan,
+            llm_error: DebugStrategy::Skip,
+            timeout: DebugStrategy::Skip,
+            policy_blocked: DebugStrategy::Skip,
+            unknown_subtask: DebugStrategy::Skip,
+            agent_exception: DebugStrategy::Skip,
+            other: DebugStrategy::Skip,
+        }
+    }
+}
+
+impl DebugStrategies {
+    fn slot(&mut self, class: FailureClass) -> &mut DebugStrategy {
+        match class {
+            FailureClass::LowVirality => &mut self.low_virality,
+            FailureClass::LlmError => &mut self.llm_error,
+            FailureClass::Timeout => &mut self.timeout,
+            FailureClass::PolicyBlocked => &mut self.policy_blocked,
+            FailureClass::UnknownSubtask => &mut self.unknown_subtask,
+            FailureClass::AgentException => &mut self.agent_exception,
+            FailureClass::Other => &mut self.other,
+        }
+    }
+
+    pub fn with(mut self, class: FailureClass, strategy: DebugStrategy) -> Self {
+        self.set(class, strategy);
+        self
+    }
+
+    pub fn set(&mut self, class: FailureClass, strategy: DebugStrategy) {
+        *self.slot(class) = strategy;
+    }
+
+    /// The strategy for `class`; a blocked subtask set to be re-planned or
+    /// retried is skipped instead.
+    pub fn strategy(&self, class: FailureClass) -> DebugStrategy {
+        let mut strategies = *self;
+        match *strategies.slot(class) {
+            DebugStrategy::Replan | DebugStrategy::Retry if class == FailureClass::PolicyBlocked => DebugStrategy::Skip,
+            strategy => strategy,
+        }
+    }
+
+    /// The first invalid strategy, by its class, with what is wrong with it.
+    pub fn invalid(&self) -> Option<(&'static str, &'static str)> {
+        matches!(self.policy_blocked, DebugStrategy::Replan | DebugStrategy::Retry)
+            .then_some((FailureClass::PolicyBlocked.name(), "must be skip or abort"))
+    }
+
+    /// The decision for `result` of `subtask`; `None` when it succeeded.
+    pub fn decide(&self, subtask: &str, result: &AgentResult) -> Option<DebugDecision> {
+        let class = FailureClass::of(result)?;
+        Some(DebugDecision { subtask: subtask.to_string(), class, strategy: self.strategy(class) })
+    }
+}
+
+/// How `self_debug` classified a failed subtask and what it did about it, as
+/// `ProcessReport::debug` lists them.
+#[pyclass(module = "sovereign_cli")]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct DebugDecision {
+    #[pyo3(get)]
+    pub subtask: String,
+    pub class: FailureClass,
+    pub strategy: DebugStrategy,
+}
+
+impl DebugDecision {
+    /// The subtask to retry in place of this one.
+    pub(crate) fn retry(&self) -> Plan {
+        let note = format!(" (retry after {})", self.class);
+        let subtask = self.subtask.strip_suffix(&note).unwrap_or(&self.subtask);
+        Plan::from(vec![format!("{}{}", subtask, note)])
+    }
+
+    /// What the debug agent is asked to re-plan.
+    pub(crate) fn replan_request(&self) -> String {
+        match self.class {
+            FailureClass::LowVirality => "replan viral alt strategy".to_string(),
+            class => format!("replan {} alt strategy for {}", class, self.subtask),
+        }
+    }
+}
+
+#[pymethods]
+impl DebugDecision {
+    #[getter(failure_class)]
+    fn py_failure_class(&self) -> &'static str {
+        self.class.name()
+    }
+
+    #[getter(strategy)]
+    fn py_strategy(&self) -> &'static str {
+        self.strategy.name()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "DebugDecision(subtask={:?}, failure_class={:?}, strategy={:?})",
+            self.subtask,
+            self.class.name(),
+            self.strategy.name()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DebugStrategies, DebugStrategy, FailureClass};
+    use crate::{AgentResult, CognitiveOrchestrator, MockBackend, OrchestratorError, ProcessReport};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    fn failed(output: &str) -> AgentResult {
+        AgentResult { output: output.to_string(), status: false, metadata: HashMap::new(), error: None }
+    }
+
+    fn ok(output: &str) -> AgentResult {
+        AgentResult { status: true, ..failed(output) }
+    }
+
+    fn classify(err: OrchestratorError) -> Option<FailureClass> {
+        FailureClass::of(&AgentResult::from_error("Test", err))
+    }
+
+    #[test]
+    fn results_are_classified_from_their_error_and_output() {
+        assert_eq!(
+            classify(OrchestratorError::Timeout { subtask: "post".to_string(), timeout_ms: 5 }),
+            Some(FailureClass::Timeout)
+        );
+        let blocked = OrchestratorError::PolicyBlocked { subtask: "post".to_string(), reason: "denied".to_string() };
+        assert_eq!(classify(blocked), Some(FailureClass::PolicyBlocked));
+        let unknown = OrchestratorError::UnknownSubtask { subtask: "dance".to_string(), agents: vec![] };
+        assert_eq!(classify(unknown), Some(FailureClass::UnknownSubtask));
+        let raised = OrchestratorError::CallFailed { target: "agent.execute".to_string(), message: "boom".to_string(), traceback: None };
+        assert_eq!(classify(raised.clone()), Some(FailureClass::AgentException));
+        assert_eq!(FailureClass::of(&AgentResult::from_error("LLM Error", raised)), Some(FailureClass::LlmError));
+        assert_eq!(classify(OrchestratorError::ShuttingDown), Some(FailureClass::Other));
+
+        assert_eq!(FailureClass::of(&failed("low virality on teaser")), Some(FailureClass::LowVirality));
+        let mut missed = failed("Viral: Virality=0.4000");
+        missed.metadata.insert("virality_margin".to_string(), serde_json::Value::from(-0.4));
+        assert_eq!(FailureClass::of(&missed), Some(FailureClass::LowVirality));
+        assert_eq!(FailureClass::of(&failed("nope")), Some(FailureClass::Other));
+        // A result built in Python keeps only the error's metadata.
+        let mut rebuilt = failed("Timeout: subtask post timed out");
+        rebuilt.metadata.insert("error".to_string(), serde_json::json!({"kind": "timeout"}));
+        assert_eq!(FailureClass::of(&rebuilt), Some(FailureClass::Timeout));
+        assert_eq!(FailureClass::of(&ok("fine")), None);
+    }
+
+    #[test]
+    fn blocked_subtasks_are_never_replanned_or_retried() {
+        let strategies = DebugStrategies::default().with(FailureClass::PolicyBlocked, DebugStrategy::Retry);
+        assert_eq!(strategies.invalid(), Some(("policy_blocked", "must be skip or abort")));
+        assert_eq!(strategies.strategy(FailureClass::PolicyBlocked), DebugStrategy::Skip);
+        let strategies = strategies.with(FailureClass::PolicyBlocked, DebugStrategy::Abort);
+        assert_eq!((strategies.invalid(), strategies.strategy(FailureClass::PolicyBlocked)), (None, DebugStrategy::Abort));
+    }
+
+    /// An orchestrator planning "launch" as "post teaser", "post launch" and
+    /// "post recap", where the teaser fails as `teaser` says.
+    fn orchestrator(teaser: fn(&str) -> AgentResult, strategies: DebugStrategies) -> (CognitiveOrchestrator, Arc<MockBackend>) {
+        let mock = Arc::new(
+            MockBackend::new()
+                .plan("launch", ["post teaser", "post launch", "post recap"])
+                .on("post teaser (retry", |_| ok("teaser retried"))
+                .on("post teaser", teaser)
+                .on("post", |rest| ok(&format!("{} posted", rest)))
+                .replan(["post alt"]),
+        );
+        let orch = CognitiveOrchestrator::builder().backend(mock.clone()).debug_strategies(strategies).learning(false).build().unwrap();
+        (orch, mock)
+    }
+
+    fn timed_out(_: &str) -> AgentResult {
+        AgentResult::from_error("Timeout", OrchestratorError::Timeout { subtask: "post teaser".to_string(), timeout_ms: 5 })
+    }
+
+    fn raised(_: &str) -> AgentResult {
+        let err = OrchestratorError::CallFailed { target: "post.execute".to_string(), message: "boom".to_string(), traceback: None };
+        AgentResult::from_error("post Error", err)
+    }
+
+    #[test]
+    fn each_class_follows_its_configured_strategy() {
+        let decided = |report: &ProcessReport| {
+            report.debug.iter().map(|decision| (decision.class, decision.strategy)).collect::<Vec<_>>()
+        };
+
+        // By default only low virality is re-planned.
+        let (orch, mock) = orchestrator(timed_out, DebugStrategies::default());
+        let report = orch.process_report("launch".to_string(), "ctx1");
+        assert_eq!(decided(&report), [(FailureClass::Timeout, DebugStrategy::Skip)]);
+        assert_eq!(report.outputs()[1..], ["launch posted", "recap posted"]);
+        assert!(mock.replans().is_empty() && report.replans == 0);
+
+        let (orch, mock) = orchestrator(|_| failed("low virality on teaser"), DebugStrategies::default());
+        let report = orch.process_report("launch".to_string(), "ctx1");
+        assert_eq!(decided(&report), [(FailureClass::LowVirality, DebugStrategy::Replan)]);
+        assert_eq!(report.outputs()[1], "alt posted");
+        assert_eq!(mock.replans()[0].0, "replan viral alt strategy");
+
+        let strategies = DebugStrategies::default().with(FailureClass::Timeout, DebugStrategy::Retry);
+        let (orch, _) = orchestrator(timed_out, strategies);
+        let report = orch.process_report("launch".to_string(), "ctx1");
+        assert_eq!(decided(&report), [(FailureClass::Timeout, DebugStrategy::Retry)]);
+        assert_eq!(report.outputs()[1], "teaser retried");
+        assert!(report.success && report.replans == 1);
+
+        let strategies = DebugStrategies::default().with(FailureClass::AgentException, DebugStrategy::Abort);
+        let (orch, _) = orchestrator(raised, strategies);
+        let report = orch.process_report("launch".to_string(), "ctx1");
+        assert_eq!(decided(&report), [(FailureClass::AgentException, DebugStrategy::Abort)]);
+        assert_eq!(report.results.len(), 1);
+        assert!(!report.success);
+
+        let strategies = DebugStrategies::default().with(FailureClass::Other, DebugStrategy::Replan);
+        let (orch, mock) = orchestrator(|_| failed("nope"), strategies);
+        let report = orch.process_report("launch".to_string(), "ctx1");
+        assert_eq!(decided(&report), [(FailureClass::Other, DebugStrategy::Replan)]);
+        assert_eq!(mock.replans()[0].0, "replan other alt strategy for post teaser");
+    }
+}
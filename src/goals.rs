@@ -0,0 +1,369 @@
+use crate::Context;
+use chrono::{DateTime, Utc};
+use pyo3::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GoalPriority {
+    Low,
+    #[default]
+    Normal,
+    /// Active goals at this priority are passed to the planner.
+    High,
+}
+
+impl GoalPriority {
+    pub fn name(self) -> &'static str {
+        match self {
+            GoalPriority::Low => "low",
+            GoalPriority::Normal => "normal",
+            GoalPriority::High => "high",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "low" => Some(GoalPriority::Low),
+            "normal" => Some(GoalPriority::Normal),
+            "high" => Some(GoalPriority::High),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GoalStatus {
+    #[default]
+    Active,
+    Completed,
+    /// Passed its deadline while active; see `CognitiveOrchestrator::sweep_goals`.
+    Expired,
+}
+
+impl GoalStatus {
+    pub fn name(self) -> &'static str {
+        match self {
+            GoalStatus::Active => "active",
+            GoalStatus::Completed => "completed",
+            GoalStatus::Expired => "expired",
+        }
+    }
+}
+
+/// What `CognitiveOrchestrator::sweep_goals` does besides marking an active goal
+/// past its deadline `Expired`, which keeps it out of planner input.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GoalExpiry {
+    #[default]
+    Expire,
+    /// A run that expires goals as it starts has the debug agent re-plan each one,
+    /// as it would a failed `goal:<id>` subtask, and runs the steps before its own plan.
+    Replan,
+}
+
+impl GoalExpiry {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "expire" => Some(GoalExpiry::Expire),
+            "replan" => Some(GoalExpiry::Replan),
+            _ => None,
+        }
+    }
+}
+
+/// One entry of `Context.active_goals`. Contexts saved when goals were plain
+/// strings load each as an active, normal-priority goal whose id is its
+/// description and whose creation time is unknown (the Unix epoch).
+#[pyclass(module = "sovereign_cli")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(from = "GoalRepr")]
+pub struct Goal {
+    #[pyo3(get)]
+    pub id: String,
+    #[pyo3(get)]
+    pub description: String,
+    pub priority: GoalPriority,
+    pub status: GoalStatus,
+    /// Set from the orchestrator's clock by `CognitiveOrchestrator::add_goal`.
+    #[pyo3(get)]
+    pub created_at: DateTime<Utc>,
+    /// When the goal expires if still active.
+    #[pyo3(get)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct GoalFields {
+    id: String,
+    description: String,
+    #[serde(default)]
+    priority: GoalPriority,
+    #[serde(default)]
+    status: GoalStatus,
+    created_at: DateTime<Utc>,
+    #[serde(default)]
+    deadline: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum GoalRepr {
+    Description(String),
+    Goal(GoalFields),
+}
+
+impl From<GoalRepr> for Goal {
+    fn from(repr: GoalRepr) -> Self {
+        match repr {
+            GoalRepr::Description(description) => Goal::from_description(description),
+            GoalRepr::Goal(GoalFields { id, description, priority, status, created_at, deadline }) => {
+                Goal { id, description, priority, status, created_at, deadline }
+            }
+        }
+    }
+}
+
+impl Goal {
+    /// An active, normal-priority goal.
+    pub fn new(id: impl Into<String>, description: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            description: description.into(),
+            priority: GoalPriority::default(),
+            status: GoalStatus::default(),
+            created_at: Utc::now(),
+            deadline: None,
+        }
+    }
+
+    /// A goal known only by its description, as older contexts stored goals.
+    pub(crate) fn from_description(description: String) -> Self {
+        Self {
+            id: description.clone(),
+            description,
+            priority: GoalPriority::default(),
+            status: GoalStatus::default(),
+            created_at: DateTime::UNIX_EPOCH,
+            deadline: None,
+        }
+    }
+
+    pub fn with_priority(mut self, priority: GoalPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn with_deadline(mut self, deadline: DateTime<Utc>) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.status == GoalStatus::Active
+    }
+
+    /// Active with its deadline reached at `now`.
+    pub fn is_overdue(&self, now: DateTime<Utc>) -> bool {
+        self.is_active() && self.deadline.is_some_and(|deadline| deadline <= now)
+    }
+}
+
+#[pymethods]
+impl Goal {
+    /// "low", "normal" or "high".
+    #[getter(priority)]
+    fn py_priority(&self) -> &'static str {
+        self.priority.name()
+    }
+
+    /// "active", "completed" or "expired".
+    #[getter(status)]
+    fn py_status(&self) -> &'static str {
+        self.status.name()
+    }
+
+    fn __eq__(&self, other: &Self) -> bool {
+        self == other
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Goal(id={:?}, priority={}, status={}, description={:?})",
+            self.id,
+            self.priority.name(),
+            self.status.name(),
+            self.description
+        )
+    }
+}
+
+impl Context {
+    pub fn goal(&self, goal_id: &str) -> Option<&Goal> {
+        self.active_goals.iter().find(|goal| goal.id == goal_id)
+    }
+
+    /// Descriptions of the goals not yet completed, in the order they were added.
+    pub fn open_goals(&self) -> impl Iterator<Item = &str> {
+        self.active_goals.iter().filter(|goal| goal.is_active()).map(|goal| goal.description.as_str())
+    }
+
+    /// The context has goals and every one is completed; an expired goal never is.
+    pub fn goals_complete(&self) -> bool {
+        !self.active_goals.is_empty() && self.active_goals.iter().all(|goal| goal.status == GoalStatus::Completed)
+    }
+}
+
+/// `command`, followed by the context's active high-priority goals when it has any,
+/// so the planner can decompose with them in mind.
+pub(crate) fn planner_prompt(command: &str, context: &Context) -> String {
+    let goals: Vec<&Goal> =
+        context.active_goals.iter().filter(|goal| goal.is_active() && goal.priority >= GoalPriority::High).collect();
+    if goals.is_empty() {
+        return command.to_string();
+    }
+    let mut prompt = format!("{}\n\nActive high-priority goals:", command);
+    for goal in goals {
+        prompt.push_str("\n- ");
+        prompt.push_str(&goal.description);
+    }
+    prompt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AgentResult, CognitiveOrchestrator, FixedClock, MockBackend, OrchestratorError};
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn plain_string_goals_still_load() {
+        let goals: Vec<Goal> = serde_json::from_str(
+            r#"["grow engagement", {"id": "g2", "description": "retain", "priority": "high", "created_at": "2025-01-01T00:00:00Z"}]"#,
+        )
+        .unwrap();
+        assert_eq!(goals[0].id, "grow engagement");
+        assert_eq!(goals[0].created_at, DateTime::UNIX_EPOCH);
+        assert!(goals[0].is_active());
+        assert_eq!((goals[1].priority, goals[1].status), (GoalPriority::High, GoalStatus::Active));
+
+        let json = serde_json::to_value(&goals[1]).unwrap();
+        assert_eq!(json["status"], "active");
+        assert_eq!(serde_json::from_value::<Goal>(json).unwrap(), goals[1]);
+    }
+
+    #[test]
+    fn planner_sees_active_high_priority_goals() {
+        let prompts = Arc::new(Mutex::new(vec![]));
+        let seen = prompts.clone();
+        let mock = MockBackend::new()
+            .planner(move |prompt| {
+                seen.lock().unwrap().push(prompt.to_string());
+                Ok(crate::Plan::from(vec!["noop".to_string()]))
+            })
+            .on("noop", |_| AgentResult { output: String::new(), status: true, metadata: HashMap::new(), error: None });
+        let orch = CognitiveOrchestrator::builder().backend(Arc::new(mock)).build().unwrap();
+        orch.add_goal("ctx1", Goal::new("reach", "reach 10k views").with_priority(GoalPriority::High)).unwrap();
+        orch.add_goal("ctx1", Goal::new("tone", "keep it upbeat")).unwrap();
+        orch.add_goal("ctx1", Goal::new("done", "post teaser").with_priority(GoalPriority::High)).unwrap();
+        orch.complete_goal("ctx1", "done").unwrap();
+        orch.process("launch".to_string(), "ctx1");
+
+        assert_eq!(prompts.lock().unwrap().as_slice(), ["launch\n\nActive high-priority goals:\n- reach 10k views"]);
+    }
+
+    #[test]
+    fn completing_the_last_goal_is_published() {
+        let orch = CognitiveOrchestrator::new();
+        let mut subscription = orch.event_bus().subscribe();
+        orch.add_goal("ctx1", Goal::new("a", "first")).unwrap();
+        orch.add_goal("ctx1", Goal::new("b", "second")).unwrap();
+        assert!(matches!(orch.add_goal("ctx1", Goal::new("a", "again")), Err(OrchestratorError::DuplicateGoal { .. })));
+
+        orch.complete_goal("ctx1", "a").unwrap();
+        assert!(!orch.goals_complete("ctx1"));
+        assert!(subscription.try_recv().is_none());
+        assert_eq!(orch.complete_goal("ctx1", "b").unwrap().status, GoalStatus::Completed);
+        assert!(orch.goals_complete("ctx1"));
+
+        let event = serde_json::to_value(subscription.try_recv().unwrap()).unwrap();
+        assert_eq!(event["event"], "goals_completed");
+        assert_eq!(event["goal_ids"], serde_json::json!(["a", "b"]));
+        // Completing again changes nothing and publishes nothing.
+        orch.complete_goal("ctx1", "b").unwrap();
+        assert!(subscription.try_recv().is_none());
+        assert!(matches!(orch.complete_goal("ctx1", "zzz"), Err(OrchestratorError::UnknownGoal { .. })));
+        assert!(matches!(orch.complete_goal("ctx2", "a"), Err(OrchestratorError::MissingContext { .. })));
+    }
+
+    #[test]
+    fn overdue_goals_expire_as_runs_start_and_leave_the_prompt() {
+        let prompts = Arc::new(Mutex::new(vec![]));
+        let seen = prompts.clone();
+        let mock = MockBackend::new()
+            .planner(move |prompt| {
+                seen.lock().unwrap().push(prompt.to_string());
+                Ok(crate::Plan::from(vec!["noop".to_string()]))
+            })
+            .on("noop", |_| AgentResult::ok(""));
+        let clock = Arc::new(FixedClock::new(DateTime::UNIX_EPOCH));
+        let orch = CognitiveOrchestrator::builder().backend(Arc::new(mock)).clock(clock.clone()).build().unwrap();
+        let deadline = DateTime::UNIX_EPOCH + chrono::Duration::days(7);
+        let high = |id: &str, description: &str| Goal::new(id, description).with_priority(GoalPriority::High);
+        orch.add_goal("ctx1", high("launch", "launch this week").with_deadline(deadline)).unwrap();
+        orch.add_goal("ctx1", high("reach", "reach 10k views")).unwrap();
+        let mut subscription = orch.event_bus().subscribe();
+
+        assert!(orch.sweep_goals("ctx1").unwrap().is_empty());
+        clock.advance(chrono::Duration::days(8));
+        orch.process("post".to_string(), "ctx1");
+
+        assert_eq!(prompts.lock().unwrap().as_slice(), ["post\n\nActive high-priority goals:\n- reach 10k views"]);
+        assert_eq!(orch.list_goals("ctx1")[0].status, GoalStatus::Expired);
+        let event = serde_json::to_value(subscription.try_recv().unwrap()).unwrap();
+        assert_eq!((event["event"].as_str(), &event["goal_ids"]), (Some("goals_expired"), &serde_json::json!(["launch"])));
+        // Expired goals are swept once, and never count as complete.
+        assert!(orch.sweep_goals("ctx1").unwrap().is_empty());
+        orch.complete_goal("ctx1", "reach").unwrap();
+        assert!(!orch.goals_complete("ctx1"));
+        assert!(matches!(orch.sweep_goals("ctx2"), Err(OrchestratorError::MissingContext { .. })));
+    }
+
+    #[test]
+    fn the_replan_policy_runs_recovery_steps_before_the_plan() {
+        let mock = Arc::new(
+            MockBackend::new()
+                .plan("post", ["post teaser"])
+                .on("post", |rest| AgentResult::ok(rest))
+                .on("retarget", |rest| AgentResult::ok(rest))
+                .replan(["retarget launch audience"]),
+        );
+        let clock = Arc::new(FixedClock::new(DateTime::UNIX_EPOCH));
+        let orch = CognitiveOrchestrator::builder()
+            .backend(mock.clone())
+            .clock(clock.clone())
+            .goal_expiry(GoalExpiry::Replan)
+            .learning(false)
+            .build()
+            .unwrap();
+        let deadline = DateTime::UNIX_EPOCH + chrono::Duration::hours(1);
+        orch.add_goal("ctx1", Goal::new("launch", "launch this week").with_deadline(deadline)).unwrap();
+        clock.advance(chrono::Duration::hours(2));
+
+        let report = orch.process_report("post teaser".to_string(), "ctx1");
+        assert_eq!(report.plan, ["post teaser"]);
+        assert_eq!(report.subtasks, ["retarget launch audience", "post teaser"]);
+        assert_eq!(report.results[0].get_str("replanned_from"), Some("goal:launch"));
+        assert_eq!(report.debug[0].subtask, "goal:launch");
+        assert!(report.success);
+        assert_eq!(mock.replans().len(), 1);
+
+        // A later run finds nothing new to expire.
+        assert_eq!(orch.process_report("post teaser".to_string(), "ctx1").subtasks, ["post teaser"]);
+    }
+}
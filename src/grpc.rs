@@ -0,0 +1,201 @@
+use crate::{CognitiveOrchestrator, IdempotencyKeys, OrchestratorError, SharedOrchestrator, ShutdownReport, Submission};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::{TcpListenerStream, UnboundedReceiverStream};
+use tokio_stream::{Stream, StreamExt};
+use tonic::{Request, Response, Status};
+
+pub mod convert;
+
+/// Messages and service stubs generated from `proto/ace.proto`.
+pub mod proto {
+    tonic::include_proto!("ace.v1");
+}
+
+pub use proto::orchestrator_client::OrchestratorClient;
+pub use proto::orchestrator_server::OrchestratorServer;
+
+/// `NOT_FOUND` for a missing context or goal, `ALREADY_EXISTS` for a duplicate
+/// goal or context, `INVALID_ARGUMENT` for a rejected plan, malformed input or an unresolved reference, `UNAVAILABLE`
+/// during shutdown or with an agent's circuit open, `ABORTED` and `FAILED_PRECONDITION` for an idempotency key in
+/// flight or reused, `ABORTED` for a busy context, `INTERNAL` otherwise. The message is the error's display text.
+pub fn status(err: OrchestratorError) -> Status {
+    let message = err.to_string();
+    match err {
+        OrchestratorError::MissingContext { .. } | OrchestratorError::UnknownGoal { .. } => Status::not_found(message),
+        OrchestratorError::DuplicateGoal { .. } | OrchestratorError::ContextExists { .. } => Status::already_exists(message),
+        OrchestratorError::IdempotencyPending { .. } | OrchestratorError::ContextBusy { .. } => Status::aborted(message),
+        OrchestratorError::IdempotencyMismatch { .. } => Status::failed_precondition(message),
+        OrchestratorError::BudgetExceeded { .. } => Status::resource_exhausted(message),
+        OrchestratorError::ShuttingDown | OrchestratorError::CircuitOpen { .. } => Status::unavailable(message),
+        OrchestratorError::Extraction { .. }
+        | OrchestratorError::MalformedSubtask { .. }
+        | OrchestratorError::UnresolvedReference { .. }
+        | OrchestratorError::InvalidArgument { .. }
+        | OrchestratorError::InvalidPlan { .. }
+        | OrchestratorError::PlanCycle { .. }
+        | OrchestratorError::InvalidTenant { .. } => Status::invalid_argument(message),
+        _ => Status::internal(message),
+    }
+}
+
+fn timeout(secs: Option<f64>) -> Result<Option<Duration>, Status> {
+    secs.map(|secs| Duration::try_from_secs_f64(secs).map_err(|e| Status::invalid_argument(format!("timeout_secs: {}", e))))
+        .transpose()
+}
+
+fn join_error(err: tokio::task::JoinError) -> Status {
+    Status::internal(err.to_string())
+}
+
+/// The `ace.v1.Orchestrator` service. Like the HTTP server, runs go to blocking
+/// threads and proceed in parallel unless they share a context.
+#[derive(Clone)]
+pub struct OrchestratorService {
+    orchestrator: SharedOrchestrator,
+    /// So a retry can wait for its key's run or be refused.
+    keys: IdempotencyKeys,
+}
+
+impl OrchestratorService {
+    pub fn new(orchestrator: CognitiveOrchestrator) -> Self {
+        let keys = orchestrator.idempotency_keys();
+        Self::shared(Arc::new(orchestrator), keys)
+    }
+
+    /// Serves an orchestrator the caller keeps a handle to, whose `idempotency_keys`
+    /// is `keys`.
+    pub fn shared(orchestrator: SharedOrchestrator, keys: IdempotencyKeys) -> Self {
+        Self { orchestrator, keys }
+    }
+
+    pub fn into_server(self) -> OrchestratorServer<Self> {
+        OrchestratorServer::new(self)
+    }
+}
+
+type EventStream = Pin<Box<dyn Stream<Item = Result<proto::ProcessEvent, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl proto::orchestrator_server::Orchestrator for OrchestratorService {
+    async fn process(&self, request: Request<proto::ProcessRequest>) -> Result<Response<proto::ProcessResponse>, Status> {
+        let request = request.into_inner();
+        let timeout = timeout(request.timeout_secs)?;
+        if let Some(key) = request.idempotency_key.clone() {
+            if request.no_wait {
+                if self.keys.is_in_flight(&key) {
+                    return Err(status(OrchestratorError::IdempotencyPending { key }));
+                }
+            } else {
+                let keys = self.keys.clone();
+                tokio::task::spawn_blocking(move || keys.wait(&key, None)).await.map_err(join_error)?;
+            }
+        }
+        let orchestrator = self.orchestrator.clone();
+        let response = tokio::task::spawn_blocking(move || -> Result<proto::ProcessResponse, Status> {
+            orchestrator.shutdown_handle().check().map_err(status)?;
+            let priority = convert::from_priority(request.priority).map_err(status)?;
+            let submission = Submission { timeout, run_id: request.run_id, priority };
+            if let Some(key) = &request.idempotency_key {
+                let run = orchestrator
+                    .process_idempotent_with(request.command, &request.context_id, key, submission)
+                    .map_err(status)?;
+                return Ok(proto::ProcessResponse {
+                    outputs: serde_json::from_str(&run.output).unwrap_or_else(|_| vec![run.output]),
+                    results: run.results.into_iter().map(Into::into).collect(),
+                    replanned: run.replanned,
+                    replayed: run.replayed,
+                    run_id: run.run_id.unwrap_or_default(),
+                    coalesced: false,
+                });
+            }
+            let mut response = proto::ProcessResponse::default();
+            orchestrator.process_streaming_with(request.command, &request.context_id, submission, |event| match event {
+                crate::ProcessEvent::SubtaskFinished { result, .. } => response.results.push(result.into()),
+                crate::ProcessEvent::Completed { run_id, output, replanned, coalesced, .. } => {
+                    response.outputs = serde_json::from_str(&output).unwrap_or_else(|_| vec![output]);
+                    response.replanned = replanned;
+                    response.coalesced = coalesced;
+                    response.run_id = run_id;
+                }
+                _ => {}
+            });
+            Ok(response)
+        })
+        .await
+        .map_err(join_error)??;
+        Ok(Response::new(response))
+    }
+
+    async fn dispatch(&self, request: Request<proto::DispatchRequest>) -> Result<Response<proto::AgentResult>, Status> {
+        let request = request.into_inner();
+        let orchestrator = self.orchestrator.clone();
+        let result = tokio::task::spawn_blocking(move || orchestrator.dispatch(request.sub_task, &request.context_id))
+            .await
+            .map_err(join_error)?;
+        Ok(Response::new(result.into()))
+    }
+
+    async fn get_context(&self, request: Request<proto::GetContextRequest>) -> Result<Response<proto::Context>, Status> {
+        let context_id = request.into_inner().context_id;
+        match self.orchestrator.get_context(&context_id) {
+            Some(context) => Ok(Response::new(context.into())),
+            None => Err(status(OrchestratorError::MissingContext { context_id })),
+        }
+    }
+
+    type ProcessStreamStream = EventStream;
+
+    async fn process_stream(&self, request: Request<proto::ProcessRequest>) -> Result<Response<EventStream>, Status> {
+        let request = request.into_inner();
+        let timeout = timeout(request.timeout_secs)?;
+        let priority = convert::from_priority(request.priority).map_err(status)?;
+        let orchestrator = self.orchestrator.clone();
+        orchestrator.shutdown_handle().check().map_err(status)?;
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::task::spawn_blocking(move || {
+            // A client that hangs up only stops receiving; the run still finishes.
+            let submission = Submission { timeout, run_id: request.run_id, priority };
+            orchestrator.process_streaming_with(request.command, &request.context_id, submission, |event| {
+                let _ = tx.send(event);
+            });
+        });
+        let events = UnboundedReceiverStream::new(rx).map(|event| Ok(proto::ProcessEvent::from(event)));
+        Ok(Response::new(Box::pin(events)))
+    }
+}
+
+/// Serves the service on `listener` until the task is dropped.
+pub async fn serve(listener: TcpListener, orchestrator: CognitiveOrchestrator) -> Result<(), tonic::transport::Error> {
+    tonic::transport::Server::builder()
+        .add_service(OrchestratorService::new(orchestrator).into_server())
+        .serve_with_incoming(TcpListenerStream::new(listener))
+        .await
+}
+
+/// Serves until `signal` resolves, then refuses new runs with `UNAVAILABLE`, lets
+/// the calls in flight finish within `grace` and shuts the orchestrator down.
+pub async fn serve_until(
+    listener: TcpListener,
+    orchestrator: CognitiveOrchestrator,
+    signal: impl Future<Output = ()> + Send,
+    grace: Duration,
+) -> Result<ShutdownReport, tonic::transport::Error> {
+    let (drain, keys) = (orchestrator.shutdown_handle(), orchestrator.idempotency_keys());
+    let shared = Arc::new(orchestrator);
+    let idle = drain.clone();
+    tonic::transport::Server::builder()
+        .add_service(OrchestratorService::shared(shared.clone(), keys).into_server())
+        .serve_with_incoming_shutdown(TcpListenerStream::new(listener), async move {
+            signal.await;
+            drain.begin(grace);
+        })
+        .await?;
+    // Runs outlive their calls on blocking threads; give them the grace period.
+    let _ = tokio::task::spawn_blocking(move || idle.wait_idle()).await;
+    Ok(shared.shutdown(grace))
+}
@@ -0,0 +1,410 @@
+use super::proto;
+use crate::{AgentResult, Context, Goal, GoalPriority, GoalStatus, MemoryMeta, MemoryVectors, MetricsHistory, OrchestratorError, Priority, ProcessEvent, ViralMetrics, DEFAULT_TENANT};
+use chrono::{DateTime, Utc};
+use prost_types::value::Kind;
+use prost_types::{ListValue, Struct, Timestamp};
+use std::collections::HashMap;
+use std::time::Duration;
+
+fn invalid(target: &str, expected: &str, message: impl Into<String>) -> OrchestratorError {
+    OrchestratorError::Extraction { target: target.to_string(), expected: expected.to_string(), message: message.into() }
+}
+
+fn required<T>(field: Option<T>, target: &str, expected: &str) -> Result<T, OrchestratorError> {
+    field.ok_or_else(|| invalid(target, expected, "missing"))
+}
+
+pub fn to_value(value: serde_json::Value) -> prost_types::Value {
+    let kind = match value {
+        serde_json::Value::Null => Kind::NullValue(0),
+        serde_json::Value::Bool(b) => Kind::BoolValue(b),
+        serde_json::Value::Number(n) => Kind::NumberValue(n.as_f64().unwrap_or(f64::NAN)),
+        serde_json::Value::String(s) => Kind::StringValue(s),
+        serde_json::Value::Array(values) => Kind::ListValue(ListValue { values: values.into_iter().map(to_value).collect() }),
+        serde_json::Value::Object(map) => Kind::StructValue(to_struct(map)),
+    };
+    prost_types::Value { kind: Some(kind) }
+}
+
+/// `Struct` numbers are all doubles, so integral ones come back as integers;
+/// that keeps counters such as `attempts` and error fields like `timeout_ms`
+/// deserializable as integers.
+pub fn from_value(value: prost_types::Value) -> serde_json::Value {
+    match value.kind {
+        None | Some(Kind::NullValue(_)) => serde_json::Value::Null,
+        Some(Kind::BoolValue(b)) => serde_json::Value::Bool(b),
+        Some(Kind::NumberValue(n)) if n.fract() == 0.0 && n.abs() < 2f64.powi(53) => serde_json::Value::from(n as i64),
+        Some(Kind::NumberValue(n)) => serde_json::Value::from(n),
+        Some(Kind::StringValue(s)) => serde_json::Value::String(s),
+        Some(Kind::ListValue(list)) => serde_json::Value::Array(list.values.into_iter().map(from_value).collect()),
+        Some(Kind::StructValue(fields)) => serde_json::Value::Object(from_struct(fields).into_iter().collect()),
+    }
+}
+
+pub fn to_struct(fields: impl IntoIterator<Item = (String, serde_json::Value)>) -> Struct {
+    Struct { fields: fields.into_iter().map(|(key, value)| (key, to_value(value))).collect() }
+}
+
+pub fn from_struct(fields: Struct) -> HashMap<String, serde_json::Value> {
+    fields.fields.into_iter().map(|(key, value)| (key, from_value(value))).collect()
+}
+
+pub fn to_timestamp(time: DateTime<Utc>) -> Timestamp {
+    Timestamp { seconds: time.timestamp(), nanos: time.timestamp_subsec_nanos() as i32 }
+}
+
+pub fn from_timestamp(timestamp: Timestamp) -> Result<DateTime<Utc>, OrchestratorError> {
+    let nanos = u32::try_from(timestamp.nanos).map_err(|e| invalid("Timestamp", "non-negative nanos", e.to_string()))?;
+    DateTime::from_timestamp(timestamp.seconds, nanos)
+        .ok_or_else(|| invalid("Timestamp", "time in range", format!("{}s {}ns", timestamp.seconds, nanos)))
+}
+
+/// A `ProcessRequest.priority`, which must be a known `Priority`.
+pub fn from_priority(priority: i32) -> Result<Priority, OrchestratorError> {
+    match proto::Priority::try_from(priority) {
+        Ok(proto::Priority::Low) => Ok(Priority::Low),
+        Ok(proto::Priority::Normal) => Ok(Priority::Normal),
+        Ok(proto::Priority::High) => Ok(Priority::High),
+        Err(e) => Err(invalid("ProcessRequest.priority", "Priority", e.to_string())),
+    }
+}
+
+impl From<ViralMetrics> for proto::ViralMetrics {
+    fn from(metrics: ViralMetrics) -> Self {
+        Self {
+            virality_score: metrics.virality_score,
+            engagement_nodes: metrics.engagement_nodes as u64,
+            hook_rate: metrics.hook_rate,
+            amplification_factor: metrics.amplification_factor,
+            quantum_fidelity: metrics.quantum_fidelity,
+        }
+    }
+}
+
+impl TryFrom<proto::ViralMetrics> for ViralMetrics {
+    type Error = OrchestratorError;
+
+    fn try_from(metrics: proto::ViralMetrics) -> Result<Self, Self::Error> {
+        Ok(Self {
+            virality_score: metrics.virality_score,
+            engagement_nodes: usize::try_from(metrics.engagement_nodes)
+                .map_err(|e| invalid("ViralMetrics.engagement_nodes", "usize", e.to_string()))?,
+            hook_rate: metrics.hook_rate,
+            amplification_factor: metrics.amplification_factor,
+            quantum_fidelity: metrics.quantum_fidelity,
+        })
+    }
+}
+
+impl From<Goal> for proto::Goal {
+    fn from(goal: Goal) -> Self {
+        let priority = match goal.priority {
+            GoalPriority::Low => proto::GoalPriority::Low,
+            GoalPriority::Normal => proto::GoalPriority::Normal,
+            GoalPriority::High => proto::GoalPriority::High,
+        };
+        let status = match goal.status {
+            GoalStatus::Active => proto::GoalStatus::Active,
+            GoalStatus::Completed => proto::GoalStatus::Completed,
+            GoalStatus::Expired => proto::GoalStatus::Expired,
+        };
+        Self {
+            id: goal.id,
+            description: goal.description,
+            priority: priority.into(),
+            status: status.into(),
+            created_at: Some(to_timestamp(goal.created_at)),
+            deadline: goal.deadline.map(to_timestamp),
+        }
+    }
+}
+
+impl TryFrom<proto::Goal> for Goal {
+    type Error = OrchestratorError;
+
+    fn try_from(goal: proto::Goal) -> Result<Self, Self::Error> {
+        let priority = match proto::GoalPriority::try_from(goal.priority) {
+            Ok(proto::GoalPriority::Low) => GoalPriority::Low,
+            Ok(proto::GoalPriority::Normal) => GoalPriority::Normal,
+            Ok(proto::GoalPriority::High) => GoalPriority::High,
+            Err(e) => return Err(invalid("Goal.priority", "GoalPriority", e.to_string())),
+        };
+        let status = match proto::GoalStatus::try_from(goal.status) {
+            Ok(proto::GoalStatus::Active) => GoalStatus::Active,
+            Ok(proto::GoalStatus::Completed) => GoalStatus::Completed,
+            Ok(proto::GoalStatus::Expired) => GoalStatus::Expired,
+            Err(e) => return Err(invalid("Goal.status", "GoalStatus", e.to_string())),
+        };
+        Ok(Self {
+            id: goal.id,
+            description: goal.description,
+            priority,
+            status,
+            created_at: from_timestamp(required(goal.created_at, "Goal.created_at", "Timestamp")?)?,
+            deadline: goal.deadline.map(from_timestamp).transpose()?,
+        })
+    }
+}
+
+impl From<Context> for proto::Context {
+    fn from(context: Context) -> Self {
+        let memory_vectors = context
+            .memory_vectors
+            .iter()
+            .enumerate()
+            .map(|(idx, values)| {
+                let meta = context.memory_meta.get(idx);
+                proto::MemoryVector {
+                    values,
+                    text: context.memory_text(idx).map(str::to_string),
+                    inserted_at: meta.map(|meta| to_timestamp(meta.inserted_at)),
+                    access_count: meta.map_or(0, |meta| meta.access_count),
+                }
+            })
+            .collect();
+        Self {
+            active_goals: context.open_goals().map(str::to_string).collect(),
+            goals: context.active_goals.into_iter().map(Into::into).collect(),
+            context_id: context.context_id,
+            memory_vectors,
+            viral_metrics: Some(context.viral_metrics.into()),
+            created_at: Some(to_timestamp(context.created_at)),
+            last_accessed: Some(to_timestamp(context.last_accessed)),
+            memory_decay: context.memory_decay,
+        }
+    }
+}
+
+impl TryFrom<proto::Context> for Context {
+    type Error = OrchestratorError;
+
+    fn try_from(context: proto::Context) -> Result<Self, Self::Error> {
+        let created_at = from_timestamp(required(context.created_at, "Context.created_at", "Timestamp")?)?;
+        let mut meta = vec![];
+        let mut timed = false;
+        for vector in &context.memory_vectors {
+            let inserted_at = vector.inserted_at.map(from_timestamp).transpose()?;
+            timed |= inserted_at.is_some();
+            meta.push(MemoryMeta { inserted_at: inserted_at.unwrap_or(created_at), access_count: vector.access_count });
+        }
+        if !timed {
+            meta.clear();
+        }
+        let (vectors, mut texts): (Vec<Vec<f64>>, Vec<Option<String>>) =
+            context.memory_vectors.into_iter().map(|vector| (vector.values, vector.text)).unzip();
+        if texts.iter().all(Option::is_none) {
+            texts.clear();
+        }
+        // Senders that predate `goals` only fill in the descriptions.
+        let active_goals = if context.goals.is_empty() {
+            context.active_goals.into_iter().map(Goal::from_description).collect()
+        } else {
+            context.goals.into_iter().map(Goal::try_from).collect::<Result<_, _>>()?
+        };
+        Ok(Self {
+            context_id: context.context_id,
+            // The gRPC API serves the default tenant.
+            tenant: DEFAULT_TENANT.to_string(),
+            active_goals,
+            memory_vectors: MemoryVectors::try_from(vectors)?,
+            memory_texts: texts,
+            memory_payloads: vec![],
+            memory_meta: meta,
+            memory_decay: context.memory_decay,
+            viral_metrics: required(context.viral_metrics, "Context.viral_metrics", "ViralMetrics")?.try_into()?,
+            // The gRPC `Context` has no viral config, metrics history, lineage, memory payloads,
+            // attributes or extra fields.
+            viral_config: None,
+            propagation: None,
+            metrics_history: MetricsHistory::default(),
+            lineage: None,
+            attributes: Default::default(),
+            created_at,
+            last_accessed: from_timestamp(required(context.last_accessed, "Context.last_accessed", "Timestamp")?)?,
+            extra: Default::default(),
+        })
+    }
+}
+
+impl From<AgentResult> for proto::AgentResult {
+    fn from(result: AgentResult) -> Self {
+        let error = result.error.and_then(|err| match serde_json::to_value(&err) {
+            Ok(serde_json::Value::Object(map)) => Some(to_struct(map)),
+            _ => None,
+        });
+        Self { output: result.output, status: result.status, metadata: Some(to_struct(result.metadata)), error }
+    }
+}
+
+impl TryFrom<proto::AgentResult> for AgentResult {
+    type Error = OrchestratorError;
+
+    fn try_from(result: proto::AgentResult) -> Result<Self, Self::Error> {
+        let error = result
+            .error
+            .map(|fields| {
+                let value = serde_json::Value::Object(from_struct(fields).into_iter().collect());
+                serde_json::from_value::<OrchestratorError>(value)
+                    .map_err(|e| invalid("AgentResult.error", "OrchestratorError", e.to_string()))
+            })
+            .transpose()?;
+        Ok(Self {
+            output: result.output,
+            status: result.status,
+            metadata: result.metadata.map(from_struct).unwrap_or_default(),
+            error,
+        })
+    }
+}
+
+impl From<ProcessEvent> for proto::ProcessEvent {
+    fn from(event: ProcessEvent) -> Self {
+        use proto::process_event::{Completed, Event, PlanReady, ReplanTriggered, SubtaskFinished, SubtaskStarted};
+
+        let (at, run_id) = (event.at().as_secs_f64(), event.run_id().to_string());
+        let event = match event {
+            ProcessEvent::PlanReady { subtasks, .. } => Event::PlanReady(PlanReady { subtasks }),
+            ProcessEvent::SubtaskStarted { subtask, .. } => Event::SubtaskStarted(SubtaskStarted { subtask }),
+            ProcessEvent::SubtaskFinished { result, .. } => {
+                Event::SubtaskFinished(SubtaskFinished { result: Some(result.into()) })
+            }
+            ProcessEvent::ReplanTriggered { subtask, subtasks, .. } => {
+                Event::ReplanTriggered(ReplanTriggered { subtask, subtasks })
+            }
+            ProcessEvent::Completed { output, replanned, coalesced, .. } => Event::Completed(Completed { output, replanned, coalesced }),
+        };
+        Self { at, run_id, event: Some(event) }
+    }
+}
+
+impl TryFrom<proto::ProcessEvent> for ProcessEvent {
+    type Error = OrchestratorError;
+
+    fn try_from(event: proto::ProcessEvent) -> Result<Self, Self::Error> {
+        use proto::process_event::Event;
+
+        let at = Duration::try_from_secs_f64(event.at).map_err(|e| invalid("ProcessEvent.at", "seconds", e.to_string()))?;
+        let run_id = event.run_id;
+        Ok(match required(event.event, "ProcessEvent.event", "event")? {
+            Event::PlanReady(ready) => ProcessEvent::PlanReady { run_id, subtasks: ready.subtasks, at },
+            Event::SubtaskStarted(started) => ProcessEvent::SubtaskStarted { run_id, subtask: started.subtask, at },
+            Event::SubtaskFinished(finished) => ProcessEvent::SubtaskFinished {
+                run_id,
+                result: required(finished.result, "SubtaskFinished.result", "AgentResult")?.try_into()?,
+                at,
+            },
+            Event::ReplanTriggered(replan) => {
+                ProcessEvent::ReplanTriggered { run_id, subtask: replan.subtask, subtasks: replan.subtasks, at }
+            }
+            Event::Completed(completed) => ProcessEvent::Completed {
+                run_id,
+                output: completed.output,
+                replanned: completed.replanned,
+                budget: None,
+                coalesced: completed.coalesced,
+                at,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn round_trip<T, P>(value: T) -> T
+    where
+        T: Clone + TryFrom<P, Error = OrchestratorError>,
+        P: From<T>,
+    {
+        T::try_from(P::from(value)).unwrap()
+    }
+
+    fn context() -> Context {
+        let created = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        let mut context = Context {
+            context_id: "ctx1".to_string(),
+            tenant: DEFAULT_TENANT.to_string(),
+            active_goals: vec![
+                Goal { created_at: created, ..Goal::new("reach", "grow engagement").with_priority(GoalPriority::High) },
+                Goal { status: GoalStatus::Completed, created_at: created, ..Goal::new("teaser", "post teaser") },
+            ],
+            memory_vectors: MemoryVectors::default(),
+            memory_texts: vec![],
+            memory_payloads: vec![],
+            memory_meta: vec![],
+            memory_decay: 0.0,
+            viral_metrics: ViralMetrics { virality_score: 0.87, engagement_nodes: 64, ..ViralMetrics::default() },
+            viral_config: None,
+            propagation: None,
+            metrics_history: MetricsHistory::default(),
+            lineage: None,
+            attributes: Default::default(),
+            created_at: created,
+            last_accessed: created + chrono::Duration::nanoseconds(1_500),
+            extra: Default::default(),
+        };
+        context.add_memory(vec![0.5, -1.0, 2.25]).unwrap();
+        context.add_memory_text(vec![0.0, 1.0, 0.0], "launch teaser".to_string()).unwrap();
+        context
+    }
+
+    #[test]
+    fn context_round_trips() {
+        let context = context();
+        assert_eq!(round_trip::<Context, proto::Context>(context.clone()), context);
+
+        let mut legacy = proto::Context::from(context);
+        assert_eq!(legacy.active_goals, ["grow engagement"]);
+        legacy.goals.clear();
+        let goals = Context::try_from(legacy).unwrap().active_goals;
+        assert_eq!(goals, [Goal::from_description("grow engagement".to_string())]);
+    }
+
+    #[test]
+    fn agent_result_round_trips_metadata_and_error() {
+        let mut metadata = HashMap::new();
+        metadata.insert("attempts".to_string(), serde_json::json!(3));
+        metadata.insert("virality".to_string(), serde_json::json!(0.42));
+        metadata.insert("nested".to_string(), serde_json::json!({ "steps": ["a", "b"], "ok": true, "none": null }));
+        let err = OrchestratorError::Timeout { subtask: "go viral".to_string(), timeout_ms: 1500 };
+        let result = AgentResult { output: "Timeout".to_string(), status: false, metadata, error: Some(err) };
+
+        assert_eq!(round_trip::<AgentResult, proto::AgentResult>(result.clone()), result);
+    }
+
+    #[test]
+    fn process_events_round_trip() {
+        let result = AgentResult { output: "done".to_string(), status: true, metadata: HashMap::new(), error: None };
+        let (at, run_id) = (Duration::from_millis(250), || "run-1".to_string());
+        for event in [
+            ProcessEvent::PlanReady { run_id: run_id(), subtasks: vec!["a".to_string(), "b".to_string()], at },
+            ProcessEvent::SubtaskStarted { run_id: run_id(), subtask: "a".to_string(), at },
+            ProcessEvent::SubtaskFinished { run_id: run_id(), result, at },
+            ProcessEvent::ReplanTriggered { run_id: run_id(), subtask: "a".to_string(), subtasks: vec!["c".to_string()], at },
+            ProcessEvent::Completed {
+                run_id: run_id(),
+                output: "[\"done\"]".to_string(),
+                replanned: vec!["c".to_string()],
+                budget: None,
+                coalesced: true,
+                at,
+            },
+        ] {
+            assert_eq!(round_trip::<ProcessEvent, proto::ProcessEvent>(event.clone()), event);
+        }
+    }
+
+    #[test]
+    fn incomplete_messages_are_rejected() {
+        let mut message = proto::Context::from(context());
+        message.viral_metrics = None;
+        let err = Context::try_from(message).unwrap_err();
+        assert!(matches!(err, OrchestratorError::Extraction { ref target, .. } if target == "Context.viral_metrics"));
+
+        let mut message = proto::Context::from(context());
+        message.memory_vectors[1].values.pop();
+        assert!(matches!(Context::try_from(message), Err(OrchestratorError::DimensionMismatch { .. })));
+    }
+}
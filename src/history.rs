@@ -0,0 +1,196 @@
+use crate::AgentResult;
+use chrono::{DateTime, Utc};
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+/// Records kept per context unless configured otherwise.
+pub const DEFAULT_HISTORY_LIMIT: usize = 1000;
+
+/// `Duration` as fractional seconds, for JSON that reads naturally outside Rust.
+pub(crate) mod secs {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub(crate) fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(duration.as_secs_f64())
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        let secs = f64::deserialize(deserializer)?;
+        Duration::try_from_secs_f64(secs).map_err(serde::de::Error::custom)
+    }
+
+    /// The same for `Option<Duration>`, with `None` as null.
+    pub(crate) mod option {
+        use serde::{Deserialize, Deserializer, Serializer};
+        use std::time::Duration;
+
+        pub(crate) fn serialize<S: Serializer>(duration: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error> {
+            match duration {
+                Some(duration) => serializer.serialize_some(&duration.as_secs_f64()),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+            Option::<f64>::deserialize(deserializer)?
+                .map(|secs| Duration::try_from_secs_f64(secs).map_err(serde::de::Error::custom))
+                .transpose()
+        }
+    }
+}
+
+/// One dispatched subtask: the command it was planned from and what it returned.
+#[pyclass(module = "sovereign_cli")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionRecord {
+    /// When the subtask started.
+    #[pyo3(get)]
+    pub timestamp: DateTime<Utc>,
+    /// The `process` command; the subtask itself for bare `dispatch` calls.
+    #[pyo3(get)]
+    pub command: String,
+    #[pyo3(get)]
+    pub subtask: String,
+    #[pyo3(get)]
+    pub result: AgentResult,
+    #[serde(with = "secs")]
+    pub duration: Duration,
+}
+
+#[pymethods]
+impl ExecutionRecord {
+    /// Wall time in seconds.
+    #[getter(duration)]
+    fn py_duration(&self) -> f64 {
+        self.duration.as_secs_f64()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "ExecutionRecord(subtask={:?}, status={}, duration={:.3})",
+            self.subtask,
+            self.result.status,
+            self.duration.as_secs_f64()
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryFormat {
+    /// One JSON-serialized `ExecutionRecord` per line.
+    JsonLines,
+    /// Header row plus one row per record; the result is flattened to status,
+    /// output and error kind.
+    Csv,
+}
+
+impl HistoryFormat {
+    /// Accepts "jsonl"/"json_lines" and "csv".
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "jsonl" | "json_lines" => Some(HistoryFormat::JsonLines),
+            "csv" => Some(HistoryFormat::Csv),
+            _ => None,
+        }
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Per-context execution records, oldest first, each list capped at `limit`.
+pub struct ExecutionHistory {
+    limit: usize,
+    records: HashMap<String, VecDeque<ExecutionRecord>>,
+}
+
+impl Default for ExecutionHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_LIMIT)
+    }
+}
+
+impl ExecutionHistory {
+    pub fn new(limit: usize) -> Self {
+        Self { limit, records: HashMap::new() }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Changes the cap, dropping the oldest records of any context over it.
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        for records in self.records.values_mut() {
+            let excess = records.len().saturating_sub(limit);
+            records.drain(..excess);
+        }
+        self.records.retain(|_, records| !records.is_empty());
+    }
+
+    pub fn record(&mut self, context_id: &str, record: ExecutionRecord) {
+        if self.limit == 0 {
+            return;
+        }
+        let records = self.records.entry(context_id.to_string()).or_default();
+        if records.len() >= self.limit {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+
+    /// The most recent `limit` records (all when `None`), oldest first.
+    pub fn get(&self, context_id: &str, limit: Option<usize>) -> Vec<ExecutionRecord> {
+        let Some(records) = self.records.get(context_id) else {
+            return vec![];
+        };
+        let skip = limit.map_or(0, |limit| records.len().saturating_sub(limit));
+        records.iter().skip(skip).cloned().collect()
+    }
+
+    /// How many records `context_id` has, without copying them.
+    pub fn count(&self, context_id: &str) -> usize {
+        self.records.get(context_id).map_or(0, VecDeque::len)
+    }
+
+    pub fn remove(&mut self, context_id: &str) {
+        self.records.remove(context_id);
+    }
+
+    pub fn export(&self, context_id: &str, format: HistoryFormat) -> String {
+        let records = self.get(context_id, None);
+        match format {
+            HistoryFormat::JsonLines => records
+                .iter()
+                .filter_map(|record| serde_json::to_string(record).ok())
+                .map(|line| line + "\n")
+                .collect(),
+            HistoryFormat::Csv => {
+                let mut out = String::from("timestamp,command,subtask,status,output,error_kind,duration\n");
+                for record in &records {
+                    let row = [
+                        record.timestamp.to_rfc3339(),
+                        csv_field(&record.command),
+                        csv_field(&record.subtask),
+                        record.result.status.to_string(),
+                        csv_field(&record.result.output),
+                        record.result.error.as_ref().map_or("", |err| err.kind()).to_string(),
+                        record.duration.as_secs_f64().to_string(),
+                    ];
+                    out.push_str(&row.join(","));
+                    out.push('\n');
+                }
+                out
+            }
+        }
+    }
+}
@@ -0,0 +1,258 @@
+use crate::{AgentResult, OrchestratorError};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long a completed idempotency key is replayed unless configured otherwise.
+pub const DEFAULT_IDEMPOTENCY_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// What a run under an idempotency key produced, as replayed to retries.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IdempotentRun {
+    /// What `process` returns.
+    pub output: String,
+    pub results: Vec<AgentResult>,
+    pub replanned: Vec<String>,
+    /// The id of the run that produced this.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    /// Set when this came from an earlier run rather than a dispatch.
+    #[serde(default, skip_serializing)]
+    pub replayed: bool,
+}
+
+/// A completed key as persisted with the contexts; `key` and `context_id` are
+/// namespaced like `Context::key`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompletedKey {
+    pub key: String,
+    pub context_id: String,
+    pub command: String,
+    pub completed_at: DateTime<Utc>,
+    pub run: IdempotentRun,
+}
+
+enum Slot {
+    InFlight { context_id: String, command: String },
+    Completed(CompletedKey),
+}
+
+struct State {
+    ttl: Duration,
+    slots: HashMap<String, Slot>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self { ttl: DEFAULT_IDEMPOTENCY_TTL, slots: HashMap::new() }
+    }
+}
+
+impl State {
+    fn expired(&self, completed: &CompletedKey, now: DateTime<Utc>) -> bool {
+        (now - completed.completed_at).to_std().is_ok_and(|age| age >= self.ttl)
+    }
+
+    fn purge(&mut self, now: DateTime<Utc>) {
+        let expired: Vec<String> = self
+            .slots
+            .iter()
+            .filter(|(_, slot)| matches!(slot, Slot::Completed(completed) if self.expired(completed, now)))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in expired {
+            self.slots.remove(&key);
+        }
+    }
+}
+
+/// Idempotency keys of `process` runs, in flight or completed. Clones share the
+/// keys, so a caller that shares the orchestrator behind a lock can wait for a
+/// key's run without holding the lock.
+#[derive(Clone, Default)]
+pub struct IdempotencyKeys(Arc<(Mutex<State>, Condvar)>);
+
+impl IdempotencyKeys {
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.0 .0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.state().ttl
+    }
+
+    pub(crate) fn set_ttl(&self, ttl: Duration) {
+        self.state().ttl = ttl;
+    }
+
+    pub fn is_in_flight(&self, key: &str) -> bool {
+        matches!(self.state().slots.get(key), Some(Slot::InFlight { .. }))
+    }
+
+    /// Blocks while a run of `key` is in flight, for at most `timeout` when given.
+    /// Returns whether it is no longer in flight.
+    pub fn wait(&self, key: &str, timeout: Option<Duration>) -> bool {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut state = self.state();
+        while matches!(state.slots.get(key), Some(Slot::InFlight { .. })) {
+            state = match deadline {
+                None => self.0 .1.wait(state).unwrap_or_else(|e| e.into_inner()),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return false;
+                    }
+                    self.0 .1.wait_timeout(state, deadline - now).unwrap_or_else(|e| e.into_inner()).0
+                }
+            };
+        }
+        true
+    }
+
+    /// Claims `key` for a run of `command` on `context_id`, or returns the run that
+    /// already completed it. A key in flight, or completed for another command or
+    /// context, is refused.
+    pub(crate) fn begin(
+        &self,
+        key: &str,
+        context_id: &str,
+        command: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Option<IdempotentRun>, OrchestratorError> {
+        let mut state = self.state();
+        let mismatch = || OrchestratorError::IdempotencyMismatch { key: key.to_string() };
+        match state.slots.get(key) {
+            Some(Slot::InFlight { .. }) => return Err(OrchestratorError::IdempotencyPending { key: key.to_string() }),
+            Some(Slot::Completed(completed)) if !state.expired(completed, now) => {
+                if completed.context_id != context_id || completed.command != command {
+                    return Err(mismatch());
+                }
+                return Ok(Some(IdempotentRun { replayed: true, ..completed.run.clone() }));
+            }
+            _ => {}
+        }
+        let slot = Slot::InFlight { context_id: context_id.to_string(), command: command.to_string() };
+        state.slots.insert(key.to_string(), slot);
+        Ok(None)
+    }
+
+    /// Records the run that completed `key`, waking those waiting on it.
+    pub(crate) fn complete(&self, key: &str, run: IdempotentRun, now: DateTime<Utc>) {
+        let mut state = self.state();
+        if let Some(Slot::InFlight { context_id, command }) = state.slots.remove(key) {
+            let completed = CompletedKey { key: key.to_string(), context_id, command, completed_at: now, run };
+            state.slots.insert(key.to_string(), Slot::Completed(completed));
+        }
+        self.0 .1.notify_all();
+    }
+
+    /// Completed keys still within the TTL, sorted by key, for persistence.
+    pub(crate) fn completed(&self, now: DateTime<Utc>) -> Vec<CompletedKey> {
+        let mut state = self.state();
+        state.purge(now);
+        let mut completed: Vec<CompletedKey> = state
+            .slots
+            .values()
+            .filter_map(|slot| match slot {
+                Slot::Completed(completed) => Some(completed.clone()),
+                Slot::InFlight { .. } => None,
+            })
+            .collect();
+        completed.sort_by(|a, b| a.key.cmp(&b.key));
+        completed
+    }
+
+    /// Adds persisted keys, skipping expired ones and any key already known here.
+    pub(crate) fn restore(&self, keys: Vec<CompletedKey>, now: DateTime<Utc>) {
+        let mut state = self.state();
+        for completed in keys {
+            if !state.expired(&completed, now) && !state.slots.contains_key(&completed.key) {
+                state.slots.insert(completed.key.clone(), Slot::Completed(completed));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::streaming::ProcessRun;
+    use crate::{CognitiveOrchestrator, FixedClock, MockBackend, ProcessEvent};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn orchestrator() -> (CognitiveOrchestrator, Arc<FixedClock>, Arc<AtomicUsize>) {
+        let clock = Arc::new(FixedClock::new(DateTime::UNIX_EPOCH));
+        let calls = Arc::new(AtomicUsize::new(0));
+        let seen = calls.clone();
+        let mock = MockBackend::new().plan("ask", ["post one", "post two"]).on("post", move |rest| {
+            seen.fetch_add(1, Ordering::Relaxed);
+            AgentResult { output: rest.to_string(), status: true, metadata: HashMap::new(), error: None }
+        });
+        let orch = CognitiveOrchestrator::builder().backend(Arc::new(mock)).clock(clock.clone()).build().unwrap();
+        (orch, clock, calls)
+    }
+
+    #[test]
+    fn completed_keys_replay_until_their_ttl_runs_out() {
+        let (orch, clock, calls) = orchestrator();
+        let orch = orch.with_idempotency_ttl(Duration::from_secs(60));
+        let first = orch.process_idempotent("ask".to_string(), "ctx1", None, "req-1").unwrap();
+        let again = orch.process_idempotent("ask".to_string(), "ctx1", None, "req-1").unwrap();
+        assert_eq!((first.replayed, again.replayed), (false, true));
+        assert_eq!(again.output, first.output);
+        assert_eq!(again.results, first.results);
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+        assert_eq!(orch.get_history("ctx1", None).len(), 2);
+        assert_eq!(
+            orch.process_idempotent("other".to_string(), "ctx1", None, "req-1"),
+            Err(OrchestratorError::IdempotencyMismatch { key: "req-1".to_string() })
+        );
+
+        // Persisted with the contexts, and replayed after a restart.
+        let path = std::env::temp_dir().join(format!("ace-idempotency-{}.json", std::process::id()));
+        orch.save_contexts(&path).unwrap();
+        let (restored, _, restored_calls) = orchestrator();
+        restored.load_contexts(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert!(restored.process_idempotent("ask".to_string(), "ctx1", None, "req-1").unwrap().replayed);
+        assert_eq!(restored_calls.load(Ordering::Relaxed), 0);
+
+        clock.advance(chrono::Duration::seconds(61));
+        assert!(!orch.process_idempotent("ask".to_string(), "ctx1", None, "req-1").unwrap().replayed);
+        assert_eq!(calls.load(Ordering::Relaxed), 4);
+    }
+
+    #[test]
+    fn duplicates_during_a_run_are_pending_until_it_completes() {
+        let (orch, _, calls) = orchestrator();
+        let keys = orch.idempotency_keys();
+        let shared = Arc::new(orch);
+        let mut run = ProcessRun::new("ask".to_string(), "ctx1".to_string(), None);
+        let mut events = vec![];
+        shared.begin_idempotent(&run, "req-1").unwrap();
+        events.extend(run.next_event(&shared));
+        assert_eq!(
+            shared.process_idempotent("ask".to_string(), "ctx1", None, "req-1"),
+            Err(OrchestratorError::IdempotencyPending { key: "req-1".to_string() })
+        );
+        assert!(keys.is_in_flight("req-1"));
+        assert!(!keys.wait("req-1", Some(Duration::from_millis(10))));
+
+        let waiter = {
+            let (keys, shared) = (keys.clone(), shared.clone());
+            std::thread::spawn(move || {
+                assert!(keys.wait("req-1", None));
+                shared.process_idempotent("ask".to_string(), "ctx1", None, "req-1").unwrap()
+            })
+        };
+        events.extend(std::iter::from_fn(|| run.next_event(&shared)));
+        let finished = shared.complete_idempotent(&run, "req-1", &events);
+        assert!(matches!(events.last(), Some(ProcessEvent::Completed { .. })));
+        let replay = waiter.join().unwrap();
+        assert!(replay.replayed);
+        assert_eq!(replay.output, finished.output);
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+    }
+}
@@ -0,0 +1,167 @@
+use crate::OrchestratorError;
+use serde::{Deserialize, Serialize};
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+
+pub const DEFAULT_MAX_COMMAND_BYTES: usize = 64 * 1024;
+
+/// The longest run id a caller may supply.
+pub const MAX_RUN_ID_BYTES: usize = 128;
+
+/// What `process` and `dispatch` accept as a command or subtask, checked before
+/// anything is planned or handed to Python; the plan validator holds planned
+/// subtasks to it too. A NUL is always refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InputPolicy {
+    /// In UTF-8 bytes, after normalization.
+    pub max_command_bytes: usize,
+    /// Composes text to Unicode NFC, so the same words plan and cache the same.
+    pub normalize_nfc: bool,
+    /// Drops control characters other than tab, line feed and carriage return.
+    pub strip_control: bool,
+}
+
+impl Default for InputPolicy {
+    fn default() -> Self {
+        Self { max_command_bytes: DEFAULT_MAX_COMMAND_BYTES, normalize_nfc: false, strip_control: true }
+    }
+}
+
+impl InputPolicy {
+    /// The first setting that can never pass an input, with why.
+    pub(crate) fn invalid(&self) -> Option<(&'static str, &'static str)> {
+        (self.max_command_bytes == 0).then_some(("max_command_bytes", "must be greater than zero"))
+    }
+
+    /// `text` as it is accepted, or why it is not. Length is checked before
+    /// anything is copied, and again after normalization, which can grow it.
+    pub fn clean(&self, text: &str) -> Result<String, OrchestratorError> {
+        self.check_length(text)?;
+        if text.contains('\0') {
+            return Err(OrchestratorError::invalid_input("nul", "contains a NUL character"));
+        }
+        let mut cleaned = match self.strip_control {
+            true => text.chars().filter(|&c| !c.is_control() || matches!(c, '\t' | '\n' | '\r')).collect(),
+            false => text.to_string(),
+        };
+        if self.normalize_nfc && is_nfc_quick(cleaned.chars()) != IsNormalized::Yes {
+            cleaned = cleaned.nfc().collect();
+            self.check_length(&cleaned)?;
+        }
+        Ok(cleaned)
+    }
+
+    fn check_length(&self, text: &str) -> Result<(), OrchestratorError> {
+        if text.len() > self.max_command_bytes {
+            let message = format!("{} bytes, over max_command_bytes {}", text.len(), self.max_command_bytes);
+            return Err(OrchestratorError::invalid_input("too_long", message));
+        }
+        Ok(())
+    }
+}
+
+/// Refuses a caller's run id that is empty, over `MAX_RUN_ID_BYTES`, or has
+/// anything but ASCII letters, digits, `-`, `_`, `.` and `:`, so it stays safe
+/// to log, journal and put in a URL.
+pub(crate) fn check_run_id(run_id: &str) -> Result<(), OrchestratorError> {
+    if run_id.is_empty() || run_id.len() > MAX_RUN_ID_BYTES {
+        return Err(OrchestratorError::invalid_input("run_id", format!("must be 1 to {} bytes", MAX_RUN_ID_BYTES)));
+    }
+    if !run_id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':')) {
+        return Err(OrchestratorError::invalid_input("run_id", "may only have ASCII letters, digits, '-', '_', '.' and ':'"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AgentResult, CognitiveOrchestrator, FindingKind, MockBackend, Plan};
+    use proptest::prelude::*;
+    use std::sync::{Arc, Mutex};
+
+    const CAP: usize = 4096;
+
+    fn policy() -> InputPolicy {
+        InputPolicy { max_command_bytes: CAP, normalize_nfc: true, strip_control: true }
+    }
+
+    #[test]
+    fn nuls_and_oversized_inputs_are_refused_and_the_rest_cleaned() {
+        let policy = policy();
+        let err = policy.clean("post\0 a").unwrap_err();
+        assert_eq!((err.kind(), err.to_string()), ("invalid_input", "invalid input (nul): contains a NUL character".to_string()));
+        let err = policy.clean(&"x".repeat(CAP + 1)).unwrap_err();
+        assert_eq!(err, OrchestratorError::invalid_input("too_long", "4097 bytes, over max_command_bytes 4096"));
+        assert_eq!(policy.clean("post\u{7}\u{1b}[31m a\tb\r\n").unwrap(), "post[31m a\tb\r\n");
+        // A decomposed é is composed, and a string already in NFC is kept as it is.
+        assert_eq!(policy.clean("cafe\u{301}").unwrap(), "caf\u{e9}");
+        assert_eq!(InputPolicy::default().clean("cafe\u{301}").unwrap(), "cafe\u{301}");
+    }
+
+    #[test]
+    fn process_and_dispatch_refuse_bad_input_before_planning_or_dispatch() {
+        let dispatched = Arc::new(Mutex::new(vec![]));
+        let seen = dispatched.clone();
+        let planned = [" post a\u{0}", "post b\u{7}", "post c"];
+        let planned: Vec<String> = planned.iter().map(|step| step.to_string()).collect();
+        let mock = MockBackend::new().planner(move |_| Ok(Plan::from(planned.clone()))).on("post", move |rest| {
+            seen.lock().unwrap().push(rest.to_string());
+            AgentResult::ok(rest)
+        });
+        let orch = CognitiveOrchestrator::builder()
+            .backend(Arc::new(mock))
+            .input_policy(policy())
+            .learning(false)
+            .build()
+            .unwrap();
+
+        let report = orch.process_report(format!("launch {}", "x".repeat(CAP)), "ctx1");
+        assert!(!report.success && report.results.is_empty());
+        assert!(report.error.as_deref().unwrap().starts_with("Input Error: invalid input (too_long)"), "{:?}", report.error);
+        let result = orch.dispatch("post\0".to_string(), "ctx1");
+        assert_eq!(result.error.map(|err| err.kind()), Some("invalid_input"));
+        assert!(dispatched.lock().unwrap().is_empty());
+
+        // The planner's subtasks are held to the same policy.
+        let report = orch.process_report("launch".to_string(), "ctx1");
+        assert_eq!(*dispatched.lock().unwrap(), ["b", "c"]);
+        assert_eq!(report.plan_findings.iter().map(|finding| finding.kind).collect::<Vec<_>>(), [FindingKind::InvalidInput]);
+        assert!(report.plan_findings[0].dropped);
+    }
+
+    fn text() -> impl Strategy<Value = String> {
+        // Arbitrary characters, heavy on combining marks and controls.
+        let char = prop_oneof![any::<char>(), (0x300u32..0x370).prop_map(|c| char::from_u32(c).unwrap()), Just('\0'), Just('\u{1b}')];
+        prop::collection::vec(char, 0..CAP / 2).prop_map(String::from_iter)
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(128))]
+
+        #[test]
+        fn cleaning_never_panics_and_stays_under_the_cap(text in text()) {
+            match policy().clean(&text) {
+                Ok(cleaned) => {
+                    prop_assert!(cleaned.len() <= CAP);
+                    prop_assert!(!cleaned.contains('\0'));
+                    prop_assert!(cleaned.chars().all(|c| !c.is_control() || matches!(c, '\t' | '\n' | '\r')));
+                }
+                Err(err) => prop_assert_eq!(err.kind(), "invalid_input"),
+            }
+        }
+
+        #[test]
+        fn dispatch_of_arbitrary_input_never_panics(text in text()) {
+            let orch = CognitiveOrchestrator::builder()
+                .backend(Arc::new(MockBackend::new()))
+                .input_policy(policy())
+                .learning(false)
+                .build()
+                .unwrap();
+            let result = orch.dispatch(text, "ctx1");
+            prop_assert!(result.output.len() <= 2 * CAP);
+            prop_assert!(orch.get_history("ctx1", None).len() <= 1);
+        }
+    }
+}
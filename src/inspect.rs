@@ -0,0 +1,98 @@
+use crate::{Context, GoalStatus, RunLockHolder};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// A context at a glance, as `CognitiveOrchestrator::list_contexts` lists it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ContextSummary {
+    pub context_id: String,
+    pub created_at: DateTime<Utc>,
+    pub last_accessed: DateTime<Utc>,
+    /// Completed goals included.
+    pub goal_count: usize,
+    pub memory_count: usize,
+    pub virality_score: f64,
+    /// When the context was deleted, for trashed ones.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+impl ContextSummary {
+    pub(crate) fn of(context: &Context) -> Self {
+        Self {
+            context_id: context.context_id.clone(),
+            created_at: context.created_at,
+            last_accessed: context.last_accessed,
+            goal_count: context.active_goals.len(),
+            memory_count: context.memory_vectors.len(),
+            virality_score: context.viral_metrics.virality_score,
+            deleted_at: None,
+        }
+    }
+}
+
+/// Counts and sizes of what the orchestrator holds for a context, from
+/// `CognitiveOrchestrator::context_stats`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ContextStats {
+    #[serde(flatten)]
+    pub summary: ContextSummary,
+    pub active_goal_count: usize,
+    /// Zero until the first vector is added.
+    pub memory_dim: usize,
+    /// The vectors' components plus the text they were remembered from; payloads are
+    /// not counted.
+    pub memory_bytes: usize,
+    /// Samples in the context's own metrics history.
+    pub metrics_samples: usize,
+    /// Execution records kept for the context.
+    pub history_len: usize,
+    pub snapshot_count: usize,
+    /// The run in progress on the context, if any.
+    pub run_lock: Option<RunLockHolder>,
+}
+
+impl ContextStats {
+    pub(crate) fn new(context: &Context, history_len: usize, snapshot_count: usize, run_lock: Option<RunLockHolder>) -> Self {
+        let texts: usize = context.memory_texts.iter().flatten().map(String::len).sum();
+        Self {
+            summary: ContextSummary::of(context),
+            active_goal_count: context.active_goals.iter().filter(|goal| goal.status == GoalStatus::Active).count(),
+            memory_dim: context.memory_vectors.dim(),
+            memory_bytes: context.memory_vectors.bytes() + texts,
+            metrics_samples: context.metrics_history.len(),
+            history_len,
+            snapshot_count,
+            run_lock,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CognitiveOrchestrator, MockBackend};
+    use std::sync::Arc;
+
+    #[test]
+    fn summaries_follow_remember_and_process() {
+        let mock = MockBackend::new().plan("ask", ["viral sim"]);
+        let orch = CognitiveOrchestrator::builder().backend(Arc::new(mock)).learning(false).build().unwrap().with_auto_snapshots(true);
+        assert!(orch.list_contexts("default").is_empty());
+        assert!(orch.context_stats("ctx1").is_none());
+
+        orch.remember("ctx1", "launch teaser").unwrap();
+        orch.remember("ctx2", "spring sale").unwrap();
+        let summaries = orch.list_contexts("default");
+        assert_eq!(summaries.iter().map(|summary| summary.context_id.as_str()).collect::<Vec<_>>(), ["ctx1", "ctx2"]);
+        assert_eq!((summaries[0].memory_count, summaries[0].virality_score), (1, 0.0));
+
+        orch.process("ask".to_string(), "ctx1");
+        let summary = &orch.list_contexts("default")[0];
+        assert!(summary.last_accessed > summaries[0].last_accessed);
+        let stats = orch.context_stats("ctx1").unwrap();
+        assert_eq!(stats.summary, *summary);
+        assert_eq!((stats.history_len, stats.metrics_samples), (1, 1));
+        assert!(stats.snapshot_count > 0);
+        assert_eq!(stats.memory_bytes, stats.memory_dim * 4 + "launch teaser".len());
+    }
+}
@@ -0,0 +1,555 @@
+use crate::tenant::{context_key, split_key};
+use crate::{AgentResult, OrchestratorError, Plan, PlanNode};
+use chrono::{DateTime, Utc};
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use tracing::warn;
+
+pub const DEFAULT_JOURNAL_MAX_BYTES: u64 = 8 * 1024 * 1024;
+pub const DEFAULT_JOURNAL_KEEP: usize = 3;
+
+/// When the journal asks the OS to put what it appended on disk.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FsyncPolicy {
+    /// After every record: nothing is lost, at a sync per subtask.
+    Always,
+    /// When a plan starts or completes; a crash may lose the results since.
+    #[default]
+    Plan,
+    /// Never; the OS writes back when it pleases.
+    Never,
+}
+
+impl FsyncPolicy {
+    pub fn name(self) -> &'static str {
+        match self {
+            FsyncPolicy::Always => "always",
+            FsyncPolicy::Plan => "plan",
+            FsyncPolicy::Never => "never",
+        }
+    }
+
+    /// `"always"`, `"plan"` or `"never"`.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "always" => Some(FsyncPolicy::Always),
+            "plan" => Some(FsyncPolicy::Plan),
+            "never" => Some(FsyncPolicy::Never),
+            _ => None,
+        }
+    }
+}
+
+/// How `process` journals its runs: one JSON record per line, rotated to
+/// `<path>.1` (and `.2` up to `.<keep>`) once the file would grow past
+/// `max_bytes`. Runs still going are carried over into the new file, so
+/// recovery never needs the rotated ones but reads them anyway.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JournalConfig {
+    pub fsync: FsyncPolicy,
+    pub max_bytes: u64,
+    /// Rotated files kept.
+    pub keep: usize,
+}
+
+impl Default for JournalConfig {
+    fn default() -> Self {
+        Self { fsync: FsyncPolicy::default(), max_bytes: DEFAULT_JOURNAL_MAX_BYTES, keep: DEFAULT_JOURNAL_KEEP }
+    }
+}
+
+impl JournalConfig {
+    /// The first field out of range, with why.
+    pub fn invalid(&self) -> Option<(&'static str, String)> {
+        (self.max_bytes == 0).then(|| ("max_bytes", "must be greater than zero".to_string()))
+    }
+}
+
+/// One line of the journal. A run is its `PlanStarted`, then a `SubtaskDispatched`
+/// as each subtask is handed to its agent and a `SubtaskFinished` when it returns,
+/// then its `PlanCompleted`; `PlanResumed` and `Replanned` replace the subtasks
+/// left to run. Subtasks are kept by wave, in plan order within each.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "record", rename_all = "snake_case")]
+pub enum JournalRecord {
+    PlanStarted {
+        run_id: String,
+        context_id: String,
+        tenant: String,
+        command: String,
+        waves: Vec<Vec<String>>,
+        at: DateTime<Utc>,
+    },
+    PlanResumed {
+        run_id: String,
+        waves: Vec<Vec<String>>,
+        at: DateTime<Utc>,
+    },
+    SubtaskDispatched {
+        run_id: String,
+        subtask: String,
+        at: DateTime<Utc>,
+    },
+    /// `digest` identifies the output without keeping it: FNV-1a, in hex.
+    SubtaskFinished {
+        run_id: String,
+        subtask: String,
+        status: bool,
+        digest: String,
+        at: DateTime<Utc>,
+    },
+    /// `waves` is everything left to run once `self_debug` replaced `subtask`.
+    Replanned {
+        run_id: String,
+        subtask: String,
+        waves: Vec<Vec<String>>,
+        at: DateTime<Utc>,
+    },
+    PlanCompleted {
+        run_id: String,
+        success: bool,
+        /// Set when the run was given up rather than run to its end.
+        #[serde(default)]
+        abandoned: bool,
+        at: DateTime<Utc>,
+    },
+}
+
+impl JournalRecord {
+    pub fn run_id(&self) -> &str {
+        match self {
+            JournalRecord::PlanStarted { run_id, .. }
+            | JournalRecord::PlanResumed { run_id, .. }
+            | JournalRecord::SubtaskDispatched { run_id, .. }
+            | JournalRecord::SubtaskFinished { run_id, .. }
+            | JournalRecord::Replanned { run_id, .. }
+            | JournalRecord::PlanCompleted { run_id, .. } => run_id,
+        }
+    }
+
+    pub(crate) fn finished(run_id: &str, subtask: &str, result: &AgentResult, at: DateTime<Utc>) -> Self {
+        JournalRecord::SubtaskFinished {
+            run_id: run_id.to_string(),
+            subtask: subtask.to_string(),
+            status: result.status,
+            digest: digest(&result.output),
+            at,
+        }
+    }
+}
+
+fn digest(output: &str) -> String {
+    let hash = output.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x100_0000_01b3));
+    format!("{:016x}", hash)
+}
+
+/// A journaled run that never completed, as `recover_incomplete_runs` finds it.
+#[pyclass(module = "sovereign_cli", get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IncompleteRun {
+    pub run_id: String,
+    pub context_id: String,
+    pub tenant: String,
+    pub command: String,
+    pub started_at: DateTime<Utc>,
+    /// Subtasks that returned, in the order they did, and those of them that failed.
+    pub finished: Vec<String>,
+    pub failed: Vec<String>,
+    /// Subtasks handed to their agent that never returned; they may have had
+    /// effects before the process died.
+    pub in_flight: Vec<String>,
+    /// Every subtask left to run, in execution order, `in_flight` ones included.
+    pub unfinished: Vec<String>,
+    #[serde(skip)]
+    waves: Vec<Vec<String>>,
+}
+
+impl IncompleteRun {
+    fn started(run_id: String, context_id: String, tenant: String, command: String, waves: Vec<Vec<String>>, at: DateTime<Utc>) -> Self {
+        let mut run = Self {
+            run_id,
+            context_id,
+            tenant,
+            command,
+            started_at: at,
+            finished: vec![],
+            failed: vec![],
+            in_flight: vec![],
+            unfinished: vec![],
+            waves: vec![],
+        };
+        run.set_waves(waves);
+        run
+    }
+
+    fn set_waves(&mut self, waves: Vec<Vec<String>>) {
+        self.waves = waves.into_iter().filter(|wave| !wave.is_empty()).collect();
+        self.unfinished = self.waves.iter().flatten().cloned().collect();
+    }
+
+    /// The orchestrator's key for the run's context.
+    pub fn key(&self) -> String {
+        context_key(&self.tenant, &self.context_id)
+    }
+
+    /// What is left to run, each wave depending on the one before it.
+    pub fn plan(&self) -> Plan {
+        let mut nodes: Vec<PlanNode> = vec![];
+        let mut previous: Vec<usize> = vec![];
+        for wave in &self.waves {
+            let first = nodes.len();
+            nodes.extend(wave.iter().enumerate().map(|(offset, subtask)| PlanNode {
+                id: first + offset,
+                subtask: subtask.clone(),
+                depends_on: previous.clone(),
+            }));
+            previous = (first..nodes.len()).collect();
+        }
+        Plan::new(nodes).unwrap_or_default()
+    }
+
+    fn apply(&mut self, record: JournalRecord) {
+        match record {
+            JournalRecord::PlanResumed { waves, .. } | JournalRecord::Replanned { waves, .. } => {
+                self.set_waves(waves);
+                self.in_flight.clear();
+            }
+            JournalRecord::SubtaskDispatched { subtask, .. } => self.in_flight.push(subtask),
+            JournalRecord::SubtaskFinished { subtask, status, .. } => {
+                if let Some(index) = self.in_flight.iter().position(|running| *running == subtask) {
+                    self.in_flight.remove(index);
+                }
+                for wave in &mut self.waves {
+                    if let Some(index) = wave.iter().position(|planned| *planned == subtask) {
+                        wave.remove(index);
+                        break;
+                    }
+                }
+                let waves = std::mem::take(&mut self.waves);
+                self.set_waves(waves);
+                if !status {
+                    self.failed.push(subtask.clone());
+                }
+                self.finished.push(subtask);
+            }
+            JournalRecord::PlanStarted { .. } | JournalRecord::PlanCompleted { .. } => {}
+        }
+    }
+}
+
+/// Runs not completed by the end of `records`, oldest first. A run started
+/// again, as carrying it over into a rotated journal does, starts afresh.
+fn incomplete(records: impl IntoIterator<Item = JournalRecord>) -> Vec<IncompleteRun> {
+    let mut runs: Vec<IncompleteRun> = vec![];
+    for record in records {
+        let index = runs.iter().position(|run| run.run_id == record.run_id());
+        match (record, index) {
+            (JournalRecord::PlanStarted { run_id, context_id, tenant, command, waves, at }, index) => {
+                if let Some(index) = index {
+                    runs.remove(index);
+                }
+                runs.push(IncompleteRun::started(run_id, context_id, tenant, command, waves, at));
+            }
+            (JournalRecord::PlanCompleted { .. }, Some(index)) => {
+                runs.remove(index);
+            }
+            (record, Some(index)) => runs[index].apply(record),
+            (_, None) => {}
+        }
+    }
+    runs
+}
+
+/// The records a run still going has journaled, to carry over on rotation.
+struct OpenRun {
+    run_id: String,
+    records: Vec<JournalRecord>,
+}
+
+struct JournalFile {
+    file: File,
+    len: u64,
+    open: Vec<OpenRun>,
+}
+
+/// An append-only record of what `process` dispatched, so runs a crash cut short
+/// can be found and resumed by the next process; see `JournalConfig`.
+pub struct Journal {
+    path: PathBuf,
+    fsync: FsyncPolicy,
+    max_bytes: u64,
+    keep: usize,
+    /// The runs open when the journal was opened, which earlier processes left.
+    inherited: Vec<String>,
+    file: Mutex<JournalFile>,
+}
+
+fn io_error(path: &Path) -> impl FnOnce(std::io::Error) -> OrchestratorError + '_ {
+    move |err| OrchestratorError::io(path, err)
+}
+
+impl Journal {
+    /// Opens the journal at `path`, creating it if needed, and reads back the
+    /// runs it left incomplete.
+    pub fn open(path: impl Into<PathBuf>, config: JournalConfig) -> Result<Self, OrchestratorError> {
+        let path = path.into();
+        let mut records = vec![];
+        for file in (1..=config.keep).rev().map(|n| rotated(&path, n)).chain([path.clone()]) {
+            records.extend(read_records(&file)?);
+        }
+        let open: Vec<OpenRun> = incomplete(records.iter().cloned())
+            .into_iter()
+            .map(|run| OpenRun {
+                records: records.iter().filter(|record| record.run_id() == run.run_id).cloned().collect(),
+                run_id: run.run_id,
+            })
+            .collect();
+        let file = OpenOptions::new().create(true).append(true).open(&path).map_err(io_error(&path))?;
+        let len = file.metadata().map_err(io_error(&path))?.len();
+        Ok(Self {
+            path,
+            fsync: config.fsync,
+            max_bytes: config.max_bytes,
+            keep: config.keep,
+            inherited: open.iter().map(|run| run.run_id.clone()).collect(),
+            file: Mutex::new(JournalFile { file, len, open }),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn file(&self) -> MutexGuard<'_, JournalFile> {
+        self.file.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Appends `record`, rotating first when it would take the file past `max_bytes`.
+    pub fn append(&self, record: JournalRecord) -> Result<(), OrchestratorError> {
+        let mut line = serde_json::to_vec(&record).map_err(OrchestratorError::serialization)?;
+        line.push(b'\n');
+        let mut file = self.file();
+        if file.len > 0 && file.len + line.len() as u64 > self.max_bytes {
+            self.rotate(&mut file)?;
+        }
+        file.file.write_all(&line).map_err(io_error(&self.path))?;
+        file.len += line.len() as u64;
+        let boundary = matches!(record, JournalRecord::PlanStarted { .. } | JournalRecord::PlanCompleted { .. });
+        if self.fsync == FsyncPolicy::Always || (self.fsync == FsyncPolicy::Plan && boundary) {
+            file.file.sync_data().map_err(io_error(&self.path))?;
+        }
+
+        let index = file.open.iter().position(|run| run.run_id == record.run_id());
+        match (&record, index) {
+            (JournalRecord::PlanStarted { run_id, .. }, None) => {
+                file.open.push(OpenRun { run_id: run_id.clone(), records: vec![record] });
+            }
+            (JournalRecord::PlanCompleted { .. }, Some(index)) => {
+                file.open.remove(index);
+            }
+            (_, Some(index)) => file.open[index].records.push(record),
+            (_, None) => {}
+        }
+        Ok(())
+    }
+
+    /// Shifts the rotated files up one, dropping the oldest past `keep`, and
+    /// starts a new file with the runs still open.
+    fn rotate(&self, file: &mut JournalFile) -> Result<(), OrchestratorError> {
+        file.file.sync_all().map_err(io_error(&self.path))?;
+        let carried: Vec<u8> = file
+            .open
+            .iter()
+            .flat_map(|run| &run.records)
+            .filter_map(|record| serde_json::to_vec(record).ok())
+            .flat_map(|mut line| {
+                line.push(b'\n');
+                line
+            })
+            .collect();
+        // Written beside the journal first, so a crash mid-rotation loses nothing.
+        let tmp = self.path.with_extension("rotating");
+        fs::write(&tmp, &carried).map_err(io_error(&tmp))?;
+        File::open(&tmp).and_then(|tmp| tmp.sync_all()).map_err(io_error(&tmp))?;
+        if self.keep == 0 {
+            fs::remove_file(&self.path).map_err(io_error(&self.path))?;
+        } else {
+            for n in (1..self.keep).rev() {
+                let from = rotated(&self.path, n);
+                if from.exists() {
+                    fs::rename(&from, rotated(&self.path, n + 1)).map_err(io_error(&from))?;
+                }
+            }
+            fs::rename(&self.path, rotated(&self.path, 1)).map_err(io_error(&self.path))?;
+        }
+        fs::rename(&tmp, &self.path).map_err(io_error(&self.path))?;
+        file.file = OpenOptions::new().append(true).open(&self.path).map_err(io_error(&self.path))?;
+        file.len = carried.len() as u64;
+        Ok(())
+    }
+
+    /// `append`, logging rather than failing the run when the journal cannot be written.
+    pub(crate) fn record(&self, record: JournalRecord) {
+        if let Err(err) = self.append(record) {
+            warn!("Journal write failed: {}", err);
+        }
+    }
+
+    /// The runs earlier processes journaled here that have still not completed,
+    /// oldest first; runs started since the journal was opened are not among them.
+    pub fn incomplete_runs(&self) -> Vec<IncompleteRun> {
+        let file = self.file();
+        let inherited = file.open.iter().filter(|run| self.inherited.contains(&run.run_id));
+        incomplete(inherited.flat_map(|run| run.records.clone()).collect::<Vec<_>>())
+    }
+
+    pub fn incomplete_run(&self, run_id: &str) -> Option<IncompleteRun> {
+        self.incomplete_runs().into_iter().find(|run| run.run_id == run_id)
+    }
+}
+
+/// `<path>.<n>`.
+fn rotated(path: &Path, n: usize) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(format!(".{}", n));
+    PathBuf::from(name)
+}
+
+/// Every record of the file at `path`, none if there is no such file. A line
+/// that does not parse, such as one a crash cut short, is skipped.
+fn read_records(path: &Path) -> Result<Vec<JournalRecord>, OrchestratorError> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(OrchestratorError::io(path, err)),
+    };
+    let mut records = vec![];
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(io_error(path))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(record) => records.push(record),
+            Err(err) => warn!("Skipping unreadable journal line in {}: {}", path.display(), err),
+        }
+    }
+    Ok(records)
+}
+
+/// The record starting a run of `command` on the context under `key`.
+pub(crate) fn plan_started(run_id: &str, key: &str, command: &str, waves: Vec<Vec<String>>, at: DateTime<Utc>) -> JournalRecord {
+    let (tenant, context_id) = split_key(key);
+    JournalRecord::PlanStarted {
+        run_id: run_id.to_string(),
+        context_id: context_id.to_string(),
+        tenant: tenant.to_string(),
+        command: command.to_string(),
+        waves,
+        at,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{rotated, FsyncPolicy, Journal, JournalConfig, JournalRecord};
+    use crate::streaming::ProcessRun;
+    use crate::{AgentResult, CognitiveOrchestrator, MockBackend, OrchestratorError, ProcessEvent};
+    use chrono::Utc;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    fn path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("sovereign-journal-{}-{}.jsonl", name, std::process::id()));
+        for file in [path.clone(), rotated(&path, 1), rotated(&path, 2)] {
+            let _ = std::fs::remove_file(file);
+        }
+        path
+    }
+
+    const CONFIG: JournalConfig = JournalConfig { fsync: FsyncPolicy::Always, max_bytes: super::DEFAULT_JOURNAL_MAX_BYTES, keep: 1 };
+
+    fn orchestrator(path: &PathBuf) -> CognitiveOrchestrator {
+        let mock = MockBackend::new().plan("launch", ["post teaser", "post launch", "post recap"]).on("post", |rest| AgentResult {
+            output: rest.to_string(),
+            status: true,
+            metadata: HashMap::new(),
+            error: None,
+        });
+        let orch = CognitiveOrchestrator::builder().backend(Arc::new(mock)).learning(false).build().unwrap();
+        orch.with_journal(Journal::open(path, CONFIG).unwrap())
+    }
+
+    #[test]
+    fn runs_cut_short_are_recovered_and_resumed() {
+        let path = path("crash");
+        let orch = orchestrator(&path);
+        let mut run = ProcessRun::new("launch".to_string(), "ctx1".to_string(), None);
+        while let Some(event) = run.next_event(&orch) {
+            if matches!(event, ProcessEvent::SubtaskFinished { .. }) {
+                break;
+            }
+        }
+        // Dropped mid-plan, without completing or abandoning, as a crash leaves it.
+        drop((run, orch));
+
+        let orch = orchestrator(&path);
+        let runs = orch.recover_incomplete_runs();
+        assert_eq!(runs.len(), 1);
+        let run = &runs[0];
+        assert_eq!((run.context_id.as_str(), run.command.as_str()), ("ctx1", "launch"));
+        assert_eq!(run.finished, ["post teaser"]);
+        assert_eq!(run.unfinished, ["post launch", "post recap"]);
+
+        let report = orch.resume_run(&run.run_id).unwrap();
+        assert!(report.success);
+        assert_eq!(report.subtasks, ["post launch", "post recap"]);
+        assert!(orch.recover_incomplete_runs().is_empty());
+        assert!(matches!(orch.resume_run(&run.run_id), Err(OrchestratorError::UnknownRun { .. })));
+
+        // A completed run leaves nothing to recover, nor does an abandoned one.
+        orch.process("launch".to_string(), "ctx2");
+        assert!(orch.recover_incomplete_runs().is_empty());
+        let journal = Journal::open(&path, CONFIG).unwrap();
+        journal.append(super::plan_started("stale", "ctx3", "launch", vec![vec!["post teaser".to_string()]], Utc::now())).unwrap();
+        drop(journal);
+        let orch = orchestrator(&path);
+        orch.abandon_run("stale").unwrap();
+        assert!(orch.recover_incomplete_runs().is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rotation_carries_open_runs_into_the_new_file() {
+        let path = path("rotate");
+        let config = JournalConfig { max_bytes: 600, ..CONFIG };
+        let journal = Journal::open(&path, config).unwrap();
+        let waves = vec![vec!["post teaser".to_string(), "post recap".to_string()]];
+        journal.append(super::plan_started("open", "ctx1", "launch", waves, Utc::now())).unwrap();
+        let dispatched = JournalRecord::SubtaskDispatched { run_id: "open".to_string(), subtask: "post teaser".to_string(), at: Utc::now() };
+        journal.append(dispatched).unwrap();
+        for n in 0..20 {
+            let run_id = format!("done{}", n);
+            journal.append(super::plan_started(&run_id, "ctx1", "launch", vec![], Utc::now())).unwrap();
+            journal.append(JournalRecord::PlanCompleted { run_id, success: true, abandoned: false, at: Utc::now() }).unwrap();
+        }
+        assert!(std::fs::metadata(&path).unwrap().len() <= 600);
+        assert!(rotated(&path, 1).exists() && !rotated(&path, 2).exists());
+        drop(journal);
+
+        let reopened = Journal::open(&path, config).unwrap();
+        let runs = reopened.incomplete_runs();
+        assert_eq!(runs.iter().map(|run| run.run_id.as_str()).collect::<Vec<_>>(), ["open"]);
+        assert_eq!((runs[0].in_flight.as_slice(), runs[0].unfinished.as_slice()), (&["post teaser".to_string()][..], &["post teaser".to_string(), "post recap".to_string()][..]));
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(rotated(&path, 1)).unwrap();
+    }
+}
@@ -0,0 +1,142 @@
+use crate::Context;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Past successes passed to the planner unless configured otherwise.
+pub const DEFAULT_LEARNED_PLANS: usize = 3;
+/// Similarity at or above which a past success's plan is reused without planning.
+pub const DEFAULT_REUSE_THRESHOLD: f64 = 0.95;
+
+/// The `type` of memory payloads stored for successful runs.
+const SUCCESS: &str = "success";
+/// Characters of each output kept in a success's summary.
+const SUMMARY_CHARS: usize = 80;
+
+/// Whether runs whose every subtask succeeded are remembered, and how
+/// `proactive_plan` draws on them. Failed subtasks are remembered for
+/// `self_debug` under the same switch.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LearningConfig {
+    /// Off for deployments that must not keep what commands produced.
+    pub enabled: bool,
+    /// Most similar past successes the planner is shown.
+    pub top_k: usize,
+    /// Cosine similarity between commands at or above which the closest past
+    /// success's plan is used as is.
+    pub reuse_threshold: f64,
+}
+
+impl Default for LearningConfig {
+    fn default() -> Self {
+        Self { enabled: true, top_k: DEFAULT_LEARNED_PLANS, reuse_threshold: DEFAULT_REUSE_THRESHOLD }
+    }
+}
+
+/// A remembered successful run, as `proactive_plan` recalls it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LearnedPlan {
+    pub command: String,
+    pub plan: Vec<String>,
+    /// The context's virality score when the run completed.
+    pub virality: f64,
+    pub similarity: f64,
+}
+
+impl LearnedPlan {
+    fn from_payload(payload: &Map<String, Value>, similarity: f64) -> Option<Self> {
+        if payload.get("type").and_then(Value::as_str) != Some(SUCCESS) {
+            return None;
+        }
+        Some(Self {
+            command: payload.get("command")?.as_str()?.to_string(),
+            plan: serde_json::from_value(payload.get("plan")?.clone()).ok()?,
+            virality: payload.get("virality").and_then(Value::as_f64).unwrap_or_default(),
+            similarity,
+        })
+    }
+}
+
+/// The text a success is remembered by: the command, then a summary of its outputs.
+pub(crate) fn success_text(command: &str, outputs: &[String]) -> String {
+    let summary: Vec<String> = outputs.iter().map(|output| output.chars().take(SUMMARY_CHARS).collect()).collect();
+    format!("{}\n{}", command, summary.join("; "))
+}
+
+pub(crate) fn success_payload(command: &str, plan: &[String], virality: f64) -> Map<String, Value> {
+    let mut payload = Map::new();
+    payload.insert("type".to_string(), Value::from(SUCCESS));
+    payload.insert("command".to_string(), Value::from(command));
+    payload.insert("plan".to_string(), Value::from(plan.to_vec()));
+    payload.insert("virality".to_string(), Value::from(virality));
+    payload
+}
+
+/// The `k` past successes in `context` whose commands are most similar to
+/// `query` at `now`, under the context's memory decay, best first.
+pub(crate) fn recall(context: &Context, query: &[f64], k: usize, now: DateTime<Utc>) -> Vec<LearnedPlan> {
+    context
+        .decayed_nearest(query, context.memory_vectors.len(), now)
+        .into_iter()
+        .filter_map(|(idx, similarity)| LearnedPlan::from_payload(context.memory_payload(idx)?, similarity))
+        .take(k)
+        .collect()
+}
+
+/// `prompt` followed by the past successes, so the planner can build on them.
+pub(crate) fn planner_prompt(prompt: String, learned: &[LearnedPlan]) -> String {
+    if learned.is_empty() {
+        return prompt;
+    }
+    let plans: Vec<String> =
+        learned.iter().map(|plan| format!("- {}: {}", plan.command, plan.plan.join(" -> "))).collect();
+    format!("{}\n\nPast successful plans:\n{}", prompt, plans.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AgentResult, CognitiveOrchestrator, MockBackend, Plan};
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    fn orchestrator(learning: bool) -> (CognitiveOrchestrator, Arc<Mutex<Vec<String>>>) {
+        let prompts = Arc::new(Mutex::new(vec![]));
+        let seen = prompts.clone();
+        let mock = MockBackend::new()
+            .planner(move |prompt| {
+                seen.lock().unwrap().push(prompt.to_string());
+                Ok(Plan::from(vec!["post teaser".to_string(), "post launch".to_string()]))
+            })
+            .on("post", |rest| AgentResult { output: format!("posted {}", rest), status: true, metadata: HashMap::new(), error: None });
+        let orch = CognitiveOrchestrator::builder().backend(Arc::new(mock)).learning(learning).build().unwrap();
+        (orch, prompts)
+    }
+
+    #[test]
+    fn a_repeated_command_reuses_the_learned_plan() {
+        let (orch, prompts) = orchestrator(true);
+        let first = orch.process("launch the spring campaign".to_string(), "ctx1");
+        let context = orch.get_context("ctx1").unwrap();
+        assert_eq!(context.memory_texts, [Some("launch the spring campaign\nposted teaser; posted launch".to_string())]);
+        assert_eq!(context.memory_payload(0).unwrap()["plan"], serde_json::json!(["post teaser", "post launch"]));
+
+        assert_eq!(orch.process("launch the spring campaign".to_string(), "ctx1"), first);
+        assert_eq!(prompts.lock().unwrap().len(), 1);
+
+        // A different command is planned afresh, with the success as a hint.
+        orch.process("launch the summer campaign".to_string(), "ctx1");
+        let prompts = prompts.lock().unwrap();
+        assert_eq!(prompts.len(), 2);
+        assert!(prompts[1].ends_with("Past successful plans:\n- launch the spring campaign: post teaser -> post launch"));
+    }
+
+    #[test]
+    fn disabled_learning_remembers_nothing() {
+        let (orch, prompts) = orchestrator(false);
+        orch.process("launch the spring campaign".to_string(), "ctx1");
+        orch.process("launch the spring campaign".to_string(), "ctx1");
+        assert_eq!(prompts.lock().unwrap().len(), 2);
+        assert_eq!(orch.get_context("ctx1").unwrap().memory_vectors.len(), 0);
+    }
+}
@@ -0,0 +1,277 @@
+use crate::{Context, GoalStatus, MemoryMeta, OrchestratorError};
+use chrono::{DateTime, Utc};
+use pyo3::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Cosine similarity at or above which a merged-in memory vector counts as one
+/// the target already has.
+pub const DEFAULT_DEDUPE_SIMILARITY: f64 = 0.999;
+
+/// Where a forked context came from; `None` on contexts that were not forked.
+#[pyclass(module = "sovereign_cli", get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Lineage {
+    /// The id of the context it was forked from, in the same tenant.
+    pub parent_id: String,
+    pub forked_at: DateTime<Utc>,
+}
+
+/// What a merge does with a source goal whose id the target has for a different
+/// description. Goals sharing id and description are one goal, completed when
+/// either side completed it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GoalConflict {
+    /// The merge fails with `DuplicateGoal` and changes nothing.
+    #[default]
+    Fail,
+    KeepTarget,
+    TakeSource,
+}
+
+impl GoalConflict {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "fail" => Some(GoalConflict::Fail),
+            "keep_target" => Some(GoalConflict::KeepTarget),
+            "take_source" => Some(GoalConflict::TakeSource),
+            _ => None,
+        }
+    }
+}
+
+/// Whose `viral_metrics` (and viral config) a merged context ends up with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetricsMerge {
+    /// The source's when its virality score is higher, the target's otherwise.
+    #[default]
+    MaxVirality,
+    TakeSource,
+}
+
+impl MetricsMerge {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "max_virality" => Some(MetricsMerge::MaxVirality),
+            "take_source" => Some(MetricsMerge::TakeSource),
+            _ => None,
+        }
+    }
+}
+
+/// How `CognitiveOrchestrator::merge_contexts` folds one context into another:
+/// goals are united, memory vectors appended unless the target already has a
+/// near-identical one, and metrics taken per `metrics`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MergeStrategy {
+    pub goal_conflict: GoalConflict,
+    /// Source vectors at least this cosine-similar to a target vector are skipped.
+    pub dedupe_similarity: f64,
+    pub metrics: MetricsMerge,
+}
+
+impl Default for MergeStrategy {
+    fn default() -> Self {
+        Self {
+            goal_conflict: GoalConflict::default(),
+            dedupe_similarity: DEFAULT_DEDUPE_SIMILARITY,
+            metrics: MetricsMerge::default(),
+        }
+    }
+}
+
+/// What a merge changed in the target.
+#[pyclass(module = "sovereign_cli", get_all)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MergeReport {
+    pub goals_added: usize,
+    /// Conflicting goals replaced by the source's under `GoalConflict::TakeSource`.
+    pub goals_replaced: usize,
+    pub memory_added: usize,
+    /// Source vectors skipped as near-identical to one the target has.
+    pub memory_deduplicated: usize,
+    pub metrics_from_source: bool,
+}
+
+impl Context {
+    /// A deep copy under `new_id`, created at `now` with lineage pointing back to
+    /// this context. Its memory vectors keep when they were added.
+    pub fn fork(&self, new_id: impl Into<String>, now: DateTime<Utc>) -> Context {
+        let mut memory_meta = self.memory_meta.clone();
+        memory_meta.resize(self.memory_vectors.len(), MemoryMeta::new(self.created_at));
+        Context {
+            context_id: new_id.into(),
+            memory_meta,
+            lineage: Some(Lineage { parent_id: self.context_id.clone(), forked_at: now }),
+            created_at: now,
+            last_accessed: now,
+            ..self.clone()
+        }
+    }
+
+    /// Folds `source` into this context under `strategy`. On error nothing changes.
+    pub fn merge_from(&mut self, source: &Context, strategy: &MergeStrategy) -> Result<MergeReport, OrchestratorError> {
+        let mut merged = self.clone();
+        let mut report = MergeReport::default();
+
+        for goal in &source.active_goals {
+            match merged.active_goals.iter_mut().find(|existing| existing.id == goal.id) {
+                None => {
+                    merged.active_goals.push(goal.clone());
+                    report.goals_added += 1;
+                }
+                Some(existing) if existing.description == goal.description => {
+                    if goal.status == GoalStatus::Completed {
+                        existing.status = GoalStatus::Completed;
+                    }
+                    existing.priority = existing.priority.max(goal.priority);
+                }
+                Some(existing) => match strategy.goal_conflict {
+                    GoalConflict::Fail => {
+                        return Err(OrchestratorError::DuplicateGoal {
+                            context_id: self.context_id.clone(),
+                            goal_id: goal.id.clone(),
+                        });
+                    }
+                    GoalConflict::KeepTarget => {}
+                    GoalConflict::TakeSource => {
+                        *existing = goal.clone();
+                        report.goals_replaced += 1;
+                    }
+                },
+            }
+        }
+
+        for (idx, vec) in source.memory_vectors.iter().enumerate() {
+            if merged.nearest(&vec, 1).first().is_some_and(|&(_, similarity)| similarity >= strategy.dedupe_similarity) {
+                report.memory_deduplicated += 1;
+                continue;
+            }
+            let at = merged.memory_vectors.push(&vec)?;
+            merged.memory_meta.resize(at, MemoryMeta::new(merged.created_at));
+            merged.memory_meta.push(source.memory_meta(idx));
+            if let Some(text) = source.memory_text(idx) {
+                merged.memory_texts.resize(at, None);
+                merged.memory_texts.push(Some(text.to_string()));
+            }
+            if let Some(payload) = source.memory_payload(idx) {
+                merged.memory_payloads.resize(at, None);
+                merged.memory_payloads.push(Some(payload.clone()));
+            }
+            report.memory_added += 1;
+        }
+
+        // The target's attributes win over the source's under the same key.
+        for (key, value) in &source.attributes {
+            merged.attributes.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+
+        report.metrics_from_source = match strategy.metrics {
+            MetricsMerge::MaxVirality => source.viral_metrics.virality_score > merged.viral_metrics.virality_score,
+            MetricsMerge::TakeSource => true,
+        };
+        if report.metrics_from_source {
+            merged.viral_metrics = source.viral_metrics.clone();
+            merged.viral_config = source.viral_config.clone();
+            merged.propagation = source.propagation.clone();
+        }
+
+        *self = merged;
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CognitiveOrchestrator, Goal, ViralMetrics};
+
+    fn metrics(virality_score: f64, hook_rate: f64) -> ViralMetrics {
+        ViralMetrics { virality_score, hook_rate, ..ViralMetrics::default() }
+    }
+
+    #[test]
+    fn forks_are_deep_copies_with_lineage() {
+        let orch = CognitiveOrchestrator::new();
+        orch.add_goal("base", Goal::new("reach", "reach 10k views")).unwrap();
+        orch.add_memory("base", vec![1.0, 0.0]).unwrap();
+        let base = orch.get_context("base").unwrap();
+
+        let fork = orch.fork_context("base", "hook-high").unwrap();
+        assert_eq!(fork.context_id, "hook-high");
+        assert_eq!(fork.lineage.as_ref().unwrap().parent_id, "base");
+        assert!(fork.created_at >= base.created_at);
+        assert_eq!((fork.active_goals.clone(), fork.memory_vectors.clone()), (base.active_goals, base.memory_vectors));
+        assert_eq!(orch.get_context("hook-high"), Some(fork));
+
+        // The copy is independent of its parent.
+        orch.add_memory("hook-high", vec![0.0, 1.0]).unwrap();
+        assert_eq!(orch.get_context("base").unwrap().memory_vectors.len(), 1);
+        assert!(orch.get_context("base").unwrap().lineage.is_none());
+
+        assert_eq!(orch.fork_context("base", "hook-high").unwrap_err().kind(), "context_exists");
+        assert_eq!(orch.fork_context("missing", "other").unwrap_err().kind(), "missing_context");
+    }
+
+    #[test]
+    fn merges_unite_goals_dedupe_memory_and_pick_metrics() {
+        let orch = CognitiveOrchestrator::new();
+        orch.add_goal("base", Goal::new("reach", "reach 10k views")).unwrap();
+        orch.add_memory("base", vec![1.0, 0.0]).unwrap();
+        orch.fork_context("base", "winner").unwrap();
+        orch.complete_goal("winner", "reach").unwrap();
+        orch.add_goal("winner", Goal::new("retain", "keep followers")).unwrap();
+        orch.add_memory("winner", vec![2.0, 0.0001]).unwrap();
+        orch.contexts.update("winner", |context| {
+            context.add_memory_text(vec![0.0, 1.0], "hooks at 0.3 work".to_string()).unwrap();
+            context.viral_metrics = metrics(0.8, 0.3);
+        });
+
+        let report = orch.merge_contexts("base", "winner", &MergeStrategy::default()).unwrap();
+        assert_eq!((report.goals_added, report.goals_replaced), (1, 0));
+        // The copied vector and its near twin are skipped; the remembered text is new.
+        assert_eq!((report.memory_added, report.memory_deduplicated), (1, 2));
+        assert!(report.metrics_from_source);
+
+        let base = orch.get_context("base").unwrap();
+        let goals: Vec<(&str, GoalStatus)> = base.active_goals.iter().map(|goal| (goal.id.as_str(), goal.status)).collect();
+        assert_eq!(goals, [("reach", GoalStatus::Completed), ("retain", GoalStatus::Active)]);
+        assert_eq!(base.memory_text(1), Some("hooks at 0.3 work"));
+        assert_eq!(base.viral_metrics, metrics(0.8, 0.3));
+
+        // A loser's lower virality leaves the target's metrics unless told otherwise.
+        orch.fork_context("base", "loser").unwrap();
+        orch.contexts.update("loser", |context| context.viral_metrics = metrics(0.1, 0.05));
+        assert!(!orch.merge_contexts("base", "loser", &MergeStrategy::default()).unwrap().metrics_from_source);
+        assert_eq!(orch.get_context("base").unwrap().viral_metrics, metrics(0.8, 0.3));
+        let take_source = MergeStrategy { metrics: MetricsMerge::TakeSource, ..MergeStrategy::default() };
+        assert!(orch.merge_contexts("base", "loser", &take_source).unwrap().metrics_from_source);
+        assert_eq!(orch.get_context("base").unwrap().viral_metrics, metrics(0.1, 0.05));
+    }
+
+    #[test]
+    fn conflicting_goal_ids_follow_the_strategy() {
+        let orch = CognitiveOrchestrator::new();
+        orch.add_goal("a", Goal::new("reach", "reach 10k views")).unwrap();
+        orch.add_goal("b", Goal::new("reach", "reach 50k views")).unwrap();
+        orch.add_goal("b", Goal::new("retain", "keep followers")).unwrap();
+        let descriptions =
+            |orch: &CognitiveOrchestrator| orch.list_goals("a").into_iter().map(|goal| goal.description).collect::<Vec<_>>();
+
+        let err = orch.merge_contexts("a", "b", &MergeStrategy::default()).unwrap_err();
+        assert_eq!(err, OrchestratorError::DuplicateGoal { context_id: "a".to_string(), goal_id: "reach".to_string() });
+        // A failed merge changes nothing, not even the goals before the conflict.
+        assert_eq!(descriptions(&orch), ["reach 10k views"]);
+
+        let keep = MergeStrategy { goal_conflict: GoalConflict::KeepTarget, ..MergeStrategy::default() };
+        orch.merge_contexts("a", "b", &keep).unwrap();
+        assert_eq!(descriptions(&orch), ["reach 10k views", "keep followers"]);
+
+        let take = MergeStrategy { goal_conflict: GoalConflict::TakeSource, ..MergeStrategy::default() };
+        assert_eq!(orch.merge_contexts("a", "b", &take).unwrap().goals_replaced, 1);
+        assert_eq!(descriptions(&orch), ["reach 50k views", "keep followers"]);
+    }
+}
@@ -0,0 +1,660 @@
+use crate::OrchestratorError;
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::mem::size_of;
+use std::ops::{Add, Mul};
+
+/// Matches the sentence-transformers models the Python memory path uses.
+pub const DEFAULT_VECTOR_SIZE: usize = 384;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryHit {
+    pub id: String,
+    pub score: f32,
+    pub payload: Map<String, Value>,
+}
+
+const MILLIS_PER_DAY: f64 = 86_400_000.0;
+
+/// When a memory vector was added and how often `recall` has returned it; kept
+/// by index in `Context.memory_meta`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct MemoryMeta {
+    pub inserted_at: DateTime<Utc>,
+    #[serde(default)]
+    pub access_count: u64,
+}
+
+impl MemoryMeta {
+    pub fn new(inserted_at: DateTime<Utc>) -> Self {
+        Self { inserted_at, access_count: 0 }
+    }
+
+    /// `exp(-lambda * age_days)`, the factor recall scales the entry's cosine
+    /// similarity by; 1 without decay.
+    pub fn decay(&self, lambda: f64, now: DateTime<Utc>) -> f64 {
+        let age_days = (now - self.inserted_at).num_milliseconds().max(0) as f64 / MILLIS_PER_DAY;
+        if lambda > 0.0 && age_days > 0.0 {
+            (-lambda * age_days).exp()
+        } else {
+            1.0
+        }
+    }
+
+    /// What `prune_memory` keeps entries by: the decay factor, raised for
+    /// entries recall keeps returning (by `ln(1 + access_count)`).
+    pub fn retention(&self, lambda: f64, now: DateTime<Utc>) -> f64 {
+        self.decay(lambda, now) * (1.0 + (self.access_count as f64).ln_1p())
+    }
+}
+
+/// Serde skip test for `Context.memory_decay`.
+pub(crate) fn no_decay(lambda: &f64) -> bool {
+    *lambda == 0.0
+}
+
+/// Keeps the items whose index `keep` marks, for lists kept by memory index.
+pub(crate) fn retain_indexed<T>(items: &mut Vec<T>, keep: &[bool]) {
+    let mut idx = 0;
+    items.retain(|_| {
+        idx += 1;
+        keep.get(idx - 1).copied().unwrap_or(false)
+    });
+}
+
+/// How `MemoryVectors` stores its components.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Quantization {
+    /// An `f32` per component.
+    #[default]
+    None,
+    /// A byte per component plus an `f32` scale per vector: about a quarter of
+    /// the space, with recall ranking close to exact.
+    Int8,
+}
+
+impl Quantization {
+    /// `"none"` or `"int8"`.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(Self::None),
+            "int8" => Some(Self::Int8),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Storage {
+    F32(Vec<f32>),
+    /// Each vector's components as multiples of its scale, the largest at ±127.
+    Int8 { codes: Vec<i8>, scales: Vec<f32> },
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        Storage::F32(vec![])
+    }
+}
+
+/// Row-major, fixed-dimension vector buffer backing `Context.memory_vectors`,
+/// in `f32` or int8-quantized. Serializes as a list of vectors so persisted
+/// contexts keep their shape, and accepts `f64` components on the way back in.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(try_from = "Vec<Vec<f64>>", into = "Vec<Vec<f32>>")]
+pub struct MemoryVectors {
+    dim: usize,
+    storage: Storage,
+}
+
+impl MemoryVectors {
+    pub fn new(quantization: Quantization) -> Self {
+        Self::default().with_quantization(quantization)
+    }
+
+    /// Zero until the first vector fixes it.
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.storage {
+            Storage::F32(data) => data.len().checked_div(self.dim).unwrap_or(0),
+            Storage::Int8 { scales, .. } => scales.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn quantization(&self) -> Quantization {
+        match self.storage {
+            Storage::F32(_) => Quantization::None,
+            Storage::Int8 { .. } => Quantization::Int8,
+        }
+    }
+
+    pub fn with_quantization(mut self, quantization: Quantization) -> Self {
+        self.quantize(quantization);
+        self
+    }
+
+    /// Re-encodes the stored vectors; quantizing loses precision that switching
+    /// back does not restore.
+    pub fn quantize(&mut self, quantization: Quantization) {
+        if quantization == self.quantization() {
+            return;
+        }
+        let rows: Vec<Vec<f32>> = (0..self.len()).map(|idx| self.row(idx)).collect();
+        self.storage = match quantization {
+            Quantization::None => Storage::F32(vec![]),
+            Quantization::Int8 => Storage::Int8 { codes: vec![], scales: vec![] },
+        };
+        for row in &rows {
+            self.append(row);
+        }
+    }
+
+    /// Bytes the components take.
+    pub fn bytes(&self) -> usize {
+        match &self.storage {
+            Storage::F32(data) => data.len() * size_of::<f32>(),
+            Storage::Int8 { codes, scales } => codes.len() + scales.len() * size_of::<f32>(),
+        }
+    }
+
+    /// The vector at `idx`, dequantized.
+    pub fn get(&self, idx: usize) -> Option<Vec<f64>> {
+        (idx < self.len()).then(|| self.row(idx).into_iter().map(f64::from).collect())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Vec<f64>> + '_ {
+        (0..self.len()).filter_map(|idx| self.get(idx))
+    }
+
+    fn row(&self, idx: usize) -> Vec<f32> {
+        let range = idx * self.dim..(idx + 1) * self.dim;
+        match &self.storage {
+            Storage::F32(data) => data[range].to_vec(),
+            Storage::Int8 { codes, scales } => codes[range].iter().map(|&code| f32::from(code) * scales[idx]).collect(),
+        }
+    }
+
+    fn append(&mut self, vec: &[f32]) {
+        match &mut self.storage {
+            Storage::F32(data) => data.extend_from_slice(vec),
+            Storage::Int8 { codes, scales } => {
+                let (row, scale) = encode(vec);
+                codes.extend(row);
+                scales.push(scale);
+            }
+        }
+    }
+
+    /// Appends a vector and returns its index.
+    pub fn push(&mut self, vec: &[f64]) -> Result<usize, OrchestratorError> {
+        if vec.is_empty() || (self.dim != 0 && vec.len() != self.dim) {
+            return Err(OrchestratorError::DimensionMismatch {
+                expected: self.dim,
+                actual: vec.len(),
+            });
+        }
+        self.dim = vec.len();
+        let vec: Vec<f32> = vec.iter().map(|&v| v as f32).collect();
+        self.append(&vec);
+        Ok(self.len() - 1)
+    }
+
+    /// Top-`k` `(index, cosine similarity)` pairs, best first. A query of the
+    /// wrong dimension matches nothing. Quantized vectors are compared by their
+    /// codes against the query's, in integers, as a vector's scale does not
+    /// change its direction.
+    pub fn nearest(&self, query: &[f64], k: usize) -> Vec<(usize, f64)> {
+        if k == 0 || self.is_empty() || query.len() != self.dim {
+            return vec![];
+        }
+        let query: Vec<f32> = query.iter().map(|&q| q as f32).collect();
+        let scored = match &self.storage {
+            Storage::F32(data) => f32_similarities(data, self.dim, &query),
+            Storage::Int8 { codes, .. } => similarities(codes, self.dim, &encode(&query).0, i32::from),
+        };
+        top_k(scored, k)
+    }
+
+    /// Keeps the vectors whose index `keep` accepts, in order.
+    pub fn retain(&mut self, mut keep: impl FnMut(usize) -> bool) {
+        let keep: Vec<bool> = (0..self.len()).map(&mut keep).collect();
+        self.storage = match &self.storage {
+            Storage::F32(data) => Storage::F32(keep_rows(data, self.dim, &keep)),
+            Storage::Int8 { codes, scales } => {
+                Storage::Int8 { codes: keep_rows(codes, self.dim, &keep), scales: keep_rows(scales, 1, &keep) }
+            }
+        };
+    }
+
+    /// The vectors, dequantized.
+    pub fn to_nested(&self) -> Vec<Vec<f64>> {
+        self.iter().collect()
+    }
+
+    /// The vectors, dequantized, end to end.
+    pub fn to_flat(&self) -> Vec<f32> {
+        match &self.storage {
+            Storage::F32(data) => data.clone(),
+            Storage::Int8 { .. } => (0..self.len()).flat_map(|idx| self.row(idx)).collect(),
+        }
+    }
+
+    /// Unquantized vectors of `dim` components each, from `data` end to end; a
+    /// length that is not a multiple of `dim` is a `DimensionMismatch`.
+    pub fn from_flat(dim: usize, data: Vec<f32>) -> Result<Self, OrchestratorError> {
+        if !data.len().is_multiple_of(dim) {
+            return Err(OrchestratorError::DimensionMismatch { expected: dim, actual: data.len() });
+        }
+        Ok(Self { dim, storage: Storage::F32(data) })
+    }
+}
+
+/// Each `dim`-long row of `data` with its cosine similarity to `query`, in order.
+/// Sums are taken in `A`, which each component is widened to.
+fn similarities<T, A>(data: &[T], dim: usize, query: &[T], widen: impl Fn(T) -> A) -> Vec<(usize, f64)>
+where
+    T: Copy,
+    A: Copy + Default + Add<Output = A> + Mul<Output = A> + Into<f64>,
+{
+    let query_norm = query.iter().fold(A::default(), |sum, &q| sum + widen(q) * widen(q)).into().sqrt();
+    data.chunks_exact(dim)
+        .enumerate()
+        .map(|(idx, row)| {
+            let (dot, norm) = row.iter().zip(query).fold((A::default(), A::default()), |(dot, norm), (&r, &q)| {
+                let r = widen(r);
+                (dot + r * widen(q), norm + r * r)
+            });
+            let denom = norm.into().sqrt() * query_norm;
+            (idx, if denom > 0.0 { dot.into() / denom } else { 0.0 })
+        })
+        .collect()
+}
+
+/// Lanes the `f32` cosine loop sums in, independent of each other so the
+/// compiler can keep each accumulator in one vector register.
+const LANES: usize = 8;
+
+/// `row`'s dot product with `query` and its squared norm, summed `LANES` wide.
+fn dot_and_norm(row: &[f32], query: &[f32]) -> (f32, f32) {
+    let (rows, queries) = (row.chunks_exact(LANES), query.chunks_exact(LANES));
+    let tail = rows.remainder().iter().zip(queries.remainder()).fold((0.0, 0.0), |(dot, norm), (&r, &q)| (dot + r * q, norm + r * r));
+    let (mut dot, mut norm) = ([0.0f32; LANES], [0.0f32; LANES]);
+    for (r, q) in rows.zip(queries) {
+        for lane in 0..LANES {
+            dot[lane] += r[lane] * q[lane];
+            norm[lane] += r[lane] * r[lane];
+        }
+    }
+    (dot.iter().sum::<f32>() + tail.0, norm.iter().sum::<f32>() + tail.1)
+}
+
+/// `similarities` over unquantized rows, through `dot_and_norm`.
+fn f32_similarities(data: &[f32], dim: usize, query: &[f32]) -> Vec<(usize, f64)> {
+    let query_norm = f64::from(dot_and_norm(query, query).1).sqrt();
+    data.chunks_exact(dim)
+        .enumerate()
+        .map(|(idx, row)| {
+            let (dot, norm) = dot_and_norm(row, query);
+            let denom = f64::from(norm).sqrt() * query_norm;
+            (idx, if denom > 0.0 { f64::from(dot) / denom } else { 0.0 })
+        })
+        .collect()
+}
+
+/// The best `k` of `scored`, best first and ties by index; only those are sorted.
+fn top_k(mut scored: Vec<(usize, f64)>, k: usize) -> Vec<(usize, f64)> {
+    let order = |a: &(usize, f64), b: &(usize, f64)| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0));
+    if k < scored.len() {
+        scored.select_nth_unstable_by(k, order);
+        scored.truncate(k);
+    }
+    scored.sort_by(order);
+    scored
+}
+
+/// Int8 codes for `vec`, its largest component at ±127, and the scale they are
+/// multiples of.
+fn encode(vec: &[f32]) -> (Vec<i8>, f32) {
+    let scale = vec.iter().fold(0.0f32, |max, v| max.max(v.abs())) / 127.0;
+    (vec.iter().map(|v| if scale > 0.0 { (v / scale).round() as i8 } else { 0 }).collect(), scale)
+}
+
+/// The `dim`-long rows of `data` that `keep` marks, concatenated.
+fn keep_rows<T: Copy>(data: &[T], dim: usize, keep: &[bool]) -> Vec<T> {
+    data.chunks_exact(dim).zip(keep).filter(|(_, keep)| **keep).flat_map(|(row, _)| row.iter().copied()).collect()
+}
+
+impl TryFrom<Vec<Vec<f64>>> for MemoryVectors {
+    type Error = OrchestratorError;
+
+    fn try_from(rows: Vec<Vec<f64>>) -> Result<Self, Self::Error> {
+        let mut vectors = MemoryVectors::default();
+        for row in &rows {
+            vectors.push(row)?;
+        }
+        Ok(vectors)
+    }
+}
+
+impl From<MemoryVectors> for Vec<Vec<f64>> {
+    fn from(vectors: MemoryVectors) -> Self {
+        vectors.to_nested()
+    }
+}
+
+impl From<MemoryVectors> for Vec<Vec<f32>> {
+    fn from(vectors: MemoryVectors) -> Self {
+        (0..vectors.len()).map(|idx| vectors.row(idx)).collect()
+    }
+}
+
+/// Vector memory used by `self_debug` to log anomalies.
+pub trait MemoryStore: Send + Sync {
+    /// Embeds `text` and stores it with `payload`; returns the new point id.
+    fn store_context(&self, text: &str, context_id: &str, payload: Map<String, Value>) -> Result<String, OrchestratorError>;
+    fn search(&self, query_vec: &[f32], limit: usize) -> Result<Vec<MemoryHit>, OrchestratorError>;
+
+    /// Whether the store can be reached now; `preflight` calls it. Stores with
+    /// nothing remote to reach are always up.
+    fn ping(&self) -> Result<(), OrchestratorError> {
+        Ok(())
+    }
+}
+
+/// Local feature-hashing embedding: each lowercased token bumps one signed bucket,
+/// then the vector is L2-normalized. Cheap and deterministic, with no model to load.
+pub fn hash_embed(text: &str, dim: usize) -> Vec<f32> {
+    let mut vec = vec![0.0f32; dim.max(1)];
+    for token in text.split(|c: char| !c.is_alphanumeric()).filter(|t| !t.is_empty()) {
+        let mut hasher = DefaultHasher::new();
+        token.to_lowercase().hash(&mut hasher);
+        let hash = hasher.finish();
+        let bucket = (hash % vec.len() as u64) as usize;
+        vec[bucket] += if hash >> 63 == 0 { 1.0 } else { -1.0 };
+    }
+    let norm = vec.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vec.iter_mut().for_each(|v| *v /= norm);
+    }
+    vec
+}
+
+#[cfg(feature = "qdrant")]
+pub use self::qdrant::QdrantStore;
+
+#[cfg(feature = "qdrant")]
+mod qdrant {
+    use super::{hash_embed, MemoryHit, MemoryStore};
+    use crate::OrchestratorError;
+    use qdrant_client::qdrant::point_id::PointIdOptions;
+    use qdrant_client::qdrant::{
+        CreateCollectionBuilder, Distance, PointStruct, SearchPointsBuilder, UpsertPointsBuilder, VectorParamsBuilder,
+    };
+    use qdrant_client::{Payload, Qdrant};
+    use serde_json::{Map, Value};
+    use std::future::Future;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{mpsc, Arc};
+
+    fn memory_err(err: impl std::fmt::Display) -> OrchestratorError {
+        OrchestratorError::Memory { message: err.to_string() }
+    }
+
+    /// Qdrant-backed memory using the official gRPC client.
+    pub struct QdrantStore {
+        client: Arc<Qdrant>,
+        collection: String,
+        vector_size: usize,
+        collection_ready: AtomicBool,
+        // Owned so sync callers (including tokio blocking-pool threads) never
+        // need to `block_on` inside someone else's runtime.
+        runtime: tokio::runtime::Runtime,
+    }
+
+    impl QdrantStore {
+        pub fn new(url: &str, collection: &str, vector_size: usize) -> Result<Self, OrchestratorError> {
+            let runtime = tokio::runtime::Builder::new_multi_thread()
+                .worker_threads(1)
+                .enable_all()
+                .build()
+                .map_err(memory_err)?;
+            let client = {
+                let _guard = runtime.enter();
+                Qdrant::from_url(url).build().map_err(memory_err)?
+            };
+            Ok(Self {
+                client: Arc::new(client),
+                collection: collection.to_string(),
+                vector_size,
+                collection_ready: AtomicBool::new(false),
+                runtime,
+            })
+        }
+
+        fn run<T: Send + 'static>(&self, fut: impl Future<Output = T> + Send + 'static) -> Result<T, OrchestratorError> {
+            let (tx, rx) = mpsc::channel();
+            self.runtime.spawn(async move {
+                let _ = tx.send(fut.await);
+            });
+            rx.recv().map_err(memory_err)
+        }
+
+        /// Creates the collection with cosine distance the first time it's needed.
+        fn ensure_collection(&self) -> Result<(), OrchestratorError> {
+            if self.collection_ready.load(Ordering::Acquire) {
+                return Ok(());
+            }
+            let client = self.client.clone();
+            let name = self.collection.clone();
+            let size = self.vector_size as u64;
+            self.run(async move {
+                if !client.collection_exists(&name).await? {
+                    client
+                        .create_collection(
+                            CreateCollectionBuilder::new(&name).vectors_config(VectorParamsBuilder::new(size, Distance::Cosine)),
+                        )
+                        .await?;
+                }
+                Ok::<(), qdrant_client::QdrantError>(())
+            })?
+            .map_err(memory_err)?;
+            self.collection_ready.store(true, Ordering::Release);
+            Ok(())
+        }
+    }
+
+    impl MemoryStore for QdrantStore {
+        fn store_context(&self, text: &str, context_id: &str, mut payload: Map<String, Value>) -> Result<String, OrchestratorError> {
+            self.ensure_collection()?;
+            payload.insert("text".to_string(), Value::String(text.to_string()));
+            payload.insert("context_id".to_string(), Value::String(context_id.to_string()));
+            let payload = Payload::try_from(Value::Object(payload)).map_err(memory_err)?;
+
+            let id = uuid::Uuid::new_v4().to_string();
+            let point = PointStruct::new(id.clone(), hash_embed(text, self.vector_size), payload);
+            let client = self.client.clone();
+            let name = self.collection.clone();
+            self.run(async move { client.upsert_points(UpsertPointsBuilder::new(name, vec![point]).wait(true)).await })?
+                .map_err(memory_err)?;
+            Ok(id)
+        }
+
+        fn search(&self, query_vec: &[f32], limit: usize) -> Result<Vec<MemoryHit>, OrchestratorError> {
+            self.ensure_collection()?;
+            let client = self.client.clone();
+            let request = SearchPointsBuilder::new(&self.collection, query_vec.to_vec(), limit as u64).with_payload(true);
+            let response = self.run(async move { client.search_points(request).await })?.map_err(memory_err)?;
+
+            Ok(response
+                .result
+                .into_iter()
+                .map(|point| MemoryHit {
+                    id: match point.id.and_then(|id| id.point_id_options) {
+                        Some(PointIdOptions::Uuid(uuid)) => uuid,
+                        Some(PointIdOptions::Num(num)) => num.to_string(),
+                        None => String::new(),
+                    },
+                    score: point.score,
+                    payload: point.payload.into_iter().map(|(k, v)| (k, v.into_json())).collect(),
+                })
+                .collect())
+        }
+
+        fn ping(&self) -> Result<(), OrchestratorError> {
+            let client = self.client.clone();
+            self.run(async move { client.health_check().await })?.map(|_| ()).map_err(memory_err)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MemoryVectors, Quantization};
+    use crate::{CognitiveOrchestrator, FixedClock};
+    use chrono::{DateTime, Duration};
+    use std::collections::HashSet;
+    use std::sync::Arc;
+
+    /// Uniform in [-1, 1), from a xorshift generator.
+    fn synthetic(seed: &mut u64, dim: usize) -> Vec<f64> {
+        (0..dim)
+            .map(|_| {
+                *seed ^= *seed << 13;
+                *seed ^= *seed >> 7;
+                *seed ^= *seed << 17;
+                (*seed >> 11) as f64 / (1u64 << 52) as f64 - 1.0
+            })
+            .collect()
+    }
+
+    #[test]
+    fn int8_recall_keeps_the_exact_top_ten() {
+        let (dim, mut seed) = (384, 0x2545_f491_4f6c_dd1d);
+        let rows: Vec<Vec<f64>> = (0..2_000).map(|_| synthetic(&mut seed, dim)).collect();
+        let exact = MemoryVectors::try_from(rows).unwrap();
+        let quantized = exact.clone().with_quantization(Quantization::Int8);
+        assert_eq!((exact.bytes(), quantized.bytes()), (2_000 * dim * 4, 2_000 * (dim + 4)));
+
+        let top = |vectors: &MemoryVectors, query: &[f64]| -> HashSet<usize> {
+            vectors.nearest(query, 10).into_iter().map(|(idx, _)| idx).collect()
+        };
+        let queries: Vec<Vec<f64>> = (0..50).map(|_| synthetic(&mut seed, dim)).collect();
+        let found: usize = queries.iter().map(|query| top(&exact, query).intersection(&top(&quantized, query)).count()).sum();
+        let recall = found as f64 / (queries.len() * 10) as f64;
+        // About 0.99 with this seed; 0.95 leaves room for other data.
+        assert!(recall >= 0.95, "recall@10 {}", recall);
+
+        let (idx, score) = quantized.nearest(&exact.get(7).unwrap(), 1)[0];
+        assert_eq!(idx, 7);
+        assert!((score - 1.0).abs() < 1e-3, "{}", score);
+        let restored: MemoryVectors = serde_json::from_str(&serde_json::to_string(&quantized).unwrap()).unwrap();
+        assert_eq!(restored.with_quantization(Quantization::Int8), quantized);
+    }
+
+    /// Per recall over 100k vectors at dim 384, with room for a loaded CI machine.
+    const RECALL_BUDGET_100K: std::time::Duration = std::time::Duration::from_millis(25);
+
+    /// Perf smoke test: `cargo test --release memory::tests::recall_over -- --ignored`.
+    #[test]
+    #[ignore = "timing-sensitive; run in release"]
+    fn recall_over_100k_vectors_fits_the_budget() {
+        let (dim, mut seed) = (384, 0x2545_f491_4f6c_dd1d);
+        let mut vectors = MemoryVectors::default();
+        for _ in 0..100_000 {
+            vectors.push(&synthetic(&mut seed, dim)).unwrap();
+        }
+        let queries: Vec<Vec<f64>> = (0..20).map(|_| synthetic(&mut seed, dim)).collect();
+        let started = std::time::Instant::now();
+        for query in &queries {
+            assert_eq!(std::hint::black_box(vectors.nearest(query, 10)).len(), 10);
+        }
+        let per_recall = started.elapsed() / queries.len() as u32;
+        assert!(per_recall < RECALL_BUDGET_100K, "{:?} per recall, budget {:?}", per_recall, RECALL_BUDGET_100K);
+    }
+
+    #[test]
+    fn wide_accumulation_matches_a_plain_sum_and_top_k_a_full_sort() {
+        let (dim, mut seed) = (19, 7);
+        let rows: Vec<Vec<f64>> = (0..300).map(|_| synthetic(&mut seed, dim)).collect();
+        let vectors = MemoryVectors::try_from(rows.clone()).unwrap();
+        let query = synthetic(&mut seed, dim);
+        let plain = |row: &[f64]| {
+            let (dot, norm) = row.iter().zip(&query).fold((0.0, 0.0), |(dot, norm), (r, q)| (dot + r * q, norm + r * r));
+            dot / (norm * query.iter().map(|q| q * q).sum::<f64>()).sqrt()
+        };
+        let all = vectors.nearest(&query, rows.len());
+        assert!(all.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+        for &(idx, similarity) in &all {
+            assert!((similarity - plain(&rows[idx])).abs() < 1e-5, "{}: {} vs {}", idx, similarity, plain(&rows[idx]));
+        }
+        assert_eq!(vectors.nearest(&query, 10), all[..10]);
+    }
+
+    fn orchestrator(clock: &Arc<FixedClock>) -> CognitiveOrchestrator {
+        CognitiveOrchestrator::builder().clock(clock.clone()).build().unwrap()
+    }
+
+    #[test]
+    fn decay_lets_a_recent_match_outrank_an_ancient_one() {
+        let clock = Arc::new(FixedClock::new(DateTime::UNIX_EPOCH));
+        let mut orch = orchestrator(&clock);
+        orch.add_memory("ctx1", vec![1.0, 0.0]).unwrap();
+        clock.advance(Duration::days(30));
+        orch.add_memory("ctx1", vec![0.6, 0.8]).unwrap();
+
+        let ranked = |orch: &mut CognitiveOrchestrator| -> Vec<usize> {
+            orch.recall("ctx1", &[1.0, 0.0], 2).unwrap().into_iter().map(|(idx, _)| idx).collect()
+        };
+        assert_eq!(ranked(&mut orch), [0, 1]);
+        orch.set_memory_decay("ctx1", 0.5);
+        assert_eq!(ranked(&mut orch), [1, 0]);
+        let hits = orch.recall("ctx1", &[1.0, 0.0], 1).unwrap();
+        assert!((hits[0].1 - 0.6).abs() < 1e-6);
+
+        let context = orch.get_context("ctx1").unwrap();
+        assert_eq!(context.memory_meta(0).inserted_at, DateTime::UNIX_EPOCH);
+        let counts: Vec<u64> = context.memory_meta.iter().map(|meta| meta.access_count).collect();
+        assert_eq!(counts, [2, 3]);
+    }
+
+    #[test]
+    fn pruning_compacts_memory_and_persistence_keeps_the_metadata() {
+        let clock = Arc::new(FixedClock::new(DateTime::UNIX_EPOCH));
+        let orch = orchestrator(&clock);
+        for text in ["old", "older recalled", "recent", "newest"] {
+            orch.remember("ctx1", text).unwrap();
+            clock.advance(Duration::days(10));
+        }
+        orch.recall_text("ctx1", "older recalled", 1).unwrap();
+        orch.set_memory_decay("ctx1", 0.1);
+
+        // 40, 30, 20 and 10 days old: retention e^-4, e^-3 (1 + ln 2), e^-2, e^-1.
+        assert_eq!(orch.prune_memory("ctx1", 0.05, None).unwrap(), 1);
+        assert_eq!(orch.prune_memory("ctx1", 0.0, Some(2)).unwrap(), 1);
+        assert!(matches!(orch.prune_memory("ctx2", 0.0, None), Err(crate::OrchestratorError::MissingContext { .. })));
+        let context = orch.get_context("ctx1").unwrap();
+        assert_eq!(context.memory_texts, [Some("recent".to_string()), Some("newest".to_string())]);
+        assert_eq!(context.memory_vectors.len(), 2);
+        assert_eq!(context.memory_meta[0].inserted_at, DateTime::UNIX_EPOCH + Duration::days(20));
+
+        let path = std::env::temp_dir().join(format!("memory-meta-{}.json", std::process::id()));
+        orch.save_contexts(&path).unwrap();
+        let restored = orchestrator(&clock);
+        restored.load_contexts(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let loaded = restored.get_context("ctx1").unwrap();
+        assert_eq!((&loaded.memory_meta, loaded.memory_decay), (&context.memory_meta, 0.1));
+    }
+}
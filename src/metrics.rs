@@ -0,0 +1,292 @@
+use crate::tenant::split_key;
+use crate::{AgentResult, Context};
+use prometheus::{Encoder, GaugeVec, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts, Registry, TextEncoder};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+// Exported metric names. These are part of the public interface: dashboards and
+// alerts key on them, so rename only with a major version bump.
+
+/// Counter, label `agent`: subtasks dispatched, by the agent that handled them
+/// (`unrouted` when no agent accepted the subtask). Retries count once.
+pub const SUBTASKS_DISPATCHED: &str = "sovereign_subtasks_dispatched_total";
+/// Counter, label `kind`: failed subtasks by `OrchestratorError::kind`, or `status`
+/// when the agent reported failure without an error.
+pub const SUBTASK_FAILURES: &str = "sovereign_subtask_failures_total";
+/// Histogram, label `call` (`Class.method`): wall time of Python agent calls, in
+/// seconds. Shared by every orchestrator in the process.
+pub const PYTHON_CALL_SECONDS: &str = "sovereign_python_call_duration_seconds";
+/// Counter: re-plans produced by `self_debug`.
+pub const REPLANS_TRIGGERED: &str = "sovereign_replans_triggered_total";
+/// Gauge: contexts currently held in memory.
+pub const ACTIVE_CONTEXTS: &str = "sovereign_active_contexts";
+/// Counter: contexts evicted for their TTL or a context cap.
+pub const CONTEXTS_EVICTED: &str = "sovereign_contexts_evicted_total";
+/// Gauge, labels `tenant` and `context_id`: latest `virality_score` of each live context.
+pub const VIRALITY_SCORE: &str = "sovereign_virality_score";
+/// Histogram, label `kind` (`AgentKind::name`): time dispatches waited on a rate
+/// limit, in seconds, zero when a token was free. Only rate-limited kinds appear.
+pub const RATE_LIMIT_WAIT_SECONDS: &str = "sovereign_rate_limit_wait_seconds";
+/// Gauge: `process` submissions waiting in the submission queue for a turn to run.
+pub const SUBMISSION_QUEUE_DEPTH: &str = "sovereign_submission_queue_depth";
+/// Histogram, label `priority` (`Priority::name`): time submissions waited in the
+/// submission queue, in seconds, zero when a turn was free. Only recorded while
+/// the queue is on.
+pub const SUBMISSION_QUEUE_WAIT_SECONDS: &str = "sovereign_submission_queue_wait_seconds";
+/// Counter: events a webhook was never delivered, after their last attempt.
+pub const WEBHOOK_FAILURES: &str = "sovereign_webhook_failures_total";
+
+fn python_call_seconds() -> &'static HistogramVec {
+    static HISTOGRAM: OnceLock<HistogramVec> = OnceLock::new();
+    HISTOGRAM.get_or_init(|| {
+        HistogramVec::new(HistogramOpts::new(PYTHON_CALL_SECONDS, "Python agent call latency in seconds"), &["call"])
+            .expect("valid python call histogram")
+    })
+}
+
+/// Records one Python call; `call_agent` times every agent method through this.
+pub(crate) fn observe_python_call(call: &str, seconds: f64) {
+    python_call_seconds().with_label_values(&[call]).observe(seconds);
+}
+
+/// Dispatch wall times kept per agent for `AgentLatency`; older ones are dropped.
+pub const LATENCY_SAMPLES: usize = 256;
+
+/// Percentiles of an agent's recent dispatch wall times, retries included.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct AgentLatency {
+    #[serde(with = "crate::history::secs")]
+    pub p50: Duration,
+    #[serde(with = "crate::history::secs")]
+    pub p95: Duration,
+    /// Dispatches the percentiles are drawn from, at most `LATENCY_SAMPLES`.
+    pub samples: usize,
+}
+
+impl AgentLatency {
+    fn of(samples: &VecDeque<Duration>) -> Option<Self> {
+        let mut sorted: Vec<Duration> = samples.iter().copied().collect();
+        sorted.sort();
+        // Nearest rank: the smallest sample at or above the given share.
+        let rank = |q: f64| sorted[((q * sorted.len() as f64).ceil() as usize).clamp(1, sorted.len()) - 1];
+        (!sorted.is_empty()).then(|| Self { p50: rank(0.5), p95: rank(0.95), samples: sorted.len() })
+    }
+}
+
+/// Per-orchestrator collectors, all registered in `registry`.
+pub(crate) struct OrchestratorMetrics {
+    registry: Registry,
+    dispatched: IntCounterVec,
+    failures: IntCounterVec,
+    replans: IntCounter,
+    active_contexts: IntGauge,
+    evictions: IntCounter,
+    virality: GaugeVec,
+    rate_limit_waits: HistogramVec,
+    queue_depth: IntGauge,
+    queue_waits: HistogramVec,
+    webhook_failures: IntCounter,
+    /// Not exported; `CognitiveOrchestrator::estimate` reads it.
+    latencies: Mutex<HashMap<String, VecDeque<Duration>>>,
+}
+
+impl OrchestratorMetrics {
+    pub(crate) fn new() -> Self {
+        let dispatched = IntCounterVec::new(Opts::new(SUBTASKS_DISPATCHED, "Subtasks dispatched by agent"), &["agent"])
+            .expect("valid dispatch counter");
+        let failures = IntCounterVec::new(Opts::new(SUBTASK_FAILURES, "Failed subtasks by error kind"), &["kind"])
+            .expect("valid failure counter");
+        let replans = IntCounter::new(REPLANS_TRIGGERED, "Re-plans triggered by self_debug").expect("valid replan counter");
+        let active_contexts = IntGauge::new(ACTIVE_CONTEXTS, "Contexts held in memory").expect("valid context gauge");
+        let evictions = IntCounter::new(CONTEXTS_EVICTED, "Contexts evicted").expect("valid eviction counter");
+        let virality = GaugeVec::new(Opts::new(VIRALITY_SCORE, "Virality score per context"), &["tenant", "context_id"])
+            .expect("valid virality gauge");
+        let rate_limit_waits =
+            HistogramVec::new(HistogramOpts::new(RATE_LIMIT_WAIT_SECONDS, "Rate limit waits in seconds"), &["kind"])
+                .expect("valid rate limit histogram");
+        let queue_depth =
+            IntGauge::new(SUBMISSION_QUEUE_DEPTH, "Submissions waiting to run").expect("valid queue depth gauge");
+        let queue_waits = HistogramVec::new(
+            HistogramOpts::new(SUBMISSION_QUEUE_WAIT_SECONDS, "Submission queue waits in seconds"),
+            &["priority"],
+        )
+        .expect("valid queue wait histogram");
+        let webhook_failures =
+            IntCounter::new(WEBHOOK_FAILURES, "Webhook events given up on").expect("valid webhook counter");
+
+        let registry = Registry::new();
+        for collector in [
+            Box::new(dispatched.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(failures.clone()),
+            Box::new(python_call_seconds().clone()),
+            Box::new(replans.clone()),
+            Box::new(active_contexts.clone()),
+            Box::new(evictions.clone()),
+            Box::new(virality.clone()),
+            Box::new(rate_limit_waits.clone()),
+            Box::new(queue_depth.clone()),
+            Box::new(queue_waits.clone()),
+            Box::new(webhook_failures.clone()),
+        ] {
+            registry.register(collector).expect("metric names are unique");
+        }
+        Self {
+            registry,
+            dispatched,
+            failures,
+            replans,
+            active_contexts,
+            evictions,
+            virality,
+            rate_limit_waits,
+            queue_depth,
+            queue_waits,
+            webhook_failures,
+            latencies: Mutex::default(),
+        }
+    }
+
+    /// Observed by `RateLimits`, which outlives borrows of the orchestrator.
+    pub(crate) fn rate_limit_waits(&self) -> HistogramVec {
+        self.rate_limit_waits.clone()
+    }
+
+    /// The depth gauge and wait histogram `SubmissionQueue` keeps up to date.
+    pub(crate) fn submission_queue(&self) -> (IntGauge, HistogramVec) {
+        (self.queue_depth.clone(), self.queue_waits.clone())
+    }
+
+    /// Incremented by each `WebhookNotifier`, which runs on its own thread.
+    #[cfg_attr(not(feature = "webhooks"), allow(dead_code))]
+    pub(crate) fn webhook_failures(&self) -> IntCounter {
+        self.webhook_failures.clone()
+    }
+
+    pub(crate) fn registry(&self) -> Registry {
+        self.registry.clone()
+    }
+
+    /// `duration` is the dispatch's wall time; only routed dispatches count towards
+    /// the agent's latency.
+    pub(crate) fn dispatched(&self, agent: Option<&str>, result: &AgentResult, duration: Duration) {
+        self.dispatched.with_label_values(&[agent.unwrap_or("unrouted")]).inc();
+        if !result.status {
+            let kind = result.error.as_ref().map_or("status", |err| err.kind());
+            self.failures.with_label_values(&[kind]).inc();
+        }
+        if let Some(agent) = agent {
+            let mut latencies = self.latencies.lock().unwrap_or_else(|e| e.into_inner());
+            let samples = latencies.entry(agent.to_string()).or_default();
+            if samples.len() >= LATENCY_SAMPLES {
+                samples.pop_front();
+            }
+            samples.push_back(duration);
+        }
+    }
+
+    /// The agent's recent latency; `None` before it has handled a dispatch.
+    pub(crate) fn latency(&self, agent: &str) -> Option<AgentLatency> {
+        self.latencies.lock().unwrap_or_else(|e| e.into_inner()).get(agent).and_then(AgentLatency::of)
+    }
+
+    pub(crate) fn replanned(&self) {
+        self.replans.inc();
+    }
+
+    pub(crate) fn context_count(&self, count: usize) {
+        self.active_contexts.set(i64::try_from(count).unwrap_or(i64::MAX));
+    }
+
+    pub(crate) fn context_evicted(&self, key: &str) {
+        self.evictions.inc();
+        self.context_dropped(key);
+    }
+
+    pub(crate) fn evictions(&self) -> u64 {
+        self.evictions.get()
+    }
+
+    pub(crate) fn context_updated(&self, context: &Context) {
+        self.virality
+            .with_label_values(&[&context.tenant, &context.context_id])
+            .set(context.viral_metrics.virality_score);
+    }
+
+    /// `key` is the context's `Context::key`.
+    pub(crate) fn context_dropped(&self, key: &str) {
+        let (tenant, context_id) = split_key(key);
+        let _ = self.virality.remove_label_values(&[tenant, context_id]);
+    }
+}
+
+/// Prometheus text exposition of everything in `registry`.
+pub fn render(registry: &Registry) -> String {
+    let mut buffer = vec![];
+    if let Err(err) = TextEncoder::new().encode(&registry.gather(), &mut buffer) {
+        tracing::warn!("Metrics encoding failed: {}", err);
+    }
+    String::from_utf8(buffer).unwrap_or_default()
+}
+
+/// How long the metrics endpoint waits on a client reading its request or
+/// taking the response before dropping the connection.
+#[cfg(feature = "metrics_http")]
+pub const METRICS_HTTP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Serves `GET /metrics` for `registry` on a background thread; other paths get 404.
+/// Each connection is answered on its own thread and dropped after
+/// `METRICS_HTTP_TIMEOUT` without progress, so an idle client holds up no other.
+/// Returns the bound address, so port 0 picks a free port.
+#[cfg(feature = "metrics_http")]
+pub fn serve(registry: Registry, addr: impl std::net::ToSocketAddrs) -> std::io::Result<std::net::SocketAddr> {
+    let listener = std::net::TcpListener::bind(addr)?;
+    let local = listener.local_addr()?;
+    std::thread::Builder::new().name("metrics".to_string()).spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let registry = registry.clone();
+            let answered = std::thread::Builder::new().name("metrics-conn".to_string()).spawn(move || respond(&registry, stream));
+            if let Err(err) = answered {
+                tracing::warn!("Metrics connection dropped: {}", err);
+            }
+        }
+    })?;
+    Ok(local)
+}
+
+#[cfg(feature = "metrics_http")]
+fn respond(registry: &Registry, stream: std::net::TcpStream) {
+    use std::io::{BufRead, BufReader, Write};
+
+    if let Err(err) = stream.set_read_timeout(Some(METRICS_HTTP_TIMEOUT)).and_then(|()| stream.set_write_timeout(Some(METRICS_HTTP_TIMEOUT))) {
+        tracing::warn!("Metrics connection dropped: {}", err);
+        return;
+    }
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+    // Drain the headers so the client sees a clean close.
+    let mut header = String::new();
+    while reader.read_line(&mut header).is_ok_and(|n| n > 2) {
+        header.clear();
+    }
+
+    let (status, content_type, body) = if request_line.starts_with("GET /metrics ") {
+        ("200 OK", TextEncoder::new().format_type().to_string(), render(registry))
+    } else {
+        ("404 Not Found", "text/plain".to_string(), "not found\n".to_string())
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    if let Err(err) = (&stream).write_all(response.as_bytes()) {
+        tracing::warn!("Metrics response failed: {}", err);
+    }
+}
@@ -0,0 +1,214 @@
+use crate::{Clock, Context, SystemClock, ViralMetrics};
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Metrics samples kept per context unless configured otherwise.
+pub const DEFAULT_METRICS_HISTORY_LIMIT: usize = 256;
+
+/// A context's viral metrics as a viral subtask left them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct MetricsSample {
+    pub at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub metrics: ViralMetrics,
+}
+
+/// A context's metrics samples, oldest first. Serializes as a plain list.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(transparent)]
+pub struct MetricsHistory(VecDeque<MetricsSample>);
+
+impl MetricsHistory {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Appends a sample, dropping the oldest beyond `limit`.
+    pub fn record(&mut self, sample: MetricsSample, limit: usize) {
+        self.0.push_back(sample);
+        while self.0.len() > limit {
+            self.0.pop_front();
+        }
+    }
+
+    /// The samples recorded since this history was `base`: what follows the
+    /// longest tail of `base` it starts with, since recording only appends and
+    /// drops the oldest.
+    pub(crate) fn recorded_since(&self, base: &MetricsHistory) -> impl Iterator<Item = &MetricsSample> {
+        let kept = (0..=base.0.len())
+            .rev()
+            .find(|&kept| self.0.iter().take(kept).eq(base.0.iter().skip(base.0.len() - kept)))
+            .unwrap_or(0);
+        self.0.iter().skip(kept)
+    }
+
+    /// Samples taken at or after `since`, or all of them.
+    pub fn since(&self, since: Option<DateTime<Utc>>) -> Vec<MetricsSample> {
+        self.0.iter().filter(|sample| since.is_none_or(|since| sample.at >= since)).cloned().collect()
+    }
+
+    /// Least-squares slopes over every sample; `None` without two samples taken
+    /// at different times.
+    pub fn trend(&self) -> Option<MetricsTrend> {
+        let (first, last) = (self.0.front()?, self.0.back()?);
+        let times: Vec<f64> = self.0.iter().map(|sample| (sample.at - first.at).num_microseconds().unwrap_or(i64::MAX) as f64 / 1e6).collect();
+        let mean_time = times.iter().sum::<f64>() / times.len() as f64;
+        let variance: f64 = times.iter().map(|t| (t - mean_time).powi(2)).sum();
+        if variance == 0.0 {
+            return None;
+        }
+        let slope = |field: fn(&ViralMetrics) -> f64| {
+            let values: Vec<f64> = self.0.iter().map(|sample| field(&sample.metrics)).collect();
+            let mean = values.iter().sum::<f64>() / values.len() as f64;
+            times.iter().zip(&values).map(|(t, v)| (t - mean_time) * (v - mean)).sum::<f64>() / variance
+        };
+        Some(MetricsTrend {
+            samples: self.0.len(),
+            from: first.at,
+            to: last.at,
+            virality_score: slope(|metrics| metrics.virality_score),
+            engagement_nodes: slope(|metrics| metrics.engagement_nodes as f64),
+            hook_rate: slope(|metrics| metrics.hook_rate),
+            amplification_factor: slope(|metrics| metrics.amplification_factor),
+            quantum_fidelity: slope(|metrics| metrics.quantum_fidelity),
+        })
+    }
+}
+
+/// Each `ViralMetrics` field's change per second, fitted over a context's
+/// metrics history.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricsTrend {
+    pub samples: usize,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub virality_score: f64,
+    pub engagement_nodes: f64,
+    pub hook_rate: f64,
+    pub amplification_factor: f64,
+    pub quantum_fidelity: f64,
+}
+
+/// Samples a context's metrics from the orchestrator's clock into its history,
+/// keeping up to the shared limit. Clones share the limit.
+#[derive(Clone)]
+pub struct MetricsRecorder {
+    clock: Arc<dyn Clock>,
+    limit: Arc<AtomicUsize>,
+}
+
+impl Default for MetricsRecorder {
+    fn default() -> Self {
+        Self::new(Arc::new(SystemClock), DEFAULT_METRICS_HISTORY_LIMIT)
+    }
+}
+
+impl MetricsRecorder {
+    pub fn new(clock: Arc<dyn Clock>, limit: usize) -> Self {
+        Self { clock, limit: Arc::new(AtomicUsize::new(limit)) }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit.load(Ordering::Relaxed)
+    }
+
+    /// Takes effect at each context's next sample.
+    pub fn set_limit(&self, limit: usize) {
+        self.limit.store(limit, Ordering::Relaxed);
+    }
+
+    pub fn record(&self, ctx: &mut Context) {
+        let sample = MetricsSample { at: self.clock.now(), metrics: ctx.viral_metrics.clone() };
+        ctx.metrics_history.record(sample, self.limit());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CognitiveOrchestrator, FixedClock, MockBackend, OrchestratorError};
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        DateTime::UNIX_EPOCH + chrono::Duration::seconds(secs)
+    }
+
+    fn sample(secs: i64, virality_score: f64, engagement_nodes: usize) -> MetricsSample {
+        MetricsSample { at: at(secs), metrics: ViralMetrics { virality_score, engagement_nodes, ..ViralMetrics::default() } }
+    }
+
+    #[test]
+    fn ring_buffer_keeps_the_latest_and_fits_slopes() {
+        let mut history = MetricsHistory::default();
+        assert_eq!(history.trend(), None);
+        for (i, virality) in [0.9, 0.1, 0.3, 0.5].into_iter().enumerate() {
+            history.record(sample(i as i64 * 10, virality, 32 - i), 3);
+        }
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.since(Some(at(20))).len(), 2);
+
+        let trend = history.trend().unwrap();
+        assert_eq!((trend.samples, trend.from, trend.to), (3, at(10), at(30)));
+        assert!((trend.virality_score - 0.02).abs() < 1e-12);
+        assert!((trend.engagement_nodes + 0.1).abs() < 1e-12);
+        assert_eq!(trend.hook_rate, 0.0);
+
+        let json = serde_json::to_value(&history).unwrap();
+        assert_eq!(json[0]["virality_score"], 0.1);
+        assert_eq!(serde_json::from_value::<MetricsHistory>(json).unwrap(), history);
+    }
+
+    #[test]
+    fn recorded_since_skips_what_the_base_had_even_once_dropped() {
+        let mut base = MetricsHistory::default();
+        base.record(sample(0, 0.1, 32), 3);
+        base.record(sample(10, 0.2, 32), 3);
+        let mut later = base.clone();
+        later.record(sample(20, 0.3, 32), 3);
+        later.record(sample(30, 0.4, 32), 3);
+        let added: Vec<_> = later.recorded_since(&base).map(|sample| sample.at).collect();
+        assert_eq!(added, [at(20), at(30)]);
+        assert_eq!(base.recorded_since(&base).count(), 0);
+        assert_eq!(later.recorded_since(&MetricsHistory::default()).count(), 3);
+    }
+
+    #[test]
+    fn viral_subtasks_sample_the_context() {
+        let clock = Arc::new(FixedClock::new(at(0)));
+        let orch = CognitiveOrchestrator::builder()
+            .backend(Arc::new(MockBackend::new().virality(0.5)))
+            .clock(clock.clone())
+            .metrics_history_limit(2)
+            .build()
+            .unwrap();
+        assert!(matches!(orch.metrics_trend("ctx1"), Err(OrchestratorError::MissingContext { .. })));
+        for hook_rate in ["0.1", "0.2", "0.3"] {
+            // Only the viral subtask samples.
+            orch.dispatch(format!("inject hook {}", hook_rate), "ctx1");
+            orch.dispatch("measure spread".to_string(), "ctx1");
+            clock.advance(chrono::Duration::seconds(5));
+        }
+
+        let history = orch.metrics_history("ctx1", None).unwrap();
+        let times: Vec<DateTime<Utc>> = history.iter().map(|sample| sample.at).collect();
+        assert_eq!(times, [at(5), at(10)]);
+        assert_eq!(orch.metrics_history("ctx1", Some(at(10))).unwrap().len(), 1);
+        let trend = orch.metrics_trend("ctx1").unwrap().unwrap();
+        assert!((trend.hook_rate - 0.02).abs() < 1e-9);
+        assert_eq!(trend.virality_score, 0.0);
+
+        let path = std::env::temp_dir().join(format!("ace-metrics-history-{}.json", std::process::id()));
+        orch.save_contexts(&path).unwrap();
+        let restored = CognitiveOrchestrator::new();
+        restored.load_contexts(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(restored.metrics_history("ctx1", None).unwrap(), history);
+    }
+}
@@ -0,0 +1,241 @@
+use crate::viral::SplitMix64;
+use roqoqo::operations::{
+    Define, DefinitionBit, MeasureQubit, OperateSingleQubit, OperateTwoQubit, Operation, PauliX, PragmaActiveReset,
+    CNOT,
+};
+use roqoqo::Circuit;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Largest repetition-code distance built for an "amplify MWPM" step: 9 data
+/// qubits plus 8 syndrome ancillas, measured for 9 rounds.
+pub const MAX_CODE_DISTANCE: usize = 9;
+
+/// Above this many detection events exact bitmask matching gets too expensive
+/// and the decoder switches to greedy nearest-first matching.
+const MAX_EXACT_DEFECTS: usize = 16;
+
+const SYNDROME: &str = "syndrome";
+const DATA: &str = "data";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MwpmReport {
+    pub distance: usize,
+    pub rounds: usize,
+    pub shots: usize,
+    pub logical_errors: usize,
+    /// Fraction of shots the decoder restored to the encoded state.
+    pub fidelity: f64,
+}
+
+/// Detection event at `(round, ancilla)`.
+type Defect = (usize, usize);
+
+/// Bit-flip repetition code memory experiment decoded by minimum-weight perfect
+/// matching over the space-time graph of syndrome changes.
+#[derive(Debug, Clone)]
+pub struct MwpmDecoder {
+    pub shots: usize,
+    pub seed: u64,
+}
+
+impl Default for MwpmDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MwpmDecoder {
+    pub fn new() -> Self {
+        Self { shots: 32, seed: 0 }
+    }
+
+    /// One code distance per 8 engagement nodes, rounded up to odd, in `3..=MAX_CODE_DISTANCE`.
+    pub fn code_distance(engagement_nodes: usize) -> usize {
+        ((engagement_nodes / 8) | 1).clamp(3, MAX_CODE_DISTANCE)
+    }
+
+    /// Data qubits are `0..distance`, the ancilla between data `i` and `i + 1` is
+    /// `distance + i`. Each round, every data qubit and every ancilla readout
+    /// flips with probability `error_rate`; the sampled flips are `PauliX` gates.
+    fn build_circuit(distance: usize, rounds: usize, error_rate: f64, rng: &mut SplitMix64) -> Circuit {
+        let mut circuit = Circuit::new();
+        circuit += DefinitionBit::new(SYNDROME.to_string(), rounds * (distance - 1), true);
+        circuit += DefinitionBit::new(DATA.to_string(), distance, true);
+
+        for round in 0..rounds {
+            for q in 0..distance {
+                if rng.next_f64() < error_rate {
+                    circuit += PauliX::new(q);
+                }
+            }
+            for i in 0..distance - 1 {
+                let ancilla = distance + i;
+                circuit += CNOT::new(i, ancilla);
+                circuit += CNOT::new(i + 1, ancilla);
+                if rng.next_f64() < error_rate {
+                    circuit += PauliX::new(ancilla);
+                }
+                circuit += MeasureQubit::new(ancilla, SYNDROME.to_string(), round * (distance - 1) + i);
+                circuit += PragmaActiveReset::new(ancilla);
+            }
+        }
+        for q in 0..distance {
+            circuit += MeasureQubit::new(q, DATA.to_string(), q);
+        }
+        circuit
+    }
+
+    /// Runs the circuit on computational basis states, which is exact for the
+    /// X/CNOT/measure/reset gate set `build_circuit` emits; other operations are ignored.
+    fn run(circuit: &Circuit, qubits: usize) -> HashMap<String, Vec<bool>> {
+        let mut state = vec![false; qubits];
+        let mut registers: HashMap<String, Vec<bool>> = HashMap::new();
+        for op in circuit.iter() {
+            match op {
+                Operation::DefinitionBit(def) => {
+                    registers.insert(def.name().clone(), vec![false; *def.length()]);
+                }
+                Operation::PauliX(op) => state[*op.qubit()] ^= true,
+                Operation::CNOT(op) => state[*op.target()] ^= state[*op.control()],
+                Operation::MeasureQubit(op) => {
+                    if let Some(register) = registers.get_mut(op.readout()) {
+                        register[*op.readout_index()] = state[*op.qubit()];
+                    }
+                }
+                Operation::PragmaActiveReset(op) => state[*op.qubit()] = false,
+                _ => {}
+            }
+        }
+        registers
+    }
+
+    /// Syndrome changes between consecutive rounds; the final data readout supplies
+    /// one last noiseless round.
+    fn defects(syndrome: &[bool], data: &[bool], distance: usize, rounds: usize) -> Vec<Defect> {
+        let width = distance - 1;
+        let final_round: Vec<bool> = (0..width).map(|i| data[i] ^ data[i + 1]).collect();
+        let mut previous = vec![false; width];
+        let mut defects = vec![];
+        for round in 0..=rounds {
+            let current = if round < rounds { &syndrome[round * width..(round + 1) * width] } else { &final_round[..] };
+            for i in 0..width {
+                if current[i] != previous[i] {
+                    defects.push((round, i));
+                }
+            }
+            previous = current.to_vec();
+        }
+        defects
+    }
+
+    /// Pairs each defect with another (`Some(j)`) or with the nearer code boundary
+    /// (`None`). Exact by bitmask DP up to `MAX_EXACT_DEFECTS`, greedy beyond it.
+    fn matching(defects: &[Defect], distance: usize) -> Vec<(usize, Option<usize>)> {
+        let boundary = |(_, i): Defect| (i + 1).min(distance - 1 - i);
+        let pair = |(r1, i1): Defect, (r2, i2): Defect| r1.abs_diff(r2) + i1.abs_diff(i2);
+        let k = defects.len();
+
+        if k > MAX_EXACT_DEFECTS {
+            let mut edges: Vec<(usize, usize, Option<usize>)> = vec![];
+            for a in 0..k {
+                edges.push((boundary(defects[a]), a, None));
+                edges.extend((a + 1..k).map(|b| (pair(defects[a], defects[b]), a, Some(b))));
+            }
+            edges.sort_unstable();
+            let mut matched = vec![false; k];
+            let mut pairs = vec![];
+            for (_, a, b) in edges {
+                if matched[a] || b.is_some_and(|b| matched[b]) {
+                    continue;
+                }
+                matched[a] = true;
+                if let Some(b) = b {
+                    matched[b] = true;
+                }
+                pairs.push((a, b));
+            }
+            return pairs;
+        }
+
+        // best[mask]: cheapest matching of the defects in `mask`, and the partner
+        // chosen for its lowest defect.
+        let full = (1usize << k) - 1;
+        let mut best: Vec<(usize, Option<usize>)> = vec![(usize::MAX, None); full + 1];
+        best[0] = (0, None);
+        for mask in 1..=full {
+            let first = mask.trailing_zeros() as usize;
+            let rest = mask & !(1 << first);
+            let mut choice = (best[rest].0.saturating_add(boundary(defects[first])), None);
+            for j in (first + 1..k).filter(|j| rest & (1 << j) != 0) {
+                let cost = best[rest & !(1 << j)].0.saturating_add(pair(defects[first], defects[j]));
+                if cost < choice.0 {
+                    choice = (cost, Some(j));
+                }
+            }
+            best[mask] = choice;
+        }
+
+        let mut pairs = vec![];
+        let mut mask = full;
+        while mask != 0 {
+            let first = mask.trailing_zeros() as usize;
+            let partner = best[mask].1;
+            mask &= !(1 << first);
+            if let Some(j) = partner {
+                mask &= !(1 << j);
+            }
+            pairs.push((first, partner));
+        }
+        pairs
+    }
+
+    /// Data-qubit flips implied by the matching: the qubits between two paired
+    /// ancillas, or between an ancilla and its nearer boundary.
+    fn correction(defects: &[Defect], distance: usize) -> Vec<bool> {
+        let mut flips = vec![false; distance];
+        let mut flip_range = |from: usize, to: usize| flips[from..to].iter_mut().for_each(|f| *f ^= true);
+        for (a, b) in Self::matching(defects, distance) {
+            let (_, i) = defects[a];
+            match b {
+                Some(b) => {
+                    let (_, other) = defects[b];
+                    flip_range(i.min(other) + 1, i.max(other) + 1);
+                }
+                None if i < distance - 1 - i => flip_range(0, i + 1),
+                None => flip_range(i + 1, distance),
+            }
+        }
+        flips
+    }
+
+    /// Runs `shots` noisy memory experiments sized from `engagement_nodes` and
+    /// reports how often MWPM decoding recovers the encoded zero state.
+    pub fn decode(&self, engagement_nodes: usize, error_rate: f64) -> MwpmReport {
+        let distance = Self::code_distance(engagement_nodes);
+        let rounds = distance;
+        let error_rate = error_rate.clamp(0.0, 0.5);
+        let mut rng = SplitMix64(self.seed);
+        let mut logical_errors = 0;
+
+        for _ in 0..self.shots {
+            let circuit = Self::build_circuit(distance, rounds, error_rate, &mut rng);
+            let registers = Self::run(&circuit, 2 * distance - 1);
+            let data = &registers[DATA];
+            let defects = Self::defects(&registers[SYNDROME], data, distance, rounds);
+            let flips = Self::correction(&defects, distance);
+            let ones = data.iter().zip(&flips).filter(|(bit, flip)| **bit ^ **flip).count();
+            if ones * 2 > distance {
+                logical_errors += 1;
+            }
+        }
+
+        MwpmReport {
+            distance,
+            rounds,
+            shots: self.shots,
+            logical_errors,
+            fidelity: if self.shots == 0 { 1.0 } else { 1.0 - logical_errors as f64 / self.shots as f64 },
+        }
+    }
+}
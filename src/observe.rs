@@ -0,0 +1,131 @@
+use crate::history::secs;
+use crate::ViralMetrics;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+/// Where a `process` run on a context has got to. Commands and subtasks are
+/// redacted, as in reports.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RunProgress {
+    pub run_id: String,
+    pub command: String,
+    /// The subtasks of the wave being dispatched; empty between waves.
+    pub running: Vec<String>,
+    /// Subtasks finished, and those planned but not yet dispatched.
+    pub completed_steps: usize,
+    pub remaining_steps: usize,
+    /// The context's viral metrics as of the last finished wave.
+    pub metrics: ViralMetrics,
+    /// By the orchestrator's clock.
+    pub started_at: DateTime<Utc>,
+    #[serde(skip)]
+    started: Instant,
+}
+
+/// A context as `CognitiveOrchestrator::observe` sees it. During a run it is
+/// taken from what the run last published, without waiting on the context,
+/// which the run's agents hold while they execute.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ContextView {
+    pub context_id: String,
+    /// The run in progress, if any.
+    pub run: Option<Arc<RunProgress>>,
+    pub metrics: ViralMetrics,
+    /// Since the run in progress started.
+    #[serde(rename = "elapsed_secs", with = "secs::option")]
+    pub elapsed: Option<Duration>,
+}
+
+impl ContextView {
+    pub(crate) fn running(context_id: &str, run: Arc<RunProgress>) -> Self {
+        let (metrics, elapsed) = (run.metrics.clone(), Some(run.started.elapsed()));
+        Self { context_id: context_id.to_string(), run: Some(run), metrics, elapsed }
+    }
+
+    pub(crate) fn idle(context_id: &str, metrics: ViralMetrics) -> Self {
+        Self { context_id: context_id.to_string(), run: None, metrics, elapsed: None }
+    }
+}
+
+/// The progress of each context's run in flight. A run replaces its entry
+/// rather than changing it, so an observer only ever clones an `Arc`.
+#[derive(Clone, Default)]
+pub(crate) struct LiveRuns(Arc<RwLock<HashMap<String, Arc<RunProgress>>>>);
+
+impl LiveRuns {
+    /// Publishes a run starting on `key`, which holds its run lock.
+    pub(crate) fn begin(&self, key: &str, run_id: &str, command: String, metrics: ViralMetrics, started_at: DateTime<Utc>) {
+        let run = RunProgress {
+            run_id: run_id.to_string(),
+            command,
+            running: vec![],
+            completed_steps: 0,
+            remaining_steps: 0,
+            metrics,
+            started_at,
+            started: Instant::now(),
+        };
+        self.0.write().unwrap_or_else(|e| e.into_inner()).insert(key.to_string(), Arc::new(run));
+    }
+
+    /// Publishes `key`'s run as `update` changes it.
+    pub(crate) fn update(&self, key: &str, update: impl FnOnce(&mut RunProgress)) {
+        let mut runs = self.0.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(run) = runs.get_mut(key) {
+            update(Arc::make_mut(run));
+        }
+    }
+
+    pub(crate) fn end(&self, key: &str) {
+        self.0.write().unwrap_or_else(|e| e.into_inner()).remove(key);
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<Arc<RunProgress>> {
+        self.0.read().unwrap_or_else(|e| e.into_inner()).get(key).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AgentResult, CognitiveOrchestrator, MockBackend};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    const NAP: Duration = Duration::from_millis(80);
+
+    #[test]
+    fn polling_during_a_slow_run_sees_its_steps_advance() {
+        let mock = MockBackend::new()
+            .plan("fetch", (0..3).map(|idx| format!("nap:{}", idx)).collect::<Vec<_>>())
+            .on("nap", |rest| {
+                thread::sleep(NAP);
+                AgentResult::ok(rest)
+            });
+        let orch = Arc::new(CognitiveOrchestrator::builder().backend(Arc::new(mock)).learning(false).build().unwrap());
+        assert!(orch.observe("ctx1").is_none());
+
+        let running = orch.clone();
+        let run = thread::spawn(move || running.process_report("fetch all".to_string(), "ctx1"));
+        let mut seen = vec![];
+        while !run.is_finished() {
+            if let Some(progress) = orch.observe("ctx1").and_then(|view| view.run) {
+                seen.push((progress.completed_steps, progress.remaining_steps, progress.running.clone()));
+                assert_eq!(progress.command, "fetch all");
+            }
+            thread::sleep(NAP / 8);
+        }
+        assert!(run.join().unwrap().success);
+
+        let completed: Vec<usize> = seen.iter().map(|(completed, ..)| *completed).collect();
+        assert!(completed.windows(2).all(|pair| pair[0] <= pair[1]), "{:?}", completed);
+        assert!(completed.contains(&1) && completed.contains(&2), "{:?}", completed);
+        assert!(seen.iter().any(|(_, remaining, running)| *remaining == 1 && running == &["nap:1"]), "{:?}", seen);
+        let view = orch.observe("ctx1").unwrap();
+        assert!(view.run.is_none() && view.elapsed.is_none());
+        assert_eq!(view.metrics, orch.get_context("ctx1").unwrap().viral_metrics);
+    }
+}
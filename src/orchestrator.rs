@@ -1,26 +1,332 @@
+use pyo3::exceptions::{PyRuntimeError, PyTimeoutError, PyTypeError, PyValueError};
 use pyo3::prelude::*;
-use pyo3::types::{PyDict, PyList};
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use pythonize::{depythonize, pythonize};
+use schemars::JsonSchema;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use pyo3::types::PyTuple;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::future::Future;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+use history::DEFAULT_HISTORY_LIMIT;
+use metrics::OrchestratorMetrics;
+use streaming::ProcessRun;
+use run_lock::RunLocks;
+use run_store::RunStore;
+use coalesce::Coalescer;
+use submission::SubmissionQueue;
+use observe::LiveRuns;
+use dispatch_policy::CompiledPolicy;
 use chrono::{DateTime, Utc};
+use context_map::{ContextMap, ContextSlot};
+use tracing::{debug, info, info_span, warn, Span};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+pub mod agent_modules;
+pub mod agents;
+pub mod anomaly;
+pub mod attributes;
+mod async_process;
+pub mod backend;
+pub mod batch;
+pub mod blackboard;
+pub mod budget;
+pub mod cancel;
+pub mod circuit;
+pub mod clock;
+mod coalesce;
+pub mod config;
+mod context_delta;
+pub mod context_handle;
+pub mod context_map;
+pub mod context_store;
+mod coroutine;
+pub mod dispatch_policy;
+pub mod embedding;
+pub mod error;
+pub mod estimate;
+pub mod events;
+pub mod failure;
+pub mod goals;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod history;
+pub mod idempotency;
+pub mod input;
+pub mod inspect;
+pub mod journal;
+pub mod learning;
+pub mod lineage;
+pub mod memory;
+pub mod metrics;
+pub mod metrics_history;
+pub mod mwpm;
+pub mod observe;
+#[cfg(feature = "otel")]
+pub mod otel;
+pub mod persistence;
+pub mod planner_input;
+pub mod plan_validator;
+pub mod planning;
+pub mod portable;
+pub mod post_process;
+pub mod preflight;
+pub mod propagation;
+pub mod quantum;
+pub mod rate_limit;
+pub mod redact;
+pub mod report;
+pub mod result_cache;
+pub mod retry;
+pub mod run_diff;
+pub mod run_lock;
+pub mod run_store;
+pub mod schema;
+pub mod search;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod shutdown;
+pub mod snapshot;
+pub mod streaming;
+pub mod submission;
+pub mod subprocess_agent;
+pub mod subtask;
+pub mod tenant;
+pub mod timing;
+pub mod trash;
+pub mod tuning;
+pub mod viral;
+pub mod webhook;
+pub mod worker_pool;
+pub mod workload;
+
+pub use agents::{
+    Agent, AgentRegistry, ContentAgent, EvalAgent, HookAgent, LlmAgent, MemoryAgent, MwpmAgent, PyAgent, SpreadAgent,
+    ViralAgent, ViralSimulation,
+};
+pub use anomaly::{Anomaly, AnomalyLog, MAX_PENDING_ANOMALIES, SIMILAR_ANOMALIES};
+pub use attributes::{
+    attributes_bytes, Attributes, ATTRIBUTE_REFERENCE_PREFIX, DEFAULT_MAX_ATTRIBUTE_BYTES, VIRAL_ATTRIBUTE_PREFIX,
+};
+pub use clock::{Clock, FixedClock, SystemClock};
+pub use agent_modules::{AgentAvailability, AgentKind, AgentModule, AgentModuleConfig, AgentModules};
+pub use backend::{AgentBackend, MockBackend, PythonBackend};
+pub use batch::BatchOutcome;
+pub use blackboard::Blackboard;
+pub use budget::{Budget, BudgetStatus, BudgetUsage, Budgets};
+pub use cancel::{CancelToken, Cancellations};
+pub use circuit::{AgentHealth, CircuitBreakers, CircuitPolicy, CircuitState};
+pub use config::{CognitiveOrchestratorBuilder, Config, ConfigError, QdrantConfig, RetryConfig};
+pub use context_handle::ContextHandle;
+pub use context_store::{ContextStore, FileContextStore};
+#[cfg(feature = "redb")]
+pub use context_store::RedbContextStore;
+#[cfg(feature = "sqlite")]
+pub use context_store::SqliteContextStore;
+pub use dispatch_policy::{DispatchPolicy, SubtaskPattern};
+pub use embedding::{Embedder, HashEmbedder};
+#[cfg(feature = "embeddings")]
+pub use embedding::CandleEmbedder;
+pub use error::OrchestratorError;
+pub use estimate::{PlanEstimate, SubtaskEstimate};
+pub use events::{BusEvent, BusPayload, EventBus, GoalsCompleted, GoalsExpired, MetricsUpdate, Subscription};
+pub use failure::{DebugDecision, DebugStrategies, DebugStrategy, FailureClass};
+pub use goals::{Goal, GoalExpiry, GoalPriority, GoalStatus};
+pub use history::{ExecutionHistory, ExecutionRecord, HistoryFormat};
+pub use idempotency::{CompletedKey, IdempotencyKeys, IdempotentRun, DEFAULT_IDEMPOTENCY_TTL};
+pub use input::{InputPolicy, DEFAULT_MAX_COMMAND_BYTES};
+pub use inspect::{ContextStats, ContextSummary};
+pub use journal::{FsyncPolicy, IncompleteRun, Journal, JournalConfig, JournalRecord, DEFAULT_JOURNAL_KEEP, DEFAULT_JOURNAL_MAX_BYTES};
+pub use learning::{LearnedPlan, LearningConfig, DEFAULT_LEARNED_PLANS, DEFAULT_REUSE_THRESHOLD};
+pub use lineage::{GoalConflict, Lineage, MergeReport, MergeStrategy, MetricsMerge, DEFAULT_DEDUPE_SIMILARITY};
+pub use memory::{MemoryHit, MemoryMeta, MemoryStore, MemoryVectors, Quantization};
+pub use metrics::{AgentLatency, LATENCY_SAMPLES};
+pub use metrics_history::{MetricsHistory, MetricsRecorder, MetricsSample, MetricsTrend, DEFAULT_METRICS_HISTORY_LIMIT};
+pub use mwpm::{MwpmDecoder, MwpmReport};
+pub use observe::{ContextView, RunProgress};
+pub use persistence::{LoadReport, PersistenceFormat};
+pub use planner_input::{PlannerGoal, PlannerInput, PlannerInputConfig, PlannerRecord};
+pub use plan_validator::{FindingKind, PlanFinding, PlanValidator};
+pub use planning::{NodeId, Plan, PlanNode, PlanTemplate, PlanTemplates, PlanTrigger};
+pub use portable::{ContextExport, ExportedSnapshot, ImportError, EXPORT_SCHEMA_VERSION};
+pub use post_process::{ExtractJsonBlock, MaxLengthTruncate, PostProcessor, PostProcessors, TrimWhitespace};
+pub use preflight::{CheckStatus, PreflightCheck, PreflightReport};
+pub use propagation::{Graph, Topology, ViralConfig, DEFAULT_VIRALITY_THRESHOLD};
+pub use quantum::{AmplificationResult, NoiseModel, QuantumAmplifier, MAX_SIMULATED_QUBITS};
+pub use rate_limit::{RateLimit, RateLimits};
+pub use redact::Redactor;
+pub use report::ProcessReport;
+pub use result_cache::{CacheStats, ResultCache, DEFAULT_CACHE_EXCLUDED};
+pub use retry::{RetryPolicy, RetryPredicate};
+pub use run_diff::{compare_runs, compare_runs_with, LatencyRegression, MetricDelta, RunDiff, StatusFlip, DEFAULT_LATENCY_REGRESSION_PCT};
+pub use run_lock::RunLockHolder;
+pub use schema::export_schemas;
+pub use search::{MemoryFilter, MemorySearchHit, RRF_K};
+pub use shutdown::{InterruptedRun, ShutdownHandle, ShutdownReport};
+pub use snapshot::{ContextDiff, ContextSnapshot, ContextSnapshots, MemoryVectorChange, MetricsDelta};
+pub use streaming::{ProcessEvent, ProcessStream};
+pub use submission::{Overflow, Priority, QueueConfig, Submission, DEFAULT_QUEUE_DEPTH};
+pub use subprocess_agent::{SubprocessAgent, SubprocessConfig, DEFAULT_SUBPROCESS_RESTARTS, DEFAULT_SUBPROCESS_TIMEOUT, SUBPROCESS_PROTOCOL};
+pub use subtask::Subtask;
+pub use tenant::{validate_tenant, DEFAULT_TENANT};
+pub use timing::{hold_gil, RunTiming};
+pub use trash::TrashedContext;
+pub use tuning::{AutoTune, TuneReport, TuneTrial, DEFAULT_TUNE_ITERS};
+pub use viral::{PropagationReport, PropagationState, ViralParams, ViralPropagator};
+pub use webhook::{EventFilter, WebhookConfig, WebhookStatus, DEFAULT_WEBHOOK_ATTEMPTS, DEFAULT_WEBHOOK_QUEUE};
+#[cfg(feature = "webhooks")]
+pub use webhook::WebhookNotifier;
+pub use worker_pool::{WorkerPool, WorkerPoolConfig, DEFAULT_WORKER_DRAIN_TIMEOUT};
+pub use workload::{
+    run_workload, workload_backend, Arrival, CommandKind, CommandMix, ContextDistribution, KindReport, LatencyPercentiles,
+    WorkloadGenerator, WorkloadReport, WorkloadRequest, WorkloadSpec,
+};
+
+#[pyclass(module = "sovereign_cli")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct AgentResult {
+    #[pyo3(get)]
     pub output: String,
+    #[pyo3(get)]
     pub status: bool,
     pub metadata: HashMap<String, serde_json::Value>,
+    /// Also mirrored into `metadata["error"]` for consumers that only read metadata.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<OrchestratorError>,
+}
+
+impl AgentResult {
+    /// Metadata key for the virality score a viral simulation reached.
+    pub const VIRALITY: &'static str = "virality";
+    /// Metadata key for the tokens an LLM call used, reported or estimated.
+    pub const TOKENS_USED: &'static str = "tokens";
+    /// Metadata key for the dispatch's wall time in milliseconds.
+    pub const TIMING: &'static str = timing::TIMING_TOTAL_MS;
+    /// Metadata key for the id of the `process` run that dispatched the subtask.
+    pub const RUN_ID: &'static str = "_run_id";
+
+    /// Successful result with no metadata.
+    pub fn ok(output: impl Into<String>) -> Self {
+        Self { output: output.into(), status: true, metadata: HashMap::new(), error: None }
+    }
+
+    /// Failed result with no metadata and no error.
+    pub fn fail(output: impl Into<String>) -> Self {
+        Self { status: false, ..Self::ok(output) }
+    }
+
+    /// `metadata[key]` if it is a number.
+    pub fn get_f64(&self, key: &str) -> Option<f64> {
+        self.metadata.get(key).and_then(serde_json::Value::as_f64)
+    }
+
+    /// `metadata[key]` if it is a string.
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.metadata.get(key).and_then(serde_json::Value::as_str)
+    }
+
+    /// `metadata[key]` if it is a bool.
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        self.metadata.get(key).and_then(serde_json::Value::as_bool)
+    }
+
+    /// `metadata[key]` if it deserializes as a `T`.
+    pub fn get_deserialized<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        self.metadata.get(key).and_then(|value| T::deserialize(value).ok())
+    }
+
+    /// Sets `metadata[key]`; a value that doesn't serialize is logged and left out.
+    pub fn set(&mut self, key: &str, value: impl Serialize) {
+        match serde_json::to_value(value) {
+            Ok(value) => {
+                self.metadata.insert(key.to_string(), value);
+            }
+            Err(err) => warn!("Metadata {:?} not set: {}", key, err),
+        }
+    }
+
+    fn failed(output: String, err: OrchestratorError) -> Self {
+        let mut result = Self::fail(output);
+        result.set("error", &err);
+        Self { error: Some(err), ..result }
+    }
+
+    /// Failed result whose output is `"{label}: {err}"`.
+    fn from_error(label: &str, err: OrchestratorError) -> Self {
+        Self::failed(format!("{}: {}", label, err), err)
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[pyclass(module = "sovereign_cli")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Context {
+    #[pyo3(get)]
     pub context_id: String,
-    pub active_goals: Vec<String>,
-    pub memory_vectors: Vec<Vec<f64>>,
+    /// Contexts of different tenants may share an id; omitted for the default tenant.
+    #[pyo3(get)]
+    #[serde(default = "tenant::default_tenant", skip_serializing_if = "tenant::is_default_tenant")]
+    pub tenant: String,
+    /// Completed goals stay listed, with their status; strings from older
+    /// snapshots load as active goals.
+    #[pyo3(get)]
+    pub active_goals: Vec<Goal>,
+    pub memory_vectors: MemoryVectors,
+    /// The text each `remember`ed vector was embedded from, by index; `None` (or
+    /// past the end) for vectors added directly.
+    #[pyo3(get)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub memory_texts: Vec<Option<String>>,
+    /// What each vector records, by index, like `memory_texts`; learned successes
+    /// carry their plan here.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub memory_payloads: Vec<Option<serde_json::Map<String, serde_json::Value>>>,
+    /// When each vector was added and how often recall returned it, by index;
+    /// vectors from before it was kept count as added with the context.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub memory_meta: Vec<MemoryMeta>,
+    /// Per-day rate at which recall discounts older vectors, scoring each
+    /// `cosine * exp(-memory_decay * age_days)`. Zero keeps them all at full weight.
+    #[pyo3(get)]
+    #[serde(default, skip_serializing_if = "memory::no_decay")]
+    pub memory_decay: f64,
+    #[pyo3(get)]
     pub viral_metrics: ViralMetrics,
+    /// Replaces the orchestrator's `ViralConfig` for this context's viral subtasks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub viral_config: Option<ViralConfig>,
+    /// Where the last native spread measurement stopped, so the next one continues
+    /// it; dropped once the engagement nodes or topology change. Never saved.
+    #[serde(skip)]
+    pub propagation: Option<PropagationState>,
+    /// `viral_metrics` after each viral subtask, oldest first.
+    #[serde(default, skip_serializing_if = "MetricsHistory::is_empty")]
+    pub metrics_history: MetricsHistory,
+    /// Set on contexts made by `fork_context`.
+    #[pyo3(get)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lineage: Option<Lineage>,
+    /// Application data set with `set_attribute`, such as a campaign id or A/B
+    /// arm. The planner is shown it, and subtasks reference it as `{{attr.<key>}}`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub attributes: Attributes,
+    #[pyo3(get)]
     pub created_at: DateTime<Utc>,
+    /// Bumped whenever the orchestrator hands the context to a subtask; drives TTL
+    /// and LRU eviction. Snapshots from before this field load as freshly accessed.
+    #[pyo3(get)]
+    #[serde(default = "Utc::now")]
+    pub last_accessed: DateTime<Utc>,
+    /// Fields this version does not know, e.g. from a newer export, kept so they
+    /// survive being saved or exported again.
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[pyclass(module = "sovereign_cli", get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct ViralMetrics {
     pub virality_score: f64,
     pub engagement_nodes: usize,
@@ -29,246 +335,4387 @@ pub struct ViralMetrics {
     pub quantum_fidelity: f64,
 }
 
-pub struct CognitiveOrchestrator {
-    contexts: HashMap<String, Context>,
-    viral_propagator: ViralPropagator,
-    quantum_amplifier: QuantumAmplifier,
-}
-
-impl CognitiveOrchestrator {
-    pub fn new() -> Self {
+/// What a context starts with before any viral subtask has run.
+impl Default for ViralMetrics {
+    fn default() -> Self {
         Self {
-            contexts: HashMap::new(),
-            viral_propagator: ViralPropagator::new(),
-            quantum_amplifier: QuantumAmplifier::new(),
+            virality_score: 0.0,
+            engagement_nodes: 32,
+            hook_rate: 0.05,
+            amplification_factor: 1.0,
+            quantum_fidelity: 0.99,
         }
     }
+}
 
-    pub fn proactive_plan(&mut self, command: String, context_id: &str) -> Vec<String> {
-        // Create context if doesn't exist
-        if !self.contexts.contains_key(context_id) {
-            self.contexts.insert(context_id.to_string(), Context {
-                context_id: context_id.to_string(),
-                active_goals: vec![],
-                memory_vectors: vec![],
-                viral_metrics: ViralMetrics {
-                    virality_score: 0.0,
-                    engagement_nodes: 32,
-                    hook_rate: 0.05,
-                    amplification_factor: 1.0,
-                    quantum_fidelity: 0.99,
-                },
-                created_at: Utc::now(),
-            });
+fn to_json<T: Serialize>(value: &T) -> PyResult<String> {
+    serde_json::to_string(value).map_err(|e| PyRuntimeError::new_err(e.to_string()))
+}
+
+fn from_json<'a, T: Deserialize<'a>>(json: &'a str) -> PyResult<T> {
+    serde_json::from_str(json).map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+fn seconds(secs: f64) -> PyResult<Duration> {
+    Duration::try_from_secs_f64(secs).map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+fn py_viral_config(config: &PyAny) -> PyResult<ViralConfig> {
+    let config: ViralConfig = depythonize(config).map_err(|e| PyValueError::new_err(e.to_string()))?;
+    match config.invalid() {
+        Some((field, message)) => Err(PyValueError::new_err(format!("{} {}", field, message))),
+        None => Ok(config),
+    }
+}
+
+fn py_topology(topology: &PyAny) -> PyResult<Topology> {
+    let topology: Topology = depythonize(topology).map_err(|e| PyValueError::new_err(e.to_string()))?;
+    match topology.invalid() {
+        Some((field, message)) => Err(PyValueError::new_err(format!("topology.{} {}", field, message))),
+        None => Ok(topology),
+    }
+}
+
+/// A run from `process`'s Python arguments.
+fn py_run(
+    command: String,
+    context_id: String,
+    timeout: Option<f64>,
+    budget: Option<&PyAny>,
+    run_id: Option<String>,
+    priority: Option<&str>,
+) -> PyResult<ProcessRun> {
+    let priority = match priority {
+        Some(name) => Priority::parse(name)
+            .ok_or_else(|| PyValueError::new_err(format!("priority must be high, normal or low, got {:?}", name)))?,
+        None => Priority::default(),
+    };
+    let submission = Submission { timeout: timeout.map(seconds).transpose()?, run_id, priority };
+    let run = ProcessRun::submitted(command, context_id, submission);
+    match budget {
+        Some(budget) => {
+            let budget: Budget = depythonize(budget).map_err(|e| PyValueError::new_err(e.to_string()))?;
+            Ok(run.with_budget(budget))
         }
+        None => Ok(run),
+    }
+}
 
-        // Viral-specific proactive planning
-        if command.contains("viral") || command.contains("engage") {
-            return vec![
-                "gen content".to_string(),
-                "inject hook".to_string(),
-                "amplify MWPM".to_string(),
-                "measure spread".to_string(),
-                "eval metrics".to_string(),
-            ];
-        }
-
-        // Use Python planner agent for general decomposition
-        Python::with_gil(|py| {
-            let planner_module = py.import("python.agents.planner_agent");
-            if let Ok(module) = planner_module {
-                if let Ok(planner_class) = module.getattr("PlannerAgent") {
-                    if let Ok(planner_inst) = planner_class.call0() {
-                        if let Ok(subtasks_py) = planner_inst.call_method1("decompose", (command.clone(),)) {
-                            if let Ok(subtasks) = subtasks_py.extract::<Vec<String>>() {
-                                return subtasks;
-                            }
-                        }
-                    }
-                }
-            }
-            vec![command] // Fallback to original command
-        }).unwrap_or(vec![command])
+/// Pickle support: rebuild through the class's `from_json` staticmethod.
+fn reduce_via_json<T: pyo3::PyClass + Serialize>(py: Python, value: &T) -> PyResult<(PyObject, (String,))> {
+    let ctor = py.get_type::<T>().getattr("from_json")?;
+    Ok((ctor.into(), (to_json(value)?,)))
+}
+
+#[pymethods]
+impl AgentResult {
+    #[new]
+    #[pyo3(signature = (output, status, metadata=None))]
+    fn py_new(output: String, status: bool, metadata: Option<&PyAny>) -> PyResult<Self> {
+        let metadata = match metadata {
+            Some(obj) => depythonize(obj)?,
+            None => HashMap::new(),
+        };
+        Ok(Self { status, metadata, ..Self::ok(output) })
     }
 
-    pub fn self_debug(&mut self, result: &AgentResult, orig_cmd: &str, context_id: &str) -> bool {
-        if !result.status {
-            // Log anomaly to Qdrant (local embed)
-            Python::with_gil(|py| {
-                let mem_module = py.import("python.memory");
-                if let Ok(module) = mem_module {
-                    if let Ok(mem_class) = module.getattr("QdrantMemory") {
-                        if let Ok(mem_inst) = mem_class.call0() {
-                            let payload = PyDict::new(py);
-                            payload.set_item("type", "error")?;
-                            let _ = mem_inst.call_method1(
-                                "store_context",
-                                (format!("Anomaly: {}", result.output), context_id, payload)
-                            );
-                        }
-                    }
-                }
-                Ok::<(), PyErr>(())
-            }).unwrap_or(());
+    #[getter(metadata)]
+    fn py_metadata(&self, py: Python) -> PyResult<PyObject> {
+        Ok(pythonize(py, &self.metadata)?)
+    }
 
-            // Viral debug: if result.output.contains("low virality")
-            if result.output.contains("low virality") {
-                let alt = "replan viral alt strategy";
-                Python::with_gil(|py| {
-                    let debug_module = py.import("python.agents.debug_agent");
-                    if let Ok(module) = debug_module {
-                        if let Ok(debug_class) = module.getattr("DebugAgent") {
-                            if let Ok(debug_inst) = debug_class.call0() {
-                                if let Ok(new_plan) = debug_inst.call_method1("re_plan", (alt, context_id)) {
-                                    if let Ok(plan_str) = new_plan.extract::<String>() {
-                                        eprintln!("Re-plan: {}", plan_str);
-                                        return Ok(true);
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    Ok::<bool, PyErr>(false)
-                }).unwrap_or(false)
-            } else {
-                false
-            }
-        } else {
-            false
+    /// `None`, or a dict with a `kind` key plus variant fields (e.g. `traceback`).
+    #[getter(error)]
+    fn py_error(&self, py: Python) -> PyResult<PyObject> {
+        match &self.error {
+            Some(err) => Ok(pythonize(py, err)?),
+            None => Ok(py.None()),
         }
     }
 
-    pub fn process(&mut self, command: String, context_id: &str) -> String {
-        let subtasks = self.proactive_plan(command.clone(), context_id);
-        let mut outputs = vec![];
+    #[pyo3(name = "to_json")]
+    fn py_to_json(&self) -> PyResult<String> {
+        to_json(self)
+    }
 
-        for sub in subtasks {
-            let res = self.dispatch(sub.clone(), context_id);
-            outputs.push(res.output.clone());
+    #[staticmethod]
+    #[pyo3(name = "from_json")]
+    fn py_from_json(json: &str) -> PyResult<Self> {
+        from_json(json)
+    }
 
-            if self.self_debug(&res, &sub, context_id) {
-                break;
-            }
+    fn __reduce__(&self, py: Python) -> PyResult<(PyObject, (String,))> {
+        reduce_via_json(py, self)
+    }
+
+    fn __eq__(&self, other: &Self) -> bool {
+        self == other
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "AgentResult(status={}, output={:?}, metadata_keys={})",
+            if self.status { "True" } else { "False" },
+            self.output,
+            self.metadata.len()
+        )
+    }
+}
+
+impl Context {
+    /// The orchestrator's key for this context: its id, qualified by its tenant
+    /// unless that is the default one.
+    pub fn key(&self) -> String {
+        tenant::context_key(&self.tenant, &self.context_id)
+    }
+
+    /// Appends a memory vector; every vector in a context shares one dimension.
+    /// It is stamped with `last_accessed`, which the orchestrator sets to now as it
+    /// hands out the context.
+    pub fn add_memory(&mut self, vec: Vec<f64>) -> Result<usize, OrchestratorError> {
+        let idx = self.memory_vectors.push(&vec)?;
+        self.memory_meta.resize(idx, MemoryMeta::new(self.created_at));
+        self.memory_meta.push(MemoryMeta::new(self.last_accessed));
+        Ok(idx)
+    }
+
+    /// `add_memory`, keeping `text` alongside the vector.
+    pub fn add_memory_text(&mut self, vec: Vec<f64>, text: String) -> Result<usize, OrchestratorError> {
+        let idx = self.add_memory(vec)?;
+        self.memory_texts.resize(idx, None);
+        self.memory_texts.push(Some(text));
+        Ok(idx)
+    }
+
+    pub fn memory_text(&self, idx: usize) -> Option<&str> {
+        self.memory_texts.get(idx).and_then(Option::as_deref)
+    }
+
+    /// `add_memory_text`, also keeping `payload` alongside the vector.
+    pub fn add_memory_payload(
+        &mut self,
+        vec: Vec<f64>,
+        text: String,
+        payload: serde_json::Map<String, serde_json::Value>,
+    ) -> Result<usize, OrchestratorError> {
+        let idx = self.add_memory_text(vec, text)?;
+        self.memory_payloads.resize(idx, None);
+        self.memory_payloads.push(Some(payload));
+        Ok(idx)
+    }
+
+    pub fn memory_payload(&self, idx: usize) -> Option<&serde_json::Map<String, serde_json::Value>> {
+        self.memory_payloads.get(idx).and_then(Option::as_ref)
+    }
+
+    pub fn memory_meta(&self, idx: usize) -> MemoryMeta {
+        self.memory_meta.get(idx).copied().unwrap_or(MemoryMeta::new(self.created_at))
+    }
+
+    /// Brute-force cosine search returning `(index, similarity)`, best first.
+    pub fn nearest(&self, query: &[f64], k: usize) -> Vec<(usize, f64)> {
+        self.memory_vectors.nearest(query, k)
+    }
+
+    /// `nearest` with each similarity scaled by the vector's `MemoryMeta::decay`
+    /// under `memory_decay` at `now`, best first.
+    pub fn decayed_nearest(&self, query: &[f64], k: usize, now: DateTime<Utc>) -> Vec<(usize, f64)> {
+        let mut scored: Vec<(usize, f64)> = self
+            .nearest(query, self.memory_vectors.len())
+            .into_iter()
+            .map(|(idx, similarity)| (idx, similarity * self.memory_meta(idx).decay(self.memory_decay, now)))
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        scored.truncate(k);
+        scored
+    }
+
+    /// Counts a recall returning the vector at `idx`.
+    fn memory_recalled(&mut self, idx: usize) {
+        if idx < self.memory_vectors.len() {
+            self.memory_meta.resize(self.memory_vectors.len(), MemoryMeta::new(self.created_at));
+            self.memory_meta[idx].access_count += 1;
+        }
+    }
+
+    /// Drops the vectors whose `MemoryMeta::retention` at `now` is below
+    /// `min_score`, then all but the `max_entries` best retained (newer ones
+    /// winning ties), with their texts, payloads and metadata. The rest keep their
+    /// order but not their indices. Returns how many were dropped.
+    pub fn prune_memory(&mut self, min_score: f64, max_entries: Option<usize>, now: DateTime<Utc>) -> usize {
+        let len = self.memory_vectors.len();
+        let retention: Vec<f64> = (0..len).map(|idx| self.memory_meta(idx).retention(self.memory_decay, now)).collect();
+        let mut ranked: Vec<usize> = (0..len).filter(|&idx| retention[idx] >= min_score).collect();
+        ranked.sort_by(|&a, &b| retention[b].total_cmp(&retention[a]).then(b.cmp(&a)));
+        ranked.truncate(max_entries.unwrap_or(len));
+        if ranked.len() == len {
+            return 0;
         }
 
-        // Learn success: if no err, Qdrant upsert (local embed)
-        serde_json::to_string(&outputs).unwrap_or_else(|_| outputs.join("\n"))
+        let mut keep = vec![false; len];
+        for idx in ranked {
+            keep[idx] = true;
+        }
+        self.memory_meta.resize(len, MemoryMeta::new(self.created_at));
+        self.memory_vectors.retain(|idx| keep[idx]);
+        memory::retain_indexed(&mut self.memory_texts, &keep);
+        memory::retain_indexed(&mut self.memory_payloads, &keep);
+        memory::retain_indexed(&mut self.memory_meta, &keep);
+        len - self.memory_vectors.len()
     }
+}
 
-    pub fn dispatch(&mut self, sub_task: String, context_id: &str) -> AgentResult {
-        if sub_task.starts_with("query llm") {
-            self.dispatch_llm(&sub_task)
-        } else if sub_task.contains("viral") {
-            self.dispatch_viral(&sub_task, context_id)
-        } else {
-            AgentResult {
-                output: format!("Unknown subtask: {}", sub_task),
-                status: false,
-                metadata: HashMap::new(),
-            }
+#[pymethods]
+impl Context {
+    #[getter(memory_vectors)]
+    fn py_memory_vectors(&self) -> Vec<Vec<f64>> {
+        self.memory_vectors.to_nested()
+    }
+
+    /// A dict of the context's attributes.
+    #[getter(attributes)]
+    fn py_attributes(&self, py: Python) -> PyResult<PyObject> {
+        Ok(pythonize(py, &self.attributes)?)
+    }
+
+    /// One dict per memory vector, with `inserted_at` and `access_count`.
+    #[getter(memory_meta)]
+    fn py_memory_meta(&self, py: Python) -> PyResult<PyObject> {
+        let meta: Vec<MemoryMeta> = (0..self.memory_vectors.len()).map(|idx| self.memory_meta(idx)).collect();
+        Ok(pythonize(py, &meta)?)
+    }
+
+    #[pyo3(name = "nearest")]
+    fn py_nearest(&self, query: Vec<f64>, k: usize) -> Vec<(usize, f64)> {
+        self.nearest(&query, k)
+    }
+
+    #[pyo3(name = "to_json")]
+    fn py_to_json(&self) -> PyResult<String> {
+        to_json(self)
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "from_json")]
+    fn py_from_json(json: &str) -> PyResult<Self> {
+        from_json(json)
+    }
+
+    fn __reduce__(&self, py: Python) -> PyResult<(PyObject, (String,))> {
+        reduce_via_json(py, self)
+    }
+
+    fn __eq__(&self, other: &Self) -> bool {
+        self == other
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Context(context_id={:?}, goals={}, memory_vectors={}, created_at={})",
+            self.context_id,
+            self.active_goals.len(),
+            self.memory_vectors.len(),
+            self.created_at.to_rfc3339()
+        )
+    }
+}
+
+#[pymethods]
+impl ViralMetrics {
+    #[pyo3(name = "to_json")]
+    fn py_to_json(&self) -> PyResult<String> {
+        to_json(self)
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "from_json")]
+    fn py_from_json(json: &str) -> PyResult<Self> {
+        from_json(json)
+    }
+
+    fn __reduce__(&self, py: Python) -> PyResult<(PyObject, (String,))> {
+        reduce_via_json(py, self)
+    }
+
+    fn __eq__(&self, other: &Self) -> bool {
+        self == other
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "ViralMetrics(virality_score={}, engagement_nodes={}, hook_rate={}, amplification_factor={}, quantum_fidelity={})",
+            self.virality_score,
+            self.engagement_nodes,
+            self.hook_rate,
+            self.amplification_factor,
+            self.quantum_fidelity
+        )
+    }
+}
+
+/// Imports the module and instantiates the class with no arguments.
+fn agent_instance<'py>(py: Python<'py>, agent: &AgentModule) -> Result<&'py PyAny, OrchestratorError> {
+    let target = agent.target();
+    py.import(agent.module.as_str())
+        .map_err(|e| OrchestratorError::import(py, &agent.module, e))?
+        .getattr(agent.class.as_str())
+        .map_err(|e| OrchestratorError::attribute(&target, e))?
+        .call0()
+        .map_err(|e| OrchestratorError::call(py, &target, e))
+}
+
+fn call_agent<'py>(
+    py: Python<'py>,
+    agent: &'py PyAny,
+    method: &str,
+    args: impl IntoPy<Py<PyTuple>>,
+) -> Result<&'py PyAny, OrchestratorError> {
+    let (target, result, elapsed_ms) = call_method(agent, method, args)?;
+    call_result(py, &target, result, elapsed_ms)
+}
+
+/// `call_agent` with `args`, retried with `fallback` when the call raises
+/// `TypeError`, as an agent written for the method's older signature does.
+fn call_agent_or<'py>(
+    py: Python<'py>,
+    agent: &'py PyAny,
+    method: &str,
+    args: impl IntoPy<Py<PyTuple>>,
+    fallback: impl IntoPy<Py<PyTuple>>,
+) -> Result<&'py PyAny, OrchestratorError> {
+    let (target, result, elapsed_ms) = call_method(agent, method, args)?;
+    match result {
+        Err(err) if err.is_instance_of::<PyTypeError>(py) => {
+            debug!(call = %target, "{}; retrying with the older arguments", err);
+            call_agent(py, agent, method, fallback)
         }
+        result => call_result(py, &target, result, elapsed_ms),
     }
+}
 
-    fn dispatch_llm(&self, sub_task: &str) -> AgentResult {
-        let prompt = sub_task.replace("query llm ", "");
+/// Calls `agent.method`, timed, returning the call's target name and elapsed
+/// milliseconds with what it returned.
+fn call_method<'py>(
+    agent: &'py PyAny,
+    method: &str,
+    args: impl IntoPy<Py<PyTuple>>,
+) -> Result<(String, PyResult<&'py PyAny>, f64), OrchestratorError> {
+    let target = format!("{}.{}", agent.get_type().name().unwrap_or("<agent>"), method);
+    let callable = agent.getattr(method).map_err(|e| OrchestratorError::attribute(&target, e))?;
+    let started = Instant::now();
+    #[cfg(feature = "otel")]
+    let result = otel::with_python_context(agent.py(), || callable.call1(args).and_then(coroutine::resolve));
+    #[cfg(not(feature = "otel"))]
+    let result = callable.call1(args).and_then(coroutine::resolve);
+    let elapsed = started.elapsed();
+    timing::python_call(elapsed);
+    metrics::observe_python_call(&target, elapsed.as_secs_f64());
+    Ok((target, result, elapsed.as_secs_f64() * 1000.0))
+}
 
-        Python::with_gil(|py| {
-            let llm_module = py.import("python.agents.llm_agent");
-            if let Ok(module) = llm_module {
-                if let Ok(llm_class) = module.getattr("LLMAgent") {
-                    if let Ok(llm_inst) = llm_class.call0() {
-                        if let Ok(output) = llm_inst.call_method1("generate", (prompt,)) {
-                            if let Ok(output_str) = output.extract::<String>() {
-                                return AgentResult {
-                                    output: output_str,
-                                    status: true,
-                                    metadata: HashMap::new(),
-                                };
-                            }
-                        }
-                    }
+fn call_result<'py>(
+    py: Python<'py>,
+    target: &str,
+    result: PyResult<&'py PyAny>,
+    elapsed_ms: f64,
+) -> Result<&'py PyAny, OrchestratorError> {
+    result
+        .inspect(|_| debug!(call = %target, elapsed_ms, "python call"))
+        .map_err(|e| {
+            let err = OrchestratorError::call(py, target, e);
+            warn!(call = %target, elapsed_ms, traceback = err.traceback(), "{}", err);
+            err
+        })
+}
+
+fn dispatch_span(sub_task: &str, context_id: &str) -> Span {
+    info_span!("dispatch", subtask = sub_task, context_id)
+}
+
+/// Logs a failed result as `anomaly`, classifies it, and acts on the class's
+/// strategy; a re-plan asks the debug agent, showing it the `similar` past
+/// anomalies. Returns the decision, for a failure, with the replacement plan for
+/// the failed subtask when one was produced.
+fn debug_failure(
+    backend: &dyn AgentBackend,
+    memory: Option<&dyn MemoryStore>,
+    anomalies: &AnomalyLog,
+    strategies: &DebugStrategies,
+    result: &AgentResult,
+    anomaly: Anomaly,
+    similar: &[MemorySearchHit],
+) -> Option<(DebugDecision, Option<Plan>)> {
+    let context_id = anomaly.context_id.clone();
+    let subtask = anomalies.redactor().redacted(&anomaly.subtask);
+    let _span = info_span!("self_debug", subtask = subtask.as_str(), context_id, status = result.status).entered();
+    let decision = strategies.decide(&anomaly.subtask, result)?;
+    // Log anomaly to Qdrant, keeping it for a later try if the store fails
+    anomalies.record(anomaly, backend, memory);
+    info!(class = %decision.class, strategy = %decision.strategy, "Failure classified");
+
+    let plan = match decision.strategy {
+        DebugStrategy::Replan => {
+            let alt = anomaly::replan_prompt(&decision.replan_request(), similar);
+            match backend.re_plan(&alt, &context_id) {
+                Ok(plan) => {
+                    let subtasks: Vec<String> = plan.subtasks().iter().map(|step| anomalies.redactor().redacted(step)).collect();
+                    info!("Re-plan: {:?}", subtasks);
+                    Some(plan)
+                }
+                Err(err) => {
+                    warn!("Re-plan failed: {}", err);
+                    None
                 }
             }
-            AgentResult {
-                output: "LLM Error".to_string(),
-                status: false,
-                metadata: HashMap::new(),
+        }
+        DebugStrategy::Retry => Some(decision.retry()),
+        DebugStrategy::Skip | DebugStrategy::Abort => None,
+    };
+    Some((decision, plan))
+}
+
+/// `plan_or_fallback`'s handling of the planner's answer.
+fn plan_fallback(command: String, planned: Result<Plan, OrchestratorError>) -> Result<Plan, OrchestratorError> {
+    match planned {
+        Ok(plan) => Ok(plan),
+        Err(err) if err.is_invalid_plan() => Err(err),
+        Err(err) => {
+            warn!("Planner fallback: {}", err);
+            Ok(Plan::from(vec![command]))
+        }
+    }
+}
+
+/// The context's `k` memory vectors nearest `query_vec` at `now`, under its decay.
+fn decayed_recall(context: &Context, query_vec: &[f64], k: usize, now: DateTime<Utc>) -> Result<Vec<(usize, f64)>, OrchestratorError> {
+    let dim = context.memory_vectors.dim();
+    if dim != 0 && query_vec.len() != dim {
+        return Err(OrchestratorError::DimensionMismatch { expected: dim, actual: query_vec.len() });
+    }
+    Ok(context.decayed_nearest(query_vec, k, now))
+}
+
+/// What a dispatch checks around the agent call, shared by every dispatch path.
+#[derive(Clone)]
+struct Guards {
+    rate_limits: RateLimits,
+    cache: ResultCache,
+    breakers: CircuitBreakers,
+    post_processors: PostProcessors,
+    /// For the subtask in the dispatch's tracing fields.
+    redactor: Redactor,
+}
+
+/// Runs `agent` on `ctx`, from the result cache when it has the answer, otherwise
+/// unless its circuit is open, once its rate limit allows, then through the
+/// post-processors for its kind. What it spent in Python is recorded in the result.
+fn execute_agent(
+    agent: &dyn Agent,
+    sub_task: &str,
+    ctx: &mut Context,
+    blackboard: &Blackboard,
+    guards: &Guards,
+) -> AgentResult {
+    let (result, spent) = timing::measure(|| {
+        guards.cache.get_or_execute(agent, sub_task, ctx, |ctx| {
+            if let Err(err) = guards.breakers.admit(agent) {
+                return AgentResult::from_error("Circuit Open", err);
             }
-        }).unwrap_or(AgentResult {
-            output: "LLM Error".to_string(),
-            status: false,
-            metadata: HashMap::new(),
+            guards.rate_limits.throttle(agent);
+            let result = agent.execute_with_blackboard(sub_task, ctx, blackboard);
+            guards.breakers.record(agent, &result);
+            result
         })
+    });
+    let mut result = guards.post_processors.apply(agent, sub_task, result);
+    timing::record_spent(&mut result, spent);
+    result
+}
+
+/// A routed dispatch that owns a working copy of its context, so it can run on any
+/// thread; the copy is written back with `CognitiveOrchestrator::complete_dispatch`,
+/// or merged with `absorb_dispatch` when it ran beside others of its wave.
+#[derive(Clone)]
+struct DispatchJob {
+    sub_task: String,
+    agent: Result<Arc<dyn Agent>, OrchestratorError>,
+    context: Context,
+    /// The run's, when the job belongs to one; empty otherwise.
+    blackboard: Blackboard,
+    guards: Guards,
+    /// The run's, when the job belongs to one; cuts the timeout and backoff waits short.
+    cancel: Option<CancelToken>,
+    /// 1-based, counting retries.
+    attempt: u32,
+}
+
+impl DispatchJob {
+    fn agent_name(&self) -> Option<String> {
+        self.agent.as_ref().ok().map(|agent| agent.name().to_string())
     }
 
-    fn dispatch_viral(&mut self, sub_task: &str, context_id: &str) -> AgentResult {
-        let context = self.contexts.get_mut(context_id).unwrap();
-        let nodes = context.viral_metrics.engagement_nodes;
-        let hook_rate = context.viral_metrics.hook_rate;
-
-        Python::with_gil(|py| {
-            let viral_module = py.import("python.agents.viral_agent");
-            if let Ok(module) = viral_module {
-                if let Ok(viral_class) = module.getattr("ViralAgent") {
-                    if let Ok(viral_inst) = viral_class.call0() {
-                        if let Ok(result_py) = viral_inst.call_method1("simulate_viral_engagement", (nodes, hook_rate)) {
-                            if let Ok(result_dict) = result_py.extract::<HashMap<String, serde_json::Value>>() {
-                                let virality = result_dict
-                                    .get("virality")
-                                    .and_then(|v| v.as_f64())
-                                    .unwrap_or(0.0);
-
-                                let status = virality > 0.8;
-
-                                return AgentResult {
-                                    output: format!(
-                                        "Viral: Virality={:.4}, Metrics: {}",
-                                        virality,
-                                        result_dict.get("metrics").unwrap_or(&serde_json::Value::String("N/A".to_string()))
-                                    ),
-                                    status,
-                                    metadata: result_dict.into_iter().map(|(k, v)| (k, v)).collect(),
-                                };
-                            }
-                        }
-                    }
+    fn with_blackboard(self, blackboard: &Blackboard) -> Self {
+        Self { blackboard: blackboard.clone(), ..self }
+    }
+
+    fn run(mut self) -> (AgentResult, Context) {
+        let result = match self.agent {
+            Ok(agent) => {
+                let _span = info_span!(
+                    "agent_call",
+                    agent = agent.name(),
+                    agent_kind = agent.kind().map_or("native", AgentKind::name),
+                    subtask = %self.guards.redactor.redacted(&self.sub_task),
+                    context_id = %self.context.key(),
+                    attempt = self.attempt,
+                )
+                .entered();
+                execute_agent(agent.as_ref(), &self.sub_task, &mut self.context, &self.blackboard, &self.guards)
+            }
+            Err(err) => unknown_subtask(err),
+        };
+        (result, self.context)
+    }
+
+    /// Runs on a helper thread and gives up after `timeout`, or once the run is
+    /// cancelled, then raises `TimeoutError` in the helper's Python thread so a
+    /// hung call aborts at its next bytecode boundary instead of running on. A
+    /// timed-out or cancelled job's context changes are discarded, so the context
+    /// comes back as `None`.
+    fn run_with_timeout(self, timeout: Option<Duration>) -> (AgentResult, Option<Context>) {
+        let Some(timeout) = timeout else {
+            let (result, context) = self.run();
+            return (result, Some(context));
+        };
+        let sub_task = self.sub_task.clone();
+        let cancel = self.cancel.clone();
+        // Python thread ident of the helper, or 0 until it has one.
+        let py_thread = Arc::new(AtomicI64::new(0));
+        let ident = py_thread.clone();
+        let (tx, rx) = mpsc::channel();
+        let span = Span::current();
+
+        let spawned = thread::Builder::new().name("subtask".to_string()).spawn(move || {
+            let _span = span.entered();
+            Python::with_gil(|py| {
+                let id = py.import("threading").and_then(|t| t.call_method0("get_ident")).and_then(|id| id.extract());
+                if let Ok(id) = id {
+                    ident.store(id, Ordering::SeqCst);
                 }
+            });
+            let _ = tx.send(self.run());
+        });
+        if let Err(err) = spawned {
+            let err = OrchestratorError::CallFailed { target: sub_task, message: err.to_string(), traceback: None };
+            return (AgentResult::from_error("Dispatch Error", err), None);
+        }
+
+        // Release the GIL while waiting, or the helper could never take it.
+        let waiting = cancel.clone();
+        let received = Python::with_gil(|py| py.allow_threads(move || recv_unless_cancelled(rx, timeout, waiting.as_ref())));
+        match received {
+            Ok((result, context)) => (result, Some(context)),
+            Err(mpsc::RecvTimeoutError::Timeout) if cancel.as_ref().is_some_and(CancelToken::is_cancelled) => {
+                interrupt_python_thread(py_thread.load(Ordering::SeqCst));
+                (AgentResult::from_error("Cancelled", OrchestratorError::Cancelled { subtask: sub_task }), None)
             }
-            AgentResult {
-                output: "Viral Error".to_string(),
-                status: false,
-                metadata: HashMap::new(),
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                interrupt_python_thread(py_thread.load(Ordering::SeqCst));
+                let timeout_ms = u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX);
+                let mut result =
+                    AgentResult::from_error("Timeout", OrchestratorError::Timeout { subtask: sub_task, timeout_ms });
+                result
+                    .metadata
+                    .insert("timeout".to_string(), serde_json::Value::from(timeout.as_secs_f64()));
+                (result, None)
             }
-        }).unwrap_or(AgentResult {
-            output: "Viral Error".to_string(),
-            status: false,
-            metadata: HashMap::new(),
-        })
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                let err = OrchestratorError::CallFailed {
+                    target: sub_task,
+                    message: "subtask thread panicked".to_string(),
+                    traceback: None,
+                };
+                (AgentResult::from_error("Dispatch Error", err), None)
+            }
+        }
+    }
+
+    /// Runs with up to `retry.max_attempts` attempts, backing off between them;
+    /// every attempt starts from the context as it was before the first. When
+    /// retries are enabled, `attempts` and `retry_latency` (seconds spent after
+    /// the first attempt) are recorded in the result metadata.
+    fn run_with_policy(self, retry: &RetryPolicy, timeout: Option<Duration>) -> (AgentResult, Option<Context>) {
+        if !retry.retries() {
+            return self.run_with_timeout(timeout);
+        }
+        let mut jitter = retry.jitter_seed.map_or_else(retry::Jitter::default, retry::Jitter::seeded);
+        let mut attempt = 1;
+        let (mut result, mut context) = self.clone().run_with_timeout(timeout);
+        let first_finished = Instant::now();
+
+        while retry.should_retry(&result, attempt) {
+            let delay = retry.backoff(attempt, &mut jitter);
+            let cancelled = Python::with_gil(|py| {
+                py.allow_threads(|| match &self.cancel {
+                    Some(cancel) => cancel.sleep(delay),
+                    None => {
+                        thread::sleep(delay);
+                        false
+                    }
+                })
+            });
+            if cancelled {
+                break;
+            }
+            attempt += 1;
+            (result, context) = DispatchJob { attempt, ..self.clone() }.run_with_timeout(timeout);
+        }
+
+        result.metadata.insert("attempts".to_string(), serde_json::Value::from(attempt));
+        result.metadata.insert(
+            "retry_latency".to_string(),
+            serde_json::Value::from(first_finished.elapsed().as_secs_f64()),
+        );
+        (result, context)
+    }
+}
+
+struct FinishedJob {
+    agent: Option<String>,
+    result: AgentResult,
+    context: Option<Context>,
+    duration: Duration,
+}
+
+/// Runs the jobs, one thread each when there are several, and returns them in
+/// order. Must not be called while holding the GIL.
+fn run_jobs(jobs: Vec<DispatchJob>, retry: &RetryPolicy, timeout: Option<Duration>, context_id: &str) -> Vec<FinishedJob> {
+    let parent = Span::current();
+    let run = |job: DispatchJob| {
+        let subtask = job.guards.redactor.redacted(&job.sub_task);
+        let span = info_span!(parent: &parent, "dispatch", subtask = subtask.as_str(), context_id);
+        let started = Instant::now();
+        let agent = job.agent_name();
+        let (result, context) = span.in_scope(|| job.run_with_policy(retry, timeout));
+        FinishedJob { agent, result, context, duration: started.elapsed() }
+    };
+    if jobs.len() == 1 {
+        return jobs.into_iter().map(run).collect();
     }
+    thread::scope(|scope| {
+        let handles: Vec<_> = jobs
+            .into_iter()
+            .map(|job| {
+                let sub_task = job.sub_task.clone();
+                (sub_task, scope.spawn(|| run(job)))
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|(sub_task, handle)| match handle.join() {
+                Ok(finished) => finished,
+                Err(_) => {
+                    let err = OrchestratorError::CallFailed {
+                        target: sub_task,
+                        message: "subtask thread panicked".to_string(),
+                        traceback: None,
+                    };
+                    FinishedJob {
+                        agent: None,
+                        result: AgentResult::from_error("Dispatch Error", err),
+                        context: None,
+                        duration: Duration::ZERO,
+                    }
+                }
+            })
+            .collect()
+    })
 }
 
-struct ViralPropagator {
-    // Roqoqo-based viral propagation logic
+/// How often a subtask's timeout wait checks whether its run was cancelled.
+const CANCEL_POLL: Duration = Duration::from_millis(10);
+
+/// `rx.recv_timeout(timeout)`, also giving up with `Timeout` once `cancel` is set.
+fn recv_unless_cancelled<T>(
+    rx: mpsc::Receiver<T>,
+    timeout: Duration,
+    cancel: Option<&CancelToken>,
+) -> Result<T, mpsc::RecvTimeoutError> {
+    let Some(cancel) = cancel else {
+        return rx.recv_timeout(timeout);
+    };
+    let deadline = Instant::now() + timeout;
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        match rx.recv_timeout(left.min(CANCEL_POLL)) {
+            Err(mpsc::RecvTimeoutError::Timeout) if !left.is_zero() && !cancel.is_cancelled() => continue,
+            received => return received,
+        }
+    }
 }
 
-impl ViralPropagator {
-    fn new() -> Self {
-        Self {}
+/// Schedules a `TimeoutError` in another Python thread. No-op when the thread never
+/// entered Python or has already left it.
+fn interrupt_python_thread(ident: i64) {
+    if ident == 0 {
+        return;
     }
+    Python::with_gil(|py| unsafe {
+        // SAFETY: the GIL is held and the exception type is a valid, immortal type object.
+        pyo3::ffi::PyThreadState_SetAsyncExc(ident as _, <PyTimeoutError as pyo3::PyTypeInfo>::type_object_raw(py).cast());
+    });
+}
+
+fn unknown_subtask(err: OrchestratorError) -> AgentResult {
+    let output = match &err {
+        OrchestratorError::UnknownSubtask { subtask, agents } => {
+            format!("Unknown subtask: {} (registered agents: {})", subtask, agents.join(", "))
+        }
+        other => other.to_string(),
+    };
+    AgentResult::failed(output, err)
+}
+
+/// Re-plans a single `process` run may splice in before ignoring further ones.
+pub const DEFAULT_MAX_REPLANS: usize = 3;
+
+fn locked<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn read<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else(|e| e.into_inner())
+}
+
+fn write<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else(|e| e.into_inner())
 }
 
-struct QuantumAmplifier {
-    // Faer-based tensor amplification
+/// Every method takes `&self`: state lives behind its own locks, none held across
+/// an agent call, so one orchestrator can be shared between threads (or Python
+/// threads, `process` releasing the GIL) and runs on different contexts proceed
+/// in parallel. Each context has a lock of its own, which a dispatch holds while
+/// its agent runs. So dispatches on one context, including those of concurrent
+/// runs on it, take turns, and runs on the same context interleave subtask by
+/// subtask. Subtasks run with a timeout or retries, and the subtasks of a
+/// parallel wave, work on a copy of the context instead and write it back when
+/// they finish, replacing what was changed meanwhile.
+#[pyclass]
+pub struct CognitiveOrchestrator {
+    contexts: ContextMap,
+    plan_templates: RwLock<PlanTemplates>,
+    /// Checked against every plan before it is dispatched.
+    dispatch_policy: RwLock<Arc<CompiledPolicy>>,
+    /// What `self_debug` does about each class of failure.
+    debug_strategies: RwLock<DebugStrategies>,
+    agents: RwLock<AgentRegistry>,
+    memory_store: RwLock<Option<Arc<dyn MemoryStore>>>,
+    viral_propagator: Arc<ViralPropagator>,
+    /// What contexts without their own `viral_config` use.
+    viral_config: ViralConfig,
+    prefer_native: Arc<AtomicBool>,
+    quantum_amplifier: Arc<QuantumAmplifier>,
+    context_ttl: Option<Duration>,
+    max_contexts: Option<usize>,
+    /// Per-tenant caps on live contexts, on top of `max_contexts`.
+    tenant_max_contexts: RwLock<HashMap<String, usize>>,
+    eviction_path: Option<PathBuf>,
+    /// Takes evicted contexts and all of them on shutdown, and gives back the
+    /// ones not in memory.
+    context_store: Option<Box<dyn ContextStore>>,
+    /// Where runs are journaled, when they are.
+    journal: Option<Journal>,
+    subtask_timeout: Option<Duration>,
+    retry_policy: RwLock<RetryPolicy>,
+    max_replans: usize,
+    /// What sweeping does with goals past their deadline.
+    goal_expiry: GoalExpiry,
+    history: Mutex<ExecutionHistory>,
+    /// Contexts with a run in flight, by number of runs; never evicted.
+    pinned: Mutex<HashMap<String, usize>>,
+    /// Deleted contexts by key, until restored or purged; never evicted.
+    trash: Mutex<HashMap<String, TrashedContext>>,
+    metrics: OrchestratorMetrics,
+    clock: Arc<dyn Clock>,
+    /// Seeds retry jitter when set; see `CognitiveOrchestratorBuilder::seed`.
+    seed: Option<u64>,
+    /// Viral metrics new contexts start with.
+    default_metrics: ViralMetrics,
+    agent_modules: AgentModules,
+    /// Answers the planner, LLM, viral, debug and memory calls.
+    backend: Arc<dyn AgentBackend>,
+    events: EventBus,
+    /// Each POSTs its share of `events` to a webhook until the orchestrator drops.
+    #[cfg(feature = "webhooks")]
+    webhooks: Vec<WebhookNotifier>,
+    /// Embeds text for `remember` and `recall_text`.
+    embedder: Arc<dyn Embedder>,
+    auto_snapshots: bool,
+    /// Whether `process` returns the bare output array rather than the `ProcessReport`.
+    legacy_output: bool,
+    /// Whether each completed run is compared with the context's previous run of
+    /// its command, in `last_reports`.
+    auto_diff: bool,
+    latency_regression_pct: f64,
+    /// Each context's last completed run, kept with auto-diff on.
+    last_reports: Mutex<HashMap<String, ProcessReport>>,
+    /// The most recent runs' reports, by run id, for `get_run`.
+    pub(crate) runs: RunStore,
+    /// Bound on each context's attributes as JSON, checked by `set_attribute`.
+    max_attribute_bytes: usize,
+    /// Identical runs in flight or just finished, with `coalesce_window` set.
+    coalescer: Coalescer<ProcessReport>,
+    /// `coalescer` for `process_async` runs, which hand on their output.
+    pub(crate) async_coalescer: Coalescer<String>,
+    learning: LearningConfig,
+    /// What of its context the planner is shown.
+    planner_input: PlannerInputConfig,
+    /// What the planner's answers are held to.
+    plan_validator: PlanValidator,
+    /// How the memory vectors of new, loaded and imported contexts are stored.
+    memory_quantization: Quantization,
+    persistence_format: PersistenceFormat,
+    snapshots: Mutex<ContextSnapshots>,
+    /// Shared with the `LlmAgent`, which charges it.
+    budgets: Budgets,
+    rate_limits: RateLimits,
+    result_cache: ResultCache,
+    breakers: CircuitBreakers,
+    post_processors: PostProcessors,
+    /// Shared with the viral simulation, which samples into `Context::metrics_history`.
+    metrics_recorder: MetricsRecorder,
+    /// Hook-rate tuning `self_debug` runs on "low virality" failures.
+    auto_tune: Mutex<Option<AutoTune>>,
+    /// Runs in flight, and whether `shutdown` has begun.
+    drain: ShutdownHandle,
+    idempotency: IdempotencyKeys,
+    cancellations: Cancellations,
+    /// Held by each run for its whole duration.
+    run_locks: RunLocks,
+    /// Turns to run, taken before the run lock when the queue is on.
+    pub(crate) queue: SubmissionQueue,
+    /// Each run's progress, for `observe`.
+    live: LiveRuns,
+    /// Anomalies whose store failed.
+    anomalies: AnomalyLog,
+    /// Shared with `anomalies`, which redacts what it stores.
+    redactor: Redactor,
+    /// Where `process_parallel` dispatches, when one is configured.
+    worker_pool: Option<WorkerPool>,
+    /// What commands and subtasks must be before they are planned or dispatched.
+    input_policy: InputPolicy,
 }
 
-impl QuantumAmplifier {
-    fn new() -> Self {
-        Self {}
+/// An orchestrator shared between async tasks, as the `server` and `grpc`
+/// front ends hold it.
+pub type SharedOrchestrator = Arc<CognitiveOrchestrator>;
+
+impl Default for CognitiveOrchestrator {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-#[pymodule]
-fn sovereign_cli(_py: Python, m: &PyModule) -> PyResult<()> {
-    m.add_class::<CognitiveOrchestrator>()?;
-    Ok(())
+impl CognitiveOrchestrator {
+    pub fn new() -> Self {
+        Self::builder().build().expect("default configuration is valid")
+    }
+
+    pub fn builder() -> CognitiveOrchestratorBuilder {
+        CognitiveOrchestratorBuilder::default()
+    }
+
+    /// Throttles dispatches to agents of `kind` to `rate` calls per second, with
+    /// bursts of up to `burst`. Waits happen before the call, without the GIL, so
+    /// parallel and batch runs share the limit. A `rate` that is not positive and
+    /// finite removes the limit.
+    pub fn with_rate_limit(self, kind: AgentKind, rate: f64, burst: u32) -> Self {
+        self.rate_limits.set(kind, RateLimit::new(rate, burst));
+        self
+    }
+
+    pub fn set_rate_limit(&self, kind: AgentKind, limit: Option<RateLimit>) {
+        self.rate_limits.set(kind, limit);
+    }
+
+    pub fn rate_limit(&self, kind: AgentKind) -> Option<RateLimit> {
+        self.rate_limits.get(kind)
+    }
+
+    /// Opens the circuit for agents of `kind` after `policy.failure_threshold`
+    /// consecutive calls that failed to reach them: dispatch then fails with
+    /// `circuit_open` without calling out, until after `policy.cooldown` one probe
+    /// dispatch closes it again or reopens it.
+    pub fn with_circuit_breaker(self, kind: AgentKind, policy: CircuitPolicy) -> Self {
+        self.breakers.set(kind, Some(policy));
+        self
+    }
+
+    pub fn set_circuit_breaker(&self, kind: AgentKind, policy: Option<CircuitPolicy>) {
+        self.breakers.set(kind, policy);
+    }
+
+    pub fn circuit_breaker(&self, kind: AgentKind) -> Option<CircuitPolicy> {
+        self.breakers.get(kind)
+    }
+
+    /// Breaker state of every agent kind dispatched so far or with a breaker.
+    pub fn agent_health(&self) -> Vec<AgentHealth> {
+        self.breakers.health()
+    }
+
+    /// Shared with the front ends, which report health from it.
+    pub fn circuit_breakers(&self) -> CircuitBreakers {
+        self.breakers.clone()
+    }
+
+    /// Caches up to `capacity` successful results of agents that call out to
+    /// Python, keyed by kind and normalized subtask, each for at most `ttl` when
+    /// set. Dispatch answers repeats from the cache, across contexts, marking them
+    /// `"cache": "hit"`. `DEFAULT_CACHE_EXCLUDED` kinds are left out; a capacity
+    /// of 0 turns the cache off.
+    pub fn with_result_cache(self, capacity: usize, ttl: Option<Duration>) -> Self {
+        self.result_cache.configure(capacity, ttl);
+        self
+    }
+
+    /// Replaces the kinds whose results are never cached, while the cache is on.
+    pub fn set_cache_exclusions(&self, kinds: impl IntoIterator<Item = AgentKind>) {
+        self.result_cache.set_excluded(kinds);
+    }
+
+    pub fn cache_exclusions(&self) -> Vec<AgentKind> {
+        self.result_cache.excluded()
+    }
+
+    /// Hits, misses and evictions so far; `None` while the cache is off.
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.result_cache.stats()
+    }
+
+    pub fn clear_result_cache(&self) {
+        self.result_cache.clear();
+    }
+
+    /// Appends `processor` to the chain run on results of agents of `kind` after
+    /// dispatch, before failures are debugged.
+    pub fn with_post_processor(self, kind: AgentKind, processor: impl PostProcessor + 'static) -> Self {
+        self.add_post_processor(kind, processor);
+        self
+    }
+
+    pub fn add_post_processor(&self, kind: AgentKind, processor: impl PostProcessor + 'static) {
+        self.post_processors.add(kind, Arc::new(processor));
+    }
+
+    pub fn clear_post_processors(&self, kind: AgentKind) {
+        self.post_processors.clear(kind);
+    }
+
+    pub fn post_processors(&self) -> &PostProcessors {
+        &self.post_processors
+    }
+
+    /// Redacts matches of `pattern` as `***<kind>***` wherever the built-in kinds are.
+    pub fn add_redaction_pattern(&self, kind: &str, pattern: &str) -> Result<(), OrchestratorError> {
+        self.redactor.add_pattern(kind, pattern)
+    }
+
+    pub fn redactor(&self) -> &Redactor {
+        &self.redactor
+    }
+
+    pub fn input_policy(&self) -> &InputPolicy {
+        &self.input_policy
+    }
+
+    /// Fails any subtask still running after `timeout` with a `timeout` error.
+    pub fn with_subtask_timeout(mut self, timeout: Duration) -> Self {
+        self.subtask_timeout = Some(timeout);
+        self
+    }
+
+    /// Caps how many `self_debug` re-plans one `process` run splices in; once reached,
+    /// further re-plans are ignored and the remaining plan runs as is.
+    pub fn with_max_replans(mut self, max_replans: usize) -> Self {
+        self.max_replans = max_replans;
+        self
+    }
+
+    /// Keeps at most `limit` execution records per context, dropping the oldest first.
+    pub fn with_history_limit(mut self, limit: usize) -> Self {
+        self.history.get_mut().unwrap_or_else(|e| e.into_inner()).set_limit(limit);
+        self
+    }
+
+    /// Keeps at most `limit` metrics samples per context, dropping the oldest at
+    /// each context's next sample.
+    pub fn with_metrics_history_limit(self, limit: usize) -> Self {
+        self.metrics_recorder.set_limit(limit);
+        self
+    }
+
+    /// Snapshots each run's context before planning and after completion; see
+    /// `get_snapshots`.
+    pub fn with_auto_snapshots(mut self, enabled: bool) -> Self {
+        self.auto_snapshots = enabled;
+        self
+    }
+
+    /// With `false`, `process` and its blocking variants return the run's
+    /// `ProcessReport` as JSON instead of the array of subtask outputs they return
+    /// by default. Streamed `Completed` events keep the array.
+    pub fn with_legacy_output(mut self, enabled: bool) -> Self {
+        self.legacy_output = enabled;
+        self
+    }
+
+    /// With `true`, every run that completes its plan is kept as its context's
+    /// `last_report`, and its report carries a `diff` against the one it replaces
+    /// when that ran the same command.
+    pub fn with_auto_diff(mut self, enabled: bool) -> Self {
+        self.auto_diff = enabled;
+        self
+    }
+
+    /// How much slower, in percent, auto-diffs and `compare_runs` here let a
+    /// subtask get before reporting it.
+    pub fn with_latency_regression_pct(mut self, pct: f64) -> Self {
+        self.latency_regression_pct = pct;
+        self
+    }
+
+    pub fn latency_regression_pct(&self) -> f64 {
+        self.latency_regression_pct
+    }
+
+    /// Stores memory vectors as `quantization` says, re-encoding those of live
+    /// contexts; see `Quantization`.
+    pub fn with_memory_quantization(mut self, quantization: Quantization) -> Self {
+        self.memory_quantization = quantization;
+        for (_, slot) in self.contexts.slots() {
+            context_map::lock(&slot).memory_vectors.quantize(quantization);
+        }
+        self
+    }
+
+    pub fn memory_quantization(&self) -> Quantization {
+        self.memory_quantization
+    }
+
+    /// Encodes `save_contexts` checkpoints and eviction and shutdown flushes in
+    /// `format`; loading detects the format of each file.
+    pub fn with_persistence_format(mut self, format: PersistenceFormat) -> Self {
+        self.persistence_format = format;
+        self
+    }
+
+    pub fn persistence_format(&self) -> PersistenceFormat {
+        self.persistence_format
+    }
+
+    pub fn with_learning(mut self, learning: LearningConfig) -> Self {
+        self.learning = learning;
+        self
+    }
+
+    pub fn learning(&self) -> LearningConfig {
+        self.learning
+    }
+
+    pub fn with_planner_input(mut self, config: PlannerInputConfig) -> Self {
+        self.planner_input = config;
+        self
+    }
+
+    pub fn planner_input_config(&self) -> PlannerInputConfig {
+        self.planner_input
+    }
+
+    pub fn with_plan_validator(mut self, validator: PlanValidator) -> Self {
+        self.plan_validator = validator;
+        self
+    }
+
+    pub fn plan_validator(&self) -> PlanValidator {
+        self.plan_validator
+    }
+
+    /// Retries failed subtasks the policy deems transient before `self_debug` sees them.
+    pub fn with_retry_policy(self, policy: RetryPolicy) -> Self {
+        self.set_retry_policy(policy);
+        self
+    }
+
+    /// A policy without its own `jitter_seed` takes the orchestrator's seed.
+    pub fn set_retry_policy(&self, mut policy: RetryPolicy) {
+        policy.jitter_seed = policy.jitter_seed.or(self.seed);
+        *write(&self.retry_policy) = policy;
+    }
+
+    pub fn retry_policy(&self) -> RetryPolicy {
+        read(&self.retry_policy).clone()
+    }
+
+    /// Evicts contexts not accessed within `ttl`.
+    pub fn with_context_ttl(mut self, ttl: Duration) -> Self {
+        self.context_ttl = Some(ttl);
+        self
+    }
+
+    /// Prometheus registry with this orchestrator's collectors; see `metrics` for
+    /// the exported names.
+    pub fn metrics_registry(&self) -> prometheus::Registry {
+        self.metrics.registry()
+    }
+
+    /// Serves the registry at `http://{addr}/metrics` until the process exits.
+    #[cfg(feature = "metrics_http")]
+    pub fn serve_metrics(&self, addr: &str) -> std::io::Result<std::net::SocketAddr> {
+        metrics::serve(self.metrics_registry(), addr)
+    }
+
+    /// Installs a stderr `tracing` subscriber filtered by `level` (an `EnvFilter`
+    /// directive such as "info" or "sovereign_cli=debug"). Returns `false` when a
+    /// global subscriber is already installed; that subscriber then keeps receiving
+    /// the orchestrator's spans. With the `otel` feature, spans are also exported
+    /// through the global OpenTelemetry tracer provider.
+    pub fn init_tracing(level: &str) -> Result<bool, OrchestratorError> {
+        use tracing_subscriber::layer::SubscriberExt;
+        use tracing_subscriber::util::SubscriberInitExt;
+
+        let filter = tracing_subscriber::EnvFilter::try_new(level)
+            .map_err(|e| OrchestratorError::InvalidLogFilter { directive: level.to_string(), message: e.to_string() })?;
+        let fmt = tracing_subscriber::fmt::layer().with_writer(std::io::stderr).with_ansi(std::io::stderr().is_terminal());
+        let registry = tracing_subscriber::registry().with(filter).with(fmt);
+        #[cfg(feature = "otel")]
+        let registry = registry.with(otel::layer());
+        Ok(registry.try_init().is_ok())
+    }
+
+    /// Caps the number of live contexts, evicting the least recently accessed first.
+    pub fn with_max_contexts(mut self, max_contexts: usize) -> Self {
+        self.max_contexts = Some(max_contexts);
+        self
+    }
+
+    /// Caps the live contexts of one tenant, evicting its least recently accessed
+    /// first; applies alongside `max_contexts`.
+    pub fn with_tenant_max_contexts(mut self, tenant: &str, max_contexts: usize) -> Self {
+        self.tenant_max_contexts.get_mut().unwrap_or_else(|e| e.into_inner()).insert(tenant.to_string(), max_contexts);
+        self
+    }
+
+    /// Sets or, with `None`, lifts a tenant's context limit. Takes effect at the
+    /// next eviction.
+    pub fn set_tenant_max_contexts(&self, tenant: &str, max_contexts: Option<usize>) -> Result<(), OrchestratorError> {
+        validate_tenant(tenant)?;
+        let mut limits = write(&self.tenant_max_contexts);
+        match max_contexts {
+            Some(max) => limits.insert(tenant.to_string(), max),
+            None => limits.remove(tenant),
+        };
+        Ok(())
+    }
+
+    /// How long completed idempotency keys are replayed; `DEFAULT_IDEMPOTENCY_TTL`
+    /// otherwise.
+    pub fn with_idempotency_ttl(self, ttl: Duration) -> Self {
+        self.idempotency.set_ttl(ttl);
+        self
+    }
+
+    /// Merges evicted contexts into the snapshot at `path` before dropping them.
+    pub fn with_eviction_flush(mut self, path: impl Into<PathBuf>) -> Self {
+        self.eviction_path = Some(path.into());
+        self
+    }
+
+    /// Puts evicted contexts into `store` before dropping them, and every context
+    /// on shutdown; a context not in memory is then looked up there before being
+    /// created afresh. This works alongside an eviction flush.
+    pub fn with_context_store(mut self, store: Box<dyn ContextStore>) -> Self {
+        self.context_store = Some(store);
+        self
+    }
+
+    pub fn context_store(&self) -> Option<&dyn ContextStore> {
+        self.context_store.as_deref()
+    }
+
+    /// Journals every run's plan, dispatches, results and completion into `journal`.
+    pub fn with_journal(mut self, journal: Journal) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    pub fn journal(&self) -> Option<&Journal> {
+        self.journal.as_ref()
+    }
+
+    /// The journaled runs an earlier process left incomplete, for the caller to
+    /// `resume_run` or `abandon_run`; none without a journal.
+    pub fn recover_incomplete_runs(&self) -> Vec<IncompleteRun> {
+        self.journal.as_ref().map(Journal::incomplete_runs).unwrap_or_default()
+    }
+
+    fn incomplete_run(&self, run_id: &str) -> Result<IncompleteRun, OrchestratorError> {
+        self.journal
+            .as_ref()
+            .and_then(|journal| journal.incomplete_run(run_id))
+            .ok_or_else(|| OrchestratorError::UnknownRun { run_id: run_id.to_string() })
+    }
+
+    /// Runs what an incomplete run had left, its in-flight subtasks included, on
+    /// its context and under its id, without planning again.
+    pub fn resume_run(&self, run_id: &str) -> Result<ProcessReport, OrchestratorError> {
+        self.drain.check()?;
+        let run = self.incomplete_run(run_id)?;
+        Ok(self.run_report(ProcessRun::new(run.command.clone(), run.key(), None).resuming(run.run_id.clone(), run.plan())))
+    }
+
+    /// Journals an incomplete run as given up, so it is no longer recovered.
+    pub fn abandon_run(&self, run_id: &str) -> Result<(), OrchestratorError> {
+        let run = self.incomplete_run(run_id)?;
+        let record = JournalRecord::PlanCompleted { run_id: run.run_id, success: false, abandoned: true, at: self.clock.now() };
+        self.journal.as_ref().map_or(Ok(()), |journal| journal.append(record))
+    }
+
+    /// The stored context under `key`, if any; a store that fails is logged and
+    /// treated as holding nothing.
+    fn stored_context(&self, key: &str) -> Option<Context> {
+        match self.context_store.as_ref()?.get(key) {
+            Ok(Some(context)) if context.key() == key => Some(context),
+            Ok(Some(context)) => {
+                warn!("Ignoring stored context {:?} kept under {:?}", context.key(), key);
+                None
+            }
+            Ok(None) => None,
+            Err(err) => {
+                warn!("Context store lookup of {} failed: {}", key, err);
+                None
+            }
+        }
+    }
+
+    /// Returns the context's slot, creating it on first use, and marks it accessed.
+    /// A new context is the context store's one when it has it, and otherwise has
+    /// default viral metrics; either first makes room for it under `max_contexts`.
+    fn ensure_context(&self, context_id: &str) -> ContextSlot {
+        let mut stored = None;
+        if !self.contexts.contains(context_id) {
+            self.evict(1, Some(tenant::split_key(context_id).0));
+            stored = self.stored_context(context_id);
+        }
+        let now = self.clock.now();
+        let (tenant, id) = tenant::split_key(context_id);
+        let (slot, created) = self.contexts.get_or_insert_with(context_id, || stored.unwrap_or_else(|| Context {
+            context_id: id.to_string(),
+            tenant: tenant.to_string(),
+            active_goals: vec![],
+            memory_vectors: MemoryVectors::new(self.memory_quantization),
+            memory_texts: vec![],
+            memory_payloads: vec![],
+            memory_meta: vec![],
+            memory_decay: 0.0,
+            viral_metrics: self.default_metrics.clone(),
+            viral_config: None,
+            propagation: None,
+            metrics_history: MetricsHistory::default(),
+            lineage: None,
+            attributes: Attributes::new(),
+            created_at: now,
+            last_accessed: now,
+            extra: BTreeMap::new(),
+        }));
+        if created {
+            self.metrics.context_count(self.contexts.len());
+        }
+        context_map::lock(&slot).last_accessed = now;
+        slot
+    }
+
+    /// `f` on the live context under its lock, or `MissingContext`.
+    fn read_context<R>(&self, context_id: &str, f: impl FnOnce(&Context) -> R) -> Result<R, OrchestratorError> {
+        self.contexts
+            .read(context_id, f)
+            .ok_or_else(|| OrchestratorError::MissingContext { context_id: context_id.to_string() })
+    }
+
+    fn update_context<R>(&self, context_id: &str, f: impl FnOnce(&mut Context) -> R) -> Result<R, OrchestratorError> {
+        self.contexts
+            .update(context_id, f)
+            .ok_or_else(|| OrchestratorError::MissingContext { context_id: context_id.to_string() })
+    }
+
+    /// A copy of the context; does not count as an access for eviction.
+    pub fn get_context(&self, context_id: &str) -> Option<Context> {
+        self.contexts.read(context_id, Context::clone)
+    }
+
+    /// Live context ids of the default tenant, sorted.
+    pub fn context_ids(&self) -> Vec<String> {
+        self.context_ids_for(DEFAULT_TENANT)
+    }
+
+    /// Live context ids of `tenant`, sorted.
+    pub fn context_ids_for(&self, tenant: &str) -> Vec<String> {
+        self.list_contexts(tenant).into_iter().map(|summary| summary.context_id).collect()
+    }
+
+    /// `tenant`'s live contexts at a glance, sorted by id; trashed ones are left out.
+    /// Read-only, like `get_context`.
+    pub fn list_contexts(&self, tenant: &str) -> Vec<ContextSummary> {
+        let mut summaries: Vec<ContextSummary> = self
+            .contexts
+            .slots()
+            .into_iter()
+            .filter_map(|(_, slot)| {
+                let context = context_map::lock(&slot);
+                (context.tenant == tenant).then(|| ContextSummary::of(&context))
+            })
+            .collect();
+        summaries.sort_by(|a, b| a.context_id.cmp(&b.context_id));
+        summaries
+    }
+
+    /// `list_contexts` with `tenant`'s contexts in the trash too, their `deleted_at`
+    /// set; those only in the context store's trash are not listed.
+    pub fn list_contexts_including_trashed(&self, tenant: &str) -> Vec<ContextSummary> {
+        let mut summaries = self.list_contexts(tenant);
+        summaries.extend(
+            locked(&self.trash)
+                .values()
+                .filter(|trashed| trashed.context.tenant == tenant)
+                .map(|trashed| ContextSummary { deleted_at: Some(trashed.deleted_at), ..ContextSummary::of(&trashed.context) }),
+        );
+        summaries.sort_by(|a, b| a.context_id.cmp(&b.context_id));
+        summaries
+    }
+
+    /// Counts and sizes of the context and of the history and snapshots kept for
+    /// it; `None` for an unknown context. Read-only, like `get_context`.
+    pub fn context_stats(&self, context_id: &str) -> Option<ContextStats> {
+        let (history_len, snapshot_count) = (locked(&self.history).count(context_id), locked(&self.snapshots).count(context_id));
+        let run_lock = self.run_locks.holder(context_id);
+        self.contexts.read(context_id, |context| ContextStats::new(context, history_len, snapshot_count, run_lock))
+    }
+
+    /// The context as it is right now; `None` for an unknown context. Safe to
+    /// poll while a run is executing on it: the view then comes from the run's
+    /// last published progress, and does not wait for the subtask in flight.
+    pub fn observe(&self, context_id: &str) -> Option<ContextView> {
+        match self.live.get(context_id) {
+            Some(run) => Some(ContextView::running(context_id, run)),
+            None => self.contexts.read(context_id, |context| ContextView::idle(context_id, context.viral_metrics.clone())),
+        }
+    }
+
+    /// Contexts evicted so far for their TTL or a context cap.
+    pub fn contexts_evicted(&self) -> u64 {
+        self.metrics.evictions()
+    }
+
+    /// `get_context` in `tenant`'s namespace; `None` for an invalid tenant.
+    pub fn get_context_for(&self, tenant: &str, context_id: &str) -> Option<Context> {
+        validate_tenant(tenant).ok()?;
+        self.get_context(&tenant::context_key(tenant, context_id))
+    }
+
+    /// `process` on `tenant`'s context, which shares nothing with other tenants'
+    /// contexts of the same id: not history, snapshots, cached results or budgets.
+    pub fn process_for(&self, tenant: &str, command: String, context_id: &str) -> Result<String, OrchestratorError> {
+        validate_tenant(tenant)?;
+        self.drain.check()?;
+        Ok(self.process(command, &tenant::context_key(tenant, context_id)))
+    }
+
+    /// `get_history` of `tenant`'s context, every record.
+    pub fn get_history_for(&self, tenant: &str, context_id: &str) -> Vec<ExecutionRecord> {
+        if validate_tenant(tenant).is_err() {
+            return vec![];
+        }
+        locked(&self.history).get(&tenant::context_key(tenant, context_id), None)
+    }
+
+    /// `export_context` of `tenant`'s context.
+    pub fn export_context_for(&self, tenant: &str, context_id: &str) -> Result<String, OrchestratorError> {
+        validate_tenant(tenant)?;
+        self.export_context(&tenant::context_key(tenant, context_id))
+    }
+
+    /// Checkpoints only `tenant`'s contexts, in the `save_contexts` format.
+    pub fn save_contexts_for(&self, tenant: &str, path: &Path) -> Result<(), OrchestratorError> {
+        validate_tenant(tenant)?;
+        let now = self.clock.now();
+        let mut keys = self.idempotency.completed(now);
+        keys.retain(|key| tenant::split_key(&key.context_id).0 == tenant);
+        let contexts = self.contexts.copies();
+        persistence::save(path, self.persistence_format, contexts.iter().filter(|context| context.tenant == tenant), &keys, now)
+    }
+
+    /// Limits LLM spend across every context of `tenant`, on top of each context's
+    /// own budget. Replacing it keeps what the tenant has spent.
+    pub fn set_tenant_budget(&self, tenant: &str, budget: Budget) -> Result<(), OrchestratorError> {
+        validate_tenant(tenant)?;
+        self.budgets.set_tenant(tenant, budget);
+        Ok(())
+    }
+
+    pub fn clear_tenant_budget(&self, tenant: &str) {
+        self.budgets.clear_tenant(tenant);
+    }
+
+    pub fn get_tenant_budget(&self, tenant: &str) -> Option<BudgetStatus> {
+        self.budgets.tenant_status(tenant)
+    }
+
+    /// Merges the context into the eviction flush snapshot and puts it into the
+    /// context store, as evicting it would, but keeps it. Returns the snapshot's
+    /// path; `None` without an eviction flush or such a context.
+    pub fn persist_context(&self, context_id: &str) -> Result<Option<PathBuf>, OrchestratorError> {
+        let Some(context) = self.get_context(context_id) else {
+            return Ok(None);
+        };
+        if let Some(store) = &self.context_store {
+            store.put(&context)?;
+        }
+        let Some(path) = &self.eviction_path else {
+            return Ok(None);
+        };
+        persistence::flush(path, self.persistence_format, [&context], &[], self.clock.now())?;
+        Ok(Some(path.clone()))
+    }
+
+    /// The context's last completed run, with auto-diff on.
+    pub fn last_report(&self, context_id: &str) -> Option<ProcessReport> {
+        locked(&self.last_reports).get(context_id).cloned()
+    }
+
+    /// The report of run `run_id`, while `run_retention` keeps it.
+    pub fn get_run(&self, run_id: &str) -> Option<ProcessReport> {
+        self.runs.get(run_id)
+    }
+
+    /// Makes `report` the context's last run, as if it had just completed here,
+    /// so the next run auto-diffs against it; for reports saved by an earlier process.
+    pub fn set_last_report(&self, context_id: &str, report: ProcessReport) {
+        locked(&self.last_reports).insert(context_id.to_string(), report);
+    }
+
+    /// `compare_runs_with` at this orchestrator's latency regression threshold.
+    pub fn compare_runs(&self, before: &ProcessReport, after: &ProcessReport) -> RunDiff {
+        compare_runs_with(before, after, self.latency_regression_pct)
+    }
+
+    /// Keeps `report` as its context's last run, returning its diff against the
+    /// previous one when that ran the same command.
+    pub(crate) fn record_report(&self, context_id: &str, report: &ProcessReport) -> Option<RunDiff> {
+        let report = &self.redact_report(report.clone());
+        let mut reports = locked(&self.last_reports);
+        let diff = reports
+            .get(context_id)
+            .filter(|previous| previous.command == report.command)
+            .map(|previous| self.compare_runs(previous, report));
+        reports.insert(context_id.to_string(), ProcessReport { diff: diff.clone(), ..report.clone() });
+        diff
+    }
+
+    /// Drops a context without flushing it; its execution history is kept.
+    pub fn remove_context(&self, context_id: &str) -> Option<Context> {
+        let removed = self.contexts.remove(context_id);
+        if removed.is_some() {
+            self.metrics.context_dropped(context_id);
+        }
+        self.metrics.context_count(self.contexts.len());
+        removed
+    }
+
+    /// Moves the context, live or only in the context store, to the trash, and
+    /// in the store to its trash, stamped with the time; `restore_context` brings
+    /// it back. Its history is kept. `MissingContext` for an unknown context,
+    /// `ContextBusy` for one with a run in flight.
+    pub fn delete_context(&self, context_id: &str) -> Result<(), OrchestratorError> {
+        if let Some(holder) = self.run_locks.holder(context_id) {
+            return Err(OrchestratorError::ContextBusy { context_id: context_id.to_string(), command: holder.command });
+        }
+        let context = self
+            .get_context(context_id)
+            .or_else(|| self.stored_context(context_id))
+            .ok_or_else(|| OrchestratorError::MissingContext { context_id: context_id.to_string() })?;
+        let trashed = TrashedContext { context, deleted_at: self.clock.now() };
+        if let Some(store) = &self.context_store {
+            store.put_trashed(&trashed)?;
+            store.delete(context_id)?;
+        }
+        self.remove_context(context_id);
+        locked(&self.trash).insert(context_id.to_string(), trashed);
+        info!(context_id, "Moved context to the trash");
+        Ok(())
+    }
+
+    /// Brings a deleted context back from the trash, or from the context store's
+    /// trash when an earlier process deleted it, as it was but accessed now.
+    /// `MissingContext` once purged, `ContextExists` while a context made since
+    /// holds its id, which leaves it in the trash.
+    pub fn restore_context(&self, context_id: &str) -> Result<Context, OrchestratorError> {
+        let in_memory = locked(&self.trash).get(context_id).cloned();
+        let trashed = match (in_memory, &self.context_store) {
+            (Some(trashed), _) => trashed,
+            (None, Some(store)) => store.get_trashed(context_id)?.filter(|trashed| trashed.key() == context_id),
+            (None, None) => None,
+        }
+        .ok_or_else(|| OrchestratorError::MissingContext { context_id: context_id.to_string() })?;
+        let exists = || OrchestratorError::ContextExists { context_id: context_id.to_string() };
+        if self.contexts.contains(context_id) {
+            return Err(exists());
+        }
+        self.evict(1, Some(&trashed.context.tenant));
+        let context = Context { last_accessed: self.clock.now(), ..trashed.context };
+        let (_, created) = self.contexts.get_or_insert_with(context_id, || context.clone());
+        if !created {
+            return Err(exists());
+        }
+        locked(&self.trash).remove(context_id);
+        if let Some(Err(err)) = self.context_store.as_ref().map(|store| store.delete_trashed(context_id)) {
+            warn!("Restored context {} is still in the store's trash: {}", context_id, err);
+        }
+        info!(context_id, "Restored context from the trash");
+        self.metrics.context_updated(&context);
+        self.metrics.context_count(self.contexts.len());
+        Ok(context)
+    }
+
+    /// Drops the contexts deleted at least `older_than` ago from the trash and
+    /// from the context store's trash for good; `Duration::ZERO` empties both.
+    /// Returns their keys, sorted.
+    pub fn purge_trash(&self, older_than: Duration) -> Result<Vec<String>, OrchestratorError> {
+        let Some(cutoff) = chrono::Duration::from_std(older_than).ok().and_then(|age| self.clock.now().checked_sub_signed(age)) else {
+            return Ok(vec![]);
+        };
+        let mut purged = BTreeSet::new();
+        locked(&self.trash).retain(|key, trashed| {
+            let old = trashed.deleted_at <= cutoff;
+            if old {
+                purged.insert(key.clone());
+            }
+            !old
+        });
+        if let Some(store) = &self.context_store {
+            for key in store.list_trashed()? {
+                if store.get_trashed(&key)?.is_some_and(|trashed| trashed.deleted_at <= cutoff) && store.delete_trashed(&key)? {
+                    purged.insert(key);
+                }
+            }
+        }
+        if !purged.is_empty() {
+            info!(purged = purged.len(), "Purged the trash");
+        }
+        Ok(purged.into_iter().collect())
+    }
+
+    /// Copies a context to `new_id` in the same tenant, with `lineage` pointing
+    /// back to it, and returns the copy; its history and snapshots stay with the
+    /// source. `ContextExists` when `new_id` is taken.
+    pub fn fork_context(&self, source_id: &str, new_id: &str) -> Result<Context, OrchestratorError> {
+        let source = self.read_context(source_id, Context::clone)?;
+        let key = tenant::context_key(&source.tenant, new_id);
+        if !self.contexts.contains(&key) {
+            self.evict(1, Some(&source.tenant));
+        }
+        let fork = source.fork(new_id, self.clock.now());
+        let (_, created) = self.contexts.get_or_insert_with(&key, || fork.clone());
+        if !created {
+            return Err(OrchestratorError::ContextExists { context_id: new_id.to_string() });
+        }
+        info!(context_id = %key, parent_id = source_id, "Forked context");
+        self.metrics.context_updated(&fork);
+        self.metrics.context_count(self.contexts.len());
+        Ok(fork)
+    }
+
+    /// Folds the source context's goals, memory and, per `strategy`, metrics into
+    /// the target; the source is left as it was. Nothing changes on error, such as
+    /// a goal conflict under `GoalConflict::Fail` or vectors of another dimension.
+    pub fn merge_contexts(
+        &self,
+        target_id: &str,
+        source_id: &str,
+        strategy: &MergeStrategy,
+    ) -> Result<MergeReport, OrchestratorError> {
+        let source = self.read_context(source_id, Context::clone)?;
+        self.update_context(target_id, |target| {
+            let report = target.merge_from(&source, strategy)?;
+            if report.metrics_from_source {
+                self.context_updated(target);
+            }
+            Ok(report)
+        })?
+    }
+
+    /// Plan, subtask, re-plan and metric events from every run, for live subscribers.
+    pub fn event_bus(&self) -> &EventBus {
+        &self.events
+    }
+
+    /// Deliveries of each configured webhook, in configuration order.
+    pub fn notifier_status(&self) -> Vec<WebhookStatus> {
+        #[cfg(feature = "webhooks")]
+        return self.webhooks.iter().map(WebhookNotifier::status).collect();
+        #[cfg(not(feature = "webhooks"))]
+        vec![]
+    }
+
+    /// Cancels the runs in flight on `context_id`: each stops before its next
+    /// subtask, cutting short a subtask's timeout or retry wait, and completes with
+    /// what has finished. Returns whether a run was in flight.
+    pub fn cancel(&self, context_id: &str) -> bool {
+        self.cancellations.cancel(context_id)
+    }
+
+    /// Cancels runs without holding the orchestrator, for callers that share it
+    /// between threads or tasks.
+    pub fn cancellations(&self) -> Cancellations {
+        self.cancellations.clone()
+    }
+
+    /// Begins a shutdown and waits for runs without holding the orchestrator, for
+    /// callers that share it between threads or tasks.
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.drain.clone()
+    }
+
+    /// Stops admitting runs: `process` and the streaming and batch variants then
+    /// complete at once with a `ShuttingDown` error. Runs already in flight may start
+    /// subtasks for `grace`, each capped to finish within it; a run still going after
+    /// that, or paused between steps here, is reported as interrupted and completes
+    /// at its next step with what it has. Every context is then snapshotted and,
+    /// with an eviction flush or a context store configured, persisted there.
+    ///
+    /// This does not wait for runs on other threads, so callers that share the
+    /// orchestrator begin with `shutdown_handle()` and `ShutdownHandle::wait_idle`.
+    /// The worker pool, if any, is closed last, draining what was queued on it.
+    /// Must not be called while holding the GIL when a pool is configured.
+    pub fn shutdown(&self, grace: Duration) -> ShutdownReport {
+        self.drain.begin(grace);
+        let (drained, interrupted) = self.drain.close();
+        let now = self.clock.now();
+        let contexts = self.contexts.copies();
+        for context in &contexts {
+            locked(&self.snapshots).record(context.snapshot_at(now));
+        }
+        let keys = self.idempotency.completed(now);
+        let persisted = self.eviction_path.clone().filter(|path| match persistence::flush(path, self.persistence_format, &contexts, &keys, now) {
+            Ok(()) => true,
+            Err(err) => {
+                warn!("Shutdown flush failed: {}", err);
+                false
+            }
+        });
+        let stored = self.context_store.as_ref().is_some_and(|store| match store.put_snapshot(&contexts) {
+            Ok(()) => true,
+            Err(err) => {
+                warn!("Shutdown store failed: {}", err);
+                false
+            }
+        });
+        let pending_anomalies = self.anomalies.flush(self.backend.as_ref(), self.memory_store().as_deref());
+        let busy_workers = self.worker_pool.as_ref().map_or(0, WorkerPool::shutdown);
+        info!(drained, interrupted = interrupted.len(), contexts = contexts.len(), pending_anomalies, busy_workers, "shut down");
+        ShutdownReport { drained, interrupted, snapshots: contexts.len(), persisted, stored, pending_anomalies, busy_workers }
+    }
+
+    /// Records a dispatch's effect on its context's metrics.
+    fn context_updated(&self, context: &Context) {
+        self.metrics.context_updated(context);
+        self.events.publish(&context.key(), || context.attributes.clone(), || {
+            BusPayload::Metrics(MetricsUpdate { viral_metrics: context.viral_metrics.clone() })
+        });
+    }
+
+    /// Evicts contexts past the TTL, then the least recently accessed ones over
+    /// `max_contexts` or their tenant's limit. Contexts with a run in flight are
+    /// skipped. Returns the evicted keys, oldest first.
+    pub fn evict_expired(&self) -> Vec<String> {
+        self.evict(0, None)
+    }
+
+    /// Evicts as `evict_expired`, leaving room for `reserve` new contexts of
+    /// `tenant`. If the eviction flush or the context store fails, nothing is evicted.
+    fn evict(&self, reserve: usize, tenant: Option<&str>) -> Vec<String> {
+        let tenant_max_contexts = read(&self.tenant_max_contexts).clone();
+        if self.context_ttl.is_none() && self.max_contexts.is_none() && tenant_max_contexts.is_empty() {
+            return vec![];
+        }
+        let slots = self.contexts.slots();
+        let pinned = locked(&self.pinned).clone();
+        let mut candidates: Vec<(DateTime<Utc>, &String)> = slots
+            .iter()
+            .filter(|(id, _)| !pinned.contains_key(id))
+            .map(|(id, slot)| (context_map::lock(slot).last_accessed, id))
+            .collect();
+        candidates.sort();
+
+        let expired_before = self
+            .context_ttl
+            .and_then(|ttl| chrono::Duration::from_std(ttl).ok())
+            .and_then(|ttl| self.clock.now().checked_sub_signed(ttl));
+        let expired = candidates
+            .iter()
+            .take_while(|(accessed, _)| expired_before.is_some_and(|cutoff| *accessed < cutoff))
+            .count();
+        let over_capacity = self
+            .max_contexts
+            .map_or(0, |max| (slots.len() + reserve).saturating_sub(max));
+        let mut evict: Vec<bool> = (0..candidates.len()).map(|i| i < expired.max(over_capacity)).collect();
+        // Then each limited tenant's oldest, counting the ones already chosen.
+        for (limited, max) in &tenant_max_contexts {
+            let live = slots.iter().filter(|(key, _)| tenant::split_key(key).0 == limited).count();
+            let mut over = (live + if tenant == Some(limited.as_str()) { reserve } else { 0 }).saturating_sub(*max);
+            for (i, (_, key)) in candidates.iter().enumerate() {
+                if over == 0 {
+                    break;
+                }
+                if tenant::split_key(key).0 == limited {
+                    evict[i] = true;
+                    over -= 1;
+                }
+            }
+        }
+        let evicted: Vec<String> = candidates
+            .into_iter()
+            .zip(evict)
+            .filter(|(_, evict)| *evict)
+            .map(|((_, id), _)| id.clone())
+            .collect();
+        if evicted.is_empty() {
+            return evicted;
+        }
+
+        if self.eviction_path.is_some() || self.context_store.is_some() {
+            let contexts: Vec<Context> = evicted.iter().filter_map(|id| self.get_context(id)).collect();
+            if let Some(path) = &self.eviction_path {
+                if let Err(err) = persistence::flush(path, self.persistence_format, &contexts, &[], self.clock.now()) {
+                    warn!("Eviction flush failed, keeping {} contexts: {}", evicted.len(), err);
+                    return vec![];
+                }
+            }
+            if let Some(Err(err)) = self.context_store.as_ref().map(|store| store.put_snapshot(&contexts)) {
+                warn!("Eviction store failed, keeping {} contexts: {}", evicted.len(), err);
+                return vec![];
+            }
+        }
+        for id in &evicted {
+            self.contexts.remove(id);
+            locked(&self.history).remove(id);
+            locked(&self.snapshots).remove(id);
+            self.metrics.context_evicted(id);
+        }
+        self.metrics.context_count(self.contexts.len());
+        evicted
+    }
+
+    /// Protects a context from eviction until the matching `unpin`.
+    fn pin(&self, context_id: &str) {
+        *locked(&self.pinned).entry(context_id.to_string()).or_insert(0) += 1;
+    }
+
+    fn unpin(&self, context_id: &str) {
+        let mut pinned = locked(&self.pinned);
+        if let Some(count) = pinned.get_mut(context_id) {
+            *count -= 1;
+            if *count == 0 {
+                pinned.remove(context_id);
+            }
+        }
+    }
+
+    pub fn add_memory(&self, context_id: &str, vec: Vec<f64>) -> Result<usize, OrchestratorError> {
+        context_map::lock(&self.ensure_context(context_id)).add_memory(vec)
+    }
+
+    /// Nearest stored memory vectors of a context by cosine similarity, discounted
+    /// by age under the context's `memory_decay`. Counts an access to each one returned.
+    pub fn recall(&self, context_id: &str, query_vec: &[f64], k: usize) -> Result<Vec<(usize, f64)>, OrchestratorError> {
+        let now = self.clock.now();
+        self.update_context(context_id, |context| {
+            let hits = decayed_recall(context, query_vec, k, now)?;
+            hits.iter().for_each(|(idx, _)| context.memory_recalled(*idx));
+            Ok(hits)
+        })?
+    }
+
+    /// Gives the context its own viral settings, creating it; dispatches from
+    /// now on use them in place of the orchestrator's.
+    pub fn set_viral_config(&self, context_id: &str, config: ViralConfig) {
+        context_map::lock(&self.ensure_context(context_id)).viral_config = Some(config);
+    }
+
+    /// Returns the context to the orchestrator's viral settings.
+    pub fn clear_viral_config(&self, context_id: &str) {
+        self.contexts.update(context_id, |context| context.viral_config = None);
+    }
+
+    /// Drops the context's propagation state, so its next spread measurement
+    /// starts over on a fresh graph. Whether it had any.
+    pub fn reset_propagation(&self, context_id: &str) -> bool {
+        self.contexts.update(context_id, |context| context.propagation.take().is_some()).unwrap_or(false)
+    }
+
+    /// The viral settings the context's subtasks use: its own, else the default,
+    /// with its `viral.*` attributes laid over them. Fails with `Extraction` when
+    /// such an attribute leaves them invalid.
+    pub fn viral_config(&self, context_id: &str) -> Result<ViralConfig, OrchestratorError> {
+        self.contexts
+            .read(context_id, |context| {
+                attributes::viral_config(context.viral_config.unwrap_or(self.viral_config), &context.attributes)
+            })
+            .unwrap_or(Ok(self.viral_config))
+    }
+
+    /// Attaches `value` to the context under `key`, creating the context. Fails
+    /// with `AttributesTooLarge`, changing nothing, when the attributes would
+    /// take more than `max_attribute_bytes` as JSON. Keys under `viral.` override
+    /// the context's viral settings, as `viral_config` resolves them; one that
+    /// leaves those settings invalid fails with `Extraction`, changing nothing.
+    pub fn set_attribute(&self, context_id: &str, key: &str, value: serde_json::Value) -> Result<(), OrchestratorError> {
+        let slot = self.ensure_context(context_id);
+        let mut context = context_map::lock(&slot);
+        let previous = context.attributes.insert(key.to_string(), value);
+        let bytes = attributes_bytes(&context.attributes);
+        let refused = if bytes > self.max_attribute_bytes {
+            Some(OrchestratorError::AttributesTooLarge {
+                context_id: context_id.to_string(),
+                bytes,
+                limit: self.max_attribute_bytes,
+            })
+        } else if key.starts_with(attributes::VIRAL_ATTRIBUTE_PREFIX) {
+            attributes::viral_config(context.viral_config.unwrap_or(self.viral_config), &context.attributes).err()
+        } else {
+            None
+        };
+        let Some(err) = refused else { return Ok(()) };
+        match previous {
+            Some(previous) => context.attributes.insert(key.to_string(), previous),
+            None => context.attributes.remove(key),
+        };
+        Err(err)
+    }
+
+    /// The context's attribute `key`, if set.
+    pub fn get_attribute(&self, context_id: &str, key: &str) -> Result<Option<serde_json::Value>, OrchestratorError> {
+        self.read_context(context_id, |context| context.attributes.get(key).cloned())
+    }
+
+    /// `get_attribute` as a `T`, failing with `Extraction` when it is not one.
+    pub fn get_attribute_as<T: DeserializeOwned>(&self, context_id: &str, key: &str) -> Result<Option<T>, OrchestratorError> {
+        self.get_attribute(context_id, key)?.map(|value| attributes::typed(key, value)).transpose()
+    }
+
+    /// Removes the context's attribute `key`, returning it.
+    pub fn remove_attribute(&self, context_id: &str, key: &str) -> Result<Option<serde_json::Value>, OrchestratorError> {
+        self.update_context(context_id, |context| context.attributes.remove(key))
+    }
+
+    /// Sets the per-day rate at which recall discounts the context's older memory
+    /// vectors, creating the context; negative rates count as zero, no decay.
+    pub fn set_memory_decay(&self, context_id: &str, lambda: f64) {
+        context_map::lock(&self.ensure_context(context_id)).memory_decay = lambda.max(0.0);
+    }
+
+    /// `Context::prune_memory` at the current time; returns how many vectors were dropped.
+    pub fn prune_memory(&self, context_id: &str, min_score: f64, max_entries: Option<usize>) -> Result<usize, OrchestratorError> {
+        let now = self.clock.now();
+        self.update_context(context_id, |context| context.prune_memory(min_score, max_entries, now))
+    }
+
+    /// Embeds `text` with the configured `Embedder` and appends it to the context's
+    /// memory, creating the context; returns the vector's index. The text is
+    /// redacted before either.
+    pub fn remember(&self, context_id: &str, text: &str) -> Result<usize, OrchestratorError> {
+        let text = self.redactor.redacted(text);
+        let vec = self.embedder.embed(&text)?;
+        context_map::lock(&self.ensure_context(context_id)).add_memory_text(vec, text)
+    }
+
+    /// `remember`, keeping `payload` alongside the text for `search_memory` to filter on.
+    pub fn remember_payload(
+        &self,
+        context_id: &str,
+        text: &str,
+        mut payload: serde_json::Map<String, serde_json::Value>,
+    ) -> Result<usize, OrchestratorError> {
+        let text = self.redactor.redacted(text);
+        self.redactor.redact_payload(&mut payload);
+        let vec = self.embedder.embed(&text)?;
+        context_map::lock(&self.ensure_context(context_id)).add_memory_payload(vec, text, payload)
+    }
+
+    /// Hybrid recall: the context's memory entries whose payloads pass every
+    /// filter, ranked by fusing their decayed similarity to `query` with how many
+    /// of its words their text contains, best first. Counts an access to each
+    /// one returned.
+    pub fn search_memory(
+        &self,
+        context_id: &str,
+        query: &str,
+        filters: &[MemoryFilter],
+        k: usize,
+    ) -> Result<Vec<MemorySearchHit>, OrchestratorError> {
+        let query_vec = self.embedder.embed(query)?;
+        let now = self.clock.now();
+        self.update_context(context_id, |context| {
+            let hits = search::search(context, query, &query_vec, filters, k, now)?;
+            hits.iter().for_each(|hit| context.memory_recalled(hit.index));
+            Ok(hits)
+        })?
+    }
+
+    /// The `remember`ed texts most similar to `query_text`, with their decayed
+    /// cosine similarity as `recall` scores it, best first. Vectors added without
+    /// text are skipped.
+    pub fn recall_text(&self, context_id: &str, query_text: &str, k: usize) -> Result<Vec<(String, f64)>, OrchestratorError> {
+        let query = self.embedder.embed(query_text)?;
+        let now = self.clock.now();
+        self.update_context(context_id, |context| {
+            let hits: Vec<(usize, String, f64)> = decayed_recall(context, &query, context.memory_vectors.len(), now)?
+                .into_iter()
+                .filter_map(|(idx, score)| context.memory_text(idx).map(|text| (idx, text.to_string(), score)))
+                .take(k)
+                .collect();
+            hits.iter().for_each(|(idx, ..)| context.memory_recalled(*idx));
+            Ok(hits.into_iter().map(|(_, text, score)| (text, score)).collect())
+        })?
+    }
+
+    pub fn embedder(&self) -> &Arc<dyn Embedder> {
+        &self.embedder
+    }
+
+    /// Checkpoints every context, of every tenant, to a versioned file in the
+    /// `persistence_format`, with the completed idempotency keys.
+    pub fn save_contexts(&self, path: &Path) -> Result<(), OrchestratorError> {
+        let now = self.clock.now();
+        persistence::save(path, self.persistence_format, &self.contexts.copies(), &self.idempotency.completed(now), now)
+    }
+
+    /// Restores contexts from `save_contexts` output in any format, replacing any with the same id,
+    /// and the idempotency keys still within their TTL. Entries that fail to decode
+    /// are listed in the report instead of aborting the load.
+    pub fn load_contexts(&self, path: &Path) -> Result<LoadReport, OrchestratorError> {
+        let persistence::Checkpoint { contexts, keys, report } = persistence::load(path)?;
+        self.idempotency.restore(keys, self.clock.now());
+        for mut context in contexts.into_values() {
+            context.memory_vectors.quantize(self.memory_quantization);
+            self.metrics.context_updated(&context);
+            self.contexts.insert(context);
+        }
+        self.metrics.context_count(self.contexts.len());
+        Ok(report)
+    }
+
+    /// One context as a portable JSON document: its fields, execution history and
+    /// snapshots, tagged with `EXPORT_SCHEMA_VERSION`.
+    pub fn export_context(&self, context_id: &str) -> Result<String, OrchestratorError> {
+        let context = self.read_context(context_id, Context::clone)?;
+        let export = ContextExport {
+            schema_version: EXPORT_SCHEMA_VERSION,
+            exported_at: self.clock.now(),
+            context,
+            history: locked(&self.history).get(context_id, None),
+            snapshots: locked(&self.snapshots)
+                .get(context_id)
+                .into_iter()
+                .map(|snapshot| ExportedSnapshot {
+                    snapshot_id: snapshot.snapshot_id,
+                    taken_at: snapshot.taken_at,
+                    context: (*snapshot.context).clone(),
+                })
+                .collect(),
+        };
+        serde_json::to_string_pretty(&export).map_err(OrchestratorError::serialization)
+    }
+
+    /// Restores an `export_context` document into the default tenant and returns
+    /// its context id. An existing context with that id is an error unless
+    /// `overwrite`, which also replaces its history and snapshots.
+    pub fn import_context(&self, json: &str, overwrite: bool) -> Result<String, ImportError> {
+        self.import_context_for(DEFAULT_TENANT, json, overwrite)
+    }
+
+    /// `import_context` into `tenant`, whatever tenant the document was exported from.
+    pub fn import_context_for(&self, tenant: &str, json: &str, overwrite: bool) -> Result<String, ImportError> {
+        if validate_tenant(tenant).is_err() {
+            return Err(ImportError::InvalidTenant { tenant: tenant.to_string() });
+        }
+        let mut export = ContextExport::parse(json)?;
+        export.context.tenant = tenant.to_string();
+        let (context_id, key) = (export.context.context_id.clone(), export.context.key());
+        if self.contexts.contains(&key) {
+            if !overwrite {
+                return Err(ImportError::Collision { context_id });
+            }
+            locked(&self.history).remove(&key);
+            locked(&self.snapshots).remove(&key);
+        }
+        for record in export.history {
+            locked(&self.history).record(&key, record);
+        }
+        for mut snapshot in export.snapshots {
+            snapshot.context.tenant = tenant.to_string();
+            locked(&self.snapshots).record(snapshot.context.snapshot_at(snapshot.taken_at));
+        }
+        export.context.memory_vectors.quantize(self.memory_quantization);
+        self.metrics.context_updated(&export.context);
+        self.contexts.insert(export.context);
+        self.metrics.context_count(self.contexts.len());
+        Ok(context_id)
+    }
+
+    /// Appended after existing templates; earlier registrations win on overlap.
+    pub fn register_plan_template(&self, trigger: PlanTrigger, steps: Vec<String>) -> Result<(), OrchestratorError> {
+        write(&self.plan_templates).register(PlanTemplate { trigger, steps })
+    }
+
+    /// Replaces every template, including the built-in viral pipeline.
+    pub fn set_plan_templates(&self, templates: Vec<PlanTemplate>) -> Result<(), OrchestratorError> {
+        write(&self.plan_templates).replace_all(templates)
+    }
+
+    pub fn clear_plan_templates(&self) {
+        write(&self.plan_templates).clear();
+    }
+
+    pub fn plan_templates(&self) -> Vec<PlanTemplate> {
+        read(&self.plan_templates).templates()
+    }
+
+    /// Replaces the dispatch policy, taking effect from the next plan; nothing
+    /// changes if any pattern fails to compile.
+    pub fn set_dispatch_policy(&self, policy: DispatchPolicy) -> Result<(), OrchestratorError> {
+        *write(&self.dispatch_policy) = Arc::new(CompiledPolicy::compile(policy)?);
+        Ok(())
+    }
+
+    pub fn dispatch_policy(&self) -> DispatchPolicy {
+        read(&self.dispatch_policy).policy().clone()
+    }
+
+    /// Sets what `self_debug` does about failures of `class`, from the next one.
+    /// Blocked subtasks are only ever skipped or aborted.
+    pub fn set_debug_strategy(&self, class: FailureClass, strategy: DebugStrategy) {
+        write(&self.debug_strategies).set(class, strategy);
+    }
+
+    pub fn debug_strategies(&self) -> DebugStrategies {
+        *read(&self.debug_strategies)
+    }
+
+    /// The dispatch policy's verdict on `subtasks`, in plan order.
+    pub(crate) fn review_plan(&self, subtasks: &[String]) -> Result<Vec<Option<OrchestratorError>>, OrchestratorError> {
+        let policy = read(&self.dispatch_policy).clone();
+        policy.review(subtasks)
+    }
+
+    /// The dispatch policy's verdict on one subtask, such as one whose references
+    /// were expanded after its plan was reviewed.
+    pub(crate) fn review_subtask(&self, subtask: &str) -> Option<OrchestratorError> {
+        read(&self.dispatch_policy).review_subtask(subtask)
+    }
+
+    /// The kind of the agent `sub_task` routes to, if it calls out to Python.
+    pub(crate) fn agent_kind(&self, sub_task: &str) -> Option<AgentKind> {
+        self.route(sub_task).ok().and_then(|agent| agent.kind())
+    }
+
+    pub fn proactive_plan(&self, command: String, context_id: &str) -> Result<Plan, OrchestratorError> {
+        let _span = info_span!("proactive_plan", context_id).entered();
+        self.ensure_context(context_id);
+        self.plan_command(&command, context_id)
+    }
+
+    /// `proactive_plan` without creating or touching the context.
+    fn plan_command(&self, command: &str, context_id: &str) -> Result<Plan, OrchestratorError> {
+        let plan = match self.template_plan(command) {
+            Some(plan) => Ok(plan),
+            None => {
+                let learned = self.learned_plans(command, context_id);
+                match learned.first() {
+                    Some(best) if best.similarity >= self.learning.reuse_threshold => {
+                        debug!(similarity = best.similarity, "reusing learned plan");
+                        Ok(Plan::from(best.plan.clone()))
+                    }
+                    _ => self.backend.plan_input(&self.planner_input(command, context_id, &learned)),
+                }
+            }
+        };
+        if let Ok(plan) = &plan {
+            debug!(steps = ?plan.subtasks(), "planned");
+        }
+        plan
+    }
+
+    /// What the planner is shown for `command` on `context_id`, without touching
+    /// the context; `learned` plans go into the single-string prompt.
+    pub(crate) fn planner_input(&self, command: &str, context_id: &str, learned: &[LearnedPlan]) -> PlannerInput {
+        let config = &self.planner_input;
+        let history = if config.history == 0 { vec![] } else { locked(&self.history).get(context_id, Some(config.history)) };
+        let memories = self.planner_memories(command, context_id);
+        let input = self.contexts.read(context_id, |context| {
+            let prompt = learning::planner_prompt(goals::planner_prompt(command, context), learned);
+            PlannerInput::new(command, Some(context), &history, memories.clone(), prompt, config)
+        });
+        input.unwrap_or_else(|| {
+            let prompt = learning::planner_prompt(command.to_string(), learned);
+            PlannerInput::new(command, None, &history, vec![], prompt, config)
+        })
+    }
+
+    /// Texts of the context's memories most similar to `command`, as many as
+    /// the planner is shown.
+    fn planner_memories(&self, command: &str, context_id: &str) -> Vec<String> {
+        if self.planner_input.memories == 0 || !self.contexts.contains(context_id) {
+            return vec![];
+        }
+        let query = match self.embedder.embed(command) {
+            Ok(query) => query,
+            Err(err) => {
+                warn!("Memories not shown to the planner: {}", err);
+                return vec![];
+            }
+        };
+        let now = self.clock.now();
+        let recalled = self.contexts.read(context_id, |context| {
+            decayed_recall(context, &query, context.memory_vectors.len(), now).map(|hits| {
+                hits.into_iter()
+                    .filter_map(|(idx, _)| context.memory_text(idx).map(str::to_string))
+                    .take(self.planner_input.memories)
+                    .collect()
+            })
+        });
+        match recalled {
+            Some(Ok(texts)) => texts,
+            Some(Err(err)) => {
+                warn!("Memories not shown to the planner: {}", err);
+                vec![]
+            }
+            None => vec![],
+        }
+    }
+
+    /// Registered templates (the viral pipeline by default) take precedence over the
+    /// Python planner; template steps run as a linear chain.
+    fn template_plan(&self, command: &str) -> Option<Plan> {
+        read(&self.plan_templates).match_command(command).map(|steps| Plan::from(steps.to_vec()))
+    }
+
+    /// The context's `top_k` past successes most similar to `command`; none while
+    /// learning is disabled.
+    fn learned_plans(&self, command: &str, context_id: &str) -> Vec<LearnedPlan> {
+        if !self.learning.enabled || !self.contexts.contains(context_id) {
+            return vec![];
+        }
+        match self.embedder.embed(command) {
+            Ok(query) => self
+                .contexts
+                .read(context_id, |context| learning::recall(context, &query, self.learning.top_k, self.clock.now()))
+                .unwrap_or_default(),
+            Err(err) => {
+                warn!("Learned plans not recalled: {}", err);
+                vec![]
+            }
+        }
+    }
+
+    /// Remembers a run whose every subtask succeeded, unless learning is disabled
+    /// or the context already holds this plan for this command. The vector embeds
+    /// the command alone, so repeating it recalls the plan; the text adds a summary
+    /// of the outputs, redacted. Mirrored to the memory store when one is configured.
+    /// A run whose command or plan would be redacted is not learned, since the
+    /// plan kept would no longer be the one that ran.
+    pub(crate) fn learn_success(&self, context_id: &str, command: &str, plan: &[String], outputs: &[String]) {
+        if !self.learning.enabled || plan.is_empty() {
+            return;
+        }
+        if std::iter::once(command).chain(plan.iter().map(String::as_str)).any(|text| self.redactor.redact(text).1 > 0) {
+            debug!("Success not learned: its plan holds redacted strings");
+            return;
+        }
+        let vec = match self.embedder.embed(command) {
+            Ok(vec) => vec,
+            Err(err) => {
+                warn!("Success not learned: {}", err);
+                return;
+            }
+        };
+        let slot = self.ensure_context(context_id);
+        let mut context = context_map::lock(&slot);
+        let known = learning::recall(&context, &vec, usize::MAX, context.last_accessed)
+            .iter()
+            .any(|learned| learned.command == command && learned.plan == plan);
+        if known {
+            return;
+        }
+        let payload = learning::success_payload(command, plan, context.viral_metrics.virality_score);
+        let text = self.redactor.redacted(&learning::success_text(command, outputs));
+        let added = context.add_memory_payload(vec, text.clone(), payload.clone());
+        drop(context);
+        if let Err(err) = added {
+            warn!("Success not learned: {}", err);
+            return;
+        }
+        if let Some(store) = self.memory_store() {
+            if let Err(err) = store.store_context(&text, context_id, payload) {
+                warn!("Success not stored: {}", err);
+            }
+        }
+    }
+
+    /// A dry run of `process`: plans the command the same way, calling no agent but
+    /// the planner and leaving the context untouched, then routes each subtask and
+    /// estimates its LLM tokens and wall time, the latter from the agents' recent
+    /// dispatches. `budget_exceeded` checks the totals against what the context's
+    /// and tenant's budgets have left.
+    pub fn estimate(&self, command: String, context_id: &str) -> Result<PlanEstimate, OrchestratorError> {
+        let _span = info_span!("estimate", context_id).entered();
+        let plan = plan_fallback(command.clone(), self.plan_command(&command, context_id))?;
+        let (plan, _) = self.validate_plan(&command, plan);
+        let subtasks = plan
+            .nodes()
+            .iter()
+            .map(|node| {
+                let agent = self.route(&node.subtask).ok();
+                let latency = agent.as_ref().and_then(|agent| self.metrics.latency(agent.name()));
+                SubtaskEstimate::new(node.id, node.subtask.clone(), agent.as_deref(), latency)
+            })
+            .collect();
+        let mut estimate = PlanEstimate::new(command, plan, subtasks);
+        estimate.budget_exceeded =
+            self.budgets.would_exceed(context_id, estimate.llm_calls, estimate.llm_tokens).map(str::to_string);
+        Ok(estimate)
+    }
+
+    /// Lossy planning: a planner failure degrades to the original command as a single
+    /// step. Only a plan that came back invalid (bad ids or a cycle) is an error, so it
+    /// is reported before anything is dispatched. The plan validator has cleaned up
+    /// whatever plan is returned.
+    pub fn plan_or_fallback(&self, command: String, context_id: &str) -> Result<Plan, OrchestratorError> {
+        self.validated_plan(command, context_id).map(|(plan, _)| plan)
+    }
+
+    /// `plan_or_fallback` with what the plan validator found.
+    pub(crate) fn validated_plan(&self, command: String, context_id: &str) -> Result<(Plan, Vec<PlanFinding>), OrchestratorError> {
+        let planned = self.proactive_plan(command.clone(), context_id);
+        let plan = plan_fallback(command.clone(), planned)?;
+        Ok(self.validate_plan(&command, plan))
+    }
+
+    /// `plan` for `command` as the plan validator leaves it, with what it found.
+    pub(crate) fn validate_plan(&self, command: &str, plan: Plan) -> (Plan, Vec<PlanFinding>) {
+        let (plan, findings) =
+            self.plan_validator.validate(command, plan, &self.input_policy, |subtask| self.route(subtask).err().map(|err| err.to_string()));
+        for finding in &findings {
+            warn!("Plan validation: {}", finding);
+        }
+        (plan, findings)
+    }
+
+    /// Logs a failure and returns the debug agent's replacement plan for `orig_cmd`, if any.
+    pub fn self_debug(&self, result: &AgentResult, orig_cmd: &str, context_id: &str) -> Option<Plan> {
+        self.debug_subtask(result, orig_cmd, orig_cmd, context_id).and_then(|(_, plan)| plan)
+    }
+
+    /// `self_debug` for a subtask of a run of `command`, with its decision.
+    pub(crate) fn debug_subtask(
+        &self,
+        result: &AgentResult,
+        command: &str,
+        subtask: &str,
+        context_id: &str,
+    ) -> Option<(DebugDecision, Option<Plan>)> {
+        self.debug_subtask_with(result, command, subtask, context_id, &self.debug_strategies())
+    }
+
+    /// `debug_subtask` under `strategies` rather than the configured ones.
+    pub(crate) fn debug_subtask_with(
+        &self,
+        result: &AgentResult,
+        command: &str,
+        subtask: &str,
+        context_id: &str,
+        strategies: &DebugStrategies,
+    ) -> Option<(DebugDecision, Option<Plan>)> {
+        self.tune_after_failure(result, context_id);
+        let anomaly = Anomaly::new(context_id, command, subtask, result, self.clock.now());
+        let mut similar = vec![];
+        if !result.status {
+            similar = self.similar_anomalies(&anomaly);
+            self.remember_anomaly(&anomaly);
+        }
+        let debugged = debug_failure(
+            self.backend.as_ref(),
+            self.memory_store().as_deref(),
+            &self.anomalies,
+            strategies,
+            result,
+            anomaly,
+            &similar,
+        );
+        if matches!(debugged, Some((_, Some(_)))) {
+            self.metrics.replanned();
+        }
+        debugged
+    }
+
+    /// The `SIMILAR_ANOMALIES` past anomalies of its context most like `anomaly`,
+    /// by `search_memory`; none without learning.
+    pub(crate) fn similar_anomalies(&self, anomaly: &Anomaly) -> Vec<MemorySearchHit> {
+        if !self.learning.enabled {
+            return vec![];
+        }
+        // A context not yet created, or whose memory has another dimension, has none.
+        self.search_memory(&anomaly.context_id, &anomaly.text(), &[anomaly::filter()], SIMILAR_ANOMALIES)
+            .unwrap_or_default()
+    }
+
+    /// Keeps `anomaly` in its context's memory for later ones to be compared
+    /// with, unless learning is off.
+    pub(crate) fn remember_anomaly(&self, anomaly: &Anomaly) {
+        if !self.learning.enabled {
+            return;
+        }
+        if let Err(err) = self.remember_payload(&anomaly.context_id, &anomaly.text(), anomaly.payload()) {
+            warn!("Anomaly not remembered: {}", err);
+        }
+    }
+
+    /// Records `steps` as how a run of `command` recovered from its latest
+    /// remembered failure of `subtask`, which `self_debug` shows with it.
+    pub(crate) fn resolve_anomaly(&self, context_id: &str, command: &str, subtask: &str, steps: &[String]) {
+        // Remembered anomalies were redacted, and so is what they are matched on.
+        let (command, subtask) = (self.redactor.redacted(command), self.redactor.redacted(subtask));
+        let steps: Vec<String> = steps.iter().map(|step| self.redactor.redacted(step)).collect();
+        let resolved = self.update_context(context_id, |context| anomaly::resolve(context, &command, &subtask, &steps));
+        debug!(subtask = subtask.as_str(), resolved = resolved.unwrap_or(false), "Anomaly resolution");
+    }
+
+    /// Anomalies whose store failed, waiting to be retried with the next one
+    /// logged or on `shutdown`; a growing count means memory is unreachable.
+    pub fn pending_anomaly_count(&self) -> usize {
+        self.anomalies.pending()
+    }
+
+    pub fn process(&self, command: String, context_id: &str) -> String {
+        self.process_with_timeout(command, context_id, None)
+    }
+
+    /// `process`, unless another run holds `context_id`: then fails with
+    /// `ContextBusy` rather than waiting its turn.
+    pub fn try_process(&self, command: String, context_id: &str) -> Result<String, OrchestratorError> {
+        let lock = self.run_locks.try_acquire(context_id, &command, self.clock.as_ref())?;
+        Ok(self.complete_run(ProcessRun::new(command, context_id.to_string(), None).with_lock(lock)))
+    }
+
+    /// `process`, returning everything the run did rather than its outputs. With
+    /// `coalesce_window` set, an identical run in flight or just finished on the
+    /// context is returned instead, marked `coalesced`.
+    pub fn process_report(&self, command: String, context_id: &str) -> ProcessReport {
+        self.run_report(ProcessRun::new(command, context_id.to_string(), None))
+    }
+
+    /// `process_report` for a run as `submission` describes it: with its own
+    /// timeout, the caller's run id, or a priority in the submission queue.
+    pub fn process_report_with(&self, command: String, context_id: &str, submission: Submission) -> ProcessReport {
+        self.run_report(ProcessRun::submitted(command, context_id.to_string(), submission))
+    }
+
+    /// `process` with `timeout` overriding the configured subtask timeout for this run.
+    pub fn process_with_timeout(&self, command: String, context_id: &str, timeout: Option<Duration>) -> String {
+        self.complete_run(ProcessRun::new(command, context_id.to_string(), timeout))
+    }
+
+    /// `process` with LLM subtasks also limited by `budget` for this run. What the
+    /// run spent is reported in its `Completed` event.
+    pub fn process_with_budget(&self, command: String, context_id: &str, budget: Budget) -> String {
+        self.complete_run(ProcessRun::new(command, context_id.to_string(), None).with_budget(budget))
+    }
+
+    /// `process` run at most once per `idempotency_key` while the key is within its
+    /// TTL: a retry with the key gets the stored run back, marked `replayed`, without
+    /// dispatching anything. A retry while the run is still in flight fails with
+    /// `IdempotencyPending`; callers sharing the orchestrator can wait for it with
+    /// `idempotency_keys()` before retrying. Keys are per tenant.
+    pub fn process_idempotent(
+        &self,
+        command: String,
+        context_id: &str,
+        timeout: Option<Duration>,
+        idempotency_key: &str,
+    ) -> Result<IdempotentRun, OrchestratorError> {
+        self.process_idempotent_with(command, context_id, idempotency_key, Submission::default().timeout(timeout))
+    }
+
+    /// `process_idempotent` for a run it dispatches as `submission` describes, as
+    /// `process_report_with`.
+    pub fn process_idempotent_with(
+        &self,
+        command: String,
+        context_id: &str,
+        idempotency_key: &str,
+        submission: Submission,
+    ) -> Result<IdempotentRun, OrchestratorError> {
+        self.complete_idempotent_run(ProcessRun::submitted(command, context_id.to_string(), submission), idempotency_key)
+    }
+
+    fn complete_idempotent_run(&self, mut run: ProcessRun, key: &str) -> Result<IdempotentRun, OrchestratorError> {
+        if let Some(replayed) = self.begin_idempotent(&run, key)? {
+            return Ok(replayed);
+        }
+        let events: Vec<ProcessEvent> = std::iter::from_fn(|| run.next_event(self)).collect();
+        Ok(self.complete_idempotent(&run, key, &events))
+    }
+
+    /// Shared with the front ends, which wait on keys in flight with it.
+    pub fn idempotency_keys(&self) -> IdempotencyKeys {
+        self.idempotency.clone()
+    }
+
+    /// Claims `key` for `run`, or returns the run that completed it.
+    pub(crate) fn begin_idempotent(&self, run: &ProcessRun, key: &str) -> Result<Option<IdempotentRun>, OrchestratorError> {
+        self.drain.check()?;
+        let key = tenant::context_key(tenant::split_key(run.context_id()).0, key);
+        self.idempotency.begin(&key, run.context_id(), run.command(), self.clock.now())
+    }
+
+    /// Stores what `run` produced under `key`, from its `events`.
+    pub(crate) fn complete_idempotent(&self, run: &ProcessRun, key: &str, events: &[ProcessEvent]) -> IdempotentRun {
+        let mut completed = IdempotentRun {
+            output: String::new(),
+            results: vec![],
+            replanned: vec![],
+            run_id: Some(run.run_id().to_string()),
+            replayed: false,
+        };
+        for event in events {
+            match event {
+                ProcessEvent::SubtaskFinished { result, .. } => completed.results.push(result.clone()),
+                ProcessEvent::Completed { output, replanned, .. } => {
+                    completed.output = output.clone();
+                    completed.replanned = replanned.clone();
+                }
+                _ => {}
+            }
+        }
+        let key = tenant::context_key(tenant::split_key(run.context_id()).0, key);
+        self.idempotency.complete(&key, completed.clone(), self.clock.now());
+        completed
+    }
+
+    fn run_report(&self, mut run: ProcessRun) -> ProcessReport {
+        let coalesces = run.coalesces();
+        let (context_id, command) = (run.context_id().to_string(), run.command().to_string());
+        let mut drive = || {
+            while run.next_event(self).is_some() {}
+            self.redact_report(run.report(self.clock.now()))
+        };
+        if !coalesces {
+            return drive();
+        }
+        match self.coalescer.run(&context_id, &command, || self.clock.now(), drive) {
+            (report, true) => ProcessReport { coalesced: true, ..report },
+            (report, false) => report,
+        }
+    }
+
+    /// `report` with every string in it redacted, counting the matches in `redactions`.
+    pub(crate) fn redact_report(&self, report: ProcessReport) -> ProcessReport {
+        match self.redactor.redact_serialized(&report) {
+            Some((redacted, redactions)) => ProcessReport { redactions, ..redacted },
+            None => report,
+        }
+    }
+
+    /// The report in the format `process` returns.
+    fn complete_run(&self, run: ProcessRun) -> String {
+        let report = self.run_report(run);
+        if self.legacy_output {
+            return report.legacy_output();
+        }
+        serde_json::to_string(&report).unwrap_or_else(|_| report.legacy_output())
+    }
+
+    /// `process`, reporting each step to `sink` as it happens; the last event is
+    /// always `Completed` with the output `process` would return. A run that
+    /// `coalesce_window` joins to an identical one gets that run's plan, results
+    /// and completion once it is done, its `Completed` marked `coalesced`.
+    pub fn process_streaming(&self, command: String, context_id: &str, sink: impl FnMut(ProcessEvent)) {
+        self.process_streaming_with_timeout(command, context_id, None, sink)
+    }
+
+    pub fn process_streaming_with_timeout(
+        &self,
+        command: String,
+        context_id: &str,
+        timeout: Option<Duration>,
+        sink: impl FnMut(ProcessEvent),
+    ) {
+        self.process_streaming_with(command, context_id, Submission::default().timeout(timeout), sink)
+    }
+
+    /// `process_streaming` for a run as `submission` describes, as `process_report_with`.
+    pub fn process_streaming_with(
+        &self,
+        command: String,
+        context_id: &str,
+        submission: Submission,
+        mut sink: impl FnMut(ProcessEvent),
+    ) {
+        let mut run = ProcessRun::submitted(command, context_id.to_string(), submission);
+        if !run.coalesces() {
+            while let Some(event) = run.next_event(self) {
+                sink(event);
+            }
+            return;
+        }
+        let (context_id, command) = (run.context_id().to_string(), run.command().to_string());
+        let drive = || {
+            while let Some(event) = run.next_event(self) {
+                sink(event);
+            }
+            self.redact_report(run.report(self.clock.now()))
+        };
+        if let (report, true) = self.coalescer.run(&context_id, &command, || self.clock.now(), drive) {
+            ProcessEvent::coalesced(&report).into_iter().for_each(sink);
+        }
+    }
+
+    /// Runs `process` on a background thread and streams its events, while other
+    /// callers keep using the orchestrator; dropping the receiver stops the run
+    /// before its next step.
+    pub fn process_channel(orchestrator: Arc<Self>, command: String, context_id: String) -> mpsc::Receiver<ProcessEvent> {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let mut run = ProcessRun::new(command, context_id, None);
+            while let Some(event) = run.next_event(&orchestrator) {
+                if tx.send(event).is_err() {
+                    run.abandon(&orchestrator);
+                    return;
+                }
+            }
+        });
+        rx
+    }
+
+    /// When set, viral subtasks run on the native propagator instead of `python.agents.viral_agent`.
+    pub fn set_prefer_native(&self, prefer_native: bool) {
+        self.prefer_native.store(prefer_native, Ordering::Relaxed);
+    }
+
+    pub fn prefer_native(&self) -> bool {
+        self.prefer_native.load(Ordering::Relaxed)
+    }
+
+    /// Runs the native propagation model directly, outside any context.
+    pub fn simulate_viral(&self, nodes: usize, hook_rate: f64, rounds: usize, seed: u64) -> PropagationReport {
+        self.viral_propagator.simulate(nodes, hook_rate, rounds, seed)
+    }
+
+    /// Re-derives `amplification_factor` and `quantum_fidelity` from the context's
+    /// current virality and hook rate, returning the updated metrics.
+    pub fn amplify_metrics(&self, context_id: &str) -> Result<ViralMetrics, OrchestratorError> {
+        self.update_context(context_id, |context| {
+            self.quantum_amplifier.amplify_metrics(&mut context.viral_metrics);
+            context.viral_metrics.clone()
+        })
+    }
+
+    /// The context's metrics after each viral subtask, oldest first, from `since`
+    /// on when given.
+    pub fn metrics_history(&self, context_id: &str, since: Option<DateTime<Utc>>) -> Result<Vec<MetricsSample>, OrchestratorError> {
+        self.read_context(context_id, |context| context.metrics_history.since(since))
+    }
+
+    /// Per-second slope of each metric over the context's metrics history; `None`
+    /// until two samples at different times have been taken.
+    pub fn metrics_trend(&self, context_id: &str) -> Result<Option<MetricsTrend>, OrchestratorError> {
+        self.read_context(context_id, |context| context.metrics_history.trend())
+    }
+
+    /// Searches for a hook rate at which the native propagator reaches
+    /// `target_virality` on the context, running up to `max_iters` simulations
+    /// with the propagator's rounds and seed. Each trial sets the context's hook
+    /// rate and virality, re-amplifies its metrics and is recorded in its metrics
+    /// history; the context keeps the last trial's.
+    pub fn auto_tune_hook_rate(&self, context_id: &str, target_virality: f64, max_iters: usize) -> Result<TuneReport, OrchestratorError> {
+        let (propagator, amplifier, recorder) = (&self.viral_propagator, &self.quantum_amplifier, &self.metrics_recorder);
+        let (trials, hook_rate, virality) = self.update_context(context_id, |context| {
+            let nodes = context.viral_metrics.engagement_nodes;
+            let trials = tuning::bisect(context.viral_metrics.hook_rate, target_virality, max_iters, |hook_rate| {
+                let virality = propagator.simulate(nodes, hook_rate, propagator.rounds, propagator.seed).virality_score;
+                context.viral_metrics.hook_rate = hook_rate;
+                context.viral_metrics.virality_score = virality;
+                amplifier.amplify_metrics(&mut context.viral_metrics);
+                recorder.record(context);
+                virality
+            });
+            if !trials.is_empty() {
+                self.context_updated(context);
+            }
+            (trials, context.viral_metrics.hook_rate, context.viral_metrics.virality_score)
+        })?;
+        debug!(context_id, trials = trials.len(), hook_rate, virality, "tuned hook rate");
+        Ok(TuneReport {
+            context_id: context_id.to_string(),
+            target_virality,
+            reached: trials.last().is_some_and(|trial| trial.virality >= target_virality),
+            trials,
+            hook_rate,
+            virality,
+        })
+    }
+
+    /// Has `self_debug` run `auto_tune_hook_rate` on "low virality" failures before
+    /// re-planning, so the re-planned steps start from the tuned hook rate.
+    pub fn with_auto_tune(self, target_virality: f64, max_iters: usize) -> Self {
+        self.set_auto_tune(Some(AutoTune { target_virality, max_iters }));
+        self
+    }
+
+    pub fn set_auto_tune(&self, auto_tune: Option<AutoTune>) {
+        *locked(&self.auto_tune) = auto_tune;
+    }
+
+    pub fn auto_tune(&self) -> Option<AutoTune> {
+        *locked(&self.auto_tune)
+    }
+
+    fn tune_after_failure(&self, result: &AgentResult, context_id: &str) {
+        let Some(tune) = self.auto_tune() else { return };
+        if result.status || !result.output.contains("low virality") || !self.contexts.contains(context_id) {
+            return;
+        }
+        if let Ok(report) = self.auto_tune_hook_rate(context_id, tune.target_virality, tune.max_iters) {
+            info!(context_id, hook_rate = report.hook_rate, reached = report.reached, "auto-tuned hook rate");
+        }
+    }
+
+    /// Routes anomaly logging to a native store instead of `python.memory`.
+    pub fn set_memory_store(&self, store: Arc<dyn MemoryStore>) {
+        *write(&self.memory_store) = Some(store);
+    }
+
+    pub fn memory_store(&self) -> Option<Arc<dyn MemoryStore>> {
+        read(&self.memory_store).clone()
+    }
+
+    pub fn agent_modules(&self) -> AgentModuleConfig {
+        self.agent_modules.config()
+    }
+
+    /// Points one built-in agent at another Python class; takes effect on its next call.
+    pub fn set_agent_module(&self, kind: AgentKind, module: AgentModule) {
+        self.agent_modules.set(kind, module);
+    }
+
+    /// Drops the cached planner, LLM, viral, debug and memory agent instances so the
+    /// next call constructs fresh ones.
+    pub fn reload_agents(&self) {
+        self.agent_modules.reload();
+    }
+
+    /// Imports each configured agent module and looks up its class, so a missing
+    /// or misnamed one surfaces at startup instead of as a fallback mid-run.
+    pub fn validate_agents(&self) -> Vec<AgentAvailability> {
+        self.agent_modules.validate()
+    }
+
+    /// Checks what runs need, without starting one: that each agent module the
+    /// backend calls imports with the methods it expects, that the memory and
+    /// context stores answer, and that the persistence paths are writable.
+    pub fn preflight(&self) -> PreflightReport {
+        let mut checks = vec![];
+        match self.backend.agent_modules() {
+            Some(modules) => {
+                for kind in AgentKind::ALL {
+                    let unused = match kind {
+                        AgentKind::Viral if self.prefer_native() => Some("unused while prefer_native is on"),
+                        AgentKind::Memory if self.memory_store().is_some() => Some("unused while a memory store is set"),
+                        _ => None,
+                    };
+                    checks.push(preflight::check_agent(kind, &modules.get(kind), unused));
+                }
+            }
+            None => checks.push(PreflightCheck::pass("agents", "the backend calls no Python agent modules")),
+        }
+        checks.push(preflight::check_memory(self.memory_store().as_deref()));
+        if let Some(store) = &self.context_store {
+            checks.push(preflight::check_context_store(store.as_ref()));
+        }
+        if let Some(path) = &self.eviction_path {
+            checks.push(PreflightCheck::writable("paths.eviction", path));
+        }
+        if let Some(journal) = self.journal() {
+            checks.push(PreflightCheck::writable("paths.journal", journal.path()));
+        }
+        PreflightReport { checks }
+    }
+
+    /// `preflight`, then plans and generates a reply to a no-op prompt through the
+    /// backend. No context is touched, but the agents do run.
+    pub fn preflight_with_smoke(&self) -> PreflightReport {
+        let mut report = self.preflight();
+        report.checks.extend(preflight::smoke(self.backend.as_ref()));
+        report
+    }
+
+    /// Adds a dispatch route. Agents are matched by priority, then registration
+    /// order, so the built-in LLM and viral agents win ties against later ones.
+    pub fn register_agent(&self, agent: Box<dyn Agent>) {
+        write(&self.agents).register(agent);
+    }
+
+    pub fn agent_names(&self) -> Vec<String> {
+        read(&self.agents).names()
+    }
+
+    /// Runs one subtask; it is recorded in the context's history with itself as the command.
+    /// A subtask the input policy refuses is neither run nor recorded.
+    pub fn dispatch(&self, sub_task: String, context_id: &str) -> AgentResult {
+        let sub_task = match self.input_policy.clean(&sub_task) {
+            Ok(sub_task) => sub_task,
+            Err(err) => return AgentResult::from_error("Input Error", err),
+        };
+        let started = Instant::now();
+        let result = self.dispatch_with_timeout(sub_task.clone(), context_id, self.subtask_timeout, &Blackboard::default());
+        self.record_execution(context_id, &sub_task, &sub_task, &result, started.elapsed());
+        result
+    }
+
+    fn dispatch_with_timeout(
+        &self,
+        sub_task: String,
+        context_id: &str,
+        timeout: Option<Duration>,
+        blackboard: &Blackboard,
+    ) -> AgentResult {
+        let _span = dispatch_span(&self.redactor.redacted(&sub_task), context_id).entered();
+        let started = Instant::now();
+        let retry = self.retry_policy();
+        if timeout.is_some() || retry.retries() {
+            let job = self.prepare_dispatch(sub_task, context_id).with_blackboard(blackboard);
+            let agent = job.agent_name();
+            let (mut result, context) = job.run_with_policy(&retry, timeout);
+            timing::record_total(&mut result, started.elapsed());
+            self.metrics.dispatched(agent.as_deref(), &result, started.elapsed());
+            if let Some(context) = context {
+                self.complete_dispatch(context);
+            }
+            return result;
+        }
+
+        let agent = self.route(&sub_task);
+        let agent_name = agent.as_ref().ok().map(|agent| agent.name().to_string());
+        let guards = self.guards();
+        let slot = self.ensure_context(context_id);
+        let mut context = context_map::lock(&slot);
+        let mut result = match agent {
+            Ok(agent) => execute_agent(agent.as_ref(), &sub_task, &mut context, blackboard, &guards),
+            Err(err) => unknown_subtask(err),
+        };
+        timing::record_total(&mut result, started.elapsed());
+        self.metrics.dispatched(agent_name.as_deref(), &result, started.elapsed());
+        self.context_updated(&context);
+        result
+    }
+
+    /// Dispatches one wave of independent plan nodes. A single subtask runs like
+    /// `dispatch`; several run on their own threads against copies of the context,
+    /// with the GIL released while they do, and what each changed is merged back
+    /// in plan order. Each result comes with its own wall time; agents see the
+    /// run's `blackboard`.
+    fn dispatch_wave(
+        &self,
+        subtasks: &[String],
+        context_id: &str,
+        timeout: Option<Duration>,
+        blackboard: &Blackboard,
+    ) -> Vec<(AgentResult, Duration)> {
+        if let [sub_task] = subtasks {
+            let started = Instant::now();
+            let result = self.dispatch_with_timeout(sub_task.clone(), context_id, timeout, blackboard);
+            return vec![(result, started.elapsed())];
+        }
+        let jobs = self.prepare_wave(subtasks, context_id, blackboard);
+        let base = jobs.first().map(|job| job.context.clone());
+        let retry = self.retry_policy();
+        let finished = Python::with_gil(|py| py.allow_threads(|| run_jobs(jobs, &retry, timeout, context_id)));
+        self.complete_wave(base.as_ref(), finished)
+    }
+
+    fn prepare_wave(&self, subtasks: &[String], context_id: &str, blackboard: &Blackboard) -> Vec<DispatchJob> {
+        subtasks.iter().map(|sub| self.prepare_dispatch(sub.clone(), context_id).with_blackboard(blackboard)).collect()
+    }
+
+    /// Merges back what each subtask of a wave `run_jobs` finished changed in its
+    /// copy of `base`, in plan order.
+    fn complete_wave(&self, base: Option<&Context>, finished: Vec<FinishedJob>) -> Vec<(AgentResult, Duration)> {
+        finished
+            .into_iter()
+            .map(|mut job| {
+                timing::record_total(&mut job.result, job.duration);
+                self.metrics.dispatched(job.agent.as_deref(), &job.result, job.duration);
+                if let (Some(base), Some(context)) = (base, job.context) {
+                    self.absorb_dispatch(base, context);
+                }
+                (job.result, job.duration)
+            })
+            .collect()
+    }
+
+    fn record_execution(&self, context_id: &str, command: &str, subtask: &str, result: &AgentResult, duration: Duration) {
+        let now = self.clock.now();
+        let timestamp = chrono::Duration::from_std(duration)
+            .ok()
+            .and_then(|elapsed| now.checked_sub_signed(elapsed))
+            .unwrap_or(now);
+        let record = ExecutionRecord {
+            timestamp,
+            command: command.to_string(),
+            subtask: subtask.to_string(),
+            result: result.clone(),
+            duration,
+        };
+        let record = match self.redactor.redact_serialized(&record) {
+            Some((redacted, _)) => redacted,
+            None => ExecutionRecord {
+                command: self.redactor.redacted(command),
+                subtask: self.redactor.redacted(subtask),
+                result: AgentResult {
+                    output: self.redactor.redacted(&result.output),
+                    metadata: HashMap::new(),
+                    error: None,
+                    ..result.clone()
+                },
+                ..record
+            },
+        };
+        locked(&self.history).record(context_id, record);
+    }
+
+    /// Limits the context's LLM subtasks to `budget` from now on. What the context
+    /// has already spent against a previous budget still counts.
+    pub fn set_budget(&self, context_id: &str, budget: Budget) {
+        self.budgets.set(context_id, budget);
+    }
+
+    pub fn clear_budget(&self, context_id: &str) {
+        self.budgets.clear(context_id);
+    }
+
+    /// The context's budget, spend and what remains, when it has a budget.
+    pub fn get_budget(&self, context_id: &str) -> Option<BudgetStatus> {
+        self.budgets.status(context_id)
+    }
+
+    /// Snapshots taken of the context by auto-snapshotting runs, oldest first; the
+    /// last `DEFAULT_SNAPSHOT_LIMIT` are kept.
+    pub fn get_snapshots(&self, context_id: &str) -> Vec<ContextSnapshot> {
+        locked(&self.snapshots).get(context_id)
+    }
+
+    /// Records a snapshot of the context, creating it, when auto-snapshots are on.
+    fn auto_snapshot(&self, context_id: &str) {
+        if !self.auto_snapshots {
+            return;
+        }
+        let now = self.clock.now();
+        let snapshot = context_map::lock(&self.ensure_context(context_id)).snapshot_at(now);
+        locked(&self.snapshots).record(snapshot);
+    }
+
+    /// The context's most recent `limit` execution records (all when `None`), oldest first.
+    pub fn get_history(&self, context_id: &str, limit: Option<usize>) -> Vec<ExecutionRecord> {
+        locked(&self.history).get(context_id, limit)
+    }
+
+    pub fn export_history(&self, context_id: &str, format: HistoryFormat) -> String {
+        locked(&self.history).export(context_id, format)
+    }
+
+    /// The backend's route, else for the structured form the agent its tag names,
+    /// else the first agent that accepts the subtask. One meant in the structured
+    /// form but malformed fails with that error unless some agent accepts it as is.
+    fn route(&self, sub_task: &str) -> Result<Arc<dyn Agent>, OrchestratorError> {
+        if let Some(agent) = self.backend.route(sub_task) {
+            return Ok(agent);
+        }
+        let parsed = Subtask::is_structured(sub_task).then(|| Subtask::parse(sub_task));
+        let agents = read(&self.agents);
+        if let Some(Ok(subtask)) = &parsed {
+            if let Some(agent) = agents.get(&subtask.agent) {
+                return Ok(agent);
+            }
+        }
+        agents.route(sub_task).ok_or_else(|| match parsed {
+            Some(Err(err)) => err,
+            _ => OrchestratorError::UnknownSubtask { subtask: sub_task.to_string(), agents: agents.names() },
+        })
+    }
+
+    /// Routes a subtask and snapshots its context without calling into Python.
+    fn prepare_dispatch(&self, sub_task: String, context_id: &str) -> DispatchJob {
+        let agent = self.route(&sub_task);
+        let context = context_map::lock(&self.ensure_context(context_id)).clone();
+        let cancel = self.cancellations.token(context_id);
+        DispatchJob { sub_task, agent, context, blackboard: Blackboard::default(), guards: self.guards(), cancel, attempt: 1 }
+    }
+
+    fn guards(&self) -> Guards {
+        Guards {
+            rate_limits: self.rate_limits.clone(),
+            cache: self.result_cache.clone(),
+            breakers: self.breakers.clone(),
+            post_processors: self.post_processors.clone(),
+            redactor: self.redactor.clone(),
+        }
+    }
+
+    /// Adds a goal, creating the context, and returns it with `created_at` set from
+    /// the orchestrator's clock. Goal ids are unique per context.
+    pub fn add_goal(&self, context_id: &str, mut goal: Goal) -> Result<Goal, OrchestratorError> {
+        goal.created_at = self.clock.now();
+        let slot = self.ensure_context(context_id);
+        let mut context = context_map::lock(&slot);
+        if context.goal(&goal.id).is_some() {
+            return Err(OrchestratorError::DuplicateGoal { context_id: context_id.to_string(), goal_id: goal.id });
+        }
+        context.active_goals.push(goal.clone());
+        Ok(goal)
+    }
+
+    /// Marks a goal completed and returns it; completing one twice, or an expired
+    /// one, is a no-op. Completing the last open goal publishes `goals_completed`.
+    pub fn complete_goal(&self, context_id: &str, goal_id: &str) -> Result<Goal, OrchestratorError> {
+        self.update_context(context_id, |context| {
+            let goal = context.active_goals.iter_mut().find(|goal| goal.id == goal_id).ok_or_else(|| {
+                OrchestratorError::UnknownGoal { context_id: context_id.to_string(), goal_id: goal_id.to_string() }
+            })?;
+            if !goal.is_active() {
+                return Ok(goal.clone());
+            }
+            goal.status = GoalStatus::Completed;
+            let goal = goal.clone();
+            if context.goals_complete() {
+                info!(context_id, "All goals completed");
+                let goal_ids = context.active_goals.iter().map(|goal| goal.id.clone()).collect();
+                self.events.publish(context_id, || context.attributes.clone(), || BusPayload::Goals(GoalsCompleted { goal_ids }));
+            }
+            Ok(goal)
+        })?
+    }
+
+    /// Marks the context's active goals whose deadline has passed by the
+    /// orchestrator's clock `Expired`, publishing `goals_expired`, and returns
+    /// them. Every run sweeps its context as it starts.
+    pub fn sweep_goals(&self, context_id: &str) -> Result<Vec<Goal>, OrchestratorError> {
+        let now = self.clock.now();
+        self.update_context(context_id, |context| {
+            let mut expired = vec![];
+            for goal in context.active_goals.iter_mut().filter(|goal| goal.is_overdue(now)) {
+                goal.status = GoalStatus::Expired;
+                expired.push(goal.clone());
+            }
+            if !expired.is_empty() {
+                info!(context_id, expired = expired.len(), "Goals expired");
+                let goal_ids = expired.iter().map(|goal| goal.id.clone()).collect();
+                let attributes = || context.attributes.clone();
+                self.events.publish(context_id, attributes, || BusPayload::ExpiredGoals(GoalsExpired { goal_ids }));
+            }
+            expired
+        })
+    }
+
+    pub fn goal_expiry(&self) -> GoalExpiry {
+        self.goal_expiry
+    }
+
+    /// The context's goals in the order they were added, completed ones included.
+    pub fn list_goals(&self, context_id: &str) -> Vec<Goal> {
+        self.contexts.read(context_id, |context| context.active_goals.clone()).unwrap_or_default()
+    }
+
+    /// Whether the context has goals and all of them are completed.
+    pub fn goals_complete(&self, context_id: &str) -> bool {
+        self.contexts.read(context_id, Context::goals_complete).unwrap_or(false)
+    }
+
+    /// Stores the working context a `DispatchJob` ran against.
+    fn complete_dispatch(&self, mut context: Context) {
+        context.last_accessed = self.clock.now();
+        self.context_updated(&context);
+        self.contexts.insert(context);
+        self.metrics.context_count(self.contexts.len());
+    }
+
+    /// Stores what a `DispatchJob` changed in its copy of `base` on top of the
+    /// context as other jobs of the same wave have left it.
+    fn absorb_dispatch(&self, base: &Context, changed: Context) {
+        let mut context = self.contexts.read(&changed.key(), Context::clone).unwrap_or_else(|| base.clone());
+        context.absorb(base, changed, self.metrics_recorder.limit());
+        self.complete_dispatch(context);
+    }
+
+    /// Dispatches every subtask of the plan, ignoring its dependencies, on up to `max_concurrency` threads, each
+    /// acquiring the GIL independently; results come back in plan order. With
+    /// `fail_fast`, the first failure stops workers from starting further subtasks,
+    /// which are reported as cancelled. Re-plans are logged but not executed, and
+    /// `{{...}}` references left as they are, since no subtask waits for another.
+    /// With a worker pool configured, the subtasks run on its warm workers, no
+    /// more at once than it has. Must not be called while holding the GIL.
+    pub fn process_parallel(
+        &self,
+        command: String,
+        context_id: &str,
+        max_concurrency: usize,
+        fail_fast: bool,
+    ) -> Vec<AgentResult> {
+        let command = match self.input_policy.clean(&command) {
+            Ok(command) => command,
+            Err(err) => return vec![AgentResult::from_error("Input Error", err)],
+        };
+        let span = info_span!("process_parallel", context_id, command = %self.redactor.redacted(&command));
+        let _entered = span.enter();
+        let reviewed = self.plan_or_fallback(command.clone(), context_id).and_then(|plan| {
+            let subtasks = plan.subtasks();
+            let blocked = self.review_plan(&subtasks)?;
+            Ok((subtasks, blocked))
+        });
+        let (subtasks, blocked) = match reviewed {
+            Ok(reviewed) => reviewed,
+            Err(err) => return vec![AgentResult::from_error("Plan Error", err)],
+        };
+        self.pin(context_id);
+        let jobs: Vec<Mutex<Option<DispatchJob>>> = subtasks
+            .iter()
+            .zip(&blocked)
+            .map(|(sub, blocked)| Mutex::new(blocked.is_none().then(|| self.prepare_dispatch(sub.clone(), context_id))))
+            .collect();
+        let base = jobs.iter().find_map(|job| job.lock().unwrap().as_ref().map(|job| job.context.clone()));
+        let results: Vec<Mutex<Option<(AgentResult, Duration)>>> = blocked
+            .into_iter()
+            .map(|blocked| Mutex::new(blocked.map(|err| (dispatch_policy::blocked_result(err), Duration::ZERO))))
+            .collect();
+        let contexts: Vec<Mutex<Option<Context>>> = subtasks.iter().map(|_| Mutex::new(None)).collect();
+        let memory = self.memory_store();
+        let memory = memory.as_deref();
+        let backend = self.backend.as_ref();
+        let (anomalies, clock) = (&self.anomalies, &self.clock);
+        let strategies = &self.debug_strategies();
+        // Remembered once the subtasks' contexts are written back, which would undo it.
+        let failures = Mutex::new(vec![]);
+        let timeout = self.subtask_timeout;
+        let retry = &self.retry_policy();
+        let metrics = &self.metrics;
+        let next = AtomicUsize::new(0);
+        let stop = AtomicBool::new(false);
+        let aborted = AtomicBool::new(false);
+        // With a pool, each thread here hands its jobs to a worker, as many at once as it has.
+        let pool = self.worker_pool.as_ref();
+        let concurrency = pool.map_or(max_concurrency, |pool| max_concurrency.min(pool.size()));
+
+        thread::scope(|scope| {
+            for _ in 0..concurrency.clamp(1, subtasks.len().max(1)) {
+                scope.spawn(|| span.in_scope(|| loop {
+                    if (fail_fast && stop.load(Ordering::SeqCst)) || aborted.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let idx = next.fetch_add(1, Ordering::SeqCst);
+                    let Some(job) = jobs.get(idx).and_then(|job| job.lock().unwrap().take()) else {
+                        break;
+                    };
+                    let started = Instant::now();
+                    let agent = job.agent_name();
+                    let span = dispatch_span(&self.redactor.redacted(&subtasks[idx]), context_id);
+                    let (mut res, context) = match pool {
+                        Some(pool) => {
+                            let retry = retry.clone();
+                            pool.run(move || span.in_scope(|| job.run_with_policy(&retry, timeout)))
+                        }
+                        None => span.in_scope(|| job.run_with_policy(retry, timeout)),
+                    };
+                    let duration = started.elapsed();
+                    timing::record_total(&mut res, duration);
+                    metrics.dispatched(agent.as_deref(), &res, duration);
+                    *contexts[idx].lock().unwrap() = context;
+                    if !res.status {
+                        stop.store(true, Ordering::SeqCst);
+                        let anomaly = Anomaly::new(context_id, &command, &subtasks[idx], &res, clock.now());
+                        let similar = self.similar_anomalies(&anomaly);
+                        failures.lock().unwrap().push(anomaly.clone());
+                        if let Some((decision, plan)) = debug_failure(backend, memory, anomalies, strategies, &res, anomaly, &similar) {
+                            if plan.is_some() {
+                                metrics.replanned();
+                            }
+                            aborted.fetch_or(decision.strategy == DebugStrategy::Abort, Ordering::SeqCst);
+                        }
+                    }
+                    *results[idx].lock().unwrap() = Some((res, duration));
+                }));
+            }
+        });
+
+        // What each subtask changed is merged in plan order, so none undoes another's.
+        if let Some(base) = &base {
+            for context in contexts.into_iter().filter_map(|c| c.into_inner().unwrap()) {
+                self.absorb_dispatch(base, context);
+            }
+        }
+        for anomaly in failures.into_inner().unwrap() {
+            self.remember_anomaly(&anomaly);
+        }
+        self.unpin(context_id);
+
+        let results: Vec<AgentResult> = results
+            .into_iter()
+            .zip(subtasks.iter().cloned())
+            .map(|(res, subtask)| match res.into_inner().unwrap() {
+                Some((res, duration)) => {
+                    self.record_execution(context_id, &command, &subtask, &res, duration);
+                    res
+                }
+                None => AgentResult::from_error("Cancelled", OrchestratorError::Cancelled { subtask }),
+            })
+            .collect();
+        if !results.is_empty() && results.iter().all(|res| res.status) {
+            let outputs: Vec<String> = results.iter().map(|res| res.output.clone()).collect();
+            self.learn_success(context_id, &command, &subtasks, &outputs);
+        }
+        results
+    }
+
+    /// Like `process`, but each Python call runs on the tokio blocking pool and the
+    /// future yields between subtasks. Dropping the future stops before the next subtask.
+    /// With `coalesce_window` set, an identical `process_async` run in flight or just
+    /// finished on the context hands over its output instead.
+    pub fn process_async<'a>(&'a self, command: String, context_id: &'a str) -> impl Future<Output = String> + Send + 'a {
+        async_process::drive(self, command, context_id.to_string())
+    }
+
+    /// Runs each `(command, context_id)` request like `process`, on up to
+    /// `concurrency` threads sharing this orchestrator and its agents; outcomes come
+    /// back in request order. Python agents take the GIL per call, so must not be
+    /// called while holding the GIL. Requests for the same context take turns on it
+    /// subtask by subtask.
+    pub fn process_batch(&self, requests: Vec<(String, String)>, concurrency: usize) -> Vec<BatchOutcome> {
+        batch::run(self, requests, concurrency, |_| {})
+    }
+
+    /// `process_batch`, appending each outcome to the JSONL file at `path` as soon as
+    /// its request finishes, so a crash loses only the requests still running. The
+    /// file is opened before anything runs; a failed write is logged and skipped.
+    pub fn process_batch_jsonl(
+        &self,
+        requests: Vec<(String, String)>,
+        concurrency: usize,
+        path: &Path,
+    ) -> Result<Vec<BatchOutcome>, OrchestratorError> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| OrchestratorError::io(path, e))?;
+        let file = Mutex::new(file);
+        Ok(batch::run(self, requests, concurrency, |outcome| {
+            let written = serde_json::to_string(outcome).map_err(|e| e.to_string()).and_then(|mut line| {
+                line.push('\n');
+                let mut file = file.lock().unwrap_or_else(|e| e.into_inner());
+                file.write_all(line.as_bytes()).and_then(|_| file.flush()).map_err(|e| e.to_string())
+            });
+            if let Err(err) = written {
+                warn!("Batch result {} not written to {}: {}", outcome.index, path.display(), err);
+            }
+        }))
+    }
+}
+
+#[pymethods]
+impl CognitiveOrchestrator {
+    /// `context_ttl` and `subtask_timeout` are in seconds; evicted contexts are merged
+    /// into `eviction_path` when given. `fixed_time` freezes the clock at that
+    /// datetime and `seed` fixes simulation and jitter randomness, for reproducible runs.
+    /// With `legacy_output=False`, `process` returns the run's `ProcessReport` as JSON;
+    /// `learning=False` stops successful runs from being remembered and reused,
+    /// `memory_quantization="int8"` stores memory vectors a byte per component, and
+    /// `persistence_format` ("json", "messagepack" or "bincode") encodes checkpoints.
+    /// With `auto_diff=True`, each report carries a `diff` against the context's
+    /// previous run of its command. `journal_path` journals every run there for
+    /// crash recovery, syncing as `journal_fsync` ("always", "plan" or "never") says.
+    #[new]
+    #[pyo3(signature = (
+        prefer_native=false,
+        context_ttl=None,
+        max_contexts=None,
+        eviction_path=None,
+        subtask_timeout=None,
+        max_replans=DEFAULT_MAX_REPLANS,
+        history_limit=DEFAULT_HISTORY_LIMIT,
+        metrics_history_limit=DEFAULT_METRICS_HISTORY_LIMIT,
+        seed=None,
+        fixed_time=None,
+        auto_snapshots=false,
+        legacy_output=true,
+        learning=true,
+        memory_quantization=None,
+        persistence_format=None,
+        auto_diff=false,
+        journal_path=None,
+        journal_fsync=None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn py_new(
+        prefer_native: bool,
+        context_ttl: Option<f64>,
+        max_contexts: Option<usize>,
+        eviction_path: Option<PathBuf>,
+        subtask_timeout: Option<f64>,
+        max_replans: usize,
+        history_limit: usize,
+        metrics_history_limit: usize,
+        seed: Option<u64>,
+        fixed_time: Option<DateTime<Utc>>,
+        auto_snapshots: bool,
+        legacy_output: bool,
+        learning: bool,
+        memory_quantization: Option<&str>,
+        persistence_format: Option<&str>,
+        auto_diff: bool,
+        journal_path: Option<PathBuf>,
+        journal_fsync: Option<&str>,
+    ) -> PyResult<Self> {
+        let quantization = match memory_quantization {
+            Some(name) => Quantization::parse(name)
+                .ok_or_else(|| PyValueError::new_err(format!("unknown memory quantization {:?}", name)))?,
+            None => Quantization::None,
+        };
+        let format = match persistence_format {
+            Some(name) => PersistenceFormat::parse(name)
+                .ok_or_else(|| PyValueError::new_err(format!("unknown persistence format {:?}", name)))?,
+            None => PersistenceFormat::Json,
+        };
+        let mut builder = Self::builder()
+            .prefer_native(prefer_native)
+            .learning(learning)
+            .max_replans(max_replans)
+            .history_limit(history_limit)
+            .metrics_history_limit(metrics_history_limit);
+        if let Some(secs) = context_ttl {
+            builder = builder.context_ttl(seconds(secs)?);
+        }
+        if let Some(secs) = subtask_timeout {
+            builder = builder.subtask_timeout(seconds(secs)?);
+        }
+        if let Some(max_contexts) = max_contexts {
+            builder = builder.max_contexts(max_contexts);
+        }
+        if let Some(path) = eviction_path {
+            builder = builder.eviction_path(path);
+        }
+        if let Some(path) = journal_path {
+            let fsync = match journal_fsync {
+                Some(name) => FsyncPolicy::parse(name).ok_or_else(|| PyValueError::new_err(format!("unknown journal fsync policy {:?}", name)))?,
+                None => FsyncPolicy::default(),
+            };
+            builder = builder.journal(path, JournalConfig { fsync, ..JournalConfig::default() });
+        }
+        if let Some(seed) = seed {
+            builder = builder.seed(seed);
+        }
+        if let Some(now) = fixed_time {
+            builder = builder.clock(Arc::new(FixedClock::new(now)));
+        }
+        Ok(builder
+            .build()?
+            .with_auto_snapshots(auto_snapshots)
+            .with_legacy_output(legacy_output)
+            .with_auto_diff(auto_diff)
+            .with_memory_quantization(quantization)
+            .with_persistence_format(format))
+    }
+
+    /// Builds from a dict shaped like `Config` (durations in seconds); unknown keys
+    /// and invalid values raise `ValueError` naming the setting.
+    #[staticmethod]
+    #[pyo3(name = "from_config")]
+    fn py_from_config(config: &PyAny) -> PyResult<Self> {
+        let value = depythonize(config).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        let config = Config::from_value(value, &HashMap::new())?;
+        Ok(CognitiveOrchestratorBuilder::from(config).build()?)
+    }
+
+    /// Builds from a TOML or YAML file; with `env`, `ACE_*` environment variables
+    /// override the file's values.
+    #[staticmethod]
+    #[pyo3(name = "from_config_file", signature = (path, env=true))]
+    fn py_from_config_file(path: PathBuf, env: bool) -> PyResult<Self> {
+        let config = if env { Config::load(path)? } else { Config::from_file(path)? };
+        Ok(CognitiveOrchestratorBuilder::from(config).build()?)
+    }
+
+    /// Builds from defaults overridden by `ACE_*` environment variables.
+    #[staticmethod]
+    #[pyo3(name = "from_env")]
+    fn py_from_env() -> PyResult<Self> {
+        Ok(CognitiveOrchestratorBuilder::from(Config::from_env()?).build()?)
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "init_tracing", signature = (level="info"))]
+    fn py_init_tracing(level: &str) -> PyResult<bool> {
+        Ok(Self::init_tracing(level)?)
+    }
+
+    /// Current metrics in the Prometheus text exposition format.
+    #[pyo3(name = "metrics_text")]
+    fn py_metrics_text(&self) -> String {
+        metrics::render(&self.metrics_registry())
+    }
+
+    /// Returns the bound `"host:port"`; port 0 picks a free one.
+    #[cfg(feature = "metrics_http")]
+    #[pyo3(name = "serve_metrics", signature = (addr="127.0.0.1:9898"))]
+    fn py_serve_metrics(&self, addr: &str) -> PyResult<String> {
+        Ok(self.serve_metrics(addr)?.to_string())
+    }
+
+    #[getter(prefer_native)]
+    fn py_get_prefer_native(&self) -> bool {
+        self.prefer_native()
+    }
+
+    #[setter(prefer_native)]
+    fn py_set_prefer_native(&self, prefer_native: bool) {
+        self.set_prefer_native(prefer_native);
+    }
+
+    /// `topology` is a dict like `{"kind": "barabasi_albert", "attachments": 2}`
+    /// overriding the configured one for this call.
+    #[pyo3(name = "simulate_viral", signature = (nodes, hook_rate, rounds=10, seed=0, topology=None))]
+    fn py_simulate_viral(
+        &self,
+        nodes: usize,
+        hook_rate: f64,
+        rounds: usize,
+        seed: u64,
+        topology: Option<&PyAny>,
+    ) -> PyResult<PropagationReport> {
+        Ok(match topology {
+            Some(topology) => {
+                let propagator = (*self.viral_propagator).clone().with_topology(py_topology(topology)?);
+                propagator.simulate(nodes, hook_rate, rounds, seed)
+            }
+            None => self.simulate_viral(nodes, hook_rate, rounds, seed),
+        })
+    }
+
+    /// The graph a viral simulation of `nodes` would spread over, as a dict of its
+    /// `topology` and sorted `edges` (pairs of node indices).
+    #[pyo3(name = "engagement_graph", signature = (nodes, seed=0, topology=None))]
+    fn py_engagement_graph(&self, py: Python, nodes: usize, seed: u64, topology: Option<&PyAny>) -> PyResult<PyObject> {
+        let topology = topology.map(py_topology).transpose()?.unwrap_or(self.viral_propagator.topology);
+        let graph = Graph::build(topology, nodes, seed);
+        let described = serde_json::json!({ "topology": topology, "nodes": nodes, "edges": graph.edges() });
+        Ok(pythonize(py, &described)?)
+    }
+
+    /// `timeout` (seconds) overrides the configured subtask timeout for this call.
+    /// `budget` is a dict with any of `max_llm_calls`, `max_tokens` and
+    /// `max_cost_usd`, limiting this run's LLM subtasks. Raises `RuntimeError` once
+    /// shutdown has begun. The run keeps the GIL rather than taking it per agent
+    /// call, letting it go while it waits and during native simulations.
+    ///
+    /// With `idempotency_key`, a command already run under the key returns its stored
+    /// output without dispatching. While the key's run is still in flight, `wait`
+    /// blocks with the GIL released until it completes; otherwise `RuntimeError` is
+    /// raised. `run_id` tags the run with the caller's id instead of a fresh one.
+    /// `priority` ("high", "normal" or "low") orders the run in the submission queue.
+    #[pyo3(
+        name = "process",
+        signature = (command, context_id, timeout=None, budget=None, idempotency_key=None, wait=true, run_id=None, priority=None)
+    )]
+    #[allow(clippy::too_many_arguments)]
+    fn py_process(
+        &self,
+        py: Python,
+        command: String,
+        context_id: &str,
+        timeout: Option<f64>,
+        budget: Option<&PyAny>,
+        idempotency_key: Option<&str>,
+        wait: bool,
+        run_id: Option<String>,
+        priority: Option<&str>,
+    ) -> PyResult<String> {
+        let run = py_run(command, context_id.to_string(), timeout, budget, run_id, priority)?;
+        let Some(key) = idempotency_key else {
+            self.drain.check()?;
+            return Ok(timing::hold_gil(py, || self.complete_run(run)));
+        };
+        if wait {
+            let keys = self.idempotency_keys();
+            py.allow_threads(|| keys.wait(key, None));
+        }
+        let completed = timing::hold_gil(py, || self.complete_idempotent_run(run, key))?;
+        Ok(completed.output)
+    }
+
+    /// `process`, raising `RuntimeError` at once while another run holds
+    /// `context_id` instead of waiting for it.
+    #[pyo3(name = "try_process")]
+    fn py_try_process(&self, py: Python, command: String, context_id: &str) -> PyResult<String> {
+        self.drain.check()?;
+        Ok(timing::hold_gil(py, || self.try_process(command, context_id))?)
+    }
+
+    #[pyo3(name = "last_report")]
+    fn py_last_report(&self, context_id: &str) -> Option<ProcessReport> {
+        self.last_report(context_id)
+    }
+
+    #[pyo3(name = "get_run")]
+    fn py_get_run(&self, run_id: &str) -> Option<ProcessReport> {
+        self.get_run(run_id)
+    }
+
+    #[pyo3(name = "recover_incomplete_runs")]
+    fn py_recover_incomplete_runs(&self) -> Vec<IncompleteRun> {
+        self.recover_incomplete_runs()
+    }
+
+    /// `RuntimeError` for a run that is not an incomplete one.
+    #[pyo3(name = "resume_run")]
+    fn py_resume_run(&self, py: Python, run_id: &str) -> PyResult<ProcessReport> {
+        Ok(py.allow_threads(|| self.resume_run(run_id))?)
+    }
+
+    #[pyo3(name = "abandon_run")]
+    fn py_abandon_run(&self, run_id: &str) -> PyResult<()> {
+        Ok(self.abandon_run(run_id)?)
+    }
+
+    #[pyo3(name = "compare_runs")]
+    fn py_compare_runs(&self, before: PyRef<'_, ProcessReport>, after: PyRef<'_, ProcessReport>) -> RunDiff {
+        self.compare_runs(&before, &after)
+    }
+
+    /// `process`, returning the run's `ProcessReport`.
+    #[pyo3(name = "process_report", signature = (command, context_id, timeout=None, budget=None, run_id=None, priority=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn py_process_report(
+        &self,
+        py: Python,
+        command: String,
+        context_id: &str,
+        timeout: Option<f64>,
+        budget: Option<&PyAny>,
+        run_id: Option<String>,
+        priority: Option<&str>,
+    ) -> PyResult<ProcessReport> {
+        let run = py_run(command, context_id.to_string(), timeout, budget, run_id, priority)?;
+        self.drain.check()?;
+        Ok(timing::hold_gil(py, || self.run_report(run)))
+    }
+
+    #[pyo3(name = "pending_anomaly_count")]
+    fn py_pending_anomaly_count(&self) -> usize {
+        self.pending_anomaly_count()
+    }
+
+    /// Cancels the runs in flight on `context_id`, as `CognitiveOrchestrator::cancel`;
+    /// safe to call from another thread while `process` runs. Returns whether a run
+    /// was in flight.
+    #[pyo3(name = "cancel")]
+    fn py_cancel(&self, context_id: &str) -> bool {
+        self.cancel(context_id)
+    }
+
+    /// Iterator of event dicts (`{"event": "plan_ready", "at": ..., ...}`) that
+    /// advances the run one step per `next()`.
+    #[pyo3(name = "process_stream", signature = (command, context_id, timeout=None, budget=None, run_id=None, priority=None))]
+    fn py_process_stream(
+        slf: Py<Self>,
+        command: String,
+        context_id: String,
+        timeout: Option<f64>,
+        budget: Option<&PyAny>,
+        run_id: Option<String>,
+        priority: Option<&str>,
+    ) -> PyResult<ProcessStream> {
+        Ok(ProcessStream::new(slf, py_run(command, context_id, timeout, budget, run_id, priority)?))
+    }
+
+    /// A `ContextHandle` on `context_id` for a `with` block. `persist` needs an
+    /// `eviction_path` to persist into, or raises ValueError.
+    #[pyo3(name = "context", signature = (context_id, persist=false, ephemeral=false))]
+    fn py_context(slf: Py<Self>, py: Python, context_id: String, persist: bool, ephemeral: bool) -> PyResult<ContextHandle> {
+        if persist && slf.borrow(py).eviction_path.is_none() {
+            return Err(PyValueError::new_err("persist requires an eviction_path"));
+        }
+        Ok(ContextHandle::new(slf, context_id, persist, ephemeral))
+    }
+
+    /// Waits up to `grace` seconds, with the GIL released, for runs in flight from
+    /// other threads or `process_async` to finish, then shuts down. Returns the
+    /// `ShutdownReport` as a dict.
+    #[pyo3(name = "shutdown", signature = (grace=30.0))]
+    fn py_shutdown(&self, py: Python, grace: f64) -> PyResult<PyObject> {
+        let grace = seconds(grace)?;
+        self.drain.begin(grace);
+        py.allow_threads(|| self.drain.wait_idle());
+        let report = py.allow_threads(|| self.shutdown(grace));
+        Ok(pythonize(py, &report)?)
+    }
+
+    #[pyo3(name = "set_budget", signature = (context_id, max_llm_calls=None, max_tokens=None, max_cost_usd=None))]
+    fn py_set_budget(&self, context_id: &str, max_llm_calls: Option<u64>, max_tokens: Option<u64>, max_cost_usd: Option<f64>) {
+        self.set_budget(context_id, Budget { max_llm_calls, max_tokens, max_cost_usd });
+    }
+
+    #[pyo3(name = "clear_budget")]
+    fn py_clear_budget(&self, context_id: &str) {
+        self.clear_budget(context_id);
+    }
+
+    /// `{"limit": ..., "used": ..., "remaining": ...}`, or None without a budget.
+    #[pyo3(name = "get_budget")]
+    fn py_get_budget(&self, py: Python, context_id: &str) -> PyResult<Option<PyObject>> {
+        Ok(self.get_budget(context_id).map(|status| pythonize(py, &status)).transpose()?)
+    }
+
+    /// Awaitable from asyncio; Python calls run on tokio's blocking pool, and
+    /// coroutines `async def` agent methods return run on the awaiting loop.
+    #[pyo3(name = "process_async")]
+    fn py_process_async<'py>(slf: Py<Self>, py: Python<'py>, command: String, context_id: String) -> PyResult<&'py PyAny> {
+        let event_loop: PyObject = pyo3_asyncio::tokio::get_current_loop(py)?.into();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let run = async_process::drive(async_process::PyOrchestrator(slf), command, context_id);
+            Ok(coroutine::on_loop(event_loop, run).await)
+        })
+    }
+
+    /// `requests` is a list of dicts with `command` and `context_id`; returns one
+    /// dict per request, in order. With `jsonl_path`, each is also appended to that
+    /// file as it finishes.
+    #[pyo3(name = "process_batch", signature = (requests, concurrency=4, jsonl_path=None))]
+    fn py_process_batch(
+        &self,
+        py: Python,
+        requests: &PyAny,
+        concurrency: usize,
+        jsonl_path: Option<PathBuf>,
+    ) -> PyResult<PyObject> {
+        let requests: Vec<batch::BatchRequest> = depythonize(requests).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        let requests = requests.into_iter().map(|request| (request.command, request.context_id)).collect();
+        let outcomes = py.allow_threads(|| match &jsonl_path {
+            Some(path) => self.process_batch_jsonl(requests, concurrency, path),
+            None => Ok(self.process_batch(requests, concurrency)),
+        })?;
+        Ok(pythonize(py, &outcomes)?)
+    }
+
+    #[pyo3(name = "process_parallel", signature = (command, context_id, max_concurrency, fail_fast=false))]
+    fn py_process_parallel(
+        &self,
+        py: Python,
+        command: String,
+        context_id: &str,
+        max_concurrency: usize,
+        fail_fast: bool,
+    ) -> Vec<AgentResult> {
+        py.allow_threads(|| self.process_parallel(command, context_id, max_concurrency, fail_fast))
+    }
+
+    /// `state` is a square matrix given as a list of equal-length rows.
+    #[pyo3(name = "amplify")]
+    fn py_amplify(&self, state: Vec<Vec<f64>>, rounds: usize) -> PyResult<AmplificationResult> {
+        let ncols = state.first().map_or(0, |row| row.len());
+        if state.iter().any(|row| row.len() != ncols) {
+            return Err(PyValueError::new_err("state rows must all have the same length"));
+        }
+        let matrix = faer::Mat::from_fn(state.len(), ncols, |i, j| state[i][j]);
+        Ok(self.quantum_amplifier.amplify(&matrix, rounds))
+    }
+
+    /// Estimated fidelity of `rounds` of amplification; unset rates default to the
+    /// amplifier's noise model.
+    #[pyo3(name = "estimate_fidelity", signature = (rounds, depolarizing=None, dephasing=None))]
+    fn py_estimate_fidelity(&self, py: Python, rounds: usize, depolarizing: Option<f64>, dephasing: Option<f64>) -> PyResult<f64> {
+        let defaults = self.quantum_amplifier.noise;
+        let noise = NoiseModel {
+            depolarizing: depolarizing.unwrap_or(defaults.depolarizing),
+            dephasing: dephasing.unwrap_or(defaults.dephasing),
+        };
+        if !(noise.depolarizing.is_finite() && noise.depolarizing >= 0.0 && noise.dephasing.is_finite() && noise.dephasing >= 0.0) {
+            return Err(PyValueError::new_err("noise rates must be non-negative numbers"));
+        }
+        Ok(py.allow_threads(|| self.quantum_amplifier.estimate_fidelity(rounds, noise)))
+    }
+
+    #[pyo3(name = "amplify_metrics")]
+    fn py_amplify_metrics(&self, context_id: &str) -> PyResult<ViralMetrics> {
+        Ok(self.amplify_metrics(context_id)?)
+    }
+
+    /// Tunes the context's hook rate toward `target_virality`; returns a dict with
+    /// the `trials` run and the final `hook_rate`, `virality` and `reached`.
+    #[pyo3(name = "auto_tune_hook_rate", signature = (context_id, target_virality=0.8, max_iters=DEFAULT_TUNE_ITERS))]
+    fn py_auto_tune_hook_rate(&self, py: Python, context_id: &str, target_virality: f64, max_iters: usize) -> PyResult<PyObject> {
+        Ok(pythonize(py, &self.auto_tune_hook_rate(context_id, target_virality, max_iters)?)?)
+    }
+
+    /// Has "low virality" failures tune the hook rate before re-planning; a
+    /// `target_virality` of None turns that off.
+    #[pyo3(name = "set_auto_tune", signature = (target_virality, max_iters=DEFAULT_TUNE_ITERS))]
+    fn py_set_auto_tune(&self, target_virality: Option<f64>, max_iters: usize) {
+        self.set_auto_tune(target_virality.map(|target_virality| AutoTune { target_virality, max_iters }));
+    }
+
+    /// Dicts of `at` and each viral metric, oldest first, from `since` on when given.
+    #[pyo3(name = "metrics_history", signature = (context_id, since=None))]
+    fn py_metrics_history(&self, py: Python, context_id: &str, since: Option<DateTime<Utc>>) -> PyResult<PyObject> {
+        Ok(pythonize(py, &self.metrics_history(context_id, since)?)?)
+    }
+
+    /// A dict of each metric's change per second over the metrics history, or None
+    /// until there are two samples at different times.
+    #[pyo3(name = "metrics_trend")]
+    fn py_metrics_trend(&self, py: Python, context_id: &str) -> PyResult<Option<PyObject>> {
+        Ok(self.metrics_trend(context_id)?.map(|trend| pythonize(py, &trend)).transpose()?)
+    }
+
+    /// `{agent: "module:Class"}` for the planner, llm, viral, debug and memory agents.
+    #[getter(agent_modules)]
+    fn py_agent_modules(&self) -> HashMap<&'static str, String> {
+        let config = self.agent_modules();
+        AgentKind::ALL
+            .into_iter()
+            .map(|kind| (kind.name(), config.get(kind).to_string()))
+            .collect()
+    }
+
+    /// `module` may be `module:Class`; otherwise `class_name`, or the agent's
+    /// default class, is used.
+    #[pyo3(name = "set_agent_module", signature = (agent, module, class_name=None))]
+    fn py_set_agent_module(&self, agent: &str, module: &str, class_name: Option<&str>) -> PyResult<()> {
+        let kind = AgentKind::parse(agent).ok_or_else(|| PyValueError::new_err(format!("Unknown agent: {}", agent)))?;
+        let default_class = AgentModuleConfig::default().get(kind).class.clone();
+        let mut module = AgentModule::parse(module, &default_class);
+        if let Some(class) = class_name {
+            module.class = class.to_string();
+        }
+        if module.module.is_empty() || module.class.is_empty() {
+            return Err(PyValueError::new_err("module and class must not be empty"));
+        }
+        self.set_agent_module(kind, module);
+        Ok(())
+    }
+
+    /// Limits dispatches to `agent` ("llm", "viral", ...) to `rate` calls per
+    /// second in bursts of up to `burst`; a `rate` of None removes the limit.
+    #[pyo3(name = "set_rate_limit", signature = (agent, rate, burst=1))]
+    fn py_set_rate_limit(&self, agent: &str, rate: Option<f64>, burst: u32) -> PyResult<()> {
+        let kind = AgentKind::parse(agent).ok_or_else(|| PyValueError::new_err(format!("Unknown agent: {}", agent)))?;
+        let limit = match rate {
+            Some(rate) => Some(RateLimit::new(rate, burst).ok_or_else(|| PyValueError::new_err("rate must be positive"))?),
+            None => None,
+        };
+        self.set_rate_limit(kind, limit);
+        Ok(())
+    }
+
+    /// Opens the circuit for `agent` ("llm", "viral", ...) after `failure_threshold`
+    /// consecutive failures to reach it, for `cooldown` seconds before a probe; a
+    /// `failure_threshold` of None removes the breaker.
+    #[pyo3(name = "set_circuit_breaker", signature = (agent, failure_threshold, cooldown=30.0))]
+    fn py_set_circuit_breaker(&self, agent: &str, failure_threshold: Option<u32>, cooldown: f64) -> PyResult<()> {
+        let kind = AgentKind::parse(agent).ok_or_else(|| PyValueError::new_err(format!("Unknown agent: {}", agent)))?;
+        let cooldown = seconds(cooldown)?;
+        self.set_circuit_breaker(kind, failure_threshold.map(|threshold| CircuitPolicy::new(threshold, cooldown)));
+        Ok(())
+    }
+
+    /// Appends a post-processor for results of `agent` ("llm", "viral", ...):
+    /// "trim_whitespace", "extract_json_block", "max_length_truncate" (cutting to
+    /// `max_chars`), or a callable `processor(subtask, result) -> AgentResult`.
+    #[pyo3(name = "add_post_processor", signature = (agent, processor, max_chars=None))]
+    fn py_add_post_processor(&self, py: Python, agent: &str, processor: Py<PyAny>, max_chars: Option<usize>) -> PyResult<()> {
+        let kind = AgentKind::parse(agent).ok_or_else(|| PyValueError::new_err(format!("Unknown agent: {}", agent)))?;
+        if let Ok(name) = processor.extract::<String>(py) {
+            match (name.as_str(), max_chars) {
+                ("trim_whitespace", _) => self.add_post_processor(kind, TrimWhitespace),
+                ("extract_json_block", _) => self.add_post_processor(kind, ExtractJsonBlock),
+                ("max_length_truncate", Some(max_chars)) => self.add_post_processor(kind, MaxLengthTruncate::new(max_chars)),
+                ("max_length_truncate", None) => return Err(PyValueError::new_err("max_length_truncate needs max_chars")),
+                _ => return Err(PyValueError::new_err(format!("Unknown post-processor: {}", name))),
+            }
+            return Ok(());
+        }
+        self.add_post_processor(kind, move |subtask: &str, result: AgentResult| {
+            Python::with_gil(|py| {
+                processor.call1(py, (subtask, result)).and_then(|processed| processed.extract(py)).unwrap_or_else(|err| {
+                    let target = format!("{}.post_processor", kind.name());
+                    let err = OrchestratorError::CallFailed { target, message: err.to_string(), traceback: None };
+                    AgentResult::from_error("Post-processor Error", err)
+                })
+            })
+        });
+        Ok(())
+    }
+
+    #[pyo3(name = "clear_post_processors")]
+    fn py_clear_post_processors(&self, agent: &str) -> PyResult<()> {
+        let kind = AgentKind::parse(agent).ok_or_else(|| PyValueError::new_err(format!("Unknown agent: {}", agent)))?;
+        self.clear_post_processors(kind);
+        Ok(())
+    }
+
+    /// Redacts matches of the regex `pattern` as `***<kind>***` before anything is
+    /// stored, logged or reported, alongside the built-in kinds.
+    #[pyo3(name = "add_redaction_pattern")]
+    fn py_add_redaction_pattern(&self, kind: &str, pattern: &str) -> PyResult<()> {
+        Ok(self.add_redaction_pattern(kind, pattern)?)
+    }
+
+    /// A dict per agent kind with its breaker `state` ("closed", "open" or
+    /// "half_open"), failure counts and `last_error`.
+    #[pyo3(name = "agent_health")]
+    fn py_agent_health(&self, py: Python) -> PyResult<PyObject> {
+        Ok(pythonize(py, &self.agent_health())?)
+    }
+
+    /// A dict per configured webhook with its `url`, `delivered`, `failed`,
+    /// `retries`, `dropped` and `queued` counts and `last_error`.
+    #[pyo3(name = "notifier_status")]
+    fn py_notifier_status(&self, py: Python) -> PyResult<PyObject> {
+        Ok(pythonize(py, &self.notifier_status())?)
+    }
+
+    /// Caches up to `capacity` agent results for `ttl` seconds (forever when None);
+    /// `exclude` lists agents ("llm", "viral", ...) never cached, by default viral.
+    /// A capacity of 0 turns the cache off.
+    #[pyo3(name = "set_result_cache", signature = (capacity, ttl=None, exclude=None))]
+    fn py_set_result_cache(&self, capacity: usize, ttl: Option<f64>, exclude: Option<Vec<String>>) -> PyResult<()> {
+        let exclude = exclude
+            .map(|names| {
+                names
+                    .iter()
+                    .map(|name| AgentKind::parse(name).ok_or_else(|| PyValueError::new_err(format!("Unknown agent: {}", name))))
+                    .collect::<PyResult<Vec<_>>>()
+            })
+            .transpose()?;
+        self.result_cache.configure(capacity, ttl.map(seconds).transpose()?);
+        if let Some(exclude) = exclude {
+            self.set_cache_exclusions(exclude);
+        }
+        Ok(())
+    }
+
+    /// A dict of `hits`, `misses`, `evictions` and `entries`, or None while the
+    /// cache is off.
+    #[pyo3(name = "cache_stats")]
+    fn py_cache_stats(&self, py: Python) -> PyResult<Option<PyObject>> {
+        Ok(self.cache_stats().map(|stats| pythonize(py, &stats)).transpose()?)
+    }
+
+    #[pyo3(name = "clear_result_cache")]
+    fn py_clear_result_cache(&self) {
+        self.clear_result_cache();
+    }
+
+    #[pyo3(name = "reload_agents")]
+    fn py_reload_agents(&self) {
+        self.reload_agents();
+    }
+
+    #[pyo3(name = "validate_agents")]
+    fn py_validate_agents(&self) -> Vec<AgentAvailability> {
+        self.validate_agents()
+    }
+
+    /// A dict with a `checks` list, each with a `name`, `status` ("pass", "warn"
+    /// or "fail"), `detail` and, unless passed, `remediation`. `smoke` also runs
+    /// the planner and LLM on a no-op prompt.
+    #[pyo3(name = "preflight", signature = (smoke=false))]
+    fn py_preflight(&self, py: Python, smoke: bool) -> PyResult<PyObject> {
+        let report = if smoke { self.preflight_with_smoke() } else { self.preflight() };
+        Ok(pythonize(py, &report)?)
+    }
+
+    /// Registers a Python object with `can_handle(sub_task)` and
+    /// `execute(sub_task, context_dict)` methods as a dispatch route.
+    #[pyo3(name = "register_python_agent", signature = (name, agent, priority=0))]
+    fn py_register_python_agent(&self, name: String, agent: Py<PyAny>, priority: i32) {
+        self.register_agent(Box::new(PyAgent::new(name, agent, priority)));
+    }
+
+    /// Starts `program` with `args` as a dispatch route speaking JSON lines over
+    /// stdin and stdout; see `SubprocessAgent`. Each call gets `timeout` seconds,
+    /// and a crashed process is started again up to `max_restarts` times.
+    #[pyo3(
+        name = "register_subprocess_agent",
+        signature = (name, program, args=None, timeout=DEFAULT_SUBPROCESS_TIMEOUT.as_secs_f64(), max_restarts=DEFAULT_SUBPROCESS_RESTARTS, priority=0)
+    )]
+    fn py_register_subprocess_agent(
+        &self,
+        name: String,
+        program: PathBuf,
+        args: Option<Vec<String>>,
+        timeout: f64,
+        max_restarts: u32,
+        priority: i32,
+    ) -> PyResult<()> {
+        let config = SubprocessConfig::new(program)
+            .args(args.unwrap_or_default())
+            .timeout(seconds(timeout)?)
+            .max_restarts(max_restarts)
+            .priority(priority);
+        self.register_agent(Box::new(SubprocessAgent::spawn(name, config)?));
+        Ok(())
+    }
+
+    /// Connects the native Qdrant client; anomalies stop going through `python.memory`.
+    #[cfg(feature = "qdrant")]
+    #[pyo3(name = "configure_qdrant", signature = (url, collection, vector_size=memory::DEFAULT_VECTOR_SIZE))]
+    fn py_configure_qdrant(&self, url: &str, collection: &str, vector_size: usize) -> PyResult<()> {
+        let store = memory::QdrantStore::new(url, collection, vector_size)?;
+        self.set_memory_store(Arc::new(store));
+        Ok(())
+    }
+
+    /// `retry_on(result) -> bool` replaces the default transient-failure check;
+    /// delays are in seconds. `max_attempts=1` turns retries off.
+    #[pyo3(name = "set_retry_policy", signature = (max_attempts, base_delay=0.1, max_delay=5.0, retry_on=None))]
+    fn py_set_retry_policy(
+        &self,
+        max_attempts: u32,
+        base_delay: f64,
+        max_delay: f64,
+        retry_on: Option<Py<PyAny>>,
+    ) -> PyResult<()> {
+        let mut policy = RetryPolicy::new(max_attempts);
+        policy.base_delay = seconds(base_delay)?;
+        policy.max_delay = seconds(max_delay)?;
+        if let Some(predicate) = retry_on {
+            policy.retry_on = Arc::new(move |result: &AgentResult| {
+                Python::with_gil(|py| {
+                    predicate
+                        .call1(py, (result.clone(),))
+                        .and_then(|verdict| verdict.is_true(py))
+                        .unwrap_or_else(|err| {
+                            warn!("retry_on failed: {}", err);
+                            false
+                        })
+                })
+            });
+        }
+        self.set_retry_policy(policy);
+        Ok(())
+    }
+
+    #[pyo3(name = "agent_names")]
+    fn py_agent_names(&self) -> Vec<String> {
+        self.agent_names()
+    }
+
+    #[pyo3(name = "dispatch")]
+    fn py_dispatch(&self, py: Python, sub_task: String, context_id: &str) -> AgentResult {
+        timing::hold_gil(py, || self.dispatch(sub_task, context_id))
+    }
+
+    /// Subtasks in execution order, or with `graph=True` the plan's nodes as
+    /// `{"id", "task", "deps"}` dicts, the same shape the Python planner may return.
+    /// `estimate` as a dict; wall times are in seconds.
+    #[pyo3(name = "estimate")]
+    fn py_estimate(&self, py: Python, command: String, context_id: &str) -> PyResult<PyObject> {
+        Ok(pythonize(py, &self.estimate(command, context_id)?)?)
+    }
+
+    #[pyo3(name = "proactive_plan", signature = (command, context_id, graph=false))]
+    fn py_proactive_plan(&self, py: Python, command: String, context_id: &str, graph: bool) -> PyResult<PyObject> {
+        let plan = self.proactive_plan(command, context_id)?;
+        if graph {
+            Ok(pythonize(py, &plan)?)
+        } else {
+            Ok(plan.subtasks().into_py(py))
+        }
+    }
+
+    /// Replacement subtasks in execution order, or `None` when there is no re-plan.
+    #[pyo3(name = "self_debug")]
+    fn py_self_debug(&self, result: AgentResult, orig_cmd: &str, context_id: &str) -> Option<Vec<String>> {
+        self.self_debug(&result, orig_cmd, context_id).map(|plan| plan.subtasks())
+    }
+
+    #[pyo3(name = "save_contexts")]
+    fn py_save_contexts(&self, path: PathBuf) -> PyResult<()> {
+        Ok(self.save_contexts(&path)?)
+    }
+
+    #[pyo3(name = "load_contexts")]
+    fn py_load_contexts(&self, path: PathBuf) -> PyResult<LoadReport> {
+        Ok(self.load_contexts(&path)?)
+    }
+
+    #[pyo3(name = "export_context")]
+    fn py_export_context(&self, context_id: &str) -> PyResult<String> {
+        Ok(self.export_context(context_id)?)
+    }
+
+    /// Raises `ValueError` for a rejected document, including an id collision
+    /// without `overwrite`.
+    #[pyo3(name = "import_context", signature = (json, overwrite=false))]
+    fn py_import_context(&self, json: &str, overwrite: bool) -> PyResult<String> {
+        Ok(self.import_context(json, overwrite)?)
+    }
+
+    #[pyo3(name = "fork_context")]
+    fn py_fork_context(&self, source_id: &str, new_id: &str) -> PyResult<Context> {
+        Ok(self.fork_context(source_id, new_id)?)
+    }
+
+    /// `goal_conflict` is "fail", "keep_target" or "take_source" and `metrics`
+    /// "max_virality" or "take_source"; source vectors at least
+    /// `dedupe_similarity` cosine-similar to a target one are skipped.
+    #[pyo3(
+        name = "merge_contexts",
+        signature = (target_id, source_id, goal_conflict="fail", dedupe_similarity=DEFAULT_DEDUPE_SIMILARITY, metrics="max_virality")
+    )]
+    fn py_merge_contexts(
+        &self,
+        target_id: &str,
+        source_id: &str,
+        goal_conflict: &str,
+        dedupe_similarity: f64,
+        metrics: &str,
+    ) -> PyResult<MergeReport> {
+        let strategy = MergeStrategy {
+            goal_conflict: GoalConflict::parse(goal_conflict)
+                .ok_or_else(|| PyValueError::new_err(format!("Unknown goal conflict strategy: {}", goal_conflict)))?,
+            dedupe_similarity,
+            metrics: MetricsMerge::parse(metrics)
+                .ok_or_else(|| PyValueError::new_err(format!("Unknown metrics merge: {}", metrics)))?,
+        };
+        Ok(self.merge_contexts(target_id, source_id, &strategy)?)
+    }
+
+    /// `kind` is one of "keyword", "prefix" or "regex".
+    #[pyo3(name = "register_plan_template")]
+    fn py_register_plan_template(&self, kind: &str, pattern: String, steps: Vec<String>) -> PyResult<()> {
+        let trigger = match kind {
+            "keyword" => PlanTrigger::Keyword(pattern),
+            "prefix" => PlanTrigger::Prefix(pattern),
+            "regex" => PlanTrigger::Regex(pattern),
+            other => return Err(PyValueError::new_err(format!("Unknown trigger kind: {}", other))),
+        };
+        Ok(self.register_plan_template(trigger, steps)?)
+    }
+
+    /// Replaces all templates from a JSON list of `{"trigger": {"kind", "pattern"}, "steps"}`.
+    #[pyo3(name = "set_plan_templates")]
+    fn py_set_plan_templates(&self, json: &str) -> PyResult<()> {
+        let templates: Vec<PlanTemplate> = from_json(json)?;
+        Ok(self.set_plan_templates(templates)?)
+    }
+
+    #[pyo3(name = "clear_plan_templates")]
+    fn py_clear_plan_templates(&self) {
+        self.clear_plan_templates();
+    }
+
+    /// Replaces the dispatch policy from a dict of `allow` and `deny` lists of
+    /// `{"kind": "exact" | "prefix" | "regex", "pattern"}`, `max_plan_len` and
+    /// `strict`.
+    #[pyo3(name = "set_dispatch_policy")]
+    fn py_set_dispatch_policy(&self, policy: &PyAny) -> PyResult<()> {
+        let policy: DispatchPolicy = depythonize(policy).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(self.set_dispatch_policy(policy)?)
+    }
+
+    #[pyo3(name = "dispatch_policy")]
+    fn py_dispatch_policy(&self, py: Python) -> PyResult<PyObject> {
+        Ok(pythonize(py, &self.dispatch_policy())?)
+    }
+
+    /// `failure_class` is one of `FailureClass`'s snake_case names, such as
+    /// "timeout", and `strategy` one of "replan", "retry", "skip" and "abort";
+    /// raises ValueError for others, or for re-planning or retrying blocked subtasks.
+    #[pyo3(name = "set_debug_strategy")]
+    fn py_set_debug_strategy(&self, failure_class: &PyAny, strategy: &PyAny) -> PyResult<()> {
+        let class: FailureClass = depythonize(failure_class).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        let strategy: DebugStrategy = depythonize(strategy).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        if let Some((class, message)) = self.debug_strategies().with(class, strategy).invalid() {
+            return Err(PyValueError::new_err(format!("{} {}", class, message)));
+        }
+        self.set_debug_strategy(class, strategy);
+        Ok(())
+    }
+
+    #[pyo3(name = "debug_strategies")]
+    fn py_debug_strategies(&self, py: Python) -> PyResult<PyObject> {
+        Ok(pythonize(py, &self.debug_strategies())?)
+    }
+
+    #[pyo3(name = "add_memory")]
+    fn py_add_memory(&self, context_id: &str, vec: Vec<f64>) -> PyResult<usize> {
+        Ok(self.add_memory(context_id, vec)?)
+    }
+
+    /// A `payload` dict is kept with the text for `search_memory` to filter on.
+    #[pyo3(name = "remember", signature = (context_id, text, payload=None))]
+    fn py_remember(&self, context_id: &str, text: &str, payload: Option<&PyAny>) -> PyResult<usize> {
+        match payload {
+            Some(payload) => {
+                let payload = depythonize(payload).map_err(|e| PyValueError::new_err(e.to_string()))?;
+                Ok(self.remember_payload(context_id, text, payload)?)
+            }
+            None => Ok(self.remember(context_id, text)?),
+        }
+    }
+
+    /// `filters` maps payload fields to the value they must hold, or to
+    /// `{"contains": substring}`. Hits are dicts with `index`, `text`, `payload`,
+    /// the fused `score` and `similarity`.
+    #[pyo3(name = "search_memory", signature = (context_id, query, filters=None, k=SIMILAR_ANOMALIES))]
+    fn py_search_memory(&self, py: Python, context_id: &str, query: &str, filters: Option<&PyAny>, k: usize) -> PyResult<PyObject> {
+        let filters = match filters {
+            Some(filters) => MemoryFilter::from_json(depythonize(filters).map_err(|e| PyValueError::new_err(e.to_string()))?),
+            None => vec![],
+        };
+        let hits = py.allow_threads(|| self.search_memory(context_id, query, &filters, k))?;
+        Ok(pythonize(py, &hits)?)
+    }
+
+    /// With a vector, `(index, similarity)` pairs; with a string, `(text,
+    /// similarity)` pairs over the `remember`ed texts.
+    #[pyo3(name = "recall")]
+    fn py_recall(&self, py: Python, context_id: &str, query: &PyAny, k: usize) -> PyResult<PyObject> {
+        if let Ok(text) = query.extract::<&str>() {
+            return Ok(self.recall_text(context_id, text, k)?.into_py(py));
+        }
+        let query_vec: Vec<f64> = query.extract()?;
+        Ok(self.recall(context_id, &query_vec, k)?.into_py(py))
+    }
+
+    /// `config` is a dict of `topology` and `virality_threshold`, each defaulting
+    /// as in `Config`; raises ValueError for an invalid one.
+    #[pyo3(name = "set_viral_config")]
+    fn py_set_viral_config(&self, context_id: &str, config: &PyAny) -> PyResult<()> {
+        self.set_viral_config(context_id, py_viral_config(config)?);
+        Ok(())
+    }
+
+    #[pyo3(name = "clear_viral_config")]
+    fn py_clear_viral_config(&self, context_id: &str) {
+        self.clear_viral_config(context_id);
+    }
+
+    #[pyo3(name = "reset_propagation")]
+    fn py_reset_propagation(&self, context_id: &str) -> bool {
+        self.reset_propagation(context_id)
+    }
+
+    #[pyo3(name = "viral_config")]
+    fn py_viral_config(&self, py: Python, context_id: &str) -> PyResult<PyObject> {
+        Ok(pythonize(py, &self.viral_config(context_id)?)?)
+    }
+
+    /// `value` must be JSON-like: None, bools, numbers, strings, lists and dicts
+    /// with string keys. Raises ValueError past `max_attribute_bytes`, and
+    /// RuntimeError for a `viral.*` value that leaves the viral settings invalid.
+    #[pyo3(name = "set_attribute")]
+    fn py_set_attribute(&self, context_id: &str, key: &str, value: &PyAny) -> PyResult<()> {
+        let value: serde_json::Value = depythonize(value).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        self.set_attribute(context_id, key, value).map_err(|e| match e {
+            OrchestratorError::AttributesTooLarge { .. } => PyValueError::new_err(e.to_string()),
+            e => e.into(),
+        })
+    }
+
+    /// None when the attribute is not set; raises RuntimeError for an unknown context.
+    #[pyo3(name = "get_attribute")]
+    fn py_get_attribute(&self, py: Python, context_id: &str, key: &str) -> PyResult<Option<PyObject>> {
+        self.get_attribute(context_id, key)?.map(|value| Ok(pythonize(py, &value)?)).transpose()
+    }
+
+    #[pyo3(name = "remove_attribute")]
+    fn py_remove_attribute(&self, py: Python, context_id: &str, key: &str) -> PyResult<Option<PyObject>> {
+        self.remove_attribute(context_id, key)?.map(|value| Ok(pythonize(py, &value)?)).transpose()
+    }
+
+    /// Raises ValueError for a negative or non-finite rate.
+    #[pyo3(name = "set_memory_decay")]
+    fn py_set_memory_decay(&self, context_id: &str, lambda: f64) -> PyResult<()> {
+        if !lambda.is_finite() || lambda < 0.0 {
+            return Err(PyValueError::new_err(format!("memory decay must be a non-negative number, got {}", lambda)));
+        }
+        self.set_memory_decay(context_id, lambda);
+        Ok(())
+    }
+
+    #[pyo3(name = "prune_memory", signature = (context_id, min_score=0.0, max_entries=None))]
+    fn py_prune_memory(&self, context_id: &str, min_score: f64, max_entries: Option<usize>) -> PyResult<usize> {
+        Ok(self.prune_memory(context_id, min_score, max_entries)?)
+    }
+
+    #[pyo3(name = "evict_expired")]
+    fn py_evict_expired(&self) -> Vec<String> {
+        self.evict_expired()
+    }
+
+    #[pyo3(name = "get_history", signature = (context_id, limit=None))]
+    fn py_get_history(&self, context_id: &str, limit: Option<usize>) -> Vec<ExecutionRecord> {
+        self.get_history(context_id, limit)
+    }
+
+    /// `format` is "jsonl" or "csv".
+    #[pyo3(name = "export_history", signature = (context_id, format="jsonl"))]
+    fn py_export_history(&self, context_id: &str, format: &str) -> PyResult<String> {
+        let format = HistoryFormat::parse(format)
+            .ok_or_else(|| PyValueError::new_err(format!("Unknown history format: {}", format)))?;
+        Ok(self.export_history(context_id, format))
+    }
+
+    /// `priority` is "low", "normal" or "high"; active goals are passed to the
+    /// planner, and high-priority ones also to planners taking a single string.
+    /// A goal still active at its `deadline` expires.
+    #[pyo3(name = "add_goal", signature = (context_id, goal_id, description, priority="normal", deadline=None))]
+    fn py_add_goal(
+        &self,
+        context_id: &str,
+        goal_id: String,
+        description: String,
+        priority: &str,
+        deadline: Option<DateTime<Utc>>,
+    ) -> PyResult<Goal> {
+        let priority =
+            GoalPriority::parse(priority).ok_or_else(|| PyValueError::new_err(format!("Unknown goal priority: {}", priority)))?;
+        let mut goal = Goal::new(goal_id, description).with_priority(priority);
+        goal.deadline = deadline;
+        Ok(self.add_goal(context_id, goal)?)
+    }
+
+    #[pyo3(name = "sweep_goals")]
+    fn py_sweep_goals(&self, context_id: &str) -> PyResult<Vec<Goal>> {
+        Ok(self.sweep_goals(context_id)?)
+    }
+
+    #[pyo3(name = "complete_goal")]
+    fn py_complete_goal(&self, context_id: &str, goal_id: &str) -> PyResult<Goal> {
+        Ok(self.complete_goal(context_id, goal_id)?)
+    }
+
+    #[pyo3(name = "list_goals")]
+    fn py_list_goals(&self, context_id: &str) -> Vec<Goal> {
+        self.list_goals(context_id)
+    }
+
+    #[pyo3(name = "goals_complete")]
+    fn py_goals_complete(&self, context_id: &str) -> bool {
+        self.goals_complete(context_id)
+    }
+
+    #[pyo3(name = "get_snapshots")]
+    fn py_get_snapshots(&self, context_id: &str) -> Vec<ContextSnapshot> {
+        self.get_snapshots(context_id)
+    }
+
+    #[pyo3(name = "get_context")]
+    fn py_get_context(&self, context_id: &str) -> Option<Context> {
+        self.get_context(context_id)
+    }
+
+    /// `process` in `tenant`'s namespace.
+    #[pyo3(name = "process_for", signature = (tenant, command, context_id, timeout=None, budget=None))]
+    fn py_process_for(
+        &self,
+        py: Python,
+        tenant: &str,
+        command: String,
+        context_id: &str,
+        timeout: Option<f64>,
+        budget: Option<&PyAny>,
+    ) -> PyResult<String> {
+        validate_tenant(tenant)?;
+        self.drain.check()?;
+        let run = py_run(command, tenant::context_key(tenant, context_id), timeout, budget, None, None)?;
+        Ok(py.allow_threads(|| self.complete_run(run)))
+    }
+
+    #[pyo3(name = "context_ids", signature = (tenant=DEFAULT_TENANT))]
+    fn py_context_ids(&self, tenant: &str) -> Vec<String> {
+        self.context_ids_for(tenant)
+    }
+
+    /// `list_contexts` as dicts.
+    #[pyo3(name = "list_contexts", signature = (tenant=DEFAULT_TENANT))]
+    fn py_list_contexts(&self, py: Python, tenant: &str) -> PyResult<PyObject> {
+        Ok(pythonize(py, &self.list_contexts(tenant))?)
+    }
+
+    /// `list_contexts_including_trashed` as dicts; trashed ones have `deleted_at`.
+    #[pyo3(name = "list_contexts_including_trashed", signature = (tenant=DEFAULT_TENANT))]
+    fn py_list_contexts_including_trashed(&self, py: Python, tenant: &str) -> PyResult<PyObject> {
+        Ok(pythonize(py, &self.list_contexts_including_trashed(tenant))?)
+    }
+
+    #[pyo3(name = "delete_context")]
+    fn py_delete_context(&self, context_id: &str) -> PyResult<()> {
+        Ok(self.delete_context(context_id)?)
+    }
+
+    #[pyo3(name = "restore_context")]
+    fn py_restore_context(&self, context_id: &str) -> PyResult<Context> {
+        Ok(self.restore_context(context_id)?)
+    }
+
+    /// Drops what was deleted at least `older_than_secs` ago; returns the ids.
+    #[pyo3(name = "purge_trash")]
+    fn py_purge_trash(&self, older_than_secs: f64) -> PyResult<Vec<String>> {
+        Ok(self.purge_trash(seconds(older_than_secs)?)?)
+    }
+
+    /// `context_stats` as a dict, or `None`.
+    #[pyo3(name = "context_stats")]
+    fn py_context_stats(&self, py: Python, context_id: &str) -> PyResult<Option<PyObject>> {
+        Ok(self.context_stats(context_id).map(|stats| pythonize(py, &stats)).transpose()?)
+    }
+
+    /// `observe` as a dict, or `None`.
+    #[pyo3(name = "observe")]
+    fn py_observe(&self, py: Python, context_id: &str) -> PyResult<Option<PyObject>> {
+        Ok(self.observe(context_id).map(|view| pythonize(py, &view)).transpose()?)
+    }
+
+    #[pyo3(name = "get_context_for")]
+    fn py_get_context_for(&self, tenant: &str, context_id: &str) -> Option<Context> {
+        self.get_context_for(tenant, context_id)
+    }
+
+    #[pyo3(name = "get_history_for")]
+    fn py_get_history_for(&self, tenant: &str, context_id: &str) -> Vec<ExecutionRecord> {
+        self.get_history_for(tenant, context_id)
+    }
+
+    #[pyo3(name = "export_context_for")]
+    fn py_export_context_for(&self, tenant: &str, context_id: &str) -> PyResult<String> {
+        Ok(self.export_context_for(tenant, context_id)?)
+    }
+
+    #[pyo3(name = "import_context_for", signature = (tenant, json, overwrite=false))]
+    fn py_import_context_for(&self, tenant: &str, json: &str, overwrite: bool) -> PyResult<String> {
+        Ok(self.import_context_for(tenant, json, overwrite)?)
+    }
+
+    #[pyo3(name = "save_contexts_for")]
+    fn py_save_contexts_for(&self, tenant: &str, path: PathBuf) -> PyResult<()> {
+        Ok(self.save_contexts_for(tenant, &path)?)
+    }
+
+    /// `None` removes the tenant's cap.
+    #[pyo3(name = "set_tenant_max_contexts", signature = (tenant, max_contexts=None))]
+    fn py_set_tenant_max_contexts(&self, tenant: &str, max_contexts: Option<usize>) -> PyResult<()> {
+        Ok(self.set_tenant_max_contexts(tenant, max_contexts)?)
+    }
+
+    #[pyo3(name = "set_tenant_budget", signature = (tenant, max_llm_calls=None, max_tokens=None, max_cost_usd=None))]
+    fn py_set_tenant_budget(
+        &self,
+        tenant: &str,
+        max_llm_calls: Option<u64>,
+        max_tokens: Option<u64>,
+        max_cost_usd: Option<f64>,
+    ) -> PyResult<()> {
+        Ok(self.set_tenant_budget(tenant, Budget { max_llm_calls, max_tokens, max_cost_usd })?)
+    }
+
+    #[pyo3(name = "clear_tenant_budget")]
+    fn py_clear_tenant_budget(&self, tenant: &str) {
+        self.clear_tenant_budget(tenant);
+    }
+
+    #[pyo3(name = "get_tenant_budget")]
+    fn py_get_tenant_budget(&self, py: Python, tenant: &str) -> PyResult<Option<PyObject>> {
+        Ok(self.get_tenant_budget(tenant).map(|status| pythonize(py, &status)).transpose()?)
+    }
+}
+
+#[pymodule]
+fn sovereign_cli(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_class::<CognitiveOrchestrator>()?;
+    m.add_class::<AgentResult>()?;
+    m.add_class::<Context>()?;
+    m.add_class::<ViralMetrics>()?;
+    m.add_class::<LoadReport>()?;
+    m.add_class::<PropagationReport>()?;
+    m.add_class::<AmplificationResult>()?;
+    m.add_class::<ProcessStream>()?;
+    m.add_class::<ContextHandle>()?;
+    m.add_class::<ProcessReport>()?;
+    m.add_class::<Blackboard>()?;
+    m.add_class::<RunTiming>()?;
+    m.add_class::<DebugDecision>()?;
+    m.add_class::<PlanFinding>()?;
+    m.add_class::<IncompleteRun>()?;
+    m.add_class::<RunDiff>()?;
+    m.add_class::<StatusFlip>()?;
+    m.add_class::<MetricDelta>()?;
+    m.add_class::<LatencyRegression>()?;
+    m.add_class::<ExecutionRecord>()?;
+    m.add_class::<AgentAvailability>()?;
+    m.add_class::<ContextSnapshot>()?;
+    m.add_class::<Goal>()?;
+    m.add_class::<Lineage>()?;
+    m.add_class::<MergeReport>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AgentResult, CognitiveOrchestrator};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn typed_metadata_accessors_read_what_set_wrote() {
+        let mut result = AgentResult::ok("posted");
+        result.set(AgentResult::VIRALITY, 0.75);
+        result.set(AgentResult::TOKENS_USED, 42u64);
+        result.set("channel", "feed");
+        result.set("pinned", true);
+        result.set("shares", BTreeMap::from([("feed".to_string(), 3u32)]));
+        assert_eq!(result.get_f64(AgentResult::VIRALITY), Some(0.75));
+        assert_eq!(result.get_deserialized::<u64>(AgentResult::TOKENS_USED), Some(42));
+        assert_eq!(result.get_str("channel"), Some("feed"));
+        assert_eq!(result.get_bool("pinned"), Some(true));
+        assert_eq!(result.get_deserialized::<BTreeMap<String, u32>>("shares").unwrap()["feed"], 3);
+        assert!(result.status && !AgentResult::fail("posted").status);
+    }
+
+    #[test]
+    fn mismatched_or_missing_metadata_is_none() {
+        let mut result = AgentResult::ok("posted");
+        result.set("channel", "feed");
+        result.set(AgentResult::VIRALITY, 0.75);
+        assert_eq!(result.get_f64("channel"), None);
+        assert_eq!(result.get_bool("channel"), None);
+        assert_eq!(result.get_str(AgentResult::VIRALITY), None);
+        assert_eq!(result.get_deserialized::<u64>(AgentResult::VIRALITY), None);
+        assert_eq!(result.get_deserialized::<Vec<String>>("channel"), None);
+        assert_eq!(result.get_f64("missing"), None);
+    }
+
+    #[test]
+    fn a_bad_log_filter_is_its_own_error() {
+        let err = CognitiveOrchestrator::init_tracing("sovereign_cli=[").unwrap_err();
+        assert_eq!(err.kind(), "invalid_log_filter");
+        assert!(err.to_string().starts_with("invalid log filter \"sovereign_cli=[\""));
+    }
 }
@@ -0,0 +1,65 @@
+use opentelemetry::trace::TraceContextExt;
+use pyo3::sync::GILOnceCell;
+use pyo3::types::PyDict;
+use pyo3::{PyAny, PyErr, PyObject, PyResult, Python};
+use tracing::Subscriber;
+use tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Name of the tracer spans are exported under.
+pub const TRACER_NAME: &str = "sovereign_cli";
+
+/// A layer exporting spans through the global tracer provider, so they reach
+/// whichever exporter the embedding application installed; without one they
+/// are dropped. `init_tracing` adds it when the `otel` feature is on.
+pub fn layer<S>() -> OpenTelemetryLayer<S, opentelemetry::global::BoxedTracer>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    tracing_opentelemetry::layer().with_tracer(opentelemetry::global::tracer(TRACER_NAME))
+}
+
+/// The W3C `traceparent` of the current span, when it is being traced.
+pub fn current_traceparent() -> Option<String> {
+    let context = tracing::Span::current().context();
+    let span = context.span();
+    let span_context = span.span_context();
+    span_context.is_valid().then(|| {
+        format!("00-{}-{}-{:02x}", span_context.trace_id(), span_context.span_id(), span_context.trace_flags().to_u8())
+    })
+}
+
+/// `opentelemetry.propagate.extract`, `opentelemetry.context.attach` and
+/// `detach`, or `None` when Python's `opentelemetry` is not installed.
+type Propagation = Option<(PyObject, PyObject, PyObject)>;
+
+fn propagation(py: Python<'_>) -> &Propagation {
+    static PROPAGATION: GILOnceCell<Propagation> = GILOnceCell::new();
+    PROPAGATION.get_or_init(py, || {
+        let import = || -> PyResult<(PyObject, PyObject, PyObject)> {
+            let propagate = py.import("opentelemetry.propagate")?;
+            let context = py.import("opentelemetry.context")?;
+            Ok((propagate.getattr("extract")?.into(), context.getattr("attach")?.into(), context.getattr("detach")?.into()))
+        };
+        import().ok()
+    })
+}
+
+/// Runs `call` with the current trace attached as Python's `opentelemetry`
+/// context, so spans an agent opens join the orchestrator's trace. Without a
+/// current trace or Python `opentelemetry`, just runs `call`.
+pub(crate) fn with_python_context<'py>(py: Python<'py>, call: impl FnOnce() -> Result<&'py PyAny, PyErr>) -> Result<&'py PyAny, PyErr> {
+    let (Some(traceparent), Some((extract, attach, detach))) = (current_traceparent(), propagation(py)) else {
+        return call();
+    };
+    let carrier = PyDict::new(py);
+    let token = carrier
+        .set_item("traceparent", traceparent)
+        .and_then(|()| extract.call1(py, (carrier,)))
+        .and_then(|context| attach.call1(py, (context,)));
+    let result = call();
+    if let Ok(token) = token {
+        let _ = detach.call1(py, (token,));
+    }
+    result
+}
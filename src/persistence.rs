@@ -0,0 +1,476 @@
+use crate::{CompletedKey, Context, MemoryVectors, OrchestratorError};
+use chrono::{DateTime, Utc};
+use pyo3::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_bytes::ByteBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use tracing::warn;
+
+/// Bumped whenever the on-disk layout changes incompatibly.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Leads a MessagePack snapshot file, so `load` can tell it from the others.
+pub const MESSAGEPACK_MAGIC: &[u8; 8] = b"ACE\0MSGP";
+/// Leads a bincode snapshot file.
+pub const BINCODE_MAGIC: &[u8; 8] = b"ACE\0BINC";
+
+/// How `save_contexts` encodes a snapshot file. `load_contexts` reads any of
+/// them, telling the binary ones by their magic bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PersistenceFormat {
+    /// Pretty-printed and diffable, but large and slow with many memory vectors.
+    #[default]
+    Json,
+    MessagePack,
+    /// The smallest and fastest to load.
+    Bincode,
+}
+
+impl PersistenceFormat {
+    pub fn name(self) -> &'static str {
+        match self {
+            PersistenceFormat::Json => "json",
+            PersistenceFormat::MessagePack => "messagepack",
+            PersistenceFormat::Bincode => "bincode",
+        }
+    }
+
+    /// `"json"`, `"messagepack"` or `"bincode"`.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "json" => Some(PersistenceFormat::Json),
+            "messagepack" => Some(PersistenceFormat::MessagePack),
+            "bincode" => Some(PersistenceFormat::Bincode),
+            _ => None,
+        }
+    }
+
+    /// The format of a snapshot file, by its leading bytes; JSON unless they are
+    /// a binary format's magic.
+    pub fn detect(bytes: &[u8]) -> Self {
+        if bytes.starts_with(MESSAGEPACK_MAGIC) {
+            PersistenceFormat::MessagePack
+        } else if bytes.starts_with(BINCODE_MAGIC) {
+            PersistenceFormat::Bincode
+        } else {
+            PersistenceFormat::Json
+        }
+    }
+
+    fn magic(self) -> &'static [u8] {
+        match self {
+            PersistenceFormat::Json => b"",
+            PersistenceFormat::MessagePack => MESSAGEPACK_MAGIC,
+            PersistenceFormat::Bincode => BINCODE_MAGIC,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct SnapshotFileRef<'a, C, K> {
+    version: u32,
+    saved_at: DateTime<Utc>,
+    contexts: &'a BTreeMap<&'a String, &'a C>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    idempotency_keys: &'a [K],
+}
+
+/// Contexts are kept as raw JSON so one bad entry doesn't fail the whole file.
+#[derive(Deserialize)]
+struct SnapshotFile {
+    version: u32,
+    contexts: HashMap<String, serde_json::Value>,
+    /// Completed `process` idempotency keys, sorted by key.
+    #[serde(default)]
+    idempotency_keys: Vec<serde_json::Value>,
+}
+
+/// The binary formats' layout of a snapshot file, after the magic. Each entry
+/// is encoded on its own, so like JSON's one bad entry doesn't fail the file;
+/// idempotency keys are paired with their key so merging need not decode them.
+#[derive(Serialize, Deserialize)]
+struct BinarySnapshotFile {
+    version: u32,
+    saved_at: DateTime<Utc>,
+    contexts: Vec<(String, ByteBuf)>,
+    idempotency_keys: Vec<(String, ByteBuf)>,
+}
+
+/// bincode keeps no field names or types, which flattened fields and JSON
+/// values need, so a bincode context carries its memory vectors as raw `f32`
+/// (the bulk of a context) and the rest of its fields as JSON.
+#[derive(Serialize, Deserialize)]
+struct BincodeContext {
+    fields: String,
+    dim: usize,
+    vectors: Vec<f32>,
+}
+
+/// A snapshot entry as its file stores it, decoded only when restored.
+enum Entry {
+    Json(serde_json::Value),
+    Binary(ByteBuf),
+}
+
+/// A snapshot file read but not yet decoded.
+struct RawSnapshot {
+    format: PersistenceFormat,
+    version: u32,
+    contexts: HashMap<String, Entry>,
+    /// `(key, entry)`, sorted by key.
+    keys: Vec<(String, Entry)>,
+}
+
+fn binary_error(err: impl std::fmt::Display) -> OrchestratorError {
+    OrchestratorError::Serialization { message: err.to_string() }
+}
+
+/// Encodes an idempotency key; bincode ones are JSON, for the reason contexts
+/// are partly JSON.
+fn encode_key(format: PersistenceFormat, key: &CompletedKey) -> Result<Entry, OrchestratorError> {
+    Ok(match format {
+        PersistenceFormat::Json => Entry::Json(serde_json::to_value(key).map_err(OrchestratorError::serialization)?),
+        PersistenceFormat::MessagePack => Entry::Binary(ByteBuf::from(rmp_serde::to_vec_named(key).map_err(binary_error)?)),
+        PersistenceFormat::Bincode => Entry::Binary(ByteBuf::from(serde_json::to_vec(key).map_err(OrchestratorError::serialization)?)),
+    })
+}
+
+fn decode_key(format: PersistenceFormat, entry: Entry) -> Option<CompletedKey> {
+    decode(format, entry, |bytes| serde_json::from_slice(bytes).map_err(|e| e.to_string())).ok()
+}
+
+fn encode_context(format: PersistenceFormat, context: &Context) -> Result<Entry, OrchestratorError> {
+    Ok(match format {
+        PersistenceFormat::Json => Entry::Json(serde_json::to_value(context).map_err(OrchestratorError::serialization)?),
+        PersistenceFormat::MessagePack => {
+            Entry::Binary(ByteBuf::from(rmp_serde::to_vec_named(context).map_err(binary_error)?))
+        }
+        PersistenceFormat::Bincode => {
+            let mut fields = context.clone();
+            let vectors = std::mem::take(&mut fields.memory_vectors);
+            let entry = BincodeContext {
+                fields: serde_json::to_string(&fields).map_err(OrchestratorError::serialization)?,
+                dim: vectors.dim(),
+                vectors: vectors.to_flat(),
+            };
+            Entry::Binary(ByteBuf::from(bincode::serde::encode_to_vec(&entry, bincode::config::standard()).map_err(binary_error)?))
+        }
+    })
+}
+
+fn decode_context(format: PersistenceFormat, entry: Entry) -> Result<Context, String> {
+    decode(format, entry, |bytes| {
+        let (entry, _): (BincodeContext, _) =
+            bincode::serde::decode_from_slice(bytes, bincode::config::standard()).map_err(|e| e.to_string())?;
+        let mut context: Context = serde_json::from_str(&entry.fields).map_err(|e| e.to_string())?;
+        context.memory_vectors = MemoryVectors::from_flat(entry.dim, entry.vectors).map_err(|e| e.to_string())?;
+        Ok(context)
+    })
+}
+
+/// Decodes a JSON or MessagePack entry, or a bincode one with `decode_bincode`.
+fn decode<T: DeserializeOwned>(
+    format: PersistenceFormat,
+    entry: Entry,
+    decode_bincode: impl FnOnce(&[u8]) -> Result<T, String>,
+) -> Result<T, String> {
+    match (format, entry) {
+        (_, Entry::Json(value)) => serde_json::from_value(value).map_err(|e| e.to_string()),
+        (PersistenceFormat::Bincode, Entry::Binary(bytes)) => decode_bincode(&bytes),
+        (_, Entry::Binary(bytes)) => rmp_serde::from_slice(&bytes).map_err(|e| e.to_string()),
+    }
+}
+
+fn read_snapshot(bytes: &[u8]) -> Result<RawSnapshot, OrchestratorError> {
+    let format = PersistenceFormat::detect(bytes);
+    let body = &bytes[format.magic().len()..];
+    let file = match format {
+        PersistenceFormat::Json => {
+            let file: SnapshotFile = serde_json::from_slice(body).map_err(OrchestratorError::serialization)?;
+            let keys = file
+                .idempotency_keys
+                .into_iter()
+                .map(|value| (value["key"].as_str().unwrap_or_default().to_string(), Entry::Json(value)))
+                .collect();
+            let contexts = file.contexts.into_iter().map(|(id, value)| (id, Entry::Json(value))).collect();
+            return Ok(RawSnapshot { format, version: file.version, contexts, keys });
+        }
+        PersistenceFormat::MessagePack => rmp_serde::from_slice::<BinarySnapshotFile>(body).map_err(binary_error)?,
+        PersistenceFormat::Bincode => {
+            bincode::serde::decode_from_slice(body, bincode::config::standard()).map_err(binary_error)?.0
+        }
+    };
+    Ok(RawSnapshot {
+        format,
+        version: file.version,
+        contexts: file.contexts.into_iter().map(|(id, bytes)| (id, Entry::Binary(bytes))).collect(),
+        keys: file.idempotency_keys.into_iter().map(|(key, bytes)| (key, Entry::Binary(bytes))).collect(),
+    })
+}
+
+#[pyclass(module = "sovereign_cli", get_all)]
+#[derive(Debug, Clone, Default)]
+pub struct LoadReport {
+    pub version: u32,
+    pub loaded: Vec<String>,
+    /// `(context_id, reason)` for each entry that could not be restored.
+    pub failed: Vec<(String, String)>,
+}
+
+/// `saved_at` is recorded in the file header. Entries are keyed by `Context::key`.
+pub fn save<'a>(
+    path: &Path,
+    format: PersistenceFormat,
+    contexts: impl IntoIterator<Item = &'a Context>,
+    keys: &[CompletedKey],
+    saved_at: DateTime<Utc>,
+) -> Result<(), OrchestratorError> {
+    let contexts: HashMap<String, &Context> = contexts.into_iter().map(|context| (context.key(), context)).collect();
+    if format == PersistenceFormat::Json {
+        return write_atomically(path, json_snapshot(&contexts, keys, saved_at)?);
+    }
+    let contexts = contexts
+        .into_iter()
+        .map(|(id, context)| Ok((id, encode_context(format, context)?)))
+        .collect::<Result<_, OrchestratorError>>()?;
+    let keys = keys.iter().map(|key| Ok((key.key.clone(), encode_key(format, key)?))).collect::<Result<_, OrchestratorError>>()?;
+    write_snapshot(path, format, contexts, keys, saved_at)
+}
+
+/// Merges `contexts` and `keys` into the snapshot at `path`, creating it if needed.
+/// Entries already in the file are kept verbatim unless one of `contexts` or
+/// `keys` replaces them, or the file is in another format than `format`; then
+/// they are re-encoded, and those that cannot be decoded are dropped.
+pub fn flush<'a>(
+    path: &Path,
+    format: PersistenceFormat,
+    contexts: impl IntoIterator<Item = &'a Context>,
+    keys: &[CompletedKey],
+    saved_at: DateTime<Utc>,
+) -> Result<(), OrchestratorError> {
+    let (mut merged, mut merged_keys) = match fs::read(path) {
+        Ok(bytes) => {
+            let file = read_snapshot(&bytes)?;
+            if file.format == format {
+                (file.contexts, file.keys)
+            } else {
+                transcode(file, format)?
+            }
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => (HashMap::new(), vec![]),
+        Err(err) => return Err(OrchestratorError::io(path, err)),
+    };
+    for context in contexts {
+        merged.insert(context.key(), encode_context(format, context)?);
+    }
+    merged_keys.retain(|(existing, _)| !keys.iter().any(|key| *existing == key.key));
+    for key in keys {
+        merged_keys.push((key.key.clone(), encode_key(format, key)?));
+    }
+    merged_keys.sort_by(|a, b| a.0.cmp(&b.0));
+    write_snapshot(path, format, merged, merged_keys, saved_at)
+}
+
+type Entries = (HashMap<String, Entry>, Vec<(String, Entry)>);
+
+/// Re-encodes `file`'s entries in `format`.
+fn transcode(file: RawSnapshot, format: PersistenceFormat) -> Result<Entries, OrchestratorError> {
+    let mut contexts = HashMap::new();
+    for (id, entry) in file.contexts {
+        match decode_context(file.format, entry) {
+            Ok(context) => {
+                contexts.insert(id, encode_context(format, &context)?);
+            }
+            Err(err) => warn!("Dropping undecodable context {} from the {} snapshot: {}", id, file.format.name(), err),
+        }
+    }
+    let mut keys = vec![];
+    for (key, entry) in file.keys {
+        if let Some(completed) = decode_key(file.format, entry) {
+            keys.push((key, encode_key(format, &completed)?));
+        }
+    }
+    Ok((contexts, keys))
+}
+
+fn json_snapshot<C: Serialize, K: Serialize>(
+    contexts: &HashMap<String, C>,
+    keys: &[K],
+    saved_at: DateTime<Utc>,
+) -> Result<Vec<u8>, OrchestratorError> {
+    // Sorted so the same contexts always produce the same bytes.
+    let contexts: BTreeMap<&String, &C> = contexts.iter().collect();
+    let file = SnapshotFileRef {
+        version: FORMAT_VERSION,
+        saved_at,
+        contexts: &contexts,
+        idempotency_keys: keys,
+    };
+    serde_json::to_vec_pretty(&file).map_err(OrchestratorError::serialization)
+}
+
+fn write_snapshot(
+    path: &Path,
+    format: PersistenceFormat,
+    contexts: HashMap<String, Entry>,
+    keys: Vec<(String, Entry)>,
+    saved_at: DateTime<Utc>,
+) -> Result<(), OrchestratorError> {
+    let binary = |entry| match entry {
+        Entry::Binary(bytes) => bytes,
+        Entry::Json(_) => unreachable!("binary snapshots hold binary entries"),
+    };
+    let bytes = match format {
+        PersistenceFormat::Json => {
+            let json = |entry| match entry {
+                Entry::Json(value) => value,
+                Entry::Binary(_) => unreachable!("JSON snapshots hold JSON entries"),
+            };
+            let contexts: HashMap<String, serde_json::Value> = contexts.into_iter().map(|(id, entry)| (id, json(entry))).collect();
+            let keys: Vec<serde_json::Value> = keys.into_iter().map(|(_, entry)| json(entry)).collect();
+            json_snapshot(&contexts, &keys, saved_at)?
+        }
+        PersistenceFormat::MessagePack | PersistenceFormat::Bincode => {
+            let mut contexts: Vec<(String, ByteBuf)> = contexts.into_iter().map(|(id, entry)| (id, binary(entry))).collect();
+            contexts.sort_by(|a, b| a.0.cmp(&b.0));
+            let file = BinarySnapshotFile {
+                version: FORMAT_VERSION,
+                saved_at,
+                contexts,
+                idempotency_keys: keys.into_iter().map(|(key, entry)| (key, binary(entry))).collect(),
+            };
+            let mut bytes = format.magic().to_vec();
+            if format == PersistenceFormat::MessagePack {
+                rmp_serde::encode::write_named(&mut bytes, &file).map_err(binary_error)?;
+            } else {
+                bincode::serde::encode_into_std_write(&file, &mut bytes, bincode::config::standard()).map_err(binary_error)?;
+            }
+            bytes
+        }
+    };
+    write_atomically(path, bytes)
+}
+
+fn write_atomically(path: &Path, bytes: Vec<u8>) -> Result<(), OrchestratorError> {
+    // Write to a sibling temp file first so a crash mid-write keeps the old checkpoint.
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, bytes).map_err(|e| OrchestratorError::io(&tmp, e))?;
+    fs::rename(&tmp, path).map_err(|e| OrchestratorError::io(path, e))
+}
+
+/// What `load` restored from a snapshot file.
+pub struct Checkpoint {
+    /// Keyed by `Context::key`.
+    pub contexts: HashMap<String, Context>,
+    /// Completed idempotency keys; those that cannot be decoded are skipped.
+    pub keys: Vec<CompletedKey>,
+    pub report: LoadReport,
+}
+
+/// Reads a snapshot in any `PersistenceFormat`.
+pub fn load(path: &Path) -> Result<Checkpoint, OrchestratorError> {
+    let bytes = fs::read(path).map_err(|e| OrchestratorError::io(path, e))?;
+    let file = read_snapshot(&bytes)?;
+
+    let mut report = LoadReport {
+        version: file.version,
+        ..LoadReport::default()
+    };
+    let mut contexts = HashMap::new();
+
+    for (context_id, raw) in file.contexts {
+        match decode_context(file.format, raw) {
+            Ok(context) => {
+                report.loaded.push(context_id);
+                contexts.insert(context.key(), context);
+            }
+            Err(err) => report.failed.push((context_id, err)),
+        }
+    }
+
+    report.loaded.sort();
+    report.failed.sort();
+    let keys = file.keys.into_iter().filter_map(|(_, raw)| decode_key(file.format, raw)).collect();
+    Ok(Checkpoint { contexts, keys, report })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{flush, load, save, PersistenceFormat};
+    use crate::{CognitiveOrchestrator, Context, Goal, MetricsSample, ViralMetrics};
+    use chrono::Utc;
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    const FORMATS: [PersistenceFormat; 3] = [PersistenceFormat::Json, PersistenceFormat::MessagePack, PersistenceFormat::Bincode];
+
+    fn context(context_id: &str) -> Context {
+        let orch = CognitiveOrchestrator::builder().learning(false).build().unwrap();
+        orch.add_goal(context_id, Goal::new("lancement", "Lancer la campagne — 春のローンチ 🚀")).unwrap();
+        let payload = json!({"type": "error", "tags": ["naïve", {"depth": [1, -2, 2.5, null, true]}], "note": "ünïcödé"});
+        orch.remember_payload(context_id, "teaser a échoué", payload.as_object().unwrap().clone()).unwrap();
+        orch.remember(context_id, "budget dépassé").unwrap();
+        let mut context = orch.get_context(context_id).unwrap();
+        context.metrics_history.record(MetricsSample { at: context.created_at, metrics: ViralMetrics::default() }, 8);
+        context.extra.insert("from_newer_version".to_string(), json!({"ключ": [1, {"x": "y"}]}));
+        context
+    }
+
+    fn path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("sovereign-persistence-{}-{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn contexts_round_trip_in_every_format() {
+        let contexts = [context("ctx1"), context("ctx2")];
+        for format in FORMATS {
+            let path = path(format.name());
+            save(&path, format, &contexts, &[], Utc::now()).unwrap();
+            assert_eq!(PersistenceFormat::detect(&std::fs::read(&path).unwrap()), format);
+
+            let checkpoint = load(&path).unwrap();
+            assert_eq!((checkpoint.report.loaded, checkpoint.report.failed), (vec!["ctx1".to_string(), "ctx2".to_string()], vec![]));
+            assert_eq!(checkpoint.contexts["ctx1"], contexts[0], "{}", format.name());
+            assert_eq!(checkpoint.contexts["ctx2"], contexts[1], "{}", format.name());
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn flushes_re_encode_files_of_another_format() {
+        let path = path("flush");
+        let saved = context("ctx1");
+        save(&path, PersistenceFormat::Bincode, [&saved], &[], Utc::now()).unwrap();
+        flush(&path, PersistenceFormat::MessagePack, &[context("ctx2")], &[], Utc::now()).unwrap();
+        assert_eq!(PersistenceFormat::detect(&std::fs::read(&path).unwrap()), PersistenceFormat::MessagePack);
+
+        let checkpoint = load(&path).unwrap();
+        assert_eq!(checkpoint.contexts["ctx1"], saved);
+        assert_eq!(checkpoint.report.loaded, ["ctx1", "ctx2"]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn persisted_contexts_are_merged_and_kept() {
+        let path = path("persist");
+        let orch = CognitiveOrchestrator::builder().learning(false).eviction_path(&path).build().unwrap();
+        assert_eq!(orch.persist_context("ctx1").unwrap(), None);
+        orch.remember("ctx1", "teaser a échoué").unwrap();
+        orch.remember("ctx2", "budget dépassé").unwrap();
+        assert_eq!(orch.persist_context("ctx1").unwrap(), Some(path.clone()));
+        orch.persist_context("ctx2").unwrap();
+
+        let checkpoint = load(&path).unwrap();
+        assert_eq!(checkpoint.report.loaded, ["ctx1", "ctx2"]);
+        assert_eq!(checkpoint.contexts["ctx1"], orch.get_context("ctx1").unwrap());
+        // Without an eviction flush there is nowhere to persist to.
+        let unflushed = CognitiveOrchestrator::builder().learning(false).build().unwrap();
+        unflushed.remember("ctx1", "teaser a échoué").unwrap();
+        assert_eq!(unflushed.persist_context("ctx1").unwrap(), None);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,315 @@
+use crate::{InputPolicy, NodeId, Plan, PlanNode};
+use pyo3::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+pub const DEFAULT_MAX_PLAN_SUBTASKS: usize = 64;
+pub const DEFAULT_MAX_SUBTASK_CHARS: usize = 4000;
+
+/// What `process` accepts from the planner. Every first plan is cleaned up
+/// before the dispatch policy sees it: subtasks the input policy refuses are
+/// dropped and the rest cleaned by it, then blank subtasks, a subtask repeating
+/// the one before it and subtasks over `max_subtask_chars` are dropped, and the plan
+/// is cut to `max_subtasks` in execution order. Whatever depended on a dropped
+/// node depends on its dependencies instead. A plan left empty falls back to the
+/// command as a single `llm:generate` subtask.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PlanValidator {
+    pub max_subtasks: usize,
+    /// In characters.
+    pub max_subtask_chars: usize,
+    /// Drop subtasks that are malformed structured ones or that no agent handles;
+    /// otherwise they are only reported, and fail when dispatched.
+    pub require_route: bool,
+}
+
+impl Default for PlanValidator {
+    fn default() -> Self {
+        Self { max_subtasks: DEFAULT_MAX_PLAN_SUBTASKS, max_subtask_chars: DEFAULT_MAX_SUBTASK_CHARS, require_route: false }
+    }
+}
+
+/// What the plan validator found wrong with a subtask, or the plan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingKind {
+    /// The input policy refuses the subtask, such as for a NUL in it.
+    InvalidInput,
+    Blank,
+    /// The subtask repeats the one before it in execution order.
+    Duplicate,
+    SubtaskTooLong,
+    /// Malformed, or no agent handles it.
+    Unroutable,
+    PlanTooLong,
+    /// Nothing was left of the plan, so the command runs as an LLM subtask.
+    Fallback,
+}
+
+impl FindingKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FindingKind::InvalidInput => "invalid_input",
+            FindingKind::Blank => "blank",
+            FindingKind::Duplicate => "duplicate",
+            FindingKind::SubtaskTooLong => "subtask_too_long",
+            FindingKind::Unroutable => "unroutable",
+            FindingKind::PlanTooLong => "plan_too_long",
+            FindingKind::Fallback => "fallback",
+        }
+    }
+}
+
+impl fmt::Display for FindingKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// One thing the plan validator changed or flagged, as `ProcessReport::plan_findings`
+/// lists them.
+#[pyclass(module = "sovereign_cli")]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct PlanFinding {
+    pub kind: FindingKind,
+    /// The planned node concerned; `None` for findings about the whole plan.
+    #[pyo3(get)]
+    pub node: Option<NodeId>,
+    #[pyo3(get)]
+    pub subtask: String,
+    #[pyo3(get)]
+    pub message: String,
+    /// Whether the node, or for a whole-plan finding any node, left the plan.
+    #[pyo3(get)]
+    pub dropped: bool,
+}
+
+impl PlanFinding {
+    fn node(kind: FindingKind, node: &PlanNode, message: String, dropped: bool) -> Self {
+        Self { kind, node: Some(node.id), subtask: node.subtask.clone(), message, dropped }
+    }
+}
+
+impl fmt::Display for PlanFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.node {
+            Some(id) => write!(f, "{} at node {}: {}", self.kind, id, self.message),
+            None => write!(f, "{}: {}", self.kind, self.message),
+        }
+    }
+}
+
+#[pymethods]
+impl PlanFinding {
+    #[getter(kind)]
+    fn py_kind(&self) -> &'static str {
+        self.kind.name()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "PlanFinding(kind={:?}, node={:?}, dropped={}, message={:?})",
+            self.kind.name(),
+            self.node,
+            self.dropped,
+            self.message
+        )
+    }
+}
+
+impl PlanValidator {
+    /// The first setting that can never pass a plan, with why.
+    pub(crate) fn invalid(&self) -> Option<(&'static str, &'static str)> {
+        if self.max_subtasks == 0 {
+            return Some(("max_subtasks", "must be greater than zero"));
+        }
+        if self.max_subtask_chars == 0 {
+            return Some(("max_subtask_chars", "must be greater than zero"));
+        }
+        None
+    }
+
+    /// `plan` for `command` cleaned up, with what was found in execution order.
+    /// `unroutable` says why a subtask cannot be dispatched, if it cannot.
+    pub(crate) fn validate(
+        &self,
+        command: &str,
+        plan: Plan,
+        input: &InputPolicy,
+        unroutable: impl Fn(&str) -> Option<String>,
+    ) -> (Plan, Vec<PlanFinding>) {
+        let mut findings = vec![];
+        // Dropped nodes' dependencies, already resolved to kept nodes.
+        let mut dropped: HashMap<NodeId, Vec<NodeId>> = HashMap::new();
+        let mut kept: Vec<PlanNode> = vec![];
+        let mut previous: Option<String> = None;
+        for node in plan.waves().into_iter().flatten() {
+            let cleaned = input.clean(&node.subtask);
+            let subtask = cleaned.as_deref().unwrap_or(&node.subtask);
+            let text = subtask.trim();
+            let chars = subtask.chars().count();
+            let finding = if let Err(err) = &cleaned {
+                Some(PlanFinding::node(FindingKind::InvalidInput, node, err.to_string(), true))
+            } else if text.is_empty() {
+                Some(PlanFinding::node(FindingKind::Blank, node, "blank subtask".to_string(), true))
+            } else if previous.as_deref() == Some(text) {
+                Some(PlanFinding::node(FindingKind::Duplicate, node, "repeats the subtask before it".to_string(), true))
+            } else if chars > self.max_subtask_chars {
+                let message = format!("{} characters, over max_subtask_chars {}", chars, self.max_subtask_chars);
+                Some(PlanFinding::node(FindingKind::SubtaskTooLong, node, message, true))
+            } else {
+                unroutable(subtask)
+                    .map(|reason| PlanFinding::node(FindingKind::Unroutable, node, reason, self.require_route))
+            };
+            if !text.is_empty() {
+                previous = Some(text.to_string());
+            }
+            let mut depends_on = vec![];
+            for dep in &node.depends_on {
+                for dep in dropped.get(dep).map_or(std::slice::from_ref(dep), Vec::as_slice) {
+                    if !depends_on.contains(dep) {
+                        depends_on.push(*dep);
+                    }
+                }
+            }
+            let drop = finding.as_ref().is_some_and(|finding| finding.dropped);
+            findings.extend(finding);
+            if drop {
+                dropped.insert(node.id, depends_on);
+            } else {
+                kept.push(PlanNode { id: node.id, subtask: subtask.to_string(), depends_on });
+            }
+        }
+
+        if kept.len() > self.max_subtasks {
+            findings.push(PlanFinding {
+                kind: FindingKind::PlanTooLong,
+                node: None,
+                subtask: String::new(),
+                message: format!("{} subtasks cut to max_subtasks {}", kept.len(), self.max_subtasks),
+                dropped: true,
+            });
+            // Execution order puts every node's dependencies before it.
+            kept.truncate(self.max_subtasks);
+        }
+        if kept.is_empty() {
+            let subtask = format!("llm:generate {}", command.trim());
+            findings.push(PlanFinding {
+                kind: FindingKind::Fallback,
+                node: None,
+                subtask: subtask.clone(),
+                message: "nothing left to dispatch; running the command as an LLM subtask".to_string(),
+                dropped: false,
+            });
+            return (Plan::from(vec![subtask]), findings);
+        }
+        // Only dependencies on kept nodes remain, and those come first.
+        (Plan::new(kept).expect("a validated plan keeps its topological order"), findings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FindingKind, PlanValidator};
+    use crate::{AgentResult, CognitiveOrchestrator, InputPolicy, MockBackend, Plan, PlanNode};
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    /// An orchestrator with `planner` planning every command, recording the
+    /// "post" subtasks dispatched.
+    fn orchestrator(
+        planner: impl Fn(&str) -> Plan + Send + Sync + 'static,
+        validator: PlanValidator,
+    ) -> (CognitiveOrchestrator, Arc<Mutex<Vec<String>>>) {
+        let dispatched = Arc::new(Mutex::new(vec![]));
+        let seen = dispatched.clone();
+        let mock = MockBackend::new().planner(move |command| Ok(planner(command))).on("post", move |rest| {
+            seen.lock().unwrap().push(rest.to_string());
+            AgentResult { output: rest.to_string(), status: true, metadata: HashMap::new(), error: None }
+        });
+        let orch = CognitiveOrchestrator::builder()
+            .backend(Arc::new(mock))
+            .plan_validator(validator)
+            .learning(false)
+            .build()
+            .unwrap();
+        (orch, dispatched)
+    }
+
+    fn kinds(findings: &[super::PlanFinding]) -> Vec<FindingKind> {
+        findings.iter().map(|finding| finding.kind).collect()
+    }
+
+    #[test]
+    fn blanks_repeats_and_overlong_subtasks_are_dropped_and_reported() {
+        let long = format!("post {}", "x".repeat(40));
+        let steps = ["post a", "   ", "post a", "post a", "", "post b", long.as_str(), "post a"];
+        let steps: Vec<String> = steps.iter().map(|step| step.to_string()).collect();
+        let validator = PlanValidator { max_subtask_chars: 20, ..PlanValidator::default() };
+        let (orch, dispatched) = orchestrator(move |_| Plan::from(steps.clone()), validator);
+        let report = orch.process_report("launch".to_string(), "ctx1");
+        assert_eq!(*dispatched.lock().unwrap(), ["a", "b", "a"]);
+        assert_eq!(report.plan, ["post a", "post b", "post a"]);
+        assert!(report.success);
+        use FindingKind::*;
+        assert_eq!(kinds(&report.plan_findings), [Blank, Duplicate, Duplicate, Blank, SubtaskTooLong]);
+        let nodes: Vec<_> = report.plan_findings.iter().map(|finding| finding.node).collect();
+        assert_eq!(nodes, [Some(1), Some(2), Some(3), Some(4), Some(6)]);
+        assert!(report.plan_findings.iter().all(|finding| finding.dropped));
+    }
+
+    #[test]
+    fn dependents_of_dropped_nodes_inherit_their_dependencies() {
+        let nodes = vec![
+            PlanNode { id: 0, subtask: "post a".to_string(), depends_on: vec![] },
+            PlanNode { id: 1, subtask: " ".to_string(), depends_on: vec![0] },
+            PlanNode { id: 2, subtask: "post b".to_string(), depends_on: vec![1] },
+            PlanNode { id: 3, subtask: "post c".to_string(), depends_on: vec![] },
+        ];
+        let plan = Plan::new(nodes).unwrap();
+        let (plan, findings) = PlanValidator::default().validate("launch", plan, &InputPolicy::default(), |_| None);
+        assert_eq!(kinds(&findings), [FindingKind::Blank]);
+        let deps: Vec<_> = plan.nodes().iter().map(|node| (node.id, node.depends_on.clone())).collect();
+        assert_eq!(deps, [(0, vec![]), (3, vec![]), (2, vec![0])]);
+    }
+
+    #[test]
+    fn runaway_plans_are_cut_and_empty_ones_fall_back_to_the_llm() {
+        let validator = PlanValidator { max_subtasks: 3, ..PlanValidator::default() };
+        let (orch, dispatched) = orchestrator(|_| Plan::from((0..5000).map(|i| format!("post {}", i)).collect::<Vec<_>>()), validator);
+        let report = orch.process_report("launch".to_string(), "ctx1");
+        assert_eq!(*dispatched.lock().unwrap(), ["0", "1", "2"]);
+        assert_eq!(kinds(&report.plan_findings), [FindingKind::PlanTooLong]);
+        assert_eq!(report.plan_findings[0].message, "5000 subtasks cut to max_subtasks 3");
+
+        for steps in [vec![], vec!["".to_string(), "\n\t".to_string()]] {
+            let (orch, dispatched) = orchestrator(move |_| Plan::from(steps.clone()), PlanValidator::default());
+            let report = orch.process_report("write a haiku".to_string(), "ctx1");
+            assert!(dispatched.lock().unwrap().is_empty());
+            assert_eq!(report.plan, ["llm:generate write a haiku"]);
+            assert_eq!(report.plan_findings.last().map(|finding| finding.kind), Some(FindingKind::Fallback));
+            assert!(report.success, "{:?}", report.results);
+        }
+    }
+
+    #[test]
+    fn unroutable_subtasks_are_reported_and_dropped_only_when_routes_are_required() {
+        let steps = || Plan::from(vec!["post a".to_string(), "juggle flaming torches".to_string(), "llm:".to_string()]);
+        let (orch, dispatched) = orchestrator(move |_| steps(), PlanValidator::default());
+        let report = orch.process_report("launch".to_string(), "ctx1");
+        assert_eq!(*dispatched.lock().unwrap(), ["a"]);
+        assert_eq!(report.results.len(), 3);
+        assert_eq!(kinds(&report.plan_findings), [FindingKind::Unroutable, FindingKind::Unroutable]);
+        assert!(report.plan_findings.iter().all(|finding| !finding.dropped));
+
+        let validator = PlanValidator { require_route: true, ..PlanValidator::default() };
+        let (orch, _) = orchestrator(move |_| steps(), validator);
+        let report = orch.process_report("launch".to_string(), "ctx1");
+        assert_eq!(report.plan, ["post a"]);
+        assert!(report.success);
+        assert!(report.plan_findings[0].message.starts_with("unknown subtask"), "{}", report.plan_findings[0].message);
+    }
+}
@@ -0,0 +1,237 @@
+use crate::{Context, ExecutionRecord, GoalPriority, ViralMetrics};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Execution records the planner is shown unless configured otherwise.
+pub const DEFAULT_PLANNER_HISTORY: usize = 5;
+/// Memories the planner is shown unless configured otherwise.
+pub const DEFAULT_PLANNER_MEMORIES: usize = 3;
+/// Characters the serialized `PlannerInput` is kept within unless configured otherwise.
+pub const DEFAULT_PLANNER_MAX_CHARS: usize = 4000;
+
+/// How much of a context `PlannerInput` carries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PlannerInputConfig {
+    /// Most recent execution records included.
+    pub history: usize,
+    /// Memories most similar to the command included.
+    pub memories: usize,
+    /// Bound on the input serialized as JSON, in characters. The command is
+    /// always kept; past the bound memories go first, then history records, then
+    /// attributes, then the metrics, then goals from the lowest priority up.
+    pub max_chars: usize,
+}
+
+impl Default for PlannerInputConfig {
+    fn default() -> Self {
+        Self { history: DEFAULT_PLANNER_HISTORY, memories: DEFAULT_PLANNER_MEMORIES, max_chars: DEFAULT_PLANNER_MAX_CHARS }
+    }
+}
+
+/// An active goal as the planner sees it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlannerGoal {
+    pub id: String,
+    pub description: String,
+    pub priority: GoalPriority,
+}
+
+/// A past subtask as the planner sees it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlannerRecord {
+    pub command: String,
+    pub subtask: String,
+    pub status: bool,
+    pub output: String,
+}
+
+impl From<&ExecutionRecord> for PlannerRecord {
+    fn from(record: &ExecutionRecord) -> Self {
+        Self {
+            command: record.command.clone(),
+            subtask: record.subtask.clone(),
+            status: record.result.status,
+            output: record.result.output.clone(),
+        }
+    }
+}
+
+/// What `PlannerAgent.decompose` is passed, as a dict: the command with what its
+/// context holds. Planners whose `decompose` raises `TypeError` on it are passed
+/// `prompt` instead, the single string they always were.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlannerInput {
+    pub command: String,
+    /// Active goals, highest priority first.
+    pub goals: Vec<PlannerGoal>,
+    /// `None` for a context not yet created.
+    pub metrics: Option<ViralMetrics>,
+    /// Oldest first.
+    pub history: Vec<PlannerRecord>,
+    /// Most similar to the command first.
+    pub memories: Vec<String>,
+    /// The context's attributes, by key.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, Value>,
+    #[serde(skip)]
+    prompt: String,
+}
+
+impl PlannerInput {
+    /// `command` on `context`, with `history` oldest first and `memories` most
+    /// similar first, trimmed to `config.max_chars`.
+    pub(crate) fn new(
+        command: &str,
+        context: Option<&Context>,
+        history: &[ExecutionRecord],
+        memories: Vec<String>,
+        prompt: String,
+        config: &PlannerInputConfig,
+    ) -> Self {
+        let mut goals: Vec<PlannerGoal> = context
+            .map(|context| {
+                context
+                    .active_goals
+                    .iter()
+                    .filter(|goal| goal.is_active())
+                    .map(|goal| PlannerGoal {
+                        id: goal.id.clone(),
+                        description: goal.description.clone(),
+                        priority: goal.priority,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        goals.sort_by_key(|goal| std::cmp::Reverse(goal.priority));
+        let skip = history.len().saturating_sub(config.history);
+        let mut input = Self {
+            command: command.to_string(),
+            goals,
+            metrics: context.map(|context| context.viral_metrics.clone()),
+            history: history.iter().skip(skip).map(PlannerRecord::from).collect(),
+            memories: memories.into_iter().take(config.memories).collect(),
+            attributes: context.map(|context| context.attributes.clone().into_iter().collect()).unwrap_or_default(),
+            prompt,
+        };
+        input.truncate(config.max_chars);
+        input
+    }
+
+    /// The single-string form, for planners that take only that.
+    pub fn prompt(&self) -> &str {
+        &self.prompt
+    }
+
+    /// The input's length as JSON, in characters.
+    pub fn chars(&self) -> usize {
+        serde_json::to_string(self).map_or(0, |json| json.chars().count())
+    }
+
+    /// Drops parts, least important first, until the input fits in `max_chars`
+    /// or only the command is left.
+    fn truncate(&mut self, max_chars: usize) {
+        while self.chars() > max_chars {
+            if self.memories.pop().is_some() {
+                continue;
+            }
+            if !self.history.is_empty() {
+                self.history.remove(0);
+                continue;
+            }
+            if self.attributes.pop_last().is_some() {
+                continue;
+            }
+            if self.metrics.take().is_some() {
+                continue;
+            }
+            if self.goals.pop().is_none() {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PlannerInput, PlannerInputConfig};
+    use crate::{AgentResult, CognitiveOrchestrator, Goal, GoalPriority, MockBackend};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    fn orchestrator() -> CognitiveOrchestrator {
+        let ok = |rest: &str| AgentResult { output: format!("{} done", rest), status: true, metadata: HashMap::new(), error: None };
+        let mock = MockBackend::new().plan("warm", ["post one", "post two", "post three"]).on("post", ok);
+        let orch = CognitiveOrchestrator::builder().backend(Arc::new(mock)).learning(false).build().unwrap();
+        orch.add_goal("ctx1", Goal::new("tone", "keep it upbeat")).unwrap();
+        orch.add_goal("ctx1", Goal::new("reach", "reach 10k views").with_priority(GoalPriority::High)).unwrap();
+        orch.remember("ctx1", "teaser flopped on monday").unwrap();
+        orch.remember("ctx1", "launch went viral").unwrap();
+        orch.process("warm up".to_string(), "ctx1");
+        orch
+    }
+
+    /// The input for "launch it" on the context `orch` left, within `max_chars`.
+    fn trimmed(orch: &CognitiveOrchestrator, max_chars: usize) -> PlannerInput {
+        let context = orch.get_context("ctx1").unwrap();
+        let history = orch.get_history("ctx1", None);
+        let memories = context.memory_texts.iter().flatten().cloned().collect();
+        let config = PlannerInputConfig { max_chars, ..PlannerInputConfig::default() };
+        PlannerInput::new("launch it", Some(&context), &history, memories, "launch it".to_string(), &config)
+    }
+
+    #[test]
+    fn assembles_goals_metrics_history_and_memories() {
+        let orch = orchestrator().with_planner_input(PlannerInputConfig { history: 2, ..PlannerInputConfig::default() });
+        let input = orch.planner_input("launch it", "ctx1", &[]);
+        assert_eq!(input.command, "launch it");
+        assert_eq!(input.goals.iter().map(|goal| goal.id.as_str()).collect::<Vec<_>>(), ["reach", "tone"]);
+        assert_eq!(input.metrics, Some(orch.get_context("ctx1").unwrap().viral_metrics));
+        assert_eq!(input.history.iter().map(|record| record.subtask.as_str()).collect::<Vec<_>>(), ["post two", "post three"]);
+        assert_eq!(input.memories.len(), 2);
+        assert_eq!(input.prompt(), "launch it\n\nActive high-priority goals:\n- reach 10k views");
+
+        let json = serde_json::to_value(&input).unwrap();
+        let record = serde_json::json!({"command": "warm up", "subtask": "post three", "status": true, "output": "three done"});
+        assert_eq!(json["history"][1], record);
+        assert!(json.get("prompt").is_none());
+        // A context that does not exist yet contributes nothing.
+        let fresh = orch.planner_input("launch it", "ctx2", &[]);
+        assert_eq!((fresh.goals.len(), fresh.metrics, fresh.history.len(), fresh.memories.len()), (0, None, 0, 0));
+    }
+
+    #[test]
+    fn truncates_memories_then_history_then_metrics_then_goals() {
+        let orch = orchestrator();
+        let full = trimmed(&orch, usize::MAX);
+        let parts = |input: &PlannerInput| (input.goals.len(), input.metrics.is_some(), input.history.len(), input.memories.len());
+
+        let mut steps = vec![];
+        for max_chars in (0..=full.chars()).rev() {
+            let input = trimmed(&orch, max_chars);
+            assert!(input.chars() <= max_chars || parts(&input) == (0, false, 0, 0), "{}", max_chars);
+            if input.goals.len() == 1 {
+                assert_eq!(input.goals[0].id, "reach");
+            }
+            if steps.last() != Some(&parts(&input)) {
+                steps.push(parts(&input));
+            }
+        }
+        assert_eq!(
+            steps,
+            [
+                (2, true, 3, 2),
+                (2, true, 3, 1),
+                (2, true, 3, 0),
+                (2, true, 2, 0),
+                (2, true, 1, 0),
+                (2, true, 0, 0),
+                (2, false, 0, 0),
+                (1, false, 0, 0),
+                (0, false, 0, 0),
+            ]
+        );
+        assert_eq!(trimmed(&orch, 0).command, "launch it");
+    }
+}
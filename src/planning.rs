@@ -0,0 +1,274 @@
+use crate::OrchestratorError;
+use petgraph::algo::tarjan_scc;
+use petgraph::graph::{DiGraph, NodeIndex};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Identifier of a node within one `Plan`.
+pub type NodeId = usize;
+
+/// One subtask of a plan; it may start once every node in `depends_on` has finished.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanNode {
+    pub id: NodeId,
+    #[serde(rename = "task")]
+    pub subtask: String,
+    #[serde(rename = "deps", default)]
+    pub depends_on: Vec<NodeId>,
+}
+
+/// Dependency graph of subtasks. Construction validates ids and rejects cycles, so
+/// every `Plan` has a topological order.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct Plan {
+    nodes: Vec<PlanNode>,
+}
+
+impl Plan {
+    pub fn new(nodes: Vec<PlanNode>) -> Result<Self, OrchestratorError> {
+        let mut graph = DiGraph::<NodeId, ()>::new();
+        let mut index: HashMap<NodeId, NodeIndex> = HashMap::new();
+        for node in &nodes {
+            if index.insert(node.id, graph.add_node(node.id)).is_some() {
+                return Err(OrchestratorError::InvalidPlan { message: format!("duplicate node id {}", node.id) });
+            }
+        }
+        for node in &nodes {
+            for dep in &node.depends_on {
+                let Some(&from) = index.get(dep) else {
+                    return Err(OrchestratorError::InvalidPlan {
+                        message: format!("{:?} depends on unknown node {}", node.subtask, dep),
+                    });
+                };
+                graph.add_edge(from, index[&node.id], ());
+            }
+        }
+
+        let cycle = tarjan_scc(&graph).into_iter().find(|component| {
+            component.len() > 1 || graph.contains_edge(component[0], component[0])
+        });
+        if let Some(component) = cycle {
+            let mut ids: Vec<NodeId> = component.iter().map(|&i| graph[i]).collect();
+            ids.sort_unstable();
+            let subtasks = ids
+                .iter()
+                .filter_map(|id| nodes.iter().find(|node| node.id == *id))
+                .map(|node| node.subtask.clone())
+                .collect();
+            return Err(OrchestratorError::PlanCycle { subtasks });
+        }
+        Ok(Self { nodes })
+    }
+
+    /// Each step depends on the one before it.
+    pub fn linear(steps: impl IntoIterator<Item = String>) -> Self {
+        let nodes = steps
+            .into_iter()
+            .enumerate()
+            .map(|(id, subtask)| PlanNode { id, subtask, depends_on: id.checked_sub(1).into_iter().collect() })
+            .collect();
+        Self { nodes }
+    }
+
+    /// Accepts the Python planner's output: a list of strings is a linear plan;
+    /// otherwise strings become nodes without dependencies next to
+    /// `{"task", "deps", "id"}` dicts, whose `id` defaults to the list position.
+    pub(crate) fn from_planner(steps: Vec<PlannerStep>) -> Result<Self, OrchestratorError> {
+        if steps.iter().all(|step| matches!(step, PlannerStep::Task(_))) {
+            return Ok(Self::linear(steps.into_iter().filter_map(|step| match step {
+                PlannerStep::Task(task) => Some(task),
+                PlannerStep::Node { .. } => None,
+            })));
+        }
+        let nodes = steps
+            .into_iter()
+            .enumerate()
+            .map(|(position, step)| match step {
+                PlannerStep::Task(task) => PlanNode { id: position, subtask: task, depends_on: vec![] },
+                PlannerStep::Node { task, deps, id } => {
+                    PlanNode { id: id.unwrap_or(position), subtask: task, depends_on: deps }
+                }
+            })
+            .collect();
+        Self::new(nodes)
+    }
+
+    pub fn nodes(&self) -> &[PlanNode] {
+        &self.nodes
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Nodes grouped into waves: every node's dependencies lie in earlier waves,
+    /// and each wave keeps plan order.
+    pub fn waves(&self) -> Vec<Vec<&PlanNode>> {
+        let mut wave_of: HashMap<NodeId, usize> = HashMap::new();
+        let mut waves: Vec<Vec<&PlanNode>> = vec![];
+        while wave_of.len() < self.nodes.len() {
+            let wave: Vec<&PlanNode> = self
+                .nodes
+                .iter()
+                .filter(|node| !wave_of.contains_key(&node.id))
+                .filter(|node| node.depends_on.iter().all(|dep| wave_of.contains_key(dep)))
+                .collect();
+            if wave.is_empty() {
+                break;
+            }
+            for node in &wave {
+                wave_of.insert(node.id, waves.len());
+            }
+            waves.push(wave);
+        }
+        waves
+    }
+
+    /// Subtasks in execution order: wave by wave, plan order within a wave.
+    pub fn subtasks(&self) -> Vec<String> {
+        self.waves().into_iter().flatten().map(|node| node.subtask.clone()).collect()
+    }
+}
+
+impl From<Vec<String>> for Plan {
+    fn from(steps: Vec<String>) -> Self {
+        Self::linear(steps)
+    }
+}
+
+/// One element of `PlannerAgent.decompose`'s result.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum PlannerStep {
+    Task(String),
+    Node {
+        task: String,
+        #[serde(default)]
+        deps: Vec<NodeId>,
+        id: Option<NodeId>,
+    },
+}
+
+/// How a template decides whether it applies to a command.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "pattern", rename_all = "snake_case")]
+pub enum PlanTrigger {
+    /// Command contains the substring anywhere.
+    Keyword(String),
+    /// Command starts with the string.
+    Prefix(String),
+    /// Command matches the regular expression.
+    Regex(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanTemplate {
+    pub trigger: PlanTrigger,
+    pub steps: Vec<String>,
+}
+
+struct CompiledTemplate {
+    template: PlanTemplate,
+    regex: Option<Regex>,
+}
+
+impl CompiledTemplate {
+    fn compile(template: PlanTemplate) -> Result<Self, OrchestratorError> {
+        let regex = match &template.trigger {
+            PlanTrigger::Regex(pattern) => Some(Regex::new(pattern).map_err(|source| {
+                OrchestratorError::InvalidPattern { pattern: pattern.clone(), message: source.to_string() }
+            })?),
+            _ => None,
+        };
+        Ok(Self { template, regex })
+    }
+
+    fn matches(&self, command: &str) -> bool {
+        match (&self.template.trigger, &self.regex) {
+            (PlanTrigger::Keyword(keyword), _) => command.contains(keyword.as_str()),
+            (PlanTrigger::Prefix(prefix), _) => command.starts_with(prefix.as_str()),
+            (PlanTrigger::Regex(_), Some(regex)) => regex.is_match(command),
+            (PlanTrigger::Regex(_), None) => false,
+        }
+    }
+}
+
+/// Ordered template registry; the first registered template that matches wins.
+pub struct PlanTemplates {
+    templates: Vec<CompiledTemplate>,
+}
+
+impl PlanTemplates {
+    pub fn empty() -> Self {
+        Self { templates: vec![] }
+    }
+
+    /// The built-in viral pipeline, triggered by "viral" or "engage".
+    pub fn with_defaults() -> Self {
+        let steps: Vec<String> = ["content:generate", "hook:inject", "mwpm:amplify", "spread:measure", "eval:metrics"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let mut templates = Self::empty();
+        for keyword in ["viral", "engage"] {
+            templates.templates.push(CompiledTemplate {
+                template: PlanTemplate { trigger: PlanTrigger::Keyword(keyword.to_string()), steps: steps.clone() },
+                regex: None,
+            });
+        }
+        templates
+    }
+
+    pub fn register(&mut self, template: PlanTemplate) -> Result<(), OrchestratorError> {
+        self.templates.push(CompiledTemplate::compile(template)?);
+        Ok(())
+    }
+
+    /// Replaces all templates; nothing changes if any pattern fails to compile.
+    pub fn replace_all(&mut self, templates: Vec<PlanTemplate>) -> Result<(), OrchestratorError> {
+        self.templates = templates
+            .into_iter()
+            .map(CompiledTemplate::compile)
+            .collect::<Result<_, _>>()?;
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.templates.clear();
+    }
+
+    pub fn templates(&self) -> Vec<PlanTemplate> {
+        self.templates.iter().map(|c| c.template.clone()).collect()
+    }
+
+    pub fn match_command(&self, command: &str) -> Option<&[String]> {
+        self.templates
+            .iter()
+            .find(|c| c.matches(command))
+            .map(|c| c.template.steps.as_slice())
+    }
+}
+
+/// `DebugAgent.re_plan`'s answer: a single replacement subtask, or a list in any
+/// shape the planner may return.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum Replan {
+    Subtask(String),
+    Steps(Vec<PlannerStep>),
+}
+
+impl Replan {
+    pub(crate) fn into_plan(self) -> Result<Plan, OrchestratorError> {
+        match self {
+            Replan::Subtask(subtask) => Ok(Plan::linear([subtask])),
+            Replan::Steps(steps) => Plan::from_planner(steps),
+        }
+    }
+}
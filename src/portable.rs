@@ -0,0 +1,197 @@
+use crate::{Context, ExecutionRecord};
+use chrono::{DateTime, Utc};
+use pyo3::exceptions::PyValueError;
+use pyo3::PyErr;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+/// Bumped whenever an exported context's layout changes. Documents from newer
+/// versions still import; fields this version does not know land in `Context::extra`.
+pub const EXPORT_SCHEMA_VERSION: u32 = 1;
+
+/// Why `CognitiveOrchestrator::import_context` rejected a document.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ImportError {
+    #[error("invalid context document: {message}")]
+    Parse { message: String },
+
+    #[error("context document has no schema_version")]
+    MissingVersion,
+
+    #[error("unsupported schema_version {found}")]
+    UnsupportedVersion { found: Value },
+
+    /// `field` is the path of the first vector whose length differs from the
+    /// vectors before it, e.g. `snapshots[1].memory_vectors[0]`.
+    #[error("{field} has {actual} dimensions, expected {expected}")]
+    DimensionMismatch { field: String, expected: usize, actual: usize },
+
+    #[error("snapshot {index} belongs to context {found}, not {context_id}")]
+    SnapshotMismatch { context_id: String, index: usize, found: String },
+
+    #[error("context {context_id} already exists")]
+    Collision { context_id: String },
+
+    #[error("invalid tenant {tenant:?}")]
+    InvalidTenant { tenant: String },
+}
+
+impl From<ImportError> for PyErr {
+    fn from(err: ImportError) -> Self {
+        PyValueError::new_err(err.to_string())
+    }
+}
+
+/// A snapshot as exported; ids are reassigned on import so they keep increasing
+/// in the order snapshots are taken.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedSnapshot {
+    pub snapshot_id: u64,
+    pub taken_at: DateTime<Utc>,
+    pub context: Context,
+}
+
+/// One context with its execution history and snapshots. The context's own
+/// fields sit at the top level next to `schema_version`, so `jq .viral_metrics`
+/// works on an export directly.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContextExport {
+    pub schema_version: u32,
+    pub exported_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub context: Context,
+    #[serde(default)]
+    pub history: Vec<ExecutionRecord>,
+    #[serde(default)]
+    pub snapshots: Vec<ExportedSnapshot>,
+}
+
+impl ContextExport {
+    /// Checks the version and vector shapes before decoding, so a bad document is
+    /// reported by what is wrong with it rather than by a serde error.
+    pub fn parse(json: &str) -> Result<Self, ImportError> {
+        let parse = |err: serde_json::Error| ImportError::Parse { message: err.to_string() };
+        let document: Value = serde_json::from_str(json).map_err(parse)?;
+        match document.get("schema_version") {
+            None => return Err(ImportError::MissingVersion),
+            Some(version) if version.as_u64().is_some_and(|version| version >= 1 && version <= u64::from(u32::MAX)) => {}
+            Some(version) => return Err(ImportError::UnsupportedVersion { found: version.clone() }),
+        }
+
+        let mut dim = None;
+        check_dims("memory_vectors", &document["memory_vectors"], &mut dim)?;
+        if let Some(snapshots) = document["snapshots"].as_array() {
+            for (i, snapshot) in snapshots.iter().enumerate() {
+                check_dims(&format!("snapshots[{}].context.memory_vectors", i), &snapshot["context"]["memory_vectors"], &mut dim)?;
+            }
+        }
+
+        let export: ContextExport = serde_json::from_value(document).map_err(parse)?;
+        let context_id = &export.context.context_id;
+        if let Some((index, snapshot)) = export.snapshots.iter().enumerate().find(|(_, snapshot)| snapshot.context.context_id != *context_id) {
+            return Err(ImportError::SnapshotMismatch {
+                context_id: context_id.clone(),
+                index,
+                found: snapshot.context.context_id.clone(),
+            });
+        }
+        Ok(export)
+    }
+}
+
+/// Every vector under `vectors` must have the length of the first one seen,
+/// across the context and all its snapshots.
+fn check_dims(field: &str, vectors: &Value, dim: &mut Option<usize>) -> Result<(), ImportError> {
+    for (i, vector) in vectors.as_array().into_iter().flatten().enumerate() {
+        let Some(len) = vector.as_array().map(Vec::len) else {
+            continue;
+        };
+        match *dim {
+            Some(expected) if expected != len => {
+                return Err(ImportError::DimensionMismatch { field: format!("{}[{}]", field, i), expected, actual: len });
+            }
+            _ => *dim = Some(len),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CognitiveOrchestrator, FixedClock, MockBackend, OrchestratorError};
+    use std::sync::Arc;
+
+    fn populated() -> CognitiveOrchestrator {
+        let clock = Arc::new(FixedClock::new(DateTime::UNIX_EPOCH + chrono::Duration::seconds(100)));
+        let orch = CognitiveOrchestrator::builder()
+            .backend(Arc::new(MockBackend::new().virality(0.4)))
+            .clock(clock.clone())
+            .build()
+            .unwrap()
+            .with_auto_snapshots(true);
+        orch.process("inject hook 0.2".to_string(), "ctx1");
+        orch.process("measure spread".to_string(), "ctx1");
+        clock.advance(chrono::Duration::seconds(5));
+        orch.process("measure spread".to_string(), "ctx1");
+        orch.remember("ctx1", "launch teaser").unwrap();
+        orch
+    }
+
+    #[test]
+    fn exports_round_trip_with_history_snapshots_and_metrics() {
+        let orch = populated();
+        let json = orch.export_context("ctx1").unwrap();
+        let document: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(document["schema_version"], EXPORT_SCHEMA_VERSION);
+        assert_eq!(document["history"].as_array().unwrap().len(), 3);
+        assert_eq!(document["metrics_history"].as_array().unwrap().len(), 2);
+        assert!(!document["snapshots"].as_array().unwrap().is_empty());
+
+        let restored = CognitiveOrchestrator::new();
+        assert_eq!(restored.import_context(&json, false).unwrap(), "ctx1");
+        assert_eq!(restored.get_context("ctx1"), orch.get_context("ctx1"));
+        assert_eq!(restored.get_history("ctx1", None), orch.get_history("ctx1", None));
+        let contents = |orch: &CognitiveOrchestrator| {
+            orch.get_snapshots("ctx1").into_iter().map(|snapshot| (snapshot.taken_at, (*snapshot.context).clone())).collect::<Vec<_>>()
+        };
+        assert_eq!(contents(&restored), contents(&orch));
+        assert!(restored.metrics_trend("ctx1").unwrap().is_some());
+
+        assert_eq!(restored.import_context(&json, false), Err(ImportError::Collision { context_id: "ctx1".to_string() }));
+        assert_eq!(restored.import_context(&json, true).unwrap(), "ctx1");
+        assert_eq!(contents(&restored), contents(&orch));
+        assert!(matches!(orch.export_context("ghost"), Err(OrchestratorError::MissingContext { .. })));
+    }
+
+    #[test]
+    fn newer_fields_survive_and_bad_documents_are_rejected() {
+        let mut document: Value = serde_json::from_str(&populated().export_context("ctx1").unwrap()).unwrap();
+        document["schema_version"] = Value::from(2);
+        document["audience"] = serde_json::json!({"region": "eu"});
+        let orch = CognitiveOrchestrator::new();
+        orch.import_context(&document.to_string(), false).unwrap();
+        assert_eq!(orch.get_context("ctx1").unwrap().extra["audience"]["region"], "eu");
+        let again: Value = serde_json::from_str(&orch.export_context("ctx1").unwrap()).unwrap();
+        assert_eq!(again["audience"], document["audience"]);
+        assert_eq!(again["schema_version"], EXPORT_SCHEMA_VERSION);
+
+        let rejected = |edit: fn(&mut Value)| {
+            let mut bad = document.clone();
+            edit(&mut bad);
+            ContextExport::parse(&bad.to_string()).unwrap_err()
+        };
+        assert_eq!(rejected(|doc| drop(doc.as_object_mut().unwrap().remove("schema_version"))), ImportError::MissingVersion);
+        assert!(matches!(rejected(|doc| doc["schema_version"] = Value::from(0)), ImportError::UnsupportedVersion { .. }));
+        assert!(matches!(rejected(|doc| doc["schema_version"] = Value::from("1")), ImportError::UnsupportedVersion { .. }));
+        let dim = |doc: &Value| doc["memory_vectors"][0].as_array().unwrap().len();
+        let expected = dim(&document);
+        assert_eq!(
+            rejected(|doc| doc["snapshots"][0]["context"]["memory_vectors"] = serde_json::json!([[1.0, 2.0]])),
+            ImportError::DimensionMismatch { field: "snapshots[0].context.memory_vectors[0]".to_string(), expected, actual: 2 }
+        );
+        assert!(matches!(rejected(|doc| doc["snapshots"][0]["context"]["context_id"] = Value::from("ctx2")), ImportError::SnapshotMismatch { index: 0, .. }));
+        assert!(matches!(ContextExport::parse("{"), Err(ImportError::Parse { .. })));
+    }
+}
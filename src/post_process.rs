@@ -0,0 +1,212 @@
+use crate::{Agent, AgentKind, AgentResult, OrchestratorError};
+use serde_json::Value;
+use std::any::Any;
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, RwLock};
+use tracing::warn;
+
+/// Rewrites or checks an agent's result after dispatch, before a failure is
+/// debugged. Closures taking `(subtask, result)` are post-processors too.
+pub trait PostProcessor: Send + Sync {
+    fn apply(&self, subtask: &str, result: AgentResult) -> AgentResult;
+}
+
+impl<F> PostProcessor for F
+where
+    F: Fn(&str, AgentResult) -> AgentResult + Send + Sync,
+{
+    fn apply(&self, subtask: &str, result: AgentResult) -> AgentResult {
+        self(subtask, result)
+    }
+}
+
+/// Trims leading and trailing whitespace off the output.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TrimWhitespace;
+
+impl PostProcessor for TrimWhitespace {
+    fn apply(&self, _subtask: &str, mut result: AgentResult) -> AgentResult {
+        let trimmed = result.output.trim();
+        if trimmed.len() != result.output.len() {
+            result.output = trimmed.to_string();
+        }
+        result
+    }
+}
+
+/// Puts the first JSON object or array in the output, fenced or not, into
+/// `metadata["parsed"]`. The output is left as it is; without one nothing changes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExtractJsonBlock;
+
+impl ExtractJsonBlock {
+    pub const PARSED: &'static str = "parsed";
+
+    fn first_block(output: &str) -> Option<Value> {
+        output.match_indices(['{', '[']).find_map(|(at, _)| {
+            // Parses one value off the front, ignoring whatever follows it.
+            let mut values = serde_json::Deserializer::from_str(&output[at..]).into_iter::<Value>();
+            values.next()?.ok()
+        })
+    }
+}
+
+impl PostProcessor for ExtractJsonBlock {
+    fn apply(&self, _subtask: &str, mut result: AgentResult) -> AgentResult {
+        if let Some(parsed) = Self::first_block(&result.output) {
+            result.metadata.insert(Self::PARSED.to_string(), parsed);
+        }
+        result
+    }
+}
+
+/// Cuts the output down to `max_chars` characters, recording the length it had
+/// in `metadata["truncated_from"]`.
+#[derive(Debug, Clone, Copy)]
+pub struct MaxLengthTruncate {
+    pub max_chars: usize,
+}
+
+impl MaxLengthTruncate {
+    pub const TRUNCATED_FROM: &'static str = "truncated_from";
+
+    pub fn new(max_chars: usize) -> Self {
+        Self { max_chars }
+    }
+}
+
+impl PostProcessor for MaxLengthTruncate {
+    fn apply(&self, _subtask: &str, mut result: AgentResult) -> AgentResult {
+        if let Some((cut, _)) = result.output.char_indices().nth(self.max_chars) {
+            let chars = result.output.chars().count();
+            result.output.truncate(cut);
+            result.set(Self::TRUNCATED_FROM, chars);
+        }
+        result
+    }
+}
+
+/// The post-processor chains per `AgentKind`, run in registration order on every
+/// result of an agent of that kind, cached ones included; agents without a kind
+/// are left alone. A post-processor that panics fails the result with
+/// `call_failed` and ends the chain. Clones share the chains.
+#[derive(Clone, Default)]
+pub struct PostProcessors {
+    chains: Arc<RwLock<HashMap<AgentKind, Vec<Arc<dyn PostProcessor>>>>>,
+}
+
+impl PostProcessors {
+    /// Appends `processor` to the chain for `kind`.
+    pub fn add(&self, kind: AgentKind, processor: Arc<dyn PostProcessor>) {
+        let mut chains = self.chains.write().unwrap_or_else(|e| e.into_inner());
+        chains.entry(kind).or_default().push(processor);
+    }
+
+    pub fn clear(&self, kind: AgentKind) {
+        self.chains.write().unwrap_or_else(|e| e.into_inner()).remove(&kind);
+    }
+
+    /// Length of the chain for `kind`.
+    pub fn len(&self, kind: AgentKind) -> usize {
+        self.chains.read().unwrap_or_else(|e| e.into_inner()).get(&kind).map_or(0, Vec::len)
+    }
+
+    /// Runs the chain for `agent`'s kind over `result`.
+    pub(crate) fn apply(&self, agent: &dyn Agent, subtask: &str, mut result: AgentResult) -> AgentResult {
+        let Some(kind) = agent.kind() else { return result };
+        let chain = match self.chains.read().unwrap_or_else(|e| e.into_inner()).get(&kind) {
+            Some(chain) => chain.clone(),
+            None => return result,
+        };
+        for (idx, processor) in chain.iter().enumerate() {
+            let input = result.clone();
+            match panic::catch_unwind(AssertUnwindSafe(|| processor.apply(subtask, input))) {
+                Ok(processed) => result = processed,
+                Err(panicked) => {
+                    let target = format!("{}.post_processor[{}]", kind.name(), idx);
+                    warn!(subtask, "Post-processor {} panicked", target);
+                    let message = format!("post-processor panicked: {}", panic_message(panicked.as_ref()));
+                    let err = OrchestratorError::CallFailed { target, message, traceback: None };
+                    return AgentResult::from_error("Post-processor Error", err);
+                }
+            }
+        }
+        result
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    match (payload.downcast_ref::<&str>(), payload.downcast_ref::<String>()) {
+        (Some(message), _) => message,
+        (_, Some(message)) => message,
+        _ => "unknown panic",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CognitiveOrchestrator, MockBackend};
+    use serde_json::json;
+
+    fn orchestrator(output: &'static str) -> CognitiveOrchestrator {
+        let mock = MockBackend::new().generator(move |_| Ok(output.to_string()));
+        CognitiveOrchestrator::builder().backend(Arc::new(mock)).learning(false).build().unwrap()
+    }
+
+    #[test]
+    fn trim_whitespace_trims_llm_output() {
+        let orch = orchestrator("\n  a hook that lands  \n").with_post_processor(AgentKind::Llm, TrimWhitespace);
+        assert_eq!(orch.dispatch("llm:write a hook".to_string(), "ctx1").output, "a hook that lands");
+        // Viral results are another kind's and keep their own chain.
+        assert_eq!(orch.post_processors().len(AgentKind::Viral), 0);
+    }
+
+    #[test]
+    fn extract_json_block_parses_the_first_fenced_value() {
+        let orch = orchestrator("Sure:\n```json\n{\"hooks\": [\"a\", \"b\"]}\n```\nand {\"more\": 1}")
+            .with_post_processor(AgentKind::Llm, ExtractJsonBlock);
+        let result = orch.dispatch("llm:list hooks".to_string(), "ctx1");
+        assert!(result.status);
+        assert_eq!(result.metadata[ExtractJsonBlock::PARSED], json!({"hooks": ["a", "b"]}));
+        assert!(result.output.starts_with("Sure:"));
+
+        // Braces that open no valid JSON are skipped for a later array.
+        let result = ExtractJsonBlock.apply("llm:x", AgentResult::ok("{not json} then [1, 2]"));
+        assert_eq!(result.metadata[ExtractJsonBlock::PARSED], json!([1, 2]));
+        assert!(ExtractJsonBlock.apply("llm:x", AgentResult::ok("no json")).metadata.is_empty());
+    }
+
+    #[test]
+    fn max_length_truncate_cuts_on_char_boundaries() {
+        let orch = orchestrator("ünïcödé output").with_post_processor(AgentKind::Llm, MaxLengthTruncate::new(7));
+        let result = orch.dispatch("llm:write".to_string(), "ctx1");
+        assert_eq!(result.output, "ünïcödé");
+        assert_eq!(result.get_f64(MaxLengthTruncate::TRUNCATED_FROM), Some(14.0));
+
+        let short = MaxLengthTruncate::new(20).apply("llm:x", AgentResult::ok("short"));
+        assert_eq!((short.output.as_str(), short.metadata.is_empty()), ("short", true));
+    }
+
+    #[test]
+    fn closures_chain_in_order_and_panics_fail_the_result() {
+        let orch = orchestrator("  draft  ")
+            .with_post_processor(AgentKind::Llm, TrimWhitespace)
+            .with_post_processor(AgentKind::Llm, |subtask: &str, result: AgentResult| AgentResult {
+                output: format!("{} <{}>", result.output, subtask),
+                ..result
+            });
+        assert_eq!(orch.dispatch("llm:edit".to_string(), "ctx1").output, "draft <llm:edit>");
+
+        orch.add_post_processor(AgentKind::Llm, |_: &str, _: AgentResult| -> AgentResult { panic!("bad regex") });
+        let result = orch.dispatch("llm:edit".to_string(), "ctx1");
+        assert!(!result.status);
+        assert_eq!(result.error.as_ref().unwrap().kind(), "call_failed");
+        assert!(result.output.contains("post-processor panicked: bad regex"), "{}", result.output);
+
+        // The orchestrator is still usable once the chain is cleared.
+        orch.clear_post_processors(AgentKind::Llm);
+        assert_eq!(orch.dispatch("llm:edit".to_string(), "ctx1").output, "  draft  ");
+    }
+}
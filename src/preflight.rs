@@ -0,0 +1,278 @@
+use crate::{AgentBackend, AgentKind, AgentModule, ContextStore, MemoryStore, OrchestratorError};
+use pyo3::prelude::*;
+use pyo3::types::PyTuple;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::path::Path;
+
+/// What the smoke check plans and generates: harmless to any backend.
+pub const SMOKE_PROMPT: &str = "Reply with the single word: ready";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    /// Works, but not as configured, or only through a fallback.
+    Warn,
+    /// Runs would fail, or silently fall back, until it is fixed.
+    Fail,
+}
+
+impl CheckStatus {
+    pub fn name(self) -> &'static str {
+        match self {
+            CheckStatus::Pass => "pass",
+            CheckStatus::Warn => "warn",
+            CheckStatus::Fail => "fail",
+        }
+    }
+}
+
+/// One row of a `PreflightReport`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PreflightCheck {
+    /// Dotted, such as `agents.planner`, `memory` or `paths.journal`.
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    /// What to change, for a warning or failure.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remediation: Option<String>,
+}
+
+impl PreflightCheck {
+    pub fn pass(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self { name: name.into(), status: CheckStatus::Pass, detail: detail.into(), remediation: None }
+    }
+
+    pub fn warn(name: impl Into<String>, detail: impl Into<String>, remediation: impl Into<String>) -> Self {
+        Self { status: CheckStatus::Warn, remediation: Some(remediation.into()), ..Self::pass(name, detail) }
+    }
+
+    pub fn fail(name: impl Into<String>, detail: impl Into<String>, remediation: impl Into<String>) -> Self {
+        Self { status: CheckStatus::Fail, remediation: Some(remediation.into()), ..Self::pass(name, detail) }
+    }
+
+    /// Whether `path` can be written: opened for append when it exists, otherwise
+    /// a probe file created and removed beside it. Nothing already there changes.
+    pub fn writable(name: impl Into<String>, path: &Path) -> Self {
+        let remediation = || format!("make {} writable by this process, or configure another path", path.display());
+        if path.exists() {
+            return match OpenOptions::new().append(true).open(path) {
+                Ok(_) => Self::pass(name, format!("{} is writable", path.display())),
+                Err(err) => Self::fail(name, format!("cannot write {}: {}", path.display(), err), remediation()),
+            };
+        }
+        let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        let file_name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        let probe = dir.join(format!(".{}.preflight-{}", file_name, std::process::id()));
+        match OpenOptions::new().write(true).create_new(true).open(&probe) {
+            Ok(_) => {
+                let _ = fs::remove_file(&probe);
+                Self::pass(name, format!("{} can be created", path.display()))
+            }
+            Err(err) => Self::fail(name, format!("cannot create files in {}: {}", dir.display(), err), remediation()),
+        }
+    }
+}
+
+/// What `CognitiveOrchestrator::preflight` found, check by check.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PreflightReport {
+    pub checks: Vec<PreflightCheck>,
+}
+
+impl PreflightReport {
+    /// The worst status of any check; `Pass` for none.
+    pub fn status(&self) -> CheckStatus {
+        self.checks.iter().map(|check| check.status).max().unwrap_or(CheckStatus::Pass)
+    }
+
+    /// No check failed; warnings allowed.
+    pub fn is_ready(&self) -> bool {
+        self.status() != CheckStatus::Fail
+    }
+
+    pub fn get(&self, name: &str) -> Option<&PreflightCheck> {
+        self.checks.iter().find(|check| check.name == name)
+    }
+}
+
+/// Imports the agent's module and looks up its class and method, checking with
+/// `inspect.signature` that the method takes the arguments the backend passes,
+/// without instantiating anything. `unused` says why a failure only warns.
+pub(crate) fn check_agent(kind: AgentKind, module: &AgentModule, unused: Option<&str>) -> PreflightCheck {
+    let name = format!("agents.{}", kind.name());
+    let (method, params) = kind.method();
+    let call = format!("{}({})", method, params.join(", "));
+    let problem = Python::with_gil(|py| -> PyResult<Option<(String, String)>> {
+        let class = match py.import(module.module.as_str()) {
+            Ok(imported) => match imported.getattr(module.class.as_str()) {
+                Ok(class) => class,
+                Err(err) => {
+                    let remediation = format!("name the class in python_modules.{} as `module:Class`", kind.name());
+                    return Ok(Some((format!("{} has no class {}: {}", module.module, module.class, err), remediation)));
+                }
+            },
+            Err(err) => {
+                let remediation =
+                    format!("install {} or point python_modules.{} at an importable module", module.module, kind.name());
+                return Ok(Some((format!("cannot import {}: {}", module.module, err), remediation)));
+            }
+        };
+        let Ok(attr) = class.getattr(method) else {
+            return Ok(Some((format!("{} has no method {}", module.target(), method), format!("define {} on the class", call))));
+        };
+        let inspect = py.import("inspect")?;
+        let Ok(signature) = inspect.call_method1("signature", (attr,)) else {
+            // Builtins and some extension methods carry no signature to check.
+            return Ok(None);
+        };
+        // A plain function looked up on the class still takes `self`.
+        let raw = inspect.call_method1("getattr_static", (class, method))?;
+        let takes_self = inspect.call_method1("isfunction", (raw,))?.is_true()?;
+        let args = vec![py.None(); params.len() + usize::from(takes_self)];
+        match signature.call_method1("bind", PyTuple::new(py, args)) {
+            Ok(_) => Ok(None),
+            Err(_) => Ok(Some((
+                format!("{}.{}{} cannot be called as {}", module.target(), method, signature, call),
+                format!("give the method the signature {}", call),
+            ))),
+        }
+    });
+    let problem = problem.unwrap_or_else(|err| {
+        Some((format!("inspecting {} failed: {}", module.target(), err), "check the module imports cleanly in Python".to_string()))
+    });
+    match (problem, unused) {
+        (None, _) => PreflightCheck::pass(name, format!("{} has {}", module.target(), call)),
+        (Some((detail, remediation)), Some(unused)) => PreflightCheck::warn(name, format!("{}; {}", detail, unused), remediation),
+        (Some((detail, remediation)), None) => PreflightCheck::fail(name, detail, remediation),
+    }
+}
+
+pub(crate) fn check_memory(store: Option<&dyn MemoryStore>) -> PreflightCheck {
+    match store.map(|store| store.ping()) {
+        None => PreflightCheck::pass("memory", "no native memory store; anomalies go to the memory agent"),
+        Some(Ok(())) => PreflightCheck::pass("memory", "the memory store answered"),
+        Some(Err(err)) => PreflightCheck::fail(
+            "memory",
+            format!("the memory store did not answer: {}", err),
+            "check the memory store (qdrant.url) is up and reachable",
+        ),
+    }
+}
+
+pub(crate) fn check_context_store(store: &dyn ContextStore) -> PreflightCheck {
+    match store.list() {
+        Ok(keys) => PreflightCheck::pass("context_store", format!("{} contexts stored", keys.len())),
+        Err(err) => PreflightCheck::fail(
+            "context_store",
+            format!("cannot list stored contexts: {}", err),
+            "check the context store's file or database is readable",
+        ),
+    }
+}
+
+/// Plans `SMOKE_PROMPT` and generates a reply to it through `backend`, without
+/// touching any context.
+pub(crate) fn smoke(backend: &dyn AgentBackend) -> [PreflightCheck; 2] {
+    let remediation = |err: &OrchestratorError| match err {
+        OrchestratorError::Extraction { .. } => "make the agent return what the orchestrator expects; see the error".to_string(),
+        _ => "fix the agent's error, then run the smoke check again".to_string(),
+    };
+    let plan = match backend.plan(SMOKE_PROMPT) {
+        Ok(plan) if plan.is_empty() => PreflightCheck::warn(
+            "smoke.plan",
+            "the planner returned no steps",
+            "have decompose return at least one subtask; empty plans fall back to the command itself",
+        ),
+        Ok(plan) => PreflightCheck::pass("smoke.plan", format!("planned {} steps", plan.len())),
+        Err(err) => PreflightCheck::fail("smoke.plan", format!("planning failed: {}", err), remediation(&err)),
+    };
+    let generate = match backend.generate(SMOKE_PROMPT) {
+        Ok(reply) => PreflightCheck::pass("smoke.generate", format!("the LLM replied with {} bytes", reply.len())),
+        Err(err) => PreflightCheck::fail("smoke.generate", format!("generation failed: {}", err), remediation(&err)),
+    };
+    [plan, generate]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CognitiveOrchestrator, MockBackend, Plan};
+    use std::sync::Arc;
+
+    #[test]
+    fn a_missing_module_fails_with_remediation_and_an_unused_one_only_warns() {
+        let missing = AgentModule::new("ace_preflight_missing_module", "Planner");
+        let check = check_agent(AgentKind::Planner, &missing, None);
+        assert_eq!((check.name.as_str(), check.status), ("agents.planner", CheckStatus::Fail));
+        assert!(check.detail.starts_with("cannot import ace_preflight_missing_module"), "{}", check.detail);
+        assert!(check.remediation.unwrap().contains("python_modules.planner"));
+        let check = check_agent(AgentKind::Viral, &missing, Some("unused while prefer_native is on"));
+        assert_eq!(check.status, CheckStatus::Warn);
+        assert!(check.detail.ends_with("unused while prefer_native is on"));
+    }
+
+    #[test]
+    fn method_arity_is_checked_against_what_the_backend_passes() {
+        Python::with_gil(|py| {
+            let module = pyo3::types::PyModule::from_code(
+                py,
+                "class Good:\n    def decompose(self, command, extra=None): pass\n\
+                 class Bad:\n    def decompose(self): pass\n\
+                 class Static:\n    @staticmethod\n    def decompose(command): pass\n",
+                "ace_preflight_agents.py",
+                "ace_preflight_agents",
+            )
+            .unwrap();
+            py.import("sys").unwrap().getattr("modules").unwrap().set_item("ace_preflight_agents", module).unwrap();
+        });
+        let status = |class: &str| check_agent(AgentKind::Planner, &AgentModule::new("ace_preflight_agents", class), None);
+        assert_eq!(status("Good").status, CheckStatus::Pass);
+        assert_eq!(status("Static").status, CheckStatus::Pass);
+        let bad = status("Bad");
+        assert_eq!(bad.status, CheckStatus::Fail);
+        assert_eq!(bad.detail, "ace_preflight_agents.Bad.decompose(self) cannot be called as decompose(command)");
+        let missing = status("Missing");
+        assert!(missing.detail.starts_with("ace_preflight_agents has no class Missing"), "{}", missing.detail);
+    }
+
+    #[test]
+    fn the_report_checks_paths_and_the_smoke_run() {
+        let dir = std::env::temp_dir().join(format!("ace-preflight-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mock = MockBackend::new().planner(|_| Ok(Plan::from(Vec::<String>::new())));
+        let orch = CognitiveOrchestrator::builder()
+            .backend(Arc::new(mock))
+            .eviction_path(dir.join("contexts.json"))
+            .learning(false)
+            .build()
+            .unwrap();
+        let report = orch.preflight();
+        assert!(report.is_ready(), "{:?}", report);
+        assert_eq!(report.get("agents").unwrap().status, CheckStatus::Pass);
+        let eviction = report.get("paths.eviction").unwrap();
+        assert_eq!(eviction.detail, format!("{} can be created", dir.join("contexts.json").display()));
+        assert!(report.get("smoke.plan").is_none());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+
+        let report = orch.preflight_with_smoke();
+        assert_eq!(report.get("smoke.plan").unwrap().status, CheckStatus::Warn);
+        assert_eq!(report.get("smoke.generate").unwrap().status, CheckStatus::Pass);
+        assert_eq!(report.status(), CheckStatus::Warn);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["checks"][0]["status"], "pass");
+        assert_eq!(serde_json::from_value::<PreflightReport>(json).unwrap(), report);
+
+        let unwritable = CognitiveOrchestrator::builder()
+            .backend(Arc::new(MockBackend::new()))
+            .eviction_path(dir.join("missing").join("contexts.json"))
+            .build()
+            .unwrap();
+        let report = unwritable.preflight();
+        assert!(!report.is_ready());
+        assert!(report.get("paths.eviction").unwrap().remediation.is_some());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,321 @@
+use crate::viral::SplitMix64;
+use petgraph::graph::{NodeIndex, UnGraph};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// How the engagement graph a viral simulation spreads over is wired.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Topology {
+    /// About `nodes * mean_degree / 2` uniformly random edges: every node links to
+    /// `mean_degree / 2` (at least one) others picked uniformly.
+    ErdosRenyi { mean_degree: usize },
+    /// Preferential attachment: each node after the first `attachments + 1`, which
+    /// start fully connected, links to `attachments` earlier nodes picked in
+    /// proportion to their degree, so a few hubs emerge.
+    BarabasiAlbert { attachments: usize },
+    /// Watts–Strogatz: a ring where every node links to its `mean_degree / 2`
+    /// nearest neighbours on each side, with each link rewired to a uniformly
+    /// random node with probability `rewire`.
+    SmallWorld { mean_degree: usize, rewire: f64 },
+}
+
+impl Default for Topology {
+    fn default() -> Self {
+        Topology::ErdosRenyi { mean_degree: 4 }
+    }
+}
+
+impl Topology {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Topology::ErdosRenyi { .. } => "erdos_renyi",
+            Topology::BarabasiAlbert { .. } => "barabasi_albert",
+            Topology::SmallWorld { .. } => "small_world",
+        }
+    }
+
+    /// The first invalid parameter, by field name, with what is wrong with it.
+    pub fn invalid(&self) -> Option<(&'static str, &'static str)> {
+        match *self {
+            Topology::BarabasiAlbert { attachments: 0 } => Some(("attachments", "must be at least 1")),
+            Topology::SmallWorld { rewire, .. } if !(0.0..=1.0).contains(&rewire) => {
+                Some(("rewire", "must be between 0 and 1"))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Virality a viral subtask must exceed to succeed unless configured otherwise.
+pub const DEFAULT_VIRALITY_THRESHOLD: f64 = 0.8;
+
+/// Viral simulation settings, under `viral` in `Config`; a context's own, set
+/// with `set_viral_config`, replace them for its subtasks.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct ViralConfig {
+    pub topology: Topology,
+    /// A viral subtask succeeds when the simulated virality exceeds this.
+    pub virality_threshold: f64,
+}
+
+impl Default for ViralConfig {
+    fn default() -> Self {
+        Self { topology: Topology::default(), virality_threshold: DEFAULT_VIRALITY_THRESHOLD }
+    }
+}
+
+impl ViralConfig {
+    /// The first invalid setting, by its path under `viral`, with what is wrong with it.
+    pub fn invalid(&self) -> Option<(String, &'static str)> {
+        if let Some((field, message)) = self.topology.invalid() {
+            return Some((format!("topology.{}", field), message));
+        }
+        (!(0.0..=1.0).contains(&self.virality_threshold))
+            .then(|| ("virality_threshold".to_string(), "must be between 0 and 1"))
+    }
+}
+
+/// An undirected engagement graph and the topology it was built from. Nodes are
+/// `0..node_count()`.
+#[derive(Debug, Clone)]
+pub struct Graph {
+    topology: Topology,
+    graph: UnGraph<(), ()>,
+}
+
+impl Graph {
+    /// The same topology, node count and seed always build the same graph.
+    pub fn build(topology: Topology, nodes: usize, seed: u64) -> Self {
+        Self::build_with(topology, nodes, &mut SplitMix64(seed))
+    }
+
+    pub(crate) fn build_with(topology: Topology, nodes: usize, rng: &mut SplitMix64) -> Self {
+        let mut graph = UnGraph::with_capacity(nodes, 0);
+        let indices: Vec<NodeIndex> = (0..nodes).map(|_| graph.add_node(())).collect();
+        match topology {
+            Topology::ErdosRenyi { mean_degree } => {
+                for &a in &indices {
+                    for _ in 0..(mean_degree / 2).max(1) {
+                        let b = indices[rng.below(nodes)];
+                        if a != b {
+                            graph.update_edge(a, b, ());
+                        }
+                    }
+                }
+            }
+            Topology::BarabasiAlbert { attachments } => {
+                let seeds = (attachments + 1).min(nodes);
+                // Every edge's two endpoints, so a uniform pick is degree-weighted.
+                let mut endpoints = vec![];
+                for a in 0..seeds {
+                    for b in a + 1..seeds {
+                        graph.update_edge(indices[a], indices[b], ());
+                        endpoints.extend([a, b]);
+                    }
+                }
+                for node in seeds..nodes {
+                    let mut targets = BTreeSet::new();
+                    while targets.len() < attachments.min(node) {
+                        targets.insert(if endpoints.is_empty() { rng.below(node) } else { endpoints[rng.below(endpoints.len())] });
+                    }
+                    for target in targets {
+                        graph.update_edge(indices[node], indices[target], ());
+                        endpoints.extend([node, target]);
+                    }
+                }
+            }
+            Topology::SmallWorld { mean_degree, rewire } => {
+                let half = (mean_degree / 2).max(1).min(nodes.saturating_sub(1) / 2);
+                let mut edges = BTreeSet::new();
+                for a in 0..nodes {
+                    for step in 1..=half {
+                        edges.insert(ordered(a, (a + step) % nodes));
+                    }
+                }
+                for a in 0..nodes {
+                    for step in 1..=half {
+                        let b = (a + step) % nodes;
+                        if rng.next_f64() >= rewire || !edges.contains(&ordered(a, b)) {
+                            continue;
+                        }
+                        // Keeps the link when `a` already reaches every other node.
+                        let free = nodes - 1 - edges.iter().filter(|&&(x, y)| x == a || y == a).count();
+                        if free == 0 {
+                            continue;
+                        }
+                        let target = loop {
+                            let c = rng.below(nodes);
+                            if c != a && !edges.contains(&ordered(a, c)) {
+                                break c;
+                            }
+                        };
+                        edges.remove(&ordered(a, b));
+                        edges.insert(ordered(a, target));
+                    }
+                }
+                for (a, b) in edges {
+                    graph.add_edge(indices[a], indices[b], ());
+                }
+            }
+        }
+        Self { topology, graph }
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.graph.node_count()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.graph.edge_count()
+    }
+
+    pub fn degree(&self, node: usize) -> usize {
+        self.graph.neighbors(NodeIndex::new(node)).count()
+    }
+
+    pub fn neighbors(&self, node: usize) -> impl Iterator<Item = usize> + '_ {
+        self.graph.neighbors(NodeIndex::new(node)).map(|index| index.index())
+    }
+
+    /// Each edge once, as `(a, b)` with `a < b`, sorted.
+    pub fn edges(&self) -> Vec<(usize, usize)> {
+        let mut edges: Vec<(usize, usize)> =
+            self.graph.edge_indices().filter_map(|edge| self.graph.edge_endpoints(edge)).map(|(a, b)| ordered(a.index(), b.index())).collect();
+        edges.sort_unstable();
+        edges
+    }
+}
+
+/// Graphs are equal when they have the same topology, nodes and edges.
+impl PartialEq for Graph {
+    fn eq(&self, other: &Self) -> bool {
+        self.topology == other.topology && self.node_count() == other.node_count() && self.edges() == other.edges()
+    }
+}
+
+fn ordered(a: usize, b: usize) -> (usize, usize) {
+    (a.min(b), a.max(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CognitiveOrchestrator, ViralPropagator};
+    use proptest::prelude::*;
+
+    const TOPOLOGIES: [Topology; 3] = [
+        Topology::ErdosRenyi { mean_degree: 4 },
+        Topology::BarabasiAlbert { attachments: 2 },
+        Topology::SmallWorld { mean_degree: 4, rewire: 0.1 },
+    ];
+
+    #[test]
+    fn topologies_have_their_shape() {
+        for topology in TOPOLOGIES {
+            let graph = Graph::build(topology, 200, 5);
+            assert_eq!(graph.node_count(), 200);
+            assert_eq!(graph.edges(), Graph::build(topology, 200, 5).edges(), "{:?}", topology);
+            assert!(graph.edges().iter().all(|&(a, b)| a < b && b < 200));
+        }
+
+        let ba = Graph::build(Topology::BarabasiAlbert { attachments: 2 }, 200, 5);
+        // Three seed edges, then two per later node.
+        assert_eq!(ba.edge_count(), 3 + 2 * 197);
+        let hub = (0..200).map(|node| ba.degree(node)).max().unwrap();
+        assert!(hub >= 15, "{}", hub);
+
+        // Rewiring moves links but keeps their number.
+        let ring = Graph::build(Topology::SmallWorld { mean_degree: 4, rewire: 0.0 }, 20, 1);
+        assert!((0..20).all(|node| ring.degree(node) == 4));
+        assert_eq!(Graph::build(Topology::SmallWorld { mean_degree: 4, rewire: 0.5 }, 20, 1).edge_count(), 40);
+        assert_eq!(Graph::build(Topology::BarabasiAlbert { attachments: 3 }, 2, 0).edge_count(), 1);
+        assert_eq!(Graph::build(Topology::default(), 0, 0).node_count(), 0);
+    }
+
+    /// Mean reach over many cascades on one graph, for each hook rate.
+    fn expected_reach(graph: &Graph, hook_rates: &[f64]) -> Vec<f64> {
+        let propagator = ViralPropagator::new();
+        hook_rates
+            .iter()
+            .map(|&hook_rate| {
+                let total: f64 = (0..200).map(|seed| propagator.simulate_graph(graph, hook_rate, 10, seed).virality_score).sum();
+                total / 200.0
+            })
+            .collect()
+    }
+
+    fn topology() -> impl Strategy<Value = Topology> {
+        prop_oneof![
+            (1usize..=8).prop_map(|mean_degree| Topology::ErdosRenyi { mean_degree }),
+            (1usize..=4).prop_map(|attachments| Topology::BarabasiAlbert { attachments }),
+            (1usize..=4, 0.0..=1.0).prop_map(|(half, rewire)| Topology::SmallWorld { mean_degree: 2 * half, rewire }),
+        ]
+    }
+
+    /// A pair `h1 <= h2`. Cascades at the two rates share seeds but not their coin
+    /// flips, so the rates are kept far enough apart that the rise in reach
+    /// outweighs the sampling noise of `expected_reach`.
+    fn hook_rates() -> impl Strategy<Value = (f64, f64)> {
+        (0.0..=0.9, 0.1..=1.0).prop_map(|(h1, gap): (f64, f64)| (h1, (h1 + gap).min(1.0)))
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(48))]
+
+        #[test]
+        fn larger_hook_rates_never_decrease_expected_reach(
+            topology in topology(),
+            nodes in 16usize..=128,
+            graph_seed in any::<u64>(),
+            (h1, h2) in hook_rates(),
+        ) {
+            let graph = Graph::build(topology, nodes, graph_seed);
+            let reach = expected_reach(&graph, &[h1, h2]);
+            prop_assert!(reach[0] <= reach[1], "{:?} on {} nodes: {:?} at {} and {}", topology, nodes, reach, h1, h2);
+            prop_assert!(reach[0] > 0.0 && reach[1] <= 1.0);
+        }
+    }
+
+    #[test]
+    fn invalid_parameters_are_named() {
+        assert_eq!(Topology::BarabasiAlbert { attachments: 0 }.invalid(), Some(("attachments", "must be at least 1")));
+        assert_eq!(Topology::SmallWorld { mean_degree: 4, rewire: 1.5 }.invalid().map(|(key, _)| key), Some("rewire"));
+        assert_eq!(Topology::default().invalid(), None);
+        let config: ViralConfig = serde_json::from_str(r#"{"topology": {"kind": "small_world", "mean_degree": 6, "rewire": 0.2}}"#).unwrap();
+        assert_eq!(config.topology, Topology::SmallWorld { mean_degree: 6, rewire: 0.2 });
+        assert_eq!(config.virality_threshold, DEFAULT_VIRALITY_THRESHOLD);
+        let config = ViralConfig { virality_threshold: 1.5, ..ViralConfig::default() };
+        assert_eq!(config.invalid(), Some(("virality_threshold".to_string(), "must be between 0 and 1")));
+    }
+
+    #[test]
+    fn context_thresholds_beat_the_default_for_later_dispatches() {
+        let orch = CognitiveOrchestrator::builder().prefer_native(true).seed(7).virality_threshold(1.0).learning(false).build().unwrap();
+        let threshold = |result: &crate::AgentResult| (result.metadata["virality_threshold"].as_f64().unwrap(), result.status);
+
+        let result = orch.dispatch("viral:simulate".to_string(), "ctx1");
+        assert_eq!(threshold(&result), (1.0, false));
+        let margin = result.metadata["virality_margin"].as_f64().unwrap();
+        assert!((margin - (result.metadata["virality"].as_f64().unwrap() - 1.0)).abs() < 1e-12);
+
+        let own = ViralConfig { topology: Topology::BarabasiAlbert { attachments: 2 }, virality_threshold: 0.0 };
+        orch.set_viral_config("ctx1", own);
+        let result = orch.dispatch("viral:simulate".to_string(), "ctx1");
+        assert_eq!(threshold(&result), (0.0, true));
+        assert_eq!(result.metadata["topology"]["kind"], "barabasi_albert");
+        assert_eq!(orch.viral_config("ctx1").unwrap(), own);
+        // Other contexts keep the orchestrator's.
+        assert_eq!(threshold(&orch.dispatch("viral:simulate".to_string(), "ctx2")), (1.0, false));
+
+        orch.clear_viral_config("ctx1");
+        assert_eq!(orch.viral_config("ctx1").unwrap().virality_threshold, 1.0);
+        assert_eq!(threshold(&orch.dispatch("viral:simulate".to_string(), "ctx1")).0, 1.0);
+    }
+}
@@ -0,0 +1,332 @@
+use crate::viral::SplitMix64;
+use crate::ViralMetrics;
+use faer::linalg::matmul::matmul;
+use faer::Mat;
+use pyo3::prelude::*;
+use roqoqo::operations::{
+    OperateSingleQubit, OperateTwoQubit, Operation, PragmaDephasing, PragmaDepolarising, RotateY, CNOT,
+};
+use roqoqo::Circuit;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::FRAC_PI_4;
+
+/// Widest register `estimate_fidelity` simulates; wider amplifiers are clamped to it.
+pub const MAX_SIMULATED_QUBITS: usize = 12;
+
+#[pyclass(module = "sovereign_cli", get_all)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AmplificationResult {
+    /// Geometric mean of the per-round growth in Frobenius norm; approaches the
+    /// state's spectral radius as rounds increase.
+    pub amplification_factor: f64,
+    /// Overlap of the last two normalized iterates, in `[0, 1]`; 1 means the
+    /// amplified mode has stabilized.
+    pub fidelity: f64,
+}
+
+#[pymethods]
+impl AmplificationResult {
+    fn __repr__(&self) -> String {
+        format!(
+            "AmplificationResult(amplification_factor={:.6}, fidelity={:.6})",
+            self.amplification_factor, self.fidelity
+        )
+    }
+}
+
+/// Frobenius inner product.
+fn overlap(a: &Mat<f64>, b: &Mat<f64>) -> f64 {
+    (0..a.ncols())
+        .map(|j| a.col_as_slice(j).iter().zip(b.col_as_slice(j)).map(|(x, y)| x * y).sum::<f64>())
+        .sum()
+}
+
+/// Multiplies every entry by `factor`, a contiguous column at a time.
+fn scale_in_place(m: &mut Mat<f64>, factor: f64) {
+    for j in 0..m.ncols() {
+        m.col_as_slice_mut(j).iter_mut().for_each(|x| *x *= factor);
+    }
+}
+
+/// Per-gate noise, as rates over a unit gate time, attached to every qubit a
+/// gate touches.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NoiseModel {
+    /// The qubit is fully depolarized with probability `1 - exp(-depolarizing)`.
+    pub depolarizing: f64,
+    /// Coherences decay by `exp(-2 * dephasing)`.
+    pub dephasing: f64,
+}
+
+impl Default for NoiseModel {
+    fn default() -> Self {
+        Self { depolarizing: 1e-4, dephasing: 1e-4 }
+    }
+}
+
+impl NoiseModel {
+    pub fn noiseless() -> Self {
+        Self { depolarizing: 0.0, dephasing: 0.0 }
+    }
+}
+
+/// Real statevector over `qubits`; qubit `q` is bit `q` of the amplitude index.
+/// The circuits below only need `RotateY`, `CNOT` and Pauli errors, which are all
+/// real up to a global phase that fidelity ignores.
+struct Statevector(Vec<f64>);
+
+impl Statevector {
+    fn zero(qubits: usize) -> Self {
+        let mut amplitudes = vec![0.0; 1 << qubits];
+        amplitudes[0] = 1.0;
+        Self(amplitudes)
+    }
+
+    fn rotate_y(&mut self, qubit: usize, theta: f64) {
+        let (sin, cos) = (theta / 2.0).sin_cos();
+        let bit = 1 << qubit;
+        for i in (0..self.0.len()).filter(|i| i & bit == 0) {
+            let (a, b) = (self.0[i], self.0[i | bit]);
+            self.0[i] = cos * a - sin * b;
+            self.0[i | bit] = sin * a + cos * b;
+        }
+    }
+
+    fn cnot(&mut self, control: usize, target: usize) {
+        let (control, target) = (1 << control, 1 << target);
+        for i in (0..self.0.len()).filter(|i| i & control != 0 && i & target == 0) {
+            self.0.swap(i, i | target);
+        }
+    }
+
+    fn pauli_x(&mut self, qubit: usize) {
+        let bit = 1 << qubit;
+        for i in (0..self.0.len()).filter(|i| i & bit == 0) {
+            self.0.swap(i, i | bit);
+        }
+    }
+
+    fn pauli_z(&mut self, qubit: usize) {
+        let bit = 1 << qubit;
+        for (i, amplitude) in self.0.iter_mut().enumerate() {
+            if i & bit != 0 {
+                *amplitude = -*amplitude;
+            }
+        }
+    }
+
+    fn fidelity(&self, ideal: &Statevector) -> f64 {
+        let overlap: f64 = self.0.iter().zip(&ideal.0).map(|(a, b)| a * b).sum();
+        (overlap * overlap).min(1.0)
+    }
+}
+
+/// Runs `circuit` once, sampling each noise pragma as a Pauli error from `rng`;
+/// without an `rng` the pragmas are skipped and the run is ideal. Other
+/// operations are ignored.
+fn run_trajectory(circuit: &Circuit, qubits: usize, mut rng: Option<&mut SplitMix64>) -> Statevector {
+    let mut state = Statevector::zero(qubits);
+    for op in circuit.iter() {
+        match op {
+            Operation::RotateY(op) => state.rotate_y(*op.qubit(), op.theta().float().copied().unwrap_or(0.0)),
+            Operation::CNOT(op) => state.cnot(*op.control(), *op.target()),
+            Operation::PragmaDepolarising(op) => {
+                if let Some(rng) = rng.as_deref_mut() {
+                    let exposure = op.rate().float().copied().unwrap_or(0.0) * op.gate_time().float().copied().unwrap_or(0.0);
+                    let p = 1.0 - (-exposure).exp();
+                    // Full depolarization applies I, X, Y or Z uniformly; Y is X·Z up to phase.
+                    if rng.next_f64() < p {
+                        match rng.below(4) {
+                            1 => state.pauli_x(*op.qubit()),
+                            2 => {
+                                state.pauli_z(*op.qubit());
+                                state.pauli_x(*op.qubit());
+                            }
+                            3 => state.pauli_z(*op.qubit()),
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            Operation::PragmaDephasing(op) => {
+                if let Some(rng) = rng.as_deref_mut() {
+                    let exposure = op.rate().float().copied().unwrap_or(0.0) * op.gate_time().float().copied().unwrap_or(0.0);
+                    let p = (1.0 - (-2.0 * exposure).exp()) / 2.0;
+                    if rng.next_f64() < p {
+                        state.pauli_z(*op.qubit());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    state
+}
+
+/// Repeated application of a square state matrix, renormalizing each round.
+#[derive(Debug, Clone)]
+pub struct QuantumAmplifier {
+    /// Rounds used when amplifying a context's viral metrics.
+    pub rounds: usize,
+    /// Register width of the circuit `estimate_fidelity` simulates.
+    pub qubits: usize,
+    /// Noise `amplify_metrics` estimates `quantum_fidelity` under.
+    pub noise: NoiseModel,
+    /// Sampled noise trajectories averaged per estimate.
+    pub trajectories: usize,
+}
+
+impl Default for QuantumAmplifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl QuantumAmplifier {
+    pub fn new() -> Self {
+        Self { rounds: 8, qubits: 4, noise: NoiseModel::default(), trajectories: 1024 }
+    }
+
+    pub fn with_noise(mut self, noise: NoiseModel) -> Self {
+        self.noise = noise;
+        self
+    }
+
+    /// Each round rotates every qubit by `RotateY(π/4)` and entangles neighbours
+    /// with a `CNOT` chain; every gate is followed by `noise` pragmas on the
+    /// qubits it touched. Zero rates emit no pragmas.
+    pub fn circuit(&self, rounds: usize, noise: NoiseModel) -> Circuit {
+        let qubits = self.qubits.clamp(1, MAX_SIMULATED_QUBITS);
+        let mut circuit = Circuit::new();
+        let noisy = |circuit: &mut Circuit, qubit: usize| {
+            if noise.depolarizing > 0.0 {
+                *circuit += PragmaDepolarising::new(qubit, 1.0.into(), noise.depolarizing.into());
+            }
+            if noise.dephasing > 0.0 {
+                *circuit += PragmaDephasing::new(qubit, 1.0.into(), noise.dephasing.into());
+            }
+        };
+        for _ in 0..rounds {
+            for q in 0..qubits {
+                circuit += RotateY::new(q, FRAC_PI_4.into());
+                noisy(&mut circuit, q);
+            }
+            for q in 1..qubits {
+                circuit += CNOT::new(q - 1, q);
+                noisy(&mut circuit, q - 1);
+                noisy(&mut circuit, q);
+            }
+        }
+        circuit
+    }
+
+    /// Mean fidelity of `rounds` of the amplification `circuit` under `noise`
+    /// against its noiseless state, over `trajectories` sampled runs on the
+    /// built-in statevector simulator. The same inputs always give the same
+    /// estimate; without noise it is 1.
+    pub fn estimate_fidelity(&self, rounds: usize, noise: NoiseModel) -> f64 {
+        let qubits = self.qubits.clamp(1, MAX_SIMULATED_QUBITS);
+        let circuit = self.circuit(rounds, noise);
+        let ideal = run_trajectory(&circuit, qubits, None);
+        if !circuit.iter().any(|op| matches!(op, Operation::PragmaDepolarising(_) | Operation::PragmaDephasing(_))) {
+            return run_trajectory(&circuit, qubits, Some(&mut SplitMix64(0))).fidelity(&ideal);
+        }
+        let trajectories = self.trajectories.max(1);
+        let mut rng = SplitMix64(0);
+        let total: f64 = (0..trajectories).map(|_| run_trajectory(&circuit, qubits, Some(&mut rng)).fidelity(&ideal)).sum();
+        total / trajectories as f64
+    }
+
+    /// Starting from the normalized identity, multiplies by `state` and
+    /// renormalizes `rounds` times. A non-square or empty state, or zero rounds,
+    /// leaves nothing amplified (factor 1, fidelity 1); a state that collapses
+    /// to zero reports factor 0, fidelity 0.
+    pub fn amplify(&self, state: &Mat<f64>, rounds: usize) -> AmplificationResult {
+        let n = state.nrows();
+        if n == 0 || n != state.ncols() || rounds == 0 {
+            return AmplificationResult { amplification_factor: 1.0, fidelity: 1.0 };
+        }
+
+        let mut current = Mat::<f64>::identity(n, n);
+        scale_in_place(&mut current, 1.0 / (n as f64).sqrt());
+        // The three iterates take turns in the same buffers, each round one
+        // blocked matmul written over the oldest.
+        let (mut previous, mut next) = (Mat::<f64>::zeros(n, n), Mat::<f64>::zeros(n, n));
+        let mut log_growth = 0.0;
+        for _ in 0..rounds {
+            matmul(next.as_mut(), state.as_ref(), current.as_ref(), None, 1.0, faer::get_global_parallelism());
+            let norm = next.norm_l2();
+            if norm == 0.0 {
+                return AmplificationResult { amplification_factor: 0.0, fidelity: 0.0 };
+            }
+            log_growth += norm.ln();
+            scale_in_place(&mut next, 1.0 / norm);
+            std::mem::swap(&mut previous, &mut current);
+            std::mem::swap(&mut current, &mut next);
+        }
+
+        AmplificationResult {
+            amplification_factor: (log_growth / rounds as f64).exp(),
+            fidelity: overlap(&current, &previous).abs().min(1.0),
+        }
+    }
+
+    /// Two-mode (reached / unreached) mixing state: virality splits the diagonal
+    /// and the hook rate couples the modes, so the spectral radius is
+    /// `1 + sqrt(virality² + hook_rate²)`.
+    pub fn state_for(metrics: &ViralMetrics) -> Mat<f64> {
+        let r = metrics.virality_score;
+        let h = metrics.hook_rate;
+        Mat::from_fn(2, 2, |i, j| match (i, j) {
+            (0, 0) => 1.0 + r,
+            (1, 1) => 1.0 - r,
+            _ => h,
+        })
+    }
+
+    /// Recomputes `amplification_factor` from the current metrics, and
+    /// `quantum_fidelity` as the estimated fidelity of `rounds` under `noise`.
+    pub fn amplify_metrics(&self, metrics: &mut ViralMetrics) -> AmplificationResult {
+        let result = self.amplify(&Self::state_for(metrics), self.rounds);
+        metrics.amplification_factor = result.amplification_factor;
+        metrics.quantum_fidelity = self.estimate_fidelity(self.rounds, self.noise);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn noiseless_circuits_keep_full_fidelity() {
+        for qubits in [1, 4, MAX_SIMULATED_QUBITS, 20] {
+            let amplifier = QuantumAmplifier { qubits, ..QuantumAmplifier::new() };
+            for rounds in [0, 1, 8] {
+                let fidelity = amplifier.estimate_fidelity(rounds, NoiseModel::noiseless());
+                assert!((fidelity - 1.0).abs() < 1e-12, "{} qubits, {} rounds: {}", qubits, rounds, fidelity);
+            }
+        }
+        // Gates only, no noise pragmas.
+        assert_eq!(QuantumAmplifier::new().circuit(3, NoiseModel::noiseless()).len(), 3 * (4 + 3));
+    }
+
+    #[test]
+    fn noise_costs_fidelity_per_round() {
+        let amplifier = QuantumAmplifier::new();
+        let noise = NoiseModel { depolarizing: 0.01, dephasing: 0.01 };
+        // Four rotations and three CNOTs a round, each followed by both pragmas per qubit.
+        assert_eq!(amplifier.circuit(2, noise).len(), 2 * (4 * 3 + 3 * 5));
+
+        let fidelities: Vec<f64> = [1, 4, 16].iter().map(|&rounds| amplifier.estimate_fidelity(rounds, noise)).collect();
+        assert!(fidelities.windows(2).all(|pair| pair[0] > pair[1]), "{:?}", fidelities);
+        assert!(fidelities.iter().all(|fidelity| (0.0..1.0).contains(fidelity)));
+        assert_eq!(amplifier.estimate_fidelity(4, noise), fidelities[1]);
+
+        let mut metrics = ViralMetrics::default();
+        amplifier.amplify_metrics(&mut metrics);
+        assert_eq!(metrics.quantum_fidelity, amplifier.estimate_fidelity(amplifier.rounds, NoiseModel::default()));
+        assert!(metrics.quantum_fidelity > 0.95 && metrics.quantum_fidelity < 1.0);
+    }
+}
@@ -0,0 +1,143 @@
+use crate::{Agent, AgentKind, Clock};
+use chrono::{DateTime, Utc};
+use prometheus::HistogramVec;
+use pyo3::prelude::*;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tracing::debug;
+
+/// A token bucket: `rate` calls per second on average, up to `burst` at once after
+/// a quiet spell.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub rate: f64,
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// `None` unless `rate` is positive and finite; `burst` is at least 1.
+    pub fn new(rate: f64, burst: u32) -> Option<Self> {
+        (rate.is_finite() && rate > 0.0).then_some(Self { rate, burst: burst.max(1) })
+    }
+}
+
+struct Bucket {
+    limit: RateLimit,
+    /// Goes negative while callers are queued for tokens not yet refilled.
+    tokens: f64,
+    updated: DateTime<Utc>,
+}
+
+impl Bucket {
+    fn new(limit: RateLimit, now: DateTime<Utc>) -> Self {
+        Self { limit, tokens: f64::from(limit.burst), updated: now }
+    }
+
+    /// Takes a token and returns how long the caller must wait for it.
+    fn reserve(&mut self, now: DateTime<Utc>) -> Duration {
+        let elapsed = (now - self.updated).to_std().map_or(0.0, |elapsed| elapsed.as_secs_f64());
+        self.tokens = (self.tokens + elapsed * self.limit.rate).min(f64::from(self.limit.burst));
+        self.updated = self.updated.max(now);
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.limit.rate)
+        }
+    }
+}
+
+/// Per-`AgentKind` limits on calls out to Python agents, applied by dispatch
+/// before the agent runs; kinds without a limit are not throttled. Each wait is
+/// observed in `sovereign_rate_limit_wait_seconds`. Clones share the buckets.
+#[derive(Clone)]
+pub struct RateLimits {
+    buckets: Arc<RwLock<HashMap<AgentKind, Arc<Mutex<Bucket>>>>>,
+    clock: Arc<dyn Clock>,
+    waits: HistogramVec,
+}
+
+impl RateLimits {
+    pub(crate) fn new(clock: Arc<dyn Clock>, waits: HistogramVec) -> Self {
+        Self { buckets: Arc::default(), clock, waits }
+    }
+
+    /// Replaces the limit for `kind`, starting with a full bucket; `None` removes it.
+    pub fn set(&self, kind: AgentKind, limit: Option<RateLimit>) {
+        let mut buckets = self.buckets.write().unwrap_or_else(|e| e.into_inner());
+        match limit {
+            Some(limit) => buckets.insert(kind, Arc::new(Mutex::new(Bucket::new(limit, self.clock.now())))),
+            None => buckets.remove(&kind),
+        };
+    }
+
+    pub fn get(&self, kind: AgentKind) -> Option<RateLimit> {
+        let buckets = self.buckets.read().unwrap_or_else(|e| e.into_inner());
+        buckets.get(&kind).map(|bucket| bucket.lock().unwrap_or_else(|e| e.into_inner()).limit)
+    }
+
+    /// Waits, without the GIL, until `agent` may call out to its Python agent kind.
+    pub(crate) fn throttle(&self, agent: &dyn Agent) {
+        let Some(kind) = agent.kind() else { return };
+        let bucket = self.buckets.read().unwrap_or_else(|e| e.into_inner()).get(&kind).cloned();
+        let Some(bucket) = bucket else { return };
+        let wait = bucket.lock().unwrap_or_else(|e| e.into_inner()).reserve(self.clock.now());
+        self.waits.with_label_values(&[kind.name()]).observe(wait.as_secs_f64());
+        if !wait.is_zero() {
+            debug!(kind = kind.name(), wait_ms = wait.as_secs_f64() * 1000.0, "rate limited");
+            Python::with_gil(|py| py.allow_threads(|| self.clock.sleep(wait)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::RATE_LIMIT_WAIT_SECONDS;
+    use crate::{CognitiveOrchestrator, FixedClock, MockBackend};
+
+    /// An orchestrator on a mock clock whose four-step "ask" plan calls the LLM
+    /// each step, and the clock's seconds since the epoch at each call.
+    fn orchestrator() -> (CognitiveOrchestrator, Arc<Mutex<Vec<f64>>>) {
+        let clock = Arc::new(FixedClock::new(DateTime::UNIX_EPOCH));
+        let calls = Arc::new(Mutex::new(vec![]));
+        let (seen, now) = (calls.clone(), clock.clone());
+        let mock = MockBackend::new().plan("ask", ["query llm a", "query llm b", "query llm c", "query llm d"]).generator(
+            move |prompt| {
+                seen.lock().unwrap().push((now.now() - DateTime::UNIX_EPOCH).num_milliseconds() as f64 / 1000.0);
+                Ok(prompt.to_string())
+            },
+        );
+        (CognitiveOrchestrator::builder().backend(Arc::new(mock)).clock(clock).build().unwrap(), calls)
+    }
+
+    #[test]
+    fn calls_are_spaced_by_the_rate_after_the_burst() {
+        let (orch, calls) = orchestrator();
+        let orch = orch.with_rate_limit(AgentKind::Llm, 2.0, 2);
+        orch.process("ask".to_string(), "ctx1");
+        assert_eq!(calls.lock().unwrap().as_slice(), [0.0, 0.0, 0.5, 1.0]);
+
+        let waits = orch
+            .metrics_registry()
+            .gather()
+            .into_iter()
+            .find(|family| family.get_name() == RATE_LIMIT_WAIT_SECONDS)
+            .unwrap();
+        let histogram = waits.get_metric()[0].get_histogram();
+        assert_eq!(histogram.get_sample_count(), 4);
+        assert!((histogram.get_sample_sum() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn unlimited_by_default_and_per_kind() {
+        let (orch, calls) = orchestrator();
+        let orch = orch.with_rate_limit(AgentKind::Viral, 1.0, 1);
+        assert_eq!(orch.rate_limit(AgentKind::Llm), None);
+        orch.process("ask".to_string(), "ctx1");
+        assert_eq!(calls.lock().unwrap().as_slice(), [0.0; 4]);
+        assert_eq!(RateLimit::new(0.0, 3), None);
+        assert_eq!(RateLimit::new(5.0, 0).map(|limit| limit.burst), Some(1));
+    }
+}
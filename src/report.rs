@@ -0,0 +1,157 @@
+use crate::{AgentResult, Blackboard, DebugDecision, PlanFinding, RunDiff, RunTiming, ViralMetrics};
+use chrono::{DateTime, Utc};
+use pyo3::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Everything one `process` run did, as `process_report` returns it.
+#[pyclass(module = "sovereign_cli", get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ProcessReport {
+    pub context_id: String,
+    pub tenant: String,
+    pub command: String,
+    /// Subtasks as first planned, in execution order; re-planned ones are in `results`.
+    pub plan: Vec<String>,
+    /// One per dispatched subtask, re-planned ones included, in dispatch order.
+    pub results: Vec<AgentResult>,
+    /// The subtask each of `results` ran, by index.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub subtasks: Vec<String>,
+    pub replans: usize,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    /// Whether the run completed its plan with no failure that re-planning did not
+    /// replace.
+    pub success: bool,
+    /// Set when `cancel` stopped the run before its plan was done.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cancelled: bool,
+    /// Why the run dispatched nothing, such as a rejected plan, as `process` reports it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// How `self_debug` classified each failed subtask and what it did about it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub debug: Vec<DebugDecision>,
+    /// What the plan validator dropped from, or flagged in, the planner's answer.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub plan_findings: Vec<PlanFinding>,
+    /// What the run's steps wrote for later ones, such as `step_1.output`.
+    #[serde(default, skip_serializing_if = "Blackboard::is_empty")]
+    pub blackboard: Blackboard,
+    /// The run's wall time with what its results spent, from their `_timing.*` metadata.
+    #[serde(default)]
+    pub timing: RunTiming,
+    /// The context's viral metrics as the run completed; `None` for a run refused
+    /// before planning.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metrics: Option<ViralMetrics>,
+    /// How the run differs from the context's previous run of the same command,
+    /// with auto-diff on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff: Option<RunDiff>,
+    /// The id the run's events, results and journal entries carry; `None` only
+    /// in reports saved before runs had ids.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    /// How many sensitive strings were redacted from the report.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub redactions: usize,
+    /// Set when this is the report of an identical run in flight or just finished,
+    /// which `coalesce_window` returned instead of dispatching again.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub coalesced: bool,
+}
+
+fn is_zero(count: &usize) -> bool {
+    *count == 0
+}
+
+impl ProcessReport {
+    /// The strings of the JSON array `process` returns in its legacy format.
+    pub fn outputs(&self) -> Vec<String> {
+        self.error.iter().cloned().chain(self.results.iter().map(|result| result.output.clone())).collect()
+    }
+
+    /// The JSON array `process` returns in its legacy format.
+    pub fn legacy_output(&self) -> String {
+        let outputs = self.outputs();
+        serde_json::to_string(&outputs).unwrap_or_else(|_| outputs.join("\n"))
+    }
+}
+
+#[pymethods]
+impl ProcessReport {
+    #[pyo3(name = "outputs")]
+    fn py_outputs(&self) -> Vec<String> {
+        self.outputs()
+    }
+
+    #[pyo3(name = "to_json")]
+    fn py_to_json(&self) -> PyResult<String> {
+        serde_json::to_string(self).map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "ProcessReport(context_id={:?}, subtasks={}, replans={}, success={}, cancelled={})",
+            self.context_id,
+            self.results.len(),
+            self.replans,
+            self.success,
+            self.cancelled
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CognitiveOrchestrator, FixedClock, MockBackend};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    fn result(output: &str, status: bool) -> AgentResult {
+        AgentResult { output: output.to_string(), status, metadata: HashMap::new(), error: None }
+    }
+
+    #[test]
+    fn reports_results_replans_and_timing() {
+        let clock = Arc::new(FixedClock::new(DateTime::UNIX_EPOCH));
+        let mock = MockBackend::new()
+            .plan("launch", ["post teaser", "post launch"])
+            .on("post teaser", |_| result("low virality on teaser", false))
+            .on("post launch", |_| result("launched", true))
+            .on("post alt", |_| result("alt posted", true))
+            .replan(["post alt"]);
+        let orch = CognitiveOrchestrator::builder().backend(Arc::new(mock)).clock(clock).build().unwrap();
+        let report = orch.process_report("launch campaign".to_string(), "ctx1");
+        assert_eq!(report.plan, ["post teaser", "post launch"]);
+        assert_eq!(report.outputs(), ["low virality on teaser", "alt posted", "launched"]);
+        assert_eq!(report.results[1].metadata["replanned_from"], "post teaser");
+        assert_eq!((report.replans, report.success), (1, true));
+        assert_eq!((report.started_at, report.finished_at), (DateTime::UNIX_EPOCH, DateTime::UNIX_EPOCH));
+        assert_eq!((report.context_id.as_str(), report.tenant.as_str()), ("ctx1", crate::DEFAULT_TENANT));
+
+        // `process` keeps returning the bare array unless told otherwise.
+        let legacy = orch.process("launch campaign".to_string(), "ctx1");
+        assert_eq!(legacy, report.legacy_output());
+        let orch = orch.with_legacy_output(false);
+        let full: ProcessReport = serde_json::from_str(&orch.process("launch campaign".to_string(), "ctx1")).unwrap();
+        // Each run's results differ only in their timings.
+        assert_eq!(full.outputs(), report.outputs());
+        assert_eq!(full.results[1].metadata["replanned_from"], "post teaser");
+    }
+
+    #[test]
+    fn a_rejected_plan_is_unsuccessful_with_its_error() {
+        let mock = MockBackend::new()
+            .planner(|_| Err(crate::OrchestratorError::PlanCycle { subtasks: vec!["step x".to_string(), "step y".to_string()] }));
+        let orch = CognitiveOrchestrator::builder().backend(Arc::new(mock)).build().unwrap();
+        let report = orch.process_report("plan it".to_string(), "ctx1");
+        assert!(!report.success);
+        assert!(report.plan.is_empty() && report.results.is_empty());
+        assert_eq!(report.error.as_deref(), Some("Plan Error: plan has a dependency cycle through: step x, step y"));
+        assert_eq!(orch.process("plan it".to_string(), "ctx1"), report.legacy_output());
+    }
+}
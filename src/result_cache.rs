@@ -0,0 +1,273 @@
+use crate::{Agent, AgentKind, AgentResult, Clock, Context};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Kinds whose results are never cached unless `ResultCache::set_excluded` says
+/// otherwise: viral simulations read the context's mutable metrics.
+pub const DEFAULT_CACHE_EXCLUDED: [AgentKind; 1] = [AgentKind::Viral];
+
+/// Lowercased, with runs of whitespace collapsed to one space, so trivially
+/// different spellings of a subtask share a cache entry.
+pub fn normalize_subtask(sub_task: &str) -> String {
+    sub_task.split_whitespace().map(str::to_lowercase).collect::<Vec<_>>().join(" ")
+}
+
+/// Counters since the cache was configured or last cleared.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Entries dropped for capacity or because their TTL ran out.
+    pub evictions: u64,
+    pub entries: usize,
+}
+
+/// Tenants never share entries, even for identical subtasks.
+type CacheKey = (String, AgentKind, String);
+
+struct Entry {
+    result: AgentResult,
+    stored_at: DateTime<Utc>,
+    /// Position in `Lru::order`.
+    used: u64,
+}
+
+struct Lru {
+    capacity: usize,
+    ttl: Option<Duration>,
+    excluded: HashSet<AgentKind>,
+    entries: HashMap<CacheKey, Entry>,
+    /// Keys by last use, least recent first.
+    order: BTreeMap<u64, CacheKey>,
+    tick: u64,
+    stats: CacheStats,
+}
+
+impl Lru {
+    fn expired(&self, entry: &Entry, now: DateTime<Utc>) -> bool {
+        self.ttl.is_some_and(|ttl| (now - entry.stored_at).to_std().is_ok_and(|age| age >= ttl))
+    }
+
+    fn remove(&mut self, key: &CacheKey) -> Option<Entry> {
+        let entry = self.entries.remove(key)?;
+        self.order.remove(&entry.used);
+        Some(entry)
+    }
+
+    fn touch(&mut self, key: &CacheKey) {
+        self.tick += 1;
+        if let Some(entry) = self.entries.get_mut(key) {
+            self.order.remove(&entry.used);
+            entry.used = self.tick;
+            self.order.insert(self.tick, key.clone());
+        }
+    }
+
+    fn get(&mut self, key: &CacheKey, now: DateTime<Utc>) -> Option<AgentResult> {
+        let expired = self.entries.get(key).map(|entry| self.expired(entry, now));
+        match expired {
+            Some(false) => {
+                self.touch(key);
+                self.stats.hits += 1;
+                self.entries.get(key).map(|entry| entry.result.clone())
+            }
+            Some(true) => {
+                self.remove(key);
+                self.stats.evictions += 1;
+                self.stats.misses += 1;
+                None
+            }
+            None => {
+                self.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    fn insert(&mut self, key: CacheKey, result: AgentResult, now: DateTime<Utc>) {
+        if self.remove(&key).is_none() && self.entries.len() >= self.capacity {
+            let oldest = self.order.first_key_value().map(|(_, key)| key.clone());
+            if let Some(oldest) = oldest {
+                self.remove(&oldest);
+                self.stats.evictions += 1;
+            }
+        }
+        self.tick += 1;
+        self.order.insert(self.tick, key.clone());
+        self.entries.insert(key, Entry { result, stored_at: now, used: self.tick });
+    }
+}
+
+/// An LRU of successful agent results keyed by kind and the agent's
+/// `Agent::cache_key`, consulted by dispatch before the agent calls out to
+/// Python. Only agents with a `kind` are cached, since native agents read and
+/// write the context; kinds in the exclusion list are skipped too. Off until
+/// given a capacity. Clones share the entries.
+#[derive(Clone)]
+pub struct ResultCache {
+    lru: Arc<Mutex<Option<Lru>>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl ResultCache {
+    pub(crate) fn new(clock: Arc<dyn Clock>) -> Self {
+        Self { lru: Arc::default(), clock }
+    }
+
+    fn lru(&self) -> std::sync::MutexGuard<'_, Option<Lru>> {
+        self.lru.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Starts an empty cache of up to `capacity` results, each kept for at most
+    /// `ttl` when set, excluding `DEFAULT_CACHE_EXCLUDED`. A capacity of 0 turns
+    /// the cache off.
+    pub fn configure(&self, capacity: usize, ttl: Option<Duration>) {
+        *self.lru() = (capacity > 0).then(|| Lru {
+            capacity,
+            ttl,
+            excluded: DEFAULT_CACHE_EXCLUDED.into_iter().collect(),
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            tick: 0,
+            stats: CacheStats::default(),
+        });
+    }
+
+    /// Replaces the kinds whose results are not cached, dropping any cached ones.
+    pub fn set_excluded(&self, kinds: impl IntoIterator<Item = AgentKind>) {
+        if let Some(lru) = self.lru().as_mut() {
+            lru.excluded = kinds.into_iter().collect();
+            let dropped: Vec<CacheKey> = lru.entries.keys().filter(|(_, kind, _)| lru.excluded.contains(kind)).cloned().collect();
+            for key in &dropped {
+                lru.remove(key);
+            }
+        }
+    }
+
+    pub fn excluded(&self) -> Vec<AgentKind> {
+        let lru = self.lru();
+        let excluded = lru.as_ref().map(|lru| &lru.excluded);
+        AgentKind::ALL.into_iter().filter(|kind| excluded.is_some_and(|excluded| excluded.contains(kind))).collect()
+    }
+
+    /// `None` while the cache is off.
+    pub fn stats(&self) -> Option<CacheStats> {
+        self.lru().as_ref().map(|lru| CacheStats { entries: lru.entries.len(), ..lru.stats })
+    }
+
+    /// Drops every entry and resets the counters.
+    pub fn clear(&self) {
+        if let Some(lru) = self.lru().as_mut() {
+            lru.entries.clear();
+            lru.order.clear();
+            lru.stats = CacheStats::default();
+        }
+    }
+
+    /// Runs `execute` unless a result for `agent` and `sub_task` is cached, in
+    /// which case that is returned with `"cache": "hit"` in its metadata.
+    /// Successful results of cacheable agents are stored.
+    pub(crate) fn get_or_execute(
+        &self,
+        agent: &dyn Agent,
+        sub_task: &str,
+        ctx: &mut Context,
+        execute: impl FnOnce(&mut Context) -> AgentResult,
+    ) -> AgentResult {
+        let cacheable = agent.kind().filter(|kind| self.lru().as_ref().is_some_and(|lru| !lru.excluded.contains(kind)));
+        let Some(key) = cacheable.and_then(|kind| Some((ctx.tenant.clone(), kind, agent.cache_key(sub_task, ctx)?))) else {
+            return execute(ctx);
+        };
+
+        let now = self.clock.now();
+        let cached = self.lru().as_mut().and_then(|lru| lru.get(&key, now));
+        if let Some(mut result) = cached {
+            result.metadata.insert("cache".to_string(), serde_json::Value::from("hit"));
+            return result;
+        }
+        let result = execute(ctx);
+        if result.status && result.error.is_none() {
+            if let Some(lru) = self.lru().as_mut() {
+                lru.insert(key, result.clone(), self.clock.now());
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CognitiveOrchestrator, FixedClock, MockBackend};
+
+    /// An orchestrator on a mock clock whose "ask" plan is one LLM query spelled two
+    /// ways and then another, and the number of LLM calls made.
+    fn orchestrator() -> (CognitiveOrchestrator, Arc<FixedClock>, Arc<Mutex<usize>>) {
+        let clock = Arc::new(FixedClock::new(DateTime::UNIX_EPOCH));
+        let calls = Arc::new(Mutex::new(0));
+        let seen = calls.clone();
+        let mock = MockBackend::new()
+            .plan("ask", ["query llm Weather", "query llm  weather ", "query llm other"])
+            .plan("again", ["query llm weather"])
+            .generator(move |prompt| {
+                *seen.lock().unwrap() += 1;
+                Ok(prompt.to_string())
+            });
+        let orch = CognitiveOrchestrator::builder().backend(Arc::new(mock)).clock(clock.clone()).build().unwrap();
+        (orch, clock, calls)
+    }
+
+    #[test]
+    fn repeated_subtasks_are_answered_from_the_cache() {
+        let (orch, _, calls) = orchestrator();
+        let orch = orch.with_result_cache(8, None);
+        let outputs: Vec<String> = serde_json::from_str(&orch.process("ask".to_string(), "ctx1")).unwrap();
+        assert_eq!(*calls.lock().unwrap(), 2);
+        assert_eq!(outputs[0], outputs[1]);
+
+        let history = orch.get_history("ctx1", None);
+        assert_eq!(history[0].result.metadata.get("cache"), None);
+        assert_eq!(history[1].result.metadata["cache"], "hit");
+        assert_eq!(orch.cache_stats(), Some(CacheStats { hits: 1, misses: 2, evictions: 0, entries: 2 }));
+
+        // Other contexts share the cache.
+        orch.process("again".to_string(), "ctx2");
+        assert_eq!(*calls.lock().unwrap(), 2);
+    }
+
+    #[test]
+    fn capacity_and_ttl_evict() {
+        let (orch, clock, calls) = orchestrator();
+        let orch = orch.with_result_cache(1, Some(Duration::from_secs(60)));
+        orch.process("ask".to_string(), "ctx1");
+        // "other" pushed out "weather".
+        orch.process("again".to_string(), "ctx1");
+        assert_eq!(*calls.lock().unwrap(), 3);
+        assert_eq!(orch.cache_stats().unwrap().evictions, 2);
+
+        clock.advance(chrono::Duration::seconds(61));
+        orch.process("again".to_string(), "ctx1");
+        assert_eq!(*calls.lock().unwrap(), 4);
+        let stats = orch.cache_stats().unwrap();
+        assert_eq!((stats.hits, stats.evictions, stats.entries), (1, 3, 1));
+    }
+
+    #[test]
+    fn off_by_default_and_excluded_kinds_are_not_cached() {
+        let (orch, _, calls) = orchestrator();
+        orch.process("ask".to_string(), "ctx1");
+        assert_eq!(*calls.lock().unwrap(), 3);
+        assert_eq!(orch.cache_stats(), None);
+
+        let orch = orch.with_result_cache(8, None);
+        assert_eq!(orch.cache_exclusions(), [AgentKind::Viral]);
+        orch.set_cache_exclusions([AgentKind::Llm]);
+        orch.process("ask".to_string(), "ctx1");
+        assert_eq!(*calls.lock().unwrap(), 6);
+        assert_eq!(orch.cache_stats().unwrap().misses, 0);
+        assert_eq!(normalize_subtask("  Query\tLLM  hi "), "query llm hi");
+    }
+}
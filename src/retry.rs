@@ -0,0 +1,89 @@
+use crate::viral::SplitMix64;
+use crate::{AgentResult, OrchestratorError};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Decides whether a failed result is worth another attempt.
+pub type RetryPredicate = Arc<dyn Fn(&AgentResult) -> bool + Send + Sync>;
+
+/// Retries timeouts, memory store errors and rate-limit responses; anything else,
+/// including unknown subtasks and malformed agent replies, is treated as permanent.
+pub fn transient_failure(result: &AgentResult) -> bool {
+    match &result.error {
+        Some(OrchestratorError::Timeout { .. } | OrchestratorError::Memory { .. }) => true,
+        Some(OrchestratorError::CallFailed { message, .. }) => message.to_lowercase().contains("rate limit"),
+        Some(_) => false,
+        None => result.output.to_lowercase().contains("rate limit"),
+    }
+}
+
+#[derive(Clone)]
+pub struct RetryPolicy {
+    /// Total attempts including the first; 1 disables retries.
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub retry_on: RetryPredicate,
+    /// Fixed jitter seed, making backoff delays reproducible; `None` seeds from
+    /// the system clock.
+    pub jitter_seed: Option<u64>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl RetryPolicy {
+    /// Retries `transient_failure`s with delays doubling from 100 ms up to 5 s.
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(5),
+            retry_on: Arc::new(transient_failure),
+            jitter_seed: None,
+        }
+    }
+
+    pub fn none() -> Self {
+        Self::new(1)
+    }
+
+    pub fn retries(&self) -> bool {
+        self.max_attempts > 1
+    }
+
+    /// Whether a result from attempt number `attempt` (1-based) should be retried.
+    pub fn should_retry(&self, result: &AgentResult, attempt: u32) -> bool {
+        !result.status && attempt < self.max_attempts && (self.retry_on)(result)
+    }
+
+    /// Delay before retry number `retry` (1-based): `base_delay * 2^(retry - 1)`
+    /// capped at `max_delay`, with equal jitter so the wait lands in `[d/2, d)`.
+    pub fn backoff(&self, retry: u32, jitter: &mut Jitter) -> Duration {
+        let exponential = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)));
+        exponential.min(self.max_delay).mul_f64(0.5 + 0.5 * jitter.0.next_f64())
+    }
+}
+
+/// Jitter source, seeded from the clock by default so concurrent retries spread out.
+pub struct Jitter(SplitMix64);
+
+impl Jitter {
+    pub fn seeded(seed: u64) -> Self {
+        Self(SplitMix64(seed))
+    }
+}
+
+impl Default for Jitter {
+    fn default() -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+        Self(SplitMix64(seed))
+    }
+}
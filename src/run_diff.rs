@@ -0,0 +1,244 @@
+use crate::{ProcessReport, ViralMetrics};
+use pyo3::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// How much slower, in percent, a subtask must get to count as a latency regression.
+pub const DEFAULT_LATENCY_REGRESSION_PCT: f64 = 50.0;
+/// Below this many milliseconds of slowdown, a change is timing noise.
+const LATENCY_NOISE_MS: f64 = 1.0;
+
+/// A subtask both runs dispatched that succeeded in one and failed in the other.
+#[pyclass(module = "sovereign_cli", get_all)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct StatusFlip {
+    pub subtask: String,
+    pub before: bool,
+    pub after: bool,
+}
+
+/// One of the context's viral metrics at the end of each run.
+#[pyclass(module = "sovereign_cli", get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct MetricDelta {
+    pub metric: String,
+    pub before: f64,
+    pub after: f64,
+    pub delta: f64,
+}
+
+/// A subtask both runs dispatched that took markedly longer in the second.
+#[pyclass(module = "sovereign_cli", get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct LatencyRegression {
+    pub subtask: String,
+    pub before_ms: f64,
+    pub after_ms: f64,
+    pub change_pct: f64,
+}
+
+/// How a run of a command differs from an earlier one, as `compare_runs` finds
+/// it. Subtasks dispatched more than once are matched by occurrence.
+#[pyclass(module = "sovereign_cli", get_all)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct RunDiff {
+    /// Planned in the second run only, in its order.
+    pub added: Vec<String>,
+    /// Planned in the first run only, in its order.
+    pub removed: Vec<String>,
+    /// Whether the subtasks both plans share come in another order.
+    pub reordered: bool,
+    pub status_flips: Vec<StatusFlip>,
+    /// Only the metrics that changed; none unless both reports carry metrics.
+    pub metric_deltas: Vec<MetricDelta>,
+    pub latency_regressions: Vec<LatencyRegression>,
+}
+
+impl RunDiff {
+    /// Whether the runs planned, succeeded and ended alike, with no slowdown.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && !self.reordered
+            && self.status_flips.is_empty()
+            && self.metric_deltas.is_empty()
+            && self.latency_regressions.is_empty()
+    }
+}
+
+#[pymethods]
+impl RunDiff {
+    #[pyo3(name = "is_empty")]
+    fn py_is_empty(&self) -> bool {
+        self.is_empty()
+    }
+
+    #[pyo3(name = "to_json")]
+    fn py_to_json(&self) -> PyResult<String> {
+        serde_json::to_string(self).map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "RunDiff(added={}, removed={}, reordered={}, status_flips={}, metric_deltas={}, latency_regressions={})",
+            self.added.len(),
+            self.removed.len(),
+            self.reordered,
+            self.status_flips.len(),
+            self.metric_deltas.len(),
+            self.latency_regressions.len()
+        )
+    }
+}
+
+/// `compare_runs_with` at `DEFAULT_LATENCY_REGRESSION_PCT`.
+pub fn compare_runs(before: &ProcessReport, after: &ProcessReport) -> RunDiff {
+    compare_runs_with(before, after, DEFAULT_LATENCY_REGRESSION_PCT)
+}
+
+/// What changed from the run `before` to the run `after`; a subtask regressed
+/// when it took over `latency_regression_pct` percent longer.
+pub fn compare_runs_with(before: &ProcessReport, after: &ProcessReport, latency_regression_pct: f64) -> RunDiff {
+    let (planned_before, planned_after) = (occurrences(&before.plan), occurrences(&after.plan));
+
+    let ran_before: HashMap<(&str, usize), usize> =
+        occurrences(&before.subtasks).into_iter().enumerate().map(|(index, step)| (step, index)).collect();
+    let mut status_flips = vec![];
+    let mut latency_regressions = vec![];
+    for (index, step) in occurrences(&after.subtasks).into_iter().enumerate() {
+        let Some(&earlier) = ran_before.get(&step) else { continue };
+        let (Some(result_before), Some(result_after)) = (before.results.get(earlier), after.results.get(index)) else { continue };
+        if result_before.status != result_after.status {
+            status_flips.push(StatusFlip { subtask: step.0.to_string(), before: result_before.status, after: result_after.status });
+        }
+        let (Some(before_ms), Some(after_ms)) = (total_ms(result_before), total_ms(result_after)) else { continue };
+        let change_pct = if before_ms > 0.0 { (after_ms - before_ms) / before_ms * 100.0 } else { 0.0 };
+        if change_pct > latency_regression_pct && after_ms - before_ms >= LATENCY_NOISE_MS {
+            latency_regressions.push(LatencyRegression { subtask: step.0.to_string(), before_ms, after_ms, change_pct });
+        }
+    }
+
+    RunDiff {
+        added: only_in(&planned_after, &planned_before),
+        removed: only_in(&planned_before, &planned_after),
+        reordered: filter_steps(&planned_before, &planned_after, true) != filter_steps(&planned_after, &planned_before, true),
+        status_flips,
+        metric_deltas: match (&before.metrics, &after.metrics) {
+            (Some(before), Some(after)) => metric_deltas(before, after),
+            _ => vec![],
+        },
+        latency_regressions,
+    }
+}
+
+/// The steps of `plan` that `other` has, or lacks, in `plan`'s order.
+fn filter_steps<'a>(plan: &[(&'a str, usize)], other: &[(&str, usize)], shared: bool) -> Vec<(&'a str, usize)> {
+    plan.iter().filter(|step| other.contains(step) == shared).copied().collect()
+}
+
+fn only_in(plan: &[(&str, usize)], other: &[(&str, usize)]) -> Vec<String> {
+    filter_steps(plan, other, false).into_iter().map(|(subtask, _)| subtask.to_string()).collect()
+}
+
+/// Each subtask with how many times it came before, so repeats can be matched.
+fn occurrences(subtasks: &[String]) -> Vec<(&str, usize)> {
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    subtasks
+        .iter()
+        .map(|subtask| {
+            let count = seen.entry(subtask.as_str()).or_default();
+            *count += 1;
+            (subtask.as_str(), *count - 1)
+        })
+        .collect()
+}
+
+fn total_ms(result: &crate::AgentResult) -> Option<f64> {
+    result.metadata.get(crate::timing::TIMING_TOTAL_MS).and_then(serde_json::Value::as_f64)
+}
+
+fn metric_deltas(before: &ViralMetrics, after: &ViralMetrics) -> Vec<MetricDelta> {
+    [
+        ("virality_score", before.virality_score, after.virality_score),
+        ("engagement_nodes", before.engagement_nodes as f64, after.engagement_nodes as f64),
+        ("hook_rate", before.hook_rate, after.hook_rate),
+        ("amplification_factor", before.amplification_factor, after.amplification_factor),
+        ("quantum_fidelity", before.quantum_fidelity, after.quantum_fidelity),
+    ]
+    .into_iter()
+    .filter(|(_, before, after)| before != after)
+    .map(|(metric, before, after)| MetricDelta { metric: metric.to_string(), before, after, delta: after - before })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compare_runs, compare_runs_with};
+    use crate::timing::TIMING_TOTAL_MS;
+    use crate::{AgentResult, CognitiveOrchestrator, MockBackend, ProcessReport};
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    fn report(plan: &[&str], ran: &[(&str, bool, f64)]) -> ProcessReport {
+        let orch = CognitiveOrchestrator::builder().backend(Arc::new(MockBackend::new())).learning(false).build().unwrap();
+        let mut report = orch.process_report("nothing".to_string(), "ctx1");
+        report.plan = plan.iter().map(|subtask| subtask.to_string()).collect();
+        report.subtasks = ran.iter().map(|(subtask, ..)| subtask.to_string()).collect();
+        report.results = ran
+            .iter()
+            .map(|&(subtask, status, ms)| {
+                let metadata = HashMap::from([(TIMING_TOTAL_MS.to_string(), serde_json::Value::from(ms))]);
+                AgentResult { output: subtask.to_string(), status, metadata, error: None }
+            })
+            .collect();
+        report
+    }
+
+    #[test]
+    fn plan_changes_flips_and_slowdowns_are_found() {
+        let before = report(&["a", "b", "c", "b"], &[("a", true, 10.0), ("b", true, 10.0), ("c", true, 10.0), ("b", false, 10.0)]);
+        let after = report(&["c", "a", "b", "d"], &[("c", true, 14.0), ("a", false, 10.0), ("b", true, 30.0), ("d", true, 1.0)]);
+        let diff = compare_runs(&before, &after);
+        assert_eq!((diff.added, diff.removed, diff.reordered), (vec!["d".to_string()], vec!["b".to_string()], true));
+        let flips: Vec<_> = diff.status_flips.iter().map(|flip| (flip.subtask.as_str(), flip.before, flip.after)).collect();
+        assert_eq!(flips, [("a", true, false)]);
+        let slower: Vec<_> = diff.latency_regressions.iter().map(|slow| (slow.subtask.as_str(), slow.change_pct)).collect();
+        assert_eq!(slower, [("b", 200.0)]);
+        // c got 40% slower, which only a tighter threshold catches.
+        assert_eq!(compare_runs_with(&before, &after, 25.0).latency_regressions.len(), 2);
+
+        let same = compare_runs(&before, &before);
+        assert!(same.is_empty(), "{:?}", same);
+        let json: serde_json::Value = serde_json::to_value(compare_runs(&before, &after)).unwrap();
+        assert_eq!(json["status_flips"][0]["subtask"], "a");
+    }
+
+    #[test]
+    fn auto_diff_compares_each_run_with_the_last_of_its_command() {
+        let failing = Arc::new(AtomicBool::new(false));
+        let fails = failing.clone();
+        let mock = MockBackend::new().plan("launch", ["post teaser", "post recap"]).on("post", move |rest| {
+            let status = !(rest == "recap" && fails.load(Ordering::Relaxed));
+            AgentResult { output: rest.to_string(), status, metadata: HashMap::new(), error: None }
+        });
+        let orch = CognitiveOrchestrator::builder().backend(Arc::new(mock)).learning(false).build().unwrap().with_auto_diff(true);
+        let first = orch.process_report("launch".to_string(), "ctx1");
+        assert!(first.diff.is_none());
+        assert_eq!(first.subtasks, ["post teaser", "post recap"]);
+        assert!(first.metrics.is_some());
+
+        failing.store(true, Ordering::Relaxed);
+        let second = orch.process_report("launch".to_string(), "ctx1");
+        let diff = second.diff.clone().unwrap();
+        assert_eq!(diff.status_flips.len(), 1);
+        assert_eq!(diff.status_flips[0].subtask, "post recap");
+        assert_eq!(orch.last_report("ctx1"), Some(second));
+
+        // Another command, or another context, has nothing to compare with.
+        assert!(orch.process_report("launch again".to_string(), "ctx1").diff.is_none());
+        assert!(orch.process_report("launch".to_string(), "ctx2").diff.is_none());
+        assert!(CognitiveOrchestrator::new().last_report("ctx1").is_none());
+    }
+}
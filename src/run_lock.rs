@@ -0,0 +1,269 @@
+use crate::{Clock, OrchestratorError};
+use chrono::{DateTime, Utc};
+use pyo3::Python;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use tokio::sync::Notify;
+
+/// The run holding a context's run lock, as `context_stats` reports it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RunLockHolder {
+    pub command: String,
+    /// When the run took the lock, by the orchestrator's clock.
+    pub since: DateTime<Utc>,
+    /// Runs queued behind it; `try_process` callers never queue.
+    pub waiting: usize,
+}
+
+#[derive(Default)]
+struct LockState {
+    holder: Option<(String, DateTime<Utc>)>,
+    waiting: usize,
+}
+
+/// One context's lock. Blocking waiters park on `freed`, async ones on
+/// `freed_async`; a release wakes both kinds and they race for it.
+#[derive(Default)]
+struct RunLock {
+    state: Mutex<LockState>,
+    freed: Condvar,
+    freed_async: Notify,
+}
+
+impl RunLock {
+    fn state(&self) -> MutexGuard<'_, LockState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn take(state: &mut LockState, command: &str, clock: &dyn Clock) -> bool {
+        if state.holder.is_some() {
+            return false;
+        }
+        state.holder = Some((command.to_string(), clock.now()));
+        true
+    }
+
+    fn try_take(&self, command: &str, clock: &dyn Clock) -> bool {
+        Self::take(&mut self.state(), command, clock)
+    }
+
+    fn release(&self) {
+        self.state().holder = None;
+        self.freed.notify_one();
+        self.freed_async.notify_waiters();
+    }
+}
+
+type LockMap = HashMap<String, Arc<RunLock>>;
+
+/// A lock per context, held by a run from planning to completion, so runs on
+/// one context take turns (keeping its metrics and history in run order) while
+/// runs on other contexts proceed. A context has an entry only while a run holds
+/// or waits for its lock, so evicted and removed contexts leave none behind.
+///
+/// A run that starts another run on its own context from inside an agent waits
+/// for itself; agents should use another context for nested runs.
+#[derive(Clone, Default)]
+pub(crate) struct RunLocks(Arc<Mutex<LockMap>>);
+
+impl RunLocks {
+    fn map(&self) -> MutexGuard<'_, LockMap> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn entry(&self, key: &str) -> Entry {
+        let lock = self.map().entry(key.to_string()).or_default().clone();
+        Entry { locks: self.clone(), key: key.to_string(), lock }
+    }
+
+    /// Takes `key`'s lock for `command`, waiting with the GIL released while
+    /// another run holds it, since that run's agents may need the GIL.
+    pub(crate) fn acquire(&self, key: &str, command: &str, clock: &dyn Clock) -> RunGuard {
+        let entry = self.entry(key);
+        if !entry.lock.try_take(command, clock) {
+            Python::with_gil(|py| {
+                py.allow_threads(|| {
+                    let mut state = entry.lock.state();
+                    state.waiting += 1;
+                    while !RunLock::take(&mut state, command, clock) {
+                        state = entry.lock.freed.wait(state).unwrap_or_else(|e| e.into_inner());
+                    }
+                    state.waiting -= 1;
+                })
+            });
+        }
+        RunGuard { entry }
+    }
+
+    /// `acquire` for async runs, which wait without blocking their thread.
+    pub(crate) async fn acquire_async(&self, key: &str, command: &str, clock: Arc<dyn Clock>) -> RunGuard {
+        let entry = self.entry(key);
+        let mut counted = false;
+        loop {
+            let freed = entry.lock.freed_async.notified();
+            tokio::pin!(freed);
+            freed.as_mut().enable();
+            {
+                let mut state = entry.lock.state();
+                if RunLock::take(&mut state, command, clock.as_ref()) {
+                    state.waiting -= usize::from(counted);
+                    break;
+                }
+                state.waiting += usize::from(!counted);
+                counted = true;
+            }
+            freed.await;
+        }
+        RunGuard { entry }
+    }
+
+    /// `acquire` without waiting: `ContextBusy` while another run holds the lock.
+    pub(crate) fn try_acquire(&self, key: &str, command: &str, clock: &dyn Clock) -> Result<RunGuard, OrchestratorError> {
+        let entry = self.entry(key);
+        let mut state = entry.lock.state();
+        if RunLock::take(&mut state, command, clock) {
+            drop(state);
+            return Ok(RunGuard { entry });
+        }
+        let command = state.holder.as_ref().map(|(command, _)| command.clone()).unwrap_or_default();
+        drop(state);
+        Err(OrchestratorError::ContextBusy { context_id: key.to_string(), command })
+    }
+
+    /// Who holds `key`'s lock, if anyone does.
+    pub(crate) fn holder(&self, key: &str) -> Option<RunLockHolder> {
+        let lock = self.map().get(key)?.clone();
+        let state = lock.state();
+        let (command, since) = state.holder.clone()?;
+        Some(RunLockHolder { command, since, waiting: state.waiting })
+    }
+}
+
+/// A claim on a context's entry, which is removed with the last claim.
+struct Entry {
+    locks: RunLocks,
+    key: String,
+    lock: Arc<RunLock>,
+}
+
+impl Drop for Entry {
+    fn drop(&mut self) {
+        let mut map = self.locks.map();
+        // The map's reference and this one: nobody else holds or waits.
+        if Arc::strong_count(&self.lock) == 2 {
+            map.remove(&self.key);
+        }
+    }
+}
+
+/// A context's run lock, released on drop.
+pub(crate) struct RunGuard {
+    entry: Entry,
+}
+
+impl Drop for RunGuard {
+    fn drop(&mut self) {
+        self.entry.lock.release();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AgentResult, CognitiveOrchestrator, MockBackend, OrchestratorError};
+    use std::collections::HashMap;
+    use std::sync::{mpsc, Arc, Mutex};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    type Windows = Arc<Mutex<Vec<(String, Instant, Instant)>>>;
+
+    /// An orchestrator whose "post" subtasks each take 50 ms and are recorded,
+    /// planning "post" as two of them.
+    fn slow_posts() -> (Arc<CognitiveOrchestrator>, Windows) {
+        let windows = Windows::default();
+        let recorded = windows.clone();
+        let mock = MockBackend::new().plan("post", ["post first", "post second"]).on("post", move |rest| {
+            let started = Instant::now();
+            thread::sleep(Duration::from_millis(50));
+            recorded.lock().unwrap().push((rest.to_string(), started, Instant::now()));
+            AgentResult { output: rest.to_string(), status: true, metadata: HashMap::new(), error: None }
+        });
+        let orch = CognitiveOrchestrator::builder().backend(Arc::new(mock)).learning(false).build().unwrap();
+        (Arc::new(orch), windows)
+    }
+
+    fn run_concurrently(orch: &Arc<CognitiveOrchestrator>, context_ids: [&'static str; 2]) {
+        let runs: Vec<_> = context_ids
+            .into_iter()
+            .map(|context_id| {
+                let orch = orch.clone();
+                thread::spawn(move || orch.process("post clip".to_string(), context_id))
+            })
+            .collect();
+        for run in runs {
+            run.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn runs_on_one_context_serialize_whole_runs() {
+        let (orch, windows) = slow_posts();
+        run_concurrently(&orch, ["ctx1", "ctx1"]);
+        let mut windows = windows.lock().unwrap().clone();
+        windows.sort_by_key(|(_, started, _)| *started);
+        let order: Vec<&str> = windows.iter().map(|(subtask, _, _)| subtask.as_str()).collect();
+        assert_eq!(order, ["first", "second", "first", "second"]);
+        // The second run planned only after the first completed.
+        assert!(windows[1].2 <= windows[2].1, "{:?}", windows);
+        assert!(orch.run_locks.map().is_empty());
+    }
+
+    #[test]
+    fn runs_on_different_contexts_overlap() {
+        let (orch, windows) = slow_posts();
+        let started = Instant::now();
+        run_concurrently(&orch, ["ctx1", "ctx2"]);
+        let windows = windows.lock().unwrap().clone();
+        assert_eq!(windows.len(), 4);
+        // Two 100 ms runs in parallel, well short of the 200 ms they take in turn.
+        assert!(started.elapsed() < Duration::from_millis(180), "{:?}", started.elapsed());
+        assert!(orch.run_locks.map().is_empty());
+    }
+
+    #[test]
+    fn try_process_fails_fast_while_a_run_holds_the_context() {
+        let (entered_tx, entered_rx) = mpsc::channel();
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let (entered_tx, release_rx) = (Mutex::new(entered_tx), Mutex::new(release_rx));
+        let mock = MockBackend::new().on("hold", move |rest| {
+            entered_tx.lock().unwrap().send(()).unwrap();
+            release_rx.lock().unwrap().recv().unwrap();
+            AgentResult { output: rest.to_string(), status: true, metadata: HashMap::new(), error: None }
+        });
+        // Under a timeout the agent works on a copy of the context, leaving it
+        // readable by `context_stats` meanwhile.
+        let builder = CognitiveOrchestrator::builder().backend(Arc::new(mock)).subtask_timeout(Duration::from_secs(5));
+        let orch = Arc::new(builder.learning(false).build().unwrap());
+        orch.remember("ctx1", "launch teaser").unwrap();
+        let holder = {
+            let orch = orch.clone();
+            thread::spawn(move || orch.process("hold on".to_string(), "ctx1"))
+        };
+        entered_rx.recv().unwrap();
+
+        let started = Instant::now();
+        let err = orch.try_process("hold again".to_string(), "ctx1").unwrap_err();
+        assert!(started.elapsed() < Duration::from_millis(50));
+        assert_eq!(err, OrchestratorError::ContextBusy { context_id: "ctx1".to_string(), command: "hold on".to_string() });
+        let lock = orch.context_stats("ctx1").unwrap().run_lock.unwrap();
+        assert_eq!((lock.command.as_str(), lock.waiting), ("hold on", 0));
+        assert!(orch.try_process("post elsewhere".to_string(), "ctx2").is_ok());
+
+        release_tx.send(()).unwrap();
+        holder.join().unwrap();
+        assert_eq!(orch.context_stats("ctx1").unwrap().run_lock, None);
+        release_tx.send(()).unwrap();
+        assert!(orch.try_process("hold again".to_string(), "ctx1").is_ok());
+    }
+}
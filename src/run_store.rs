@@ -0,0 +1,98 @@
+use crate::ProcessReport;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// The reports of the most recent runs, by run id, for `get_run`; keeps none
+/// with a limit of 0. A run reusing a caller's id replaces the report before it.
+pub(crate) struct RunStore {
+    limit: usize,
+    reports: Mutex<VecDeque<ProcessReport>>,
+}
+
+impl RunStore {
+    pub(crate) fn new(limit: usize) -> Self {
+        Self { limit, reports: Mutex::new(VecDeque::new()) }
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.limit > 0
+    }
+
+    /// Keeps `report`, dropping the oldest beyond the limit.
+    pub(crate) fn keep(&self, report: ProcessReport) {
+        if !self.is_enabled() {
+            return;
+        }
+        let mut reports = self.reports.lock().unwrap_or_else(|e| e.into_inner());
+        reports.retain(|kept| kept.run_id != report.run_id);
+        reports.push_back(report);
+        while reports.len() > self.limit {
+            reports.pop_front();
+        }
+    }
+
+    pub(crate) fn get(&self, run_id: &str) -> Option<ProcessReport> {
+        let reports = self.reports.lock().unwrap_or_else(|e| e.into_inner());
+        reports.iter().find(|report| report.run_id.as_deref() == Some(run_id)).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AgentResult, CognitiveOrchestrator, MockBackend, ProcessEvent, Submission};
+    use std::sync::Arc;
+
+    fn orchestrator(retention: usize) -> CognitiveOrchestrator {
+        let mock = MockBackend::new()
+            .plan("launch", ["post teaser", "post launch"])
+            .on("post", |rest| AgentResult::ok(format!("posted {}", rest)));
+        CognitiveOrchestrator::builder().backend(Arc::new(mock)).run_retention(retention).learning(false).build().unwrap()
+    }
+
+    #[test]
+    fn one_run_id_tags_events_results_and_report() {
+        let orch = orchestrator(2);
+        let mut events = vec![];
+        orch.process_streaming("launch".to_string(), "ctx1", |event| events.push(event));
+        let run_id = events[0].run_id().to_string();
+        assert!(events.iter().all(|event| event.run_id() == run_id), "{:?}", events);
+        let results: Vec<&AgentResult> = events
+            .iter()
+            .filter_map(|event| match event {
+                ProcessEvent::SubtaskFinished { result, .. } => Some(result),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|result| result.metadata[AgentResult::RUN_ID] == run_id.as_str()));
+        let report = orch.get_run(&run_id).unwrap();
+        assert_eq!(report.run_id.as_deref(), Some(run_id.as_str()));
+        assert!(report.results.iter().all(|result| result.metadata[AgentResult::RUN_ID] == run_id.as_str()));
+        assert!(orch.get_history("ctx1", None).iter().all(|record| record.result.metadata[AgentResult::RUN_ID] == run_id.as_str()));
+
+        let second = orch.process_report("launch".to_string(), "ctx1");
+        let second_id = second.run_id.clone().unwrap();
+        assert_ne!(second_id, run_id);
+        assert_eq!(orch.get_run(&second_id), Some(second));
+        // The oldest goes once the limit is reached.
+        orch.process_report("launch".to_string(), "ctx1");
+        assert!(orch.get_run(&run_id).is_none());
+        let off = orchestrator(0);
+        let run_id = off.process_report("launch".to_string(), "ctx1").run_id.unwrap();
+        assert!(off.get_run(&run_id).is_none());
+    }
+
+    #[test]
+    fn callers_can_supply_the_run_id() {
+        let orch = orchestrator(4);
+        let report = orch.process_report_with("launch".to_string(), "ctx1", Submission::default().run_id("upstream-42"));
+        assert!(report.success);
+        assert_eq!(report.run_id.as_deref(), Some("upstream-42"));
+        assert!(report.results.iter().all(|result| result.metadata[AgentResult::RUN_ID] == "upstream-42"));
+        assert_eq!(orch.get_run("upstream-42"), Some(report));
+
+        let report = orch.process_report_with("launch".to_string(), "ctx1", Submission::default().run_id("no spaces"));
+        assert!(!report.success && report.results.is_empty());
+        assert!(report.error.unwrap().starts_with("Input Error: invalid input (run_id)"));
+    }
+}
@@ -0,0 +1,98 @@
+use crate::{AgentResult, Context, OrchestratorError, ProcessReport, ViralMetrics};
+use schemars::generate::SchemaSettings;
+use schemars::{JsonSchema, Schema};
+use std::path::{Path, PathBuf};
+
+/// The JSON Schema (draft 2020-12) of `T` as this crate serializes it, which
+/// is what downstream services receive.
+pub fn schema_for<T: JsonSchema>() -> Schema {
+    SchemaSettings::draft2020_12().for_serialize().into_generator().into_root_schema_for::<T>()
+}
+
+/// Every exported schema by type name.
+pub fn schemas() -> Vec<(&'static str, Schema)> {
+    vec![
+        ("AgentResult", schema_for::<AgentResult>()),
+        ("Context", schema_for::<Context>()),
+        ("ViralMetrics", schema_for::<ViralMetrics>()),
+        ("ProcessReport", schema_for::<ProcessReport>()),
+    ]
+}
+
+/// Writes `<Type>.schema.json` into `dir` for each of `schemas`, creating
+/// `dir` if needed, and returns the paths written.
+pub fn export_schemas(dir: &Path) -> Result<Vec<PathBuf>, OrchestratorError> {
+    std::fs::create_dir_all(dir).map_err(|e| OrchestratorError::io(dir, e))?;
+    schemas()
+        .into_iter()
+        .map(|(name, schema)| {
+            let path = dir.join(format!("{}.schema.json", name));
+            let json = serde_json::to_string_pretty(&schema).map_err(OrchestratorError::serialization)?;
+            std::fs::write(&path, json + "\n").map_err(|e| OrchestratorError::io(&path, e))?;
+            Ok(path)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{export_schemas, schema_for};
+    use crate::{AgentResult, CognitiveOrchestrator, Context, Goal, MetricsSample, OrchestratorError, ViralMetrics};
+    use schemars::JsonSchema;
+    use serde::Serialize;
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+
+    fn assert_valid<T: JsonSchema + Serialize>(value: &T) {
+        let schema = serde_json::to_value(schema_for::<T>()).unwrap();
+        let validator = jsonschema::validator_for(&schema).unwrap();
+        let instance = serde_json::to_value(value).unwrap();
+        let errors: Vec<String> = validator.iter_errors(&instance).map(|e| e.to_string()).collect();
+        assert!(errors.is_empty(), "{:?}\n{}", errors, instance);
+    }
+
+    fn context() -> Context {
+        let orch = CognitiveOrchestrator::builder().learning(false).build().unwrap();
+        orch.add_goal("ctx1", Goal::new("launch", "launch the campaign")).unwrap();
+        orch.remember_payload("ctx1", "teaser flopped", json!({"type": "error"}).as_object().unwrap().clone()).unwrap();
+        let mut context = orch.get_context("ctx1").unwrap();
+        let sample = MetricsSample { at: context.created_at, metrics: ViralMetrics::default() };
+        context.metrics_history.record(sample, 8);
+        context.extra.insert("from_newer_version".to_string(), json!([1, 2]));
+        context
+    }
+
+    #[test]
+    fn serialized_contexts_match_their_schema() {
+        assert_valid(&context());
+
+        let schema = serde_json::to_value(schema_for::<Context>()).unwrap();
+        assert_eq!(schema["properties"]["created_at"], json!({"type": "string", "format": "date-time"}));
+        let mut stale = serde_json::to_value(context()).unwrap();
+        stale["viral_metrics"] = json!("high");
+        assert!(!jsonschema::is_valid(&schema, &stale));
+    }
+
+    #[test]
+    fn results_with_errors_and_open_metadata_match_their_schema() {
+        let metadata = HashMap::from([("anything".to_string(), json!({"nested": [true, null]}))]);
+        let err = OrchestratorError::Timeout { subtask: "post teaser".to_string(), timeout_ms: 50 };
+        assert_valid(&AgentResult { output: String::new(), status: false, metadata, error: Some(err) });
+        let schema = serde_json::to_value(schema_for::<AgentResult>()).unwrap();
+        assert_eq!(schema["properties"]["metadata"], json!({"type": "object", "additionalProperties": true}));
+    }
+
+    #[test]
+    fn exports_one_file_per_type() {
+        let dir = std::env::temp_dir().join(format!("sovereign-schemas-{}", std::process::id()));
+        let written = export_schemas(&dir).unwrap();
+        let names: Vec<String> = written.iter().map(|path| path.file_name().unwrap().to_string_lossy().into_owned()).collect();
+        assert_eq!(
+            names,
+            ["AgentResult.schema.json", "Context.schema.json", "ViralMetrics.schema.json", "ProcessReport.schema.json"]
+        );
+        let context: Value = serde_json::from_str(&std::fs::read_to_string(&written[1]).unwrap()).unwrap();
+        assert_eq!(context["title"], "Context");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,184 @@
+use crate::{decayed_recall, Context, OrchestratorError};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+/// Added to each rank in reciprocal rank fusion, so no one ranking's top few
+/// hits outweigh agreement between the two.
+pub const RRF_K: f64 = 60.0;
+
+/// A condition `search_memory` puts on a memory entry's payload; entries
+/// without a payload match none.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryFilter {
+    /// The field holds exactly `value`.
+    Equals { field: String, value: Value },
+    /// The field is a string containing `substring`.
+    Contains { field: String, substring: String },
+}
+
+impl MemoryFilter {
+    pub fn equals(field: &str, value: impl Into<Value>) -> Self {
+        Self::Equals { field: field.to_string(), value: value.into() }
+    }
+
+    pub fn contains(field: &str, substring: &str) -> Self {
+        Self::Contains { field: field.to_string(), substring: substring.to_string() }
+    }
+
+    /// One filter per field of `filters`: `{"contains": s}` for a substring,
+    /// any other value for equality.
+    pub fn from_json(filters: Map<String, Value>) -> Vec<Self> {
+        filters
+            .into_iter()
+            .map(|(field, value)| match value.get("contains").and_then(Value::as_str) {
+                Some(substring) if value.as_object().is_some_and(|spec| spec.len() == 1) => {
+                    Self::Contains { field, substring: substring.to_string() }
+                }
+                _ => Self::Equals { field, value },
+            })
+            .collect()
+    }
+
+    pub(crate) fn matches(&self, payload: &Map<String, Value>) -> bool {
+        match self {
+            Self::Equals { field, value } => payload.get(field) == Some(value),
+            Self::Contains { field, substring } => {
+                payload.get(field).and_then(Value::as_str).is_some_and(|text| text.contains(substring.as_str()))
+            }
+        }
+    }
+}
+
+/// A memory entry `search_memory` found.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MemorySearchHit {
+    pub index: usize,
+    pub text: Option<String>,
+    pub payload: Map<String, Value>,
+    /// Reciprocal rank fusion of the entry's rank by similarity and by keywords.
+    pub score: f64,
+    /// Decayed cosine similarity to the query, as `recall` scores it.
+    pub similarity: f64,
+}
+
+/// Lowercased words, split as `hash_embed` splits them.
+fn words(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric()).filter(|word| !word.is_empty()).map(str::to_lowercase).collect()
+}
+
+/// The `k` entries of `context` whose payloads pass every filter, best first
+/// by fusing two rankings: by decayed similarity to `query_vec`, and by how
+/// many of `query`'s words their text contains, which leaves out entries that
+/// contain none.
+pub(crate) fn search(
+    context: &Context,
+    query: &str,
+    query_vec: &[f64],
+    filters: &[MemoryFilter],
+    k: usize,
+    now: DateTime<Utc>,
+) -> Result<Vec<MemorySearchHit>, OrchestratorError> {
+    let passes = |idx: usize| context.memory_payload(idx).filter(|payload| filters.iter().all(|filter| filter.matches(payload)));
+    let by_similarity: Vec<(usize, f64)> = decayed_recall(context, query_vec, context.memory_vectors.len(), now)?
+        .into_iter()
+        .filter(|(idx, _)| passes(*idx).is_some())
+        .collect();
+
+    let query_words = words(query);
+    let mut by_keywords: Vec<(usize, usize)> = by_similarity
+        .iter()
+        .filter_map(|&(idx, _)| {
+            let matched = context.memory_text(idx).map_or(0, |text| words(text).intersection(&query_words).count());
+            (matched > 0).then_some((idx, matched))
+        })
+        .collect();
+    // Stable, so equal keyword matches keep their similarity order.
+    by_keywords.sort_by_key(|&(_, matched)| Reverse(matched));
+
+    let mut fused: HashMap<usize, f64> = HashMap::new();
+    for (rank, idx) in by_similarity.iter().map(|(idx, _)| idx).enumerate().chain(by_keywords.iter().map(|(idx, _)| idx).enumerate()) {
+        *fused.entry(*idx).or_default() += 1.0 / (RRF_K + rank as f64 + 1.0);
+    }
+    let mut hits: Vec<MemorySearchHit> = by_similarity
+        .into_iter()
+        .map(|(index, similarity)| MemorySearchHit {
+            index,
+            text: context.memory_text(index).map(str::to_string),
+            payload: passes(index).cloned().unwrap_or_default(),
+            score: fused[&index],
+            similarity,
+        })
+        .collect();
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.index.cmp(&b.index)));
+    hits.truncate(k);
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MemoryFilter;
+    use crate::{CognitiveOrchestrator, Embedder, OrchestratorError};
+    use serde_json::{json, Map, Value};
+    use std::sync::Arc;
+
+    /// One dimension per topic word, so similarity is topical overlap.
+    struct TopicEmbedder;
+
+    impl Embedder for TopicEmbedder {
+        fn embed(&self, text: &str) -> Result<Vec<f64>, OrchestratorError> {
+            Ok(["launch", "teaser", "budget", "deploy"].iter().map(|word| if text.contains(word) { 1.0 } else { 0.0 }).collect())
+        }
+
+        fn dim(&self) -> usize {
+            4
+        }
+    }
+
+    fn payload(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    fn seeded() -> CognitiveOrchestrator {
+        let orch = CognitiveOrchestrator::builder().embedder(Arc::new(TopicEmbedder)).learning(false).build().unwrap();
+        let entries = [
+            ("launch teaser flopped", json!({"type": "error", "command": "spring launch"})),
+            ("launch budget overrun", json!({"type": "error", "command": "summer launch"})),
+            ("launch teaser went viral", json!({"type": "success", "command": "spring launch"})),
+            ("deploy teaser stalled", json!({"type": "error", "command": "deploy site"})),
+        ];
+        for (text, meta) in entries {
+            orch.remember_payload("ctx1", text, payload(meta)).unwrap();
+        }
+        orch
+    }
+
+    #[test]
+    fn filters_narrow_and_rankings_fuse() {
+        let orch = seeded();
+        let texts = |filters: &[MemoryFilter], k| -> Vec<String> {
+            orch.search_memory("ctx1", "launch teaser", filters, k).unwrap().into_iter().filter_map(|hit| hit.text).collect()
+        };
+        // The first three tie on similarity; keywords lift the last of them.
+        let fused = orch.search_memory("ctx1", "deploy stalled teaser launch", &[], 4).unwrap();
+        assert_eq!(fused.iter().map(|hit| hit.index).collect::<Vec<_>>(), [0, 3, 2, 1]);
+        assert_eq!((fused[1].similarity, fused[2].similarity), (fused[0].similarity, fused[0].similarity));
+        assert_eq!(texts(&[MemoryFilter::equals("type", "error")], 2), ["launch teaser flopped", "launch budget overrun"]);
+        let in_spring = [MemoryFilter::equals("type", "error"), MemoryFilter::contains("command", "spring")];
+        assert_eq!(texts(&in_spring, 3), ["launch teaser flopped"]);
+
+        let hits = orch.search_memory("ctx1", "launch teaser", &in_spring, 1).unwrap();
+        assert_eq!((hits[0].index, hits[0].payload["command"].as_str()), (0, Some("spring launch")));
+        assert!((hits[0].score - 2.0 / 61.0).abs() < 1e-12);
+        assert!(orch.search_memory("ctx2", "launch", &[], 1).is_err());
+    }
+
+    #[test]
+    fn filters_read_from_json() {
+        let filters = MemoryFilter::from_json(payload(json!({"type": "error", "command": {"contains": "launch"}})));
+        assert_eq!(filters, [MemoryFilter::contains("command", "launch"), MemoryFilter::equals("type", "error")]);
+    }
+}
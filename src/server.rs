@@ -0,0 +1,427 @@
+use crate::{
+    AgentResult, CircuitBreakers, CognitiveOrchestrator, Context, ContextView, EventBus, IdempotencyKeys, IdempotentRun,
+    OrchestratorError, PreflightCheck, PreflightReport, Priority, ProcessEvent, SharedOrchestrator, ShutdownHandle, ShutdownReport,
+    Submission, Subscription,
+};
+use axum::extract::rejection::JsonRejection;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_stream::{Stream, StreamExt};
+use tracing::warn;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProcessRequest {
+    pub command: String,
+    pub context_id: String,
+    /// Overrides the configured subtask timeout for this run.
+    #[serde(default, rename = "timeout_secs", with = "crate::history::secs::option")]
+    pub timeout: Option<Duration>,
+    /// A retry with the key of a completed run gets that run's response back.
+    #[serde(default)]
+    pub idempotency_key: Option<String>,
+    /// While the key's run is in flight, waits for it; `false` answers 409 instead.
+    #[serde(default = "waits")]
+    pub wait: bool,
+    /// Tags the run instead of a fresh id, for correlation with upstream systems.
+    #[serde(default)]
+    pub run_id: Option<String>,
+    /// Orders the run in the submission queue: `high`, `normal` or `low`.
+    #[serde(default)]
+    pub priority: Priority,
+}
+
+fn waits() -> bool {
+    true
+}
+
+/// Purges what was deleted at least `older_than_secs` ago.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PurgeRequest {
+    #[serde(rename = "older_than_secs", with = "crate::history::secs")]
+    pub older_than: Duration,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessResponse {
+    pub context_id: String,
+    /// What `process` returns, one entry per subtask.
+    pub outputs: Vec<String>,
+    pub results: Vec<AgentResult>,
+    pub replanned: Vec<String>,
+    /// Set when the response is a completed run's, replayed for its idempotency key.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub replayed: bool,
+    /// Set when the response is an identical run's, which `coalesce_window` joined
+    /// this request to.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub coalesced: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+}
+
+impl ProcessResponse {
+    fn replay(context_id: String, run: IdempotentRun) -> Self {
+        Self {
+            context_id,
+            outputs: serde_json::from_str(&run.output).unwrap_or_else(|_| vec![run.output]),
+            results: run.results,
+            replanned: run.replanned,
+            replayed: run.replayed,
+            coalesced: false,
+            run_id: run.run_id,
+        }
+    }
+}
+
+/// An `OrchestratorError` with the status it is served with, as
+/// `{"error": {"kind", ...}, "message"}`.
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub error: OrchestratorError,
+}
+
+impl ApiError {
+    fn missing_context(context_id: &str) -> Self {
+        Self {
+            status: StatusCode::NOT_FOUND,
+            error: OrchestratorError::MissingContext { context_id: context_id.to_string() },
+        }
+    }
+
+    /// Refuses new runs once shutdown has begun.
+    fn admit(drain: &ShutdownHandle) -> Result<(), Self> {
+        drain.check().map_err(Self::from)
+    }
+}
+
+/// 404 for an unknown context, 409 for an idempotency key in flight or a busy or
+/// taken context, 422 for a key reused or input refused, 503 during shutdown, 500 otherwise.
+impl From<OrchestratorError> for ApiError {
+    fn from(error: OrchestratorError) -> Self {
+        let status = match error {
+            OrchestratorError::MissingContext { .. } => StatusCode::NOT_FOUND,
+            OrchestratorError::IdempotencyPending { .. }
+            | OrchestratorError::ContextBusy { .. }
+            | OrchestratorError::ContextExists { .. } => StatusCode::CONFLICT,
+            OrchestratorError::IdempotencyMismatch { .. } | OrchestratorError::InvalidInput { .. } => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            OrchestratorError::ShuttingDown => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        Self { status, error }
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        Self {
+            status: rejection.status(),
+            error: OrchestratorError::Extraction {
+                target: "request body".to_string(),
+                expected: "JSON object".to_string(),
+                message: rejection.body_text(),
+            },
+        }
+    }
+}
+
+impl From<tokio::task::JoinError> for ApiError {
+    fn from(err: tokio::task::JoinError) -> Self {
+        Self {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            error: OrchestratorError::CallFailed { target: "process".to_string(), message: err.to_string(), traceback: None },
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = serde_json::json!({ "error": self.error, "message": self.error.to_string() });
+        (self.status, Json(body)).into_response()
+    }
+}
+
+/// The parts of an orchestrator's state that handlers use on their own.
+#[derive(Clone)]
+pub struct Handles {
+    /// So subscribing does not wait for a run.
+    pub events: EventBus,
+    /// So requests are refused during shutdown without waiting.
+    pub drain: ShutdownHandle,
+    /// So a retry can wait for its key's run or be refused.
+    pub keys: IdempotencyKeys,
+    /// So `/healthz` answers during a run.
+    pub breakers: CircuitBreakers,
+}
+
+impl Handles {
+    pub fn of(orchestrator: &CognitiveOrchestrator) -> Self {
+        Self {
+            events: orchestrator.event_bus().clone(),
+            drain: orchestrator.shutdown_handle(),
+            keys: orchestrator.idempotency_keys(),
+            breakers: orchestrator.circuit_breakers(),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct AppState {
+    orchestrator: SharedOrchestrator,
+    events: EventBus,
+    drain: ShutdownHandle,
+    keys: IdempotencyKeys,
+    breakers: CircuitBreakers,
+}
+
+/// The routes, serving `orchestrator`.
+pub fn router(orchestrator: CognitiveOrchestrator) -> Router {
+    let handles = Handles::of(&orchestrator);
+    router_shared(Arc::new(orchestrator), handles)
+}
+
+/// The routes, serving an orchestrator the caller keeps a handle to, with its
+/// `Handles`. Runs go to blocking threads and proceed in parallel, other than
+/// runs on the same context, which take turns on it subtask by subtask.
+pub fn router_shared(orchestrator: SharedOrchestrator, handles: Handles) -> Router {
+    let Handles { events, drain, keys, breakers } = handles;
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/process", post(process))
+        .route("/process/stream", post(process_stream))
+        .route("/contexts", get(list_contexts))
+        .route("/contexts/{id}", get(get_context).delete(delete_context))
+        .route("/contexts/{id}/live", get(observe_context))
+        .route("/contexts/{id}/restore", post(restore_context))
+        .route("/trash/purge", post(purge_trash))
+        .route("/events", get(events_socket))
+        .with_state(AppState { orchestrator, events, drain, keys, breakers })
+}
+
+/// Serves `router(orchestrator)` on `listener` until the task is dropped.
+pub async fn serve(listener: TcpListener, orchestrator: CognitiveOrchestrator) -> std::io::Result<()> {
+    axum::serve(listener, router(orchestrator)).await
+}
+
+/// Serves until `signal` resolves, then refuses new runs with 503, lets the
+/// requests in flight finish within `grace` and shuts the orchestrator down.
+pub async fn serve_until(
+    listener: TcpListener,
+    orchestrator: CognitiveOrchestrator,
+    signal: impl Future<Output = ()> + Send + 'static,
+    grace: Duration,
+) -> std::io::Result<ShutdownReport> {
+    let handles = Handles::of(&orchestrator);
+    let drain = handles.drain.clone();
+    let shared = Arc::new(orchestrator);
+    let app = router_shared(shared.clone(), handles);
+    let idle = drain.clone();
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async move {
+            signal.await;
+            drain.begin(grace);
+        })
+        .await?;
+    // Runs outlive their connections on blocking threads; give them the grace period.
+    tokio::task::spawn_blocking(move || idle.wait_idle()).await?;
+    Ok(shared.shutdown(grace))
+}
+
+/// `"degraded"` while an agent's circuit is not closed, with each agent's
+/// `AgentHealth` once any has been dispatched to.
+async fn healthz(State(AppState { breakers, .. }): State<AppState>) -> Json<serde_json::Value> {
+    let status = if breakers.is_open() { "degraded" } else { "ok" };
+    let agents = breakers.health();
+    if agents.is_empty() {
+        return Json(serde_json::json!({ "status": status }));
+    }
+    Json(serde_json::json!({ "status": status, "agents": agents }))
+}
+
+/// The `preflight` report, 200 when no check failed and 503 otherwise,
+/// including while the server drains.
+async fn readyz(
+    State(AppState { orchestrator, drain, .. }): State<AppState>,
+) -> Result<(StatusCode, Json<PreflightReport>), ApiError> {
+    let mut report = tokio::task::spawn_blocking(move || orchestrator.preflight()).await?;
+    if drain.is_shutting_down() {
+        report.checks.push(PreflightCheck::fail("shutdown", "the server is draining", "route requests to another instance"));
+    }
+    let status = if report.is_ready() { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    Ok((status, Json(report)))
+}
+
+async fn process(
+    State(AppState { orchestrator, drain, keys, .. }): State<AppState>,
+    request: Result<Json<ProcessRequest>, JsonRejection>,
+) -> Result<Json<ProcessResponse>, ApiError> {
+    let Json(request) = request?;
+    ApiError::admit(&drain)?;
+    if let Some(key) = request.idempotency_key.clone() {
+        if !request.wait {
+            if keys.is_in_flight(&key) {
+                return Err(OrchestratorError::IdempotencyPending { key }.into());
+            }
+        } else {
+            tokio::task::spawn_blocking(move || keys.wait(&key, None)).await?;
+        }
+    }
+    let response = tokio::task::spawn_blocking(move || -> Result<ProcessResponse, ApiError> {
+        // Shutdown may have begun while this waited for the key.
+        ApiError::admit(&drain)?;
+        if let Some(key) = &request.idempotency_key {
+            let (command, context_id, timeout) = (request.command, &request.context_id, request.timeout);
+            let submission = Submission { timeout, run_id: request.run_id, priority: request.priority };
+            let run = orchestrator.process_idempotent_with(command, context_id, key, submission)?;
+            return Ok(ProcessResponse::replay(request.context_id, run));
+        }
+        let mut response = ProcessResponse {
+            context_id: request.context_id.clone(),
+            outputs: vec![],
+            results: vec![],
+            replanned: vec![],
+            replayed: false,
+            coalesced: false,
+            run_id: None,
+        };
+        let submission = Submission { timeout: request.timeout, run_id: request.run_id, priority: request.priority };
+        orchestrator.process_streaming_with(request.command, &request.context_id, submission, |event| match event {
+            ProcessEvent::SubtaskFinished { result, .. } => response.results.push(result),
+            ProcessEvent::Completed { run_id, output, replanned, coalesced, .. } => {
+                response.outputs = serde_json::from_str(&output).unwrap_or_else(|_| vec![output]);
+                response.replanned = replanned;
+                response.coalesced = coalesced;
+                response.run_id = Some(run_id);
+            }
+            _ => {}
+        });
+        Ok(response)
+    })
+    .await??;
+    Ok(Json(response))
+}
+
+/// One SSE message per `ProcessEvent`, named by its `event` tag; the stream
+/// ends after `completed`.
+async fn process_stream(
+    State(AppState { orchestrator, drain, .. }): State<AppState>,
+    request: Result<Json<ProcessRequest>, JsonRejection>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    let Json(request) = request?;
+    ApiError::admit(&drain)?;
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::task::spawn_blocking(move || {
+        // A client that hangs up only stops receiving; the run still finishes.
+        let submission = Submission { timeout: request.timeout, run_id: request.run_id, priority: request.priority };
+        orchestrator.process_streaming_with(request.command, &request.context_id, submission, |event| {
+            let _ = tx.send(event);
+        });
+    });
+    let events = UnboundedReceiverStream::new(rx).map(|event| {
+        let data = serde_json::to_value(&event).unwrap_or_else(|err| {
+            warn!("Event serialization failed: {}", err);
+            serde_json::Value::Null
+        });
+        let name = data.get("event").and_then(|name| name.as_str()).unwrap_or("message").to_string();
+        Ok(Event::default().event(name).data(data.to_string()))
+    });
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+async fn list_contexts(State(AppState { orchestrator, .. }): State<AppState>) -> Json<Vec<Context>> {
+    Json(orchestrator.context_ids().iter().filter_map(|id| orchestrator.get_context(id)).collect())
+}
+
+async fn get_context(State(AppState { orchestrator, .. }): State<AppState>, Path(id): Path<String>) -> Result<Json<Context>, ApiError> {
+    orchestrator.get_context(&id).map(Json).ok_or_else(|| ApiError::missing_context(&id))
+}
+
+/// The context's run in progress and live metrics; answers during a run without
+/// waiting for the subtask in flight.
+async fn observe_context(
+    State(AppState { orchestrator, .. }): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<ContextView>, ApiError> {
+    orchestrator.observe(&id).map(Json).ok_or_else(|| ApiError::missing_context(&id))
+}
+
+/// Moves the context to the trash, from where `/contexts/{id}/restore` brings it back.
+async fn delete_context(State(AppState { orchestrator, .. }): State<AppState>, Path(id): Path<String>) -> Result<StatusCode, ApiError> {
+    orchestrator.delete_context(&id)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn restore_context(State(AppState { orchestrator, .. }): State<AppState>, Path(id): Path<String>) -> Result<Json<Context>, ApiError> {
+    Ok(Json(orchestrator.restore_context(&id)?))
+}
+
+/// `{"purged": [<context_id>, ...]}`.
+async fn purge_trash(
+    State(AppState { orchestrator, .. }): State<AppState>,
+    request: Result<Json<PurgeRequest>, JsonRejection>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let Json(request) = request?;
+    let purged = orchestrator.purge_trash(request.older_than)?;
+    Ok(Json(serde_json::json!({ "purged": purged })))
+}
+
+#[derive(Debug, Deserialize)]
+struct EventsQuery {
+    context_id: Option<String>,
+}
+
+/// A WebSocket of `BusEvent` JSON text messages, optionally only those for
+/// `?context_id=`. A client that reads slowly loses the oldest messages rather
+/// than holding up dispatching; gaps in `seq` show where.
+async fn events_socket(
+    State(AppState { events, .. }): State<AppState>,
+    Query(query): Query<EventsQuery>,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    // Subscribed before the handshake completes, so nothing published after it is missed.
+    let subscription = events.subscribe();
+    upgrade.on_upgrade(move |socket| forward_events(socket, subscription, query.context_id))
+}
+
+async fn forward_events(mut socket: WebSocket, mut subscription: Subscription, context_id: Option<String>) {
+    loop {
+        tokio::select! {
+            event = subscription.recv() => {
+                let Some(event) = event else { break };
+                if context_id.as_ref().is_some_and(|id| *id != event.context_id) {
+                    continue;
+                }
+                let text = match serde_json::to_string(&event) {
+                    Ok(text) => text,
+                    Err(err) => {
+                        warn!("Event serialization failed: {}", err);
+                        continue;
+                    }
+                };
+                if socket.send(Message::Text(text.into())).await.is_err() {
+                    break;
+                }
+            }
+            incoming = socket.recv() => match incoming {
+                None | Some(Err(_)) | Some(Ok(Message::Close(_))) => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
@@ -0,0 +1,276 @@
+use crate::tenant::split_key;
+use crate::OrchestratorError;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// A run that shutdown stopped before its plan was done.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InterruptedRun {
+    pub context_id: String,
+    pub tenant: String,
+    pub command: String,
+    /// Subtasks that had finished, and those left undispatched.
+    pub finished: usize,
+    pub remaining: usize,
+}
+
+/// What `CognitiveOrchestrator::shutdown` did.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ShutdownReport {
+    /// Runs in flight when shutdown began that completed within the grace period.
+    pub drained: usize,
+    pub interrupted: Vec<InterruptedRun>,
+    /// Contexts snapshotted, and where they were persisted, if anywhere.
+    pub snapshots: usize,
+    pub persisted: Option<PathBuf>,
+    /// Whether they were put into the context store.
+    pub stored: bool,
+    /// Anomalies still not stored after the final flush.
+    pub pending_anomalies: usize,
+    /// Worker pool threads still running a job when its drain timeout ran out.
+    pub busy_workers: usize,
+}
+
+#[derive(Debug, Clone)]
+struct InFlight {
+    key: String,
+    command: String,
+    finished: usize,
+    remaining: usize,
+}
+
+impl InFlight {
+    fn interrupted(self) -> InterruptedRun {
+        let (tenant, context_id) = split_key(&self.key);
+        InterruptedRun {
+            context_id: context_id.to_string(),
+            tenant: tenant.to_string(),
+            command: self.command,
+            finished: self.finished,
+            remaining: self.remaining,
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    /// When the grace period ends; set once shutdown begins.
+    deadline: Option<Instant>,
+    /// Set by `CognitiveOrchestrator::shutdown`; every run still going stops at its next step.
+    closed: bool,
+    next_id: u64,
+    runs: BTreeMap<u64, InFlight>,
+    drained: usize,
+    interrupted: Vec<InterruptedRun>,
+}
+
+impl State {
+    fn expired(&self) -> bool {
+        self.closed || self.deadline.is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
+/// The orchestrator's runs in flight and whether it is shutting down. Clones share
+/// the state, so a caller that shares the orchestrator behind a lock can begin a
+/// shutdown and wait for runs to drain without holding the lock.
+#[derive(Clone, Default)]
+pub struct ShutdownHandle(Arc<(Mutex<State>, Condvar)>);
+
+impl ShutdownHandle {
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.0 .0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Stops admitting runs; those in flight may start new subtasks for `grace`.
+    /// Beginning again never extends the deadline.
+    pub fn begin(&self, grace: Duration) {
+        let mut state = self.state();
+        let deadline = Instant::now() + grace;
+        state.deadline = Some(state.deadline.map_or(deadline, |earlier| earlier.min(deadline)));
+        self.0 .1.notify_all();
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.state().deadline.is_some()
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.state().runs.len()
+    }
+
+    /// `ShuttingDown` once shutdown has begun.
+    pub fn check(&self) -> Result<(), OrchestratorError> {
+        match self.state().deadline {
+            Some(_) => Err(OrchestratorError::ShuttingDown),
+            None => Ok(()),
+        }
+    }
+
+    /// Blocks until shutdown has begun and no run is in flight, or its grace period
+    /// has run out. Returns whether every run drained.
+    pub fn wait_idle(&self) -> bool {
+        let mut state = self.state();
+        loop {
+            let Some(deadline) = state.deadline else {
+                state = self.0 .1.wait(state).unwrap_or_else(|e| e.into_inner());
+                continue;
+            };
+            if state.runs.is_empty() {
+                return true;
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            state = self.0 .1.wait_timeout(state, deadline - now).unwrap_or_else(|e| e.into_inner()).0;
+        }
+    }
+
+    /// Counts a run on `key` as in flight, unless shutdown has begun.
+    pub(crate) fn admit(&self, key: &str, command: &str) -> Result<u64, OrchestratorError> {
+        let mut state = self.state();
+        if state.deadline.is_some() {
+            return Err(OrchestratorError::ShuttingDown);
+        }
+        let id = state.next_id;
+        state.next_id += 1;
+        state.runs.insert(id, InFlight { key: key.to_string(), command: command.to_string(), finished: 0, remaining: 0 });
+        Ok(id)
+    }
+
+    /// Records how many of the run's subtasks have finished and how many are left.
+    pub(crate) fn progress(&self, id: u64, finished: usize, remaining: usize) {
+        if let Some(run) = self.state().runs.get_mut(&id) {
+            run.finished = finished;
+            run.remaining = remaining;
+        }
+    }
+
+    /// Whether the run may start its next wave. After the grace period it may not,
+    /// and is reported as interrupted.
+    pub(crate) fn proceed(&self, id: u64) -> bool {
+        let mut state = self.state();
+        if !state.runs.contains_key(&id) {
+            return false;
+        }
+        if !state.expired() {
+            return true;
+        }
+        let run = state.runs.remove(&id).map(InFlight::interrupted);
+        state.interrupted.extend(run);
+        self.0 .1.notify_all();
+        false
+    }
+
+    /// `timeout` no longer than what is left of the grace period.
+    pub(crate) fn cap(&self, timeout: Option<Duration>) -> Option<Duration> {
+        let Some(deadline) = self.state().deadline else {
+            return timeout;
+        };
+        let left = deadline.saturating_duration_since(Instant::now());
+        Some(timeout.map_or(left, |timeout| timeout.min(left)))
+    }
+
+    /// The run is over; a no-op for one already reported as interrupted.
+    pub(crate) fn finish(&self, id: u64) {
+        let mut state = self.state();
+        if state.runs.remove(&id).is_some() && state.deadline.is_some() {
+            state.drained += 1;
+        }
+        self.0 .1.notify_all();
+    }
+
+    /// Ends the shutdown: runs still in flight are interrupted, with those stopped
+    /// during the grace period, and they stop at their next step.
+    pub(crate) fn close(&self) -> (usize, Vec<InterruptedRun>) {
+        let mut state = self.state();
+        state.closed = true;
+        let runs = std::mem::take(&mut state.runs);
+        let mut interrupted = std::mem::take(&mut state.interrupted);
+        interrupted.extend(runs.into_values().map(InFlight::interrupted));
+        self.0 .1.notify_all();
+        (std::mem::take(&mut state.drained), interrupted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::streaming::ProcessRun;
+    use crate::{AgentResult, CognitiveOrchestrator, MockBackend, ProcessEvent};
+    use std::collections::HashMap;
+    use std::thread;
+
+    fn orchestrator() -> CognitiveOrchestrator {
+        let mock = MockBackend::new().plan("ask", ["post one", "post two", "post three"]).on("post", |rest| {
+            thread::sleep(Duration::from_millis(50));
+            AgentResult { output: rest.to_string(), status: true, metadata: HashMap::new(), error: None }
+        });
+        CognitiveOrchestrator::builder().backend(Arc::new(mock)).build().unwrap()
+    }
+
+    #[test]
+    fn runs_in_flight_finish_and_later_ones_are_rejected() {
+        let path = std::env::temp_dir().join(format!("ace-shutdown-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let orch = orchestrator().with_eviction_flush(&path);
+        let handle = orch.shutdown_handle();
+        let shared = Arc::new(orch);
+        let events = CognitiveOrchestrator::process_channel(shared.clone(), "ask".to_string(), "ctx1".to_string());
+        assert!(matches!(events.recv().unwrap(), ProcessEvent::PlanReady { .. }));
+
+        handle.begin(Duration::from_secs(30));
+        assert!(handle.wait_idle());
+        let outputs: Vec<String> = events
+            .iter()
+            .filter_map(|event| match event {
+                ProcessEvent::Completed { output, .. } => Some(serde_json::from_str(&output).unwrap()),
+                _ => None,
+            })
+            .next()
+            .unwrap();
+        assert_eq!(outputs, ["one", "two", "three"]);
+
+        let orch = &shared;
+        assert!(orch.process("ask".to_string(), "ctx2").starts_with(r#"["Shutdown Error"#));
+        assert_eq!(orch.process_for("acme", "ask".to_string(), "ctx1"), Err(OrchestratorError::ShuttingDown));
+        assert!(orch.get_context("ctx2").is_none());
+        let report = orch.shutdown(Duration::from_secs(30));
+        let persisted = std::fs::read_to_string(&path);
+        let _ = std::fs::remove_file(&path);
+        assert_eq!((report.drained, report.interrupted.len(), report.snapshots), (1, 0, 1));
+        assert_eq!(report.persisted.as_deref(), Some(path.as_path()));
+        assert!(persisted.unwrap().contains("ctx1"));
+        assert_eq!(orch.get_snapshots("ctx1").len(), 1);
+    }
+
+    #[test]
+    fn runs_past_the_grace_period_are_interrupted() {
+        let orch = orchestrator();
+        let mut run = ProcessRun::new("ask".to_string(), "ctx1".to_string(), None);
+        assert!(matches!(run.next_event(&orch), Some(ProcessEvent::PlanReady { .. })));
+        while !matches!(run.next_event(&orch), Some(ProcessEvent::SubtaskFinished { .. })) {}
+
+        let report = orch.shutdown(Duration::ZERO);
+        assert_eq!(report.drained, 0);
+        assert_eq!(
+            report.interrupted,
+            [InterruptedRun {
+                context_id: "ctx1".to_string(),
+                tenant: crate::DEFAULT_TENANT.to_string(),
+                command: "ask".to_string(),
+                finished: 1,
+                remaining: 2,
+            }]
+        );
+        // The run completes at its next step with only what it had dispatched.
+        let completed = std::iter::from_fn(|| run.next_event(&orch)).last().unwrap();
+        let ProcessEvent::Completed { output, .. } = completed else { panic!("{:?}", completed) };
+        assert_eq!(output, r#"["one"]"#);
+        assert_eq!(orch.get_history("ctx1", None).len(), 1);
+        assert!(report.persisted.is_none());
+    }
+}
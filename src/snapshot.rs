@@ -0,0 +1,327 @@
+use crate::{Context, Goal, GoalStatus, ViralMetrics};
+use chrono::{DateTime, Utc};
+use pyo3::prelude::*;
+use pythonize::pythonize;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Snapshots kept per context with auto-snapshots on: two per run.
+pub const DEFAULT_SNAPSHOT_LIMIT: usize = 64;
+
+static NEXT_SNAPSHOT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// A context frozen at `taken_at`. Clones share the copy, so handing snapshots
+/// out is cheap; ids increase in the order snapshots are taken.
+#[pyclass(module = "sovereign_cli")]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ContextSnapshot {
+    pub snapshot_id: u64,
+    pub taken_at: DateTime<Utc>,
+    pub context: Arc<Context>,
+}
+
+/// One memory vector, identified by its index and L2 norm.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MemoryVectorChange {
+    pub index: usize,
+    pub norm: f64,
+}
+
+/// Each `ViralMetrics` field's change, later minus earlier.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct MetricsDelta {
+    pub virality_score: f64,
+    pub engagement_nodes: i64,
+    pub hook_rate: f64,
+    pub amplification_factor: f64,
+    pub quantum_fidelity: f64,
+}
+
+impl MetricsDelta {
+    pub fn between(before: &ViralMetrics, after: &ViralMetrics) -> Self {
+        let nodes = |metrics: &ViralMetrics| i64::try_from(metrics.engagement_nodes).unwrap_or(i64::MAX);
+        Self {
+            virality_score: after.virality_score - before.virality_score,
+            engagement_nodes: nodes(after) - nodes(before),
+            hook_rate: after.hook_rate - before.hook_rate,
+            amplification_factor: after.amplification_factor - before.amplification_factor,
+            quantum_fidelity: after.quantum_fidelity - before.quantum_fidelity,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// What changed from one snapshot to another. Goals are identified by id; memory
+/// vectors are compared by index, so one overwritten in place shows up as both
+/// removed and added.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ContextDiff {
+    pub context_id: String,
+    pub from_snapshot: u64,
+    pub to_snapshot: u64,
+    pub goals_added: Vec<String>,
+    pub goals_removed: Vec<String>,
+    /// Goals present in both whose status changed from active to completed.
+    pub goals_completed: Vec<String>,
+    pub memory_added: Vec<MemoryVectorChange>,
+    pub memory_removed: Vec<MemoryVectorChange>,
+    pub metrics: MetricsDelta,
+}
+
+impl ContextDiff {
+    pub fn is_empty(&self) -> bool {
+        self.goals_added.is_empty()
+            && self.goals_removed.is_empty()
+            && self.goals_completed.is_empty()
+            && self.memory_added.is_empty()
+            && self.memory_removed.is_empty()
+            && self.metrics.is_zero()
+    }
+}
+
+fn norm(vec: &[f64]) -> f64 {
+    vec.iter().map(|x| x * x).sum::<f64>().sqrt()
+}
+
+impl Context {
+    pub fn snapshot(&self) -> ContextSnapshot {
+        self.snapshot_at(Utc::now())
+    }
+
+    /// `snapshot` with the timestamp given, e.g. from the orchestrator's clock.
+    pub fn snapshot_at(&self, taken_at: DateTime<Utc>) -> ContextSnapshot {
+        ContextSnapshot {
+            snapshot_id: NEXT_SNAPSHOT_ID.fetch_add(1, Ordering::Relaxed),
+            taken_at,
+            context: Arc::new(self.clone()),
+        }
+    }
+}
+
+impl ContextSnapshot {
+    /// The changes from this snapshot to `other`.
+    pub fn diff(&self, other: &ContextSnapshot) -> ContextDiff {
+        let (before, after) = (&*self.context, &*other.context);
+        let only_in = |goals: &Context, other: &Context| -> Vec<String> {
+            goals.active_goals.iter().filter(|goal| other.goal(&goal.id).is_none()).map(|goal| goal.id.clone()).collect()
+        };
+        let goals_completed = after
+            .active_goals
+            .iter()
+            .filter(|goal| goal.status == GoalStatus::Completed && before.goal(&goal.id).is_some_and(Goal::is_active))
+            .map(|goal| goal.id.clone())
+            .collect();
+
+        let mut memory_added = vec![];
+        let mut memory_removed = vec![];
+        for index in 0..before.memory_vectors.len().max(after.memory_vectors.len()) {
+            let (old, new) = (before.memory_vectors.get(index), after.memory_vectors.get(index));
+            if old == new {
+                continue;
+            }
+            if let Some(old) = old {
+                memory_removed.push(MemoryVectorChange { index, norm: norm(&old) });
+            }
+            if let Some(new) = new {
+                memory_added.push(MemoryVectorChange { index, norm: norm(&new) });
+            }
+        }
+
+        ContextDiff {
+            context_id: after.context_id.clone(),
+            from_snapshot: self.snapshot_id,
+            to_snapshot: other.snapshot_id,
+            goals_added: only_in(after, before),
+            goals_removed: only_in(before, after),
+            goals_completed,
+            memory_added,
+            memory_removed,
+            metrics: MetricsDelta::between(&before.viral_metrics, &after.viral_metrics),
+        }
+    }
+}
+
+#[pymethods]
+impl ContextSnapshot {
+    #[getter(snapshot_id)]
+    fn py_snapshot_id(&self) -> u64 {
+        self.snapshot_id
+    }
+
+    #[getter(taken_at)]
+    fn py_taken_at(&self) -> DateTime<Utc> {
+        self.taken_at
+    }
+
+    #[getter(context)]
+    fn py_context(&self) -> Context {
+        (*self.context).clone()
+    }
+
+    /// The changes from this snapshot to `other`, as a dict.
+    #[pyo3(name = "diff")]
+    fn py_diff(&self, py: Python, other: &ContextSnapshot) -> PyResult<PyObject> {
+        Ok(pythonize(py, &self.diff(other))?)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "ContextSnapshot(snapshot_id={}, context_id={:?}, taken_at={})",
+            self.snapshot_id,
+            self.context.context_id,
+            self.taken_at.to_rfc3339()
+        )
+    }
+}
+
+/// Per-context snapshots, oldest first, each list capped at `limit`.
+pub struct ContextSnapshots {
+    limit: usize,
+    snapshots: HashMap<String, VecDeque<ContextSnapshot>>,
+}
+
+impl Default for ContextSnapshots {
+    fn default() -> Self {
+        Self::new(DEFAULT_SNAPSHOT_LIMIT)
+    }
+}
+
+impl ContextSnapshots {
+    pub fn new(limit: usize) -> Self {
+        Self { limit, snapshots: HashMap::new() }
+    }
+
+    pub fn record(&mut self, snapshot: ContextSnapshot) {
+        if self.limit == 0 {
+            return;
+        }
+        let snapshots = self.snapshots.entry(snapshot.context.key()).or_default();
+        if snapshots.len() >= self.limit {
+            snapshots.pop_front();
+        }
+        snapshots.push_back(snapshot);
+    }
+
+    pub fn get(&self, context_id: &str) -> Vec<ContextSnapshot> {
+        self.snapshots.get(context_id).map_or_else(Vec::new, |snapshots| snapshots.iter().cloned().collect())
+    }
+
+    pub fn count(&self, context_id: &str) -> usize {
+        self.snapshots.get(context_id).map_or(0, VecDeque::len)
+    }
+
+    pub fn remove(&mut self, context_id: &str) {
+        self.snapshots.remove(context_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MemoryVectors, MetricsHistory};
+
+    fn context() -> Context {
+        Context {
+            context_id: "ctx1".to_string(),
+            tenant: crate::DEFAULT_TENANT.to_string(),
+            active_goals: vec![Goal::new("reach", "reach 10k views"), Goal::new("retain", "keep followers")],
+            memory_vectors: MemoryVectors::try_from(vec![vec![3.0, 4.0], vec![1.0, 0.0]]).unwrap(),
+            memory_texts: vec![],
+            memory_payloads: vec![],
+            memory_meta: vec![],
+            memory_decay: 0.0,
+            viral_metrics: ViralMetrics::default(),
+            viral_config: None,
+            propagation: None,
+            metrics_history: MetricsHistory::default(),
+            lineage: None,
+            attributes: Default::default(),
+            created_at: DateTime::UNIX_EPOCH,
+            last_accessed: DateTime::UNIX_EPOCH,
+            extra: Default::default(),
+        }
+    }
+
+    #[test]
+    fn unchanged_context_diffs_empty() {
+        let context = context();
+        let (before, after) = (context.snapshot(), context.snapshot());
+        assert!(after.snapshot_id > before.snapshot_id);
+        assert!(before.diff(&after).is_empty());
+    }
+
+    #[test]
+    fn diff_reports_goals_memory_and_metrics() {
+        let mut context = context();
+        let before = context.snapshot();
+        context.active_goals.remove(0);
+        context.active_goals[0].status = crate::GoalStatus::Completed;
+        context.active_goals.push(Goal::new("convert", "sell merch"));
+        context.memory_vectors = MemoryVectors::try_from(vec![vec![3.0, 4.0], vec![0.0, 2.0], vec![1.0, 1.0]]).unwrap();
+        context.viral_metrics.engagement_nodes -= 2;
+        context.viral_metrics.hook_rate += 0.25;
+        let diff = before.diff(&context.snapshot());
+
+        assert_eq!(diff.goals_added, ["convert"]);
+        assert_eq!(diff.goals_removed, ["reach"]);
+        assert_eq!(diff.goals_completed, ["retain"]);
+        assert_eq!(diff.memory_removed, [MemoryVectorChange { index: 1, norm: 1.0 }]);
+        let added: Vec<usize> = diff.memory_added.iter().map(|change| change.index).collect();
+        assert_eq!(added, [1, 2]);
+        assert_eq!(diff.memory_added[0].norm, 2.0);
+        assert_eq!(diff.metrics.engagement_nodes, -2);
+        assert!((diff.metrics.hook_rate - 0.25).abs() < 1e-12);
+        assert_eq!(diff.metrics.virality_score, 0.0);
+
+        let json = serde_json::to_value(&diff).unwrap();
+        assert_eq!(json["metrics"]["engagement_nodes"], -2);
+        assert_eq!(json["memory_removed"][0]["index"], 1);
+    }
+
+    #[test]
+    fn store_keeps_the_latest_per_context() {
+        let mut snapshots = ContextSnapshots::new(2);
+        let context = context();
+        let taken: Vec<u64> = (0..3)
+            .map(|_| {
+                let snapshot = context.snapshot();
+                let id = snapshot.snapshot_id;
+                snapshots.record(snapshot);
+                id
+            })
+            .collect();
+        let kept: Vec<u64> = snapshots.get("ctx1").iter().map(|snapshot| snapshot.snapshot_id).collect();
+        assert_eq!(kept, taken[1..]);
+        assert!(snapshots.get("ctx2").is_empty());
+    }
+
+    #[test]
+    fn auto_snapshots_bracket_each_run() {
+        use crate::{AgentResult, CognitiveOrchestrator, MockBackend};
+
+        let mock = MockBackend::new().on("grow", |_| AgentResult {
+            output: "grown".to_string(),
+            status: true,
+            metadata: HashMap::new(),
+            error: None,
+        });
+        let orch = CognitiveOrchestrator::builder().backend(Arc::new(mock)).learning(false).build().unwrap();
+        orch.process("grow".to_string(), "ctx1");
+        assert!(orch.get_snapshots("ctx1").is_empty());
+
+        let orch = orch.with_auto_snapshots(true);
+        orch.process("grow".to_string(), "ctx1");
+        orch.add_memory("ctx1", vec![0.0, 5.0]).unwrap();
+        orch.process("grow".to_string(), "ctx1");
+        let snapshots = orch.get_snapshots("ctx1");
+        assert_eq!(snapshots.len(), 4);
+        assert!(snapshots[0].diff(&snapshots[1]).is_empty());
+        let between_runs = snapshots[1].diff(&snapshots[2]);
+        assert_eq!(between_runs.memory_added, [MemoryVectorChange { index: 0, norm: 5.0 }]);
+    }
+}
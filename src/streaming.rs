@@ -0,0 +1,829 @@
+use crate::dispatch_policy::blocked_result;
+use crate::history::secs;
+use crate::input::check_run_id;
+use crate::journal::{self, JournalRecord};
+use crate::run_lock::RunGuard;
+use crate::submission::{Priority, QueueSlot, Submission};
+use crate::tenant::split_key;
+use crate::{
+    AgentKind, AgentResult, Blackboard, Budget, BudgetStatus, BusPayload, CancelToken, CognitiveOrchestrator, DebugDecision,
+    DebugStrategies, DebugStrategy, Goal, GoalExpiry, OrchestratorError, Plan, PlanFinding, ProcessReport, RunDiff, RunTiming,
+    ViralMetrics,
+};
+use chrono::{DateTime, Utc};
+use pyo3::prelude::*;
+use pythonize::pythonize;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use tracing::{info_span, warn, Span};
+
+/// Progress of one `process` run, tagged with the run's id. `at` is the monotonic
+/// time since the run started; it never decreases from one event to the next.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ProcessEvent {
+    /// Subtasks in execution order.
+    PlanReady {
+        run_id: String,
+        subtasks: Vec<String>,
+        #[serde(serialize_with = "secs::serialize")]
+        at: Duration,
+    },
+    SubtaskStarted {
+        run_id: String,
+        subtask: String,
+        #[serde(serialize_with = "secs::serialize")]
+        at: Duration,
+    },
+    SubtaskFinished {
+        run_id: String,
+        result: AgentResult,
+        #[serde(serialize_with = "secs::serialize")]
+        at: Duration,
+    },
+    /// `self_debug` replaced this subtask with `subtasks`, which run next, before
+    /// the rest of the plan.
+    ReplanTriggered {
+        run_id: String,
+        subtask: String,
+        subtasks: Vec<String>,
+        #[serde(serialize_with = "secs::serialize")]
+        at: Duration,
+    },
+    /// The JSON array `process` returns; `replanned` lists the subtasks that
+    /// came from re-planning. `budget` is the run's LLM spend: against the run's
+    /// own budget if it had one, else the context's. `coalesced` is set when the
+    /// events are those of an identical run `coalesce_window` joined this one to.
+    Completed {
+        run_id: String,
+        output: String,
+        replanned: Vec<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        budget: Option<BudgetStatus>,
+        #[serde(skip_serializing_if = "std::ops::Not::not")]
+        coalesced: bool,
+        #[serde(serialize_with = "secs::serialize")]
+        at: Duration,
+    },
+}
+
+impl ProcessEvent {
+    pub fn at(&self) -> Duration {
+        match self {
+            ProcessEvent::PlanReady { at, .. }
+            | ProcessEvent::SubtaskStarted { at, .. }
+            | ProcessEvent::SubtaskFinished { at, .. }
+            | ProcessEvent::ReplanTriggered { at, .. }
+            | ProcessEvent::Completed { at, .. } => *at,
+        }
+    }
+
+    pub fn run_id(&self) -> &str {
+        match self {
+            ProcessEvent::PlanReady { run_id, .. }
+            | ProcessEvent::SubtaskStarted { run_id, .. }
+            | ProcessEvent::SubtaskFinished { run_id, .. }
+            | ProcessEvent::ReplanTriggered { run_id, .. }
+            | ProcessEvent::Completed { run_id, .. } => run_id,
+        }
+    }
+
+    /// What a run joined to the finished run `report` describes is shown: its
+    /// plan, each result and its completion, all at the time that run took.
+    pub(crate) fn coalesced(report: &ProcessReport) -> Vec<ProcessEvent> {
+        let run_id = report.run_id.clone().unwrap_or_default();
+        let at = Duration::try_from_secs_f64(report.timing.total_ms / 1000.0).unwrap_or_default();
+        let replanned = report
+            .results
+            .iter()
+            .zip(&report.subtasks)
+            .filter(|(result, _)| result.get_str("replanned_from").is_some())
+            .map(|(_, subtask)| subtask.clone())
+            .collect();
+        let mut events = vec![ProcessEvent::PlanReady { run_id: run_id.clone(), subtasks: report.plan.clone(), at }];
+        events.extend(report.results.iter().map(|result| ProcessEvent::SubtaskFinished { run_id: run_id.clone(), result: result.clone(), at }));
+        events.push(ProcessEvent::Completed { run_id, output: report.legacy_output(), replanned, budget: None, coalesced: true, at });
+        events
+    }
+}
+
+/// A planned subtask; re-planned ones remember the subtask they replace, and
+/// ones the dispatch policy blocked, or whose references did not resolve, why.
+#[derive(Clone)]
+struct Step {
+    subtask: String,
+    replanned_from: Option<String>,
+    blocked: Option<OrchestratorError>,
+}
+
+impl Step {
+    /// `blocked` is the dispatch policy's review of `plan`, in execution order.
+    fn waves(plan: &Plan, replanned_from: Option<&str>, blocked: Vec<Option<OrchestratorError>>) -> Vec<Vec<Step>> {
+        let mut blocked = blocked.into_iter();
+        plan.waves()
+            .into_iter()
+            .map(|wave| {
+                wave.into_iter()
+                    .map(|node| Step {
+                        subtask: node.subtask.clone(),
+                        replanned_from: replanned_from.map(str::to_string),
+                        blocked: blocked.next().flatten(),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+enum Stage {
+    Plan,
+    Start,
+    Dispatch,
+    Done,
+}
+
+/// `process` as a state machine yielding one event per step, so callers can
+/// interleave other work (or release the orchestrator) between steps. Each plan
+/// wave is one dispatch step. The run's context stays pinned, and its run lock
+/// held, from the first step until completion or `abandon`.
+pub(crate) struct ProcessRun {
+    command: String,
+    context_id: String,
+    timeout: Option<Duration>,
+    started: Instant,
+    /// By the orchestrator's clock, once the run has taken its first step.
+    started_at: Option<DateTime<Utc>>,
+    stage: Stage,
+    waves: VecDeque<Vec<Step>>,
+    replans: usize,
+    max_replans: usize,
+    replanned: Vec<String>,
+    /// Each re-planned subtask with the steps that replaced it.
+    replacements: Vec<(String, Vec<String>)>,
+    /// Subtasks as first planned, and what every dispatched one returned.
+    plan: Vec<String>,
+    results: Vec<AgentResult>,
+    /// The subtask each of `results` ran.
+    subtasks: Vec<String>,
+    outputs: Vec<String>,
+    /// Failed subtasks that no re-plan replaced.
+    unrecovered: usize,
+    /// What `self_debug` decided for each failed subtask, in dispatch order.
+    debug: Vec<DebugDecision>,
+    /// What the plan validator found in the first plan.
+    plan_findings: Vec<PlanFinding>,
+    blackboard: Blackboard,
+    /// Set when shutdown stopped the run before its plan was done.
+    interrupted: bool,
+    /// Set when `cancel` stopped the run before its plan was done.
+    cancelled: bool,
+    plan_error: Option<OrchestratorError>,
+    budget: Option<Budget>,
+    /// The run's id with `ShutdownHandle`, once admitted.
+    admitted: Option<u64>,
+    /// Registered with the orchestrator's `Cancellations` once admitted.
+    cancel: Option<CancelToken>,
+    /// The context's run lock, taken at the first step unless the caller took it.
+    lock: Option<RunGuard>,
+    priority: Priority,
+    /// The run's turn in the submission queue, taken before its lock unless the
+    /// caller took the lock.
+    slot: Option<QueueSlot>,
+    queue_wait: Duration,
+    pending: VecDeque<ProcessEvent>,
+    /// Since `started`, once the run completed.
+    elapsed: Option<Duration>,
+    /// By the orchestrator's clock, once the run completed its plan.
+    finished_at: Option<DateTime<Utc>>,
+    /// The context's viral metrics once the run completed its plan.
+    metrics: Option<ViralMetrics>,
+    diff: Option<RunDiff>,
+    /// Tags the run's events, results, spans and report, and keys its journal entries.
+    run_id: String,
+    /// Whether the caller chose `run_id`, which is then checked before planning.
+    supplied_run_id: bool,
+    /// Set once the run has journaled its plan.
+    journaled: bool,
+    /// The rest of a journaled run being resumed, run instead of planning.
+    resumed: Option<Plan>,
+    span: Span,
+}
+
+impl ProcessRun {
+    /// `timeout` overrides the orchestrator's subtask timeout for this run.
+    pub(crate) fn new(command: String, context_id: String, timeout: Option<Duration>) -> Self {
+        // The command is recorded redacted once the run has the orchestrator.
+        let run_id = uuid::Uuid::new_v4().to_string();
+        let span = info_span!("process", run_id = %run_id, context_id = %context_id, command = tracing::field::Empty);
+        Self {
+            command,
+            context_id,
+            timeout,
+            started: Instant::now(),
+            started_at: None,
+            stage: Stage::Plan,
+            waves: VecDeque::new(),
+            replans: 0,
+            max_replans: 0,
+            replanned: vec![],
+            replacements: vec![],
+            plan: vec![],
+            results: vec![],
+            subtasks: vec![],
+            outputs: vec![],
+            unrecovered: 0,
+            debug: vec![],
+            plan_findings: vec![],
+            blackboard: Blackboard::default(),
+            interrupted: false,
+            cancelled: false,
+            plan_error: None,
+            budget: None,
+            admitted: None,
+            cancel: None,
+            lock: None,
+            priority: Priority::Normal,
+            slot: None,
+            queue_wait: Duration::ZERO,
+            pending: VecDeque::new(),
+            elapsed: None,
+            finished_at: None,
+            metrics: None,
+            diff: None,
+            run_id,
+            supplied_run_id: false,
+            journaled: false,
+            resumed: None,
+            span,
+        }
+    }
+
+    /// A run as `submission` describes it.
+    pub(crate) fn submitted(command: String, context_id: String, submission: Submission) -> Self {
+        let run = Self::new(command, context_id, submission.timeout).with_priority(submission.priority);
+        match submission.run_id {
+            Some(run_id) => run.with_run_id(run_id),
+            None => run,
+        }
+    }
+
+    /// Runs `plan` as the rest of the journaled run `run_id`, without planning.
+    pub(crate) fn resuming(mut self, run_id: String, plan: Plan) -> Self {
+        self.span.record("run_id", run_id.as_str());
+        self.run_id = run_id;
+        self.resumed = Some(plan);
+        self
+    }
+
+    /// Tags the run with the caller's `run_id`, to correlate it with upstream systems.
+    pub(crate) fn with_run_id(mut self, run_id: String) -> Self {
+        self.span.record("run_id", run_id.as_str());
+        self.run_id = run_id;
+        self.supplied_run_id = true;
+        self
+    }
+
+    /// Waits in the submission queue at `priority` rather than `Normal`.
+    pub(crate) fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Limits this run's LLM subtasks to `budget`, on top of the context's budget.
+    pub(crate) fn with_budget(mut self, budget: Budget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Runs under `lock`, already taken on the run's context.
+    pub(crate) fn with_lock(mut self, lock: RunGuard) -> Self {
+        self.lock = Some(lock);
+        self
+    }
+
+    pub(crate) fn context_id(&self) -> &str {
+        &self.context_id
+    }
+
+    pub(crate) fn command(&self) -> &str {
+        &self.command
+    }
+
+    pub(crate) fn run_id(&self) -> &str {
+        &self.run_id
+    }
+
+    /// Whether `coalesce_window` may join the run to an identical one. Not when
+    /// it asks for something the other may not honour (its own id, timeout,
+    /// budget or priority), nor when it holds the context's lock already, which
+    /// the run it joined would wait on, nor when it resumes a journaled run.
+    pub(crate) fn coalesces(&self) -> bool {
+        !self.supplied_run_id
+            && self.timeout.is_none()
+            && self.budget.is_none()
+            && self.priority == Priority::Normal
+            && self.lock.is_none()
+            && self.resumed.is_none()
+    }
+
+    pub(crate) fn is_done(&self) -> bool {
+        matches!(self.stage, Stage::Done) && self.pending.is_empty()
+    }
+
+    /// Why the plan was rejected, or the run itself during shutdown, if either
+    /// was; the run then completes without dispatching anything.
+    pub(crate) fn plan_error(&self) -> Option<&OrchestratorError> {
+        self.plan_error.as_ref()
+    }
+
+    pub(crate) fn next_event(&mut self, orch: &CognitiveOrchestrator) -> Option<ProcessEvent> {
+        let span = self.span.clone();
+        let _entered = span.enter();
+        while self.pending.is_empty() {
+            match self.stage {
+                Stage::Plan => {
+                    if let Err(err) = self.supplied_run_id.then(|| check_run_id(&self.run_id)).transpose() {
+                        self.reject("Input Error", err);
+                        continue;
+                    }
+                    match orch.input_policy.clean(&self.command) {
+                        Ok(command) => self.command = command,
+                        Err(err) => {
+                            self.reject("Input Error", err);
+                            continue;
+                        }
+                    }
+                    self.span.record("command", orch.redactor.redacted(&self.command).as_str());
+                    // A caller holding the lock already has its turn; waiting here could deadlock.
+                    if self.lock.is_none() {
+                        match orch.queue.enter(self.priority) {
+                            Ok(slot) => {
+                                self.queue_wait = slot.waited();
+                                self.slot = Some(slot);
+                            }
+                            Err(err) => {
+                                self.reject("Queue Error", err);
+                                continue;
+                            }
+                        }
+                    }
+                    self.started_at = Some(orch.clock.now());
+                    match orch.drain.admit(&self.context_id, &self.command) {
+                        Ok(id) => {
+                            self.admitted = Some(id);
+                            self.cancel = Some(orch.cancellations.register(&self.context_id));
+                            if self.lock.is_none() {
+                                let lock = orch.run_locks.acquire(&self.context_id, &self.command, orch.clock.as_ref());
+                                self.lock = Some(lock);
+                            }
+                            let metrics = orch.contexts.read(&self.context_id, |context| context.viral_metrics.clone());
+                            let command = orch.redactor.redacted(&self.command);
+                            orch.live.begin(&self.context_id, &self.run_id, command, metrics.unwrap_or_default(), orch.clock.now());
+                        }
+                        Err(err) => {
+                            self.reject("Shutdown Error", err);
+                            continue;
+                        }
+                    }
+                    orch.pin(&self.context_id);
+                    orch.auto_snapshot(&self.context_id);
+                    if let Some(budget) = self.budget {
+                        orch.budgets.begin_run(&self.context_id, budget);
+                    }
+                    self.timeout = self.timeout.or(orch.subtask_timeout);
+                    self.max_replans = orch.max_replans;
+                    // Before planning, so expired goals stay out of the planner's input.
+                    let expired = orch.sweep_goals(&self.context_id).unwrap_or_default();
+                    let resumed = self.resumed.take();
+                    let planned = match &resumed {
+                        Some(plan) => Ok((plan.clone(), vec![])),
+                        None => orch.validated_plan(self.command.clone(), &self.context_id),
+                    };
+                    let reviewed = planned.and_then(|(plan, findings)| {
+                        self.plan_findings = findings;
+                        let blocked = orch.review_plan(&plan.subtasks())?;
+                        Ok((plan, blocked))
+                    });
+                    let resuming = resumed.is_some();
+                    match reviewed {
+                        Ok((plan, blocked)) => {
+                            self.waves = Step::waves(&plan, None, blocked).into();
+                            let subtasks = plan.subtasks();
+                            self.plan = subtasks.clone();
+                            if let Some(journal) = &orch.journal {
+                                let (waves, now) = (self.journaled_waves(), orch.clock.now());
+                                let record = match resumed {
+                                    Some(_) => JournalRecord::PlanResumed { run_id: self.run_id.clone(), waves, at: now },
+                                    None => journal::plan_started(&self.run_id, &self.context_id, &self.command, waves, now),
+                                };
+                                self.journaled = true;
+                                journal.record(record);
+                            }
+                            orch.live.update(&self.context_id, |run| run.remaining_steps = subtasks.len());
+                            self.push(|run_id, at| ProcessEvent::PlanReady { run_id, subtasks, at });
+                            if orch.goal_expiry == GoalExpiry::Replan && !resuming {
+                                self.replan_expired(orch, expired);
+                            }
+                        }
+                        Err(err) => {
+                            warn!("Plan rejected: {}", err);
+                            self.outputs.push(format!("Plan Error: {}", err));
+                            self.plan_error = Some(err);
+                        }
+                    }
+                    self.stage = Stage::Start;
+                }
+                Stage::Start => match self.waves.front() {
+                    // Past the shutdown grace period: complete with what has finished.
+                    Some(_) if !self.admitted.is_some_and(|id| orch.drain.proceed(id)) => {
+                        self.waves.clear();
+                        self.interrupted = true;
+                    }
+                    Some(_) if self.cancel.as_ref().is_some_and(CancelToken::is_cancelled) => {
+                        warn!("Run cancelled");
+                        self.waves.clear();
+                        self.cancelled = true;
+                    }
+                    Some(_) => {
+                        let wave = self.expand_next_wave(orch);
+                        let running: Vec<String> = wave.iter().map(|step| orch.redactor.redacted(&step.subtask)).collect();
+                        let remaining = self.waves.iter().map(Vec::len).sum::<usize>() - wave.len();
+                        orch.live.update(&self.context_id, |run| {
+                            run.running = running;
+                            run.remaining_steps = remaining;
+                        });
+                        for step in wave {
+                            self.push(|run_id, at| ProcessEvent::SubtaskStarted { run_id, subtask: step.subtask, at });
+                        }
+                        self.stage = Stage::Dispatch;
+                    }
+                    None => {
+                        let succeeded = self.plan_error.is_none()
+                            && !self.interrupted
+                            && !self.cancelled
+                            && !self.results.is_empty()
+                            && self.results.iter().all(|result| result.status);
+                        if succeeded {
+                            orch.learn_success(&self.context_id, &self.command, &self.plan, &self.outputs);
+                        }
+                        for (subtask, steps) in &self.replacements {
+                            if self.recovered(subtask, steps.len()) {
+                                orch.resolve_anomaly(&self.context_id, &self.command, subtask, steps);
+                            }
+                        }
+                        orch.auto_snapshot(&self.context_id);
+                        orch.unpin(&self.context_id);
+                        self.end_admission(orch);
+                        self.lock = None;
+                        self.slot = None;
+                        let budget = self.end_budget(orch).or_else(|| orch.get_budget(&self.context_id));
+                        let output =
+                            serde_json::to_string(&self.outputs).unwrap_or_else(|_| self.outputs.join("\n"));
+                        let replanned = self.replanned.clone();
+                        self.elapsed = Some(self.started.elapsed());
+                        self.finished_at = Some(orch.clock.now());
+                        self.metrics = orch.contexts.read(&self.context_id, |context| context.viral_metrics.clone());
+                        if orch.auto_diff {
+                            self.diff = orch.record_report(&self.context_id, &self.report(orch.clock.now()));
+                        }
+                        // One shutdown interrupted stays open, to be resumed like one a crash cut short.
+                        if !self.interrupted {
+                            self.journal_completed(orch, false);
+                        }
+                        self.push(|run_id, at| ProcessEvent::Completed { run_id, output, replanned, budget, coalesced: false, at });
+                        self.stage = Stage::Done;
+                    }
+                },
+                Stage::Dispatch => {
+                    let wave = self.waves.pop_front().unwrap_or_default();
+                    let runnable: Vec<String> =
+                        wave.iter().filter(|step| step.blocked.is_none()).map(|step| step.subtask.clone()).collect();
+                    let timeout = orch.drain.cap(self.timeout);
+                    if let Some(journal) = orch.journal.as_ref().filter(|_| self.journaled) {
+                        for subtask in &runnable {
+                            let (run_id, at) = (self.run_id.clone(), orch.clock.now());
+                            journal.record(JournalRecord::SubtaskDispatched { run_id, subtask: subtask.clone(), at });
+                        }
+                    }
+                    let dispatched = if runnable.is_empty() {
+                        vec![]
+                    } else {
+                        orch.dispatch_wave(&runnable, &self.context_id, timeout, &self.blackboard)
+                    };
+                    let mut dispatched = dispatched.into_iter();
+                    let mut results = vec![];
+                    for step in &wave {
+                        let completed = match &step.blocked {
+                            Some(err) => Some((blocked_result(err.clone()), Duration::ZERO)),
+                            None => dispatched.next(),
+                        };
+                        let Some((mut res, duration)) = completed else { continue };
+                        if let Some(from) = &step.replanned_from {
+                            res.metadata.insert("replanned_from".to_string(), serde_json::Value::from(from.as_str()));
+                        }
+                        res.set(AgentResult::RUN_ID, &self.run_id);
+                        orch.record_execution(&self.context_id, &self.command, &step.subtask, &res, duration);
+                        if let Some(journal) = orch.journal.as_ref().filter(|_| self.journaled) {
+                            journal.record(JournalRecord::finished(&self.run_id, &step.subtask, &res, orch.clock.now()));
+                        }
+                        self.subtasks.push(step.subtask.clone());
+                        if res.status && orch.agent_kind(&step.subtask) == Some(AgentKind::Llm) {
+                            self.blackboard.set(Blackboard::step_output(self.subtasks.len()), res.output.clone());
+                        }
+                        results.push(res);
+                    }
+                    // A cancelled run stops here, so its failures are not debugged.
+                    let cancelled = self.cancel.as_ref().is_some_and(CancelToken::is_cancelled);
+                    let mut replans = vec![];
+                    let mut aborted = None;
+                    // Every failure of the wave is debugged, not only the first.
+                    for (step, res) in wave.iter().zip(&results).filter(|_| !cancelled) {
+                        let debugged = match &step.blocked {
+                            // Nor are blocked subtasks, which a re-plan would only route around the policy,
+                            // or ones whose references did not resolve.
+                            Some(_) => orch.debug_strategies().decide(&step.subtask, res).map(|decision| (decision, None)),
+                            None => orch.debug_subtask(res, &self.command, &step.subtask, &self.context_id),
+                        };
+                        let Some((decision, plan)) = debugged else { continue };
+                        if decision.strategy == DebugStrategy::Abort && aborted.is_none() {
+                            aborted = Some(step.subtask.clone());
+                        }
+                        self.debug.push(decision);
+                        replans.extend(plan.map(|plan| (step.subtask.clone(), plan)));
+                    }
+                    if let Some(subtask) = aborted {
+                        warn!("Dropping the rest of the plan after {:?} failed", orch.redactor.redacted(&subtask));
+                        self.waves.clear();
+                        replans.clear();
+                    }
+
+                    for res in results {
+                        self.outputs.push(res.output.clone());
+                        self.unrecovered += usize::from(!res.status);
+                        self.results.push(res.clone());
+                        self.push(|run_id, at| ProcessEvent::SubtaskFinished { run_id, result: res, at });
+                    }
+                    // Spliced last first, so the replacements run in plan order.
+                    for (subtask, plan) in replans.into_iter().rev() {
+                        if self.replan(orch, subtask, plan) {
+                            self.unrecovered -= 1;
+                        }
+                    }
+                    let remaining = self.waves.iter().map(Vec::len).sum();
+                    if let Some(id) = self.admitted {
+                        orch.drain.progress(id, self.outputs.len(), remaining);
+                    }
+                    let metrics = orch.contexts.read(&self.context_id, |context| context.viral_metrics.clone());
+                    orch.live.update(&self.context_id, |run| {
+                        run.running.clear();
+                        run.completed_steps = self.outputs.len();
+                        run.remaining_steps = remaining;
+                        if let Some(metrics) = metrics {
+                            run.metrics = metrics;
+                        }
+                    });
+                    self.stage = Stage::Start;
+                }
+                Stage::Done => return None,
+            }
+        }
+        let event = self.pending.pop_front();
+        if let Some(event) = &event {
+            if matches!(event, ProcessEvent::Completed { .. }) && orch.runs.is_enabled() {
+                orch.runs.keep(orch.redact_report(self.report(orch.clock.now())));
+            }
+            let attributes = || orch.contexts.read(&self.context_id, |context| context.attributes.clone()).unwrap_or_default();
+            orch.events.publish(&self.context_id, attributes, || BusPayload::Process(event.clone()));
+        }
+        event
+    }
+
+    /// Expands the references in the next wave's subtasks from what earlier waves
+    /// wrote and the context's attributes, and returns the wave. A step whose
+    /// references do not resolve is blocked, as is one the dispatch policy denies
+    /// once expanded.
+    fn expand_next_wave(&mut self, orch: &CognitiveOrchestrator) -> Vec<Step> {
+        let Some(wave) = self.waves.front_mut() else { return vec![] };
+        let attributes = orch.contexts.read(&self.context_id, |context| context.attributes.clone()).unwrap_or_default();
+        for step in wave.iter_mut().filter(|step| step.blocked.is_none()) {
+            match self.blackboard.expand_with(&step.subtask, &attributes) {
+                Ok(expanded) if expanded != step.subtask => {
+                    step.blocked = orch.review_subtask(&expanded);
+                    step.subtask = expanded;
+                }
+                Ok(_) => {}
+                Err(err) => step.blocked = Some(err),
+            }
+        }
+        wave.clone()
+    }
+
+    /// Reviews `plan` and splices it in place of `subtask`, journaling the new
+    /// waves. Returns whether it did.
+    fn replan(&mut self, orch: &CognitiveOrchestrator, subtask: String, plan: Plan) -> bool {
+        let blocked = match orch.review_plan(&plan.subtasks()) {
+            Ok(blocked) => blocked,
+            Err(err) => {
+                warn!("Re-plan for {:?} rejected: {}", subtask, err);
+                return false;
+            }
+        };
+        if !self.splice(subtask.clone(), plan, blocked) {
+            return false;
+        }
+        if let Some(journal) = orch.journal.as_ref().filter(|_| self.journaled) {
+            let (run_id, waves, at) = (self.run_id.clone(), self.journaled_waves(), orch.clock.now());
+            journal.record(JournalRecord::Replanned { run_id, subtask, waves, at });
+        }
+        true
+    }
+
+    /// Has the debug agent re-plan for each goal the run's sweep expired, as for a
+    /// failed `goal:<id>` subtask, queueing the steps ahead of the plan.
+    fn replan_expired(&mut self, orch: &CognitiveOrchestrator, expired: Vec<Goal>) {
+        // The policy asks for a re-plan whatever the strategy for other failures is.
+        let strategies = DebugStrategies { other: DebugStrategy::Replan, ..orch.debug_strategies() };
+        for goal in expired {
+            let subtask = format!("goal:{}", goal.id);
+            let failure = AgentResult::fail(format!("Goal expired: {}", goal.description));
+            let debugged = orch.debug_subtask_with(&failure, &self.command, &subtask, &self.context_id, &strategies);
+            let Some((decision, plan)) = debugged else {
+                continue;
+            };
+            self.debug.push(decision);
+            if let Some(plan) = plan {
+                self.replan(orch, subtask, plan);
+            }
+        }
+    }
+
+    /// Queues `plan`, reviewed as `blocked`, ahead of the remaining waves in place
+    /// of the failed `subtask`, unless the run has used up its `max_replans`.
+    /// Returns whether it did.
+    fn splice(&mut self, subtask: String, plan: Plan, blocked: Vec<Option<OrchestratorError>>) -> bool {
+        if self.replans >= self.max_replans {
+            warn!("Re-plan limit of {} reached; keeping the plan for {:?}", self.max_replans, subtask);
+            return false;
+        }
+        self.replans += 1;
+        for wave in Step::waves(&plan, Some(&subtask), blocked).into_iter().rev() {
+            self.waves.push_front(wave);
+        }
+        let subtasks = plan.subtasks();
+        self.replanned.extend(subtasks.iter().cloned());
+        self.replacements.push((subtask.clone(), subtasks.clone()));
+        self.push(|run_id, at| ProcessEvent::ReplanTriggered { run_id, subtask, subtasks, at });
+        true
+    }
+
+    /// The waves left to run, as the journal records them.
+    fn journaled_waves(&self) -> Vec<Vec<String>> {
+        self.waves.iter().map(|wave| wave.iter().map(|step| step.subtask.clone()).collect()).collect()
+    }
+
+    /// Journals the end of a run that journaled its plan.
+    fn journal_completed(&self, orch: &CognitiveOrchestrator, abandoned: bool) {
+        let Some(journal) = orch.journal.as_ref().filter(|_| self.journaled) else { return };
+        let (run_id, success) = (self.run_id.clone(), !abandoned && self.succeeded());
+        journal.record(JournalRecord::PlanCompleted { run_id, success, abandoned, at: orch.clock.now() });
+    }
+
+    /// Whether the run completed its plan with no failure that re-planning did not replace.
+    fn succeeded(&self) -> bool {
+        self.plan_error.is_none() && !self.interrupted && !self.cancelled && self.unrecovered == 0
+    }
+
+    /// Whether all `steps` steps that replaced `subtask` ran and succeeded.
+    fn recovered(&self, subtask: &str, steps: usize) -> bool {
+        let replaced: Vec<&AgentResult> = self
+            .results
+            .iter()
+            .filter(|result| result.metadata.get("replanned_from").and_then(serde_json::Value::as_str) == Some(subtask))
+            .collect();
+        replaced.len() == steps && replaced.iter().all(|result| result.status)
+    }
+
+    /// Completes a run refused before planning, such as one started after shutdown
+    /// began or one whose command the input policy refuses.
+    fn reject(&mut self, label: &str, err: OrchestratorError) {
+        warn!("Run rejected: {}", err);
+        self.outputs.push(format!("{}: {}", label, err));
+        let output = serde_json::to_string(&self.outputs).unwrap_or_default();
+        self.plan_error = Some(err);
+        self.lock = None;
+        self.slot = None;
+        self.elapsed = Some(self.started.elapsed());
+        self.push(|run_id, at| ProcessEvent::Completed { run_id, output, replanned: vec![], budget: None, coalesced: false, at });
+        self.stage = Stage::Done;
+    }
+
+    /// What the run did, for one that has completed at `finished_at`.
+    pub(crate) fn report(&self, finished_at: DateTime<Utc>) -> ProcessReport {
+        let (tenant, context_id) = split_key(&self.context_id);
+        let error = self.plan_error.as_ref().and_then(|_| self.outputs.first().cloned());
+        ProcessReport {
+            context_id: context_id.to_string(),
+            tenant: tenant.to_string(),
+            command: self.command.clone(),
+            plan: self.plan.clone(),
+            results: self.results.clone(),
+            subtasks: self.subtasks.clone(),
+            replans: self.replans,
+            started_at: self.started_at.unwrap_or(finished_at),
+            finished_at: self.finished_at.unwrap_or(finished_at),
+            success: self.succeeded(),
+            cancelled: self.cancelled,
+            error,
+            debug: self.debug.clone(),
+            plan_findings: self.plan_findings.clone(),
+            blackboard: self.blackboard.clone(),
+            timing: RunTiming::new(self.elapsed.unwrap_or_else(|| self.started.elapsed()), self.queue_wait, &self.results),
+            metrics: self.metrics.clone(),
+            diff: self.diff.clone(),
+            run_id: Some(self.run_id.clone()),
+            redactions: 0,
+            coalesced: false,
+        }
+    }
+
+    /// Releases the context pin of a run that will not be driven to completion.
+    pub(crate) fn abandon(&mut self, orch: &CognitiveOrchestrator) {
+        if !matches!(self.stage, Stage::Plan | Stage::Done) {
+            self.journal_completed(orch, true);
+            orch.unpin(&self.context_id);
+            self.end_budget(orch);
+            self.end_admission(orch);
+        }
+        self.lock = None;
+        self.slot = None;
+        self.stage = Stage::Done;
+        self.pending.clear();
+    }
+
+    /// No longer counts the run as in flight.
+    fn end_admission(&mut self, orch: &CognitiveOrchestrator) {
+        if let Some(id) = self.admitted.take() {
+            orch.live.end(&self.context_id);
+            orch.drain.finish(id);
+        }
+        if let Some(cancel) = &self.cancel {
+            orch.cancellations.release(&self.context_id, cancel);
+        }
+    }
+
+    /// Ends the run's budget, if it has one, returning what it spent.
+    fn end_budget(&self, orch: &CognitiveOrchestrator) -> Option<BudgetStatus> {
+        self.budget.and_then(|_| orch.budgets.end_run(&self.context_id))
+    }
+
+    fn push(&mut self, event: impl FnOnce(String, Duration) -> ProcessEvent) {
+        self.pending.push_back(event(self.run_id.clone(), self.started.elapsed()));
+    }
+}
+
+/// Python iterator over a run's events, each a dict with an `event` key. Every
+/// `next()` advances the run by one step, with the GIL released.
+#[pyclass(module = "sovereign_cli")]
+pub struct ProcessStream {
+    orchestrator: Py<CognitiveOrchestrator>,
+    run: ProcessRun,
+}
+
+impl ProcessStream {
+    pub(crate) fn new(orchestrator: Py<CognitiveOrchestrator>, run: ProcessRun) -> Self {
+        Self { orchestrator, run }
+    }
+}
+
+#[pymethods]
+impl ProcessStream {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python) -> PyResult<Option<PyObject>> {
+        let event = {
+            let orch = self.orchestrator.try_borrow(py)?;
+            let (orch, run) = (&*orch, &mut self.run);
+            py.allow_threads(|| run.next_event(orch))
+        };
+        match event {
+            Some(event) => Ok(Some(pythonize(py, &event)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Drop for ProcessStream {
+    fn drop(&mut self) {
+        if self.run.is_done() {
+            return;
+        }
+        Python::with_gil(|py| {
+            if let Ok(orch) = self.orchestrator.try_borrow(py) {
+                self.run.abandon(&orch);
+            }
+        });
+    }
+}
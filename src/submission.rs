@@ -0,0 +1,358 @@
+use crate::{timing, OrchestratorError};
+use prometheus::{HistogramVec, IntGauge};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_QUEUE_DEPTH: usize = 64;
+
+/// Where a `process` submission waits in the submission queue: a higher one
+/// runs before every lower one already waiting.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    /// Shed first when the queue overflows with `Overflow::Shed`.
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl Priority {
+    pub fn name(self) -> &'static str {
+        match self {
+            Priority::Low => "low",
+            Priority::Normal => "normal",
+            Priority::High => "high",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "low" => Some(Priority::Low),
+            "normal" => Some(Priority::Normal),
+            "high" => Some(Priority::High),
+            _ => None,
+        }
+    }
+}
+
+/// What a submission does when `depth` others are already waiting.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Overflow {
+    /// Waits for room in the queue.
+    #[default]
+    Block,
+    /// Fails with `QueueFull`.
+    Reject,
+    /// Fails the newest waiting submission of the lowest priority below its own
+    /// with `QueueFull` and takes its place; fails itself when none is lower.
+    Shed,
+}
+
+/// The opt-in bound on runs in flight; a `max_running` of 0 leaves it off. Runs
+/// past it wait their turn by priority, then by arrival. Runs that took their
+/// context's lock beforehand, as `try_process` does, and `process_async` runs
+/// are not queued.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QueueConfig {
+    pub max_running: usize,
+    /// Submissions that may wait at once before `overflow` applies.
+    pub depth: usize,
+    pub overflow: Overflow,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self { max_running: 0, depth: DEFAULT_QUEUE_DEPTH, overflow: Overflow::Block }
+    }
+}
+
+impl QueueConfig {
+    pub fn new(max_running: usize) -> Self {
+        Self { max_running, ..Self::default() }
+    }
+
+    pub fn depth(mut self, depth: usize) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    pub fn overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// The first field out of range, with why.
+    pub fn invalid(&self) -> Option<(&'static str, &'static str)> {
+        (self.max_running > 0 && self.depth == 0 && self.overflow == Overflow::Block)
+            .then_some(("depth", "must be greater than zero to block"))
+    }
+}
+
+/// How one `process` submission runs, for the `_with` variants of `process`;
+/// the defaults run it as plain `process` does.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Submission {
+    /// Overrides the configured subtask timeout for this run.
+    pub timeout: Option<Duration>,
+    /// Tags the run instead of a fresh id; an id with anything but ASCII letters,
+    /// digits, `-`, `_`, `.` and `:` is refused like invalid input.
+    pub run_id: Option<String>,
+    pub priority: Priority,
+}
+
+impl Submission {
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn run_id(mut self, run_id: impl Into<String>) -> Self {
+        self.run_id = Some(run_id.into());
+        self
+    }
+
+    pub fn priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+#[derive(Default)]
+struct QueueState {
+    running: usize,
+    /// Sequence number and priority of each waiting submission.
+    waiting: Vec<(u64, Priority)>,
+    /// Waiting submissions shed for a higher priority one, until they see it.
+    shed: HashSet<u64>,
+    next_seq: u64,
+}
+
+impl QueueState {
+    /// The waiting submission to run next: the highest priority, then the oldest.
+    fn next(&self) -> Option<u64> {
+        self.waiting.iter().max_by_key(|(seq, priority)| (*priority, Reverse(*seq))).map(|(seq, _)| *seq)
+    }
+
+    /// The waiting submission to shed for one at `priority`: the newest of the
+    /// lowest priority, if that is below `priority`.
+    fn victim(&self, priority: Priority) -> Option<u64> {
+        let (seq, lowest) = self.waiting.iter().min_by_key(|(seq, priority)| (*priority, Reverse(*seq)))?;
+        (*lowest < priority).then_some(*seq)
+    }
+}
+
+struct Shared {
+    config: QueueConfig,
+    state: Mutex<QueueState>,
+    changed: Condvar,
+    depth: IntGauge,
+    waits: HistogramVec,
+}
+
+/// Bounds the runs in flight at `QueueConfig::max_running`, exporting how many
+/// wait as `SUBMISSION_QUEUE_DEPTH` and how long as `SUBMISSION_QUEUE_WAIT_SECONDS`.
+#[derive(Clone)]
+pub(crate) struct SubmissionQueue(Arc<Shared>);
+
+impl SubmissionQueue {
+    pub(crate) fn new(config: QueueConfig, depth: IntGauge, waits: HistogramVec) -> Self {
+        let state = Mutex::new(QueueState::default());
+        Self(Arc::new(Shared { config, state, changed: Condvar::new(), depth, waits }))
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.0.config.max_running > 0
+    }
+
+    /// Submissions waiting now.
+    pub(crate) fn waiting(&self) -> usize {
+        self.state().waiting.len()
+    }
+
+    fn state(&self) -> MutexGuard<'_, QueueState> {
+        self.0.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Waits, with the GIL released, for a turn to run at `priority`, or fails
+    /// with `QueueFull` as the overflow policy says. The turn lasts until the
+    /// slot is dropped; with the queue off, it is taken at once.
+    pub(crate) fn enter(&self, priority: Priority) -> Result<QueueSlot, OrchestratorError> {
+        let started = Instant::now();
+        let config = self.0.config;
+        if !self.is_enabled() {
+            return Ok(QueueSlot { queue: None, waited: Duration::ZERO });
+        }
+        let mut state = self.state();
+        if state.waiting.is_empty() && state.running < config.max_running {
+            state.running += 1;
+            drop(state);
+            return Ok(self.slot(priority, started));
+        }
+        drop(state);
+        // The guard cannot cross into `release_gil`, so the lock is taken again there.
+        let entered = timing::release_gil(|| {
+            let full = || OrchestratorError::QueueFull { depth: config.depth, shed: false };
+            let mut state = self.state();
+            if state.waiting.len() >= config.depth {
+                match config.overflow {
+                    Overflow::Reject => return Err(full()),
+                    Overflow::Shed => {
+                        let victim = state.victim(priority).ok_or_else(full)?;
+                        state.waiting.retain(|(seq, _)| *seq != victim);
+                        state.shed.insert(victim);
+                        self.0.changed.notify_all();
+                    }
+                    Overflow::Block => {}
+                }
+            }
+            while state.waiting.len() >= config.depth {
+                state = self.0.changed.wait(state).unwrap_or_else(|e| e.into_inner());
+            }
+            let seq = state.next_seq;
+            state.next_seq += 1;
+            state.waiting.push((seq, priority));
+            self.0.depth.set(state.waiting.len() as i64);
+            loop {
+                if state.shed.remove(&seq) {
+                    self.0.depth.set(state.waiting.len() as i64);
+                    return Err(OrchestratorError::QueueFull { depth: config.depth, shed: true });
+                }
+                if state.running < config.max_running && state.next() == Some(seq) {
+                    state.waiting.retain(|(waiting, _)| *waiting != seq);
+                    state.running += 1;
+                    self.0.depth.set(state.waiting.len() as i64);
+                    // Another slot may be free for the next in line.
+                    self.0.changed.notify_all();
+                    return Ok(());
+                }
+                state = self.0.changed.wait(state).unwrap_or_else(|e| e.into_inner());
+            }
+        });
+        entered.map(|()| self.slot(priority, started))
+    }
+
+    fn slot(&self, priority: Priority, started: Instant) -> QueueSlot {
+        let waited = started.elapsed();
+        self.0.waits.with_label_values(&[priority.name()]).observe(waited.as_secs_f64());
+        QueueSlot { queue: Some(self.clone()), waited }
+    }
+}
+
+/// A run's turn in the submission queue, given back to the next in line on drop.
+pub(crate) struct QueueSlot {
+    queue: Option<SubmissionQueue>,
+    waited: Duration,
+}
+
+impl QueueSlot {
+    /// How long the run waited for its turn.
+    pub(crate) fn waited(&self) -> Duration {
+        self.waited
+    }
+}
+
+impl Drop for QueueSlot {
+    fn drop(&mut self) {
+        if let Some(queue) = self.queue.take() {
+            queue.state().running -= 1;
+            queue.0.changed.notify_all();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AgentResult, CognitiveOrchestrator, MockBackend, ProcessReport};
+    use std::thread::{self, JoinHandle};
+
+    const NAP: Duration = Duration::from_millis(60);
+
+    /// Runs `nap <name>` one at a time, recording the order they start in.
+    fn orchestrator(queue: QueueConfig) -> (Arc<CognitiveOrchestrator>, Arc<Mutex<Vec<String>>>) {
+        let started = Arc::new(Mutex::new(vec![]));
+        let seen = started.clone();
+        let mock = MockBackend::new().on("nap", move |rest| {
+            seen.lock().unwrap().push(rest.to_string());
+            thread::sleep(NAP);
+            AgentResult::ok(rest)
+        });
+        let orch = CognitiveOrchestrator::builder().backend(Arc::new(mock)).submission_queue(queue).learning(false).build().unwrap();
+        (Arc::new(orch), started)
+    }
+
+    /// Submits `nap <name>` from another thread, once `waiting` others wait.
+    fn submit(orch: &Arc<CognitiveOrchestrator>, name: &str, priority: Priority, waiting: usize) -> JoinHandle<ProcessReport> {
+        while orch.queue.waiting() != waiting {
+            thread::sleep(Duration::from_millis(2));
+        }
+        let (orch, name) = (orch.clone(), name.to_string());
+        thread::spawn(move || {
+            let submission = Submission::default().priority(priority);
+            orch.process_report_with(format!("nap {}", name), &name, submission)
+        })
+    }
+
+    /// Submits `nap first` and waits for it to hold the only slot.
+    fn occupy(orch: &Arc<CognitiveOrchestrator>, started: &Mutex<Vec<String>>) -> JoinHandle<ProcessReport> {
+        let first = submit(orch, "first", Priority::Normal, 0);
+        while started.lock().unwrap().is_empty() {
+            thread::sleep(Duration::from_millis(2));
+        }
+        first
+    }
+
+    #[test]
+    fn high_priority_submissions_overtake_queued_normal_ones() {
+        let (orch, started) = orchestrator(QueueConfig::new(1));
+        let first = occupy(&orch, &started);
+        let runs = [
+            submit(&orch, "normal-1", Priority::Normal, 0),
+            submit(&orch, "normal-2", Priority::Normal, 1),
+            submit(&orch, "low", Priority::Low, 2),
+            submit(&orch, "high", Priority::High, 3),
+        ];
+        let reports: Vec<ProcessReport> = runs.into_iter().map(|run| run.join().unwrap()).collect();
+        assert!(first.join().unwrap().success);
+        assert!(reports.iter().all(|report| report.success));
+        assert_eq!(*started.lock().unwrap(), ["first", "high", "normal-1", "normal-2", "low"]);
+        // Each waited its turn, the last in line the longest.
+        assert!(reports.iter().all(|report| report.timing.queue_wait_ms > 0.0));
+        assert!(reports[2].timing.queue_wait_ms >= 3.0 * NAP.as_secs_f64() * 1000.0);
+        assert_eq!(orch.queue.waiting(), 0);
+        let metrics = crate::metrics::render(&orch.metrics_registry());
+        assert!(metrics.contains("sovereign_submission_queue_depth 0"), "{}", metrics);
+        assert!(metrics.contains("sovereign_submission_queue_wait_seconds_count{priority=\"normal\"} 3"), "{}", metrics);
+    }
+
+    #[test]
+    fn a_full_queue_rejects_or_sheds_the_lowest_priority_first() {
+        let (orch, started) = orchestrator(QueueConfig::new(1).depth(1).overflow(Overflow::Reject));
+        let first = occupy(&orch, &started);
+        let queued = submit(&orch, "queued", Priority::Normal, 0);
+        let rejected = submit(&orch, "rejected", Priority::High, 1).join().unwrap();
+        assert!(!rejected.success && rejected.results.is_empty());
+        assert_eq!(rejected.error.as_deref(), Some("Queue Error: submission queue full at depth 1"));
+        assert!(first.join().unwrap().success && queued.join().unwrap().success);
+        assert_eq!(*started.lock().unwrap(), ["first", "queued"]);
+
+        let (orch, started) = orchestrator(QueueConfig::new(1).depth(1).overflow(Overflow::Shed));
+        let first = occupy(&orch, &started);
+        let low = submit(&orch, "low", Priority::Low, 0);
+        let high = submit(&orch, "high", Priority::High, 1);
+        // A submission no higher than any waiting one is the one shed.
+        let refused = submit(&orch, "refused", Priority::Low, 1).join().unwrap();
+        assert!(refused.error.unwrap().ends_with("submission queue full at depth 1"));
+        let low = low.join().unwrap();
+        assert_eq!(low.error.as_deref(), Some("Queue Error: submission queue full at depth 1; shed for a higher priority"));
+        assert!(first.join().unwrap().success && high.join().unwrap().success);
+        assert_eq!(*started.lock().unwrap(), ["first", "high"]);
+    }
+}
@@ -0,0 +1,275 @@
+use crate::timing;
+use crate::{Agent, AgentResult, Context, OrchestratorError, Subtask};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Version of the JSON-lines protocol `SubprocessAgent` speaks.
+pub const SUBPROCESS_PROTOCOL: u32 = 1;
+pub const DEFAULT_SUBPROCESS_TIMEOUT: Duration = Duration::from_secs(30);
+pub const DEFAULT_SUBPROCESS_RESTARTS: u32 = 3;
+
+/// The executable behind a `SubprocessAgent` and how long it is given.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SubprocessConfig {
+    pub program: PathBuf,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Per request, the handshake included.
+    #[serde(default = "default_timeout", rename = "timeout_secs", with = "crate::history::secs")]
+    pub timeout: Duration,
+    /// Times the process is started again after crashing or timing out, over the
+    /// agent's life; past them every call fails.
+    #[serde(default = "default_restarts")]
+    pub max_restarts: u32,
+    #[serde(default)]
+    pub priority: i32,
+}
+
+fn default_timeout() -> Duration {
+    DEFAULT_SUBPROCESS_TIMEOUT
+}
+
+fn default_restarts() -> u32 {
+    DEFAULT_SUBPROCESS_RESTARTS
+}
+
+impl SubprocessConfig {
+    pub fn new(program: impl Into<PathBuf>) -> Self {
+        Self {
+            program: program.into(),
+            args: vec![],
+            timeout: DEFAULT_SUBPROCESS_TIMEOUT,
+            max_restarts: DEFAULT_SUBPROCESS_RESTARTS,
+            priority: 0,
+        }
+    }
+
+    pub fn args(mut self, args: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.args = args.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn max_restarts(mut self, max_restarts: u32) -> Self {
+        self.max_restarts = max_restarts;
+        self
+    }
+
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+/// The handshake answer: the subtask tags the agent takes, e.g. `["echo"]` for
+/// `echo:...` subtasks. Without any it takes the ones tagged with its name.
+#[derive(Deserialize)]
+struct Handshake {
+    protocol: u32,
+    #[serde(default)]
+    handles: Vec<String>,
+}
+
+/// The answer to `execute`, as `PyAgent` expects from Python.
+#[derive(Deserialize)]
+struct Reply {
+    output: String,
+    status: bool,
+    #[serde(default)]
+    metadata: HashMap<String, serde_json::Value>,
+}
+
+/// A running agent process: requests go to its stdin and a thread reads its
+/// answers off stdout, so waits for them can time out. Killed when dropped.
+struct Process {
+    child: Child,
+    stdin: ChildStdin,
+    lines: mpsc::Receiver<String>,
+}
+
+impl Process {
+    fn spawn(config: &SubprocessConfig) -> Result<Self, OrchestratorError> {
+        let mut child = Command::new(&config.program)
+            .args(&config.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|err| OrchestratorError::io(&config.program, err))?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            unreachable!("stdin and stdout are piped");
+        };
+        let (sender, lines) = mpsc::channel();
+        thread::Builder::new()
+            .name("subprocess-agent".to_string())
+            .spawn(move || {
+                for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                    if sender.send(line).is_err() {
+                        break;
+                    }
+                }
+            })
+            .map_err(|err| OrchestratorError::io(&config.program, err))?;
+        Ok(Self { child, stdin, lines })
+    }
+
+    /// Sends `request` as one line and waits up to `timeout` for the answering one.
+    /// `target` names the call in errors.
+    fn call(&mut self, target: &str, request: &serde_json::Value, timeout: Duration) -> Result<String, OrchestratorError> {
+        let exited = |process: &mut Self, message: String| {
+            let status = process.child.try_wait().ok().flatten().map(|status| format!(" ({})", status)).unwrap_or_default();
+            OrchestratorError::CallFailed {
+                target: target.to_string(),
+                message: format!("agent process exited{}: {}", status, message),
+                traceback: None,
+            }
+        };
+        if let Err(err) = writeln!(self.stdin, "{}", request).and_then(|()| self.stdin.flush()) {
+            return Err(exited(self, err.to_string()));
+        }
+        match self.lines.recv_timeout(timeout) {
+            Ok(line) => Ok(line),
+            Err(RecvTimeoutError::Timeout) => Err(OrchestratorError::Timeout {
+                subtask: request["subtask"].as_str().unwrap_or(target).to_string(),
+                timeout_ms: u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX),
+            }),
+            Err(RecvTimeoutError::Disconnected) => Err(exited(self, "stdout closed".to_string())),
+        }
+    }
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+struct State {
+    /// `None` once the process crashed or timed out, until the next call restarts it.
+    process: Option<Process>,
+    restarts: u32,
+}
+
+/// An agent in any language: an executable speaking JSON lines over its stdin and
+/// stdout. It is sent `{"method": "handshake", "protocol": 1, "agent": <name>}` on
+/// start, answering `{"protocol": 1, "handles": [<tag>, ...]}`, then
+/// `{"method": "execute", "subtask": ..., "context": {...}}` per subtask, answering
+/// `{"output": ..., "status": ..., "metadata": {...}}`. Calls go out one at a
+/// time. A process that crashes or times out is killed and started again on the
+/// next call, up to `max_restarts` times.
+pub struct SubprocessAgent {
+    name: String,
+    config: SubprocessConfig,
+    handles: Vec<String>,
+    state: Mutex<State>,
+}
+
+impl SubprocessAgent {
+    /// Starts the process and shakes hands with it.
+    pub fn spawn(name: impl Into<String>, config: SubprocessConfig) -> Result<Self, OrchestratorError> {
+        let name = name.into();
+        let (process, handles) = Self::start(&name, &config)?;
+        let handles = if handles.is_empty() { vec![name.clone()] } else { handles };
+        Ok(Self { name, config, handles, state: Mutex::new(State { process: Some(process), restarts: 0 }) })
+    }
+
+    /// The subtask tags this agent takes.
+    pub fn handles(&self) -> &[String] {
+        &self.handles
+    }
+
+    /// Times the process has been started again so far.
+    pub fn restarts(&self) -> u32 {
+        self.lock().restarts
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn start(name: &str, config: &SubprocessConfig) -> Result<(Process, Vec<String>), OrchestratorError> {
+        let target = format!("{}.handshake", name);
+        let mut process = Process::spawn(config)?;
+        let request = json!({ "method": "handshake", "protocol": SUBPROCESS_PROTOCOL, "agent": name });
+        let line = process.call(&target, &request, config.timeout)?;
+        let handshake: Handshake =
+            serde_json::from_str(&line).map_err(|e| OrchestratorError::extraction(&target, "handshake", e))?;
+        if handshake.protocol != SUBPROCESS_PROTOCOL {
+            return Err(OrchestratorError::extraction(
+                &target,
+                "handshake",
+                format!("protocol {} is not {}", handshake.protocol, SUBPROCESS_PROTOCOL),
+            ));
+        }
+        Ok((process, handshake.handles))
+    }
+
+    fn call(&self, sub_task: &str, ctx: &Context) -> Result<Reply, OrchestratorError> {
+        let target = format!("{}.execute", self.name);
+        let request = json!({
+            "method": "execute",
+            "subtask": sub_task,
+            "context": serde_json::to_value(ctx).map_err(OrchestratorError::serialization)?,
+        });
+
+        let mut state = self.lock();
+        let mut process = match state.process.take() {
+            Some(process) => process,
+            None if state.restarts >= self.config.max_restarts => {
+                return Err(OrchestratorError::CallFailed {
+                    target,
+                    message: format!("agent process gave out after {} restarts", state.restarts),
+                    traceback: None,
+                });
+            }
+            None => {
+                state.restarts += 1;
+                info!(agent = %self.name, restarts = state.restarts, "Restarting agent process");
+                Self::start(&self.name, &self.config)?.0
+            }
+        };
+        // A process that failed a call is dropped, and so killed, rather than put back.
+        let line = process.call(&target, &request, self.config.timeout).inspect_err(|err| {
+            warn!(agent = %self.name, "Agent process dropped: {}", err);
+        })?;
+        state.process = Some(process);
+        serde_json::from_str(&line).map_err(|e| OrchestratorError::extraction(&target, "result object", e))
+    }
+}
+
+impl Agent for SubprocessAgent {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn priority(&self) -> i32 {
+        self.config.priority
+    }
+
+    fn can_handle(&self, sub_task: &str) -> bool {
+        self.handles.iter().any(|tag| Subtask::is_for(sub_task, tag))
+    }
+
+    fn execute(&self, sub_task: &str, ctx: &mut Context) -> AgentResult {
+        let ctx = &*ctx;
+        match timing::release_gil(|| self.call(sub_task, ctx)) {
+            Ok(reply) => AgentResult { status: reply.status, metadata: reply.metadata, ..AgentResult::ok(reply.output) },
+            Err(err) => AgentResult::from_error(&format!("{} Error", self.name), err),
+        }
+    }
+}
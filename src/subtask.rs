@@ -0,0 +1,172 @@
+use crate::OrchestratorError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Free-text prefixes of the built-in agents' subtasks before the structured
+/// form, and the tag and action each stands for; the rest becomes the payload.
+const LEGACY_PREFIXES: [(&str, &str, &str); 5] = [
+    ("query llm", "llm", "generate"),
+    ("gen content", "content", "generate"),
+    ("inject hook", "hook", "inject"),
+    ("measure spread", "spread", "measure"),
+    ("eval metrics", "eval", "metrics"),
+];
+
+/// Words that routed a free-text subtask by appearing anywhere in it, checked
+/// after the prefixes, in their old precedence.
+const LEGACY_MENTIONS: [(&str, &str, &str); 2] = [("viral", "viral", "simulate"), ("MWPM", "mwpm", "amplify")];
+
+/// A subtask in the structured `agent:action payload` form, e.g.
+/// `llm:generate write a tagline` or `viral:simulate`. Dispatch routes it to the
+/// agent named by its tag, whatever the payload mentions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Subtask {
+    pub agent: String,
+    pub action: String,
+    /// Everything after the first run of whitespace; may be empty.
+    pub payload: String,
+}
+
+impl Subtask {
+    pub fn new(agent: impl Into<String>, action: impl Into<String>, payload: impl Into<String>) -> Self {
+        Self { agent: agent.into(), action: action.into(), payload: payload.into() }
+    }
+
+    /// Parses the structured form. The tag and action are ASCII letters, digits,
+    /// `_` and `-`, starting with a letter.
+    pub fn parse(text: &str) -> Result<Self, OrchestratorError> {
+        let malformed = |message: &str| OrchestratorError::MalformedSubtask {
+            subtask: text.to_string(),
+            message: message.to_string(),
+        };
+        let text = text.trim();
+        let (head, payload) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        let (agent, action) = head.split_once(':').ok_or_else(|| malformed("no agent tag"))?;
+        for (part, name) in [(agent, "agent tag"), (action, "action")] {
+            if part.is_empty() {
+                return Err(malformed(&format!("empty {}", name)));
+            }
+            if !part.starts_with(|c: char| c.is_ascii_alphabetic())
+                || !part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+            {
+                return Err(malformed(&format!("invalid {} {:?}", name, part)));
+            }
+        }
+        Ok(Self::new(agent, action, payload.trim_start()))
+    }
+
+    /// Whether `text` is meant in the structured form, rightly or not: its first
+    /// word has a colon.
+    pub fn is_structured(text: &str) -> bool {
+        text.split_whitespace().next().is_some_and(|head| head.contains(':'))
+    }
+
+    /// The structured form of the free-text subtasks the built-in agents used to
+    /// match: `query llm <prompt>` is `llm:generate <prompt>`, and one mentioning
+    /// "viral" is `viral:simulate`. The prefixes win, so a prompt that mentions
+    /// viral marketing still goes to the LLM.
+    pub fn from_legacy(text: &str) -> Option<Self> {
+        let prefixed = LEGACY_PREFIXES.iter().find_map(|(prefix, agent, action)| {
+            let payload = text.strip_prefix(prefix)?;
+            Some(Self::new(*agent, *action, payload.trim()))
+        });
+        prefixed.or_else(|| {
+            LEGACY_MENTIONS
+                .iter()
+                .find(|(word, ..)| text.contains(word))
+                .map(|(_, agent, action)| Self::new(*agent, *action, ""))
+        })
+    }
+
+    /// `parse` for structured text, else `from_legacy`; `None` for free text no
+    /// built-in agent recognizes.
+    pub fn resolve(text: &str) -> Result<Option<Self>, OrchestratorError> {
+        if Self::is_structured(text) {
+            Self::parse(text).map(Some)
+        } else {
+            Ok(Self::from_legacy(text))
+        }
+    }
+
+    /// The payload's `key=value` words, in order; agents read the rest as they like.
+    pub fn args(&self) -> Vec<(&str, &str)> {
+        self.payload.split_whitespace().filter_map(|word| word.split_once('=')).collect()
+    }
+
+    /// Whether `text` resolves to a subtask for `agent`.
+    pub(crate) fn is_for(text: &str, agent: &str) -> bool {
+        Self::resolve(text).ok().flatten().is_some_and(|subtask| subtask.agent == agent)
+    }
+
+    /// The payload `text` resolves to; the whole text when it does not resolve.
+    pub(crate) fn payload_of(text: &str) -> String {
+        match Self::resolve(text) {
+            Ok(Some(subtask)) => subtask.payload,
+            _ => text.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Subtask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.agent, self.action)?;
+        if !self.payload.is_empty() {
+            write!(f, " {}", self.payload)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CognitiveOrchestrator, MockBackend};
+    use std::sync::Arc;
+
+    #[test]
+    fn parses_the_structured_form_and_rejects_malformed_strings() {
+        let subtask = Subtask::parse("llm:generate  write a tagline").unwrap();
+        assert_eq!(subtask, Subtask::new("llm", "generate", "write a tagline"));
+        assert_eq!(subtask.to_string(), "llm:generate write a tagline");
+        assert_eq!(Subtask::parse("viral:simulate").unwrap().to_string(), "viral:simulate");
+        let args = Subtask::parse("viral:simulate campaign nodes=128 hook_rate=0.12").unwrap();
+        assert_eq!(args.args(), [("nodes", "128"), ("hook_rate", "0.12")]);
+
+        for (text, message) in [
+            ("llm generate", "no agent tag"),
+            (":generate x", "empty agent tag"),
+            ("llm: x", "empty action"),
+            ("9llm:generate", "invalid agent tag \"9llm\""),
+            ("llm:gen/erate x", "invalid action \"gen/erate\""),
+        ] {
+            let err = Subtask::parse(text).unwrap_err();
+            assert_eq!(err, OrchestratorError::MalformedSubtask { subtask: text.to_string(), message: message.to_string() });
+        }
+    }
+
+    #[test]
+    fn legacy_free_text_maps_to_tags_and_prefixes_win() {
+        let essay = "query llm write an essay about viral marketing";
+        assert_eq!(Subtask::resolve(essay).unwrap(), Some(Subtask::new("llm", "generate", "write an essay about viral marketing")));
+        assert_eq!(Subtask::resolve("go viral").unwrap(), Some(Subtask::new("viral", "simulate", "")));
+        assert_eq!(Subtask::resolve("inject hook 0.4").unwrap(), Some(Subtask::new("hook", "inject", "0.4")));
+        assert_eq!(Subtask::resolve("post teaser").unwrap(), None);
+        assert!(Subtask::resolve("llm:").is_err());
+    }
+
+    #[test]
+    fn dispatch_routes_on_the_agent_tag() {
+        let mock = MockBackend::new().generator(|prompt| Ok(format!("essay on {}", prompt)));
+        let orch = CognitiveOrchestrator::builder().backend(Arc::new(mock)).build().unwrap();
+        for subtask in ["query llm write an essay about viral marketing", "llm:generate write an essay about viral marketing"] {
+            let result = orch.dispatch(subtask.to_string(), "ctx1");
+            assert_eq!(result.output, "essay on write an essay about viral marketing");
+        }
+        assert!(orch.dispatch("eval:metrics".to_string(), "ctx1").output.starts_with("Metrics: virality="));
+
+        let result = orch.dispatch("llm:".to_string(), "ctx1");
+        assert_eq!(result.error.as_ref().map(OrchestratorError::kind), Some("malformed_subtask"));
+        let result = orch.dispatch("nobody:home".to_string(), "ctx1");
+        assert_eq!(result.error.as_ref().map(OrchestratorError::kind), Some("unknown_subtask"));
+    }
+}
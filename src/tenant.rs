@@ -0,0 +1,134 @@
+use crate::OrchestratorError;
+
+/// The tenant every method without a `tenant` argument works in.
+pub const DEFAULT_TENANT: &str = "default";
+
+/// Joins tenant and context id in the orchestrator's internal keys. Tenant names
+/// cannot contain it, so a key splits back unambiguously; context ids of the
+/// default tenant should not either.
+const SEPARATOR: char = '\u{1f}';
+
+/// A default-tenant context is keyed by its bare id, so contexts created before
+/// tenants existed keep their keys, history and snapshots.
+pub(crate) fn context_key(tenant: &str, context_id: &str) -> String {
+    if tenant == DEFAULT_TENANT {
+        context_id.to_string()
+    } else {
+        format!("{}{}{}", tenant, SEPARATOR, context_id)
+    }
+}
+
+/// `(tenant, context_id)` of an internal key.
+pub(crate) fn split_key(key: &str) -> (&str, &str) {
+    key.split_once(SEPARATOR).unwrap_or((DEFAULT_TENANT, key))
+}
+
+pub(crate) fn default_tenant() -> String {
+    DEFAULT_TENANT.to_string()
+}
+
+pub(crate) fn is_default_tenant(tenant: &str) -> bool {
+    tenant == DEFAULT_TENANT
+}
+
+/// Tenant names are non-empty and free of control characters.
+pub fn validate_tenant(tenant: &str) -> Result<(), OrchestratorError> {
+    if tenant.is_empty() || tenant.chars().any(char::is_control) {
+        return Err(OrchestratorError::InvalidTenant { tenant: tenant.to_string() });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Budget, CognitiveOrchestrator, ImportError, MockBackend};
+    use std::sync::Arc;
+
+    fn orchestrator() -> CognitiveOrchestrator {
+        let mock = MockBackend::new().virality(0.5).plan("ask", ["query llm one", "query llm two"]);
+        // Learned successes would add memory vectors of their own.
+        CognitiveOrchestrator::builder().backend(Arc::new(mock)).learning(false).build().unwrap()
+    }
+
+    #[test]
+    fn keys_split_back_and_default_keys_are_bare() {
+        assert_eq!(context_key(DEFAULT_TENANT, "ctx1"), "ctx1");
+        assert_eq!(split_key("ctx1"), (DEFAULT_TENANT, "ctx1"));
+        assert_eq!(split_key(&context_key("acme", "ctx1")), ("acme", "ctx1"));
+        assert!(validate_tenant("acme").is_ok());
+        for bad in ["", "a\u{1f}b", "line\nbreak"] {
+            assert!(matches!(validate_tenant(bad), Err(OrchestratorError::InvalidTenant { .. })), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn tenants_sharing_a_context_id_stay_isolated() {
+        let orch = orchestrator();
+        orch.process("inject hook 0.3".to_string(), "ctx1");
+        orch.process_for("acme", "inject hook 0.7".to_string(), "ctx1").unwrap();
+        orch.remember("ctx1", "default secret").unwrap();
+
+        let default = orch.get_context("ctx1").unwrap();
+        let acme = orch.get_context_for("acme", "ctx1").unwrap();
+        assert_eq!((default.tenant.as_str(), acme.tenant.as_str()), (DEFAULT_TENANT, "acme"));
+        assert_eq!(acme.context_id, "ctx1");
+        assert!((default.viral_metrics.hook_rate - 0.3).abs() < 1e-12);
+        assert!((acme.viral_metrics.hook_rate - 0.7).abs() < 1e-12);
+        assert!(acme.memory_vectors.is_empty());
+        assert_eq!(orch.get_history("ctx1", None).len(), 1);
+        assert_eq!(orch.get_history_for("acme", "ctx1").len(), 1);
+        assert_eq!(orch.context_ids_for(DEFAULT_TENANT), ["ctx1"]);
+        assert_eq!(orch.context_ids(), ["ctx1"]);
+        assert_eq!(orch.context_ids_for("acme"), ["ctx1"]);
+        assert!(orch.context_ids_for("globex").is_empty());
+
+        // An export only carries its own tenant's context, and imports into the caller's tenant.
+        let export = orch.export_context_for("acme", "ctx1").unwrap();
+        assert!(!export.contains("default secret"));
+        let other = orchestrator();
+        other.import_context_for("globex", &export, false).unwrap();
+        assert_eq!(other.context_ids_for("globex"), ["ctx1"]);
+        assert!(other.context_ids_for("acme").is_empty() && other.get_context("ctx1").is_none());
+        assert_eq!(other.import_context_for("a\u{1f}b", &export, false), Err(ImportError::InvalidTenant { tenant: "a\u{1f}b".to_string() }));
+
+        let path = std::env::temp_dir().join(format!("ace-tenant-{}.json", std::process::id()));
+        orch.save_contexts_for("acme", &path).unwrap();
+        let restored = CognitiveOrchestrator::new();
+        let report = restored.load_contexts(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(report.loaded.len(), 1);
+        assert!(restored.get_context("ctx1").is_none());
+        assert_eq!(restored.get_context_for("acme", "ctx1"), orch.get_context_for("acme", "ctx1"));
+
+        let metrics = crate::metrics::render(&orch.metrics_registry());
+        assert!(metrics.contains(r#"sovereign_virality_score{context_id="ctx1",tenant="acme"}"#), "{}", metrics);
+        assert!(metrics.contains(r#"sovereign_virality_score{context_id="ctx1",tenant="default"}"#));
+        assert!(matches!(orch.process_for("", "ask".to_string(), "ctx1"), Err(OrchestratorError::InvalidTenant { .. })));
+    }
+
+    #[test]
+    fn tenant_limits_evict_and_budgets_span_the_tenant() {
+        let orch = orchestrator().with_tenant_max_contexts("acme", 2);
+        for id in ["a", "b", "c"] {
+            orch.process_for("acme", "inject hook 0.1".to_string(), id).unwrap();
+            orch.process(format!("inject hook 0.1 {}", id), id);
+        }
+        assert_eq!(orch.context_ids_for("acme"), ["b", "c"]);
+        assert_eq!(orch.context_ids_for(DEFAULT_TENANT), ["a", "b", "c"]);
+
+        orch.set_tenant_budget("acme", Budget { max_llm_calls: Some(3), ..Budget::default() }).unwrap();
+        orch.process_for("acme", "ask".to_string(), "b").unwrap();
+        orch.process_for("acme", "ask".to_string(), "c").unwrap();
+        orch.process("ask".to_string(), "b");
+        let refused = |records: Vec<crate::ExecutionRecord>| {
+            records.iter().filter(|record| matches!(record.result.error, Some(OrchestratorError::BudgetExceeded { .. }))).count()
+        };
+        // The second acme context only gets the one call the tenant has left.
+        assert_eq!((refused(orch.get_history_for("acme", "b")), refused(orch.get_history_for("acme", "c"))), (0, 1));
+        assert_eq!(refused(orch.get_history("b", None)), 0);
+        let status = orch.get_tenant_budget("acme").unwrap();
+        assert_eq!((status.used.llm_calls, status.remaining.max_llm_calls), (3, Some(0)));
+        assert!(orch.get_tenant_budget(DEFAULT_TENANT).is_none());
+    }
+}
@@ -0,0 +1,232 @@
+use crate::AgentResult;
+use pyo3::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+/// Prefix of the result metadata keys reserved for dispatch timing.
+pub const TIMING_PREFIX: &str = "_timing.";
+/// Milliseconds from dispatch to result, retries and their backoff included.
+pub const TIMING_TOTAL_MS: &str = "_timing.total_ms";
+/// Milliseconds spent inside Python agent calls.
+pub const TIMING_PY_MS: &str = "_timing.py_ms";
+/// Milliseconds spent waiting to take the GIL.
+pub const TIMING_GIL_WAIT_MS: &str = "_timing.gil_wait_ms";
+/// Prepended to agent metadata keys under `TIMING_PREFIX`, which would otherwise
+/// pass for the orchestrator's.
+pub const AGENT_KEY_PREFIX: &str = "agent.";
+
+/// Python time and GIL waits on one thread during `measure`.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Spent {
+    py: Duration,
+    gil_wait: Duration,
+}
+
+thread_local! {
+    static SPENT: Cell<Option<Spent>> = const { Cell::new(None) };
+    /// Whether `hold_gil` holds the GIL on this thread, outside `release_gil`.
+    static HOLDING_GIL: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Sets `HOLDING_GIL` until dropped, then puts back what it was.
+struct Holding(bool);
+
+impl Holding {
+    fn set(holding: bool) -> Self {
+        Self(HOLDING_GIL.with(|cell| cell.replace(holding)))
+    }
+}
+
+impl Drop for Holding {
+    fn drop(&mut self) {
+        HOLDING_GIL.with(|cell| cell.set(self.0));
+    }
+}
+
+/// Runs `f` keeping the GIL `py` holds, so the Python calls inside take it
+/// without waiting, instead of one acquisition per call. Other Python threads
+/// still get to run while `f` waits, while its agents run Python code, and in
+/// its `release_gil` sections.
+pub fn hold_gil<T>(_py: Python<'_>, f: impl FnOnce() -> T) -> T {
+    let _holding = Holding::set(true);
+    f()
+}
+
+/// Runs long native work with the GIL released when `hold_gil` holds it on this
+/// thread; otherwise runs it as is, rather than taking the GIL just to drop it.
+pub(crate) fn release_gil<T: Send>(f: impl FnOnce() -> T + Send) -> T {
+    if !HOLDING_GIL.with(Cell::get) {
+        return f();
+    }
+    Python::with_gil(|py| {
+        py.allow_threads(|| {
+            let _holding = Holding::set(false);
+            f()
+        })
+    })
+}
+
+fn add(update: impl FnOnce(&mut Spent)) {
+    SPENT.with(|cell| {
+        if let Some(mut spent) = cell.get() {
+            update(&mut spent);
+            cell.set(Some(spent));
+        }
+    });
+}
+
+/// Counts a Python call of `elapsed` toward the current measurement.
+pub(crate) fn python_call(elapsed: Duration) {
+    add(|spent| spent.py += elapsed);
+}
+
+/// `Python::with_gil`, counting the wait for the GIL toward the current measurement.
+pub(crate) fn with_gil<F, R>(f: F) -> R
+where
+    F: for<'py> FnOnce(Python<'py>) -> R,
+{
+    let waiting = Instant::now();
+    Python::with_gil(|py| {
+        add(|spent| spent.gil_wait += waiting.elapsed());
+        f(py)
+    })
+}
+
+/// Runs `f`, measuring what it spends in Python calls and GIL waits on this
+/// thread; an enclosing measurement counts it too.
+pub(crate) fn measure<T>(f: impl FnOnce() -> T) -> (T, Spent) {
+    let outer = SPENT.with(|cell| cell.replace(Some(Spent::default())));
+    let value = f();
+    let inner = SPENT.with(|cell| cell.replace(outer)).unwrap_or_default();
+    add(|spent| {
+        spent.py += inner.py;
+        spent.gil_wait += inner.gil_wait;
+    });
+    (value, inner)
+}
+
+fn ms(duration: Duration) -> serde_json::Value {
+    serde_json::Value::from(duration.as_secs_f64() * 1000.0)
+}
+
+/// Records what an agent call spent in its result, moving any metadata the
+/// agent set under `TIMING_PREFIX` aside first.
+pub(crate) fn record_spent(result: &mut AgentResult, spent: Spent) {
+    let reserved: Vec<String> = result.metadata.keys().filter(|key| key.starts_with(TIMING_PREFIX)).cloned().collect();
+    for key in reserved {
+        if let Some(value) = result.metadata.remove(&key) {
+            result.metadata.insert(format!("{}{}", AGENT_KEY_PREFIX, key), value);
+        }
+    }
+    result.metadata.insert(TIMING_PY_MS.to_string(), ms(spent.py));
+    result.metadata.insert(TIMING_GIL_WAIT_MS.to_string(), ms(spent.gil_wait));
+}
+
+/// Records a dispatch's wall time in its result. Results no agent produced, such
+/// as timeouts, spent nothing in Python; after retries, the Python and GIL times
+/// are the last attempt's.
+pub(crate) fn record_total(result: &mut AgentResult, total: Duration) {
+    result.metadata.insert(TIMING_TOTAL_MS.to_string(), ms(total));
+    for key in [TIMING_PY_MS, TIMING_GIL_WAIT_MS] {
+        result.metadata.entry(key.to_string()).or_insert_with(|| ms(Duration::ZERO));
+    }
+}
+
+/// Where a run's time went, as `ProcessReport::timing`. Milliseconds.
+#[pyclass(module = "sovereign_cli", get_all)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct RunTiming {
+    /// The run's wall time, planning and its wait in the submission queue included.
+    pub total_ms: f64,
+    /// Waiting in the submission queue for a turn to run.
+    #[serde(default)]
+    pub queue_wait_ms: f64,
+    /// Summed over its results, like the rest.
+    pub dispatch_ms: f64,
+    pub py_ms: f64,
+    pub gil_wait_ms: f64,
+}
+
+impl RunTiming {
+    pub(crate) fn new(total: Duration, queue_wait: Duration, results: &[AgentResult]) -> Self {
+        let sum = |key: &str| {
+            results.iter().filter_map(|result| result.metadata.get(key).and_then(serde_json::Value::as_f64)).sum()
+        };
+        Self {
+            total_ms: total.as_secs_f64() * 1000.0,
+            queue_wait_ms: queue_wait.as_secs_f64() * 1000.0,
+            dispatch_ms: sum(TIMING_TOTAL_MS),
+            py_ms: sum(TIMING_PY_MS),
+            gil_wait_ms: sum(TIMING_GIL_WAIT_MS),
+        }
+    }
+}
+
+#[pymethods]
+impl RunTiming {
+    fn __repr__(&self) -> String {
+        format!(
+            "RunTiming(total_ms={:.1}, queue_wait_ms={:.1}, dispatch_ms={:.1}, py_ms={:.1}, gil_wait_ms={:.1})",
+            self.total_ms, self.queue_wait_ms, self.dispatch_ms, self.py_ms, self.gil_wait_ms
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hold_gil, release_gil, TIMING_GIL_WAIT_MS, TIMING_PY_MS, TIMING_TOTAL_MS};
+    use crate::{AgentResult, CognitiveOrchestrator, MockBackend};
+    use pyo3::Python;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    /// An orchestrator planning "launch" as two "post" subtasks that each sleep 20 ms.
+    fn orchestrator() -> CognitiveOrchestrator {
+        let mock = MockBackend::new().plan("launch", ["post teaser", "post launch"]).on("post", |rest| {
+            thread::sleep(Duration::from_millis(20));
+            let metadata = HashMap::from([("_timing.total_ms".to_string(), serde_json::Value::from("agent's own"))]);
+            AgentResult { output: rest.to_string(), status: true, metadata, error: None }
+        });
+        CognitiveOrchestrator::builder().backend(Arc::new(mock)).learning(false).build().unwrap()
+    }
+
+    fn ms(result: &AgentResult, key: &str) -> f64 {
+        result.metadata[key].as_f64().unwrap()
+    }
+
+    #[test]
+    fn results_carry_their_timing_and_agent_keys_are_moved_aside() {
+        let result = orchestrator().dispatch("post teaser".to_string(), "ctx1");
+        assert!(ms(&result, TIMING_TOTAL_MS) >= 20.0, "{:?}", result.metadata);
+        // The mock agent is native: no Python call, and no GIL taken.
+        assert_eq!((ms(&result, TIMING_PY_MS), ms(&result, TIMING_GIL_WAIT_MS)), (0.0, 0.0));
+        assert_eq!(result.metadata["agent._timing.total_ms"], "agent's own");
+    }
+
+    #[test]
+    fn reports_sum_their_results_timing() {
+        let report = orchestrator().process_report("launch".to_string(), "ctx1");
+        let timing = report.timing;
+        let dispatched: f64 = report.results.iter().map(|result| ms(result, TIMING_TOTAL_MS)).sum();
+        assert!((timing.dispatch_ms - dispatched).abs() < 1e-9);
+        assert!(timing.dispatch_ms >= 40.0 && timing.total_ms >= timing.dispatch_ms, "{:?}", timing);
+        assert_eq!((timing.py_ms, timing.gil_wait_ms), (0.0, 0.0));
+    }
+
+    #[test]
+    fn held_gil_is_let_go_in_native_sections() {
+        let report = Python::with_gil(|py| {
+            hold_gil(py, || {
+                // Another thread only gets the GIL if this one let it go.
+                release_gil(|| thread::spawn(|| Python::with_gil(|_| ())).join().unwrap());
+                orchestrator().process_report("launch".to_string(), "ctx1")
+            })
+        });
+        assert!(report.success);
+        assert_eq!(report.timing.gil_wait_ms, 0.0);
+    }
+}
@@ -0,0 +1,120 @@
+use crate::Context;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A context `CognitiveOrchestrator::delete_context` moved out of the live ones,
+/// kept until `restore_context` brings it back or `purge_trash` drops it.
+/// Eviction and the context TTL never touch it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrashedContext {
+    pub context: Context,
+    pub deleted_at: DateTime<Utc>,
+}
+
+impl TrashedContext {
+    pub fn key(&self) -> String {
+        self.context.key()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Clock, CognitiveOrchestrator, ContextStore, FileContextStore, FixedClock, Goal, MockBackend};
+    use chrono::DateTime;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn orchestrator(clock: &Arc<FixedClock>) -> CognitiveOrchestrator {
+        CognitiveOrchestrator::builder()
+            .backend(Arc::new(MockBackend::new()))
+            .clock(clock.clone())
+            .learning(false)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn deleted_contexts_are_listed_only_with_the_trash_and_restore_as_they_were() {
+        let clock = Arc::new(FixedClock::new(DateTime::UNIX_EPOCH));
+        let orch = orchestrator(&clock);
+        orch.add_goal("ctx1", Goal::new("reach", "reach 10k views")).unwrap();
+        orch.add_memory("ctx1", vec![1.0, 0.0]).unwrap();
+        orch.add_memory("ctx2", vec![0.0, 1.0]).unwrap();
+        let before = orch.get_context("ctx1").unwrap();
+
+        orch.delete_context("ctx1").unwrap();
+        assert_eq!(orch.get_context("ctx1"), None);
+        assert_eq!(orch.context_ids(), ["ctx2"]);
+        let listed: Vec<_> = orch
+            .list_contexts_including_trashed("default")
+            .into_iter()
+            .map(|summary| (summary.context_id, summary.deleted_at))
+            .collect();
+        assert_eq!(listed, [("ctx1".to_string(), Some(clock.now())), ("ctx2".to_string(), None)]);
+        assert_eq!(orch.delete_context("ctx1").unwrap_err().kind(), "missing_context");
+
+        clock.advance(chrono::Duration::minutes(5));
+        let restored = orch.restore_context("ctx1").unwrap();
+        assert_eq!((restored.active_goals.clone(), restored.memory_vectors.clone()), (before.active_goals, before.memory_vectors));
+        assert_eq!(restored.last_accessed, clock.now());
+        assert_eq!(orch.context_ids(), ["ctx1", "ctx2"]);
+        assert!(orch.list_contexts_including_trashed("default").iter().all(|summary| summary.deleted_at.is_none()));
+
+        // A context made again under a trashed id blocks its restore.
+        orch.delete_context("ctx2").unwrap();
+        orch.add_memory("ctx2", vec![1.0, 1.0]).unwrap();
+        assert_eq!(orch.restore_context("ctx2").unwrap_err().kind(), "context_exists");
+    }
+
+    #[test]
+    fn ttl_leaves_trash_alone_and_restore_after_purge_is_not_found() {
+        let clock = Arc::new(FixedClock::new(DateTime::UNIX_EPOCH));
+        let orch = CognitiveOrchestrator::builder()
+            .backend(Arc::new(MockBackend::new()))
+            .clock(clock.clone())
+            .learning(false)
+            .context_ttl(Duration::from_secs(60))
+            .build()
+            .unwrap();
+        orch.add_memory("old", vec![1.0]).unwrap();
+        orch.add_memory("kept", vec![1.0]).unwrap();
+        orch.delete_context("old").unwrap();
+        orch.delete_context("kept").unwrap();
+
+        clock.advance(chrono::Duration::hours(1));
+        assert!(orch.evict_expired().is_empty());
+        orch.restore_context("kept").unwrap();
+        orch.delete_context("kept").unwrap();
+
+        assert_eq!(orch.purge_trash(Duration::from_secs(30 * 60)).unwrap(), ["old"]);
+        let err = orch.restore_context("old").unwrap_err();
+        assert_eq!(err.kind(), "missing_context");
+        assert_eq!(err.to_string(), "unknown context: old");
+        assert_eq!(orch.restore_context("kept").unwrap().context_id, "kept");
+    }
+
+    #[test]
+    fn the_context_store_keeps_the_trash_across_orchestrators() {
+        let dir = std::env::temp_dir().join(format!("sovereign-trash-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let clock = Arc::new(FixedClock::new(DateTime::UNIX_EPOCH));
+        let orch = orchestrator(&clock).with_context_store(Box::new(FileContextStore::new(&dir)));
+        orch.remember("ctx1", "teaser a échoué").unwrap();
+        orch.persist_context("ctx1").unwrap();
+        orch.delete_context("ctx1").unwrap();
+        let store = FileContextStore::new(&dir);
+        assert_eq!(store.list().unwrap(), Vec::<String>::new());
+        assert_eq!(store.list_trashed().unwrap(), ["ctx1"]);
+
+        // A later process restores what an earlier one deleted.
+        let later = orchestrator(&clock).with_context_store(Box::new(FileContextStore::new(&dir)));
+        assert_eq!(later.restore_context("ctx1").unwrap().memory_texts, [Some("teaser a échoué".to_string())]);
+        assert!(store.list_trashed().unwrap().is_empty());
+
+        later.delete_context("ctx1").unwrap();
+        let fresh = orchestrator(&clock).with_context_store(Box::new(FileContextStore::new(&dir)));
+        assert_eq!(fresh.purge_trash(Duration::ZERO).unwrap(), ["ctx1"]);
+        assert_eq!(store.get_trashed("ctx1").unwrap(), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
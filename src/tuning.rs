@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+
+/// Trials `auto_tune_hook_rate` runs unless told otherwise.
+pub const DEFAULT_TUNE_ITERS: usize = 10;
+
+/// One simulated hook rate and the virality it reached.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TuneTrial {
+    pub hook_rate: f64,
+    pub virality: f64,
+}
+
+/// The trials of `CognitiveOrchestrator::auto_tune_hook_rate`, in the order they
+/// ran. The context keeps the last trial's hook rate and virality.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TuneReport {
+    pub context_id: String,
+    pub target_virality: f64,
+    pub trials: Vec<TuneTrial>,
+    /// Whether the last trial reached the target.
+    pub reached: bool,
+    pub hook_rate: f64,
+    pub virality: f64,
+}
+
+/// Tuning `self_debug` runs on "low virality" failures when enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AutoTune {
+    pub target_virality: f64,
+    pub max_iters: usize,
+}
+
+/// Bisects the hook rate upward from `start` until `simulate` reaches `target` or
+/// `max_iters` trials have run: each miss becomes the lower bound and the next
+/// trial is halfway to 1.0. Stops early once a trial at 1.0 misses.
+pub(crate) fn bisect(start: f64, target: f64, max_iters: usize, mut simulate: impl FnMut(f64) -> f64) -> Vec<TuneTrial> {
+    let mut trials = Vec::with_capacity(max_iters);
+    let mut hook_rate = if start.is_finite() { start.clamp(0.0, 1.0) } else { 0.0 };
+    for _ in 0..max_iters {
+        let virality = simulate(hook_rate);
+        trials.push(TuneTrial { hook_rate, virality });
+        if virality >= target || hook_rate >= 1.0 {
+            break;
+        }
+        hook_rate = (hook_rate + 1.0) / 2.0;
+    }
+    trials
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AgentResult, CognitiveOrchestrator, Goal, MockBackend, OrchestratorError};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    #[test]
+    fn bisection_climbs_to_the_target_within_bounds() {
+        let trials = bisect(0.1, 0.7, 10, |hook_rate| hook_rate);
+        let rates: Vec<f64> = trials.iter().map(|trial| trial.hook_rate).collect();
+        assert_eq!(rates, [0.1, 0.55, 0.775]);
+
+        // An unreachable target stops at the upper bound.
+        let trials = bisect(1.5, 2.0, 10, |hook_rate| hook_rate);
+        assert_eq!(trials, [TuneTrial { hook_rate: 1.0, virality: 1.0 }]);
+        assert_eq!(bisect(0.2, 0.9, 2, |_| 0.0).len(), 2);
+        assert!(bisect(0.2, 0.9, 0, |_| 0.0).is_empty());
+    }
+
+    #[test]
+    fn tuning_is_deterministic_and_sampled() {
+        let tuned = |seed| {
+            let orch = CognitiveOrchestrator::builder().seed(seed).build().unwrap();
+            orch.add_goal("ctx1", Goal::new("reach", "reach everyone")).unwrap();
+            let report = orch.auto_tune_hook_rate("ctx1", 0.9, 8).unwrap();
+            let samples = orch.metrics_history("ctx1", None).unwrap().len();
+            (report, samples, orch.get_context("ctx1").unwrap().viral_metrics.clone())
+        };
+        let (report, samples, metrics) = tuned(7);
+        assert_eq!(tuned(7).0, report);
+        assert!(report.reached);
+        assert!(report.virality >= 0.9);
+        assert_eq!(samples, report.trials.len());
+        assert_eq!((metrics.hook_rate, metrics.virality_score), (report.hook_rate, report.virality));
+        assert!(report.trials.iter().all(|trial| (0.0..=1.0).contains(&trial.hook_rate)));
+        assert!(report.trials.windows(2).all(|pair| pair[0].hook_rate < pair[1].hook_rate));
+
+        let orch = CognitiveOrchestrator::new();
+        assert!(matches!(orch.auto_tune_hook_rate("ctx1", 0.8, 3), Err(OrchestratorError::MissingContext { .. })));
+    }
+
+    #[test]
+    fn self_debug_tunes_low_virality_failures_when_enabled() {
+        let failing = || {
+            let mock = MockBackend::new().on("boost", |_| AgentResult {
+                output: "low virality".to_string(),
+                status: false,
+                metadata: HashMap::new(),
+                error: None,
+            });
+            CognitiveOrchestrator::builder().backend(Arc::new(mock)).seed(3).build().unwrap()
+        };
+        let orch = failing();
+        orch.process("boost reach".to_string(), "ctx1");
+        assert!(orch.metrics_history("ctx1", None).unwrap().is_empty());
+
+        let orch = failing().with_auto_tune(0.8, 4);
+        orch.process("boost reach".to_string(), "ctx1");
+        let history = orch.metrics_history("ctx1", None).unwrap();
+        assert!(!history.is_empty() && history.len() <= 4);
+        assert!(orch.get_context("ctx1").unwrap().viral_metrics.hook_rate > 0.05);
+    }
+}
@@ -0,0 +1,338 @@
+use crate::propagation::{Graph, Topology};
+use crate::{OrchestratorError, Subtask};
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Outcome of one `ViralPropagator::simulate` run.
+#[pyclass(module = "sovereign_cli", get_all)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PropagationReport {
+    /// Cumulative nodes reached after each round; round 0 is the seed node.
+    /// Shorter than `rounds + 1` when the spread dies out early.
+    pub reached_per_round: Vec<usize>,
+    /// Largest round-over-round growth in newly reached nodes.
+    pub peak_amplification: f64,
+    /// Fraction of the graph reached, in `[0, 1]`.
+    pub virality_score: f64,
+}
+
+#[pymethods]
+impl PropagationReport {
+    fn __repr__(&self) -> String {
+        format!(
+            "PropagationReport(reached={}, peak_amplification={:.4}, virality_score={:.4})",
+            self.reached_per_round.last().copied().unwrap_or(0),
+            self.peak_amplification,
+            self.virality_score
+        )
+    }
+}
+
+/// Inline `key=value` arguments of a viral subtask, as in
+/// `viral:simulate nodes=128 hook_rate=0.12 rounds=20`. The overrides apply to
+/// that dispatch alone, leaving the context as it was, unless `commit=true`
+/// writes `nodes` and `hook_rate` to its metrics first; `rounds` is never kept,
+/// and only the native propagator reads it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct ViralParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nodes: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hook_rate: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rounds: Option<usize>,
+    pub commit: bool,
+}
+
+impl ViralParams {
+    /// The arguments of `sub_task`; words without `=` are left to the payload.
+    pub fn parse(sub_task: &str) -> Result<Self, OrchestratorError> {
+        let mut params = Self::default();
+        let Some(subtask) = Subtask::resolve(sub_task)? else {
+            return Ok(params);
+        };
+        let invalid = |key: &str, message: &str| OrchestratorError::invalid_argument(sub_task, key, message);
+        for (key, value) in subtask.args() {
+            match key {
+                "nodes" => match value.parse::<usize>() {
+                    Ok(nodes) if nodes >= 1 => params.nodes = Some(nodes),
+                    _ => return Err(invalid(key, "must be a whole number of at least 1")),
+                },
+                "hook_rate" => match value.parse::<f64>() {
+                    Ok(rate) if (0.0..=1.0).contains(&rate) => params.hook_rate = Some(rate),
+                    _ => return Err(invalid(key, "must be a number between 0 and 1")),
+                },
+                "rounds" => params.rounds = Some(value.parse().map_err(|_| invalid(key, "must be a whole number"))?),
+                "commit" => params.commit = value.parse().map_err(|_| invalid(key, "must be true or false"))?,
+                _ => return Err(invalid(key, "is not nodes, hook_rate, rounds or commit")),
+            }
+        }
+        Ok(params)
+    }
+
+    pub fn overrides(&self) -> bool {
+        self.nodes.is_some() || self.hook_rate.is_some() || self.rounds.is_some()
+    }
+
+    /// Whether the dispatch must leave the context untouched.
+    pub fn trial(&self) -> bool {
+        self.overrides() && !self.commit
+    }
+}
+
+/// SplitMix64. Hand-rolled rather than pulled from `rand` so a given seed
+/// produces the same spread on every platform and dependency version.
+pub(crate) struct SplitMix64(pub(crate) u64);
+
+impl SplitMix64 {
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[0, 1)`.
+    pub(crate) fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+/// Independent-cascade spread over an engagement graph: each newly reached node
+/// gets one chance to reach each unreached neighbour with probability `hook_rate`.
+#[derive(Debug, Clone)]
+pub struct ViralPropagator {
+    /// How the graphs `simulate` builds are wired.
+    pub topology: Topology,
+    /// Rounds and seed used when dispatching viral subtasks natively.
+    pub rounds: usize,
+    pub seed: u64,
+}
+
+impl Default for ViralPropagator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ViralPropagator {
+    pub fn new() -> Self {
+        Self { topology: Topology::default(), rounds: 10, seed: 0 }
+    }
+
+    pub fn with_topology(mut self, topology: Topology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    /// Builds a `topology` graph of `nodes` and spreads over it; `seed` drives both.
+    pub fn simulate(&self, nodes: usize, hook_rate: f64, rounds: usize, seed: u64) -> PropagationReport {
+        self.start_simulation(nodes, hook_rate, rounds, seed).0
+    }
+
+    /// `simulate`, also returning where the spread stopped so `continue_simulation`
+    /// can extend it.
+    pub fn start_simulation(&self, nodes: usize, hook_rate: f64, rounds: usize, seed: u64) -> (PropagationReport, PropagationState) {
+        let mut rng = SplitMix64(seed);
+        let graph = Graph::build_with(self.topology, nodes, &mut rng);
+        let mut state = PropagationState::seeded(graph, hook_rate, rng);
+        state.spread(rounds);
+        (state.report(), state)
+    }
+
+    /// Spreads over `graph` as given, from a seed node and coin flips drawn from `seed`.
+    pub fn simulate_graph(&self, graph: &Graph, hook_rate: f64, rounds: usize, seed: u64) -> PropagationReport {
+        let mut state = PropagationState::seeded(graph.clone(), hook_rate, SplitMix64(seed));
+        state.spread(rounds);
+        state.report()
+    }
+
+    /// Spreads `additional_rounds` further from where `state` stopped, over the
+    /// same graph at its `hook_rate`. The report covers every round so far, so its
+    /// reach never falls from one continuation to the next.
+    pub fn continue_simulation(&self, state: &mut PropagationState, additional_rounds: usize) -> PropagationReport {
+        state.spread(additional_rounds);
+        state.report()
+    }
+}
+
+/// A spread in progress: the engagement graph, which nodes it has reached and
+/// the frontier that reaches further, with the coin flips still to come.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropagationState {
+    graph: Graph,
+    pub hook_rate: f64,
+    reached: Vec<bool>,
+    frontier: Vec<usize>,
+    reached_per_round: Vec<usize>,
+    peak_amplification: f64,
+    rng: u64,
+}
+
+impl PropagationState {
+    /// Reaches a seed node drawn from `rng`, unless the graph is empty.
+    fn seeded(graph: Graph, hook_rate: f64, mut rng: SplitMix64) -> Self {
+        let nodes = graph.node_count();
+        let mut reached = vec![false; nodes];
+        let frontier = if nodes == 0 { vec![] } else { vec![rng.below(nodes)] };
+        for &node in &frontier {
+            reached[node] = true;
+        }
+        Self {
+            graph,
+            hook_rate,
+            reached,
+            reached_per_round: if nodes == 0 { vec![] } else { vec![1] },
+            frontier,
+            peak_amplification: 0.0,
+            rng: rng.0,
+        }
+    }
+
+    pub fn graph(&self) -> &Graph {
+        &self.graph
+    }
+
+    /// Rounds spread so far that reached someone new.
+    pub fn rounds(&self) -> usize {
+        self.reached_per_round.len().saturating_sub(1)
+    }
+
+    pub fn reached(&self) -> usize {
+        self.reached_per_round.last().copied().unwrap_or(0)
+    }
+
+    /// Whether the frontier died out, so no further round can reach anyone.
+    pub fn is_exhausted(&self) -> bool {
+        self.frontier.is_empty()
+    }
+
+    fn spread(&mut self, rounds: usize) {
+        let hook_rate = self.hook_rate.clamp(0.0, 1.0);
+        let mut rng = SplitMix64(self.rng);
+        for _ in 0..rounds {
+            let mut next = vec![];
+            for &node in &self.frontier {
+                for neighbour in self.graph.neighbors(node) {
+                    if !self.reached[neighbour] && rng.next_f64() < hook_rate {
+                        self.reached[neighbour] = true;
+                        next.push(neighbour);
+                    }
+                }
+            }
+            if next.is_empty() {
+                self.frontier.clear();
+                break;
+            }
+            self.peak_amplification = self.peak_amplification.max(next.len() as f64 / self.frontier.len() as f64);
+            self.reached_per_round.push(self.reached() + next.len());
+            self.frontier = next;
+        }
+        self.rng = rng.0;
+    }
+
+    fn report(&self) -> PropagationReport {
+        let nodes = self.graph.node_count();
+        if nodes == 0 {
+            return PropagationReport::default();
+        }
+        PropagationReport {
+            reached_per_round: self.reached_per_round.clone(),
+            peak_amplification: self.peak_amplification,
+            virality_score: self.reached() as f64 / nodes as f64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ViralParams, ViralPropagator};
+    use crate::{AgentResult, CognitiveOrchestrator, OrchestratorError, Topology, ViralConfig};
+
+    #[test]
+    fn continued_rounds_extend_the_same_spread() {
+        let propagator = ViralPropagator::new();
+        let (first, mut state) = propagator.start_simulation(300, 0.6, 2, 11);
+        let mut reach = vec![first.reached_per_round.last().copied().unwrap()];
+        for _ in 0..4 {
+            let report = propagator.continue_simulation(&mut state, 2);
+            assert!(report.reached_per_round.starts_with(&first.reached_per_round));
+            reach.push(report.reached_per_round.last().copied().unwrap());
+        }
+        assert!(reach.windows(2).all(|pair| pair[0] <= pair[1]), "{:?}", reach);
+        assert!(reach[4] > reach[0], "{:?}", reach);
+        // Five continuations of two rounds spread exactly like ten rounds at once.
+        assert_eq!(propagator.continue_simulation(&mut state, 0), propagator.simulate(300, 0.6, 10, 11));
+        assert_eq!(state.reached(), reach[4]);
+    }
+
+    #[test]
+    fn spread_measurements_accumulate_until_the_graph_changes_or_is_reset() {
+        let orch = CognitiveOrchestrator::builder().prefer_native(true).seed(3).learning(false).build().unwrap();
+        let measure = || orch.dispatch("measure spread".to_string(), "ctx1");
+        let continued = |result: &AgentResult| result.metadata["continued"].as_bool().unwrap();
+        let virality = |result: &AgentResult| result.metadata["virality"].as_f64().unwrap();
+
+        let first = measure();
+        assert!(!continued(&first));
+        let mut last = virality(&first);
+        for _ in 0..3 {
+            let result = measure();
+            assert!(continued(&result) && virality(&result) >= last);
+            last = virality(&result);
+        }
+        // Runs of the viral agent start from scratch and leave the state alone.
+        orch.dispatch("viral:simulate".to_string(), "ctx1");
+        assert!(continued(&measure()));
+
+        orch.contexts.update("ctx1", |context| context.viral_metrics.engagement_nodes += 10);
+        assert!(!continued(&measure()));
+        orch.set_viral_config("ctx1", ViralConfig { topology: Topology::BarabasiAlbert { attachments: 2 }, ..ViralConfig::default() });
+        assert!(!continued(&measure()));
+        assert!(continued(&measure()));
+
+        assert!(orch.reset_propagation("ctx1"));
+        assert!(!orch.reset_propagation("ctx1") && !orch.reset_propagation("ctx2"));
+        assert!(!continued(&measure()));
+    }
+
+    #[test]
+    fn inline_overrides_run_once_unless_committed() {
+        let orch = CognitiveOrchestrator::builder().prefer_native(true).seed(5).learning(false).build().unwrap();
+        orch.dispatch("viral:simulate".to_string(), "ctx1");
+        let before = orch.get_context("ctx1").unwrap().viral_metrics;
+
+        let result = orch.dispatch("viral:simulate campaign 3 nodes=128 hook_rate=0.5 rounds=20".to_string(), "ctx1");
+        assert!(result.error.is_none(), "{:?}", result.error);
+        let reached = result.metadata["metrics"]["reached_per_round"].as_array().unwrap();
+        assert!(reached.len() <= 21 && reached.last().unwrap().as_u64().unwrap() <= 128);
+        assert_eq!(result.metadata["overrides"], serde_json::json!({"nodes": 128, "hook_rate": 0.5, "rounds": 20, "commit": false}));
+        assert_eq!(orch.get_context("ctx1").unwrap().viral_metrics, before);
+
+        let result = orch.dispatch("viral:simulate nodes=128 hook_rate=0.5 commit=true".to_string(), "ctx1");
+        let after = orch.get_context("ctx1").unwrap().viral_metrics;
+        assert_eq!((after.engagement_nodes, after.hook_rate), (128, 0.5));
+        assert_eq!(after.virality_score, result.metadata["virality"].as_f64().unwrap());
+    }
+
+    #[test]
+    fn invalid_arguments_fail_the_subtask_by_key() {
+        let orch = CognitiveOrchestrator::builder().prefer_native(true).learning(false).build().unwrap();
+        for (sub_task, key) in [("viral:simulate hook_rate=1.5", "hook_rate"), ("viral:simulate nodes=0", "nodes"), ("viral:simulate seeds=3", "seeds")] {
+            let result = orch.dispatch(sub_task.to_string(), "ctx1");
+            assert!(!result.status);
+            match result.error {
+                Some(OrchestratorError::InvalidArgument { key: refused, .. }) => assert_eq!(refused, key),
+                other => panic!("{}: {:?}", sub_task, other),
+            }
+        }
+        assert_eq!(orch.get_context("ctx1").map_or(0.0, |context| context.viral_metrics.virality_score), 0.0);
+        assert_eq!(ViralParams::parse("run a viral campaign").unwrap(), ViralParams::default());
+        assert!(ViralParams::parse("viral:simulate commit=yes").is_err());
+    }
+}
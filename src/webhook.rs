@@ -0,0 +1,340 @@
+use crate::history::secs;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+pub const DEFAULT_WEBHOOK_ATTEMPTS: u32 = 3;
+pub const DEFAULT_WEBHOOK_QUEUE: usize = 256;
+
+/// Which events a webhook is sent, e.g. `{event = "subtask_finished", status = false}`
+/// for failed subtasks only.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EventFilter {
+    /// The event's `event` tag, such as `"replan_triggered"`.
+    pub event: String,
+    /// When set, the event's `status`, or its result's, must equal it.
+    #[serde(default)]
+    pub status: Option<bool>,
+}
+
+impl EventFilter {
+    pub fn new(event: impl Into<String>) -> Self {
+        Self { event: event.into(), status: None }
+    }
+
+    pub fn with_status(mut self, status: bool) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// `event` is a `BusEvent` as JSON.
+    pub fn matches(&self, event: &serde_json::Value) -> bool {
+        if event["event"] != self.event.as_str() {
+            return false;
+        }
+        let Some(wanted) = self.status else {
+            return true;
+        };
+        let status = event.get("status").or_else(|| event.get("result").and_then(|result| result.get("status")));
+        status.and_then(serde_json::Value::as_bool) == Some(wanted)
+    }
+}
+
+/// Where and how a `WebhookNotifier` POSTs bus events, one JSON `BusEvent` per
+/// request. Answers of 5xx and connection errors are retried up to `max_attempts`
+/// times in all, waiting `retry_delay` and twice as long each time after; events
+/// arriving while `queue_capacity` are waiting are dropped.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// Sent as the `Authorization` header, e.g. `"Bearer <token>"`.
+    #[serde(default)]
+    pub auth_header: Option<String>,
+    /// Events sent when any matches; every event when empty.
+    #[serde(default)]
+    pub events: Vec<EventFilter>,
+    /// Context attributes sent with each event, under `attributes`; those the
+    /// context lacks are left out.
+    #[serde(default)]
+    pub attributes: Vec<String>,
+    #[serde(default = "default_attempts")]
+    pub max_attempts: u32,
+    #[serde(default = "default_retry_delay", rename = "retry_delay_secs", with = "secs")]
+    pub retry_delay: Duration,
+    #[serde(default = "default_timeout", rename = "timeout_secs", with = "secs")]
+    pub timeout: Duration,
+    #[serde(default = "default_queue")]
+    pub queue_capacity: usize,
+}
+
+fn default_attempts() -> u32 {
+    DEFAULT_WEBHOOK_ATTEMPTS
+}
+
+fn default_retry_delay() -> Duration {
+    Duration::from_millis(500)
+}
+
+fn default_timeout() -> Duration {
+    Duration::from_secs(5)
+}
+
+fn default_queue() -> usize {
+    DEFAULT_WEBHOOK_QUEUE
+}
+
+impl WebhookConfig {
+    /// Every event to `url`, with the default retries and queue.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            auth_header: None,
+            events: vec![],
+            attributes: vec![],
+            max_attempts: DEFAULT_WEBHOOK_ATTEMPTS,
+            retry_delay: default_retry_delay(),
+            timeout: default_timeout(),
+            queue_capacity: DEFAULT_WEBHOOK_QUEUE,
+        }
+    }
+
+    pub fn auth_header(mut self, value: impl Into<String>) -> Self {
+        self.auth_header = Some(value.into());
+        self
+    }
+
+    /// Adds `filter` to the events sent.
+    pub fn event(mut self, filter: EventFilter) -> Self {
+        self.events.push(filter);
+        self
+    }
+
+    /// Sends the context's attribute `key` with each event.
+    pub fn attribute(mut self, key: impl Into<String>) -> Self {
+        self.attributes.push(key.into());
+        self
+    }
+
+    pub fn wants(&self, event: &serde_json::Value) -> bool {
+        self.events.is_empty() || self.events.iter().any(|filter| filter.matches(event))
+    }
+
+    /// The first field out of range, with why.
+    pub fn invalid(&self) -> Option<(&'static str, String)> {
+        if !(self.url.starts_with("http://") || self.url.starts_with("https://")) {
+            return Some(("url", format!("must be an http:// or https:// URL, got {:?}", self.url)));
+        }
+        if self.max_attempts == 0 {
+            return Some(("max_attempts", "must be at least 1".to_string()));
+        }
+        if self.timeout.is_zero() {
+            return Some(("timeout_secs", "must be greater than zero".to_string()));
+        }
+        (self.queue_capacity == 0).then(|| ("queue_capacity", "must be greater than zero".to_string()))
+    }
+}
+
+/// A webhook's deliveries so far, as `notifier_status` reports them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct WebhookStatus {
+    pub url: String,
+    pub delivered: u64,
+    /// Events given up on after their last attempt.
+    pub failed: u64,
+    /// Attempts beyond each event's first.
+    pub retries: u64,
+    /// Events lost to a full queue, or to the bus while the notifier lagged.
+    pub dropped: u64,
+    pub queued: usize,
+    pub last_error: Option<String>,
+}
+
+#[cfg(feature = "webhooks")]
+pub use notifier::WebhookNotifier;
+
+#[cfg(feature = "webhooks")]
+mod notifier {
+    use super::{WebhookConfig, WebhookStatus};
+    use crate::{BusEvent, EventBus, Subscription};
+    use prometheus::IntCounter;
+    use std::sync::{Arc, Mutex};
+    use tokio::sync::{mpsc, Notify};
+    use tracing::{debug, warn};
+
+    /// POSTs the events `config` wants from a bus to a webhook, on a thread of its
+    /// own: publishing never waits on the endpoint, however slow or dead. Stops
+    /// when dropped; events still queued then are not sent.
+    pub struct WebhookNotifier {
+        status: Arc<Mutex<WebhookStatus>>,
+        stop: Arc<Notify>,
+    }
+
+    impl WebhookNotifier {
+        /// Subscribes to `bus` from now on; `failures` counts events given up on.
+        pub fn start(config: WebhookConfig, bus: &EventBus, failures: IntCounter) -> std::io::Result<Self> {
+            let status = Arc::new(Mutex::new(WebhookStatus { url: config.url.clone(), ..WebhookStatus::default() }));
+            let stop = Arc::new(Notify::new());
+            let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+            let client = reqwest::Client::builder().timeout(config.timeout).build().map_err(std::io::Error::other)?;
+            let worker = Worker { config, client, status: status.clone(), failures };
+            let subscription = bus.subscribe();
+            let stopped = stop.clone();
+            std::thread::Builder::new().name("webhook".to_string()).spawn(move || {
+                runtime.block_on(async move {
+                    tokio::select! {
+                        _ = stopped.notified() => {}
+                        _ = worker.run(subscription) => {}
+                    }
+                });
+            })?;
+            Ok(Self { status, stop })
+        }
+
+        pub fn status(&self) -> WebhookStatus {
+            self.status.lock().unwrap_or_else(|e| e.into_inner()).clone()
+        }
+    }
+
+    impl Drop for WebhookNotifier {
+        fn drop(&mut self) {
+            self.stop.notify_one();
+        }
+    }
+
+    struct Worker {
+        config: WebhookConfig,
+        client: reqwest::Client,
+        status: Arc<Mutex<WebhookStatus>>,
+        failures: IntCounter,
+    }
+
+    impl Worker {
+        fn update(&self, f: impl FnOnce(&mut WebhookStatus)) {
+            f(&mut self.status.lock().unwrap_or_else(|e| e.into_inner()));
+        }
+
+        /// Queues wanted events as they arrive while sending the queued ones in order.
+        async fn run(&self, mut subscription: Subscription) {
+            let (sender, mut queue) = mpsc::channel::<String>(self.config.queue_capacity);
+            let forward = async {
+                let mut lost = 0;
+                while let Some(event) = subscription.recv().await {
+                    if subscription.dropped() > lost {
+                        let missed = subscription.dropped() - lost;
+                        lost = subscription.dropped();
+                        self.update(|status| status.dropped += missed);
+                    }
+                    let Some(body) = self.body(&event) else {
+                        continue;
+                    };
+                    match sender.try_send(body) {
+                        Ok(()) => self.update(|status| status.queued += 1),
+                        Err(_) => self.update(|status| status.dropped += 1),
+                    }
+                }
+            };
+            let deliver = async {
+                while let Some(body) = queue.recv().await {
+                    self.deliver(body).await;
+                    self.update(|status| status.queued -= 1);
+                }
+            };
+            tokio::join!(forward, deliver);
+        }
+
+        fn body(&self, event: &BusEvent) -> Option<String> {
+            let mut value = serde_json::to_value(event).ok()?;
+            if !self.config.wants(&value) {
+                return None;
+            }
+            if !self.config.attributes.is_empty() {
+                let attributes: serde_json::Map<String, serde_json::Value> = self
+                    .config
+                    .attributes
+                    .iter()
+                    .filter_map(|key| Some((key.clone(), event.attributes.get(key)?.clone())))
+                    .collect();
+                value["attributes"] = serde_json::Value::Object(attributes);
+            }
+            Some(value.to_string())
+        }
+
+        async fn deliver(&self, body: String) {
+            let mut delay = self.config.retry_delay;
+            for attempt in 1..=self.config.max_attempts {
+                if attempt > 1 {
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                    self.update(|status| status.retries += 1);
+                }
+                let error = match self.post(body.clone()).await {
+                    Ok(()) => {
+                        self.update(|status| status.delivered += 1);
+                        return;
+                    }
+                    Err((error, retryable)) => {
+                        debug!(url = %self.config.url, attempt, "Webhook delivery failed: {}", error);
+                        if retryable && attempt < self.config.max_attempts {
+                            continue;
+                        }
+                        error
+                    }
+                };
+                warn!(url = %self.config.url, "Webhook event dropped after {} attempts: {}", attempt, error);
+                self.failures.inc();
+                self.update(|status| {
+                    status.failed += 1;
+                    status.last_error = Some(error);
+                });
+                return;
+            }
+        }
+
+        /// The error, and whether another attempt may fare better.
+        async fn post(&self, body: String) -> Result<(), (String, bool)> {
+            let mut request =
+                self.client.post(&self.config.url).header(reqwest::header::CONTENT_TYPE, "application/json").body(body);
+            if let Some(auth) = &self.config.auth_header {
+                request = request.header(reqwest::header::AUTHORIZATION, auth);
+            }
+            match request.send().await {
+                Ok(response) if response.status().is_success() => Ok(()),
+                Ok(response) => Err((format!("HTTP {}", response.status()), response.status().is_server_error())),
+                Err(err) => Err((err.to_string(), true)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn filters_match_the_event_tag_and_status() {
+        let failed = json!({"seq": 1, "context_id": "ctx1", "event": "subtask_finished", "result": {"status": false}});
+        let passed = json!({"seq": 2, "context_id": "ctx1", "event": "subtask_finished", "result": {"status": true}});
+        let replan = json!({"seq": 3, "context_id": "ctx1", "event": "replan_triggered", "subtask": "post"});
+        let config = WebhookConfig::new("http://localhost:9")
+            .event(EventFilter::new("subtask_finished").with_status(false))
+            .event(EventFilter::new("replan_triggered"));
+        assert!(config.wants(&failed) && config.wants(&replan));
+        assert!(!config.wants(&passed));
+        assert!(WebhookConfig::new("http://localhost:9").wants(&passed));
+    }
+
+    #[test]
+    fn configs_read_with_defaults_and_are_checked() {
+        let config: WebhookConfig = toml::from_str(
+            "url = \"https://hooks.example.com/ace\"\nevents = [{event = \"subtask_finished\", status = false}]",
+        )
+        .unwrap();
+        assert_eq!((config.max_attempts, config.queue_capacity), (DEFAULT_WEBHOOK_ATTEMPTS, DEFAULT_WEBHOOK_QUEUE));
+        assert_eq!(config.events, [EventFilter::new("subtask_finished").with_status(false)]);
+        assert_eq!(config.invalid(), None);
+        assert_eq!(WebhookConfig::new("ftp://example.com").invalid().unwrap().0, "url");
+        assert_eq!(WebhookConfig { queue_capacity: 0, ..config }.invalid().unwrap().0, "queue_capacity");
+    }
+}
@@ -0,0 +1,233 @@
+use crate::history::secs;
+use pyo3::Python;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+/// How long `shutdown` waits by default for the workers to finish what was queued.
+pub const DEFAULT_WORKER_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The opt-in pool `process_parallel` dispatches through; a `size` of 0 leaves it off.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkerPoolConfig {
+    pub size: usize,
+    /// Python modules each worker imports as it starts, so the first call of an
+    /// agent from them does not pay for the import.
+    pub imports: Vec<String>,
+    /// How long `shutdown` waits for the workers to finish the jobs queued.
+    #[serde(rename = "drain_timeout_secs", with = "secs")]
+    pub drain_timeout: Duration,
+}
+
+impl Default for WorkerPoolConfig {
+    fn default() -> Self {
+        Self { size: 0, imports: vec![], drain_timeout: DEFAULT_WORKER_DRAIN_TIMEOUT }
+    }
+}
+
+impl WorkerPoolConfig {
+    pub fn new(size: usize) -> Self {
+        Self { size, ..Self::default() }
+    }
+
+    pub fn imports(mut self, imports: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.imports = imports.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn drain_timeout(mut self, timeout: Duration) -> Self {
+        self.drain_timeout = timeout;
+        self
+    }
+
+    /// The first field out of range, with why.
+    pub fn invalid(&self) -> Option<(&'static str, String)> {
+        self.imports.iter().any(|module| module.trim().is_empty()).then(|| ("imports", "must not be empty".to_string()))
+    }
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+type Outcome<T> = Result<T, Box<dyn Any + Send>>;
+
+/// Dedicated threads, started once, that run dispatch jobs sent over a channel.
+/// Python agents release the GIL while they wait on I/O, so jobs on different
+/// workers overlap; each worker imports the configured modules as it starts.
+/// pyo3 has no sub-interpreters, so the workers share the one interpreter.
+pub struct WorkerPool {
+    sender: Mutex<Option<mpsc::Sender<Job>>>,
+    workers: Mutex<Vec<JoinHandle<()>>>,
+    size: usize,
+    drain_timeout: Duration,
+}
+
+impl WorkerPool {
+    pub fn start(config: &WorkerPoolConfig) -> Self {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let workers = (0..config.size)
+            .map(|idx| {
+                let (receiver, imports) = (receiver.clone(), config.imports.clone());
+                thread::Builder::new()
+                    .name(format!("sovereign-worker-{}", idx))
+                    .spawn(move || work(&receiver, &imports))
+                    .expect("spawning a pool worker")
+            })
+            .collect();
+        Self {
+            sender: Mutex::new(Some(sender)),
+            workers: Mutex::new(workers),
+            size: config.size,
+            drain_timeout: config.drain_timeout,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Runs `job` on the next free worker and waits for it; on the calling thread
+    /// once the pool is shut down. A panic in `job` is resumed here. Must not be
+    /// called while holding the GIL.
+    pub(crate) fn run<T: Send + 'static>(&self, job: impl FnOnce() -> T + Send + 'static) -> T {
+        let (reply, outcome) = mpsc::channel::<Outcome<T>>();
+        let job: Job = Box::new(move || {
+            let _ = reply.send(panic::catch_unwind(AssertUnwindSafe(job)));
+        });
+        let sender = self.sender.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        let unsent = match sender {
+            Some(sender) => sender.send(job).err().map(|mpsc::SendError(job)| job),
+            None => Some(job),
+        };
+        if let Some(job) = unsent {
+            job();
+        }
+        match outcome.recv() {
+            Ok(Ok(value)) => value,
+            Ok(Err(panicked)) => panic::resume_unwind(panicked),
+            Err(_) => unreachable!("a pool job always replies"),
+        }
+    }
+
+    /// Stops taking jobs and waits up to the drain timeout for the workers to
+    /// finish those queued; returns how many were still busy when it ran out.
+    pub fn shutdown(&self) -> usize {
+        self.sender.lock().unwrap_or_else(|e| e.into_inner()).take();
+        let mut workers = self.workers.lock().unwrap_or_else(|e| e.into_inner());
+        let deadline = Instant::now() + self.drain_timeout;
+        while workers.iter().any(|worker| !worker.is_finished()) && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(5));
+        }
+        let (finished, busy): (Vec<_>, Vec<_>) = workers.drain(..).partition(JoinHandle::is_finished);
+        for worker in finished {
+            let _ = worker.join();
+        }
+        if !busy.is_empty() {
+            warn!(busy = busy.len(), "Pool workers still running at the drain timeout");
+        }
+        busy.len()
+    }
+}
+
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        // Closing the queue lets idle workers exit; busy ones finish on their own.
+        self.sender.get_mut().unwrap_or_else(|e| e.into_inner()).take();
+    }
+}
+
+fn work(receiver: &Mutex<mpsc::Receiver<Job>>, imports: &[String]) {
+    if !imports.is_empty() {
+        Python::with_gil(|py| {
+            for module in imports {
+                match py.import(module.as_str()) {
+                    Ok(_) => debug!(module, "Pool worker imported"),
+                    Err(err) => warn!(module, "Pool worker import failed: {}", err),
+                }
+            }
+        });
+    }
+    loop {
+        // The lock is held only while waiting, so one idle worker takes each job.
+        let job = receiver.lock().unwrap_or_else(|e| e.into_inner()).recv();
+        match job {
+            Ok(job) => job(),
+            Err(_) => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AgentResult, CognitiveOrchestrator, MockBackend};
+    use std::collections::HashSet;
+
+    const NAP: Duration = Duration::from_millis(100);
+
+    fn orchestrator(pool: WorkerPoolConfig) -> CognitiveOrchestrator {
+        let mock = MockBackend::new()
+            .plan("fetch", (0..4).map(|idx| format!("nap:{}", idx)).collect::<Vec<_>>())
+            .on("nap", |_| {
+                thread::sleep(NAP);
+                AgentResult::ok(thread::current().name().unwrap_or_default())
+            });
+        CognitiveOrchestrator::builder().backend(Arc::new(mock)).learning(false).worker_pool(pool).build().unwrap()
+    }
+
+    #[test]
+    fn process_parallel_runs_on_the_pool_up_to_its_size() {
+        let orch = orchestrator(WorkerPoolConfig::new(4));
+        let started = Instant::now();
+        let results = orch.process_parallel("fetch all".to_string(), "ctx1", 8, false);
+        assert!(started.elapsed() < NAP * 3, "{:?}", started.elapsed());
+        let workers: HashSet<&str> = results.iter().map(|result| result.output.as_str()).collect();
+        assert!(workers.iter().all(|name| name.starts_with("sovereign-worker-")), "{:?}", workers);
+        assert_eq!(workers.len(), 4);
+
+        // Two workers take two jobs each, whatever the concurrency asked for.
+        let orch = orchestrator(WorkerPoolConfig::new(2));
+        let started = Instant::now();
+        orch.process_parallel("fetch all".to_string(), "ctx1", 8, false);
+        assert!(started.elapsed() >= NAP * 2, "{:?}", started.elapsed());
+    }
+
+    #[test]
+    fn shutdown_drains_queued_jobs_and_later_ones_run_inline() {
+        let pool = WorkerPool::start(&WorkerPoolConfig::new(1).drain_timeout(Duration::from_secs(5)));
+        let pool = Arc::new(pool);
+        let queued: Vec<_> = (0..3)
+            .map(|idx| {
+                let pool = pool.clone();
+                thread::spawn(move || {
+                    pool.run(move || {
+                        thread::sleep(NAP / 4);
+                        idx
+                    })
+                })
+            })
+            .collect();
+        thread::sleep(NAP / 8);
+        assert_eq!(pool.shutdown(), 0);
+        let mut done: Vec<i32> = queued.into_iter().map(|job| job.join().unwrap()).collect();
+        done.sort();
+        assert_eq!(done, [0, 1, 2]);
+
+        let here = thread::current().id();
+        assert_eq!(pool.run(|| thread::current().id()), here);
+    }
+
+    #[test]
+    fn a_panicking_job_panics_its_caller_and_leaves_the_worker_running() {
+        let pool = WorkerPool::start(&WorkerPoolConfig::new(1));
+        let panicked = panic::catch_unwind(AssertUnwindSafe(|| pool.run(|| panic!("agent blew up"))));
+        assert_eq!(panicked.unwrap_err().downcast_ref::<&str>(), Some(&"agent blew up"));
+        assert_eq!(pool.run(|| 7), 7);
+        assert_eq!(WorkerPoolConfig::new(1).imports([" "]).invalid().map(|(field, _)| field), Some("imports"));
+    }
+}
@@ -0,0 +1,417 @@
+use crate::history::secs;
+use crate::viral::SplitMix64;
+use crate::{CognitiveOrchestrator, MockBackend};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// What a generated command asks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommandKind {
+    /// An `llm:generate` prompt.
+    Llm,
+    /// A `viral:simulate` run.
+    Viral,
+    /// A command no agent handles, which fails.
+    Unknown,
+}
+
+impl CommandKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CommandKind::Llm => "llm",
+            CommandKind::Viral => "viral",
+            CommandKind::Unknown => "unknown",
+        }
+    }
+
+    fn command(self, n: u64) -> String {
+        match self {
+            CommandKind::Llm => format!("llm:generate a post about topic {}", n),
+            CommandKind::Viral => format!("viral:simulate campaign {}", n),
+            CommandKind::Unknown => format!("juggle torch {}", n),
+        }
+    }
+}
+
+/// Relative weights of each kind of command; they need not sum to 1.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CommandMix {
+    pub llm: f64,
+    pub viral: f64,
+    pub unknown: f64,
+}
+
+impl Default for CommandMix {
+    fn default() -> Self {
+        Self { llm: 0.5, viral: 0.4, unknown: 0.1 }
+    }
+}
+
+impl CommandMix {
+    fn weights(&self) -> [(CommandKind, f64); 3] {
+        [(CommandKind::Llm, self.llm), (CommandKind::Viral, self.viral), (CommandKind::Unknown, self.unknown)]
+    }
+}
+
+/// How requests pick among the workload's contexts.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ContextDistribution {
+    Uniform,
+    /// The `k`th context is picked in proportion to `1 / k^exponent`, so a few
+    /// contexts stay hot and the tail is touched rarely.
+    Zipfian { exponent: f64 },
+}
+
+/// When requests are sent.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Arrival {
+    /// `concurrency` clients, each sending its next request as soon as its last
+    /// one completes.
+    ClosedLoop { concurrency: usize },
+    /// A request falls due every `1 / per_sec` seconds, and is sent once one of
+    /// `concurrency` clients is free. Latency counts from when it fell due, so a
+    /// backlog shows up in it.
+    ConstantRate { per_sec: f64, concurrency: usize },
+}
+
+impl Arrival {
+    fn concurrency(&self) -> usize {
+        match *self {
+            Arrival::ClosedLoop { concurrency } | Arrival::ConstantRate { concurrency, .. } => concurrency,
+        }
+    }
+
+    /// When the `idx`th request falls due, after the start; `None` for closed loops.
+    fn due(&self, idx: usize) -> Option<Duration> {
+        match *self {
+            Arrival::ClosedLoop { .. } => None,
+            Arrival::ConstantRate { per_sec, .. } => Duration::try_from_secs_f64(idx as f64 / per_sec).ok(),
+        }
+    }
+}
+
+/// A synthetic load for `run_workload`. The same spec issues the same requests.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkloadSpec {
+    pub seed: u64,
+    pub requests: usize,
+    /// Context ids are `load-0` up to `load-<contexts - 1>`.
+    pub contexts: usize,
+    pub context_distribution: ContextDistribution,
+    pub mix: CommandMix,
+    pub arrival: Arrival,
+}
+
+impl Default for WorkloadSpec {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            requests: 1000,
+            contexts: 100,
+            context_distribution: ContextDistribution::Uniform,
+            mix: CommandMix::default(),
+            arrival: Arrival::ClosedLoop { concurrency: 8 },
+        }
+    }
+}
+
+impl WorkloadSpec {
+    /// The first field out of range, with why. `run_workload` runs one anyway,
+    /// with at least one context and client and all commands LLM ones if no
+    /// weight is positive.
+    pub fn invalid(&self) -> Option<(&'static str, String)> {
+        if self.contexts == 0 {
+            return Some(("contexts", "must be greater than zero".to_string()));
+        }
+        if let ContextDistribution::Zipfian { exponent } = self.context_distribution {
+            if !(exponent.is_finite() && exponent > 0.0) {
+                return Some(("context_distribution.exponent", format!("must be positive, got {}", exponent)));
+            }
+        }
+        let weights = self.mix.weights();
+        if weights.iter().any(|(_, weight)| !(weight.is_finite() && *weight >= 0.0)) {
+            return Some(("mix", "weights must be finite and not negative".to_string()));
+        }
+        if weights.iter().all(|(_, weight)| *weight == 0.0) {
+            return Some(("mix", "needs a positive weight".to_string()));
+        }
+        if self.arrival.concurrency() == 0 {
+            return Some(("arrival.concurrency", "must be greater than zero".to_string()));
+        }
+        match self.arrival {
+            Arrival::ConstantRate { per_sec, .. } if !(per_sec.is_finite() && per_sec > 0.0) => {
+                Some(("arrival.per_sec", format!("must be positive, got {}", per_sec)))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// One generated request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WorkloadRequest {
+    pub kind: CommandKind,
+    pub command: String,
+    pub context_id: String,
+}
+
+/// A seeded, endless stream of requests. Hand-rolled randomness, like the viral
+/// simulation's, so a seed gives the same stream on every platform.
+pub struct WorkloadGenerator {
+    rng: SplitMix64,
+    mix: CommandMix,
+    /// Cumulative weights of each context, the last being 1.
+    contexts: Vec<f64>,
+}
+
+impl WorkloadGenerator {
+    /// The default spec's mix and contexts, drawn from `seed`.
+    pub fn new(seed: u64) -> Self {
+        let spec = WorkloadSpec::default();
+        Self { rng: SplitMix64(seed), mix: spec.mix, contexts: vec![] }.contexts(spec.contexts, spec.context_distribution)
+    }
+
+    pub fn from_spec(spec: &WorkloadSpec) -> Self {
+        Self::new(spec.seed).mix(spec.mix.clone()).contexts(spec.contexts, spec.context_distribution)
+    }
+
+    pub fn mix(mut self, mix: CommandMix) -> Self {
+        self.mix = mix;
+        self
+    }
+
+    /// Picks among `count` contexts, at least one, as `distribution` says.
+    pub fn contexts(mut self, count: usize, distribution: ContextDistribution) -> Self {
+        let weight = |rank: usize| match distribution {
+            ContextDistribution::Uniform => 1.0,
+            ContextDistribution::Zipfian { exponent } => 1.0 / (rank as f64).powf(exponent),
+        };
+        let mut total = 0.0;
+        let cumulative: Vec<f64> = (1..=count.max(1))
+            .map(|rank| {
+                total += weight(rank);
+                total
+            })
+            .collect();
+        self.contexts = cumulative.iter().map(|sum| sum / total).collect();
+        self
+    }
+
+    fn kind(&mut self) -> CommandKind {
+        let weights = self.mix.weights().map(|(kind, weight)| (kind, if weight.is_finite() { weight.max(0.0) } else { 0.0 }));
+        let total: f64 = weights.iter().map(|(_, weight)| weight).sum();
+        if total <= 0.0 {
+            return CommandKind::Llm;
+        }
+        let mut pick = self.rng.next_f64() * total;
+        for (kind, weight) in weights {
+            if pick < weight {
+                return kind;
+            }
+            pick -= weight;
+        }
+        CommandKind::Unknown
+    }
+
+    fn context(&mut self) -> usize {
+        let pick = self.rng.next_f64();
+        self.contexts.partition_point(|&sum| sum <= pick).min(self.contexts.len() - 1)
+    }
+}
+
+impl Iterator for WorkloadGenerator {
+    type Item = WorkloadRequest;
+
+    fn next(&mut self) -> Option<WorkloadRequest> {
+        let kind = self.kind();
+        let context = self.context();
+        let command = kind.command(self.rng.next_u64() % 1000);
+        Some(WorkloadRequest { kind, command, context_id: format!("load-{}", context) })
+    }
+}
+
+/// A mock backend for `run_workload`: LLM calls echo the prompt after `latency`,
+/// and the viral simulation scores 0.5.
+pub fn workload_backend(latency: Duration) -> MockBackend {
+    MockBackend::new().virality(0.5).generator(move |prompt| {
+        thread::sleep(latency);
+        Ok(prompt.to_string())
+    })
+}
+
+/// Nearest-rank percentiles of request latencies, in seconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct LatencyPercentiles {
+    #[serde(with = "secs")]
+    pub p50: Duration,
+    #[serde(with = "secs")]
+    pub p90: Duration,
+    #[serde(with = "secs")]
+    pub p99: Duration,
+    #[serde(with = "secs")]
+    pub max: Duration,
+}
+
+impl LatencyPercentiles {
+    fn of(mut samples: Vec<Duration>) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        samples.sort();
+        let rank = |q: f64| samples[((q * samples.len() as f64).ceil() as usize).clamp(1, samples.len()) - 1];
+        Self { p50: rank(0.5), p90: rank(0.9), p99: rank(0.99), max: samples[samples.len() - 1] }
+    }
+}
+
+/// Requests and failures of one kind of command.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct KindReport {
+    pub requests: usize,
+    pub failures: usize,
+    pub failure_rate: f64,
+    pub latency: LatencyPercentiles,
+}
+
+impl KindReport {
+    fn of(outcomes: &[(CommandKind, Duration, bool)], kind: Option<CommandKind>) -> Self {
+        let outcomes: Vec<_> = outcomes.iter().filter(|(of, ..)| kind.is_none_or(|kind| kind == *of)).collect();
+        let failures = outcomes.iter().filter(|(.., success)| !success).count();
+        Self {
+            requests: outcomes.len(),
+            failures,
+            failure_rate: if outcomes.is_empty() { 0.0 } else { failures as f64 / outcomes.len() as f64 },
+            latency: LatencyPercentiles::of(outcomes.iter().map(|(_, latency, _)| *latency).collect()),
+        }
+    }
+}
+
+/// What `run_workload` measured, as JSON for comparing against a baseline.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WorkloadReport {
+    pub spec: WorkloadSpec,
+    #[serde(flatten)]
+    pub overall: KindReport,
+    pub by_kind: BTreeMap<CommandKind, KindReport>,
+    /// Requests completed a second.
+    pub throughput: f64,
+    #[serde(rename = "elapsed_secs", with = "secs")]
+    pub elapsed: Duration,
+    /// Distinct contexts the requests went to, and contexts evicted meanwhile.
+    pub contexts_touched: usize,
+    pub evictions: u64,
+}
+
+/// Sends `spec`'s requests to `orchestrator` through `process`, as its arrival
+/// pattern says, and reports how they went. A request fails when its run does
+/// not succeed. Use `workload_backend` to measure the orchestrator alone.
+pub fn run_workload(orchestrator: &CognitiveOrchestrator, spec: &WorkloadSpec) -> WorkloadReport {
+    let requests: Vec<WorkloadRequest> = WorkloadGenerator::from_spec(spec).take(spec.requests).collect();
+    let evicted_before = orchestrator.contexts_evicted();
+    let outcomes = Mutex::new(Vec::with_capacity(requests.len()));
+    let next = AtomicUsize::new(0);
+    let started = Instant::now();
+    thread::scope(|scope| {
+        for _ in 0..spec.arrival.concurrency().clamp(1, requests.len().max(1)) {
+            scope.spawn(|| loop {
+                let idx = next.fetch_add(1, Ordering::SeqCst);
+                let Some(request) = requests.get(idx) else { break };
+                let due = match spec.arrival.due(idx) {
+                    Some(due) => {
+                        thread::sleep((started + due).saturating_duration_since(Instant::now()));
+                        started + due
+                    }
+                    None => Instant::now(),
+                };
+                let report = orchestrator.process_report(request.command.clone(), &request.context_id);
+                let outcome = (request.kind, due.elapsed(), report.success);
+                outcomes.lock().unwrap_or_else(|e| e.into_inner()).push(outcome);
+            });
+        }
+    });
+    let elapsed = started.elapsed();
+    let outcomes = outcomes.into_inner().unwrap_or_else(|e| e.into_inner());
+    let kinds: HashSet<CommandKind> = requests.iter().map(|request| request.kind).collect();
+    WorkloadReport {
+        spec: spec.clone(),
+        overall: KindReport::of(&outcomes, None),
+        by_kind: kinds.into_iter().map(|kind| (kind, KindReport::of(&outcomes, Some(kind)))).collect(),
+        throughput: if elapsed.is_zero() { 0.0 } else { outcomes.len() as f64 / elapsed.as_secs_f64() },
+        elapsed,
+        contexts_touched: requests.iter().map(|request| &request.context_id).collect::<HashSet<_>>().len(),
+        evictions: orchestrator.contexts_evicted() - evicted_before,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn a_seed_gives_the_same_requests_and_zipf_favours_the_first_contexts() {
+        let spec = WorkloadSpec { context_distribution: ContextDistribution::Zipfian { exponent: 1.2 }, ..WorkloadSpec::default() };
+        let first: Vec<_> = WorkloadGenerator::from_spec(&spec).take(500).collect();
+        assert_eq!(first, WorkloadGenerator::from_spec(&spec).take(500).collect::<Vec<_>>());
+        assert_ne!(first, WorkloadGenerator::new(1).take(500).collect::<Vec<_>>());
+
+        let hot = first.iter().filter(|request| request.context_id == "load-0").count();
+        let uniform = WorkloadGenerator::new(0).take(500).filter(|request| request.context_id == "load-0").count();
+        assert!(hot > 100 && uniform < 25, "{} {}", hot, uniform);
+        let llm = first.iter().filter(|request| request.kind == CommandKind::Llm).count();
+        assert!((200..300).contains(&llm), "{}", llm);
+
+        let only_viral = CommandMix { llm: 0.0, viral: 1.0, unknown: 0.0 };
+        assert!(WorkloadGenerator::new(3).mix(only_viral).take(50).all(|request| request.kind == CommandKind::Viral));
+        let broken = WorkloadSpec { mix: CommandMix { llm: 0.0, viral: 0.0, unknown: 0.0 }, ..WorkloadSpec::default() };
+        assert_eq!(broken.invalid().map(|(field, _)| field), Some("mix"));
+    }
+
+    #[test]
+    fn run_workload_reports_failures_latency_and_evictions() {
+        let orch = CognitiveOrchestrator::builder()
+            .backend(Arc::new(workload_backend(Duration::from_millis(2))))
+            .learning(false)
+            .max_contexts(4)
+            .build()
+            .unwrap();
+        let spec = WorkloadSpec { requests: 200, contexts: 20, ..WorkloadSpec::default() };
+        let report = run_workload(&orch, &spec);
+
+        assert_eq!(report.overall.requests, 200);
+        let unknown = &report.by_kind[&CommandKind::Unknown];
+        assert_eq!((unknown.failures, unknown.failure_rate), (unknown.requests, 1.0));
+        assert_eq!(report.by_kind[&CommandKind::Llm].failures, 0);
+        assert_eq!(report.overall.failures, unknown.requests);
+        let llm = report.by_kind[&CommandKind::Llm].latency;
+        assert!(llm.p50 >= Duration::from_millis(2) && llm.p50 <= llm.p99 && llm.p99 <= llm.max);
+        assert!(report.evictions > 0 && report.contexts_touched == 20);
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["requests"], 200);
+        assert!(json["by_kind"]["viral"]["latency"]["p90"].is_number());
+        assert_eq!(json["spec"]["arrival"], serde_json::json!({"kind": "closed_loop", "concurrency": 8}));
+    }
+
+    #[test]
+    fn constant_rate_paces_the_requests() {
+        let orch = CognitiveOrchestrator::builder().backend(Arc::new(workload_backend(Duration::ZERO))).build().unwrap();
+        let spec = WorkloadSpec {
+            requests: 20,
+            arrival: Arrival::ConstantRate { per_sec: 200.0, concurrency: 4 },
+            ..WorkloadSpec::default()
+        };
+        let report = run_workload(&orch, &spec);
+        // The last request falls due 95ms in.
+        assert!(report.elapsed >= Duration::from_millis(95), "{:?}", report.elapsed);
+        assert_eq!(report.overall.requests, 20);
+    }
+}
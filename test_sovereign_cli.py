@@ -0,0 +1,2458 @@
+#!/usr/bin/env python3
+"""
+Smoke tests for the sovereign_cli Rust extension
+Build first with `maturin develop`, then run with pytest
+"""
+
+import asyncio
+import json
+import os
+import pathlib
+import pickle
+import subprocess
+import sys
+import types
+import urllib.request
+
+import pytest
+
+sovereign_cli = pytest.importorskip("sovereign_cli")
+
+
+def test_process_llm_query():
+    """Orchestrator constructs and returns a JSON list of subtask outputs"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator()
+    outputs = json.loads(orchestrator.process("query llm hello", "ctx1"))
+    assert isinstance(outputs, list)
+    assert len(outputs) >= 1
+
+
+def test_dispatch_returns_agent_result():
+    """dispatch hands back an AgentResult with attribute access"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator()
+    result = orchestrator.dispatch("unknown step", "ctx1")
+    assert isinstance(result, sovereign_cli.AgentResult)
+    assert result.status is False
+    assert isinstance(result.metadata, dict)
+    assert "AgentResult(" in repr(result)
+
+
+def test_agent_result_pickle_roundtrip():
+    """AgentResult survives pickling and compares by value"""
+    result = sovereign_cli.AgentResult("ok", True, {"virality": 0.9, "tags": ["a"]})
+    restored = pickle.loads(pickle.dumps(result))
+    assert restored == result
+    assert restored.metadata["tags"] == ["a"]
+
+
+def test_context_field_access():
+    """Contexts expose their viral metrics after planning"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator()
+    orchestrator.proactive_plan("go viral", "ctx1")
+    ctx = orchestrator.get_context("ctx1")
+    assert ctx.context_id == "ctx1"
+    assert ctx.viral_metrics.hook_rate == 0.05
+    assert pickle.loads(pickle.dumps(ctx)) == ctx
+
+
+def test_save_and_load_contexts(tmp_path):
+    """Checkpointed contexts restore with their timestamps intact"""
+    path = tmp_path / "contexts.json"
+    orchestrator = sovereign_cli.CognitiveOrchestrator()
+    orchestrator.proactive_plan("go viral", "ctx1")
+    orchestrator.save_contexts(str(path))
+
+    restored = sovereign_cli.CognitiveOrchestrator()
+    report = restored.load_contexts(str(path))
+    assert report.loaded == ["ctx1"]
+    assert report.failed == []
+    assert restored.get_context("ctx1") == orchestrator.get_context("ctx1")
+
+
+def test_plan_templates_first_registered_wins():
+    """Overlapping triggers resolve to the earliest registration"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator()
+    orchestrator.clear_plan_templates()
+    orchestrator.register_plan_template("prefix", "launch", ["a", "b"])
+    orchestrator.register_plan_template("regex", r"^launch\s+\w+", ["c"])
+    orchestrator.register_plan_template("keyword", "rocket", ["d"])
+
+    assert orchestrator.proactive_plan("launch rocket", "ctx1") == ["a", "b"]
+    assert orchestrator.proactive_plan("big rocket", "ctx1") == ["d"]
+
+
+def test_default_viral_template():
+    """The built-in viral pipeline is served from the template registry"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator()
+    plan = orchestrator.proactive_plan("make it engage", "ctx1")
+    assert plan[0] == "content:generate"
+    assert len(plan) == 5
+
+
+def test_dispatch_viral_without_process():
+    """Dispatching a viral subtask on an unseen context must not panic"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator()
+    result = orchestrator.dispatch("do viral thing", "never-seen-ctx")
+    assert isinstance(result, sovereign_cli.AgentResult)
+    assert orchestrator.get_context("never-seen-ctx") is not None
+
+
+def _install_agent_module(name, **attrs):
+    """Register a stub agent module so the orchestrator imports it instead of the real one"""
+    module = types.ModuleType(name)
+    for key, value in attrs.items():
+        setattr(module, key, value)
+    sys.modules[name] = module
+    return module
+
+
+def _without_timing(metadata):
+    """Result metadata without the orchestrator's _timing.* keys, which vary between runs"""
+    return {key: value for key, value in metadata.items() if not key.startswith("_timing.")}
+
+
+def test_broken_llm_agent_reports_call_failure():
+    """A raising LLM agent yields a call_failed error with its traceback"""
+    class LLMAgent:
+        def generate(self, prompt):
+            raise RuntimeError("model exploded")
+
+    _install_agent_module("python.agents.llm_agent", LLMAgent=LLMAgent)
+    try:
+        orchestrator = sovereign_cli.CognitiveOrchestrator()
+        result = orchestrator.dispatch("query llm hello", "ctx1")
+        assert result.status is False
+        assert result.error["kind"] == "call_failed"
+        assert "model exploded" in result.error["message"]
+        assert "raise RuntimeError" in result.error["traceback"]
+        assert result.metadata["error"]["kind"] == "call_failed"
+    finally:
+        sys.modules.pop("python.agents.llm_agent", None)
+
+
+def test_unknown_subtask_error_kind():
+    """Unroutable subtasks carry the unknown_subtask error kind"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator()
+    result = orchestrator.dispatch("make coffee", "ctx1")
+    assert result.error["kind"] == "unknown_subtask"
+    assert result.output.startswith("Unknown subtask")
+    assert "registered agents: llm, viral, mwpm, content, hook, spread, eval, memory" in result.output
+    assert orchestrator.agent_names() == ["llm", "viral", "mwpm", "content", "hook", "spread", "eval", "memory"]
+
+
+def test_process_async_from_asyncio():
+    """process_async is awaitable and yields the same output shape as process"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator()
+
+    async def run():
+        return await orchestrator.process_async("query llm hello", "ctx1")
+
+    outputs = json.loads(asyncio.run(run()))
+    assert outputs == json.loads(orchestrator.process("query llm hello", "ctx1"))
+
+
+def test_process_parallel_overlaps_and_keeps_order():
+    """Independent subtasks are all in flight at once and come back in plan order"""
+    import threading
+
+    # Passes only once all three calls are waiting on it together.
+    barrier = threading.Barrier(3, timeout=10)
+
+    class LLMAgent:
+        def generate(self, prompt):
+            barrier.wait()
+            return prompt.upper()
+
+    _install_agent_module("python.agents.llm_agent", LLMAgent=LLMAgent)
+    try:
+        orchestrator = sovereign_cli.CognitiveOrchestrator()
+        orchestrator.register_plan_template(
+            "prefix", "variants", ["query llm a", "query llm b", "query llm c"]
+        )
+        results = orchestrator.process_parallel("variants please", "ctx1", 3)
+        assert [r.output for r in results] == ["A", "B", "C"]
+        assert not barrier.broken
+    finally:
+        sys.modules.pop("python.agents.llm_agent", None)
+
+
+def test_process_parallel_fail_fast_cancels_pending():
+    """With fail_fast a failure stops not-yet-started subtasks"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator()
+    orchestrator.register_plan_template("prefix", "steps", ["bogus", "also bogus", "more"])
+    results = orchestrator.process_parallel("steps", "ctx1", 1, fail_fast=True)
+    assert results[0].error["kind"] == "unknown_subtask"
+    assert [r.error["kind"] for r in results[1:]] == ["cancelled", "cancelled"]
+
+
+class _ResearchAgent:
+    """Toy Python agent handling `research ...` subtasks"""
+
+    def can_handle(self, sub_task):
+        return sub_task.startswith("research")
+
+    def execute(self, sub_task, context):
+        if "explode" in sub_task:
+            raise ValueError("research failed")
+        return {
+            "output": f"found 3 papers for {context['context_id']}",
+            "status": True,
+            "metadata": {"papers": 3},
+        }
+
+
+def test_python_agent_output_appears_in_process():
+    """A registered Python agent serves matching subtasks during process"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator()
+    orchestrator.register_python_agent("research", _ResearchAgent())
+    orchestrator.register_plan_template("prefix", "study", ["research topic"])
+
+    assert json.loads(orchestrator.process("study it", "ctx1")) == ["found 3 papers for ctx1"]
+    assert _without_timing(orchestrator.dispatch("research topic", "ctx1").metadata) == {"papers": 3}
+
+
+def test_python_agent_exception_becomes_failed_result():
+    """Exceptions inside a Python agent fail the subtask without crashing"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator()
+    orchestrator.register_python_agent("research", _ResearchAgent())
+    result = orchestrator.dispatch("research explode", "ctx1")
+    assert result.status is False
+    assert "research failed" in result.error["traceback"] + result.error["message"]
+
+
+def test_recall_ranks_by_cosine_similarity():
+    """recall returns the closest memory vectors first, capped by the store size"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator()
+    assert orchestrator.add_memory("ctx1", [1.0, 0.0]) == 0
+    assert orchestrator.add_memory("ctx1", [0.0, 1.0]) == 1
+    assert orchestrator.add_memory("ctx1", [1.0, 1.0]) == 2
+
+    hits = orchestrator.recall("ctx1", [1.0, 0.1], 10)
+    assert [idx for idx, _ in hits] == [0, 2, 1]
+    assert hits[0][1] == pytest.approx(0.995, abs=1e-3)
+    assert orchestrator.get_context("ctx1").memory_vectors == [[1.0, 0.0], [0.0, 1.0], [1.0, 1.0]]
+
+
+def test_recall_empty_memory_and_dimension_mismatch():
+    """Empty memory yields no hits; mismatched dimensions are rejected"""
+    # Without learning, the failed run leaves no anomaly in memory.
+    orchestrator = sovereign_cli.CognitiveOrchestrator(learning=False)
+    orchestrator.process("query llm hi", "ctx1")
+    assert orchestrator.recall("ctx1", [1.0, 0.0, 0.0], 3) == []
+
+    orchestrator.add_memory("ctx1", [1.0, 0.0, 0.0])
+    with pytest.raises(RuntimeError, match="dimension mismatch"):
+        orchestrator.add_memory("ctx1", [1.0, 0.0])
+    with pytest.raises(RuntimeError, match="dimension mismatch"):
+        orchestrator.recall("ctx1", [1.0], 1)
+
+
+def test_simulate_viral_is_deterministic_per_seed():
+    """The native propagator reproduces exact spread counts for a seed"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator()
+    report = orchestrator.simulate_viral(32, 0.9, 10, 7)
+    assert report.reached_per_round == [1, 3, 9, 18, 29, 32]
+    assert report.peak_amplification == 3.0
+    assert report.virality_score == 1.0
+
+    assert orchestrator.simulate_viral(32, 0.5, 10, 7).reached_per_round == [1, 2, 4, 7]
+    assert orchestrator.simulate_viral(0, 0.9, 10, 7).reached_per_round == []
+
+
+def test_prefer_native_dispatch_updates_viral_metrics():
+    """With prefer_native, viral subtasks skip python.agents.viral_agent"""
+    _install_agent_module("python.agents.viral_agent")
+    orchestrator = sovereign_cli.CognitiveOrchestrator(prefer_native=True)
+    result = orchestrator.dispatch("viral sim", "ctx1")
+
+    assert result.metadata["engine"] == "native"
+    assert result.metadata["metrics"]["reached_per_round"][0] == 1
+    metrics = orchestrator.get_context("ctx1").viral_metrics
+    assert metrics.virality_score == result.metadata["virality"]
+
+    orchestrator.prefer_native = False
+    assert orchestrator.dispatch("viral sim", "ctx1").error["kind"] == "attribute_missing"
+
+
+def test_amplify_hand_computed_2x2():
+    """diag(2, 1) over two rounds: growth sqrt(5/2) then sqrt(17/5)"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator()
+    result = orchestrator.amplify([[2.0, 0.0], [0.0, 1.0]], 2)
+    assert result.amplification_factor == pytest.approx(8.5 ** 0.25, abs=1e-12)
+    assert result.fidelity == pytest.approx(9 / 85 ** 0.5, abs=1e-12)
+
+
+def test_amplify_hand_computed_4x4():
+    """The all-ones 4x4 matrix grows by 2, then by its spectral radius 4"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator()
+    ones = [[1.0] * 4 for _ in range(4)]
+
+    one_round = orchestrator.amplify(ones, 1)
+    assert one_round.amplification_factor == pytest.approx(2.0, abs=1e-12)
+    assert one_round.fidelity == pytest.approx(0.5, abs=1e-12)
+
+    three_rounds = orchestrator.amplify(ones, 3)
+    assert three_rounds.amplification_factor == pytest.approx(32 ** (1 / 3), abs=1e-12)
+    assert three_rounds.fidelity == pytest.approx(1.0, abs=1e-12)
+
+
+def test_amplify_metrics_updates_context():
+    """Viral dispatch and amplify_metrics move amplification off its initial values"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator(prefer_native=True)
+    with pytest.raises(RuntimeError, match="ghost"):
+        orchestrator.amplify_metrics("ghost")
+
+    orchestrator.dispatch("viral sim", "ctx1")
+    metrics = orchestrator.get_context("ctx1").viral_metrics
+    assert metrics.amplification_factor > 1.0
+    assert metrics.quantum_fidelity != 0.99
+    assert orchestrator.amplify_metrics("ctx1") == metrics
+
+
+def test_mwpm_step_reports_decoded_fidelity():
+    """amplify MWPM decodes a capped repetition code and reports corrected amplification"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator()
+    result = orchestrator.dispatch("amplify MWPM", "ctx1")
+    assert result.status is True
+    assert result.metadata["distance"] == 5
+    assert 0.5 <= result.metadata["fidelity"] <= 1.0
+    assert result.metadata["corrected_amplification"] == pytest.approx(result.metadata["fidelity"], abs=1e-12)
+    repeat = orchestrator.dispatch("amplify MWPM", "ctx1")
+    assert (repeat.output, repeat.status, _without_timing(repeat.metadata)) == (
+        result.output,
+        result.status,
+        _without_timing(result.metadata),
+    )
+
+
+class _EchoLLM:
+    """Stub LLM that echoes its prompt"""
+
+    def generate(self, prompt):
+        return f"post: {prompt}"
+
+
+def test_gen_content_uses_templated_prompt():
+    """gen content sends a templated prompt to the LLM agent"""
+    _install_agent_module("python.agents.llm_agent", LLMAgent=_EchoLLM)
+    try:
+        orchestrator = sovereign_cli.CognitiveOrchestrator()
+        result = orchestrator.dispatch("gen content about rust", "ctx1")
+        assert result.status is True
+        assert result.output == "post: " + result.metadata["prompt"]
+        assert "Goals: grow engagement" in result.metadata["prompt"]
+        assert "Brief: about rust" in result.metadata["prompt"]
+    finally:
+        sys.modules.pop("python.agents.llm_agent", None)
+
+
+def test_inject_hook_adjusts_hook_rate():
+    """inject hook boosts the hook rate, or sets it when given a value"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator()
+    result = orchestrator.dispatch("inject hook", "ctx1")
+    assert result.status is True
+    assert orchestrator.get_context("ctx1").viral_metrics.hook_rate == pytest.approx(0.15)
+
+    orchestrator.dispatch("inject hook 1.7", "ctx1")
+    assert orchestrator.get_context("ctx1").viral_metrics.hook_rate == 1.0
+    assert orchestrator.dispatch("inject hook lots", "ctx1").error["kind"] == "extraction"
+
+
+def test_measure_spread_runs_viral_simulation():
+    """measure spread reports the simulated virality without judging it"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator(prefer_native=True)
+    result = orchestrator.dispatch("measure spread", "ctx1")
+    expected = orchestrator.simulate_viral(32, 0.05, 10, 0).virality_score
+    assert result.status is True
+    assert result.metadata["virality"] == expected
+    assert orchestrator.get_context("ctx1").viral_metrics.virality_score == expected
+
+
+def test_eval_metrics_summarizes_viral_metrics():
+    """eval metrics echoes the context's ViralMetrics in output and metadata"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator()
+    result = orchestrator.dispatch("eval metrics", "ctx1")
+    assert result.status is True
+    assert result.output.startswith("Metrics: virality=0.0000, nodes=32, hook_rate=0.0500")
+    assert result.metadata["engagement_nodes"] == 32
+    assert result.metadata["quantum_fidelity"] == 0.99
+
+
+def test_full_viral_plan_succeeds_through_process():
+    """process("go viral") runs all five viral plan steps successfully"""
+    _install_agent_module("python.agents.llm_agent", LLMAgent=_EchoLLM)
+    try:
+        orchestrator = sovereign_cli.CognitiveOrchestrator(prefer_native=True)
+        outputs = json.loads(orchestrator.process("go viral", "ctx1"))
+        assert len(outputs) == 5
+        assert outputs[0].startswith("post: ")
+        assert outputs[1].startswith("Hook: hook_rate 0.0500 -> 0.1500")
+        assert outputs[2].startswith("MWPM: distance=5")
+        assert outputs[3].startswith("Spread: Virality=")
+        assert outputs[4].startswith("Metrics: ")
+
+        steps = orchestrator.proactive_plan("go viral", "ctx2")
+        assert all(orchestrator.dispatch(step, "ctx2").status for step in steps)
+    finally:
+        sys.modules.pop("python.agents.llm_agent", None)
+
+
+def test_context_ttl_expiry():
+    """Contexts idle past the TTL are evicted by evict_expired"""
+    import time
+
+    orchestrator = sovereign_cli.CognitiveOrchestrator(context_ttl=0.05)
+    orchestrator.add_memory("ctx1", [1.0])
+    assert orchestrator.evict_expired() == []
+    time.sleep(0.1)
+    orchestrator.add_memory("ctx2", [1.0])
+    assert orchestrator.get_context("ctx1") is None
+    assert orchestrator.evict_expired() == []
+    assert orchestrator.get_context("ctx2") is not None
+
+
+def test_context_lru_eviction_flushes_to_disk(tmp_path):
+    """Over max_contexts the least recently accessed context is flushed and evicted"""
+    path = tmp_path / "evicted.json"
+    orchestrator = sovereign_cli.CognitiveOrchestrator(max_contexts=2, eviction_path=str(path))
+    orchestrator.add_memory("a", [1.0])
+    orchestrator.add_memory("b", [2.0])
+    orchestrator.add_memory("a", [3.0])
+    orchestrator.add_memory("c", [4.0])
+
+    assert orchestrator.get_context("b") is None
+    assert orchestrator.get_context("a").memory_vectors == [[1.0], [3.0]]
+
+    restored = sovereign_cli.CognitiveOrchestrator()
+    assert restored.load_contexts(str(path)).loaded == ["b"]
+    assert restored.get_context("b").memory_vectors == [[2.0]]
+
+
+def test_active_context_not_evicted_mid_process():
+    """A context with a run in flight survives eviction pressure from other contexts"""
+    import time
+
+    class LLMAgent:
+        def generate(self, prompt):
+            time.sleep(0.3)
+            return "slow"
+
+    _install_agent_module("python.agents.llm_agent", LLMAgent=LLMAgent)
+    try:
+        orchestrator = sovereign_cli.CognitiveOrchestrator(max_contexts=1)
+        orchestrator.register_plan_template(
+            "prefix", "slowplan", ["inject hook 0.9", "query llm slow", "eval metrics"]
+        )
+
+        async def run():
+            task = asyncio.ensure_future(orchestrator.process_async("slowplan", "ctxA"))
+            await asyncio.sleep(0.1)
+            orchestrator.add_memory("ctxB", [1.0])
+            assert orchestrator.get_context("ctxA") is not None
+            return await task
+
+        outputs = json.loads(asyncio.run(run()))
+        assert "hook_rate=0.9000" in outputs[2]
+        assert orchestrator.evict_expired() == ["ctxB"]
+    finally:
+        sys.modules.pop("python.agents.llm_agent", None)
+
+
+class _HangingLLM:
+    """LLM stub that never returns on its own; records when it is interrupted"""
+
+    import threading
+
+    aborted = threading.Event()
+
+    def generate(self, prompt):
+        import time
+
+        if "slow" in prompt:
+            time.sleep(0.2)
+            return "slow done"
+        try:
+            while True:
+                time.sleep(0.01)
+        except TimeoutError:
+            _HangingLLM.aborted.set()
+            raise
+
+
+def test_subtask_timeout_aborts_hung_agent():
+    """A hung agent times out with a timeout marker and its Python call is interrupted"""
+    import time
+
+    _HangingLLM.aborted.clear()
+    _install_agent_module("python.agents.llm_agent", LLMAgent=_HangingLLM)
+    try:
+        orchestrator = sovereign_cli.CognitiveOrchestrator(subtask_timeout=0.1)
+        started = time.monotonic()
+        result = orchestrator.dispatch("query llm hang", "ctx1")
+        assert time.monotonic() - started < 1.0
+        assert result.status is False
+        assert result.error["kind"] == "timeout"
+        assert result.metadata["timeout"] == 0.1
+        assert _HangingLLM.aborted.wait(1.0)
+    finally:
+        sys.modules.pop("python.agents.llm_agent", None)
+
+
+def test_process_timeout_overrides_default():
+    """process(timeout=...) overrides the orchestrator's subtask timeout per call"""
+    _install_agent_module("python.agents.llm_agent", LLMAgent=_HangingLLM)
+    try:
+        orchestrator = sovereign_cli.CognitiveOrchestrator(subtask_timeout=0.05)
+        assert json.loads(orchestrator.process("query llm slow", "ctx1", timeout=1.0)) == ["slow done"]
+        assert json.loads(orchestrator.process("query llm slow", "ctx1"))[0].startswith("Timeout")
+
+        unbounded = sovereign_cli.CognitiveOrchestrator()
+        assert json.loads(unbounded.process("query llm hang", "ctx1", timeout=0.05))[0].startswith("Timeout")
+    finally:
+        sys.modules.pop("python.agents.llm_agent", None)
+
+
+class _FlakyAgent:
+    """Fails with a rate-limit reply `failures` times, then succeeds"""
+
+    def __init__(self, failures, message="rate limit exceeded"):
+        self.failures = failures
+        self.message = message
+        self.calls = 0
+
+    def can_handle(self, sub_task):
+        return sub_task.startswith("flaky")
+
+    def execute(self, sub_task, context):
+        self.calls += 1
+        if self.calls <= self.failures:
+            return {"output": self.message, "status": False}
+        return {"output": f"ok after {self.calls}", "status": True}
+
+
+def test_retry_recovers_from_transient_failures():
+    """Rate-limited subtasks are retried with backoff until they succeed"""
+    agent = _FlakyAgent(failures=2)
+    orchestrator = sovereign_cli.CognitiveOrchestrator()
+    orchestrator.register_python_agent("flaky", agent)
+    orchestrator.set_retry_policy(4, base_delay=0.01, max_delay=0.05)
+
+    result = orchestrator.dispatch("flaky call", "ctx1")
+    assert result.status is True
+    assert result.output == "ok after 3"
+    assert result.metadata["attempts"] == 3
+    assert result.metadata["retry_latency"] >= 0.01
+
+
+def test_retry_gives_up_and_skips_permanent_failures():
+    """Retries stop at max_attempts and never apply to unknown subtasks"""
+    agent = _FlakyAgent(failures=10)
+    orchestrator = sovereign_cli.CognitiveOrchestrator()
+    orchestrator.register_python_agent("flaky", agent)
+    orchestrator.set_retry_policy(3, base_delay=0.001, max_delay=0.001)
+
+    assert orchestrator.dispatch("flaky call", "ctx1").metadata["attempts"] == 3
+    assert agent.calls == 3
+    unknown = orchestrator.dispatch("make coffee", "ctx1")
+    assert unknown.error["kind"] == "unknown_subtask"
+    assert unknown.metadata["attempts"] == 1
+
+
+def test_retry_custom_predicate():
+    """retry_on replaces the default transient-failure check"""
+    agent = _FlakyAgent(failures=1, message="flaky backend")
+    orchestrator = sovereign_cli.CognitiveOrchestrator()
+    orchestrator.register_python_agent("flaky", agent)
+
+    orchestrator.set_retry_policy(3, base_delay=0.001)
+    assert orchestrator.dispatch("flaky call", "ctx1").status is False
+
+    agent.calls = 0
+    orchestrator.set_retry_policy(3, base_delay=0.001, retry_on=lambda r: "flaky" in r.output)
+    assert orchestrator.dispatch("flaky call", "ctx1").metadata["attempts"] == 2
+
+
+def test_process_stream_yields_ordered_events():
+    """process_stream yields plan, per-subtask and completion events in order"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator()
+    orchestrator.register_python_agent("research", _ResearchAgent())
+    orchestrator.register_plan_template("prefix", "study", ["research topic", "research more"])
+
+    events = list(orchestrator.process_stream("study it", "ctx1"))
+    assert [e["event"] for e in events] == [
+        "plan_ready",
+        "subtask_started",
+        "subtask_finished",
+        "subtask_started",
+        "subtask_finished",
+        "completed",
+    ]
+    assert events[0]["subtasks"] == ["research topic", "research more"]
+    assert events[1]["subtask"] == "research topic"
+    assert events[2]["result"]["output"] == "found 3 papers for ctx1"
+    assert json.loads(events[-1]["output"]) == ["found 3 papers for ctx1"] * 2
+    times = [e["at"] for e in events]
+    assert times == sorted(times)
+
+
+class _LowViralityAgent:
+    """Handles `spread ...`; only the alternate strategy gets traction"""
+
+    def can_handle(self, sub_task):
+        return sub_task.startswith("spread")
+
+    def execute(self, sub_task, context):
+        if "alt" in sub_task:
+            return {"output": f"{sub_task} ok", "status": True}
+        return {"output": "low virality", "status": False}
+
+
+def _install_debug_agent(re_plan):
+    _install_agent_module("python.memory", QdrantMemory=lambda: types.SimpleNamespace(store_context=lambda *a: None))
+    _install_agent_module("python.agents.debug_agent", DebugAgent=lambda: types.SimpleNamespace(re_plan=re_plan))
+
+
+def _remove_debug_agent():
+    sys.modules.pop("python.memory", None)
+    sys.modules.pop("python.agents.debug_agent", None)
+
+
+def test_process_stream_reports_replan():
+    """A self_debug re-plan is streamed and its subtasks run before the rest of the plan"""
+    _install_debug_agent(lambda *a: ["spread alt"])
+    try:
+        orchestrator = sovereign_cli.CognitiveOrchestrator()
+        orchestrator.register_python_agent("spread", _LowViralityAgent())
+        orchestrator.register_plan_template("prefix", "boost", ["spread post", "spread again"])
+
+        stream = orchestrator.process_stream("boost it", "ctx1")
+        assert iter(stream) is stream
+        events = list(stream)
+        assert [e["event"] for e in events][:7] == [
+            "plan_ready",
+            "subtask_started",
+            "subtask_finished",
+            "replan_triggered",
+            "subtask_started",
+            "subtask_finished",
+            "subtask_started",
+        ]
+        assert events[3]["subtask"] == "spread post"
+        assert events[3]["subtasks"] == ["spread alt"]
+        assert events[5]["result"]["metadata"]["replanned_from"] == "spread post"
+        assert events[6]["subtask"] == "spread again"
+        assert events[-1]["replanned"] == ["spread alt", "spread alt"]
+    finally:
+        _remove_debug_agent()
+
+
+def test_low_virality_replan_executes_alternate_plan():
+    """process runs the debug agent's alternate plan in place of the failed step"""
+    _install_debug_agent(lambda *a: "spread alt strategy")
+    try:
+        orchestrator = sovereign_cli.CognitiveOrchestrator()
+        orchestrator.register_python_agent("spread", _LowViralityAgent())
+        orchestrator.register_plan_template("prefix", "boost", ["spread post"])
+
+        assert json.loads(orchestrator.process("boost it", "ctx1")) == ["low virality", "spread alt strategy ok"]
+        failed = orchestrator.dispatch("spread post", "ctx1")
+        assert orchestrator.self_debug(failed, "spread post", "ctx1") == ["spread alt strategy"]
+    finally:
+        _remove_debug_agent()
+
+
+def test_max_replans_stops_replan_loop():
+    """A re-plan that keeps failing is retried at most max_replans times"""
+    _install_debug_agent(lambda *a: "spread post again")
+    try:
+        orchestrator = sovereign_cli.CognitiveOrchestrator(max_replans=2)
+        orchestrator.register_python_agent("spread", _LowViralityAgent())
+        orchestrator.register_plan_template("prefix", "boost", ["spread post"])
+
+        assert json.loads(orchestrator.process("boost it", "ctx1")) == ["low virality"] * 3
+
+        async def run():
+            return await orchestrator.process_async("boost it", "ctx1")
+
+        assert json.loads(asyncio.run(run())) == ["low virality"] * 3
+    finally:
+        _remove_debug_agent()
+
+
+_TRACED_RUN = """
+import sovereign_cli
+
+class Research:
+    def can_handle(self, sub_task):
+        return sub_task.startswith("research")
+
+    def execute(self, sub_task, context):
+        if "explode" in sub_task:
+            raise ValueError("research failed")
+        return {"output": "ok", "status": True}
+
+assert sovereign_cli.CognitiveOrchestrator.init_tracing("debug")
+assert not sovereign_cli.CognitiveOrchestrator.init_tracing("debug")
+orchestrator = sovereign_cli.CognitiveOrchestrator()
+orchestrator.register_python_agent("research", Research())
+orchestrator.register_plan_template("prefix", "study", ["research topic", "research explode"])
+orchestrator.process("study it", "ctx1")
+"""
+
+
+def test_tracing_spans_nest_under_process():
+    """init_tracing logs dispatch and self_debug events inside the process span"""
+    run = subprocess.run([sys.executable, "-c", _TRACED_RUN], capture_output=True, text=True, env={"PYTHONPATH": ":".join(sys.path)})
+    assert run.returncode == 0, run.stderr
+    lines = run.stderr.splitlines()
+    process = 'process{context_id=ctx1 command=study it}'
+    assert any(process + ":proactive_plan{" in line and "planned" in line for line in lines)
+    assert any(process + ':dispatch{subtask="research topic"' in line and "python call" in line for line in lines)
+    assert any(process + ':dispatch{subtask="research explode"' in line and "research failed" in line for line in lines)
+    assert any(process + ":self_debug{" in line and "Anomaly log failed" in line for line in lines)
+
+    with pytest.raises(RuntimeError, match="invalid log filter"):
+        sovereign_cli.CognitiveOrchestrator.init_tracing("sovereign_cli=[")
+
+
+def _metric(text, name, **labels):
+    """Value of one sample in Prometheus text exposition, or None"""
+    selector = ",".join(f'{k}="{v}"' for k, v in labels.items())
+    prefix = f"{name}{{{selector}}} " if labels else f"{name} "
+    values = [float(line[len(prefix):]) for line in text.splitlines() if line.startswith(prefix)]
+    return values[0] if values else None
+
+
+def test_metrics_count_dispatches_failures_and_contexts():
+    """metrics_text exports dispatch, failure, latency, context and virality metrics"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator(prefer_native=True)
+    orchestrator.register_python_agent("research", _ResearchAgent())
+    orchestrator.dispatch("research topic", "ctx1")
+    orchestrator.dispatch("research explode", "ctx1")
+    orchestrator.dispatch("no agent for this", "ctx2")
+    orchestrator.dispatch("viral spread", "ctx2")
+
+    text = orchestrator.metrics_text()
+    assert _metric(text, "sovereign_subtasks_dispatched_total", agent="research") == 2
+    assert _metric(text, "sovereign_subtasks_dispatched_total", agent="unrouted") == 1
+    assert _metric(text, "sovereign_subtasks_dispatched_total", agent="viral") == 1
+    assert _metric(text, "sovereign_subtask_failures_total", kind="call_failed") == 1
+    assert _metric(text, "sovereign_subtask_failures_total", kind="unknown_subtask") == 1
+    assert _metric(text, "sovereign_python_call_duration_seconds_count", call="_ResearchAgent.execute") >= 2
+    assert _metric(text, "sovereign_active_contexts") == 2
+    virality = orchestrator.get_context("ctx2").viral_metrics.virality_score
+    assert _metric(text, "sovereign_virality_score", context_id="ctx2", tenant="default") == pytest.approx(virality)
+    assert _metric(text, "sovereign_replans_triggered_total") == 0
+
+
+def test_metrics_http_endpoint():
+    """serve_metrics exposes the registry at /metrics when built with metrics_http"""
+    if not hasattr(sovereign_cli.CognitiveOrchestrator, "serve_metrics"):
+        pytest.skip("built without the metrics_http feature")
+    orchestrator = sovereign_cli.CognitiveOrchestrator()
+    orchestrator.dispatch("no agent for this", "ctx1")
+    addr = orchestrator.serve_metrics("127.0.0.1:0")
+    with urllib.request.urlopen(f"http://{addr}/metrics", timeout=5) as response:
+        body = response.read().decode()
+    assert _metric(body, "sovereign_subtasks_dispatched_total", agent="unrouted") == 1
+
+
+def test_metrics_http_idle_client_does_not_block_scrapes():
+    """A connection that never sends its request leaves /metrics answering others"""
+    import socket
+
+    if not hasattr(sovereign_cli.CognitiveOrchestrator, "serve_metrics"):
+        pytest.skip("built without the metrics_http feature")
+    orchestrator = sovereign_cli.CognitiveOrchestrator()
+    host, port = orchestrator.serve_metrics("127.0.0.1:0").rsplit(":", 1)
+    with socket.create_connection((host, int(port)), timeout=5):
+        with urllib.request.urlopen(f"http://{host}:{port}/metrics", timeout=2) as response:
+            assert response.status == 200
+
+
+class _Planner:
+    """Stub PlannerAgent returning a fixed decomposition"""
+
+    steps = []
+
+    def decompose(self, command):
+        return _Planner.steps
+
+
+class _BarrierAgent:
+    """Handles `step ...`; subtasks marked `together` only finish if they overlap"""
+
+    def __init__(self):
+        import threading
+
+        self.barrier = threading.Barrier(2, timeout=2)
+        self.calls = []
+
+    def can_handle(self, sub_task):
+        return sub_task.startswith("step")
+
+    def execute(self, sub_task, context):
+        if "together" in sub_task:
+            self.barrier.wait()
+        self.calls.append(sub_task)
+        return {"output": sub_task, "status": True}
+
+
+def test_dag_plan_runs_independent_nodes_in_parallel():
+    """Nodes whose dependencies are met run together; dependents wait for them"""
+    _Planner.steps = [
+        {"task": "step together a"},
+        {"task": "step together b"},
+        {"task": "step joined", "deps": [0, 1]},
+    ]
+    _install_agent_module("python.agents.planner_agent", PlannerAgent=_Planner)
+    try:
+        agent = _BarrierAgent()
+        orchestrator = sovereign_cli.CognitiveOrchestrator()
+        orchestrator.register_python_agent("steps", agent)
+
+        assert orchestrator.proactive_plan("plan it", "ctx1", graph=True) == [
+            {"id": 0, "task": "step together a", "deps": []},
+            {"id": 1, "task": "step together b", "deps": []},
+            {"id": 2, "task": "step joined", "deps": [0, 1]},
+        ]
+        outputs = json.loads(orchestrator.process("plan it", "ctx1"))
+        assert outputs == ["step together a", "step together b", "step joined"]
+        assert agent.calls[-1] == "step joined"
+    finally:
+        sys.modules.pop("python.agents.planner_agent", None)
+
+
+def test_plan_cycle_rejected_before_dispatch():
+    """A cyclic planner result is reported without dispatching anything"""
+    _Planner.steps = [{"task": "step x", "deps": [1]}, {"task": "step y", "deps": [0]}]
+    _install_agent_module("python.agents.planner_agent", PlannerAgent=_Planner)
+    try:
+        agent = _BarrierAgent()
+        orchestrator = sovereign_cli.CognitiveOrchestrator()
+        orchestrator.register_python_agent("steps", agent)
+
+        with pytest.raises(RuntimeError, match="cycle through: step x, step y"):
+            orchestrator.proactive_plan("plan it", "ctx1")
+        outputs = json.loads(orchestrator.process("plan it", "ctx1"))
+        assert outputs == ["Plan Error: plan has a dependency cycle through: step x, step y"]
+        assert agent.calls == []
+    finally:
+        sys.modules.pop("python.agents.planner_agent", None)
+
+
+def test_list_plans_stay_linear():
+    """Template and list-of-strings plans become linear dependency chains"""
+    _Planner.steps = ["step one", "step two"]
+    _install_agent_module("python.agents.planner_agent", PlannerAgent=_Planner)
+    try:
+        orchestrator = sovereign_cli.CognitiveOrchestrator()
+        assert orchestrator.proactive_plan("plan it", "ctx1", graph=True) == [
+            {"id": 0, "task": "step one", "deps": []},
+            {"id": 1, "task": "step two", "deps": [0]},
+        ]
+        plan = orchestrator.proactive_plan("go viral", "ctx1", graph=True)
+        assert [node["deps"] for node in plan] == [[], [0], [1], [2], [3]]
+    finally:
+        sys.modules.pop("python.agents.planner_agent", None)
+
+
+def test_history_records_and_bounds_executions():
+    """Dispatched subtasks are recorded per context, keeping only the newest records"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator(history_limit=3)
+    orchestrator.register_python_agent("research", _ResearchAgent())
+    orchestrator.register_plan_template("prefix", "study", ["research one", "research two"])
+
+    orchestrator.process("study it", "ctx1")
+    history = orchestrator.get_history("ctx1")
+    assert [(r.command, r.subtask) for r in history] == [("study it", "research one"), ("study it", "research two")]
+    assert history[0].result.output == "found 3 papers for ctx1"
+    assert history[0].duration >= 0
+    assert history[0].timestamp <= history[1].timestamp
+
+    orchestrator.dispatch("research explode", "ctx1")
+    orchestrator.dispatch("research three", "ctx1")
+    history = orchestrator.get_history("ctx1")
+    assert [r.subtask for r in history] == ["research two", "research explode", "research three"]
+    assert [r.subtask for r in orchestrator.get_history("ctx1", limit=1)] == ["research three"]
+    assert orchestrator.get_history("ctx2") == []
+
+
+def test_export_history_jsonl_and_csv():
+    """export_history writes JSON lines and CSV"""
+    import csv
+    import io
+
+    orchestrator = sovereign_cli.CognitiveOrchestrator()
+    orchestrator.register_python_agent("research", _ResearchAgent())
+    orchestrator.dispatch("research topic, quickly", "ctx1")
+    orchestrator.dispatch("research explode", "ctx1")
+
+    lines = [json.loads(line) for line in orchestrator.export_history("ctx1", "jsonl").splitlines()]
+    assert [line["subtask"] for line in lines] == ["research topic, quickly", "research explode"]
+    assert lines[1]["result"]["error"]["kind"] == "call_failed"
+    assert isinstance(lines[0]["duration"], float)
+
+    rows = list(csv.DictReader(io.StringIO(orchestrator.export_history("ctx1", "csv"))))
+    assert rows[0]["subtask"] == "research topic, quickly"
+    assert rows[0]["status"] == "true"
+    assert rows[1]["error_kind"] == "call_failed"
+
+    with pytest.raises(ValueError, match="Unknown history format"):
+        orchestrator.export_history("ctx1", "xml")
+
+
+_GOLDEN_CONTEXT = pathlib.Path(__file__).parent / "fixtures" / "deterministic_context.json"
+
+
+def _deterministic_snapshot(path):
+    from datetime import datetime, timezone
+
+    orchestrator = sovereign_cli.CognitiveOrchestrator(
+        prefer_native=True, seed=7, fixed_time=datetime(2025, 1, 1, tzinfo=timezone.utc)
+    )
+    orchestrator.process("go viral", "ctx1")
+    orchestrator.save_contexts(str(path))
+    return path.read_bytes()
+
+
+def test_deterministic_run_matches_golden_context(tmp_path):
+    """A fixed clock and seed serialize byte-for-byte the same context every run;
+    set SOVEREIGN_UPDATE_GOLDEN=1 to rewrite the fixture after an intended change"""
+    _install_agent_module("python.agents.llm_agent", LLMAgent=_EchoLLM)
+    try:
+        first = _deterministic_snapshot(tmp_path / "first.json")
+        second = _deterministic_snapshot(tmp_path / "second.json")
+    finally:
+        sys.modules.pop("python.agents.llm_agent", None)
+    assert first == second
+    if os.environ.get("SOVEREIGN_UPDATE_GOLDEN"):
+        _GOLDEN_CONTEXT.write_bytes(first)
+    assert first == _GOLDEN_CONTEXT.read_bytes()
+
+
+def test_from_config_applies_defaults_and_modules():
+    """from_config seeds new contexts and routes LLM calls to the configured module"""
+    _install_agent_module("custom_llm", LLMAgent=_EchoLLM)
+    try:
+        orchestrator = sovereign_cli.CognitiveOrchestrator.from_config(
+            {"default": {"engagement_nodes": 64}, "python_modules": {"llm": "custom_llm"}}
+        )
+        assert orchestrator.dispatch("query llm hi", "ctx1").output == "post: hi"
+        assert orchestrator.get_context("ctx1").viral_metrics.engagement_nodes == 64
+    finally:
+        sys.modules.pop("custom_llm", None)
+
+
+def test_invalid_config_names_the_setting():
+    """Bad values and unknown keys raise ValueError with the offending key"""
+    with pytest.raises(ValueError, match="retry.base_delay_secs"):
+        sovereign_cli.CognitiveOrchestrator.from_config({"retry": {"max_attempts": 3, "base_delay_secs": 0}})
+    with pytest.raises(ValueError, match="default.engagement_nodes"):
+        sovereign_cli.CognitiveOrchestrator.from_config({"default": {"engagement_nodes": 0}})
+    with pytest.raises(ValueError, match="subtask_timeout_secs"):
+        sovereign_cli.CognitiveOrchestrator(subtask_timeout=0)
+    with pytest.raises(ValueError, match="retries"):
+        sovereign_cli.CognitiveOrchestrator.from_config({"retries": 2})
+
+
+_CONFIG_FILES = {
+    "toml": ("max_replans = 2\n", "[default]\nengagement_nodes = {}\n"),
+    "yaml": ("max_replans: 2\n", "default:\n  engagement_nodes: {}\n"),
+}
+
+
+@pytest.mark.parametrize(
+    "fmt, file_nodes, env_nodes, expected",
+    [
+        ("toml", None, None, 32),
+        ("toml", 48, None, 48),
+        ("toml", None, "96", 96),
+        ("toml", 48, "96", 96),
+        ("yaml", None, None, 32),
+        ("yaml", 48, None, 48),
+        ("yaml", None, "96", 96),
+        ("yaml", 48, "96", 96),
+    ],
+)
+def test_config_file_and_env_precedence(tmp_path, monkeypatch, fmt, file_nodes, env_nodes, expected):
+    """ACE_* environment variables override file values, which override defaults"""
+    path = tmp_path / f"ace.{fmt}"
+    header, nodes = _CONFIG_FILES[fmt]
+    path.write_text(header + (nodes.format(file_nodes) if file_nodes else ""))
+    monkeypatch.delenv("ACE_DEFAULT_ENGAGEMENT_NODES", raising=False)
+    if env_nodes:
+        monkeypatch.setenv("ACE_DEFAULT_ENGAGEMENT_NODES", env_nodes)
+
+    orchestrator = sovereign_cli.CognitiveOrchestrator.from_config_file(str(path))
+    orchestrator.proactive_plan("go viral", "ctx1")
+    assert orchestrator.get_context("ctx1").viral_metrics.engagement_nodes == expected
+
+
+def test_config_rejects_unknown_keys(tmp_path, monkeypatch):
+    """Unknown file keys and ACE_* variables are reported by name"""
+    path = tmp_path / "ace.toml"
+    path.write_text("[retry]\nmax_atempts = 3\n")
+    with pytest.raises(ValueError, match="max_atempts"):
+        sovereign_cli.CognitiveOrchestrator.from_config_file(str(path), env=False)
+
+    monkeypatch.setenv("ACE_SUBTASK_TIMEOUT", "5")
+    with pytest.raises(ValueError, match="ACE_SUBTASK_TIMEOUT"):
+        sovereign_cli.CognitiveOrchestrator.from_env()
+    monkeypatch.delenv("ACE_SUBTASK_TIMEOUT")
+
+    monkeypatch.setenv("ACE_SUBTASK_TIMEOUT_SECS", "soon")
+    with pytest.raises(ValueError, match="ACE_SUBTASK_TIMEOUT_SECS"):
+        sovereign_cli.CognitiveOrchestrator.from_env()
+
+
+def test_set_agent_module_and_validate_agents():
+    """Agents import from the configured module and class; validate_agents reports each one"""
+    _install_agent_module("custom_agents", Writer=_EchoLLM)
+    try:
+        orchestrator = sovereign_cli.CognitiveOrchestrator()
+        orchestrator.set_agent_module("llm", "custom_agents:Writer")
+        orchestrator.set_agent_module("planner", "custom_agents", class_name="Planner")
+        assert orchestrator.agent_modules["llm"] == "custom_agents:Writer"
+        assert orchestrator.dispatch("query llm hi", "ctx1").output == "post: hi"
+
+        report = {row.agent: row for row in orchestrator.validate_agents()}
+        assert set(report) == {"planner", "llm", "viral", "debug", "memory"}
+        assert report["llm"].available and report["llm"].error is None
+        assert not report["planner"].available
+        assert "Planner" in report["planner"].error
+        assert not report["debug"].available
+        assert report["debug"].module == "python.agents.debug_agent"
+
+        with pytest.raises(ValueError, match="Unknown agent"):
+            orchestrator.set_agent_module("oracle", "custom_agents")
+    finally:
+        sys.modules.pop("custom_agents", None)
+
+
+def test_agent_instances_cached_across_threads():
+    """Concurrent dispatches share one agent instance until reload_agents"""
+    import threading
+    import time
+
+    created = []
+
+    class LLMAgent:
+        def __init__(self):
+            time.sleep(0.05)  # releases the GIL while other workers ask for the agent
+            created.append(threading.get_ident())
+
+        def generate(self, prompt):
+            return prompt
+
+    _install_agent_module("python.agents.llm_agent", LLMAgent=LLMAgent)
+    try:
+        orchestrator = sovereign_cli.CognitiveOrchestrator()
+        steps = [f"query llm {i}" for i in range(32)]
+        orchestrator.register_plan_template("prefix", "fanout", steps)
+        for _ in range(3):
+            results = orchestrator.process_parallel("fanout", "ctx1", 8)
+            assert [r.output for r in results] == [str(i) for i in range(32)]
+        assert len(created) == 1
+
+        orchestrator.reload_agents()
+        orchestrator.process_parallel("fanout", "ctx1", 8)
+        assert len(created) == 2
+
+        orchestrator.set_agent_module("llm", "python.agents.llm_agent")
+        orchestrator.dispatch("query llm again", "ctx1")
+        assert len(created) == 3
+    finally:
+        sys.modules.pop("python.agents.llm_agent", None)
+
+
+def test_concurrent_dispatch_threads_share_one_agent_instance():
+    """dispatch from several Python threads at once builds the cached agent once"""
+    import threading
+    import time
+
+    created = []
+
+    class LLMAgent:
+        def __init__(self):
+            time.sleep(0.05)  # releases the GIL while the other threads ask for the agent
+            created.append(threading.get_ident())
+
+        def generate(self, prompt):
+            return prompt.upper()
+
+    _install_agent_module("python.agents.llm_agent", LLMAgent=LLMAgent)
+    try:
+        orchestrator = sovereign_cli.CognitiveOrchestrator()
+        threads = 8
+        start = threading.Barrier(threads)
+        outputs = [None] * threads
+
+        def dispatch(idx):
+            start.wait()
+            for call in range(4):
+                result = orchestrator.dispatch(f"query llm t{idx} c{call}", f"ctx{idx}")
+                outputs[idx] = (outputs[idx] or []) + [result.output]
+
+        workers = [threading.Thread(target=dispatch, args=(idx,)) for idx in range(threads)]
+        for worker in workers:
+            worker.start()
+        for worker in workers:
+            worker.join()
+        assert len(created) == 1
+        assert outputs == [[f"T{idx} C{call}" for call in range(4)] for idx in range(threads)]
+    finally:
+        sys.modules.pop("python.agents.llm_agent", None)
+
+
+def test_auto_snapshots_diff_each_run():
+    """auto_snapshots records the context before and after each run, and diffs report the changes"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator(prefer_native=True, auto_snapshots=True, learning=False)
+    orchestrator.process("inject hook 0.5", "ctx1")
+    before, after = orchestrator.get_snapshots("ctx1")
+    assert before.snapshot_id < after.snapshot_id
+    assert after.context.viral_metrics.hook_rate == 0.5
+
+    diff = before.diff(after)
+    assert diff["context_id"] == "ctx1"
+    assert diff["metrics"]["hook_rate"] == pytest.approx(0.45)
+    assert diff["metrics"]["engagement_nodes"] == 0
+    assert diff["memory_added"] == [] and diff["goals_added"] == []
+    assert sovereign_cli.CognitiveOrchestrator().get_snapshots("ctx1") == []
+
+
+def test_goals_add_complete_and_list():
+    """Goals are added, completed and listed per context; unknown goals raise"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator()
+    goal = orchestrator.add_goal("ctx1", "reach", "reach 10k views", priority="high")
+    assert (goal.id, goal.priority, goal.status) == ("reach", "high", "active")
+    orchestrator.add_goal("ctx1", "tone", "keep it upbeat")
+    assert not orchestrator.goals_complete("ctx1")
+
+    assert orchestrator.complete_goal("ctx1", "reach").status == "completed"
+    orchestrator.complete_goal("ctx1", "tone")
+    assert orchestrator.goals_complete("ctx1")
+    assert [g.status for g in orchestrator.list_goals("ctx1")] == ["completed", "completed"]
+    assert orchestrator.get_context("ctx1").active_goals == orchestrator.list_goals("ctx1")
+
+    with pytest.raises(RuntimeError, match="unknown goal"):
+        orchestrator.complete_goal("ctx1", "missing")
+    with pytest.raises(ValueError):
+        orchestrator.add_goal("ctx1", "x", "y", priority="urgent")
+
+
+def test_goals_past_their_deadline_expire():
+    """sweep_goals expires active goals whose deadline has passed"""
+    from datetime import datetime, timedelta, timezone
+
+    fixed = datetime(2025, 1, 1, tzinfo=timezone.utc)
+    orchestrator = sovereign_cli.CognitiveOrchestrator(prefer_native=True, fixed_time=fixed)
+    goal = orchestrator.add_goal("ctx1", "launch", "launch by new year", deadline=fixed - timedelta(hours=1))
+    assert goal.deadline == fixed - timedelta(hours=1)
+    orchestrator.add_goal("ctx1", "reach", "reach 10k views", deadline=fixed + timedelta(days=7))
+
+    assert [g.id for g in orchestrator.sweep_goals("ctx1")] == ["launch"]
+    assert [g.status for g in orchestrator.list_goals("ctx1")] == ["expired", "active"]
+    assert orchestrator.sweep_goals("ctx1") == []
+
+
+def test_remember_and_recall_text():
+    """remember embeds text into memory_vectors; recall with a string returns texts"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator()
+    assert orchestrator.remember("ctx1", "viral launch teaser") == 0
+    orchestrator.remember("ctx1", "quarterly budget review")
+    context = orchestrator.get_context("ctx1")
+    assert context.memory_texts == ["viral launch teaser", "quarterly budget review"]
+    assert len(context.memory_vectors[0]) == 384
+
+    hits = orchestrator.recall("ctx1", "launch teaser", 1)
+    assert hits[0][0] == "viral launch teaser"
+    assert 0 < hits[0][1] <= 1
+
+
+def test_process_batch_runs_concurrently_and_writes_jsonl(tmp_path):
+    """process_batch overlaps sleeping agents, keeps request order and appends JSONL lines"""
+    import time
+
+    class LLMAgent:
+        def generate(self, prompt):
+            time.sleep(0.2)
+            return prompt.upper()
+
+    _install_agent_module("python.agents.llm_agent", LLMAgent=LLMAgent)
+    try:
+        orchestrator = sovereign_cli.CognitiveOrchestrator()
+        orchestrator.register_plan_template("prefix", "ask", ["query llm x"])
+        orchestrator.register_plan_template("prefix", "broken", ["bogus subtask"])
+        requests = [{"command": "ask away", "context_id": f"ctx{i}"} for i in range(4)]
+        requests.append({"command": "broken run", "context_id": "ctx4"})
+        path = tmp_path / "batch.jsonl"
+
+        started = time.monotonic()
+        outcomes = orchestrator.process_batch(requests, concurrency=5, jsonl_path=str(path))
+        assert time.monotonic() - started < 0.6
+        assert [o["context_id"] for o in outcomes] == ["ctx0", "ctx1", "ctx2", "ctx3", "ctx4"]
+        assert json.loads(outcomes[0]["output"]) == ["X"]
+        assert outcomes[0]["errors"] == [] and outcomes[0]["duration"] >= 0.2
+        assert outcomes[4]["errors"][0]["kind"] == "unknown_subtask"
+
+        lines = [json.loads(line) for line in path.read_text().splitlines()]
+        assert sorted(line["index"] for line in lines) == [0, 1, 2, 3, 4]
+        with pytest.raises(ValueError):
+            orchestrator.process_batch([{"command": "ask"}])
+    finally:
+        sys.modules.pop("python.agents.llm_agent", None)
+
+
+def test_llm_budget_uses_reported_tokens():
+    """Tokens an LLMAgent reports count against the budget; exhausted budgets refuse LLM subtasks"""
+    class LLMAgent:
+        def generate(self, prompt):
+            return {"output": prompt.upper(), "metadata": {"tokens": 50, "cost_usd": 0.01}}
+
+    _install_agent_module("python.agents.llm_agent", LLMAgent=LLMAgent)
+    try:
+        orchestrator = sovereign_cli.CognitiveOrchestrator()
+        orchestrator.set_budget("ctx1", max_tokens=80)
+        assert orchestrator.dispatch("query llm a", "ctx1").output == "A"
+        assert orchestrator.dispatch("query llm b", "ctx1").status
+        refused = orchestrator.dispatch("query llm c", "ctx1")
+        assert refused.error["kind"] == "budget_exceeded"
+
+        budget = orchestrator.get_budget("ctx1")
+        assert budget["used"] == {"llm_calls": 2, "tokens": 100, "cost_usd": pytest.approx(0.02)}
+        assert budget["remaining"]["max_tokens"] == 0
+        orchestrator.clear_budget("ctx1")
+        assert orchestrator.get_budget("ctx1") is None
+
+        orchestrator.register_plan_template("prefix", "ask", ["query llm d"])
+        outputs = json.loads(orchestrator.process("ask", "ctx2", budget={"max_llm_calls": 0}))
+        assert outputs[0].startswith("Budget Exceeded")
+        with pytest.raises(ValueError):
+            orchestrator.process("ask", "ctx2", budget={"max_calls": 1})
+    finally:
+        sys.modules.pop("python.agents.llm_agent", None)
+
+
+def test_rate_limit_spaces_parallel_llm_calls():
+    """set_rate_limit throttles parallel LLM dispatches to the configured rate"""
+    import time
+
+    class LLMAgent:
+        def generate(self, prompt):
+            return prompt
+
+    _install_agent_module("python.agents.llm_agent", LLMAgent=LLMAgent)
+    try:
+        orchestrator = sovereign_cli.CognitiveOrchestrator()
+        orchestrator.set_rate_limit("llm", 10.0)
+        orchestrator.register_plan_template("prefix", "fanout", ["query llm a", "query llm b", "query llm c"])
+        started = time.monotonic()
+        results = orchestrator.process_parallel("fanout", "ctx1", 3)
+        assert [r.output for r in results] == ["a", "b", "c"]
+        assert time.monotonic() - started >= 0.19
+
+        with pytest.raises(ValueError):
+            orchestrator.set_rate_limit("llm", 0.0)
+        with pytest.raises(ValueError):
+            orchestrator.set_rate_limit("oracle", 1.0)
+        orchestrator.set_rate_limit("llm", None)
+    finally:
+        sys.modules.pop("python.agents.llm_agent", None)
+
+
+def test_result_cache_skips_repeated_llm_calls():
+    """set_result_cache answers a repeated LLM subtask without calling the agent"""
+    prompts = []
+
+    class LLMAgent:
+        def generate(self, prompt):
+            prompts.append(prompt)
+            return prompt.upper()
+
+    _install_agent_module("python.agents.llm_agent", LLMAgent=LLMAgent)
+    try:
+        orchestrator = sovereign_cli.CognitiveOrchestrator()
+        assert orchestrator.cache_stats() is None
+        orchestrator.set_result_cache(16, ttl=60.0)
+        first = orchestrator.dispatch("query llm hello", "ctx1")
+        again = orchestrator.dispatch("query llm  Hello ", "ctx2")
+        assert prompts == ["hello"]
+        assert again.output == first.output == "HELLO"
+        assert again.metadata["cache"] == "hit"
+        assert "cache" not in first.metadata
+        assert orchestrator.cache_stats() == {"hits": 1, "misses": 1, "evictions": 0, "entries": 1}
+
+        orchestrator.clear_result_cache()
+        assert orchestrator.cache_stats()["entries"] == 0
+        with pytest.raises(ValueError):
+            orchestrator.set_result_cache(16, exclude=["oracle"])
+    finally:
+        sys.modules.pop("python.agents.llm_agent", None)
+
+
+def test_metrics_history_and_trend():
+    """viral subtasks sample the context's metrics for metrics_history and metrics_trend"""
+    from datetime import datetime, timezone
+
+    fixed = datetime(2025, 1, 1, tzinfo=timezone.utc)
+    orchestrator = sovereign_cli.CognitiveOrchestrator(prefer_native=True, metrics_history_limit=2, fixed_time=fixed)
+    for _ in range(3):
+        orchestrator.dispatch("measure spread", "ctx1")
+    history = orchestrator.metrics_history("ctx1")
+    assert len(history) == 2
+    assert history[-1]["virality_score"] == orchestrator.get_context("ctx1").viral_metrics.virality_score
+    assert {sample["at"] for sample in history} == {fixed.isoformat().replace("+00:00", "Z")}
+    assert orchestrator.metrics_history("ctx1", since=datetime(2999, 1, 1, tzinfo=timezone.utc)) == []
+
+    # Every sample was taken at the same instant, so there is no slope yet.
+    assert orchestrator.metrics_trend("ctx1") is None
+    with pytest.raises(RuntimeError, match="unknown context"):
+        orchestrator.metrics_trend("missing")
+
+
+def test_auto_tune_hook_rate_is_deterministic():
+    """auto_tune_hook_rate raises the hook rate until the native simulation reaches the target"""
+    def tuned():
+        orchestrator = sovereign_cli.CognitiveOrchestrator(seed=11)
+        orchestrator.dispatch("inject hook 0.05", "ctx1")
+        return orchestrator, orchestrator.auto_tune_hook_rate("ctx1", target_virality=0.9, max_iters=12)
+
+    orchestrator, report = tuned()
+    assert report == tuned()[1]
+    assert report["reached"] is True
+    assert report["trials"][0]["hook_rate"] == pytest.approx(0.05)
+    assert all(0.0 <= trial["hook_rate"] <= 1.0 for trial in report["trials"])
+    assert orchestrator.get_context("ctx1").viral_metrics.hook_rate == report["hook_rate"]
+    assert len(orchestrator.metrics_history("ctx1")) == len(report["trials"])
+    orchestrator.set_auto_tune(0.8, max_iters=3)
+    orchestrator.set_auto_tune(None)
+
+
+def test_viral_topologies():
+    """simulate_viral and engagement_graph build the requested topology, recorded in native results"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator(prefer_native=True)
+    ba = {"kind": "barabasi_albert", "attachments": 2}
+    graph = orchestrator.engagement_graph(50, seed=3, topology=ba)
+    assert graph["topology"] == ba
+    assert len(graph["edges"]) == 3 + 2 * 47
+    assert graph == orchestrator.engagement_graph(50, seed=3, topology=ba)
+    assert orchestrator.engagement_graph(10)["topology"] == {"kind": "erdos_renyi", "mean_degree": 4}
+
+    small_world = {"kind": "small_world", "mean_degree": 4, "rewire": 0.0}
+    report = orchestrator.simulate_viral(40, 1.0, 100, 1, topology=small_world)
+    assert report.virality_score == 1.0
+    # A ring reaches two more nodes per round in each direction.
+    assert report.reached_per_round[:3] == [1, 5, 9]
+    with pytest.raises(ValueError):
+        orchestrator.simulate_viral(40, 0.5, topology={"kind": "small_world", "mean_degree": 4, "rewire": 2.0})
+    with pytest.raises(ValueError):
+        orchestrator.engagement_graph(10, topology={"kind": "lattice"})
+
+    result = orchestrator.dispatch("measure spread", "ctx1")
+    assert result.metadata["topology"] == {"kind": "erdos_renyi", "mean_degree": 4}
+
+    configured = sovereign_cli.CognitiveOrchestrator.from_config({"prefer_native": True, "viral": {"topology": ba}})
+    assert configured.dispatch("measure spread", "ctx1").metadata["topology"] == ba
+    with pytest.raises(ValueError, match="viral.topology.attachments"):
+        sovereign_cli.CognitiveOrchestrator.from_config({"viral": {"topology": {"kind": "barabasi_albert", "attachments": 0}}})
+
+
+def test_estimate_fidelity_falls_with_noise_and_rounds():
+    """Noiseless amplification keeps full fidelity; noise costs more each round"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator(prefer_native=True)
+    assert orchestrator.estimate_fidelity(8, depolarizing=0.0, dephasing=0.0) == pytest.approx(1.0, abs=1e-12)
+    short = orchestrator.estimate_fidelity(2, depolarizing=0.01, dephasing=0.01)
+    long = orchestrator.estimate_fidelity(16, depolarizing=0.01, dephasing=0.01)
+    assert 0.0 < long < short < 1.0
+    with pytest.raises(ValueError):
+        orchestrator.estimate_fidelity(2, depolarizing=-0.1)
+
+    orchestrator.dispatch("viral sim", "ctx1")
+    assert orchestrator.get_context("ctx1").viral_metrics.quantum_fidelity == orchestrator.estimate_fidelity(8)
+
+
+def test_export_import_context_round_trips():
+    """An exported context imports elsewhere with its history; collisions need overwrite"""
+    source = sovereign_cli.CognitiveOrchestrator(prefer_native=True, auto_snapshots=True)
+    source.process("viral sim", "ctx1")
+    document = json.loads(source.export_context("ctx1"))
+    assert document["schema_version"] == 1
+    assert document["context_id"] == "ctx1"
+    assert document["history"] and document["snapshots"] and document["metrics_history"]
+
+    target = sovereign_cli.CognitiveOrchestrator()
+    assert target.import_context(json.dumps(document)) == "ctx1"
+    assert target.get_context("ctx1").viral_metrics == source.get_context("ctx1").viral_metrics
+    assert len(target.get_history("ctx1")) == len(source.get_history("ctx1"))
+    assert json.loads(target.export_context("ctx1"))["metrics_history"] == document["metrics_history"]
+    with pytest.raises(ValueError, match="already exists"):
+        target.import_context(json.dumps(document))
+    assert target.import_context(json.dumps(document), overwrite=True) == "ctx1"
+    with pytest.raises(ValueError, match="schema_version"):
+        target.import_context(json.dumps({"context_id": "ctx2"}))
+    with pytest.raises(RuntimeError, match="ghost"):
+        target.export_context("ghost")
+
+
+def test_tenants_sharing_a_context_id_stay_isolated():
+    """Each tenant sees only its own contexts, history and budget"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator(prefer_native=True)
+    orchestrator.process("viral sim", "ctx1")
+    orchestrator.process_for("acme", "viral sim", "ctx1")
+    assert orchestrator.context_ids() == ["ctx1"]
+    assert orchestrator.context_ids("acme") == ["ctx1"]
+    assert orchestrator.get_context_for("acme", "ctx1").tenant == "acme"
+    assert orchestrator.get_context("ctx1").tenant == "default"
+    assert len(orchestrator.get_history_for("acme", "ctx1")) == len(orchestrator.get_history("ctx1"))
+
+    orchestrator.set_tenant_budget("acme", max_llm_calls=5)
+    assert orchestrator.get_tenant_budget("acme")["remaining"]["max_llm_calls"] == 5
+    orchestrator.clear_tenant_budget("acme")
+    assert orchestrator.get_tenant_budget("acme") is None
+
+    target = sovereign_cli.CognitiveOrchestrator()
+    assert target.import_context_for("globex", orchestrator.export_context_for("acme", "ctx1")) == "ctx1"
+    assert target.context_ids("globex") == ["ctx1"] and target.context_ids() == []
+    with pytest.raises(RuntimeError, match="invalid tenant"):
+        orchestrator.process_for("", "viral sim", "ctx1")
+
+
+def test_shutdown_rejects_new_runs_and_persists_contexts(tmp_path):
+    """After shutdown, process raises and every context has been flushed"""
+    path = tmp_path / "contexts.json"
+    orchestrator = sovereign_cli.CognitiveOrchestrator(prefer_native=True, eviction_path=str(path))
+    orchestrator.process("viral sim", "ctx1")
+    report = orchestrator.shutdown(grace=1.0)
+    assert report["interrupted"] == [] and report["snapshots"] == 1
+    assert report["persisted"] == str(path)
+    assert "ctx1" in json.loads(path.read_text())["contexts"]
+    with pytest.raises(RuntimeError, match="shutting down"):
+        orchestrator.process("viral sim", "ctx2")
+
+
+def test_idempotency_key_replays_the_completed_run(tmp_path):
+    """A retry under the same key returns the stored output without running again"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator(prefer_native=True)
+    first = orchestrator.process("viral sim", "ctx1", idempotency_key="req-1")
+    history = len(orchestrator.get_history("ctx1"))
+    assert orchestrator.process("viral sim", "ctx1", idempotency_key="req-1", wait=False) == first
+    assert len(orchestrator.get_history("ctx1")) == history
+    with pytest.raises(RuntimeError, match="req-1"):
+        orchestrator.process("viral sim", "ctx2", idempotency_key="req-1")
+
+    path = tmp_path / "contexts.json"
+    orchestrator.save_contexts(str(path))
+    restored = sovereign_cli.CognitiveOrchestrator(prefer_native=True)
+    restored.load_contexts(str(path))
+    assert restored.process("viral sim", "ctx1", idempotency_key="req-1") == first
+    assert restored.get_history("ctx1") == []
+
+
+def test_process_report_keeps_results_and_timing():
+    """process_report returns the plan, full results and timing that process drops"""
+    from datetime import datetime, timezone
+
+    fixed = datetime(2025, 1, 1, tzinfo=timezone.utc)
+    reproducible = lambda **kw: sovereign_cli.CognitiveOrchestrator(prefer_native=True, seed=7, fixed_time=fixed, **kw)
+    report = reproducible().process_report("viral sim", "ctx1")
+    assert isinstance(report, sovereign_cli.ProcessReport)
+    assert report.context_id == "ctx1" and report.command == "viral sim"
+    assert report.plan and len(report.results) >= len(report.plan)
+    assert report.started_at == report.finished_at == fixed
+    assert json.loads(reproducible().process("viral sim", "ctx1")) == report.outputs()
+    assert json.loads(report.to_json())["success"] == report.success
+
+    document = json.loads(reproducible(legacy_output=False).process("viral sim", "ctx1"))
+    assert document["plan"] == report.plan
+    assert [result["output"] for result in document["results"]] == report.outputs()
+
+
+def test_circuit_breaker_stops_calling_a_failing_agent():
+    """After the failure threshold, LLM dispatches fail with circuit_open without calling Python"""
+
+    class LLMAgent:
+        calls = 0
+
+        def generate(self, prompt):
+            LLMAgent.calls += 1
+            raise ConnectionError("backend down")
+
+    _install_agent_module("python.agents.llm_agent", LLMAgent=LLMAgent)
+    try:
+        orchestrator = sovereign_cli.CognitiveOrchestrator()
+        orchestrator.set_circuit_breaker("llm", 2, cooldown=60.0)
+        for subtask in ["query llm a", "query llm b"]:
+            assert orchestrator.dispatch(subtask, "ctx1").error["kind"] == "call_failed"
+        refused = orchestrator.dispatch("query llm c", "ctx1")
+        assert refused.error["kind"] == "circuit_open" and LLMAgent.calls == 2
+
+        health = orchestrator.agent_health()[0]
+        assert health["agent"] == "llm" and health["state"] == "open"
+        assert health["consecutive_failures"] == 2 and "backend down" in health["last_error"]
+        orchestrator.set_circuit_breaker("llm", None)
+        assert orchestrator.agent_health()[0]["state"] == "closed"
+        with pytest.raises(ValueError):
+            orchestrator.set_circuit_breaker("oracle", 1)
+    finally:
+        sys.modules.pop("python.agents.llm_agent", None)
+
+
+def test_successful_runs_are_learned_and_reused():
+    """A repeated command reuses the plan its earlier success learned instead of asking the planner"""
+
+    class Planner:
+        prompts = []
+
+        def decompose(self, command):
+            Planner.prompts.append(command)
+            return ["step teaser", "step launch"]
+
+    class Steps:
+        def can_handle(self, sub_task):
+            return sub_task.startswith("step")
+
+        def execute(self, sub_task, context):
+            return {"output": sub_task, "status": True}
+
+    _install_agent_module("python.agents.planner_agent", PlannerAgent=Planner)
+    try:
+        for learning, prompts in [(True, 1), (False, 2)]:
+            Planner.prompts = []
+            orchestrator = sovereign_cli.CognitiveOrchestrator(learning=learning)
+            orchestrator.register_python_agent("steps", Steps())
+            first = orchestrator.process("launch the campaign", "ctx1")
+            assert orchestrator.process("launch the campaign", "ctx1") == first
+            assert len(Planner.prompts) == prompts
+            assert len(orchestrator.get_context("ctx1").memory_texts) == (1 if learning else 0)
+    finally:
+        sys.modules.pop("python.agents.planner_agent", None)
+
+
+def test_cancel_from_another_thread_stops_the_run():
+    """cancel from another thread stops a running process_report after its current subtask"""
+    import threading
+    import time
+
+    _Planner.steps = ["step one", "step two", "step three"]
+
+    class Slow:
+        started = threading.Event()
+
+        def can_handle(self, sub_task):
+            return sub_task.startswith("step")
+
+        def execute(self, sub_task, context):
+            Slow.started.set()
+            time.sleep(0.2)
+            return {"output": sub_task, "status": True}
+
+    _install_agent_module("python.agents.planner_agent", PlannerAgent=_Planner)
+    try:
+        orchestrator = sovereign_cli.CognitiveOrchestrator()
+        orchestrator.register_python_agent("steps", Slow())
+        assert orchestrator.cancel("ctx1") is False
+        reports = []
+        runner = threading.Thread(target=lambda: reports.append(orchestrator.process_report("plan it", "ctx1")))
+        runner.start()
+        assert Slow.started.wait(5)
+        assert orchestrator.cancel("ctx1") is True
+        runner.join(5)
+
+        report = reports[0]
+        assert report.outputs() == ["step one"]
+        assert report.cancelled and not report.success
+        assert json.loads(report.to_json())["cancelled"] is True
+    finally:
+        sys.modules.pop("python.agents.planner_agent", None)
+
+
+def test_failed_anomaly_logs_are_retried():
+    """Anomalies the memory agent fails to store stay pending until a later store succeeds"""
+
+    class QdrantMemory:
+        failures = 2
+        stored = []
+
+        def store_context(self, text, context_id, payload):
+            if QdrantMemory.failures:
+                QdrantMemory.failures -= 1
+                raise ConnectionError("qdrant unavailable")
+            QdrantMemory.stored.append(payload)
+
+    _install_agent_module("python.memory", QdrantMemory=QdrantMemory)
+    try:
+        orchestrator = sovereign_cli.CognitiveOrchestrator()
+        pending = []
+        for subtask in ["deploy one", "deploy two", "deploy three"]:
+            orchestrator.self_debug(sovereign_cli.AgentResult(f"{subtask} failed", False), subtask, "ctx1")
+            pending.append(orchestrator.pending_anomaly_count())
+        assert pending == [1, 2, 0]
+        assert [payload["subtask"] for payload in QdrantMemory.stored] == ["deploy one", "deploy two", "deploy three"]
+        assert QdrantMemory.stored[0]["type"] == "error" and QdrantMemory.stored[0]["context_id"] == "ctx1"
+        assert orchestrator.shutdown(grace=0.0)["pending_anomalies"] == 0
+    finally:
+        sys.modules.pop("python.memory", None)
+
+
+def test_estimate_plans_without_running_or_creating_the_context():
+    """estimate calls only the planner and leaves the context as it was"""
+
+    _Planner.steps = ["query llm write a tagline", "eval metrics"]
+    _install_agent_module("python.agents.planner_agent", PlannerAgent=_Planner)
+    try:
+        orchestrator = sovereign_cli.CognitiveOrchestrator(prefer_native=True)
+        estimate = orchestrator.estimate("launch it", "ctx1")
+        assert [subtask["agent"] for subtask in estimate["subtasks"]] == ["llm", "eval"]
+        assert estimate["llm_calls"] == 1 and estimate["llm_tokens"] == 8
+        assert estimate["without_latency"] == 2 and estimate["budget_exceeded"] is None
+        assert orchestrator.get_context("ctx1") is None
+
+        orchestrator.set_budget("ctx1", max_llm_calls=0)
+        assert orchestrator.estimate("launch it", "ctx1")["budget_exceeded"] == "llm_calls"
+    finally:
+        sys.modules.pop("python.agents.planner_agent", None)
+
+
+def test_memory_decay_and_pruning():
+    """Decay discounts old memory vectors at recall and prune_memory drops them"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator()
+    orchestrator.add_memory("ctx1", [1.0, 0.0])
+    orchestrator.add_memory("ctx1", [0.6, 0.8])
+    assert [idx for idx, _ in orchestrator.recall("ctx1", [1.0, 0.0], 2)] == [0, 1]
+
+    meta = orchestrator.get_context("ctx1").memory_meta
+    assert [entry["access_count"] for entry in meta] == [1, 1]
+    orchestrator.set_memory_decay("ctx1", 0.25)
+    assert orchestrator.get_context("ctx1").memory_decay == 0.25
+    with pytest.raises(ValueError, match="non-negative"):
+        orchestrator.set_memory_decay("ctx1", -1.0)
+
+    assert orchestrator.prune_memory("ctx1", max_entries=1) == 1
+    assert orchestrator.get_context("ctx1").memory_vectors == [pytest.approx([0.6, 0.8])]
+
+
+def test_post_processors_rewrite_llm_results():
+    """Built-in and callable post-processors run in order on LLM results"""
+
+    class LLMAgent:
+        def generate(self, prompt):
+            return '  Here you go:\n```json\n{"hooks": ["a", "b"]}\n```  '
+
+    _install_agent_module("python.agents.llm_agent", LLMAgent=LLMAgent)
+    try:
+        orchestrator = sovereign_cli.CognitiveOrchestrator()
+        orchestrator.add_post_processor("llm", "trim_whitespace")
+        orchestrator.add_post_processor("llm", "extract_json_block")
+        orchestrator.add_post_processor(
+            "llm", lambda subtask, r: sovereign_cli.AgentResult(r.output.upper(), r.status, r.metadata)
+        )
+        result = orchestrator.dispatch("llm:generate hooks", "ctx1")
+        assert result.output.startswith("HERE YOU GO:") and result.output.endswith("```")
+        assert result.metadata["parsed"] == {"hooks": ["a", "b"]}
+
+        orchestrator.clear_post_processors("llm")
+        orchestrator.add_post_processor("llm", "max_length_truncate", max_chars=6)
+        assert orchestrator.dispatch("llm:generate hooks", "ctx1").output == "  Here"
+        with pytest.raises(ValueError):
+            orchestrator.add_post_processor("llm", "max_length_truncate")
+        with pytest.raises(ValueError):
+            orchestrator.add_post_processor("llm", "uppercase")
+    finally:
+        sys.modules.pop("python.agents.llm_agent", None)
+
+
+def test_later_steps_reference_earlier_llm_outputs():
+    """{{step_n.output}} expands from the blackboard, which agents may take as a dict"""
+
+    class LLMAgent:
+        def generate(self, prompt):
+            return f"hook: {prompt}"
+
+    class Simulator:
+        def __init__(self):
+            self.boards = []
+
+        def can_handle(self, sub_task):
+            return sub_task.startswith("simulate")
+
+        def execute(self, sub_task, context, blackboard):
+            self.boards.append(blackboard)
+            return {"output": f"simulated {sub_task[len('simulate '):]}", "status": True}
+
+    _install_agent_module("python.agents.llm_agent", LLMAgent=LLMAgent)
+    try:
+        simulator = Simulator()
+        orchestrator = sovereign_cli.CognitiveOrchestrator()
+        orchestrator.register_python_agent("simulate", simulator)
+        orchestrator.register_plan_template(
+            "prefix", "launch", ["llm:generate wait for it", "simulate {{step_1.output}}", "simulate {{step_5.output}}"]
+        )
+        report = orchestrator.process_report("launch teaser", "ctx1")
+        assert report.subtasks[1] == "simulate hook: wait for it"
+        assert report.results[1].output == "simulated hook: wait for it"
+        assert simulator.boards == [{"step_1.output": "hook: wait for it"}]
+        assert report.results[2].error["kind"] == "unresolved_reference"
+        assert report.blackboard.get("step_1.output") == "hook: wait for it"
+        assert report.blackboard.to_dict() == {"step_1.output": "hook: wait for it"}
+    finally:
+        sys.modules.pop("python.agents.llm_agent", None)
+
+
+def test_structured_subtasks_route_on_their_agent_tag():
+    """A prompt that mentions viral marketing goes to the LLM, in either form"""
+
+    class LLMAgent:
+        def generate(self, prompt):
+            return f"essay: {prompt}"
+
+    _install_agent_module("python.agents.llm_agent", LLMAgent=LLMAgent)
+    try:
+        orchestrator = sovereign_cli.CognitiveOrchestrator()
+        for subtask in ["query llm write an essay about viral marketing", "llm:generate write an essay about viral marketing"]:
+            assert orchestrator.dispatch(subtask, "ctx1").output == "essay: write an essay about viral marketing"
+
+        malformed = orchestrator.dispatch("llm: essay", "ctx1")
+        assert malformed.error["kind"] == "malformed_subtask"
+        assert "empty action" in malformed.output
+
+        orchestrator.dispatch("memory:remember launch teaser on friday", "ctx1")
+        assert orchestrator.dispatch("memory:recall teaser", "ctx1").output == "launch teaser on friday"
+    finally:
+        sys.modules.pop("python.agents.llm_agent", None)
+
+
+def test_list_contexts_and_context_stats_follow_mutations():
+    """Context summaries and stats reflect remember and process"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator(prefer_native=True)
+    assert orchestrator.list_contexts() == [] and orchestrator.context_stats("ctx1") is None
+
+    orchestrator.remember("ctx1", "launch teaser")
+    [summary] = orchestrator.list_contexts()
+    assert (summary["context_id"], summary["memory_count"], summary["virality_score"]) == ("ctx1", 1, 0.0)
+
+    orchestrator.process("viral sim", "ctx1")
+    [summary] = orchestrator.list_contexts()
+    assert summary["virality_score"] > 0.0
+    stats = orchestrator.context_stats("ctx1")
+    assert stats["context_id"] == "ctx1" and stats["history_len"] == len(orchestrator.get_history("ctx1")) > 0
+    assert stats["memory_bytes"] >= stats["memory_dim"] * 4 + len("launch teaser")
+
+
+def test_deleted_contexts_restore_until_the_trash_is_purged():
+    """delete_context moves a context to the trash; restore brings it back until purge_trash drops it"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator(prefer_native=True)
+    orchestrator.remember("oops", "launch teaser")
+    orchestrator.remember("old", "budget spent")
+    orchestrator.delete_context("oops")
+    orchestrator.delete_context("old")
+    assert orchestrator.list_contexts() == [] and orchestrator.get_context("oops") is None
+    listed = orchestrator.list_contexts_including_trashed()
+    assert [summary["context_id"] for summary in listed] == ["old", "oops"]
+    assert all("deleted_at" in summary for summary in listed)
+
+    assert orchestrator.restore_context("oops").memory_texts == ["launch teaser"]
+    assert "deleted_at" not in orchestrator.list_contexts()[0]
+    assert orchestrator.purge_trash(0.0) == ["old"]
+    with pytest.raises(RuntimeError, match="unknown context: old"):
+        orchestrator.restore_context("old")
+
+
+def test_process_runs_concurrently_across_python_threads():
+    """Runs on different contexts from two Python threads overlap"""
+    import threading
+    import time
+
+    class _SlowAgent:
+        windows = []
+
+        def can_handle(self, sub_task):
+            return sub_task.startswith("research")
+
+        def execute(self, sub_task, context):
+            started = time.monotonic()
+            time.sleep(0.2)
+            _SlowAgent.windows.append((started, time.monotonic()))
+            return {"output": context["context_id"], "status": True}
+
+    orchestrator = sovereign_cli.CognitiveOrchestrator()
+    orchestrator.register_python_agent("research", _SlowAgent())
+    orchestrator.register_plan_template("prefix", "study", ["research topic"])
+    threads = [threading.Thread(target=orchestrator.process, args=("study it", ctx)) for ctx in ("ctx1", "ctx2")]
+    for thread in threads:
+        thread.start()
+    for thread in threads:
+        thread.join()
+
+    (a_start, a_end), (b_start, b_end) = _SlowAgent.windows
+    assert a_start < b_end and b_start < a_end
+    assert orchestrator.context_ids() == ["ctx1", "ctx2"]
+
+
+def test_int8_memory_quantization_shrinks_vectors_and_keeps_recall():
+    """memory_quantization="int8" stores a byte per component and still recalls the closest text"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator(memory_quantization="int8")
+    for text in ("launch teaser", "spring sale", "quarterly report"):
+        orchestrator.remember("ctx1", text)
+    assert [text for text, _ in orchestrator.recall("ctx1", "spring sale", 1)] == ["spring sale"]
+    stats = orchestrator.context_stats("ctx1")
+    texts = len("launch teaser") + len("spring sale") + len("quarterly report")
+    assert stats["memory_bytes"] == 3 * (stats["memory_dim"] + 4) + texts
+
+    with pytest.raises(ValueError, match="unknown memory quantization"):
+        sovereign_cli.CognitiveOrchestrator(memory_quantization="int4")
+
+
+def test_search_memory_filters_payloads_and_fuses_rankings():
+    """search_memory keeps entries whose payloads pass the filters, best first"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator()
+    orchestrator.remember("ctx1", "launch teaser flopped", {"type": "error", "command": "spring launch"})
+    orchestrator.remember("ctx1", "launch teaser went viral", {"type": "success", "command": "spring launch"})
+    orchestrator.remember("ctx1", "budget overrun", {"type": "error", "command": "summer launch"})
+    orchestrator.remember("ctx1", "untagged launch teaser")
+
+    hits = orchestrator.search_memory("ctx1", "launch teaser", {"type": "error"})
+    assert [hit["text"] for hit in hits] == ["launch teaser flopped", "budget overrun"]
+    assert hits[0]["payload"]["command"] == "spring launch" and hits[0]["score"] > hits[1]["score"]
+    hits = orchestrator.search_memory("ctx1", "launch", {"command": {"contains": "spring"}}, k=1)
+    assert [hit["index"] for hit in hits] == [0]
+
+
+def test_binary_persistence_formats_round_trip_and_load_by_magic(tmp_path):
+    """persistence_format picks the checkpoint encoding; load_contexts detects it"""
+    for name, magic in (("messagepack", b"ACE\0MSGP"), ("bincode", b"ACE\0BINC")):
+        orchestrator = sovereign_cli.CognitiveOrchestrator(persistence_format=name)
+        orchestrator.remember("ctx1", "lancement réussi 🚀", {"étiquette": ["naïve", {"depth": 2}]})
+        path = tmp_path / f"contexts.{name}"
+        orchestrator.save_contexts(str(path))
+        assert path.read_bytes().startswith(magic)
+
+        restored = sovereign_cli.CognitiveOrchestrator()
+        assert restored.load_contexts(str(path)).loaded == ["ctx1"]
+        assert restored.get_context("ctx1").memory_texts == ["lancement réussi 🚀"]
+        assert restored.search_memory("ctx1", "lancement")[0]["payload"] == {"étiquette": ["naïve", {"depth": 2}]}
+
+    with pytest.raises(ValueError, match="unknown persistence format"):
+        sovereign_cli.CognitiveOrchestrator(persistence_format="xml")
+
+
+def test_try_process_raises_while_a_run_holds_the_context():
+    """try_process fails fast on a busy context and context_stats names the holder"""
+    import threading
+
+    entered, release = threading.Event(), threading.Event()
+
+    class _HoldingAgent:
+        def can_handle(self, sub_task):
+            return sub_task.startswith("hold")
+
+        def execute(self, sub_task, context):
+            entered.set()
+            release.wait(5)
+            return {"output": sub_task, "status": True}
+
+    # Under a timeout the agent works on a copy of the context, which stays readable.
+    orchestrator = sovereign_cli.CognitiveOrchestrator(subtask_timeout=5)
+    orchestrator.register_python_agent("hold", _HoldingAgent())
+    orchestrator.register_plan_template("prefix", "keep", ["hold on"])
+    holder = threading.Thread(target=orchestrator.process, args=("keep it", "ctx1"))
+    holder.start()
+    assert entered.wait(5)
+
+    with pytest.raises(RuntimeError, match="context ctx1 is busy"):
+        orchestrator.try_process("keep it", "ctx1")
+    lock = orchestrator.context_stats("ctx1")["run_lock"]
+    assert (lock["command"], lock["waiting"]) == ("keep it", 0)
+
+    release.set()
+    holder.join()
+    assert orchestrator.context_stats("ctx1")["run_lock"] is None
+    assert "hold on" in orchestrator.try_process("keep it", "ctx1")
+
+
+def test_planner_gets_context_input_and_string_planners_still_work():
+    """decompose receives goals, metrics, history and memories; planners raising TypeError get the string"""
+
+    class Planner:
+        inputs = []
+
+        def decompose(self, planner_input):
+            Planner.inputs.append(planner_input)
+            return ["step one"]
+
+    class StringPlanner:
+        prompts = []
+
+        def decompose(self, command):
+            if not isinstance(command, str):
+                raise TypeError("command must be a str")
+            StringPlanner.prompts.append(command)
+            return ["step one"]
+
+    class Steps:
+        def can_handle(self, sub_task):
+            return sub_task.startswith("step")
+
+        def execute(self, sub_task, context):
+            return {"output": sub_task, "status": True}
+
+    try:
+        _install_agent_module("python.agents.planner_agent", PlannerAgent=Planner)
+        orchestrator = sovereign_cli.CognitiveOrchestrator(learning=False)
+        orchestrator.register_python_agent("steps", Steps())
+        orchestrator.add_goal("ctx1", "reach", "reach 10k views", priority="high")
+        orchestrator.remember("ctx1", "teaser flopped")
+        orchestrator.process("launch it", "ctx1")
+        orchestrator.process("launch again", "ctx1")
+        planner_input = Planner.inputs[-1]
+        assert planner_input["command"] == "launch again"
+        assert planner_input["goals"] == [{"id": "reach", "description": "reach 10k views", "priority": "high"}]
+        assert planner_input["metrics"]["virality_score"] == 0.0
+        assert [record["subtask"] for record in planner_input["history"]] == ["step one"]
+        assert planner_input["memories"] == ["teaser flopped"]
+
+        _install_agent_module("python.agents.planner_agent", PlannerAgent=StringPlanner)
+        orchestrator = sovereign_cli.CognitiveOrchestrator(learning=False)
+        orchestrator.register_python_agent("steps", Steps())
+        orchestrator.add_goal("ctx1", "reach", "reach 10k views", priority="high")
+        assert json.loads(orchestrator.process("launch it", "ctx1")) == ["step one"]
+        assert StringPlanner.prompts == ["launch it\n\nActive high-priority goals:\n- reach 10k views"]
+    finally:
+        sys.modules.pop("python.agents.planner_agent", None)
+
+
+def test_dispatch_policy_blocks_subtasks_from_config_and_reloads():
+    """Denied subtasks fail as policy_blocked, strict policies reject the plan, and policies reload"""
+
+    class Planner:
+        def decompose(self, command):
+            return ["step teaser", "step rm -rf /", "step recap"]
+
+    class Steps:
+        ran = []
+
+        def can_handle(self, sub_task):
+            return sub_task.startswith("step")
+
+        def execute(self, sub_task, context):
+            Steps.ran.append(sub_task)
+            return {"output": sub_task, "status": True}
+
+    try:
+        _install_agent_module("python.agents.planner_agent", PlannerAgent=Planner)
+        deny = [{"kind": "regex", "pattern": r"rm\s+-rf"}]
+        orchestrator = sovereign_cli.CognitiveOrchestrator.from_config(
+            {"dispatch_policy": {"deny": deny}, "learning": {"enabled": False}}
+        )
+        orchestrator.register_python_agent("steps", Steps())
+        outputs = json.loads(orchestrator.process("launch it", "ctx1"))
+        assert Steps.ran == ["step teaser", "step recap"]
+        assert outputs[1].startswith("Policy Blocked: subtask \"step rm -rf /\" blocked")
+
+        orchestrator.set_dispatch_policy({"deny": deny, "strict": True})
+        assert orchestrator.dispatch_policy()["strict"] is True
+        outputs = json.loads(orchestrator.process("launch it", "ctx1"))
+        assert len(outputs) == 1 and outputs[0].startswith("Plan Error:")
+        assert len(Steps.ran) == 2
+
+        with pytest.raises(RuntimeError, match="invalid pattern"):
+            orchestrator.set_dispatch_policy({"deny": [{"kind": "regex", "pattern": "("}]})
+        with pytest.raises(ValueError):
+            orchestrator.set_dispatch_policy({"denied": []})
+        with pytest.raises(ValueError, match="dispatch_policy"):
+            sovereign_cli.CognitiveOrchestrator.from_config(
+                {"dispatch_policy": {"allow": [{"kind": "regex", "pattern": "["}]}}
+            )
+    finally:
+        sys.modules.pop("python.agents.planner_agent", None)
+
+
+def test_async_agents_are_awaited_from_sync_and_async_entry_points():
+    """Coroutines from async def agent methods are run: on the caller's loop under process_async"""
+
+    class LLMAgent:
+        loops = []
+
+        async def generate(self, prompt):
+            await asyncio.sleep(0.01)
+            LLMAgent.loops.append(asyncio.get_running_loop())
+            return f"async: {prompt}"
+
+    class Planner:
+        async def decompose(self, command):
+            await asyncio.sleep(0.01)
+            return ["query llm hi", "step done"]
+
+    class Steps:
+        def can_handle(self, sub_task):
+            return sub_task.startswith("step")
+
+        async def execute(self, sub_task, context):
+            await asyncio.sleep(0.01)
+            return {"output": sub_task, "status": True}
+
+    try:
+        _install_agent_module("python.agents.llm_agent", LLMAgent=LLMAgent)
+        _install_agent_module("python.agents.planner_agent", PlannerAgent=Planner)
+        orchestrator = sovereign_cli.CognitiveOrchestrator(learning=False)
+        orchestrator.register_python_agent("steps", Steps())
+        expected = ["async: hi", "step done"]
+        assert json.loads(orchestrator.process("launch it", "ctx1")) == expected
+
+        async def run_async():
+            return asyncio.get_running_loop(), await orchestrator.process_async("launch it", "ctx1")
+
+        caller_loop, output = asyncio.run(run_async())
+        assert json.loads(output) == expected
+        assert LLMAgent.loops[-1] is caller_loop
+
+        async def run_sync_inside_a_loop():
+            return orchestrator.process("launch it", "ctx1")
+
+        assert json.loads(asyncio.run(run_sync_inside_a_loop())) == expected
+    finally:
+        sys.modules.pop("python.agents.llm_agent", None)
+        sys.modules.pop("python.agents.planner_agent", None)
+
+
+def test_results_and_reports_carry_dispatch_timing():
+    """Subtasks record total, Python and GIL wait milliseconds; colliding agent keys are namespaced"""
+    import time
+
+    class Sleeper:
+        def can_handle(self, sub_task):
+            return sub_task.startswith("nap")
+
+        def execute(self, sub_task, context):
+            time.sleep(0.05)
+            return {"output": sub_task, "status": True, "metadata": {"_timing.py_ms": "mine"}}
+
+    orchestrator = sovereign_cli.CognitiveOrchestrator(learning=False)
+    orchestrator.register_python_agent("sleeper", Sleeper())
+    orchestrator.register_plan_template("prefix", "rest", ["nap one", "nap two"])
+    report = orchestrator.process_report("rest up", "ctx1")
+    for result in report.results:
+        timing = result.metadata
+        assert 50.0 <= timing["_timing.py_ms"] <= timing["_timing.total_ms"] < 5000.0
+        assert 0.0 <= timing["_timing.gil_wait_ms"] <= timing["_timing.total_ms"]
+        assert timing["agent._timing.py_ms"] == "mine"
+    assert report.timing.py_ms >= 100.0
+    assert report.timing.dispatch_ms == pytest.approx(sum(r.metadata["_timing.total_ms"] for r in report.results))
+    assert report.timing.total_ms >= report.timing.dispatch_ms
+    assert json.loads(report.to_json())["timing"]["py_ms"] == report.timing.py_ms
+
+
+def test_context_viral_config_overrides_the_configured_threshold():
+    """A context's virality threshold replaces the configured one for its later viral subtasks"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator.from_config(
+        {"prefer_native": True, "seed": 7, "viral": {"virality_threshold": 1.0}, "learning": {"enabled": False}}
+    )
+    result = orchestrator.dispatch("viral:simulate", "ctx1")
+    assert not result.status and result.metadata["virality_threshold"] == 1.0
+    assert result.metadata["virality_margin"] == pytest.approx(result.metadata["virality"] - 1.0)
+
+    orchestrator.set_viral_config("ctx1", {"virality_threshold": 0.0})
+    assert orchestrator.viral_config("ctx1")["virality_threshold"] == 0.0
+    result = orchestrator.dispatch("viral:simulate", "ctx1")
+    assert result.status and result.metadata["virality_threshold"] == 0.0
+    assert orchestrator.viral_config("ctx2")["virality_threshold"] == 1.0
+
+    orchestrator.clear_viral_config("ctx1")
+    assert orchestrator.dispatch("viral:simulate", "ctx1").metadata["virality_threshold"] == 1.0
+    with pytest.raises(ValueError, match="virality_threshold"):
+        orchestrator.set_viral_config("ctx1", {"virality_threshold": 1.5})
+    with pytest.raises(ValueError, match="viral.virality_threshold"):
+        sovereign_cli.CognitiveOrchestrator.from_config({"viral": {"virality_threshold": -0.1}})
+
+
+def test_failures_are_classified_and_follow_configured_strategies():
+    """Failed subtasks are classified in the report and handled by their class's strategy"""
+
+    class Steps:
+        ran = []
+
+        def can_handle(self, sub_task):
+            return sub_task.startswith("step")
+
+        def execute(self, sub_task, context):
+            Steps.ran.append(sub_task)
+            if sub_task == "step boom":
+                raise RuntimeError("boom")
+            return {"output": sub_task, "status": sub_task != "step flaky"}
+
+    orchestrator = sovereign_cli.CognitiveOrchestrator.from_config(
+        {"debug_strategies": {"agent_exception": "abort"}, "learning": {"enabled": False}}
+    )
+    orchestrator.register_python_agent("steps", Steps())
+    orchestrator.register_plan_template("prefix", "go", ["step one", "step boom", "step two"])
+    report = orchestrator.process_report("go now", "ctx1")
+    assert Steps.ran == ["step one", "step boom"] and not report.success
+    [decision] = report.debug
+    assert (decision.subtask, decision.failure_class, decision.strategy) == ("step boom", "agent_exception", "abort")
+    assert json.loads(report.to_json())["debug"] == [
+        {"subtask": "step boom", "class": "agent_exception", "strategy": "abort"}
+    ]
+
+    orchestrator.set_debug_strategy("other", "retry")
+    assert orchestrator.debug_strategies()["other"] == "retry"
+    orchestrator.register_plan_template("prefix", "try", ["step flaky"])
+    report = orchestrator.process_report("try it", "ctx1")
+    assert [d.strategy for d in report.debug][0] == "retry"
+    assert Steps.ran[2:4] == ["step flaky", "step flaky (retry after other)"]
+
+    with pytest.raises(ValueError, match="policy_blocked must be skip or abort"):
+        orchestrator.set_debug_strategy("policy_blocked", "replan")
+    with pytest.raises(ValueError):
+        orchestrator.set_debug_strategy("gremlins", "skip")
+    with pytest.raises(ValueError, match="debug_strategies.policy_blocked"):
+        sovereign_cli.CognitiveOrchestrator.from_config({"debug_strategies": {"policy_blocked": "retry"}})
+
+
+def test_context_handle_persists_on_exit(tmp_path):
+    """A persisting context handle creates its context on enter and merges it into the eviction snapshot on exit"""
+    path = tmp_path / "contexts.json"
+    orchestrator = sovereign_cli.CognitiveOrchestrator(prefer_native=True, eviction_path=str(path))
+    with orchestrator.context("exp-42", persist=True) as ctx:
+        assert ctx.context_id == "exp-42" and "exp-42" in orchestrator.context_ids()
+        assert ctx.remember("launch went viral") == 0
+        json.loads(ctx.process("viral sim"))
+        metrics = ctx.metrics()
+        assert not path.exists()
+    assert orchestrator.get_context("exp-42").viral_metrics.virality_score == metrics.virality_score
+    saved = json.loads(path.read_text())["contexts"]["exp-42"]
+    assert saved["memory_texts"][0] == "launch went viral"
+
+    with pytest.raises(ValueError, match="eviction_path"):
+        sovereign_cli.CognitiveOrchestrator().context("exp-43", persist=True)
+
+
+def test_ephemeral_context_handle_is_removed_even_when_the_body_raises():
+    """An ephemeral context handle removes its context on exit, and the body's exception still propagates"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator(prefer_native=True)
+    with orchestrator.context("scratch", ephemeral=True) as ctx:
+        ctx.process("viral sim")
+        assert orchestrator.get_context("scratch") is not None
+    assert orchestrator.get_context("scratch") is None
+
+    with pytest.raises(KeyError):
+        with orchestrator.context("scratch", ephemeral=True) as ctx:
+            ctx.remember("half-done")
+            raise KeyError("boom")
+    assert "scratch" not in orchestrator.context_ids()
+    with pytest.raises(RuntimeError, match="unknown context"):
+        ctx.metrics()
+
+
+def test_garbage_plans_are_validated_and_reported():
+    """Blank, repeated and overlong planner entries are dropped and reported; an empty plan falls back to the LLM"""
+
+    class Planner:
+        plan = ["", "step a", "step a", "   ", "step " + "x" * 100, "step b"]
+
+        def decompose(self, command):
+            return Planner.plan
+
+    class LLMAgent:
+        def generate(self, prompt):
+            return f"llm: {prompt}"
+
+    class Steps:
+        def can_handle(self, sub_task):
+            return sub_task.startswith("step")
+
+        def execute(self, sub_task, context):
+            return {"output": sub_task, "status": True}
+
+    try:
+        _install_agent_module("python.agents.planner_agent", PlannerAgent=Planner)
+        _install_agent_module("python.agents.llm_agent", LLMAgent=LLMAgent)
+        orchestrator = sovereign_cli.CognitiveOrchestrator.from_config(
+            {"plan_validation": {"max_subtask_chars": 50}, "learning": {"enabled": False}}
+        )
+        orchestrator.register_python_agent("steps", Steps())
+        report = orchestrator.process_report("launch it", "ctx1")
+        assert report.plan == ["step a", "step b"] and report.success
+        findings = [(finding.kind, finding.node, finding.dropped) for finding in report.plan_findings]
+        assert findings == [("blank", 0, True), ("duplicate", 2, True), ("blank", 3, True), ("subtask_too_long", 4, True)]
+        assert json.loads(report.to_json())["plan_findings"][3]["kind"] == "subtask_too_long"
+
+        Planner.plan = [" ", "\n"]
+        report = orchestrator.process_report("write a haiku", "ctx1")
+        assert report.plan == ["llm:generate write a haiku"]
+        assert report.outputs() == ["llm: write a haiku"]
+        assert report.plan_findings[-1].kind == "fallback"
+
+        with pytest.raises(ValueError, match="plan_validation.max_subtasks"):
+            sovereign_cli.CognitiveOrchestrator.from_config({"plan_validation": {"max_subtasks": 0}})
+    finally:
+        sys.modules.pop("python.agents.planner_agent", None)
+        sys.modules.pop("python.agents.llm_agent", None)
+
+
+def test_spread_measurements_continue_until_reset():
+    """Native spread measurements extend the context's last one until reset_propagation starts it over"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator(prefer_native=True, seed=3)
+    results = [orchestrator.dispatch("measure spread", "ctx1") for _ in range(3)]
+    assert [result.metadata["continued"] for result in results] == [False, True, True]
+    reach = [result.metadata["metrics"]["reached_per_round"][-1] for result in results]
+    assert reach == sorted(reach)
+    assert orchestrator.reset_propagation("ctx1") is True
+    assert orchestrator.dispatch("measure spread", "ctx1").metadata["continued"] is False
+    assert orchestrator.reset_propagation("missing") is False
+
+
+def test_auto_diff_reports_what_changed_since_the_last_run():
+    """With auto_diff, each report carries a diff against the context's previous run of the same command"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator(prefer_native=True, seed=1, auto_diff=True, learning=False)
+    first = orchestrator.process_report("eval metrics", "ctx1")
+    assert first.diff is None and first.subtasks == ["eval metrics"]
+    orchestrator.dispatch("inject hook 0.9", "ctx1")
+    second = orchestrator.process_report("eval metrics", "ctx1")
+    deltas = {delta.metric: delta for delta in second.diff.metric_deltas}
+    assert deltas["hook_rate"].after == 0.9 and not second.diff.status_flips
+    assert orchestrator.last_report("ctx1").diff.to_json() == second.diff.to_json()
+    assert json.loads(second.to_json())["diff"]["metric_deltas"]
+    assert orchestrator.compare_runs(second, second).is_empty()
+    assert orchestrator.last_report("ctx2") is None
+
+
+def test_journaled_runs_a_crash_cut_short_are_resumed(tmp_path):
+    """A run the journal shows unfinished is recovered by the next process and resumed from where it stopped"""
+    path = tmp_path / "runs.jsonl"
+    at = "2026-01-01T00:00:00Z"
+    records = [
+        {"record": "plan_started", "run_id": "run-1", "context_id": "ctx1", "tenant": "default", "command": "tune and measure",
+         "waves": [["eval metrics"], ["inject hook 0.9"], ["eval metrics"]], "at": at},
+        {"record": "subtask_dispatched", "run_id": "run-1", "subtask": "eval metrics", "at": at},
+        {"record": "subtask_finished", "run_id": "run-1", "subtask": "eval metrics", "status": True, "digest": "0", "at": at},
+        {"record": "subtask_dispatched", "run_id": "run-1", "subtask": "inject hook 0.9", "at": at},
+    ]
+    path.write_text("".join(json.dumps(record) + "\n" for record in records))
+
+    orchestrator = sovereign_cli.CognitiveOrchestrator(prefer_native=True, seed=1, journal_path=str(path), journal_fsync="always")
+    [run] = orchestrator.recover_incomplete_runs()
+    assert (run.run_id, run.context_id, run.finished) == ("run-1", "ctx1", ["eval metrics"])
+    assert run.in_flight == ["inject hook 0.9"]
+    assert run.unfinished == ["inject hook 0.9", "eval metrics"]
+
+    report = orchestrator.resume_run("run-1")
+    assert report.success and report.run_id == "run-1"
+    assert report.subtasks == ["inject hook 0.9", "eval metrics"]
+    assert orchestrator.recover_incomplete_runs() == []
+    with pytest.raises(RuntimeError, match="no incomplete journaled run"):
+        orchestrator.resume_run("run-1")
+    assert orchestrator.process_report("eval metrics", "ctx1").run_id
+    with pytest.raises(ValueError, match="journal fsync"):
+        sovereign_cli.CognitiveOrchestrator(journal_path=str(path), journal_fsync="sometimes")
+
+
+def test_forked_contexts_merge_back_into_their_parent():
+    """A fork records its parent; merging it back unites goals, skips duplicate memories and keeps the best metrics"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator(prefer_native=True, seed=1)
+    orchestrator.add_goal("base", "reach", "reach 10k views")
+    orchestrator.add_memory("base", [1.0, 0.0])
+    fork = orchestrator.fork_context("base", "hook-high")
+    assert fork.lineage.parent_id == "base" and orchestrator.get_context("base").lineage is None
+    with pytest.raises(RuntimeError, match="already exists"):
+        orchestrator.fork_context("base", "hook-high")
+
+    orchestrator.add_goal("hook-high", "retain", "keep followers")
+    orchestrator.add_memory("hook-high", [0.0, 1.0])
+    report = orchestrator.merge_contexts("base", "hook-high")
+    assert (report.goals_added, report.memory_added, report.memory_deduplicated) == (1, 1, 1)
+    assert [goal.id for goal in orchestrator.list_goals("base")] == ["reach", "retain"]
+
+    orchestrator.add_goal("rival", "reach", "reach 50k views")
+    with pytest.raises(RuntimeError, match="already has a goal"):
+        orchestrator.merge_contexts("base", "rival")
+    assert orchestrator.merge_contexts("base", "rival", goal_conflict="take_source").goals_replaced == 1
+    with pytest.raises(ValueError, match="goal conflict"):
+        orchestrator.merge_contexts("base", "rival", goal_conflict="coin_flip")
+
+
+def test_secrets_are_redacted_from_memory_and_history():
+    """Built-in and added patterns are masked before memory and history keep them"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator(prefer_native=True, seed=1)
+    orchestrator.add_redaction_pattern("ticket", r"TCK-\d+")
+    with pytest.raises(RuntimeError, match="invalid"):
+        orchestrator.add_redaction_pattern("broken", "(")
+
+    orchestrator.remember("ctx1", "jane.doe@example.com reopened TCK-12")
+    assert orchestrator.get_context("ctx1").memory_texts == ["***email*** reopened ***ticket***"]
+    orchestrator.dispatch("viral:simulate for jane.doe@example.com", "ctx1")
+    history = orchestrator.export_history("ctx1", "jsonl")
+    assert "***email***" in history and "jane.doe@example.com" not in history
+
+
+def test_observe_answers_while_an_agent_holds_the_context():
+    """observe reads a run's published progress rather than the context its agent holds"""
+    import threading
+
+    entered, release = threading.Event(), threading.Event()
+
+    class _HoldingAgent:
+        def can_handle(self, sub_task):
+            return sub_task.startswith("hold")
+
+        def execute(self, sub_task, context):
+            if sub_task == "hold second":
+                entered.set()
+                release.wait(5)
+            return {"output": sub_task, "status": True}
+
+    orchestrator = sovereign_cli.CognitiveOrchestrator()
+    orchestrator.register_python_agent("hold", _HoldingAgent())
+    orchestrator.register_plan_template("prefix", "keep", ["hold first", "hold second"])
+    assert orchestrator.observe("ctx1") is None
+    holder = threading.Thread(target=orchestrator.process, args=("keep it", "ctx1"))
+    holder.start()
+    assert entered.wait(5)
+
+    view = orchestrator.observe("ctx1")
+    assert view["run"]["running"] == ["hold second"]
+    assert (view["run"]["completed_steps"], view["run"]["remaining_steps"]) == (1, 0)
+    assert view["elapsed_secs"] > 0
+
+    release.set()
+    holder.join()
+    assert orchestrator.observe("ctx1")["run"] is None
+
+
+def test_run_ids_tie_a_runs_events_results_and_report_together():
+    """one id per run, on every event and result, and a caller's id is kept"""
+    orchestrator = sovereign_cli.CognitiveOrchestrator.from_config({"prefer_native": True, "run_retention": 4})
+    orchestrator.register_plan_template("prefix", "measure", ["eval metrics", "measure spread"])
+    events = list(orchestrator.process_stream("measure twice", "ctx1"))
+    run_id = events[0]["run_id"]
+    assert {event["run_id"] for event in events} == {run_id}
+    finished = [event["result"] for event in events if event["event"] == "subtask_finished"]
+    assert [result["metadata"]["_run_id"] for result in finished] == [run_id, run_id]
+    assert orchestrator.get_run(run_id).run_id == run_id
+
+    report = orchestrator.process_report("measure again", "ctx1", run_id="upstream-7")
+    assert report.run_id == "upstream-7" != run_id
+    kept = orchestrator.get_run("upstream-7")
+    assert [result.metadata["_run_id"] for result in kept.results] == ["upstream-7", "upstream-7"]
+    assert orchestrator.get_run("never-ran") is None
+
+
+def test_priority_orders_runs_in_the_submission_queue():
+    """priority is checked by name, and a queued run reports its wait"""
+    config = {"prefer_native": True, "queue": {"max_running": 1, "depth": 4}}
+    orchestrator = sovereign_cli.CognitiveOrchestrator.from_config(config)
+    report = orchestrator.process_report("eval metrics", "ctx1", priority="high")
+    assert report.success and report.timing.queue_wait_ms >= 0
+    with pytest.raises(ValueError, match="priority"):
+        orchestrator.process("eval metrics", "ctx1", priority="urgent")
+
+
+def test_preflight_lists_each_check_with_remediation():
+    """a missing agent module fails the report and says what to fix"""
+    config = {"prefer_native": True, "python_modules": {"planner": "ace_py_test_missing_planner"}}
+    orchestrator = sovereign_cli.CognitiveOrchestrator.from_config(config)
+    checks = {check["name"]: check for check in orchestrator.preflight()["checks"]}
+    assert checks["agents.planner"]["status"] == "fail"
+    assert "python_modules.planner" in checks["agents.planner"]["remediation"]
+    assert checks["agents.viral"]["status"] != "fail"
+    assert checks["memory"]["status"] == "pass" and "remediation" not in checks["memory"]
+    assert "smoke.plan" not in checks
+
+
+def test_context_attributes_keep_their_types_and_are_bounded():
+    """attributes travel with the context, typed as set, within max_attribute_bytes"""
+    config = {"prefer_native": True, "max_attribute_bytes": 128}
+    orchestrator = sovereign_cli.CognitiveOrchestrator.from_config(config)
+    orchestrator.set_attribute("ctx1", "campaign", 42)
+    orchestrator.set_attribute("ctx1", "weight", 1.0)
+    orchestrator.set_attribute("ctx1", "arm", {"name": "b", "holdout": False})
+    assert orchestrator.get_attribute("ctx1", "campaign") == 42
+    weight = orchestrator.get_attribute("ctx1", "weight")
+    assert isinstance(weight, float) and weight == 1.0
+    assert orchestrator.get_context("ctx1").attributes["arm"] == {"name": "b", "holdout": False}
+    restored = sovereign_cli.Context.from_json(orchestrator.get_context("ctx1").to_json())
+    assert isinstance(restored.attributes["campaign"], int)
+
+    with pytest.raises(ValueError, match="over the limit of 128"):
+        orchestrator.set_attribute("ctx1", "notes", "x" * 128)
+    assert orchestrator.get_attribute("ctx1", "notes") is None
+    assert orchestrator.remove_attribute("ctx1", "campaign") == 42
+    assert orchestrator.remove_attribute("ctx1", "campaign") is None
+
+    orchestrator.set_attribute("ctx1", "viral.virality_threshold", 0.25)
+    assert orchestrator.viral_config("ctx1")["virality_threshold"] == 0.25
+    with pytest.raises(RuntimeError, match="viral.virality_threshold"):
+        orchestrator.set_attribute("ctx1", "viral.virality_threshold", 2)
+    assert orchestrator.get_attribute("ctx1", "viral.virality_threshold") == 0.25
+    with pytest.raises(RuntimeError, match="viral.bogus"):
+        orchestrator.set_attribute("ctx1", "viral.bogus", 1)
+    assert orchestrator.viral_config("ctx1")["virality_threshold"] == 0.25
+
+
+def test_identical_runs_coalesce_only_when_configured():
+    """a repeated (command, context) within coalesce_window_secs gets the first report back"""
+    config = {"prefer_native": True, "coalesce_window_secs": 30}
+    orchestrator = sovereign_cli.CognitiveOrchestrator.from_config(config)
+    orchestrator.register_plan_template("prefix", "measure", ["eval metrics"])
+    first = orchestrator.process_report("measure twice", "ctx1")
+    again = orchestrator.process_report("measure twice", "ctx1")
+    assert not first.coalesced and again.coalesced
+    assert again.run_id == first.run_id
+    assert not orchestrator.process_report("measure twice", "ctx2").coalesced
+
+    plain = sovereign_cli.CognitiveOrchestrator.from_config({"prefer_native": True})
+    plain.register_plan_template("prefix", "measure", ["eval metrics"])
+    plain.process_report("measure twice", "ctx1")
+    assert not plain.process_report("measure twice", "ctx1").coalesced
+
+
+class _SlowAgent:
+    """Handles `slow ...` in Python, signalling `started` and then holding the run"""
+
+    def __init__(self):
+        import threading
+
+        self.started = threading.Event()
+        self.calls = 0
+
+    def can_handle(self, sub_task):
+        return sub_task.startswith("slow")
+
+    def execute(self, sub_task, context):
+        import time
+
+        self.calls += 1
+        self.started.set()
+        # Runs Python, needing the GIL, both before and after the duplicate joins.
+        for _ in range(20):
+            time.sleep(0.01)
+            sum(range(1000))
+        return {"output": sub_task.upper(), "status": True}
+
+
+def test_a_duplicate_joins_a_python_run_in_flight_without_deadlock():
+    """a process_report arriving while an identical run's Python agent works waits for it with the GIL released"""
+    import threading
+
+    config = {"prefer_native": True, "coalesce_window_secs": 30}
+    orchestrator = sovereign_cli.CognitiveOrchestrator.from_config(config)
+    orchestrator.register_plan_template("prefix", "crawl", ["slow crawl"])
+    agent = _SlowAgent()
+    orchestrator.register_python_agent("slow", agent)
+    reports = []
+
+    def run():
+        reports.append(orchestrator.process_report("crawl site", "ctx1"))
+
+    leader = threading.Thread(target=run)
+    leader.start()
+    assert agent.started.wait(5)
+    duplicate = threading.Thread(target=run)
+    duplicate.start()
+    leader.join(10)
+    duplicate.join(10)
+    assert not leader.is_alive() and not duplicate.is_alive()
+
+    assert agent.calls == 1
+    assert sorted(report.coalesced for report in reports) == [False, True]
+    assert reports[0].run_id == reports[1].run_id
+    assert all(report.outputs() == ["SLOW CRAWL"] for report in reports)
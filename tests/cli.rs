@@ -0,0 +1,202 @@
+use serde_json::Value;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+/// A state file of its own per test; native simulation and a planner module that
+/// does not exist, so runs need no Python agents.
+struct Ace {
+    state: PathBuf,
+}
+
+impl Ace {
+    fn new(name: &str) -> Self {
+        let state = std::env::temp_dir().join(format!("ace-cli-{}-{}.json", name, std::process::id()));
+        let _ = std::fs::remove_file(&state);
+        Self { state }
+    }
+
+    fn command(&self, args: &[&str]) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_ace"));
+        command
+            .arg("--state")
+            .arg(&self.state)
+            .args(args)
+            .env("ACE_PREFER_NATIVE", "true")
+            .env("ACE_PYTHON_MODULES_PLANNER", "ace_cli_test_missing_planner")
+            .env("NO_COLOR", "1");
+        command
+    }
+
+    fn run(&self, args: &[&str]) -> Output {
+        self.command(args).output().unwrap()
+    }
+
+    fn json_lines(&self, args: &[&str]) -> Vec<Value> {
+        let output = self.run(args);
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        String::from_utf8(output.stdout).unwrap().lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+    }
+}
+
+impl Drop for Ace {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.state);
+        let _ = std::fs::remove_file(self.state.with_extension("runs.json"));
+    }
+}
+
+#[test]
+fn run_streams_events_and_persists_the_context() {
+    let ace = Ace::new("run");
+    let events = ace.json_lines(&["--json", "run", "eval metrics", "--context", "c1"]);
+    let kinds: Vec<&str> = events.iter().map(|event| event["event"].as_str().unwrap()).collect();
+    assert_eq!(kinds, ["plan_ready", "subtask_started", "subtask_finished", "completed"]);
+    assert_eq!(events[2]["result"]["status"], true);
+
+    let contexts = ace.json_lines(&["--json", "contexts", "list"]);
+    assert_eq!(contexts[0][0]["context_id"], "c1");
+    let shown = ace.json_lines(&["--json", "contexts", "show", "c1"]);
+    assert_eq!(shown[0]["viral_metrics"]["engagement_nodes"], 32);
+}
+
+#[test]
+fn failed_subtasks_exit_with_status_two() {
+    let ace = Ace::new("failed");
+    let output = ace.run(&["run", "bogus subtask"]);
+    assert_eq!(output.status.code(), Some(2));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("FAIL bogus subtask"), "{}", stdout);
+}
+
+#[test]
+fn plan_shows_steps_without_running_them() {
+    let ace = Ace::new("plan");
+    let output = ace.run(&["plan", "go viral"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("0. content:generate"), "{}", stdout);
+    assert!(stdout.contains("4. eval:metrics  (after 3)"), "{}", stdout);
+    assert!(!ace.state.exists());
+}
+
+#[test]
+fn plan_estimate_reports_agents_and_tokens() {
+    let ace = Ace::new("estimate");
+    let estimate = &ace.json_lines(&["--json", "plan", "go viral", "--estimate"])[0];
+    let agents: Vec<&str> = estimate["subtasks"].as_array().unwrap().iter().map(|s| s["agent"].as_str().unwrap()).collect();
+    assert_eq!(agents, ["content", "hook", "mwpm", "spread", "eval"]);
+    assert_eq!(estimate["subtasks"][0]["llm"], true);
+    assert_eq!(estimate["llm_calls"], 1);
+    assert_eq!(estimate["budget_exceeded"], Value::Null);
+
+    let output = ace.run(&["plan", "query llm write a tagline", "--estimate"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("0. query llm write a tagline  llm  ~8 tokens  -"), "{}", stdout);
+    assert!(stdout.contains("llm calls:      1"), "{}", stdout);
+    assert!(!ace.state.exists());
+}
+
+#[test]
+fn delete_removes_a_saved_context() {
+    let ace = Ace::new("delete");
+    ace.json_lines(&["--json", "run", "eval metrics", "--context", "gone"]);
+    assert_eq!(ace.json_lines(&["--json", "contexts", "delete", "gone"])[0]["deleted"], "gone");
+
+    let output = ace.run(&["contexts", "show", "gone"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8(output.stderr).unwrap().trim(), "ace: unknown context: gone");
+}
+
+#[test]
+fn repl_keeps_one_context_across_commands() {
+    let ace = Ace::new("repl");
+    let mut child = ace
+        .command(&["--json", "repl", "--context", "live"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(b"inject hook 0.5\neval metrics\n:context\n:quit\n").unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+
+    let lines: Vec<Value> =
+        String::from_utf8(output.stdout).unwrap().lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    let context = lines.last().unwrap();
+    assert_eq!(context["context_id"], "live");
+    assert_eq!(context["viral_metrics"]["hook_rate"], 0.5);
+    assert_eq!(ace.json_lines(&["--json", "contexts", "list"])[0][0]["context_id"], "live");
+}
+
+#[test]
+fn schema_writes_one_file_per_type_without_state() {
+    let ace = Ace::new("schema");
+    let dir = std::env::temp_dir().join(format!("ace-cli-schemas-{}", std::process::id()));
+    let lines = ace.json_lines(&["--json", "schema", dir.to_str().unwrap()]);
+    assert_eq!(lines.len(), 4);
+    let context = dir.join("Context.schema.json");
+    assert_eq!(lines[1]["written"], context.to_str().unwrap());
+    let schema: Value = serde_json::from_str(&std::fs::read_to_string(&context).unwrap()).unwrap();
+    assert_eq!(schema["title"], "Context");
+    assert!(!ace.state.exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn run_diff_compares_with_the_last_run_of_the_command() {
+    let ace = Ace::new("diff");
+    let first = ace.json_lines(&["--json", "run", "measure spread", "--diff"]);
+    assert_eq!(first.last().unwrap()["diff"], Value::Null);
+    assert!(ace.run(&["run", "inject hook 0.9"]).status.success());
+
+    let second = ace.json_lines(&["--json", "run", "measure spread", "--diff"]);
+    let diff = &second.last().unwrap()["diff"];
+    assert_eq!(diff["added"], serde_json::json!([]));
+    assert_eq!(diff["reordered"], false);
+    let hook_rate = diff["metric_deltas"].as_array().unwrap().iter().find(|delta| delta["metric"] == "hook_rate").cloned();
+    assert_eq!(hook_rate.map(|delta| delta["after"].clone()), Some(serde_json::json!(0.9)), "{}", diff);
+
+    let output = ace.run(&["run", "eval metrics", "--diff"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("diff: no earlier run of this command"), "{}", stdout);
+}
+
+#[test]
+fn bench_reports_the_same_workload_for_the_same_seed() {
+    let ace = Ace::new("bench");
+    let args = ["--json", "bench", "--seed", "7", "--requests", "60", "--contexts", "5", "--zipf", "1.1", "--mix", "1,1,1"];
+    let first = ace.json_lines(&args);
+    let report = &first[0];
+    assert_eq!(report["requests"], 60);
+    assert_eq!(report["by_kind"]["unknown"]["failure_rate"], 1.0);
+    assert_eq!(report["by_kind"]["llm"]["failures"], 0);
+    assert!(report["latency"]["p99"].as_f64().unwrap() >= report["latency"]["p50"].as_f64().unwrap());
+    let requests = |report: &Value| ["llm", "viral", "unknown"].map(|kind| report["by_kind"][kind]["requests"].clone());
+    assert_eq!(requests(report), requests(&ace.json_lines(&args)[0]));
+    assert!(!ace.state.exists());
+
+    let output = ace.run(&["bench", "--mix", "1,1"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--mix: expected 3 weights, got 2"));
+}
+
+#[test]
+fn doctor_reports_each_check_and_fails_on_a_missing_module() {
+    let ace = Ace::new("doctor");
+    let output = ace.run(&["--json", "doctor"]);
+    assert_eq!(output.status.code(), Some(2), "{}", String::from_utf8_lossy(&output.stderr));
+    let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let check = |name: &str| report["checks"].as_array().unwrap().iter().find(|check| check["name"] == name).cloned().unwrap();
+    let planner = check("agents.planner");
+    assert_eq!(planner["status"], "fail");
+    assert!(planner["detail"].as_str().unwrap().starts_with("cannot import ace_cli_test_missing_planner"), "{}", planner);
+    assert!(planner["remediation"].as_str().unwrap().contains("python_modules.planner"));
+    assert_ne!(check("agents.viral")["status"], "fail");
+    assert_eq!(check("paths.state")["status"], "pass");
+    assert!(!ace.state.exists());
+
+    let stdout = String::from_utf8(ace.run(&["doctor"]).stdout).unwrap();
+    assert!(stdout.lines().any(|line| line.starts_with("FAIL agents.planner")), "{}", stdout);
+    assert!(stdout.contains("fix: install ace_cli_test_missing_planner"), "{}", stdout);
+}
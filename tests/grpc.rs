@@ -0,0 +1,103 @@
+use serde_json::Value;
+use sovereign_cli::grpc::{self, proto, OrchestratorClient};
+use sovereign_cli::{AgentResult, CognitiveOrchestrator, MockBackend, ProcessEvent};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tonic::transport::Channel;
+use tonic::Code;
+
+fn result(output: &str, status: bool) -> AgentResult {
+    AgentResult { output: output.to_string(), status, metadata: HashMap::new(), error: None }
+}
+
+fn orchestrator() -> CognitiveOrchestrator {
+    let mock = MockBackend::new()
+        .plan("launch", ["post teaser", "post launch"])
+        .on("post teaser", |_| result("low virality on teaser", false))
+        .on("post launch", |_| result("launched", true))
+        .on("post alt", |_| result("alt posted", true))
+        .replan(["post alt"]);
+    CognitiveOrchestrator::builder().backend(Arc::new(mock)).build().unwrap()
+}
+
+async fn spawn_server() -> OrchestratorClient<Channel> {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(grpc::serve(listener, orchestrator()));
+    OrchestratorClient::connect(url).await.unwrap()
+}
+
+fn process_request(command: &str, context_id: &str) -> proto::ProcessRequest {
+    proto::ProcessRequest { command: command.to_string(), context_id: context_id.to_string(), ..Default::default() }
+}
+
+/// The event as JSON without its timing, which differs between runs.
+fn untimed(event: ProcessEvent) -> Value {
+    let mut value = serde_json::to_value(event).unwrap();
+    value.as_object_mut().unwrap().remove("at");
+    if let Some(metadata) = value.pointer_mut("/result/metadata").and_then(Value::as_object_mut) {
+        metadata.retain(|key, _| !key.starts_with(sovereign_cli::timing::TIMING_PREFIX));
+    }
+    value
+}
+
+#[tokio::test]
+async fn process_returns_outputs_and_results() {
+    let mut client = spawn_server().await;
+    let response = client.process(process_request("launch campaign", "ctx1")).await.unwrap().into_inner();
+
+    assert_eq!(response.outputs, ["low virality on teaser", "alt posted", "launched"]);
+    assert_eq!(response.replanned, ["post alt"]);
+    let results: Vec<AgentResult> = response.results.into_iter().map(|r| r.try_into().unwrap()).collect();
+    assert!(!results[0].status);
+    assert_eq!(results[1].metadata["replanned_from"], "post teaser");
+
+    let context = client
+        .get_context(proto::GetContextRequest { context_id: "ctx1".to_string() })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(context.context_id, "ctx1");
+    assert!(context.viral_metrics.is_some());
+}
+
+#[tokio::test]
+async fn dispatch_runs_one_subtask() {
+    let mut client = spawn_server().await;
+    let request = proto::DispatchRequest { sub_task: "post launch".to_string(), context_id: "ctx1".to_string() };
+    let result = client.dispatch(request).await.unwrap().into_inner();
+    assert_eq!(result.output, "launched");
+    assert!(result.status);
+
+    let request = proto::DispatchRequest { sub_task: "bogus".to_string(), context_id: "ctx1".to_string() };
+    let result = AgentResult::try_from(client.dispatch(request).await.unwrap().into_inner()).unwrap();
+    assert!(!result.status);
+    assert!(matches!(result.error, Some(sovereign_cli::OrchestratorError::UnknownSubtask { .. })));
+}
+
+#[tokio::test]
+async fn missing_context_is_not_found() {
+    let mut client = spawn_server().await;
+    let err = client
+        .get_context(proto::GetContextRequest { context_id: "nope".to_string() })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::NotFound);
+    assert_eq!(err.message(), "unknown context: nope");
+}
+
+#[tokio::test]
+async fn process_stream_matches_the_callback_api() {
+    let mut expected = vec![];
+    orchestrator().process_streaming("launch campaign".to_string(), "ctx1", |event| expected.push(untimed(event)));
+
+    let mut client = spawn_server().await;
+    let mut stream = client.process_stream(process_request("launch campaign", "ctx1")).await.unwrap().into_inner();
+    let mut streamed = vec![];
+    while let Some(event) = stream.message().await.unwrap() {
+        streamed.push(untimed(event.try_into().unwrap()));
+    }
+
+    assert_eq!(streamed, expected);
+    assert_eq!(streamed.last().unwrap()["event"], "completed");
+}
@@ -0,0 +1,68 @@
+use opentelemetry::trace::{TraceContextExt, TracerProvider};
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use pyo3::types::PyModule;
+use pyo3::Python;
+use sovereign_cli::{otel, CognitiveOrchestrator, MockBackend, PyAgent};
+use std::sync::Arc;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::layer::SubscriberExt;
+
+/// Stands in for Python's `opentelemetry`: `attach` makes the extracted carrier
+/// current, and the agent echoes its `traceparent` back in its metadata.
+const STUB: &str = r#"
+import sys, types
+
+current = [{}]
+
+def extract(carrier):
+    return dict(carrier)
+
+def attach(context):
+    current.append(context)
+    return len(current) - 1
+
+def detach(token):
+    del current[token:]
+
+package = types.ModuleType("opentelemetry")
+context = types.ModuleType("opentelemetry.context")
+context.attach, context.detach = attach, detach
+propagate = types.ModuleType("opentelemetry.propagate")
+propagate.extract = extract
+package.context, package.propagate = context, propagate
+sys.modules.update({"opentelemetry": package, "opentelemetry.context": context, "opentelemetry.propagate": propagate})
+
+class EchoAgent:
+    def can_handle(self, sub_task):
+        return sub_task.startswith("echo")
+
+    def execute(self, sub_task, context):
+        return {"output": sub_task, "status": True, "metadata": {"traceparent": current[-1].get("traceparent")}}
+"#;
+
+#[test]
+fn traceparent_reaches_python_agents() {
+    let provider = SdkTracerProvider::builder().build();
+    let subscriber = tracing_subscriber::registry().with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let agent = Python::with_gil(|py| {
+        let stub = PyModule::from_code(py, STUB, "otel_stub.py", "otel_stub").unwrap();
+        stub.getattr("EchoAgent").unwrap().call0().unwrap().into()
+    });
+    let mock = MockBackend::new().plan("trace", ["echo trace"]);
+    let orch = CognitiveOrchestrator::builder().backend(Arc::new(mock)).learning(false).build().unwrap();
+    orch.register_agent(Box::new(PyAgent::new("echo".to_string(), agent, 10)));
+
+    let root = tracing::info_span!("request");
+    let report = root.in_scope(|| orch.process_report("trace it".to_string(), "ctx1"));
+    assert!(report.success, "{:?}", report.results);
+    let traceparent = report.results[0].metadata["traceparent"].as_str().unwrap().to_string();
+
+    let parts: Vec<&str> = traceparent.split('-').collect();
+    assert_eq!(parts.iter().map(|part| part.len()).collect::<Vec<_>>(), [2, 32, 16, 2], "{}", traceparent);
+    assert_eq!(parts[1], root.context().span().span_context().trace_id().to_string());
+    assert_ne!(parts[2], root.context().span().span_context().span_id().to_string());
+    // Outside any traced span there is nothing to propagate.
+    assert_eq!(otel::current_traceparent(), None);
+}
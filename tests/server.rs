@@ -0,0 +1,419 @@
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use sovereign_cli::{server, AgentKind, AgentResult, CircuitPolicy, CognitiveOrchestrator, MockBackend, OrchestratorError};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+fn result(output: &str, status: bool) -> AgentResult {
+    AgentResult { output: output.to_string(), status, metadata: HashMap::new(), error: None }
+}
+
+/// Serves a mock-backed orchestrator on a free port and returns its base URL.
+async fn spawn_server() -> String {
+    let mock = MockBackend::new()
+        .plan("launch", ["post teaser", "post launch"])
+        .on("post teaser", |_| result("low virality on teaser", false))
+        .on("post launch", |_| result("launched", true))
+        .on("post alt", |_| result("alt posted", true))
+        .replan(["post alt"]);
+    let orchestrator = CognitiveOrchestrator::builder().backend(Arc::new(mock)).build().unwrap();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(server::serve(listener, orchestrator));
+    url
+}
+
+#[tokio::test]
+async fn healthz_reports_ok() {
+    let url = spawn_server().await;
+    let body: Value = reqwest::get(format!("{}/healthz", url)).await.unwrap().json().await.unwrap();
+    assert_eq!(body, json!({ "status": "ok" }));
+}
+
+#[tokio::test]
+async fn readyz_is_unavailable_until_every_preflight_check_passes() {
+    let url = spawn_server().await;
+    let response = reqwest::get(format!("{}/readyz", url)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = response.json().await.unwrap();
+    assert!(body["checks"].as_array().unwrap().iter().all(|check| check["status"] == "pass"), "{}", body);
+
+    let missing = std::env::temp_dir().join(format!("ace-readyz-missing-{}", std::process::id())).join("contexts.json");
+    let orchestrator =
+        CognitiveOrchestrator::builder().backend(Arc::new(MockBackend::new())).eviction_path(&missing).build().unwrap();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(server::serve(listener, orchestrator));
+    let response = reqwest::get(format!("{}/readyz", url)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body: Value = response.json().await.unwrap();
+    let eviction = body["checks"].as_array().unwrap().iter().find(|check| check["name"] == "paths.eviction").unwrap();
+    assert_eq!(eviction["status"], "fail");
+}
+
+#[tokio::test]
+async fn process_runs_the_plan_and_creates_the_context() {
+    let url = spawn_server().await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(format!("{}/process", url))
+        .json(&json!({ "command": "launch campaign", "context_id": "ctx1" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["outputs"], json!(["low virality on teaser", "alt posted", "launched"]));
+    assert_eq!(body["replanned"], json!(["post alt"]));
+    assert_eq!(body["results"][1]["metadata"]["replanned_from"], "post teaser");
+
+    let contexts: Value = client.get(format!("{}/contexts", url)).send().await.unwrap().json().await.unwrap();
+    assert_eq!(contexts[0]["context_id"], "ctx1");
+    let context: Value = client.get(format!("{}/contexts/ctx1", url)).send().await.unwrap().json().await.unwrap();
+    assert_eq!(context["context_id"], "ctx1");
+}
+
+#[tokio::test]
+async fn delete_removes_the_context() {
+    let url = spawn_server().await;
+    let client = reqwest::Client::new();
+    client
+        .post(format!("{}/process", url))
+        .json(&json!({ "command": "post launch", "context_id": "gone" }))
+        .send()
+        .await
+        .unwrap();
+
+    let deleted = client.delete(format!("{}/contexts/gone", url)).send().await.unwrap();
+    assert_eq!(deleted.status(), StatusCode::NO_CONTENT);
+    let missing = client.get(format!("{}/contexts/gone", url)).send().await.unwrap();
+    assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+    let body: Value = missing.json().await.unwrap();
+    assert_eq!(body["error"], json!({ "kind": "missing_context", "context_id": "gone" }));
+    assert_eq!(body["message"], "unknown context: gone");
+    let again = client.delete(format!("{}/contexts/gone", url)).send().await.unwrap();
+    assert_eq!(again.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn deleted_contexts_restore_until_the_trash_is_purged() {
+    let url = spawn_server().await;
+    let client = reqwest::Client::new();
+    for context_id in ["oops", "purged"] {
+        client
+            .post(format!("{}/process", url))
+            .json(&json!({ "command": "post launch", "context_id": context_id }))
+            .send()
+            .await
+            .unwrap();
+        let deleted = client.delete(format!("{}/contexts/{}", url, context_id)).send().await.unwrap();
+        assert_eq!(deleted.status(), StatusCode::NO_CONTENT);
+    }
+
+    let restored = client.post(format!("{}/contexts/oops/restore", url)).send().await.unwrap();
+    assert_eq!(restored.status(), StatusCode::OK);
+    let context: Value = restored.json().await.unwrap();
+    assert_eq!(context["context_id"], "oops");
+    let contexts: Value = client.get(format!("{}/contexts", url)).send().await.unwrap().json().await.unwrap();
+    assert_eq!(contexts.as_array().unwrap().len(), 1);
+
+    let purge = client.post(format!("{}/trash/purge", url)).json(&json!({ "older_than_secs": 0 })).send().await.unwrap();
+    let body: Value = purge.json().await.unwrap();
+    assert_eq!(body, json!({ "purged": ["purged"] }));
+    let gone = client.post(format!("{}/contexts/purged/restore", url)).send().await.unwrap();
+    assert_eq!(gone.status(), StatusCode::NOT_FOUND);
+    let body: Value = gone.json().await.unwrap();
+    assert_eq!(body["error"], json!({ "kind": "missing_context", "context_id": "purged" }));
+}
+
+#[tokio::test]
+async fn malformed_requests_get_an_error_body() {
+    let url = spawn_server().await;
+    let response = reqwest::Client::new()
+        .post(format!("{}/process", url))
+        .json(&json!({ "command": "launch" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["error"]["kind"], "extraction");
+    assert!(body["error"]["message"].as_str().unwrap().contains("context_id"));
+}
+
+#[tokio::test]
+async fn process_stream_sends_each_event() {
+    let url = spawn_server().await;
+    let body = reqwest::Client::new()
+        .post(format!("{}/process/stream", url))
+        .json(&json!({ "command": "launch campaign", "context_id": "ctx1" }))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+
+    let mut events = vec![];
+    for message in body.split("\n\n").filter(|message| !message.trim().is_empty()) {
+        let field = |name: &str| {
+            message.lines().find_map(|line| line.strip_prefix(name)).map(|value| value.trim().to_string())
+        };
+        if let (Some(event), Some(data)) = (field("event:"), field("data:")) {
+            let data: Value = serde_json::from_str(&data).unwrap();
+            assert_eq!(data["event"], event.as_str());
+            events.push(event);
+        }
+    }
+    assert_eq!(
+        events,
+        [
+            "plan_ready",
+            "subtask_started",
+            "subtask_finished",
+            "replan_triggered",
+            "subtask_started",
+            "subtask_finished",
+            "subtask_started",
+            "subtask_finished",
+            "completed",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn events_socket_broadcasts_runs_in_order() {
+    use futures_util::StreamExt;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let url = spawn_server().await;
+    let ws_url = format!("{}/events?context_id=ctx1", url.replacen("http", "ws", 1));
+    let (mut socket, _) = tokio_tungstenite::connect_async(ws_url).await.unwrap();
+
+    let client = reqwest::Client::new();
+    for context_id in ["other", "ctx1"] {
+        client
+            .post(format!("{}/process", url))
+            .json(&json!({ "command": "launch campaign", "context_id": context_id }))
+            .send()
+            .await
+            .unwrap();
+    }
+
+    let mut events = vec![];
+    while let Some(message) = socket.next().await {
+        let Message::Text(text) = message.unwrap() else { continue };
+        let event: Value = serde_json::from_str(&text).unwrap();
+        let done = event["event"] == "completed";
+        events.push(event);
+        if done {
+            break;
+        }
+    }
+
+    assert!(events.iter().all(|event| event["context_id"] == "ctx1"));
+    let seqs: Vec<u64> = events.iter().map(|event| event["seq"].as_u64().unwrap()).collect();
+    assert!(seqs.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", seqs);
+    let kinds: Vec<&str> = events.iter().map(|event| event["event"].as_str().unwrap()).collect();
+    assert_eq!(kinds.first(), Some(&"plan_ready"));
+    assert!(kinds.contains(&"replan_triggered"));
+    assert!(kinds.contains(&"metrics_updated"));
+    assert!(kinds.contains(&"subtask_finished"));
+}
+
+#[tokio::test]
+async fn shutdown_lets_the_run_in_flight_finish() {
+    let mock = MockBackend::new().on("post slow", |_| {
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        result("posted", true)
+    });
+    let orchestrator = CognitiveOrchestrator::builder().backend(Arc::new(mock)).build().unwrap();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+    let grace = std::time::Duration::from_secs(5);
+    let served = tokio::spawn(server::serve_until(listener, orchestrator, async { drop(stopped.await) }, grace));
+
+    let request = reqwest::Client::new()
+        .post(format!("{}/process", url))
+        .json(&json!({ "command": "post slow", "context_id": "ctx1" }))
+        .send();
+    let request = tokio::spawn(request);
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    stop.send(()).unwrap();
+
+    let response = request.await.unwrap().unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["outputs"], json!(["posted"]));
+    let report = served.await.unwrap().unwrap();
+    assert_eq!((report.drained, report.interrupted.len(), report.snapshots), (1, 0, 1));
+}
+
+#[tokio::test]
+async fn retries_with_an_idempotency_key_replay_the_first_run() {
+    let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let seen = calls.clone();
+    let mock = MockBackend::new().on("post slow", move |_| {
+        seen.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        result("posted", true)
+    });
+    let orchestrator = CognitiveOrchestrator::builder().backend(Arc::new(mock)).build().unwrap();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(server::serve(listener, orchestrator));
+    let client = reqwest::Client::new();
+    let submit = |body: Value| client.post(format!("{}/process", url)).json(&body).send();
+    let request = json!({ "command": "post slow", "context_id": "ctx1", "idempotency_key": "req-1" });
+
+    let first = tokio::spawn(submit(request.clone()));
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    let mut no_wait = request.clone();
+    no_wait["wait"] = json!(false);
+    let pending = submit(no_wait).await.unwrap();
+    assert_eq!(pending.status(), StatusCode::CONFLICT);
+    let body: Value = pending.json().await.unwrap();
+    assert_eq!(body["error"]["kind"], "idempotency_pending");
+
+    // A retry during the run waits for it, and one after it just replays it.
+    let during: Value = submit(request.clone()).await.unwrap().json().await.unwrap();
+    let first: Value = first.await.unwrap().unwrap().json().await.unwrap();
+    let after: Value = submit(request.clone()).await.unwrap().json().await.unwrap();
+    assert_eq!(first["outputs"], json!(["posted"]));
+    assert!(first.get("replayed").is_none());
+    for replay in [&during, &after] {
+        assert_eq!(replay["replayed"], true);
+        assert_eq!(replay["outputs"], first["outputs"]);
+        assert_eq!(replay["results"], first["results"]);
+    }
+    assert_eq!(calls.load(std::sync::atomic::Ordering::Relaxed), 1);
+
+    let reused = submit(json!({ "command": "post slow", "context_id": "ctx2", "idempotency_key": "req-1" })).await.unwrap();
+    assert_eq!(reused.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn identical_requests_at_once_share_one_run() {
+    let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let seen = calls.clone();
+    let mock = MockBackend::new().on("post slow", move |_| {
+        seen.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        result("posted", true)
+    });
+    let orchestrator = CognitiveOrchestrator::builder()
+        .backend(Arc::new(mock))
+        .coalesce_window(std::time::Duration::from_secs(60))
+        .build()
+        .unwrap();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(server::serve(listener, orchestrator));
+    let client = reqwest::Client::new();
+    let submit = |body: Value| client.post(format!("{}/process", url)).json(&body).send();
+    let request = json!({ "command": "post slow", "context_id": "ctx1" });
+
+    let (first, second) = tokio::join!(submit(request.clone()), submit(request.clone()));
+    let mut bodies: Vec<Value> = vec![first.unwrap().json().await.unwrap(), second.unwrap().json().await.unwrap()];
+    bodies.sort_by_key(|body| body.get("coalesced").is_some());
+    assert!(bodies[0].get("coalesced").is_none());
+    assert_eq!(bodies[1]["coalesced"], true);
+    assert_eq!(bodies[1]["run_id"], bodies[0]["run_id"]);
+    assert_eq!(bodies[1]["outputs"], json!(["posted"]));
+    assert_eq!(bodies[1]["results"], bodies[0]["results"]);
+    assert_eq!(calls.load(std::sync::atomic::Ordering::Relaxed), 1);
+
+    // A caller's own run id is never handed another run.
+    let own: Value = submit(json!({ "command": "post slow", "context_id": "ctx1", "run_id": "upstream-7" }))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(own["run_id"], "upstream-7");
+    assert!(own.get("coalesced").is_none());
+    assert_eq!(calls.load(std::sync::atomic::Ordering::Relaxed), 2);
+}
+
+#[tokio::test]
+async fn healthz_reports_an_open_circuit() {
+    let mock = MockBackend::new().generator(|_| {
+        Err(OrchestratorError::CallFailed { target: "llm".to_string(), message: "down".to_string(), traceback: None })
+    });
+    let orchestrator = CognitiveOrchestrator::builder()
+        .backend(Arc::new(mock))
+        .build()
+        .unwrap()
+        .with_circuit_breaker(AgentKind::Llm, CircuitPolicy::new(1, std::time::Duration::from_secs(60)));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(server::serve(listener, orchestrator));
+    let client = reqwest::Client::new();
+    let health = || async { client.get(format!("{}/healthz", url)).send().await.unwrap().json::<Value>().await.unwrap() };
+    assert_eq!(health().await["agents"][0]["state"], "closed");
+
+    let submit = |context_id: &str| {
+        client.post(format!("{}/process", url)).json(&json!({ "command": "query llm", "context_id": context_id })).send()
+    };
+    submit("ctx1").await.unwrap();
+    let body = health().await;
+    assert_eq!(body["status"], "degraded");
+    assert_eq!(body["agents"][0], json!({
+        "agent": "llm",
+        "state": "open",
+        "consecutive_failures": 1,
+        "total_failures": 1,
+        "last_error": "Python call llm raised down",
+        "opened_at": body["agents"][0]["opened_at"],
+    }));
+    let body: Value = submit("ctx2").await.unwrap().json().await.unwrap();
+    assert_eq!(body["results"][0]["error"]["kind"], "circuit_open");
+}
+
+#[tokio::test]
+async fn live_view_follows_a_run_in_progress() {
+    let mock = MockBackend::new().plan("launch", ["post slow", "post slow"]).on("post slow", |_| {
+        std::thread::sleep(std::time::Duration::from_millis(150));
+        result("posted", true)
+    });
+    let orchestrator = CognitiveOrchestrator::builder().backend(Arc::new(mock)).learning(false).build().unwrap();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(server::serve(listener, orchestrator));
+    let client = reqwest::Client::new();
+    let missing = client.get(format!("{}/contexts/ctx1/live", url)).send().await.unwrap();
+    assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+
+    let request = client.post(format!("{}/process", url)).json(&json!({ "command": "launch", "context_id": "ctx1" })).send();
+    let request = tokio::spawn(request);
+    tokio::time::sleep(std::time::Duration::from_millis(225)).await;
+    let live: Value = client.get(format!("{}/contexts/ctx1/live", url)).send().await.unwrap().json().await.unwrap();
+    assert_eq!(live["run"]["command"], "launch");
+    assert_eq!(live["run"]["completed_steps"], 1);
+    assert_eq!(live["run"]["running"], json!(["post slow"]));
+    assert!(live["elapsed_secs"].as_f64().unwrap() > 0.2, "{}", live);
+
+    assert_eq!(request.await.unwrap().unwrap().status(), StatusCode::OK);
+    let idle: Value = client.get(format!("{}/contexts/ctx1/live", url)).send().await.unwrap().json().await.unwrap();
+    assert_eq!((idle["run"].clone(), idle["elapsed_secs"].clone()), (Value::Null, Value::Null));
+    assert!(idle["metrics"]["virality_score"].is_number());
+}
+
+#[tokio::test]
+async fn process_keeps_a_callers_run_id_on_the_response_and_results() {
+    let url = spawn_server().await;
+    let client = reqwest::Client::new();
+    let body = json!({ "command": "launch campaign", "context_id": "ctx1", "run_id": "upstream-42" });
+    let response: Value = client.post(format!("{}/process", url)).json(&body).send().await.unwrap().json().await.unwrap();
+    assert_eq!(response["run_id"], "upstream-42");
+    let results = response["results"].as_array().unwrap();
+    assert!(!results.is_empty() && results.iter().all(|result| result["metadata"]["_run_id"] == "upstream-42"), "{}", response);
+
+    // Without one, each run gets its own.
+    let body = json!({ "command": "launch campaign", "context_id": "ctx1" });
+    let first: Value = client.post(format!("{}/process", url)).json(&body).send().await.unwrap().json().await.unwrap();
+    let second: Value = client.post(format!("{}/process", url)).json(&body).send().await.unwrap().json().await.unwrap();
+    assert!(first["run_id"].is_string() && first["run_id"] != second["run_id"]);
+}
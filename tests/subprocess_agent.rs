@@ -0,0 +1,84 @@
+use sovereign_cli::{Agent, AgentResult, CognitiveOrchestrator, MockBackend, SubprocessAgent, SubprocessConfig};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Takes `flaky:` subtasks: `flaky:crash` kills it, `flaky:stall` hangs it, and
+/// anything else answers with its pid.
+const FLAKY: &str = r#"
+while IFS= read -r line; do
+    case $line in
+        *'"handshake"'*) echo '{"protocol": 1, "handles": ["flaky"]}' ;;
+        *'flaky:crash'*) exit 3 ;;
+        *'flaky:stall'*) sleep 5 ;;
+        *) echo "{\"output\": \"ok\", \"status\": true, \"metadata\": {\"pid\": $$}}" ;;
+    esac
+done
+"#;
+
+fn orchestrator(agent: SubprocessAgent) -> CognitiveOrchestrator {
+    let orch = CognitiveOrchestrator::builder().backend(Arc::new(MockBackend::new())).learning(false).build().unwrap();
+    orch.register_agent(Box::new(agent));
+    orch
+}
+
+fn flaky(config: SubprocessConfig) -> CognitiveOrchestrator {
+    orchestrator(SubprocessAgent::spawn("flaky", config.args(["-c", FLAKY])).unwrap())
+}
+
+fn pid(result: &AgentResult) -> u64 {
+    assert!(result.status, "{:?}", result);
+    result.get_deserialized("pid").unwrap()
+}
+
+fn error_kind(result: &AgentResult) -> &'static str {
+    assert!(!result.status, "{:?}", result);
+    result.error.as_ref().unwrap().kind()
+}
+
+#[test]
+fn the_example_agent_round_trips() {
+    let script = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/echo_agent.sh");
+    let agent = SubprocessAgent::spawn("echo", SubprocessConfig::new(script)).unwrap();
+    assert_eq!(agent.handles(), ["echo"]);
+    assert!(agent.can_handle("echo:say hi") && !agent.can_handle("llm:generate hi"));
+    let orch = orchestrator(agent);
+
+    let result = orch.dispatch("echo:say hello \"world\"".to_string(), "ctx1");
+    assert!(result.status, "{:?}", result);
+    assert_eq!(result.output, "hello \"world\"");
+    assert_eq!(result.get_str("context_id"), Some("ctx1"));
+}
+
+#[test]
+fn crashed_processes_restart_a_bounded_number_of_times() {
+    let orch = flaky(SubprocessConfig::new("sh").max_restarts(1));
+    let first = pid(&orch.dispatch("flaky:run".to_string(), "ctx1"));
+    assert_eq!(pid(&orch.dispatch("flaky:run".to_string(), "ctx1")), first);
+
+    assert_eq!(error_kind(&orch.dispatch("flaky:crash".to_string(), "ctx1")), "call_failed");
+    let second = pid(&orch.dispatch("flaky:run".to_string(), "ctx1"));
+    assert_ne!(second, first);
+
+    // The one restart is used up.
+    error_kind(&orch.dispatch("flaky:crash".to_string(), "ctx1"));
+    let given_up = orch.dispatch("flaky:run".to_string(), "ctx1");
+    assert_eq!(error_kind(&given_up), "call_failed");
+    assert!(given_up.output.contains("gave out after 1 restarts"), "{}", given_up.output);
+}
+
+#[test]
+fn stalled_calls_time_out_and_the_process_is_replaced() {
+    let orch = flaky(SubprocessConfig::new("sh").timeout(Duration::from_millis(200)));
+    let first = pid(&orch.dispatch("flaky:run".to_string(), "ctx1"));
+
+    let started = Instant::now();
+    assert_eq!(error_kind(&orch.dispatch("flaky:stall".to_string(), "ctx1")), "timeout");
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert_ne!(pid(&orch.dispatch("flaky:run".to_string(), "ctx1")), first);
+}
+
+#[test]
+fn missing_executables_fail_to_spawn() {
+    let err = SubprocessAgent::spawn("ghost", SubprocessConfig::new("/nonexistent/agent")).err().unwrap();
+    assert_eq!(err.kind(), "io");
+}
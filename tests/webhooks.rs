@@ -0,0 +1,169 @@
+use serde_json::Value;
+use sovereign_cli::metrics::{self, WEBHOOK_FAILURES};
+use sovereign_cli::{AgentResult, CognitiveOrchestrator, EventFilter, MockBackend, WebhookConfig};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// One POST as the endpoint saw it: its lowercased headers and JSON body.
+struct Received {
+    headers: Vec<(String, String)>,
+    body: Value,
+}
+
+impl Received {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+}
+
+/// Serves a webhook on a free port that answers the first `failures` requests with
+/// 503 and the rest with 204. Returns its URL and what it received.
+fn spawn_endpoint(failures: usize) -> (String, Arc<Mutex<Vec<Received>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let received = Arc::new(Mutex::new(vec![]));
+    let log = received.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let mut reader = BufReader::new(&stream);
+            let mut headers = vec![];
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            loop {
+                line.clear();
+                reader.read_line(&mut line).unwrap();
+                match line.trim_end().split_once(':') {
+                    Some((key, value)) => headers.push((key.to_lowercase(), value.trim().to_string())),
+                    None => break,
+                }
+            }
+            let length = headers.iter().find(|(key, _)| key == "content-length").map_or(0, |(_, v)| v.parse().unwrap());
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+
+            let mut log = log.lock().unwrap();
+            log.push(Received { headers, body: serde_json::from_slice(&body).unwrap() });
+            let status = if log.len() <= failures { "503 Service Unavailable" } else { "204 No Content" };
+            let response = format!("HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+            (&stream).write_all(response.as_bytes()).unwrap();
+        }
+    });
+    (url, received)
+}
+
+fn result(output: &str, status: bool) -> AgentResult {
+    AgentResult { status, ..AgentResult::ok(output) }
+}
+
+fn orchestrator(webhook: WebhookConfig) -> CognitiveOrchestrator {
+    let mock = MockBackend::new()
+        .plan("launch", ["post teaser", "post launch"])
+        .on("post teaser", |_| result("low virality on teaser", false))
+        .on("post", |rest| result(rest, true))
+        .replan(["post alt"]);
+    CognitiveOrchestrator::builder().backend(Arc::new(mock)).learning(false).webhook(webhook).build().unwrap()
+}
+
+/// Waits for the notifier to settle: nothing queued and `done` events accounted for.
+fn settle(orch: &CognitiveOrchestrator, done: u64) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        let status = &orch.notifier_status()[0];
+        if status.queued == 0 && status.delivered + status.failed >= done {
+            return;
+        }
+        assert!(Instant::now() < deadline, "webhook never settled: {:?}", status);
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn failures_and_replans_are_posted_with_retries_on_5xx() {
+    let (url, received) = spawn_endpoint(1);
+    let webhook = WebhookConfig { retry_delay: Duration::from_millis(10), ..WebhookConfig::new(url) }
+        .auth_header("Bearer secret")
+        .event(EventFilter::new("subtask_finished").with_status(false))
+        .event(EventFilter::new("replan_triggered"));
+    let orch = orchestrator(webhook);
+    orch.process("launch campaign".to_string(), "ctx1");
+    settle(&orch, 2);
+
+    let received = received.lock().unwrap();
+    // The first POST got a 503 and was sent again.
+    assert_eq!(received.len(), 3);
+    assert_eq!(received[0].body, received[1].body);
+    let kinds: Vec<&str> = received[1..].iter().map(|post| post.body["event"].as_str().unwrap()).collect();
+    assert_eq!(kinds, ["subtask_finished", "replan_triggered"]);
+
+    let failed = &received[1];
+    assert_eq!(failed.header("authorization"), Some("Bearer secret"));
+    assert_eq!(failed.header("content-type"), Some("application/json"));
+    assert_eq!(failed.body["context_id"], "ctx1");
+    assert!(failed.body["seq"].as_u64().is_some());
+    assert_eq!(failed.body["result"]["output"], "low virality on teaser");
+    assert_eq!(failed.body["result"]["status"], false);
+    assert_eq!(received[2].body["subtask"], "post teaser");
+
+    let status = &orch.notifier_status()[0];
+    assert_eq!((status.delivered, status.failed, status.retries, status.dropped), (2, 0, 1, 0));
+}
+
+#[test]
+fn undeliverable_events_are_counted_without_blocking_runs() {
+    let (url, received) = spawn_endpoint(usize::MAX);
+    let webhook = WebhookConfig {
+        max_attempts: 2,
+        retry_delay: Duration::from_millis(10),
+        ..WebhookConfig::new(url).event(EventFilter::new("replan_triggered"))
+    };
+    let orch = orchestrator(webhook);
+    let started = Instant::now();
+    orch.process("launch campaign".to_string(), "ctx1");
+    assert!(started.elapsed() < Duration::from_secs(5));
+    settle(&orch, 1);
+
+    assert_eq!(received.lock().unwrap().len(), 2);
+    let status = &orch.notifier_status()[0];
+    assert_eq!((status.delivered, status.failed, status.retries), (0, 1, 1));
+    assert_eq!(status.last_error.as_deref(), Some("HTTP 503 Service Unavailable"));
+    let text = metrics::render(&orch.metrics_registry());
+    let line = text.lines().find(|line| line.starts_with(WEBHOOK_FAILURES)).unwrap().to_string();
+    assert_eq!(line, format!("{} 1", WEBHOOK_FAILURES));
+}
+
+#[test]
+fn configured_context_attributes_ride_along_with_each_event() {
+    let (url, received) = spawn_endpoint(0);
+    let webhook = WebhookConfig::new(url)
+        .event(EventFilter::new("subtask_finished"))
+        .event(EventFilter::new("completed"))
+        .attribute("campaign")
+        .attribute("arm")
+        .attribute("absent");
+    let orch = orchestrator(webhook);
+    orch.set_attribute("ctx1", "campaign", serde_json::json!(42)).unwrap();
+    orch.set_attribute("ctx1", "arm", serde_json::json!("b")).unwrap();
+    orch.set_attribute("ctx1", "segment", serde_json::json!("18-24")).unwrap();
+    orch.process("launch campaign".to_string(), "ctx1");
+    settle(&orch, 4);
+
+    let received = received.lock().unwrap();
+    let kinds: Vec<&str> = received.iter().map(|post| post.body["event"].as_str().unwrap()).collect();
+    assert_eq!(kinds, ["subtask_finished", "subtask_finished", "subtask_finished", "completed"]);
+    // Each delivered body carries the listed attributes the context has, and only those.
+    for post in received.iter() {
+        assert_eq!(post.body["attributes"], serde_json::json!({"campaign": 42, "arm": "b"}), "{}", post.body);
+    }
+    drop(received);
+
+    // A webhook listing no attributes sends none.
+    let (url, received) = spawn_endpoint(0);
+    let orch = orchestrator(WebhookConfig::new(url).event(EventFilter::new("completed")));
+    orch.set_attribute("ctx1", "campaign", serde_json::json!(42)).unwrap();
+    orch.process("launch campaign".to_string(), "ctx1");
+    settle(&orch, 1);
+    assert!(received.lock().unwrap()[0].body.get("attributes").is_none());
+}